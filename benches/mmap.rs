@@ -0,0 +1,54 @@
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::fs::File;
+use std::io::Read;
+
+use static_web_server::fs::mmap;
+
+/// Sizes of the benchmarked files, from the smallest one served via memory maps.
+const FILE_SIZES: [usize; 3] = [16 * 1024, 256 * 1024, 4 * 1024 * 1024];
+
+/// Size of the chunks of the regular file reads, the usual block size used by the file streams.
+const READ_BUF_SIZE: usize = 4 * 1024;
+
+fn file_body_benchmark(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let max_file_size = *FILE_SIZES.iter().max().unwrap() as u64;
+
+    let mut group = c.benchmark_group("file_body");
+    for size in FILE_SIZES {
+        let path = std::env::temp_dir().join(format!("sws-bench-mmap-{size}"));
+        std::fs::write(&path, vec![b'a'; size]).unwrap();
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("mmap", size), &path, |b, path| {
+            b.iter(|| {
+                let file = File::open(path).unwrap();
+                let body = mmap::file_body(&file, size as u64, max_file_size).unwrap();
+                runtime.block_on(hyper::body::to_bytes(body)).unwrap()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("read", size), &path, |b, path| {
+            b.iter(|| {
+                let mut file = File::open(path).unwrap();
+                let mut contents = Vec::with_capacity(size);
+                let mut buf = vec![0; READ_BUF_SIZE];
+                loop {
+                    let n = file.read(&mut buf).unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    contents.extend_from_slice(&buf[..n]);
+                }
+                contents
+            })
+        });
+
+        std::fs::remove_file(&path).unwrap();
+    }
+    group.finish();
+}
+
+criterion_group!(mmap_bench, file_body_benchmark);
+criterion_main!(mmap_bench);
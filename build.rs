@@ -1,6 +1,59 @@
 use shadow_rs::{SdResult, ShadowBuilder};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use std::{env, fs, io};
 
 fn main() -> SdResult<()> {
     ShadowBuilder::builder().build()?;
+    embed_assets()?;
+    Ok(())
+}
+
+/// A file to embed: its path relative to the embedded directory, its absolute path
+/// and its modification time in seconds since the UNIX epoch.
+type AssetFile = (String, PathBuf, Option<u64>);
+
+/// Generates the list of files embedded into the binary from the `SWS_EMBED_DIR` directory if any.
+fn embed_assets() -> io::Result<()> {
+    println!("cargo:rerun-if-env-changed=SWS_EMBED_DIR");
+
+    let mut files = Vec::new();
+    if let Some(dir) = env::var_os("SWS_EMBED_DIR").filter(|dir| !dir.is_empty()) {
+        let dir = fs::canonicalize(dir)?;
+        println!("cargo:rerun-if-changed={}", dir.display());
+        collect_files(&dir, "", &mut files)?;
+        files.sort();
+    }
+
+    let mut code = String::from("&[\n");
+    for (path, abs_path, modified) in files {
+        code.push_str(&format!(
+            "    EmbeddedFile {{ path: {path:?}, contents: include_bytes!({abs_path:?}), modified: {modified:?} }},\n"
+        ));
+    }
+    code.push(']');
+
+    let out_dir = env::var_os("OUT_DIR").expect("OUT_DIR env is not set");
+    fs::write(Path::new(&out_dir).join("embedded_assets.rs"), code)
+}
+
+/// Collects the files of a directory recursively.
+fn collect_files(dir: &Path, prefix: &str, files: &mut Vec<AssetFile>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = [prefix, &name].concat();
+        let meta = fs::metadata(entry.path())?;
+        if meta.is_dir() {
+            collect_files(&entry.path(), &[&path, "/"].concat(), files)?;
+        } else if meta.is_file() {
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs());
+            files.push((path, entry.path(), modified));
+        }
+    }
     Ok(())
 }
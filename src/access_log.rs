@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that allows embedders to receive structured access-log records.
+//!
+//! Every request handled by the server produces an [`AccessLogRecord`] once its response
+//! is ready, which is forwarded to the [`AccessLogSink`]s registered via
+//! [`Server::with_access_log_sink`](crate::Server::with_access_log_sink).
+//! This happens in addition to the regular logging to `stderr`.
+//!
+
+use headers::{ContentLength, HeaderMapExt};
+use hyper::{
+    Body, HeaderMap, Method, Request, Response, StatusCode, Uri, Version,
+    body::HttpBody,
+    header::{HOST, REFERER, USER_AGENT},
+};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::Error;
+
+/// A structured record of a request and its response.
+#[derive(Clone, Debug)]
+pub struct AccessLogRecord {
+    /// Time the request was received.
+    pub timestamp: SystemTime,
+    /// Remote address of the client if available.
+    pub remote_addr: Option<SocketAddr>,
+    /// Request method.
+    pub method: Method,
+    /// Request URI as received, before any rewrite.
+    pub uri: Uri,
+    /// Request HTTP version.
+    pub version: Version,
+    /// Request `Host` header or URI authority if any.
+    pub host: Option<String>,
+    /// Request `User-Agent` header if any.
+    pub user_agent: Option<String>,
+    /// Request `Referer` header if any.
+    pub referer: Option<String>,
+    /// Response status code.
+    pub status: StatusCode,
+    /// Response body length in bytes if known in advance.
+    pub content_length: Option<u64>,
+    /// Time elapsed until the response was ready to be sent.
+    pub duration: Duration,
+}
+
+/// A destination of the access-log records.
+///
+/// Sinks are called on the request tasks, so they should not block
+/// (E.g. by forwarding the records to a channel).
+pub trait AccessLogSink: Send + Sync {
+    /// Receives the record of a request once its response is ready.
+    fn log(&self, record: &AccessLogRecord);
+}
+
+impl<F> AccessLogSink for F
+where
+    F: Fn(&AccessLogRecord) + Send + Sync,
+{
+    fn log(&self, record: &AccessLogRecord) {
+        self(record)
+    }
+}
+
+/// The access-log sinks of the request handler.
+#[derive(Clone, Default)]
+pub struct AccessLog {
+    sinks: Vec<Arc<dyn AccessLogSink>>,
+}
+
+impl AccessLog {
+    /// Adds a sink which will receive the access-log records.
+    pub fn add_sink<S: AccessLogSink + 'static>(&mut self, sink: S) {
+        self.sinks.push(Arc::new(sink));
+    }
+
+    /// Returns `true` if there are no sinks.
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    /// Starts a record of the given request if there are sinks.
+    pub(crate) fn start<T>(
+        &self,
+        req: &Request<T>,
+        remote_addr: Option<SocketAddr>,
+    ) -> Option<PendingRecord<'_>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let headers = req.headers();
+        let host = header_str(headers, HOST.as_str())
+            .or_else(|| req.uri().authority().map(|auth| auth.to_string()));
+        Some(PendingRecord {
+            sinks: &self.sinks,
+            started: Instant::now(),
+            record: AccessLogRecord {
+                timestamp: SystemTime::now(),
+                remote_addr,
+                method: req.method().clone(),
+                uri: req.uri().clone(),
+                version: req.version(),
+                host,
+                user_agent: header_str(headers, USER_AGENT.as_str()),
+                referer: header_str(headers, REFERER.as_str()),
+                status: StatusCode::OK,
+                content_length: None,
+                duration: Duration::ZERO,
+            },
+        })
+    }
+}
+
+/// Gets the value of a header as a string if valid.
+fn header_str(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned)
+}
+
+/// A record waiting for the response of its request.
+pub(crate) struct PendingRecord<'a> {
+    sinks: &'a [Arc<dyn AccessLogSink>],
+    started: Instant,
+    record: AccessLogRecord,
+}
+
+impl PendingRecord<'_> {
+    /// Completes the record with the given response and sends it to the sinks.
+    pub fn finish(mut self, resp: &Response<Body>) {
+        self.record.status = resp.status();
+        self.record.content_length = resp
+            .headers()
+            .typed_get::<ContentLength>()
+            .map(|len| len.0)
+            .or_else(|| resp.body().size_hint().exact());
+        self.record.duration = self.started.elapsed();
+
+        for sink in self.sinks {
+            sink.log(&self.record);
+        }
+    }
+}
+
+/// Records the request handled by the given future once its response is ready.
+pub(crate) async fn record<F>(
+    pending: Option<PendingRecord<'_>>,
+    handle: F,
+) -> Result<Response<Body>, Error>
+where
+    F: Future<Output = Result<Response<Body>, Error>>,
+{
+    let resp = handle.await?;
+    if let Some(pending) = pending {
+        pending.finish(&resp);
+    }
+    Ok(resp)
+}
@@ -0,0 +1,110 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that advertises alternative services via the `Alt-Svc` response header.
+//!
+//! It allows clients to reach the same content through a different protocol,
+//! host or port, E.g. an HTTP/3 endpoint or a proxy fronting the server.
+//!
+
+use hyper::{
+    Body, Request, Response,
+    header::{ALT_SVC, HeaderValue},
+};
+
+use crate::{Context, Error, Result, handler::RequestHandlerOpts};
+
+/// Builds the `Alt-Svc` header value from a comma-separated list of alternative services
+/// like `h3=:443, h2=alt.example.com:8443` along with their freshness lifetime in seconds.
+///
+/// The `clear` value invalidates the alternative services previously advertised.
+pub(crate) fn parse(value: &str, max_age: u64) -> Result<Option<HeaderValue>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    if value == "clear" {
+        return Ok(Some(HeaderValue::from_static("clear")));
+    }
+
+    let mut alternatives = Vec::new();
+    for alternative in value.split(',').map(str::trim) {
+        let Some((protocol, authority)) = alternative.split_once('=') else {
+            bail!("invalid alt-svc entry `{alternative}`, expected `protocol=[host]:port`");
+        };
+        let protocol = protocol.trim();
+        let authority = authority.trim().trim_matches('"');
+        if protocol.is_empty()
+            || !protocol
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+        {
+            bail!("invalid alt-svc protocol id `{protocol}` in entry `{alternative}`");
+        }
+        let port = authority.rsplit_once(':').map(|(_, port)| port);
+        if port.is_none_or(|port| port.parse::<u16>().is_err()) || authority.contains('"') {
+            bail!("invalid alt-svc authority `{authority}` in entry `{alternative}`");
+        }
+        alternatives.push(format!("{protocol}=\"{authority}\"; ma={max_age}"));
+    }
+
+    HeaderValue::try_from(alternatives.join(", "))
+        .map(Some)
+        .with_context(|| format!("invalid alt-svc value: {value}"))
+}
+
+/// Initializes the `Alt-Svc` header.
+pub(crate) fn init(value: &str, max_age: u64, handler_opts: &mut RequestHandlerOpts) -> Result {
+    handler_opts.alt_svc = parse(value, max_age)?;
+    tracing::info!(
+        "alt-svc header: enabled={}, value={:?}",
+        handler_opts.alt_svc.is_some(),
+        handler_opts.alt_svc
+    );
+    Ok(())
+}
+
+/// Appends the `Alt-Svc` header to a response if enabled.
+pub(crate) fn post_process<T>(
+    opts: &RequestHandlerOpts,
+    _req: &Request<T>,
+    mut resp: Response<Body>,
+) -> Result<Response<Body>, Error> {
+    if let Some(alt_svc) = &opts.alt_svc {
+        resp.headers_mut().insert(ALT_SVC, alt_svc.clone());
+    }
+    Ok(resp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("", 86400).unwrap(), None);
+        assert_eq!(parse("clear", 86400).unwrap().unwrap(), "clear");
+        assert_eq!(
+            parse("h3=:443", 86400).unwrap().unwrap(),
+            "h3=\":443\"; ma=86400"
+        );
+        assert_eq!(
+            parse(" h3=\":443\" , h2=alt.example.com:8443", 3600)
+                .unwrap()
+                .unwrap(),
+            "h3=\":443\"; ma=3600, h2=\"alt.example.com:8443\"; ma=3600"
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse("h3", 86400).is_err());
+        assert!(parse("=:443", 86400).is_err());
+        assert!(parse("h3 draft=:443", 86400).is_err());
+        assert!(parse("h3=example.com", 86400).is_err());
+        assert!(parse("h3=:http", 86400).is_err());
+        assert!(parse("h3=:443,", 86400).is_err());
+    }
+}
@@ -0,0 +1,530 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module to preview the contents of `.zip`, `.tar.gz` and `.tgz` archives.
+//!
+//! An archive requested with a `list` query parameter responds with the list of its members
+//! and with an `extract=<member>` query parameter responds with the content of a single member.
+//! Members are streamed straight from the archive, no temporary files are written.
+//!
+
+use async_compression::tokio::bufread::GzipDecoder;
+use bytes::Bytes;
+use futures_util::StreamExt;
+use headers::{ContentLength, ContentType, HeaderMapExt};
+use hyper::{
+    Body, Request, Response, StatusCode,
+    body::Sender,
+    header::{ACCEPT_RANGES, CONTENT_DISPOSITION, CONTENT_ENCODING, CONTENT_RANGE},
+};
+use mime_guess::{Mime, mime};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tokio::io::BufReader;
+use tokio_util::compat::{Compat, FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
+use tokio_util::io::ReaderStream;
+
+use crate::{
+    Error, content_type_options,
+    deadline::{self, Deadline},
+    error_page,
+    handler::RequestHandlerOpts,
+    http_ext::MethodExt,
+};
+
+/// Query parameter key to list the members of an archive.
+pub const LIST_PARAM_KEY: &str = "list";
+
+/// Query parameter key to extract a single member of an archive.
+pub const EXTRACT_PARAM_KEY: &str = "extract";
+
+/// Size of the chunks used to stream archive members.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Reader of the tar entries of a `.tar.gz` archive.
+type TarGzReader = Compat<GzipDecoder<BufReader<tokio::fs::File>>>;
+
+/// Initializes the archive preview.
+pub(crate) fn init(
+    enabled: bool,
+    max_entries: usize,
+    max_size: u64,
+    handler_opts: &mut RequestHandlerOpts,
+) {
+    handler_opts.archive_preview = enabled;
+    handler_opts.archive_preview_max_entries = max_entries;
+    handler_opts.archive_preview_max_size = max_size;
+    tracing::info!(
+        "archive preview: enabled={enabled}, max_entries={max_entries}, max_size={max_size}"
+    );
+}
+
+/// Supported archive formats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ArchiveKind {
+    Zip,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// Detects the archive format of a file from its extension.
+    fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// Preview requested via the query string.
+#[derive(Debug, PartialEq)]
+enum Action {
+    /// List the archive members as HTML or JSON.
+    List { json: bool },
+    /// Extract a single archive member.
+    Extract(String),
+}
+
+impl Action {
+    /// Gets the preview requested by a query string if any.
+    fn from_query(query: Option<&str>) -> Option<Self> {
+        form_urlencoded::parse(query?.as_bytes()).find_map(|(k, v)| match k.as_ref() {
+            LIST_PARAM_KEY => Some(Self::List { json: v == "json" }),
+            EXTRACT_PARAM_KEY => Some(Self::Extract(v.into_owned())),
+            _ => None,
+        })
+    }
+}
+
+/// An archive member.
+#[derive(Serialize)]
+struct Member {
+    name: String,
+    size: u64,
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+/// The listed members of an archive.
+#[derive(Serialize)]
+struct Listing {
+    entries: Vec<Member>,
+    /// Whether the archive has more members than the ones listed.
+    truncated: bool,
+}
+
+/// Post-process the response to preview an archive if requested.
+pub(crate) async fn post_process<T>(
+    opts: &RequestHandlerOpts,
+    req: &Request<T>,
+    resp: Response<Body>,
+    file_path: Option<&PathBuf>,
+    deadline: &Deadline,
+) -> Result<Response<Body>, Error> {
+    if !opts.archive_preview {
+        return Ok(resp);
+    }
+
+    let method = req.method();
+    if !(method.is_get() || method.is_head())
+        || !matches!(resp.status(), StatusCode::OK | StatusCode::PARTIAL_CONTENT)
+        || resp.headers().contains_key(CONTENT_ENCODING)
+    {
+        return Ok(resp);
+    }
+
+    let (file_path, kind) = match file_path.and_then(|p| Some((p, ArchiveKind::from_path(p)?))) {
+        Some(v) => v,
+        None => return Ok(resp),
+    };
+    let action = match Action::from_query(req.uri().query()) {
+        Some(action) => action,
+        None => return Ok(resp),
+    };
+
+    let with_body = !method.is_head();
+    let is_listing = matches!(action, Action::List { .. });
+    let result = match action {
+        Action::List { json } => list(kind, file_path, opts.archive_preview_max_entries, deadline)
+            .await
+            .map(|listing| {
+                let (content, content_type) = if json {
+                    (
+                        serde_json::to_string(&listing).unwrap_or_default(),
+                        mime::APPLICATION_JSON,
+                    )
+                } else {
+                    (html_listing(file_path, &listing), mime::TEXT_HTML_UTF_8)
+                };
+                let len = content.len() as u64;
+                let body = if with_body {
+                    Body::from(content)
+                } else {
+                    Body::empty()
+                };
+                (content_type, len, body)
+            }),
+        Action::Extract(member) => {
+            let content_type = mime_guess::from_path(&member).first_or_octet_stream();
+            extract(kind, file_path, &member, opts, with_body, deadline)
+                .await
+                .map(|(len, body)| (content_type, len, body))
+        }
+    };
+
+    let (content_type, len, body): (Mime, u64, Body) = match result {
+        Ok(v) => v,
+        Err(status) => {
+            return error_page::error_response(req.uri(), method, &status, &opts.error_pages);
+        }
+    };
+
+    tracing::debug!("archive preview: previewed {}", file_path.display());
+
+    let (mut head, _) = resp.into_parts();
+    head.status = StatusCode::OK;
+    head.headers.remove(ACCEPT_RANGES);
+    head.headers.remove(CONTENT_RANGE);
+    head.headers.remove(CONTENT_DISPOSITION);
+    head.headers.typed_insert(ContentType::from(content_type));
+    head.headers.typed_insert(ContentLength(len));
+
+    let mut resp = Response::from_parts(head, body);
+    if is_listing {
+        content_type_options::append_generated(&mut resp);
+    }
+    Ok(resp)
+}
+
+/// Lists the members of an archive up to `max_entries`.
+async fn list(
+    kind: ArchiveKind,
+    path: &Path,
+    max_entries: usize,
+    deadline: &Deadline,
+) -> Result<Listing, StatusCode> {
+    match kind {
+        ArchiveKind::Zip => {
+            let path = path.to_owned();
+            deadline::spawn_blocking(Some(deadline), move |deadline| {
+                zip_list(&path, max_entries, deadline)
+            })
+            .await
+        }
+        ArchiveKind::TarGz => tar_list(path, max_entries).await,
+    }
+}
+
+/// Finds a regular file member of an archive and streams its content.
+/// It returns the member size along with the response body.
+async fn extract(
+    kind: ArchiveKind,
+    path: &Path,
+    member: &str,
+    opts: &RequestHandlerOpts,
+    with_body: bool,
+    deadline: &Deadline,
+) -> Result<(u64, Body), StatusCode> {
+    let max_size = opts.archive_preview_max_size;
+    match kind {
+        ArchiveKind::Zip => {
+            let (path, member) = (path.to_owned(), member.to_owned());
+            let (path, index, size) = deadline::spawn_blocking(Some(deadline), move |_| {
+                let (index, size) = zip_find(&path, &member)?;
+                check_size(&member, size, max_size)?;
+                Ok((path, index, size))
+            })
+            .await?;
+
+            if !with_body {
+                return Ok((size, Body::empty()));
+            }
+            let (sender, body) = Body::channel();
+            let handle = tokio::runtime::Handle::current();
+            tokio::task::spawn_blocking(move || zip_stream(&path, index, sender, &handle));
+            Ok((size, body))
+        }
+        ArchiveKind::TarGz => {
+            let mut entries = tar_entries(path).await?;
+            let mut scanned: usize = 0;
+            while let Some(entry) = entries.next().await {
+                let entry = entry.map_err(|err| corrupted(path, err))?;
+                scanned += 1;
+                if scanned > opts.archive_preview_max_entries {
+                    break;
+                }
+                if !entry.header().entry_type().is_file()
+                    || *entry.path_bytes() != *member.as_bytes()
+                {
+                    continue;
+                }
+
+                let size = entry.header().size().map_err(|err| corrupted(path, err))?;
+                check_size(member, size, max_size)?;
+                if !with_body {
+                    return Ok((size, Body::empty()));
+                }
+                let stream = ReaderStream::with_capacity(entry.compat(), CHUNK_SIZE);
+                return Ok((size, Body::wrap_stream(stream)));
+            }
+
+            tracing::debug!("archive preview: member {member} not found");
+            Err(StatusCode::NOT_FOUND)
+        }
+    }
+}
+
+/// Rejects archive members larger than `max_size`.
+fn check_size(member: &str, size: u64, max_size: u64) -> Result<(), StatusCode> {
+    if size > max_size {
+        tracing::debug!("archive preview: member {member} of {size} bytes exceeds the size limit");
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(())
+}
+
+/// Logs an unreadable archive error and maps it to a status code.
+fn corrupted(path: &Path, err: impl std::fmt::Debug) -> StatusCode {
+    tracing::error!(
+        "archive preview: unable to read archive {}: {:?}",
+        path.display(),
+        err
+    );
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// Opens a zip archive.
+fn zip_open(path: &Path) -> Result<zip::ZipArchive<std::fs::File>, StatusCode> {
+    let file = std::fs::File::open(path).map_err(|err| corrupted(path, err))?;
+    zip::ZipArchive::new(file).map_err(|err| corrupted(path, err))
+}
+
+/// Lists the members of a zip archive up to `max_entries`.
+fn zip_list(
+    path: &Path,
+    max_entries: usize,
+    deadline: Option<&Deadline>,
+) -> Result<Listing, StatusCode> {
+    let mut archive = zip_open(path)?;
+    let mut entries = Vec::with_capacity(archive.len().min(max_entries));
+    for index in 0..archive.len().min(max_entries) {
+        deadline::check(deadline)?;
+        let file = archive
+            .by_index_raw(index)
+            .map_err(|err| corrupted(path, err))?;
+        entries.push(Member {
+            name: file.name().to_owned(),
+            size: file.size(),
+            kind: if file.is_dir() {
+                "dir"
+            } else if file.is_symlink() {
+                "symlink"
+            } else {
+                "file"
+            },
+        });
+    }
+
+    Ok(Listing {
+        entries,
+        truncated: archive.len() > max_entries,
+    })
+}
+
+/// Finds a regular file member of a zip archive and gets its index and size.
+fn zip_find(path: &Path, member: &str) -> Result<(usize, u64), StatusCode> {
+    let mut archive = zip_open(path)?;
+    let index = archive.index_for_name(member).ok_or_else(|| {
+        tracing::debug!("archive preview: member {member} not found");
+        StatusCode::NOT_FOUND
+    })?;
+    let file = archive
+        .by_index_raw(index)
+        .map_err(|err| corrupted(path, err))?;
+    if file.is_dir() || file.is_symlink() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok((index, file.size()))
+}
+
+/// Streams the decompressed content of a zip archive member into a body channel.
+/// The body gets aborted if the member can not be read.
+fn zip_stream(path: &Path, index: usize, mut sender: Sender, handle: &tokio::runtime::Handle) {
+    let mut archive = match zip_open(path) {
+        Ok(archive) => archive,
+        Err(_) => return sender.abort(),
+    };
+    let mut file = match archive.by_index(index) {
+        Ok(file) => file,
+        Err(err) => {
+            corrupted(path, err);
+            return sender.abort();
+        }
+    };
+
+    // Never send more than the size announced via `Content-Length`
+    let size = file.size();
+    let mut reader = (&mut file).take(size);
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                let chunk = Bytes::copy_from_slice(&buf[..n]);
+                if handle.block_on(sender.send_data(chunk)).is_err() {
+                    // The client went away
+                    break;
+                }
+            }
+            Err(err) => {
+                corrupted(path, err);
+                return sender.abort();
+            }
+        }
+    }
+}
+
+/// Opens a `.tar.gz` archive and gets a stream of its entries.
+async fn tar_entries(path: &Path) -> Result<async_tar::Entries<TarGzReader>, StatusCode> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|err| corrupted(path, err))?;
+    let reader = GzipDecoder::new(BufReader::new(file)).compat();
+    async_tar::Archive::new(reader)
+        .entries()
+        .map_err(|err| corrupted(path, err))
+}
+
+/// Lists the members of a `.tar.gz` archive up to `max_entries`.
+async fn tar_list(path: &Path, max_entries: usize) -> Result<Listing, StatusCode> {
+    let mut entries = tar_entries(path).await?;
+    let mut listing = Listing {
+        entries: Vec::new(),
+        truncated: false,
+    };
+    while let Some(entry) = entries.next().await {
+        let entry = entry.map_err(|err| corrupted(path, err))?;
+        if listing.entries.len() >= max_entries {
+            listing.truncated = true;
+            break;
+        }
+
+        let entry_type = entry.header().entry_type();
+        listing.entries.push(Member {
+            name: String::from_utf8_lossy(&entry.path_bytes()).into_owned(),
+            size: entry.header().size().unwrap_or_default(),
+            kind: if entry_type.is_dir() {
+                "dir"
+            } else if entry_type.is_symlink() || entry_type.is_hard_link() {
+                "symlink"
+            } else {
+                "file"
+            },
+        });
+    }
+
+    Ok(listing)
+}
+
+/// Renders the members of an archive as an HTML page linking to their extraction.
+fn html_listing(path: &Path, listing: &Listing) -> String {
+    use maud::{DOCTYPE, html};
+
+    let title = path
+        .file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    html! {
+        (DOCTYPE)
+        html {
+            head {
+                meta charset="utf-8";
+                meta name="viewport" content="width=device-width,minimum-scale=1,initial-scale=1";
+                title { "Contents of " (title) }
+                style {
+                    "body{padding:1rem;font-family:Consolas,'Liberation Mono',Menlo,monospace;font-size:.75rem;max-width:70rem;margin:0 auto;color:#4a4a4a;line-height:1.5}h1{margin:0 0 .5rem;font-size:1rem}table{width:100%;border-spacing:0}table th,table td{padding:.15rem 0;white-space:nowrap}table tr th{text-align:left}"
+                }
+            }
+            body {
+                h1 { "Contents of " (title) }
+                table {
+                    thead {
+                        tr {
+                            th { "Name" }
+                            th style="width:10rem;text-align:right;" { "Size" }
+                        }
+                    }
+                    tbody {
+                        @for member in &listing.entries {
+                            tr {
+                                td {
+                                    @if member.kind == "file" {
+                                        a href={
+                                            "?" (EXTRACT_PARAM_KEY) "="
+                                            (form_urlencoded::byte_serialize(member.name.as_bytes()).collect::<String>())
+                                        } { (member.name) }
+                                    } @else {
+                                        (member.name)
+                                    }
+                                }
+                                td align="right" { (member.size) }
+                            }
+                        }
+                    }
+                }
+                @if listing.truncated {
+                    p { "Only the first " (listing.entries.len()) " members are listed." }
+                }
+            }
+        }
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, ArchiveKind};
+    use std::path::Path;
+
+    #[test]
+    fn test_archive_kind() {
+        assert_eq!(
+            ArchiveKind::from_path(Path::new("dist/build.zip")),
+            Some(ArchiveKind::Zip)
+        );
+        assert_eq!(
+            ArchiveKind::from_path(Path::new("dist/build.TAR.GZ")),
+            Some(ArchiveKind::TarGz)
+        );
+        assert_eq!(
+            ArchiveKind::from_path(Path::new("dist/build.tgz")),
+            Some(ArchiveKind::TarGz)
+        );
+        assert_eq!(ArchiveKind::from_path(Path::new("dist/build.gz")), None);
+    }
+
+    #[test]
+    fn test_action_from_query() {
+        assert_eq!(Action::from_query(None), None);
+        assert_eq!(Action::from_query(Some("a=1")), None);
+        assert_eq!(
+            Action::from_query(Some("list")),
+            Some(Action::List { json: false })
+        );
+        assert_eq!(
+            Action::from_query(Some("list=json")),
+            Some(Action::List { json: true })
+        );
+        assert_eq!(
+            Action::from_query(Some("extract=docs%2Fa%20b.txt")),
+            Some(Action::Extract("docs/a b.txt".to_owned()))
+        );
+    }
+}
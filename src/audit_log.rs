@@ -0,0 +1,281 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module to keep an append-only audit log of the configuration reloads and runtime changes.
+//!
+//! Every event is appended to the log file as a JSON line including what triggered it
+//! and the settings keys that changed since the previous successful event.
+//!
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::fs::atomic::{self, FsyncPolicy};
+use crate::settings::include::read_toml_files;
+use crate::{Context, Result, helpers};
+
+/// What triggered a configuration event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Trigger {
+    /// The server start up.
+    Startup,
+    /// A `SIGHUP` or `SIGUSR1` signal.
+    Signal,
+    /// A control API request.
+    ControlApi,
+}
+
+/// A line of the audit log.
+#[derive(Serialize)]
+struct Entry<'a> {
+    timestamp: String,
+    event: &'a str,
+    trigger: Trigger,
+    success: bool,
+    changed: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Audit log of the configuration events.
+pub(crate) struct AuditLog {
+    path: PathBuf,
+    fsync: FsyncPolicy,
+    /// Settings of the last successful event, which also serializes the writes.
+    last: Mutex<Value>,
+}
+
+impl AuditLog {
+    /// Opens the audit log file recording the server start up with the given `general` settings.
+    /// It's disabled if the path is empty.
+    pub fn new(path: &Path, fsync: FsyncPolicy, general: &Value) -> Result<Option<Self>> {
+        if path.as_os_str().is_empty() {
+            tracing::info!("config audit log: enabled=false");
+            return Ok(None);
+        }
+
+        atomic::append(path, b"", FsyncPolicy::Off)
+            .with_context(|| format!("unable to open the config audit log: {}", path.display()))?;
+        tracing::info!("config audit log: enabled=true, path={}", path.display());
+
+        let audit_log = Self {
+            path: path.to_owned(),
+            fsync,
+            last: Mutex::new(snapshot(general)),
+        };
+        audit_log.write(&audit_log.entry("start", Trigger::Startup, Vec::new(), None));
+        Ok(Some(audit_log))
+    }
+
+    /// Records a configuration reload along with the settings keys that changed if successful.
+    pub fn reload(&self, trigger: Trigger, general: &Value, error: Option<&crate::Error>) {
+        let Ok(mut last) = self.last.lock() else {
+            tracing::error!("config audit log lock is poisoned");
+            return;
+        };
+
+        let entry = match error {
+            Some(err) => self.entry("reload", trigger, Vec::new(), Some(format!("{err:#}"))),
+            None => {
+                let next = snapshot(general);
+                let changed = changed_keys(&last, &next);
+                *last = next;
+                self.entry("reload", trigger, changed, None)
+            }
+        };
+        self.write(&entry);
+    }
+
+    /// Records a change of the maintenance mode at runtime, along with its HTML file if swapped.
+    pub fn maintenance_mode(&self, trigger: Trigger, enabled: bool, file_changed: bool) {
+        let Ok(_last) = self.last.lock() else {
+            tracing::error!("config audit log lock is poisoned");
+            return;
+        };
+
+        let event = if enabled {
+            "maintenance-mode-on"
+        } else {
+            "maintenance-mode-off"
+        };
+        let mut changed = vec!["maintenance-mode".to_owned()];
+        if file_changed {
+            changed.push("maintenance-mode-file".to_owned());
+        }
+        self.write(&self.entry(event, trigger, changed, None));
+    }
+
+    fn entry<'a>(
+        &self,
+        event: &'a str,
+        trigger: Trigger,
+        changed: Vec<String>,
+        error: Option<String>,
+    ) -> Entry<'a> {
+        Entry {
+            timestamp: helpers::rfc3339_now(),
+            event,
+            trigger,
+            success: error.is_none(),
+            changed,
+            error,
+        }
+    }
+
+    fn write(&self, entry: &Entry<'_>) {
+        // Each entry is appended in a single write, so entries are never interleaved
+        let result = serde_json::to_string(entry)
+            .map_err(anyhow::Error::from)
+            .and_then(|line| {
+                atomic::append(&self.path, format!("{line}\n").as_bytes(), self.fsync)
+            });
+        if let Err(err) = result {
+            tracing::error!(
+                "unable to write the config audit log {}: {:?}",
+                self.path.display(),
+                err
+            );
+        }
+    }
+}
+
+/// Gets the `general` settings along with the `advanced` ones of the config file if any.
+fn snapshot(general: &Value) -> Value {
+    let mut snapshot = general.clone();
+    if let Some(advanced) = advanced_settings(general) {
+        snapshot["advanced"] = advanced;
+    }
+    snapshot
+}
+
+fn advanced_settings(general: &Value) -> Option<Value> {
+    let config_file = Path::new(general.get("config-file")?.as_str()?);
+    if !config_file.is_file() {
+        return None;
+    }
+    let settings = read_toml_files(config_file).ok()?;
+    serde_json::to_value(settings.get("advanced")?).ok()
+}
+
+/// Gets the settings keys whose values differ, where the `advanced` ones are prefixed.
+fn changed_keys(prev: &Value, next: &Value) -> Vec<String> {
+    let mut changed = Vec::new();
+    diff_keys(prev, next, "", &mut changed);
+    changed
+}
+
+fn diff_keys(prev: &Value, next: &Value, prefix: &str, changed: &mut Vec<String>) {
+    let (Some(prev), Some(next)) = (prev.as_object(), next.as_object()) else {
+        if prev != next {
+            changed.push(prefix.trim_end_matches('.').to_owned());
+        }
+        return;
+    };
+
+    let keys: BTreeSet<&String> = prev.keys().chain(next.keys()).collect();
+    for key in keys {
+        let (prev_value, next_value) = (prev.get(key), next.get(key));
+        if prev_value == next_value {
+            continue;
+        }
+        if prefix.is_empty() && key == "advanced" {
+            let null = Value::Null;
+            diff_keys(
+                prev_value.unwrap_or(&null),
+                next_value.unwrap_or(&null),
+                "advanced.",
+                changed,
+            );
+        } else {
+            changed.push(format!("{prefix}{key}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AuditLog, Trigger, changed_keys};
+    use crate::fs::atomic::FsyncPolicy;
+    use serde_json::{Value, json};
+    use std::path::Path;
+
+    #[test]
+    fn test_changed_keys() {
+        let prev = json!({
+            "root": "./public",
+            "port": 8787,
+            "advanced": { "redirects": [{ "source": "/a" }], "charsets": { "txt": "utf-8" } }
+        });
+        let next = json!({
+            "root": "./public",
+            "port": 8080,
+            "advanced": { "redirects": [{ "source": "/b" }], "rewrites": [] }
+        });
+        assert_eq!(
+            changed_keys(&prev, &next),
+            [
+                "advanced.charsets",
+                "advanced.redirects",
+                "advanced.rewrites",
+                "port"
+            ]
+        );
+        assert!(changed_keys(&prev, &prev).is_empty());
+
+        // Advanced settings added or removed as a whole
+        let next = json!({ "root": "./public", "port": 8787 });
+        assert_eq!(changed_keys(&prev, &next), ["advanced"]);
+    }
+
+    #[test]
+    fn test_audit_log_entries() {
+        let path = std::env::temp_dir().join(format!("sws-audit-{}.log", std::process::id()));
+        std::fs::remove_file(&path).ok();
+
+        let general = json!({ "root": "./public", "config-file": "./missing.toml" });
+        let audit_log = AuditLog::new(&path, FsyncPolicy::Off, &general)
+            .unwrap()
+            .unwrap();
+        let general = json!({ "root": "./html", "config-file": "./missing.toml" });
+        audit_log.reload(Trigger::Signal, &general, None);
+        let err = anyhow::anyhow!("invalid root");
+        audit_log.reload(Trigger::ControlApi, &general, Some(&err));
+        audit_log.maintenance_mode(Trigger::ControlApi, true, false);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        let entries: Vec<Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 4);
+        assert!(entries.iter().all(|entry| entry["timestamp"].is_string()));
+
+        assert_eq!(entries[0]["event"], "start");
+        assert_eq!(entries[0]["trigger"], "startup");
+        assert_eq!(entries[1]["event"], "reload");
+        assert_eq!(entries[1]["trigger"], "signal");
+        assert_eq!(entries[1]["success"], true);
+        assert_eq!(entries[1]["changed"], json!(["root"]));
+        assert_eq!(entries[2]["trigger"], "control-api");
+        assert_eq!(entries[2]["success"], false);
+        assert_eq!(entries[2]["error"], "invalid root");
+        assert_eq!(entries[3]["event"], "maintenance-mode-on");
+        assert_eq!(entries[3]["changed"], json!(["maintenance-mode"]));
+    }
+
+    #[test]
+    fn test_audit_log_disabled() {
+        assert!(
+            AuditLog::new(Path::new(""), FsyncPolicy::Full, &json!({}))
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,275 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that throttles the response bodies to a maximum bandwidth per response
+//! and optionally for all the responses together, E.g. for file mirrors
+//! that must not saturate their uplink.
+//!
+//! The bodies are sent by small pieces, each one reserving its transmission time
+//! on the rate of its response and on the global one, so the responses share
+//! the global bandwidth in turns. The tunneled connections (E.g. WebSocket proxy) send
+//! the data written to their clients the same way.
+//!
+
+use bytes::Bytes;
+use futures_util::{Stream, ready};
+use hyper::{Body, Response, body::HttpBody};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::{Instant, Sleep};
+
+use crate::{Error, handler::RequestHandlerOpts, zero_copy::ZeroCopyFile};
+
+/// Number of pieces sent per second at most, so the bodies flow steadily.
+const PIECES_PER_SECOND: u64 = 10;
+
+/// Maximum size of the pieces of a throttled body.
+const MAX_PIECE_SIZE: u64 = 64 * 1024;
+
+/// Initializes the bandwidth limits.
+pub fn init(limit_rate: u64, limit_rate_global: u64, handler_opts: &mut RequestHandlerOpts) {
+    let enabled = limit_rate > 0 || limit_rate_global > 0;
+    handler_opts.bandwidth_limit =
+        enabled.then(|| BandwidthLimit::new(limit_rate, limit_rate_global));
+    tracing::info!(
+        "bandwidth limit: enabled={enabled}, rate={limit_rate} bytes/s, global rate={limit_rate_global} bytes/s"
+    );
+}
+
+/// Bandwidth limits of the response bodies.
+#[derive(Clone)]
+pub struct BandwidthLimit {
+    /// Maximum rate of every response in bytes per second. Disabled if zero.
+    rate: u64,
+    /// Pacer shared by all the responses if there is a global rate.
+    global: Option<Arc<Mutex<Pacer>>>,
+    /// Size of the pieces of the throttled bodies.
+    piece_size: usize,
+}
+
+impl BandwidthLimit {
+    /// Creates the limits of `rate` bytes per second for every response
+    /// and `global_rate` for all of them, both disabled if zero.
+    pub fn new(rate: u64, global_rate: u64) -> Self {
+        let min_rate = [rate, global_rate]
+            .into_iter()
+            .filter(|rate| *rate > 0)
+            .min()
+            .unwrap_or(MAX_PIECE_SIZE);
+        Self {
+            rate,
+            global: (global_rate > 0).then(|| Arc::new(Mutex::new(Pacer::new(global_rate)))),
+            piece_size: (min_rate / PIECES_PER_SECOND).clamp(1, MAX_PIECE_SIZE) as usize,
+        }
+    }
+
+    /// Reserves the transmission of `len` bytes of a response, returning when they can be sent.
+    fn reserve(&self, pacer: Option<&mut Pacer>, len: usize) -> Instant {
+        let now = Instant::now();
+        let at = pacer.map_or(now, |pacer| pacer.reserve(len, now));
+        match &self.global {
+            Some(global) => global
+                .lock()
+                .map_or(at, |mut global| global.reserve(len, at)),
+            None => at,
+        }
+    }
+}
+
+/// Paces the transmissions to a rate by reserving them one after another.
+struct Pacer {
+    /// Rate in bytes per second.
+    rate: u64,
+    /// Time when the next transmission can start.
+    next: Instant,
+}
+
+impl Pacer {
+    fn new(rate: u64) -> Self {
+        Self {
+            rate,
+            next: Instant::now(),
+        }
+    }
+
+    /// Reserves the transmission of `len` bytes not before `at`, returning when it can start.
+    fn reserve(&mut self, len: usize, at: Instant) -> Instant {
+        let at = at.max(self.next);
+        self.next = at + Duration::from_secs_f64(len as f64 / self.rate as f64);
+        at
+    }
+}
+
+/// Throttles the body of a response if a bandwidth limit is set.
+pub(crate) fn post_process(
+    opts: &RequestHandlerOpts,
+    mut resp: Response<Body>,
+) -> Result<Response<Body>, Error> {
+    let Some(limit) = opts.bandwidth_limit.as_ref() else {
+        return Ok(resp);
+    };
+    if resp.body().is_end_stream() {
+        return Ok(resp);
+    }
+
+    // The zero-copy transmission would bypass the throttled body
+    resp.extensions_mut().remove::<ZeroCopyFile>();
+
+    let (parts, body) = resp.into_parts();
+    let body = Body::wrap_stream(ThrottledBody::new(body, limit.clone()));
+    Ok(Response::from_parts(parts, body))
+}
+
+/// Copies the data of a reader to a writer at the pace of the bandwidth limits if any,
+/// then shuts the writer down. It returns the number of bytes copied.
+pub(crate) async fn copy<R, W>(
+    limit: Option<&BandwidthLimit>,
+    reader: &mut R,
+    writer: &mut W,
+) -> io::Result<u64>
+where
+    R: AsyncRead + Unpin + ?Sized,
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    let Some(limit) = limit else {
+        let copied = tokio::io::copy(reader, writer).await?;
+        writer.shutdown().await?;
+        return Ok(copied);
+    };
+
+    let mut pacer = (limit.rate > 0).then(|| Pacer::new(limit.rate));
+    let mut piece = vec![0; limit.piece_size];
+    let mut copied = 0;
+    loop {
+        let len = reader.read(&mut piece).await?;
+        if len == 0 {
+            writer.shutdown().await?;
+            return Ok(copied);
+        }
+        tokio::time::sleep_until(limit.reserve(pacer.as_mut(), len)).await;
+        writer.write_all(&piece[..len]).await?;
+        writer.flush().await?;
+        copied += len as u64;
+    }
+}
+
+/// Stream of the pieces of a body sent at the pace of the bandwidth limits.
+struct ThrottledBody {
+    body: Body,
+    limit: BandwidthLimit,
+    /// Pacer of the response if there is a rate per response.
+    pacer: Option<Pacer>,
+    /// Contents received from the body and not sent yet.
+    pending: Bytes,
+    /// Piece waiting for its transmission time.
+    delayed: Option<(Pin<Box<Sleep>>, Bytes)>,
+}
+
+impl ThrottledBody {
+    fn new(body: Body, limit: BandwidthLimit) -> Self {
+        Self {
+            body,
+            pacer: (limit.rate > 0).then(|| Pacer::new(limit.rate)),
+            limit,
+            pending: Bytes::new(),
+            delayed: None,
+        }
+    }
+}
+
+impl Stream for ThrottledBody {
+    type Item = Result<Bytes, hyper::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        loop {
+            if let Some((sleep, _)) = pin.delayed.as_mut() {
+                ready!(sleep.as_mut().poll(cx));
+                let (_, piece) = pin.delayed.take().expect("delayed piece");
+                return Poll::Ready(Some(Ok(piece)));
+            }
+
+            if pin.pending.is_empty() {
+                match ready!(Pin::new(&mut pin.body).poll_next(cx)) {
+                    Some(Ok(chunk)) => pin.pending = chunk,
+                    other => return Poll::Ready(other),
+                }
+                continue;
+            }
+
+            let len = pin.pending.len().min(pin.limit.piece_size);
+            let piece = pin.pending.split_to(len);
+            let at = pin.limit.reserve(pin.pacer.as_mut(), len);
+            if at <= Instant::now() {
+                return Poll::Ready(Some(Ok(piece)));
+            }
+            pin.delayed = Some((Box::pin(tokio::time::sleep_until(at)), piece));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BandwidthLimit, ThrottledBody, copy};
+    use hyper::Body;
+    use std::time::{Duration, Instant};
+
+    static BODY: [u8; 3_000] = [7; 3_000];
+
+    async fn send(limit: BandwidthLimit) -> Duration {
+        let started = Instant::now();
+        let body = Body::wrap_stream(ThrottledBody::new(Body::from(&BODY[..]), limit));
+        let bytes = hyper::body::to_bytes(body).await.unwrap();
+        assert_eq!(bytes, BODY[..]);
+        started.elapsed()
+    }
+
+    #[tokio::test]
+    async fn test_rate_per_response() {
+        // The first piece is sent at once and the other two take 100ms each
+        let elapsed = send(BandwidthLimit::new(10_000, 0)).await;
+        assert!(elapsed >= Duration::from_millis(190), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(2), "{elapsed:?}");
+    }
+
+    #[tokio::test]
+    async fn test_global_rate() {
+        // Two responses share the global rate, so they take twice as long
+        let limit = BandwidthLimit::new(0, 10_000);
+        let (first, second) = tokio::join!(send(limit.clone()), send(limit));
+        let elapsed = first.max(second);
+        assert!(elapsed >= Duration::from_millis(490), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(3), "{elapsed:?}");
+    }
+
+    #[tokio::test]
+    async fn test_copy() {
+        let limit = BandwidthLimit::new(10_000, 0);
+        let mut reader = &BODY[..];
+        let mut writer = Vec::new();
+        let started = Instant::now();
+        let copied = copy(Some(&limit), &mut reader, &mut writer).await.unwrap();
+        let elapsed = started.elapsed();
+        assert_eq!(copied, 3_000);
+        assert_eq!(writer, BODY);
+        assert!(elapsed >= Duration::from_millis(190), "{elapsed:?}");
+
+        let mut reader = &BODY[..];
+        let mut writer = Vec::new();
+        assert_eq!(copy(None, &mut reader, &mut writer).await.unwrap(), 3_000);
+        assert_eq!(writer, BODY);
+    }
+
+    #[tokio::test]
+    async fn test_unlimited() {
+        let elapsed = send(BandwidthLimit::new(0, 0)).await;
+        assert!(elapsed < Duration::from_secs(1), "{elapsed:?}");
+    }
+}
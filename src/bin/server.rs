@@ -14,26 +14,44 @@ static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
 use static_web_server::{
     Result, Settings,
-    settings::{Commands, cli::General},
+    fs::atomic,
+    settings::{
+        Commands,
+        cli::{ConfigCommands, General},
+        dump,
+    },
 };
 
+#[cfg(unix)]
+use static_web_server::workers;
+
 fn main() -> Result {
-    let opts = Settings::get(true)?;
+    let mut opts = Settings::get(true)?;
 
     if opts.general.version {
         return static_web_server::settings::cli_output::display_version();
     }
 
-    if let Some(commands) = opts.general.commands {
+    if let Some(commands) = opts.general.commands.take() {
         match commands {
             #[cfg(windows)]
             Commands::Install {} => {
-                return static_web_server::winservice::install_service(&opts.general.config_file);
+                return static_web_server::winservice::install_service(
+                    &opts.general.config_file,
+                    &Default::default(),
+                );
             }
             #[cfg(windows)]
             Commands::Uninstall {} => {
                 return static_web_server::winservice::uninstall_service();
             }
+            #[cfg(windows)]
+            Commands::Service(command) => {
+                return static_web_server::winservice::run_service_command(
+                    command,
+                    &opts.general.config_file,
+                );
+            }
             Commands::Generate {
                 completions,
                 man_pages,
@@ -53,6 +71,54 @@ fn main() -> Result {
                 }
                 return Ok(());
             }
+            Commands::Schema { output } => {
+                let schema = static_web_server::settings::file::Settings::json_schema()?;
+                match output {
+                    Some(path) => {
+                        atomic::write(&path, schema.as_bytes(), opts.general.fsync_policy)?;
+                        tracing::info!("wrote json schema to {}", path.to_string_lossy());
+                    }
+                    None => println!("{schema}"),
+                }
+                return Ok(());
+            }
+            Commands::Selftest {} => {
+                return static_web_server::selftest::run(opts);
+            }
+            Commands::Check {} => {
+                return static_web_server::check::run(opts);
+            }
+            Commands::MigrateConfig { output } => {
+                let (config, deprecations) = static_web_server::settings::migration::migrate_file(
+                    &opts.general.config_file,
+                )?;
+                tracing::info!("migrated {} deprecated key(s)", deprecations.len());
+                match output {
+                    Some(path) => {
+                        atomic::write(&path, config.as_bytes(), opts.general.fsync_policy)?;
+                        tracing::info!(
+                            "wrote migrated configuration to {}",
+                            path.to_string_lossy()
+                        );
+                    }
+                    None => print!("{config}"),
+                }
+                return Ok(());
+            }
+            Commands::Config(ConfigCommands::Dump { format, output }) => {
+                let config = dump::dump(&opts, format)?;
+                match output {
+                    Some(path) => {
+                        atomic::write(&path, config.as_bytes(), opts.general.fsync_policy)?;
+                        tracing::info!(
+                            "wrote effective configuration to {}",
+                            path.to_string_lossy()
+                        );
+                    }
+                    None => print!("{config}"),
+                }
+                return Ok(());
+            }
         }
     }
 
@@ -61,6 +127,17 @@ fn main() -> Result {
         return static_web_server::winservice::run_server_as_service();
     }
 
+    // Run the server in multiple worker processes if enabled
+    #[cfg(unix)]
+    if opts.general.worker_processes > 1 {
+        if workers::worker_id().is_none() {
+            return workers::supervise(&opts);
+        }
+        let cancel = workers::parent_shutdown();
+        static_web_server::Server::new(opts)?.run_standalone(Some(cancel))?;
+        return Ok(());
+    }
+
     // Run the server by default
     static_web_server::Server::new(opts)?.run_standalone(None)?;
 
@@ -0,0 +1,92 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that persists the indices of the in-memory caches on graceful shutdown
+//! and pre-warms the caches with them on the next start, so the latency stays smooth
+//! after routine restarts or deploys.
+//!
+//! Only the cached entries are persisted, their contents are read again from the file system
+//! when warming up the caches, so a file modified in the meantime is never served stale.
+//!
+
+use serde::{Serialize, de::DeserializeOwned};
+use std::path::{Path, PathBuf};
+
+use crate::fs::atomic::{self, FsyncPolicy};
+use crate::{Context, Result, chunk_cache, workers};
+
+/// Returns the index file path of the current process.
+/// Every worker process keeps its own caches, so it gets its own index file.
+pub(crate) fn index_path(path: &Path) -> PathBuf {
+    match workers::worker_id() {
+        Some(id) => {
+            let mut path = path.as_os_str().to_owned();
+            path.push(format!(".{id}"));
+            PathBuf::from(path)
+        }
+        None => path.to_owned(),
+    }
+}
+
+/// Loads the entries of an index file, which is empty if the file does not exist yet.
+pub(crate) fn load<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("unable to read the cache index {}", path.display()));
+        }
+    };
+    serde_json::from_slice(&data)
+        .with_context(|| format!("unable to parse the cache index {}", path.display()))
+}
+
+/// Saves the entries to an index file atomically.
+pub(crate) fn save<T: Serialize>(path: &Path, entries: &[T]) -> Result {
+    let data = serde_json::to_vec(entries)?;
+    // NOTE: an index is only a hint to warm up the caches, so it's not worth syncing it
+    atomic::write(path, &data, FsyncPolicy::Off)
+}
+
+/// Pre-warms the in-memory caches in the background from their persisted indices.
+pub(crate) fn warm_up() {
+    tokio::task::spawn_blocking(|| {
+        #[cfg(feature = "experimental")]
+        crate::mem_cache::cache::warm_up();
+        chunk_cache::warm_up();
+    });
+}
+
+/// Persists the indices of the in-memory caches, which is done on graceful shutdown.
+pub(crate) fn persist() {
+    #[cfg(feature = "experimental")]
+    if let Err(err) = crate::mem_cache::cache::save_index() {
+        tracing::error!("unable to persist the in-memory cache index: {:?}", err);
+    }
+    if let Err(err) = chunk_cache::save_index() {
+        tracing::error!("unable to persist the chunk cache index: {:?}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load, save};
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("sws-cache-{}.index", std::process::id()));
+
+        assert!(load::<String>(&path).unwrap().is_empty());
+
+        let entries = vec!["/a.html".to_owned(), "/b.css".to_owned()];
+        save(&path, &entries).unwrap();
+        assert_eq!(load::<String>(&path).unwrap(), entries);
+
+        std::fs::write(&path, "{invalid").unwrap();
+        assert!(load::<String>(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,306 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module invalidating the in-memory caches when the files under the root directories change,
+//! so the caching features can be enabled safely on frequently redeployed sites.
+//!
+//! The watched directories are notified by the OS of the files added, modified or removed,
+//! sharing the watcher of the live-reload. The changed files are collected until no further
+//! changes are seen for the debounce time, so a deploy replacing many files invalidates the
+//! caches once. The memory and chunk caches drop the changed files only (or everything under
+//! a changed directory), while the not-found and directory size caches are cleared.
+//!
+
+use arc_swap::ArcSwap;
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::fs::watch::{self, Subscription};
+use crate::{Result, chunk_cache, handler::RequestHandlerOpts};
+
+#[cfg(feature = "experimental")]
+use crate::mem_cache;
+
+/// Default time in milliseconds without further changes to wait for before invalidating.
+const DEFAULT_DEBOUNCE: u64 = 500;
+
+/// Time between the checks of the options, which may change on configuration reload.
+const OPTIONS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// It defines the cache invalidation options.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CacheInvalidationOpts {
+    /// Time without further changes to wait for before invalidating the caches.
+    pub debounce: Duration,
+    /// Subtrees of the root directories to watch, relative to them.
+    pub paths: Vec<PathBuf>,
+}
+
+/// Initializes the cache invalidation on file changes.
+pub(crate) fn init(handler_opts: &mut RequestHandlerOpts) -> Result {
+    let cache_invalidation = handler_opts
+        .advanced_opts
+        .as_ref()
+        .and_then(|advanced| advanced.cache_invalidation.as_ref());
+
+    let Some(opts) = cache_invalidation else {
+        tracing::info!("cache invalidation: enabled=false");
+        return Ok(());
+    };
+
+    let debounce = opts.debounce.unwrap_or(DEFAULT_DEBOUNCE);
+
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for path in opts.paths.iter().flatten() {
+        let path = path.trim();
+        if !path.starts_with('/') {
+            bail!("cache invalidation path must start with a slash: {}", path);
+        }
+        let relative = PathBuf::from(path.trim_start_matches('/'));
+        if relative
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+        {
+            bail!(
+                "cache invalidation path must not contain `.` or `..`: {}",
+                path
+            );
+        }
+        // The whole root directories are watched if any path is the root one
+        if relative.as_os_str().is_empty() {
+            paths.clear();
+            break;
+        }
+        if !paths.contains(&relative) {
+            paths.push(relative);
+        }
+    }
+
+    tracing::info!(
+        "cache invalidation: enabled=true, debounce={debounce}ms, paths={:?}",
+        opts.paths.as_deref().unwrap_or_default()
+    );
+
+    handler_opts.cache_invalidation = Some(CacheInvalidationOpts {
+        debounce: Duration::from_millis(debounce),
+        paths,
+    });
+
+    Ok(())
+}
+
+/// Spawns the watcher of the directories served by the current request handler options.
+///
+/// The options are loaded on every check, so the configuration reloads are taken into account.
+pub(crate) fn spawn(handler_opts: Arc<ArcSwap<RequestHandlerOpts>>) {
+    tokio::spawn(watch(handler_opts));
+}
+
+/// Watches the directories for changes, invalidating the caches once they settle.
+async fn watch(handler_opts: Arc<ArcSwap<RequestHandlerOpts>>) {
+    let mut subscription: Option<(Vec<PathBuf>, Subscription)> = None;
+    let mut changed = HashSet::new();
+    let mut changed_at = None;
+    loop {
+        let opts = handler_opts.load_full();
+        let Some(invalidation) = opts.cache_invalidation.clone() else {
+            // Disabled by a configuration reload
+            subscription = None;
+            changed.clear();
+            changed_at = None;
+            tokio::time::sleep(OPTIONS_INTERVAL).await;
+            continue;
+        };
+
+        // The watched directories may change on configuration reload
+        let dirs = watched_dirs(&opts, &invalidation.paths);
+        if subscription.as_ref().is_none_or(|(last, _)| *last != dirs) {
+            subscription = None;
+            match watch::subscribe(&dirs) {
+                Ok(subscribed) => {
+                    tracing::debug!("cache invalidation: watching directories {dirs:?}");
+                    subscription = Some((dirs, subscribed));
+                }
+                Err(err) => tracing::error!("cache invalidation: {err:?}"),
+            }
+        }
+
+        let wait = match changed_at {
+            Some(at) => invalidation
+                .debounce
+                .saturating_sub(Instant::now() - at)
+                .min(OPTIONS_INTERVAL),
+            None => OPTIONS_INTERVAL,
+        };
+        match &mut subscription {
+            Some((_, subscribed)) => {
+                if let Ok(Some(path)) = tokio::time::timeout(wait, subscribed.recv()).await {
+                    changed.insert(path);
+                    changed_at = Some(Instant::now());
+                }
+            }
+            None => tokio::time::sleep(wait).await,
+        }
+
+        if changed_at.is_some_and(|at: Instant| at.elapsed() >= invalidation.debounce) {
+            invalidate(&opts, &changed);
+            changed.clear();
+            changed_at = None;
+        }
+    }
+}
+
+/// Returns the directories to watch, which are the given subtrees of every root directory.
+fn watched_dirs(opts: &RequestHandlerOpts, paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut roots = vec![opts.root_dir.clone()];
+    roots.extend(opts.overlay_dirs.iter().cloned());
+    if let Some(vhosts) = opts
+        .advanced_opts
+        .as_ref()
+        .and_then(|advanced| advanced.virtual_hosts.as_ref())
+    {
+        roots.extend(vhosts.iter().map(|vhost| vhost.root.clone()));
+    }
+
+    let mut dirs = Vec::new();
+    for root in roots {
+        if paths.is_empty() {
+            dirs.push(root);
+        } else {
+            dirs.extend(paths.iter().map(|path| root.join(path)));
+        }
+    }
+    dirs.dedup();
+    dirs
+}
+
+/// Checks if the given path or one of its parent directories changed.
+pub(crate) fn is_changed(changed: &HashSet<PathBuf>, path: &Path) -> bool {
+    path.ancestors().any(|path| changed.contains(path))
+}
+
+/// Invalidates the caches of the changed files, returning the names of the caches invalidated.
+fn invalidate(opts: &RequestHandlerOpts, changed: &HashSet<PathBuf>) -> Vec<&'static str> {
+    let mut invalidated = Vec::new();
+
+    #[cfg(feature = "experimental")]
+    if mem_cache::cache::invalidate(changed) {
+        invalidated.push("memory-cache");
+    }
+
+    if opts.chunk_cache.is_some() {
+        chunk_cache::invalidate(changed);
+        invalidated.push("chunk-cache");
+    }
+
+    // New files may be found for the paths not found before, so the whole cache is cleared
+    if let Some(not_found) = &opts.not_found_cache {
+        not_found.clear();
+        invalidated.push("not-found-cache");
+    }
+
+    #[cfg(feature = "directory-listing")]
+    if let Some(dir_size) = &opts.dir_listing_dir_size {
+        dir_size.clear();
+        invalidated.push("directory-listing-dir-size");
+    }
+
+    tracing::info!(
+        "cache invalidation: {} path(s) changed, caches invalidated: {:?}",
+        changed.len(),
+        invalidated
+    );
+    invalidated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CacheInvalidationOpts, init, invalidate, is_changed, watched_dirs};
+    use crate::handler::RequestHandlerOpts;
+    use crate::not_found_cache::NotFoundCache;
+    use crate::settings::{Advanced, file::CacheInvalidation};
+    use std::collections::HashSet;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    fn opts(invalidation: CacheInvalidation) -> RequestHandlerOpts {
+        RequestHandlerOpts {
+            advanced_opts: Some(Advanced {
+                cache_invalidation: Some(invalidation),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn paths(paths: &[&str]) -> Option<Vec<String>> {
+        Some(paths.iter().map(|path| path.to_string()).collect())
+    }
+
+    #[test]
+    fn test_init() {
+        let mut handler_opts = opts(CacheInvalidation {
+            debounce: Some(0),
+            paths: paths(&["/assets/", "/docs", "/assets"]),
+        });
+        init(&mut handler_opts).unwrap();
+        assert_eq!(
+            handler_opts.cache_invalidation,
+            Some(CacheInvalidationOpts {
+                debounce: Duration::ZERO,
+                paths: vec![PathBuf::from("assets"), PathBuf::from("docs")],
+            })
+        );
+
+        // The root path watches the whole root directories
+        let mut handler_opts = opts(CacheInvalidation {
+            debounce: None,
+            paths: paths(&["/assets", "/"]),
+        });
+        init(&mut handler_opts).unwrap();
+        let invalidation = handler_opts.cache_invalidation.clone().unwrap();
+        assert!(invalidation.paths.is_empty());
+        assert_eq!(
+            watched_dirs(&handler_opts, &invalidation.paths),
+            vec![handler_opts.root_dir.clone()]
+        );
+
+        for path in ["assets", "/assets/../.."] {
+            let mut handler_opts = opts(CacheInvalidation {
+                debounce: None,
+                paths: paths(&[path]),
+            });
+            assert!(init(&mut handler_opts).is_err(), "{path}");
+        }
+    }
+
+    #[test]
+    fn test_is_changed() {
+        let changed = HashSet::from([
+            PathBuf::from("./public/index.html"),
+            PathBuf::from("./public/assets"),
+        ]);
+        assert!(is_changed(&changed, Path::new("./public/index.html")));
+        assert!(is_changed(
+            &changed,
+            Path::new("./public/assets/css/main.css")
+        ));
+        assert!(!is_changed(&changed, Path::new("./public/about.html")));
+        assert!(!is_changed(&changed, Path::new("./public")));
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let handler_opts = RequestHandlerOpts {
+            not_found_cache: Some(NotFoundCache::new(60, 16)),
+            ..Default::default()
+        };
+        let changed = HashSet::from([PathBuf::from("index.html")]);
+        assert!(invalidate(&handler_opts, &changed).contains(&"not-found-cache"));
+    }
+}
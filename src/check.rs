@@ -0,0 +1,285 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that validates the server configuration without starting the server.
+//!
+//! Besides the validations done on start up, it checks the redirect and rewrite targets,
+//! the TLS certificates and private keys as well as the file paths of the configuration,
+//! printing every problem found, which is useful as a pre-deploy step in CI pipelines.
+//!
+
+use hyper::Uri;
+use hyper::header::HeaderValue;
+use regex_lite::Regex;
+use std::path::Path;
+use std::sync::LazyLock;
+
+#[cfg(feature = "http2")]
+use crate::tls::TlsConfigBuilder;
+use crate::{Context, ErrorList, Result, Settings, server, settings::include};
+
+/// Placeholder of a redirect or rewrite destination replaced by a capture group (E.g. `$1`).
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$(\d+)").expect("invalid placeholder regex"));
+
+/// Validates the given settings and prints the problems found.
+///
+/// It fails if any error is found, while warnings (E.g. a missing error page) are only printed.
+pub fn run(opts: Settings) -> Result {
+    let general = &opts.general;
+    let mut errors = ErrorList::default();
+    let mut warnings: Vec<(&str, String)> = Vec::new();
+
+    // Configuration files
+    if general.config_file.is_file() {
+        if let Some(files) = errors.check("config", include::config_files(&general.config_file)) {
+            for file in files {
+                println!("FILE   {}", file.display());
+            }
+        }
+    } else {
+        warnings.push((
+            "config",
+            format!(
+                "config file {} not found, only the arguments and environment variables were checked",
+                general.config_file.display()
+            ),
+        ));
+    }
+
+    // Request handler options: root directories, globs, headers, patterns, etc
+    server::build_request_handler_opts(general, opts.advanced.clone(), &mut errors);
+
+    // Redirect and rewrite targets
+    if let Some(advanced) = &opts.advanced {
+        for (index, redirect) in advanced.redirects.iter().flatten().enumerate() {
+            let relative = errors.check(
+                "redirects",
+                check_destination(&redirect.destination, &redirect.source).with_context(|| {
+                    format!("invalid destination of `advanced.redirects.{index}`")
+                }),
+            );
+            if relative == Some(true) {
+                warnings.push((
+                    "redirects",
+                    format!(
+                        "destination \"{}\" of `advanced.redirects.{index}` is relative to the requested path",
+                        redirect.destination
+                    ),
+                ));
+            }
+        }
+        for (index, rewrite) in advanced.rewrites.iter().flatten().enumerate() {
+            let relative = errors.check(
+                "rewrites",
+                check_destination(&rewrite.destination, &rewrite.source)
+                    .with_context(|| format!("invalid destination of `advanced.rewrites.{index}`")),
+            );
+            // Only a redirect can be relative to the requested path
+            if relative == Some(true) && rewrite.redirect.is_some() {
+                warnings.push((
+                    "rewrites",
+                    format!(
+                        "destination \"{}\" of `advanced.rewrites.{index}` is relative to the requested path",
+                        rewrite.destination
+                    ),
+                ));
+            } else if relative == Some(true) {
+                errors.push(
+                    "rewrites",
+                    anyhow!(
+                        "invalid destination of `advanced.rewrites.{index}`: \"{}\" should be \
+                        an absolute path (E.g. `/new`) or URL (E.g. `https://example.com/new`)",
+                        rewrite.destination
+                    ),
+                );
+            }
+        }
+    }
+
+    // TLS certificates and private keys
+    #[cfg(feature = "http2")]
+    if general.http2 {
+        if let (Some(cert), Some(key)) = (&general.http2_tls_cert, &general.http2_tls_key) {
+            errors.check("tls", check_tls(cert, key));
+        }
+    }
+    #[cfg(feature = "http2")]
+    for listener in &opts.listeners {
+        if let Some(tls) = &listener.tls {
+            errors.check(
+                "listeners",
+                check_tls(&tls.cert, &tls.key)
+                    .with_context(|| format!("invalid TLS of listener {}", listener.address)),
+            );
+        }
+    }
+
+    // File paths
+    if !general.config_audit_log.as_os_str().is_empty() {
+        errors.check(
+            "config-audit-log",
+            check_parent_dir(&general.config_audit_log),
+        );
+    }
+    let resolve_page = |path: &Path| {
+        if path.is_relative() && !path.starts_with(&general.root) {
+            general.root.join(path)
+        } else {
+            path.to_owned()
+        }
+    };
+    for (name, path, default) in [
+        ("page404", &general.page404, "./404.html"),
+        ("page50x", &general.page50x, "./50x.html"),
+    ] {
+        let page = resolve_page(path);
+        if path != Path::new(default) && !page.is_file() {
+            warnings.push((
+                name,
+                format!(
+                    "error page {} not found, a generic error page is used instead",
+                    page.display()
+                ),
+            ));
+        }
+    }
+    #[cfg(feature = "fallback-page")]
+    if !general.page_fallback.as_os_str().is_empty() && !general.page_fallback.is_file() {
+        warnings.push((
+            "page-fallback",
+            format!(
+                "fallback page {} not found, the fallback page is disabled",
+                general.page_fallback.display()
+            ),
+        ));
+    }
+
+    report(errors, &warnings)
+}
+
+/// Checks a redirect or rewrite destination against the capture groups of its source,
+/// returning whether it's a reference relative to the requested path (E.g. `page.html`).
+fn check_destination(destination: &str, source: &Regex) -> Result<bool> {
+    // The capture group zero is the whole match
+    let groups = source.captures_len();
+    for caps in PLACEHOLDER.captures_iter(destination) {
+        let index = caps[1].parse::<usize>().unwrap_or(usize::MAX);
+        if index >= groups {
+            bail!(
+                "placeholder ${} has no matching capture group, the source pattern has {} capture group(s)",
+                &caps[1],
+                groups - 1
+            );
+        }
+    }
+
+    // Destination with every placeholder replaced by a sample value
+    let sample = PLACEHOLDER.replace_all(destination, "x");
+    HeaderValue::from_str(&sample)
+        .with_context(|| format!("\"{destination}\" is not a valid header value"))?;
+
+    if sample.starts_with('/') {
+        sample
+            .parse::<Uri>()
+            .with_context(|| format!("\"{destination}\" is not a valid path"))?;
+        return Ok(false);
+    }
+    if let Ok(uri) = sample.parse::<Uri>() {
+        if let Some(scheme) = uri.scheme_str() {
+            if scheme != "http" && scheme != "https" {
+                bail!("\"{destination}\" has an unsupported scheme `{scheme}`");
+            }
+            if uri.authority().is_none() {
+                bail!("\"{destination}\" has no host");
+            }
+            return Ok(false);
+        }
+    }
+    format!("/{sample}")
+        .parse::<Uri>()
+        .with_context(|| format!("\"{destination}\" is not a valid URL or path"))?;
+    Ok(true)
+}
+
+/// Checks that a TLS certificate and private key can be loaded.
+#[cfg(feature = "http2")]
+fn check_tls(cert: &Path, key: &Path) -> Result {
+    TlsConfigBuilder::new()
+        .cert_path(cert)
+        .key_path(key)
+        .build()
+        .with_context(|| {
+            format!(
+                "invalid TLS certificate {} or private key {}",
+                cert.display(),
+                key.display()
+            )
+        })?;
+    Ok(())
+}
+
+/// Checks that the directory of a file to write exists.
+fn check_parent_dir(path: &Path) -> Result {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => return Ok(()),
+    };
+    if !dir.is_dir() {
+        bail!(
+            "directory {} of file {} was not found",
+            dir.display(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Prints the errors and warnings found, failing if there is any error.
+fn report(errors: ErrorList, warnings: &[(&str, String)]) -> Result {
+    for (module, err) in errors.iter() {
+        println!("ERROR  [{module}] {err:#}");
+    }
+    for (module, warning) in warnings {
+        println!("WARN   [{module}] {warning}");
+    }
+    println!(
+        "check: {} error(s), {} warning(s)",
+        errors.len(),
+        warnings.len()
+    );
+
+    if !errors.is_empty() {
+        bail!("configuration check failed with {} error(s)", errors.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_destination;
+    use regex_lite::Regex;
+
+    #[test]
+    fn test_check_destination() {
+        let source = Regex::new(r"^/old/(.*)/(.*)$").unwrap();
+        assert!(!check_destination("/new/$1/$2", &source).unwrap());
+        assert!(!check_destination("https://example.com/$2?from=$0", &source).unwrap());
+        assert!(check_destination("page-$1.html", &source).unwrap());
+
+        let err = check_destination("/new/$3", &source).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "placeholder $3 has no matching capture group, the source pattern has 2 capture group(s)"
+        );
+        let err = check_destination("ftp://example.com/$1", &source).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "\"ftp://example.com/$1\" has an unsupported scheme `ftp`"
+        );
+        assert!(check_destination("/new path", &source).is_err());
+        assert!(check_destination("/new\n", &source).is_err());
+    }
+}
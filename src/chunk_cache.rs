@@ -0,0 +1,523 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! It provides an in-memory cache of fixed-size blocks of large files,
+//! so the byte ranges requested often (e.g. video seeking) are served
+//! without reading the file system again.
+//!
+//! The eviction from the cache is controlled by the Least Recently Used (LRU) policy.
+//!
+
+use bytes::Bytes;
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "cache-invalidation")]
+use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Mutex, OnceLock};
+use std::task::{Context, Poll};
+use std::time::SystemTime;
+
+use crate::handler::RequestHandlerOpts;
+use crate::{Result, cache_index};
+
+#[cfg(feature = "cache-invalidation")]
+use crate::cache_invalidation::is_changed;
+#[cfg(feature = "metrics")]
+use crate::metrics;
+
+/// Global store of the cached file blocks.
+static BLOCK_STORE: OnceLock<Mutex<BlockStore>> = OnceLock::new();
+
+/// It defines the chunk cache options.
+#[derive(Clone)]
+pub struct ChunkCacheOpts {
+    /// The size of each cached block in bytes.
+    pub block_size: u64,
+    /// The minimum size in bytes for a file to be served through the cache.
+    pub min_file_size: u64,
+}
+
+/// Make sure to initialize the chunk cache store.
+pub(crate) fn init(handler_opts: &mut RequestHandlerOpts) -> Result {
+    let chunk_cache = handler_opts
+        .advanced_opts
+        .as_ref()
+        .and_then(|advanced| advanced.chunk_cache.as_ref());
+
+    let Some(opts) = chunk_cache else {
+        tracing::info!("chunk cache: enabled=false");
+        return Ok(());
+    };
+
+    // Default 1mb
+    let block_size = opts.block_size.unwrap_or(1024);
+    // Default 256 blocks
+    let capacity = opts.capacity.unwrap_or(256);
+    // Default 64mb
+    let min_file_size = opts.min_file_size.unwrap_or(64);
+
+    if block_size == 0 {
+        bail!("chunk cache `block-size` option must be greater than zero");
+    }
+    if capacity == 0 {
+        bail!("chunk cache `capacity` option must be greater than zero");
+    }
+
+    tracing::info!(
+        "chunk cache: enabled=true, block_size={block_size}, capacity={capacity}, min_file_size={min_file_size}, index_file={:?}",
+        opts.index_file
+    );
+
+    let block_size = 1024 * block_size;
+    // NOTE: the store is initialized once and reconfigured on configuration reload
+    let mut store = BLOCK_STORE
+        .get_or_init(|| Mutex::new(BlockStore::new(block_size, capacity as usize)))
+        .lock()
+        .unwrap();
+    store.configure(block_size, capacity as usize);
+    store.index_file = opts.index_file.as_deref().map(cache_index::index_path);
+    drop(store);
+
+    handler_opts.chunk_cache = Some(ChunkCacheOpts {
+        block_size,
+        min_file_size: 1024 * 1024 * min_file_size,
+    });
+
+    Ok(())
+}
+
+/// Identifies a block of a given file version.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct BlockKey {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+    index: u64,
+}
+
+/// Persisted entry of the cache index, which identifies a cached block of a file.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct IndexEntry {
+    path: PathBuf,
+    index: u64,
+}
+
+/// Cached blocks along with their recency of use.
+struct BlockStore {
+    block_size: u64,
+    capacity: usize,
+    index_file: Option<PathBuf>,
+    blocks: HashMap<BlockKey, (Bytes, u64)>,
+    recency: BTreeMap<u64, BlockKey>,
+    tick: u64,
+}
+
+impl BlockStore {
+    fn new(block_size: u64, capacity: usize) -> Self {
+        Self {
+            block_size,
+            capacity,
+            index_file: None,
+            blocks: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    /// Updates the store options, dropping the blocks that no longer fit.
+    fn configure(&mut self, block_size: u64, capacity: usize) {
+        if block_size != self.block_size {
+            self.blocks.clear();
+            self.recency.clear();
+            self.block_size = block_size;
+        }
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    /// Gets a block marking it as the most recently used one.
+    fn get(&mut self, key: &BlockKey) -> Option<Bytes> {
+        let tick = self.next_tick();
+        let (data, used) = self.blocks.get_mut(key)?;
+        self.recency.remove(used);
+        self.recency.insert(tick, key.clone());
+        *used = tick;
+        Some(data.clone())
+    }
+
+    /// Inserts a block evicting the least recently used ones if the store is full.
+    fn insert(&mut self, key: BlockKey, data: Bytes) {
+        let tick = self.next_tick();
+        if let Some((_, used)) = self.blocks.insert(key.clone(), (data, tick)) {
+            self.recency.remove(&used);
+        }
+        self.recency.insert(tick, key);
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.blocks.len() > self.capacity {
+            match self.recency.pop_first() {
+                Some((_, key)) => self.blocks.remove(&key),
+                None => break,
+            };
+        }
+    }
+
+    /// Removes the blocks of the given files or of the files under the given directories,
+    /// returning the number of blocks removed.
+    #[cfg(feature = "cache-invalidation")]
+    fn remove_files(&mut self, paths: &HashSet<PathBuf>) -> usize {
+        let before = self.blocks.len();
+        self.blocks.retain(|key, _| !is_changed(paths, &key.path));
+        self.recency.retain(|_, key| !is_changed(paths, &key.path));
+        before - self.blocks.len()
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    /// Returns the index of the cached blocks from the least to the most recently used one.
+    fn index(&self) -> Vec<IndexEntry> {
+        self.recency
+            .values()
+            .map(|key| IndexEntry {
+                path: key.path.clone(),
+                index: key.index,
+            })
+            .collect()
+    }
+
+    /// Reads the blocks of the given index from the file system,
+    /// skipping the files which no longer exist.
+    fn warm_up(&mut self, index: Vec<IndexEntry>) -> usize {
+        let mut loaded = 0;
+        for entry in index {
+            let Ok(mut file) = File::open(&entry.path) else {
+                continue;
+            };
+            let Ok(meta) = file.metadata() else {
+                continue;
+            };
+            if !meta.is_file() || entry.index * self.block_size >= meta.len() {
+                continue;
+            }
+            let Ok(data) = read_block(&mut file, self.block_size, meta.len(), entry.index) else {
+                continue;
+            };
+            let key = BlockKey {
+                path: entry.path,
+                modified: meta.modified().ok(),
+                len: meta.len(),
+                index: entry.index,
+            };
+            self.insert(key, data);
+            loaded += 1;
+        }
+        loaded
+    }
+}
+
+/// Persists the index of the chunk cache if enabled.
+pub(crate) fn save_index() -> Result {
+    let Some(store) = BLOCK_STORE.get() else {
+        return Ok(());
+    };
+    let store = store.lock().unwrap();
+    let Some(path) = store.index_file.as_deref() else {
+        return Ok(());
+    };
+    let index = store.index();
+    cache_index::save(path, &index)?;
+    tracing::info!(
+        "chunk cache: {} block(s) persisted to the index {}",
+        index.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Pre-warms the chunk cache from its persisted index if enabled.
+pub(crate) fn warm_up() {
+    let Some(store) = BLOCK_STORE.get() else {
+        return;
+    };
+    let Some(path) = store.lock().unwrap().index_file.clone() else {
+        return;
+    };
+    let index = match cache_index::load::<IndexEntry>(&path) {
+        Ok(index) => index,
+        Err(err) => {
+            tracing::warn!("chunk cache: unable to load the index, skipping the warm-up: {err:?}");
+            return;
+        }
+    };
+    // NOTE: the blocks are read before locking the store, so the requests are never held back
+    let mut warm = BlockStore::new(store.lock().unwrap().block_size, index.len());
+    let loaded = warm.warm_up(index);
+    let mut store = store.lock().unwrap();
+    if store.block_size == warm.block_size {
+        for key in warm.recency.into_values() {
+            if let Some((data, _)) = warm.blocks.remove(&key) {
+                if !store.blocks.contains_key(&key) {
+                    store.insert(key, data);
+                }
+            }
+        }
+    }
+    tracing::info!(
+        "chunk cache: {} block(s) pre-warmed from the index {}",
+        loaded,
+        path.display()
+    );
+}
+
+/// Removes the cached blocks of the given files or directories if the chunk cache is enabled.
+#[cfg(feature = "cache-invalidation")]
+pub(crate) fn invalidate(paths: &HashSet<PathBuf>) {
+    if let Some(store) = BLOCK_STORE.get() {
+        let removed = store.lock().unwrap().remove_files(paths);
+        tracing::debug!("chunk cache: {removed} block(s) of the changed files removed");
+    }
+}
+
+/// Reads a block of a file.
+fn read_block(file: &mut File, block_size: u64, len: u64, index: u64) -> io::Result<Bytes> {
+    let start = index * block_size;
+    let size = block_size.min(len.saturating_sub(start));
+    let mut buf = Vec::with_capacity(size as usize);
+    file.seek(SeekFrom::Start(start))?;
+    file.take(size).read_to_end(&mut buf)?;
+    Ok(Bytes::from(buf))
+}
+
+/// A file stream serving a byte range by blocks taken from the chunk cache
+/// or read from the file system and cached otherwise.
+pub(crate) struct ChunkCacheStream {
+    file: File,
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+    block_size: u64,
+    pos: u64,
+    end: u64,
+}
+
+impl ChunkCacheStream {
+    /// Creates a stream for the `start..end` byte range of the given file.
+    pub(crate) fn new(
+        file: File,
+        path: &Path,
+        meta: &Metadata,
+        opts: &ChunkCacheOpts,
+        start: u64,
+        end: u64,
+    ) -> Self {
+        Self {
+            file,
+            path: path.to_owned(),
+            modified: meta.modified().ok(),
+            len: meta.len(),
+            block_size: opts.block_size,
+            pos: start,
+            end,
+        }
+    }
+
+    /// Gets a block from the cache store or reads it from the file otherwise.
+    fn block(&mut self, index: u64) -> io::Result<Bytes> {
+        let key = BlockKey {
+            path: self.path.clone(),
+            modified: self.modified,
+            len: self.len,
+            index,
+        };
+
+        let store = BLOCK_STORE.get();
+        if let Some(data) = store.and_then(|store| store.lock().unwrap().get(&key)) {
+            #[cfg(feature = "metrics")]
+            metrics::chunk_cache_lookup(true);
+            return Ok(data);
+        }
+        #[cfg(feature = "metrics")]
+        metrics::chunk_cache_lookup(false);
+
+        let data = read_block(&mut self.file, self.block_size, self.len, index)?;
+        if let Some(store) = store {
+            store.lock().unwrap().insert(key, data.clone());
+        }
+        Ok(data)
+    }
+}
+
+impl Stream for ChunkCacheStream {
+    type Item = Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = Pin::into_inner(self);
+        if this.pos >= this.end {
+            return Poll::Ready(None);
+        }
+
+        let index = this.pos / this.block_size;
+        let block = match this.block(index) {
+            Ok(block) => block,
+            Err(err) => return Poll::Ready(Some(Err(anyhow::Error::from(err)))),
+        };
+
+        let offset = (this.pos - index * this.block_size) as usize;
+        let size = (block.len().saturating_sub(offset) as u64).min(this.end - this.pos);
+        if size == 0 {
+            // The file was truncated after its metadata was read
+            return Poll::Ready(None);
+        }
+
+        this.pos += size;
+        Poll::Ready(Some(Ok(block.slice(offset..offset + size as usize))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockKey, BlockStore, ChunkCacheOpts, ChunkCacheStream, IndexEntry};
+    use bytes::Bytes;
+    use futures_util::StreamExt;
+    #[cfg(feature = "cache-invalidation")]
+    use std::collections::HashSet;
+    use std::fs::File;
+    use std::path::{Path, PathBuf};
+
+    fn key(index: u64) -> BlockKey {
+        BlockKey {
+            path: PathBuf::from("video.mp4"),
+            modified: None,
+            len: 1024,
+            index,
+        }
+    }
+
+    #[test]
+    fn test_store_evicts_least_recently_used() {
+        let mut store = BlockStore::new(4, 2);
+        store.insert(key(0), Bytes::from_static(b"aaaa"));
+        store.insert(key(1), Bytes::from_static(b"bbbb"));
+        assert_eq!(store.get(&key(0)), Some(Bytes::from_static(b"aaaa")));
+
+        // Block 1 is the least recently used one
+        store.insert(key(2), Bytes::from_static(b"cccc"));
+        assert_eq!(store.get(&key(1)), None);
+        assert!(store.get(&key(0)).is_some());
+        assert!(store.get(&key(2)).is_some());
+        assert_eq!(store.blocks.len(), store.recency.len());
+    }
+
+    #[test]
+    #[cfg(feature = "cache-invalidation")]
+    fn test_store_removes_files() {
+        let mut store = BlockStore::new(4, 8);
+        store.insert(key(0), Bytes::from_static(b"aaaa"));
+        store.insert(key(1), Bytes::from_static(b"bbbb"));
+        let mut other = key(0);
+        other.path = PathBuf::from("audio.mp3");
+        store.insert(other.clone(), Bytes::from_static(b"cccc"));
+
+        let paths = HashSet::from([PathBuf::from("video.mp4")]);
+        assert_eq!(store.remove_files(&paths), 2);
+        assert_eq!(store.get(&key(0)), None);
+        assert!(store.get(&other).is_some());
+
+        // The files under a changed directory are removed
+        let mut nested = key(0);
+        nested.path = PathBuf::from("media/video.mp4");
+        store.insert(nested.clone(), Bytes::from_static(b"dddd"));
+        assert_eq!(
+            store.remove_files(&HashSet::from([PathBuf::from("media")])),
+            1
+        );
+        assert_eq!(store.get(&nested), None);
+        assert_eq!(store.blocks.len(), store.recency.len());
+    }
+
+    #[test]
+    fn test_store_configure() {
+        let mut store = BlockStore::new(4, 3);
+        for index in 0..3 {
+            store.insert(key(index), Bytes::from_static(b"data"));
+        }
+
+        store.configure(4, 1);
+        assert_eq!(store.blocks.len(), 1);
+        assert!(store.get(&key(2)).is_some());
+
+        store.configure(8, 1);
+        assert!(store.blocks.is_empty());
+        assert!(store.recency.is_empty());
+    }
+
+    #[test]
+    fn test_store_index_warm_up() {
+        let path = PathBuf::from("tests/fixtures/public/index.htm");
+        let content = std::fs::read(&path).unwrap();
+        let entry = |path: &str, index| IndexEntry {
+            path: PathBuf::from(path),
+            index,
+        };
+
+        let mut store = BlockStore::new(16, 8);
+        let index = vec![
+            entry("tests/fixtures/public/index.htm", 1),
+            entry("tests/fixtures/public/missing.htm", 0),
+            entry("tests/fixtures/public/index.htm", 0),
+            entry("tests/fixtures/public/index.htm", u64::MAX / 16),
+        ];
+        assert_eq!(store.warm_up(index), 2);
+
+        // The recency of the blocks is kept
+        assert_eq!(
+            store.index(),
+            vec![
+                entry("tests/fixtures/public/index.htm", 1),
+                entry("tests/fixtures/public/index.htm", 0),
+            ]
+        );
+        let (data, _) = store.blocks.values().min_by_key(|(_, used)| *used).unwrap();
+        assert_eq!(&data[..], &content[16..32]);
+    }
+
+    async fn read_range(path: &Path, block_size: u64, start: u64, end: u64) -> Vec<u8> {
+        let file = File::open(path).unwrap();
+        let meta = file.metadata().unwrap();
+        let opts = ChunkCacheOpts {
+            block_size,
+            min_file_size: 0,
+        };
+        let stream = ChunkCacheStream::new(file, path, &meta, &opts, start, end);
+        let chunks: Vec<_> = stream.map(|chunk| chunk.unwrap()).collect().await;
+        chunks.concat()
+    }
+
+    #[tokio::test]
+    async fn test_stream_ranges() {
+        let path = Path::new("tests/fixtures/public/index.htm");
+        let content = std::fs::read(path).unwrap();
+        let len = content.len() as u64;
+
+        for (block_size, start, end) in [(7, 0, len), (7, 3, 20), (16, 15, 17), (64, len - 5, len)]
+        {
+            assert_eq!(
+                read_range(path, block_size, start, end).await,
+                &content[start as usize..end as usize],
+                "block_size={block_size}, range={start}-{end}"
+            );
+        }
+    }
+}
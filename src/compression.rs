@@ -14,30 +14,48 @@ use async_compression::tokio::bufread::BrotliEncoder;
 use async_compression::tokio::bufread::DeflateEncoder;
 #[cfg(any(feature = "compression", feature = "compression-gzip"))]
 use async_compression::tokio::bufread::GzipEncoder;
-#[cfg(any(feature = "compression", feature = "compression-zstd"))]
-use async_compression::tokio::bufread::ZstdEncoder;
 
 use bytes::Bytes;
 use futures_util::Stream;
-use headers::{ContentType, HeaderMap, HeaderMapExt, HeaderValue};
+use headers::{ContentLength, ContentType, HeaderMap, HeaderMapExt, HeaderValue};
 use hyper::{
     Body, Method, Request, Response, StatusCode,
-    header::{CONTENT_ENCODING, CONTENT_LENGTH},
+    header::{CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE},
 };
 use mime_guess::{Mime, mime};
 use pin_project::pin_project;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
+#[cfg(any(
+    feature = "compression",
+    feature = "compression-gzip",
+    feature = "compression-deflate",
+    feature = "compression-brotli"
+))]
+use tokio::io::AsyncRead;
+#[cfg(any(
+    feature = "compression",
+    feature = "compression-gzip",
+    feature = "compression-deflate",
+    feature = "compression-brotli"
+))]
 use tokio_util::io::{ReaderStream, StreamReader};
 
 use crate::{
-    Error, Result, error_page,
+    Error, Result,
+    compression_adaptive::{self, Mode},
+    error_page,
     handler::RequestHandlerOpts,
     headers_ext::{AcceptEncoding, ContentCoding},
     http_ext::MethodExt,
     settings::CompressionLevel,
+    type_map::TypeInfo,
 };
 
+#[cfg(any(feature = "compression", feature = "compression-zstd"))]
+use crate::compression_pool;
+
 /// Contains a fixed list of common text-based MIME types that aren't recognizable in a generic way.
 const TEXT_MIME_TYPES: [&str; 8] = [
     "application/rtf",
@@ -50,6 +68,9 @@ const TEXT_MIME_TYPES: [&str; 8] = [
     "application/wasm",
 ];
 
+/// Maximum size in bytes of every compressed chunk sent to the client.
+pub(crate) const COMPRESSION_BUFFER_SIZE: usize = 8 * 1024;
+
 /// List of encodings that can be handled given enabled features.
 const AVAILABLE_ENCODINGS: &[ContentCoding] = &[
     #[cfg(any(feature = "compression", feature = "compression-deflate"))]
@@ -62,10 +83,49 @@ const AVAILABLE_ENCODINGS: &[ContentCoding] = &[
     ContentCoding::ZSTD,
 ];
 
+/// Compression levels per algorithm which take precedence over the general compression level.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AlgorithmLevels {
+    /// Gzip and Deflate compression level (`0` to `9`).
+    pub gzip: Option<i32>,
+    /// Brotli compression level (`0` to `11`).
+    pub brotli: Option<i32>,
+    /// Zstd compression level (`1` to `22`).
+    pub zstd: Option<i32>,
+}
+
+impl AlgorithmLevels {
+    /// Checks that every algorithm-specific level is within its valid range.
+    pub(crate) fn validate(&self) -> Result {
+        let ranges = [
+            ("gzip", self.gzip, 0..=9),
+            ("brotli", self.brotli, 0..=11),
+            ("zstd", self.zstd, 1..=22),
+        ];
+        for (name, level, range) in ranges {
+            if let Some(level) = level.filter(|v| !range.contains(v)) {
+                bail!(
+                    "invalid {name} compression level {level}, it should be between {} and {}",
+                    range.start(),
+                    range.end()
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Initializes dynamic compression.
-pub fn init(enabled: bool, level: CompressionLevel, handler_opts: &mut RequestHandlerOpts) {
+pub fn init(
+    enabled: bool,
+    level: CompressionLevel,
+    levels: AlgorithmLevels,
+    handler_opts: &mut RequestHandlerOpts,
+) -> Result {
+    levels.validate()?;
     handler_opts.compression = enabled;
     handler_opts.compression_level = level;
+    handler_opts.compression_levels = levels;
 
     const FORMATS: &[&str] = &[
         #[cfg(any(feature = "compression", feature = "compression-deflate"))]
@@ -78,9 +138,51 @@ pub fn init(enabled: bool, level: CompressionLevel, handler_opts: &mut RequestHa
         "zstd",
     ];
     tracing::info!(
-        "auto compression: enabled={enabled}, formats={}, compression level={level:?}",
+        "auto compression: enabled={enabled}, formats={}, compression level={level:?}, algorithm levels={levels:?}",
         FORMATS.join(",")
     );
+    Ok(())
+}
+
+/// Initializes the dynamic compression filters by response size and MIME type.
+pub fn init_filters(
+    min_size: u64,
+    mime_types: &str,
+    exclude_mime_types: &str,
+    handler_opts: &mut RequestHandlerOpts,
+) -> Result {
+    handler_opts.compression_min_size = min_size;
+    handler_opts.compression_mime_types = parse_mime_types(mime_types)?;
+    handler_opts.compression_exclude_mime_types = parse_mime_types(exclude_mime_types)?;
+    tracing::info!(
+        "auto compression filters: min size={min_size}, mime types={}, exclude mime types={}",
+        handler_opts.compression_mime_types.join(","),
+        handler_opts.compression_exclude_mime_types.join(",")
+    );
+    Ok(())
+}
+
+/// Parses a comma-separated list of MIME types or MIME type wildcards (E.g `image/*`).
+pub(crate) fn parse_mime_types(mime_types: &str) -> Result<Vec<String>> {
+    let mut patterns = Vec::new();
+    for pattern in mime_types
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        let pattern = pattern.to_lowercase();
+        let is_valid = match pattern.split_once('/') {
+            Some((type_, subtype)) => {
+                !type_.is_empty() && type_ != "*" && !subtype.is_empty() && !subtype.contains('/')
+            }
+            None => false,
+        };
+        if !is_valid {
+            bail!("invalid compression MIME type: {pattern}");
+        }
+        patterns.push(pattern);
+    }
+    Ok(patterns)
 }
 
 /// Post-processing to dynamically compress the response if necessary.
@@ -110,8 +212,26 @@ pub(crate) fn post_process<T>(
     );
     resp.headers_mut().insert(hyper::header::VARY, value);
 
+    // Skip the compression stage entirely for responses without a body to compress
+    // or whose body is a range of the original one
+    if req.method().is_head() || req.method().is_options() || !has_compressible_body(&resp) {
+        return Ok(resp);
+    }
+
+    // Skip compression for small responses or filtered MIME types
+    if !is_compressible(opts, &resp) {
+        return Ok(resp);
+    }
+
+    // Lower the compression level or skip compression under CPU load
+    let (level, levels) = match opts.compression_adaptive.then(compression_adaptive::mode) {
+        Some(Mode::Disabled) => return Ok(resp),
+        Some(Mode::Reduced) => (CompressionLevel::Fastest, AlgorithmLevels::default()),
+        _ => (opts.compression_level, opts.compression_levels),
+    };
+
     // Auto compression based on the `Accept-Encoding` header
-    match auto(req.method(), req.headers(), opts.compression_level, resp) {
+    match encode(req.method(), req.headers(), level, levels, resp) {
         Ok(resp) => Ok(resp),
         Err(err) => {
             tracing::error!("error during body compression: {:?}", err);
@@ -119,8 +239,7 @@ pub(crate) fn post_process<T>(
                 req.uri(),
                 req.method(),
                 &StatusCode::INTERNAL_SERVER_ERROR,
-                &opts.page404,
-                &opts.page50x,
+                &opts.error_pages,
             )
         }
     }
@@ -135,6 +254,25 @@ pub fn auto(
     headers: &HeaderMap<HeaderValue>,
     level: CompressionLevel,
     resp: Response<Body>,
+) -> Result<Response<Body>> {
+    // Skip compression for non-text-based MIME types
+    if let Some(content_type) = resp.headers().typed_get::<ContentType>() {
+        if !is_text(&Mime::from(content_type)) {
+            return Ok(resp);
+        }
+    }
+
+    encode(method, headers, level, AlgorithmLevels::default(), resp)
+}
+
+/// Compresses the Body of a [`hyper::Response`] using the preferred encoding
+/// of the `Accept-Encoding` header regardless of its MIME type.
+fn encode(
+    method: &Method,
+    headers: &HeaderMap<HeaderValue>,
+    level: CompressionLevel,
+    levels: AlgorithmLevels,
+    resp: Response<Body>,
 ) -> Result<Response<Body>> {
     // Skip compression for HEAD and OPTIONS request methods
     if method.is_head() || method.is_options() {
@@ -148,35 +286,28 @@ pub fn auto(
             encoding
         );
 
-        // Skip compression for non-text-based MIME types
-        if let Some(content_type) = resp.headers().typed_get::<ContentType>() {
-            if !is_text(Mime::from(content_type)) {
-                return Ok(resp);
-            }
-        }
-
         #[cfg(any(feature = "compression", feature = "compression-gzip"))]
         if encoding == ContentCoding::GZIP {
             let (head, body) = resp.into_parts();
-            return Ok(gzip(head, body.into(), level));
+            return Ok(gzip(head, body.into(), level, levels.gzip));
         }
 
         #[cfg(any(feature = "compression", feature = "compression-deflate"))]
         if encoding == ContentCoding::DEFLATE {
             let (head, body) = resp.into_parts();
-            return Ok(deflate(head, body.into(), level));
+            return Ok(deflate(head, body.into(), level, levels.gzip));
         }
 
         #[cfg(any(feature = "compression", feature = "compression-brotli"))]
         if encoding == ContentCoding::BROTLI {
             let (head, body) = resp.into_parts();
-            return Ok(brotli(head, body.into(), level));
+            return Ok(brotli(head, body.into(), level, levels.brotli));
         }
 
         #[cfg(any(feature = "compression", feature = "compression-zstd"))]
         if encoding == ContentCoding::ZSTD {
             let (head, body) = resp.into_parts();
-            return Ok(zstd(head, body.into(), level));
+            return Ok(zstd(head, body.into(), level, levels.zstd));
         }
 
         tracing::trace!(
@@ -187,8 +318,65 @@ pub fn auto(
     Ok(resp)
 }
 
+/// Checks whether the response status allows compressing its body.
+/// `204 No Content` and `304 Not Modified` responses have no body while the body of
+/// `206 Partial Content` responses is a range of the original body.
+fn has_compressible_body(resp: &Response<Body>) -> bool {
+    !matches!(
+        resp.status(),
+        StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED | StatusCode::PARTIAL_CONTENT
+    ) && !resp.headers().contains_key(CONTENT_RANGE)
+}
+
+/// Checks whether the response can be compressed based on its size and MIME type.
+/// If no allowed MIME types are configured then only text-based MIME types are compressed.
+fn is_compressible(opts: &RequestHandlerOpts, resp: &Response<Body>) -> bool {
+    let content_length = resp.headers().typed_get::<ContentLength>().map(|v| v.0);
+    if content_length.is_some_and(|len| len < opts.compression_min_size) {
+        tracing::trace!("response body is smaller than the compression min size, skipping");
+        return false;
+    }
+
+    // The compressibility of the served file type is already known if the type map is enabled
+    if let Some(info) = resp.extensions().get::<Arc<TypeInfo>>() {
+        if resp.headers().get(CONTENT_TYPE) == Some(&info.content_type) {
+            return info.compressible;
+        }
+    }
+
+    match resp.headers().typed_get::<ContentType>() {
+        Some(content_type) => is_compressible_mime(opts, &Mime::from(content_type)),
+        None => true,
+    }
+}
+
+/// Checks whether a MIME type can be compressed according to the allowed
+/// and excluded MIME types.
+pub(crate) fn is_compressible_mime(opts: &RequestHandlerOpts, mime: &Mime) -> bool {
+    if matches_mime_type(&opts.compression_exclude_mime_types, mime) {
+        tracing::trace!("response mime type is excluded from compression, skipping");
+        return false;
+    }
+    if opts.compression_mime_types.is_empty() {
+        is_text(mime)
+    } else {
+        matches_mime_type(&opts.compression_mime_types, mime)
+    }
+}
+
+/// Checks whether the MIME type matches any of the given MIME types or MIME type wildcards.
+fn matches_mime_type(patterns: &[String], mime: &Mime) -> bool {
+    let essence = mime.essence_str();
+    patterns
+        .iter()
+        .any(|pattern| match pattern.strip_suffix("/*") {
+            Some(type_) => type_.eq_ignore_ascii_case(mime.type_().as_str()),
+            None => pattern.eq_ignore_ascii_case(essence),
+        })
+}
+
 /// Checks whether the MIME type corresponds to any of the known text types.
-fn is_text(mime: Mime) -> bool {
+fn is_text(mime: &Mime) -> bool {
     mime.type_() == mime::TEXT
         || mime
             .suffix()
@@ -198,114 +386,136 @@ fn is_text(mime: Mime) -> bool {
 
 /// Create a wrapping handler that compresses the Body of a [`Response`].
 /// using gzip, adding `content-encoding: gzip` to the Response's [`HeaderMap`].
+/// The optional algorithm-specific level takes precedence over the general compression level.
 #[cfg(any(feature = "compression", feature = "compression-gzip"))]
 #[cfg_attr(
     docsrs,
     doc(cfg(any(feature = "compression", feature = "compression-gzip")))
 )]
 pub fn gzip(
-    mut head: http::response::Parts,
+    head: http::response::Parts,
     body: CompressableBody<Body, hyper::Error>,
     level: CompressionLevel,
+    algorithm_level: Option<i32>,
 ) -> Response<Body> {
     const DEFAULT_COMPRESSION_LEVEL: i32 = 4;
 
     tracing::trace!("compressing response body on the fly using GZIP");
 
-    let level = level.into_algorithm_level(DEFAULT_COMPRESSION_LEVEL);
-    let body = Body::wrap_stream(ReaderStream::new(GzipEncoder::with_quality(
-        StreamReader::new(body),
-        level,
-    )));
-    let header = create_encoding_header(head.headers.remove(CONTENT_ENCODING), ContentCoding::GZIP);
-    head.headers.remove(CONTENT_LENGTH);
-    head.headers.insert(CONTENT_ENCODING, header);
-    Response::from_parts(head, body)
+    let level = level.into_algorithm_level(algorithm_level, DEFAULT_COMPRESSION_LEVEL);
+    let encoder = GzipEncoder::with_quality(StreamReader::new(body), level);
+    compressed_response(head, encoder, ContentCoding::GZIP)
 }
 
 /// Create a wrapping handler that compresses the Body of a [`Response`].
 /// using deflate, adding `content-encoding: deflate` to the Response's [`HeaderMap`].
+/// The optional algorithm-specific level takes precedence over the general compression level.
 #[cfg(any(feature = "compression", feature = "compression-deflate"))]
 #[cfg_attr(
     docsrs,
     doc(cfg(any(feature = "compression", feature = "compression-deflate")))
 )]
 pub fn deflate(
-    mut head: http::response::Parts,
+    head: http::response::Parts,
     body: CompressableBody<Body, hyper::Error>,
     level: CompressionLevel,
+    algorithm_level: Option<i32>,
 ) -> Response<Body> {
     const DEFAULT_COMPRESSION_LEVEL: i32 = 4;
 
     tracing::trace!("compressing response body on the fly using DEFLATE");
 
-    let level = level.into_algorithm_level(DEFAULT_COMPRESSION_LEVEL);
-    let body = Body::wrap_stream(ReaderStream::new(DeflateEncoder::with_quality(
-        StreamReader::new(body),
-        level,
-    )));
-    let header = create_encoding_header(
-        head.headers.remove(CONTENT_ENCODING),
-        ContentCoding::DEFLATE,
-    );
-    head.headers.remove(CONTENT_LENGTH);
-    head.headers.insert(CONTENT_ENCODING, header);
-    Response::from_parts(head, body)
+    let level = level.into_algorithm_level(algorithm_level, DEFAULT_COMPRESSION_LEVEL);
+    let encoder = DeflateEncoder::with_quality(StreamReader::new(body), level);
+    compressed_response(head, encoder, ContentCoding::DEFLATE)
 }
 
 /// Create a wrapping handler that compresses the Body of a [`Response`].
 /// using brotli, adding `content-encoding: br` to the Response's [`HeaderMap`].
+/// The optional algorithm-specific level takes precedence over the general compression level.
 #[cfg(any(feature = "compression", feature = "compression-brotli"))]
 #[cfg_attr(
     docsrs,
     doc(cfg(any(feature = "compression", feature = "compression-brotli")))
 )]
 pub fn brotli(
-    mut head: http::response::Parts,
+    head: http::response::Parts,
     body: CompressableBody<Body, hyper::Error>,
     level: CompressionLevel,
+    algorithm_level: Option<i32>,
 ) -> Response<Body> {
     const DEFAULT_COMPRESSION_LEVEL: i32 = 4;
 
     tracing::trace!("compressing response body on the fly using BROTLI");
 
-    let level = level.into_algorithm_level(DEFAULT_COMPRESSION_LEVEL);
-    let body = Body::wrap_stream(ReaderStream::new(BrotliEncoder::with_quality(
-        StreamReader::new(body),
-        level,
-    )));
-    let header =
-        create_encoding_header(head.headers.remove(CONTENT_ENCODING), ContentCoding::BROTLI);
-    head.headers.remove(CONTENT_LENGTH);
-    head.headers.insert(CONTENT_ENCODING, header);
-    Response::from_parts(head, body)
+    let level = level.into_algorithm_level(algorithm_level, DEFAULT_COMPRESSION_LEVEL);
+    let encoder = BrotliEncoder::with_quality(StreamReader::new(body), level);
+    compressed_response(head, encoder, ContentCoding::BROTLI)
 }
 
 /// Create a wrapping handler that compresses the Body of a [`Response`].
 /// using zstd, adding `content-encoding: zstd` to the Response's [`HeaderMap`].
+/// The optional algorithm-specific level takes precedence over the general compression level.
 #[cfg(any(feature = "compression", feature = "compression-zstd"))]
 #[cfg_attr(
     docsrs,
     doc(cfg(any(feature = "compression", feature = "compression-zstd")))
 )]
 pub fn zstd(
-    mut head: http::response::Parts,
+    head: http::response::Parts,
     body: CompressableBody<Body, hyper::Error>,
     level: CompressionLevel,
+    algorithm_level: Option<i32>,
 ) -> Response<Body> {
     const DEFAULT_COMPRESSION_LEVEL: i32 = 3;
 
     tracing::trace!("compressing response body on the fly using ZSTD");
 
-    let level = level.into_algorithm_level(DEFAULT_COMPRESSION_LEVEL);
-    let body = Body::wrap_stream(ReaderStream::new(ZstdEncoder::with_quality(
-        StreamReader::new(body),
-        level,
-    )));
-    let header = create_encoding_header(head.headers.remove(CONTENT_ENCODING), ContentCoding::ZSTD);
+    let level = level.into_algorithm_level(algorithm_level, DEFAULT_COMPRESSION_LEVEL);
+    // NOTE: zstd contexts are pooled since they are expensive to create per response
+    let body = Body::wrap_stream(compression_pool::zstd(body, level));
+    Response::from_parts(compressed_head(head, ContentCoding::ZSTD), body)
+}
+
+/// Create a [`Response`] whose body is compressed on the fly by the given encoder,
+/// adding `content-encoding: <coding>` to the Response's [`HeaderMap`].
+///
+/// The body is compressed in a streaming fashion: the encoder only reads the next chunk of
+/// the inner body when the client is ready to receive more compressed data, so the memory
+/// used stays bounded regardless of the body size.
+/// The [`COMPRESSION_BUFFER_SIZE`] only determines the size of the compressed chunks sent.
+/// Since the compressed size is not known in advance, the `Content-Length` header is removed
+/// so the body is sent using chunked transfer encoding (HTTP/1.1) or data frames (HTTP/2).
+#[cfg(any(
+    feature = "compression",
+    feature = "compression-gzip",
+    feature = "compression-deflate",
+    feature = "compression-brotli"
+))]
+fn compressed_response<E>(
+    head: http::response::Parts,
+    encoder: E,
+    coding: ContentCoding,
+) -> Response<Body>
+where
+    E: AsyncRead + Send + 'static,
+{
+    let body = Body::wrap_stream(ReaderStream::with_capacity(
+        encoder,
+        COMPRESSION_BUFFER_SIZE,
+    ));
+    Response::from_parts(compressed_head(head, coding), body)
+}
+
+/// Adds `content-encoding: <coding>` to the response head, removing its `Content-Length`.
+fn compressed_head(
+    mut head: http::response::Parts,
+    coding: ContentCoding,
+) -> http::response::Parts {
+    let header = create_encoding_header(head.headers.remove(CONTENT_ENCODING), coding);
     head.headers.remove(CONTENT_LENGTH);
     head.headers.insert(CONTENT_ENCODING, header);
-    Response::from_parts(head, body)
+    head
 }
 
 /// Given an optional existing encoding header, appends to the existing or creates a new one.
@@ -382,3 +592,100 @@ impl From<Body> for CompressableBody<Body, hyper::Error> {
         CompressableBody { body }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{AlgorithmLevels, matches_mime_type, parse_mime_types, post_process};
+    use crate::handler::RequestHandlerOpts;
+    use hyper::{Body, Method, Request, Response, StatusCode};
+    use mime_guess::Mime;
+
+    #[test]
+    fn test_parse_mime_types() {
+        assert_eq!(
+            parse_mime_types(" text/* , Application/JSON,").unwrap(),
+            vec!["text/*", "application/json"]
+        );
+        assert!(parse_mime_types("").unwrap().is_empty());
+        assert!(parse_mime_types("text").is_err());
+        assert!(parse_mime_types("*/*").is_err());
+    }
+
+    #[test]
+    fn test_algorithm_levels_validate() {
+        let levels = AlgorithmLevels {
+            gzip: Some(9),
+            brotli: Some(0),
+            zstd: Some(22),
+        };
+        assert!(levels.validate().is_ok());
+        assert!(AlgorithmLevels::default().validate().is_ok());
+        assert!(
+            AlgorithmLevels {
+                brotli: Some(12),
+                ..Default::default()
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            AlgorithmLevels {
+                zstd: Some(0),
+                ..Default::default()
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_matches_mime_type() {
+        let patterns = vec!["image/*".to_owned(), "application/zip".to_owned()];
+        let mime = |s: &str| s.parse::<Mime>().unwrap();
+
+        assert!(matches_mime_type(&patterns, &mime("image/png")));
+        assert!(matches_mime_type(&patterns, &mime("application/zip")));
+        assert!(!matches_mime_type(&patterns, &mime("application/json")));
+        assert!(!matches_mime_type(
+            &patterns,
+            &mime("text/html; charset=utf-8")
+        ));
+        assert!(!matches_mime_type(&[], &mime("image/png")));
+    }
+
+    #[test]
+    fn test_compression_skipped() {
+        let opts = RequestHandlerOpts::default();
+        let response = |status: StatusCode| {
+            Response::builder()
+                .status(status)
+                .header("content-type", "text/html")
+                .body(Body::from("<h1>Static Web Server</h1>".repeat(64)))
+                .unwrap()
+        };
+        let request = |method: Method| {
+            Request::builder()
+                .method(method)
+                .header("accept-encoding", "gzip, br, zstd")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let resp = post_process(&opts, &request(Method::GET), response(StatusCode::OK)).unwrap();
+        assert!(resp.headers().contains_key("content-encoding"));
+
+        let resp = post_process(&opts, &request(Method::HEAD), response(StatusCode::OK)).unwrap();
+        assert!(!resp.headers().contains_key("content-encoding"));
+        assert_eq!(resp.headers()["vary"], "accept-encoding");
+
+        for status in [
+            StatusCode::NO_CONTENT,
+            StatusCode::NOT_MODIFIED,
+            StatusCode::PARTIAL_CONTENT,
+        ] {
+            let resp = post_process(&opts, &request(Method::GET), response(status)).unwrap();
+            assert!(!resp.headers().contains_key("content-encoding"), "{status}");
+            assert_eq!(resp.headers()["vary"], "accept-encoding");
+        }
+    }
+}
@@ -0,0 +1,224 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Adaptive compression governor which monitors the process CPU usage
+//! and lowers the dynamic compression level or disables dynamic compression under load.
+//!
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[cfg(target_os = "linux")]
+use std::{sync::Once, time::Duration};
+
+use crate::{Result, handler::RequestHandlerOpts};
+
+/// Interval between two samples of the process CPU usage.
+#[cfg(target_os = "linux")]
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Ratio of a threshold the load must drop below before leaving its mode,
+/// so the governor doesn't flap around a threshold.
+#[cfg(target_os = "linux")]
+const HYSTERESIS: f64 = 0.85;
+
+/// Current compression mode decided by the governor.
+static MODE: AtomicU8 = AtomicU8::new(Mode::Normal as u8);
+/// CPU usage percentage from which the fastest compression level is used.
+#[cfg(target_os = "linux")]
+static HIGH_LOAD: AtomicU8 = AtomicU8::new(70);
+/// CPU usage percentage from which the dynamic compression is disabled.
+#[cfg(target_os = "linux")]
+static MAX_LOAD: AtomicU8 = AtomicU8::new(90);
+/// The governor is started once and keeps running across configuration reloads.
+#[cfg(target_os = "linux")]
+static GOVERNOR: Once = Once::new();
+
+/// Compression mode decided by the governor according to the CPU load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Mode {
+    /// The configured compression levels are used.
+    Normal = 0,
+    /// The fastest compression level is used.
+    Reduced = 1,
+    /// The dynamic compression is disabled.
+    Disabled = 2,
+}
+
+impl Mode {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Reduced,
+            2 => Self::Disabled,
+            _ => Self::Normal,
+        }
+    }
+}
+
+/// Initializes the adaptive compression governor.
+pub(crate) fn init(
+    enabled: bool,
+    high_load: u8,
+    max_load: u8,
+    handler_opts: &mut RequestHandlerOpts,
+) -> Result {
+    handler_opts.compression_adaptive = enabled;
+    if !enabled {
+        tracing::info!("adaptive compression: enabled=false");
+        return Ok(());
+    }
+
+    if !(1..=100).contains(&high_load) || !(1..=100).contains(&max_load) {
+        bail!("adaptive compression load thresholds should be between 1 and 100");
+    }
+    if high_load >= max_load {
+        bail!(
+            "adaptive compression high load ({high_load}) should be lower than the maximum load ({max_load})"
+        );
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    bail!("adaptive compression is only supported on Linux");
+
+    #[cfg(target_os = "linux")]
+    {
+        HIGH_LOAD.store(high_load, Ordering::Relaxed);
+        MAX_LOAD.store(max_load, Ordering::Relaxed);
+        GOVERNOR.call_once(start);
+
+        tracing::info!(
+            "adaptive compression: enabled=true, high load={high_load}%, max load={max_load}%"
+        );
+        Ok(())
+    }
+}
+
+/// Gets the current compression mode.
+pub(crate) fn mode() -> Mode {
+    Mode::from_u8(MODE.load(Ordering::Relaxed))
+}
+
+/// Starts the governor thread sampling the process CPU usage.
+#[cfg(target_os = "linux")]
+fn start() {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get()) as f64;
+    let spawned = std::thread::Builder::new()
+        .name("sws-compression-governor".into())
+        .spawn(move || {
+            let mut last = (std::time::Instant::now(), cpu_time());
+            loop {
+                std::thread::sleep(SAMPLE_INTERVAL);
+
+                let now = (std::time::Instant::now(), cpu_time());
+                let (Some(last_cpu), Some(now_cpu)) = (last.1, now.1) else {
+                    tracing::error!(
+                        "adaptive compression: unable to read the process CPU usage, governor stopped"
+                    );
+                    MODE.store(Mode::Normal as u8, Ordering::Relaxed);
+                    return;
+                };
+                let elapsed = now.0.duration_since(last.0).as_secs_f64();
+                let load = now_cpu.saturating_sub(last_cpu).as_secs_f64() / (elapsed * cores) * 100.0;
+                last = now;
+
+                let current = mode();
+                let next = next_mode(
+                    current,
+                    load,
+                    HIGH_LOAD.load(Ordering::Relaxed),
+                    MAX_LOAD.load(Ordering::Relaxed),
+                );
+                if next == current {
+                    continue;
+                }
+                MODE.store(next as u8, Ordering::Relaxed);
+                match next {
+                    Mode::Normal => tracing::info!(
+                        "adaptive compression: cpu load {load:.0}%, configured compression levels restored"
+                    ),
+                    Mode::Reduced => tracing::warn!(
+                        "adaptive compression: cpu load {load:.0}%, compression level lowered to fastest"
+                    ),
+                    Mode::Disabled => tracing::warn!(
+                        "adaptive compression: cpu load {load:.0}%, dynamic compression disabled"
+                    ),
+                }
+            }
+        });
+
+    if let Err(err) = spawned {
+        tracing::error!("adaptive compression: unable to start the governor: {err}");
+    }
+}
+
+/// Decides the next compression mode for the given CPU load percentage.
+#[cfg(target_os = "linux")]
+fn next_mode(current: Mode, load: f64, high_load: u8, max_load: u8) -> Mode {
+    let (high_load, max_load) = (f64::from(high_load), f64::from(max_load));
+    if load >= max_load || (current == Mode::Disabled && load >= max_load * HYSTERESIS) {
+        Mode::Disabled
+    } else if load >= high_load || (current != Mode::Normal && load >= high_load * HYSTERESIS) {
+        Mode::Reduced
+    } else {
+        Mode::Normal
+    }
+}
+
+/// Reads the CPU time (user and system) consumed by the process so far.
+#[cfg(target_os = "linux")]
+fn cpu_time() -> Option<Duration> {
+    parse_cpu_time(&std::fs::read_to_string("/proc/self/stat").ok()?)
+}
+
+/// Parses the CPU time of a `/proc/<pid>/stat` content.
+#[cfg(target_os = "linux")]
+fn parse_cpu_time(stat: &str) -> Option<Duration> {
+    // Clock ticks per second of the `/proc` interface (`USER_HZ`)
+    const USER_HZ: u64 = 100;
+
+    // The process name may contain spaces so fields are taken after it,
+    // being `utime` and `stime` the 14th and 15th fields
+    let (_, fields) = stat.rsplit_once(')')?;
+    let mut fields = fields.split_whitespace().skip(11);
+    let utime: u64 = fields.next()?.parse().ok()?;
+    let stime: u64 = fields.next()?.parse().ok()?;
+    Some(Duration::from_millis((utime + stime) * 1000 / USER_HZ))
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::{Mode, cpu_time, next_mode, parse_cpu_time};
+    use std::time::Duration;
+
+    #[test]
+    fn test_next_mode() {
+        assert_eq!(next_mode(Mode::Normal, 20.0, 70, 90), Mode::Normal);
+        assert_eq!(next_mode(Mode::Normal, 75.0, 70, 90), Mode::Reduced);
+        assert_eq!(next_mode(Mode::Normal, 95.0, 70, 90), Mode::Disabled);
+        assert_eq!(next_mode(Mode::Reduced, 90.0, 70, 90), Mode::Disabled);
+    }
+
+    #[test]
+    fn test_next_mode_hysteresis() {
+        // Staying in the current mode until the load drops clearly below its threshold
+        assert_eq!(next_mode(Mode::Reduced, 65.0, 70, 90), Mode::Reduced);
+        assert_eq!(next_mode(Mode::Reduced, 55.0, 70, 90), Mode::Normal);
+        assert_eq!(next_mode(Mode::Disabled, 80.0, 70, 90), Mode::Disabled);
+        assert_eq!(next_mode(Mode::Disabled, 75.0, 70, 90), Mode::Reduced);
+        assert_eq!(next_mode(Mode::Disabled, 10.0, 70, 90), Mode::Normal);
+        assert_eq!(next_mode(Mode::Normal, 85.0, 70, 90), Mode::Reduced);
+        // Low thresholds
+        assert_eq!(next_mode(Mode::Disabled, 0.0, 5, 30), Mode::Normal);
+        assert_eq!(next_mode(Mode::Reduced, 1.0, 5, 30), Mode::Normal);
+    }
+
+    #[test]
+    fn test_parse_cpu_time() {
+        let stat = "1234 (static web) S 1 1234 1234 0 -1 4194560 2101 0 0 0 250 130 0 0 20 0 9 0";
+        assert_eq!(parse_cpu_time(stat), Some(Duration::from_millis(3800)));
+        assert_eq!(parse_cpu_time("1234 (sws) S 1"), None);
+        assert!(cpu_time().is_some());
+    }
+}
@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Pool of the zstd compression contexts used to compress responses on the fly.
+//!
+//! Creating a zstd context allocates its buffers and match tables, so contexts are reset
+//! and kept for the next responses once a body is compressed instead of per response.
+//!
+
+use async_compression::Level;
+use bytes::Bytes;
+use futures_util::Stream;
+use std::io;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll, ready};
+use zstd::stream::raw::{CParameter, Encoder, InBuffer, Operation, OutBuffer};
+
+use crate::compression::COMPRESSION_BUFFER_SIZE;
+
+/// Maximum number of idle zstd contexts kept in the pool.
+const MAX_IDLE_CONTEXTS: usize = 64;
+
+/// Idle zstd contexts ready to be reused.
+static ZSTD_CONTEXTS: Mutex<Vec<Encoder<'static>>> = Mutex::new(Vec::new());
+
+/// Takes an idle zstd context from the pool or creates a new one if there is none.
+fn acquire(level: i32) -> io::Result<Encoder<'static>> {
+    let idle = ZSTD_CONTEXTS
+        .lock()
+        .ok()
+        .and_then(|mut contexts| contexts.pop());
+    match idle {
+        Some(mut encoder) => {
+            encoder.set_parameter(CParameter::CompressionLevel(level))?;
+            Ok(encoder)
+        }
+        None => Encoder::new(level),
+    }
+}
+
+/// Resets a zstd context and returns it to the pool unless the pool is full.
+fn release(mut encoder: Encoder<'static>) {
+    if encoder.reinit().is_err() {
+        return;
+    }
+    if let Ok(mut contexts) = ZSTD_CONTEXTS.lock() {
+        if contexts.len() < MAX_IDLE_CONTEXTS {
+            contexts.push(encoder);
+        }
+    }
+}
+
+/// A body stream compressed using zstd by a pooled context.
+pub(crate) struct ZstdStream<S> {
+    body: S,
+    /// The context, taken once the body is compressed or an error happens.
+    encoder: Option<Encoder<'static>>,
+    /// Whether the body was read completely, so the frame is being finished.
+    finishing: bool,
+    /// Error creating the context, returned as the first item of the stream.
+    error: Option<io::Error>,
+}
+
+/// Compresses a body stream using zstd with a context of the pool.
+pub(crate) fn zstd<S>(body: S, level: Level) -> ZstdStream<S> {
+    let level = async_compression::codecs::zstd::params::CParameter::quality(level);
+    let (encoder, error) = match acquire(level) {
+        Ok(encoder) => (Some(encoder), None),
+        Err(err) => (None, Some(err)),
+    };
+    ZstdStream {
+        body,
+        encoder,
+        finishing: false,
+        error,
+    }
+}
+
+impl<S> Stream for ZstdStream<S>
+where
+    S: Stream<Item = io::Result<Bytes>> + Unpin,
+{
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        if let Some(err) = this.error.take() {
+            return Poll::Ready(Some(Err(err)));
+        }
+        loop {
+            let Some(encoder) = this.encoder.as_mut() else {
+                return Poll::Ready(None);
+            };
+
+            let result = if this.finishing {
+                let mut output = Vec::with_capacity(COMPRESSION_BUFFER_SIZE);
+                let finished = encoder
+                    .finish(&mut OutBuffer::around(&mut output), false)
+                    .map(|remaining| remaining == 0);
+                if let Ok(true) = finished {
+                    // The frame is complete, so the context can be reused
+                    if let Some(encoder) = this.encoder.take() {
+                        release(encoder);
+                    }
+                }
+                finished.map(|_| output)
+            } else {
+                match ready!(Pin::new(&mut this.body).poll_next(cx)) {
+                    Some(Ok(chunk)) => compress(encoder, &chunk),
+                    Some(Err(err)) => Err(err),
+                    None => {
+                        this.finishing = true;
+                        continue;
+                    }
+                }
+            };
+
+            match result {
+                Ok(output) if output.is_empty() => continue,
+                Ok(output) => return Poll::Ready(Some(Ok(Bytes::from(output)))),
+                Err(err) => {
+                    // NOTE: a context in an unknown state is not reused
+                    this.encoder = None;
+                    return Poll::Ready(Some(Err(err)));
+                }
+            }
+        }
+    }
+}
+
+impl<S> Drop for ZstdStream<S> {
+    fn drop(&mut self) {
+        // The body may not be compressed completely (E.g. the client disconnected)
+        if let Some(encoder) = self.encoder.take() {
+            release(encoder);
+        }
+    }
+}
+
+/// Compresses a chunk of the body, returning the compressed data available so far.
+fn compress(encoder: &mut Encoder<'static>, chunk: &[u8]) -> io::Result<Vec<u8>> {
+    let mut input = InBuffer::around(chunk);
+    let mut output = Vec::new();
+    while input.pos() < chunk.len() {
+        if output.len() == output.capacity() {
+            output.reserve(COMPRESSION_BUFFER_SIZE);
+        }
+        let pos = output.len();
+        encoder.run(&mut input, &mut OutBuffer::around_pos(&mut output, pos))?;
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ZSTD_CONTEXTS, zstd};
+    use async_compression::Level;
+    use bytes::Bytes;
+    use futures_util::{StreamExt, stream};
+    use std::io;
+
+    async fn compress(chunks: Vec<Bytes>, level: Level) -> Vec<u8> {
+        let body = stream::iter(chunks.into_iter().map(Ok::<_, io::Error>));
+        let mut compressed = Vec::new();
+        let mut stream = zstd(body, level);
+        while let Some(chunk) = stream.next().await {
+            compressed.extend_from_slice(&chunk.unwrap());
+        }
+        compressed
+    }
+
+    #[tokio::test]
+    async fn test_zstd_contexts_reused() {
+        let text = "Static Web Server ".repeat(1024);
+        let chunks = vec![
+            Bytes::from(text.clone()),
+            Bytes::new(),
+            Bytes::from(text.clone()),
+        ];
+
+        for level in [Level::Fastest, Level::Precise(19), Level::Default] {
+            let compressed = compress(chunks.clone(), level).await;
+            assert!(compressed.len() < text.len());
+            let decompressed = ::zstd::stream::decode_all(compressed.as_slice()).unwrap();
+            assert_eq!(decompressed, text.repeat(2).as_bytes());
+        }
+        assert!(!ZSTD_CONTEXTS.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_zstd_body_error() {
+        let body = stream::iter([
+            Ok(Bytes::from_static(b"Static Web Server")),
+            Err(io::Error::other("body error")),
+        ]);
+        let mut stream = zstd(body, Level::Default);
+        while let Some(chunk) = stream.next().await {
+            if chunk.is_err() {
+                assert!(stream.next().await.is_none());
+                return;
+            }
+        }
+        panic!("the body error was not returned");
+    }
+}
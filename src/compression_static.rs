@@ -6,17 +6,30 @@
 //! Compression static module to serve compressed files directly from the file system.
 //!
 
-use headers::{HeaderMap, HeaderValue};
+use headers::{HeaderMap, HeaderMapExt, HeaderValue};
 use hyper::{Body, Request, Response};
 use std::ffi::OsStr;
 use std::fs::Metadata;
 use std::path::{Path, PathBuf};
 
-use crate::Error;
-use crate::compression;
 use crate::fs::meta::try_metadata;
 use crate::handler::RequestHandlerOpts;
-use crate::headers_ext::ContentCoding;
+use crate::headers_ext::{AcceptEncoding, ContentCoding};
+use crate::{Error, Result};
+
+/// List of pre-compressed file variants that can be served given enabled features.
+const AVAILABLE_VARIANTS: &[ContentCoding] = &[
+    #[cfg(any(feature = "compression", feature = "compression-brotli"))]
+    ContentCoding::BROTLI,
+    #[cfg(any(feature = "compression", feature = "compression-zstd"))]
+    ContentCoding::ZSTD,
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-deflate"
+    ))]
+    ContentCoding::GZIP,
+];
 
 /// It defines the pre-compressed file variant metadata of a particular file path.
 pub struct CompressedFileVariant {
@@ -29,9 +42,43 @@ pub struct CompressedFileVariant {
 }
 
 /// Initializes static compression.
-pub fn init(enabled: bool, handler_opts: &mut RequestHandlerOpts) {
+pub fn init(enabled: bool, order: &str, handler_opts: &mut RequestHandlerOpts) -> Result {
     handler_opts.compression_static = enabled;
-    tracing::info!("compression static: enabled={enabled}");
+    handler_opts.compression_static_order = parse_order(order)?;
+    tracing::info!(
+        "compression static: enabled={enabled}, order={}",
+        handler_opts
+            .compression_static_order
+            .iter()
+            .map(ContentCoding::as_str)
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+    Ok(())
+}
+
+/// Parses a comma-separated list of pre-compressed file variants in priority order.
+/// Variants not supported by the enabled features are ignored.
+pub(crate) fn parse_order(order: &str) -> Result<Vec<ContentCoding>> {
+    let mut variants = Vec::new();
+    for name in order.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let variant = match name.to_lowercase().as_str() {
+            "br" | "brotli" => ContentCoding::BROTLI,
+            "zst" | "zstd" => ContentCoding::ZSTD,
+            "gz" | "gzip" => ContentCoding::GZIP,
+            _ => bail!("invalid pre-compressed file variant: {name}"),
+        };
+        if !AVAILABLE_VARIANTS.contains(&variant) {
+            tracing::warn!(
+                "pre-compressed file variant \"{name}\" is not supported by the enabled features, ignoring"
+            );
+            continue;
+        }
+        if !variants.contains(&variant) {
+            variants.push(variant);
+        }
+    }
+    Ok(variants)
 }
 
 /// Post-processing to add Vary header if necessary.
@@ -59,31 +106,67 @@ pub(crate) fn post_process<T>(
     Ok(resp)
 }
 
-/// Search for the pre-compressed variant of the given file path.
+/// Returns the pre-compressed file variants accepted by the client along with
+/// the content encoding to use for each one, sorted by preference.
+///
+/// Variants are sorted by their `Accept-Encoding` quality values and then by the given order
+/// when their quality values are equal. Variants with a zero quality value are excluded.
+fn sorted_variants(
+    headers: &HeaderMap<HeaderValue>,
+    order: &[ContentCoding],
+) -> Vec<(ContentCoding, ContentCoding)> {
+    let accept_encoding = match headers.typed_get::<AcceptEncoding>() {
+        Some(v) => v,
+        None => return vec![],
+    };
+    tracing::trace!("request with accept-encoding header: {:?}", accept_encoding);
+
+    let mut variants: Vec<_> = order
+        .iter()
+        .filter_map(|&variant| {
+            // A gzip file variant can be served for both `gzip` and `deflate` encodings
+            // but `gzip` is preferred when acceptable
+            // https://zlib.net/zlib_faq.html#faq39
+            let encodings: &[ContentCoding] = match variant {
+                ContentCoding::GZIP => &[ContentCoding::GZIP, ContentCoding::DEFLATE],
+                _ => &[variant],
+            };
+            encodings
+                .iter()
+                .filter_map(|&encoding| Some((encoding, accept_encoding.quality(encoding)?)))
+                .find(|(_, quality)| *quality > 0)
+                .map(|(encoding, quality)| (variant, encoding, quality))
+        })
+        .collect();
+
+    // Stable sort so the configured order is preserved for equal quality values
+    variants.sort_by_key(|v| std::cmp::Reverse(v.2));
+    variants
+        .into_iter()
+        .map(|(variant, encoding, _)| (variant, encoding))
+        .collect()
+}
+
+/// Search for the pre-compressed variant of the given file path
+/// looking up the given variants in priority order.
 pub fn precompressed_variant(
     file_path: &Path,
     headers: &HeaderMap<HeaderValue>,
+    order: &[ContentCoding],
 ) -> Option<CompressedFileVariant> {
     tracing::trace!(
         "preparing pre-compressed file variant path of {}",
         file_path.display()
     );
 
-    for encoding in compression::get_encodings(headers) {
-        // Determine preferred-encoding extension if available
-        let comp_ext = match encoding {
+    for (variant, encoding) in sorted_variants(headers, order) {
+        // Determine the file extension of the variant
+        let comp_ext = match variant {
             // https://zlib.net/zlib_faq.html#faq39
-            #[cfg(any(
-                feature = "compression",
-                feature = "compression-gzip",
-                feature = "compression-deflate"
-            ))]
-            ContentCoding::GZIP | ContentCoding::DEFLATE => "gz",
+            ContentCoding::GZIP => "gz",
             // https://peazip.github.io/brotli-compressed-file-format.html
-            #[cfg(any(feature = "compression", feature = "compression-brotli"))]
             ContentCoding::BROTLI => "br",
             // https://datatracker.ietf.org/doc/html/rfc8878
-            #[cfg(any(feature = "compression", feature = "compression-zstd"))]
             ContentCoding::ZSTD => "zst",
             _ => {
                 tracing::trace!(
@@ -131,3 +214,78 @@ pub fn precompressed_variant(
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "compression")]
+    use super::parse_order;
+    use super::sorted_variants;
+    use crate::headers_ext::ContentCoding;
+    use headers::{HeaderMap, HeaderValue};
+
+    fn headers(accept_encoding: &'static str) -> HeaderMap<HeaderValue> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            hyper::header::ACCEPT_ENCODING,
+            HeaderValue::from_static(accept_encoding),
+        );
+        headers
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_parse_order() {
+        assert_eq!(
+            parse_order("zstd, br,gz,zst").unwrap(),
+            vec![
+                ContentCoding::ZSTD,
+                ContentCoding::BROTLI,
+                ContentCoding::GZIP
+            ]
+        );
+        assert!(parse_order("").unwrap().is_empty());
+        assert!(parse_order("br,deflate").is_err());
+    }
+
+    #[test]
+    fn test_sorted_variants() {
+        let order = &[
+            ContentCoding::ZSTD,
+            ContentCoding::BROTLI,
+            ContentCoding::GZIP,
+        ];
+
+        // Equal quality values follow the configured order
+        assert_eq!(
+            sorted_variants(&headers("gzip, br, zstd"), order),
+            vec![
+                (ContentCoding::ZSTD, ContentCoding::ZSTD),
+                (ContentCoding::BROTLI, ContentCoding::BROTLI),
+                (ContentCoding::GZIP, ContentCoding::GZIP),
+            ]
+        );
+        // Higher quality values come first and zero quality values are excluded
+        assert_eq!(
+            sorted_variants(&headers("gzip;q=0.5, br, zstd;q=0"), order),
+            vec![
+                (ContentCoding::BROTLI, ContentCoding::BROTLI),
+                (ContentCoding::GZIP, ContentCoding::GZIP),
+            ]
+        );
+        // A gzip variant is also used for the `deflate` encoding
+        assert_eq!(
+            sorted_variants(&headers("deflate"), order),
+            vec![(ContentCoding::GZIP, ContentCoding::DEFLATE)]
+        );
+        // The wildcard applies to the encodings not listed
+        assert_eq!(
+            sorted_variants(&headers("gzip;q=0.5, *;q=0.8"), order),
+            vec![
+                (ContentCoding::ZSTD, ContentCoding::ZSTD),
+                (ContentCoding::BROTLI, ContentCoding::BROTLI),
+                (ContentCoding::GZIP, ContentCoding::GZIP),
+            ]
+        );
+        assert!(sorted_variants(&HeaderMap::new(), order).is_empty());
+    }
+}
@@ -0,0 +1,316 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module to limit the number of concurrent connections, in total and per client IP address,
+//! so connection floods can't exhaust the resources of the server.
+//!
+//! The connections exceeding a limit are rejected once accepted, either with a `503` response
+//! on the plain HTTP/1 listeners or by closing them right away (E.g. on the TLS listeners).
+//!
+
+use hyper::server::accept::Accept;
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf};
+
+use crate::transport::Transport;
+use crate::zero_copy::ZeroCopySender;
+
+#[cfg(feature = "metrics")]
+use crate::metrics;
+
+/// Response sent to the connections rejected on the plain HTTP/1 listeners.
+const REJECTED_RESPONSE: &[u8] =
+    b"HTTP/1.1 503 Service Unavailable\r\nconnection: close\r\ncontent-length: 0\r\n\r\n";
+
+/// Maximum time to send the response of a rejected connection.
+const REJECTED_RESPONSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Limit exceeded by a rejected connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Exceeded {
+    /// Maximum number of connections.
+    Total,
+    /// Maximum number of connections per client IP address.
+    PerIp,
+}
+
+impl Exceeded {
+    /// Name of the option of the exceeded limit.
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Total => "max-connections",
+            Self::PerIp => "max-connections-per-ip",
+        }
+    }
+}
+
+/// Concurrent connection limits shared by all the listeners.
+#[derive(Clone, Default)]
+pub struct ConnectionLimits {
+    /// Open connections if any limit is set.
+    counts: Option<Arc<Counts>>,
+}
+
+struct Counts {
+    /// Maximum number of connections. Disabled if zero.
+    max: usize,
+    /// Maximum number of connections per client IP address. Disabled if zero.
+    max_per_ip: usize,
+    open: Mutex<Open>,
+}
+
+/// Open connections in total and per client IP address.
+#[derive(Default)]
+struct Open {
+    total: usize,
+    per_ip: HashMap<IpAddr, usize>,
+}
+
+impl ConnectionLimits {
+    /// Creates the limits of `max` connections in total and `max_per_ip` per client IP address,
+    /// both disabled if zero.
+    pub fn new(max: usize, max_per_ip: usize) -> Self {
+        let counts = (max > 0 || max_per_ip > 0).then(|| {
+            Arc::new(Counts {
+                max,
+                max_per_ip,
+                open: Mutex::new(Open::default()),
+            })
+        });
+        Self { counts }
+    }
+
+    /// Counts a new connection from the given address, failing if it exceeds a limit.
+    /// The connection is counted until the returned permit gets dropped.
+    pub(crate) fn acquire(&self, addr: Option<SocketAddr>) -> Result<Permit, Exceeded> {
+        let Some(counts) = &self.counts else {
+            return Ok(Permit(None));
+        };
+        // The per-IP limit doesn't apply to the connections without address (E.g. Unix sockets)
+        let ip = addr
+            .filter(|_| counts.max_per_ip > 0)
+            .map(|addr| addr.ip().to_canonical());
+
+        let mut open = counts.open.lock().unwrap_or_else(|err| err.into_inner());
+        if counts.max > 0 && open.total >= counts.max {
+            return Err(Exceeded::Total);
+        }
+        if let Some(ip) = ip {
+            let count = open.per_ip.entry(ip).or_default();
+            if *count >= counts.max_per_ip {
+                return Err(Exceeded::PerIp);
+            }
+            *count += 1;
+        }
+        open.total += 1;
+
+        Ok(Permit(Some((counts.clone(), ip))))
+    }
+}
+
+/// It keeps a connection counted until it gets dropped.
+pub(crate) struct Permit(Option<(Arc<Counts>, Option<IpAddr>)>);
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let Some((counts, ip)) = self.0.take() else {
+            return;
+        };
+        let mut open = counts.open.lock().unwrap_or_else(|err| err.into_inner());
+        open.total -= 1;
+        if let Some(ip) = ip {
+            if let Some(count) = open.per_ip.get_mut(&ip) {
+                *count -= 1;
+                if *count == 0 {
+                    open.per_ip.remove(&ip);
+                }
+            }
+        }
+    }
+}
+
+/// Type to intercept incoming connections and reject the ones exceeding the connection limits.
+pub struct ConnectionLimitAcceptor<A> {
+    incoming: A,
+    limits: ConnectionLimits,
+    /// Whether the rejected connections get a `503` response before being closed.
+    respond: bool,
+}
+
+impl<A> ConnectionLimitAcceptor<A> {
+    /// Creates a new connection limits interceptor.
+    pub fn new(incoming: A, limits: ConnectionLimits, respond: bool) -> Self {
+        Self {
+            incoming,
+            limits,
+            respond,
+        }
+    }
+}
+
+impl<A> Accept for ConnectionLimitAcceptor<A>
+where
+    A: Accept + Unpin,
+    A::Conn: Transport + Unpin + Send + 'static,
+{
+    type Conn = LimitedStream<A::Conn>;
+    type Error = A::Error;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        let pin = self.get_mut();
+        loop {
+            let conn = match Pin::new(&mut pin.incoming).poll_accept(cx) {
+                Poll::Ready(Some(Ok(conn))) => conn,
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+            let addr = conn.remote_addr();
+            match pin.limits.acquire(addr) {
+                Ok(permit) => {
+                    return Poll::Ready(Some(Ok(LimitedStream {
+                        inner: conn,
+                        _permit: permit,
+                    })));
+                }
+                Err(exceeded) => reject(conn, addr, exceeded, pin.respond),
+            }
+        }
+    }
+}
+
+/// Rejects a connection exceeding a limit, sending it a `503` response if requested.
+fn reject<T>(mut conn: T, addr: Option<SocketAddr>, exceeded: Exceeded, respond: bool)
+where
+    T: AsyncWrite + Unpin + Send + 'static,
+{
+    tracing::debug!(
+        "connection from {:?} rejected: {} exceeded",
+        addr,
+        exceeded.as_str()
+    );
+    #[cfg(feature = "metrics")]
+    metrics::connection_rejected(exceeded.as_str());
+
+    if respond {
+        tokio::spawn(async move {
+            let _ = tokio::time::timeout(REJECTED_RESPONSE_TIMEOUT, async {
+                conn.write_all(REJECTED_RESPONSE).await?;
+                conn.shutdown().await
+            })
+            .await;
+        });
+    }
+}
+
+/// Connection stream counted by the connection limits until it gets dropped.
+pub struct LimitedStream<T> {
+    inner: T,
+    _permit: Permit,
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for LimitedStream<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for LimitedStream<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+impl<T: Transport + Unpin> Transport for LimitedStream<T> {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        self.inner.remote_addr()
+    }
+
+    fn zero_copy(&self) -> Option<ZeroCopySender> {
+        self.inner.zero_copy()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConnectionLimits, Exceeded};
+    use std::net::SocketAddr;
+
+    fn addr(addr: &str) -> Option<SocketAddr> {
+        Some(addr.parse().unwrap())
+    }
+
+    #[test]
+    fn test_max_connections() {
+        let limits = ConnectionLimits::new(2, 0);
+        let first = limits.acquire(addr("10.0.0.1:1000")).unwrap();
+        let _second = limits.acquire(None).unwrap();
+        assert_eq!(
+            limits.acquire(addr("10.0.0.2:1000")).err(),
+            Some(Exceeded::Total)
+        );
+
+        drop(first);
+        assert!(limits.acquire(addr("10.0.0.2:1000")).is_ok());
+    }
+
+    #[test]
+    fn test_max_connections_per_ip() {
+        let limits = ConnectionLimits::new(0, 2);
+        let first = limits.acquire(addr("10.0.0.1:1000")).unwrap();
+        let _second = limits.acquire(addr("10.0.0.1:1001")).unwrap();
+        assert_eq!(
+            limits.acquire(addr("10.0.0.1:1002")).err(),
+            Some(Exceeded::PerIp)
+        );
+        // The IPv4-mapped IPv6 addresses count as their IPv4 address
+        assert_eq!(
+            limits.acquire(addr("[::ffff:10.0.0.1]:1003")).err(),
+            Some(Exceeded::PerIp)
+        );
+        // Other addresses and the connections without address are not limited
+        let _other = limits.acquire(addr("10.0.0.2:1000")).unwrap();
+        let _unix = limits.acquire(None).unwrap();
+        let _unix = limits.acquire(None).unwrap();
+        let _unix = limits.acquire(None).unwrap();
+
+        drop(first);
+        assert!(limits.acquire(addr("10.0.0.1:1002")).is_ok());
+    }
+
+    #[test]
+    fn test_limits_disabled() {
+        let limits = ConnectionLimits::new(0, 0);
+        let permits: Vec<_> = (0..100)
+            .map(|_| limits.acquire(addr("10.0.0.1:1000")).unwrap())
+            .collect();
+        assert_eq!(permits.len(), 100);
+    }
+}
@@ -0,0 +1,141 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that protects the responses against MIME type sniffing
+//! via the `X-Content-Type-Options: nosniff` header.
+//!
+//! The responses generated by the server (directory listings, error and fallback pages)
+//! always get the header. The file responses get it or not according to the
+//! `content-type-options` policies of the `advanced` options matching the request path,
+//! otherwise the security headers option applies.
+//!
+
+use http::header::X_CONTENT_TYPE_OPTIONS;
+use hyper::{Body, Request, Response, header::HeaderValue};
+
+use crate::{Error, Result, handler::RequestHandlerOpts};
+
+/// `X-Content-Type-Options` header value disabling the MIME type sniffing.
+const NOSNIFF: &str = "nosniff";
+
+/// Marks a response generated by the server instead of serving a file.
+#[derive(Clone, Copy, Debug)]
+struct Generated;
+
+pub(crate) fn init(handler_opts: &RequestHandlerOpts) {
+    let policies = handler_opts
+        .advanced_opts
+        .as_ref()
+        .and_then(|advanced| advanced.content_type_options.as_ref())
+        .map_or(0, Vec::len);
+    tracing::info!("content type options: policies={policies}");
+}
+
+/// Appends the `X-Content-Type-Options: nosniff` header to a response generated by the server.
+pub fn append_generated(resp: &mut Response<Body>) {
+    resp.headers_mut()
+        .insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static(NOSNIFF));
+    resp.extensions_mut().insert(Generated);
+}
+
+/// Appends or removes the `X-Content-Type-Options` header of a file response
+/// according to the policy matching the request path if any.
+pub(crate) fn post_process<T>(
+    opts: &RequestHandlerOpts,
+    req: &Request<T>,
+    mut resp: Response<Body>,
+) -> Result<Response<Body>, Error> {
+    if resp.extensions().get::<Generated>().is_some() {
+        return Ok(resp);
+    }
+
+    let uri_path = req.uri().path();
+    let policy = opts
+        .advanced_opts
+        .as_ref()
+        .and_then(|advanced| advanced.content_type_options.as_deref())
+        .and_then(|policies| policies.iter().find(|p| p.source.is_match(uri_path)));
+
+    match policy {
+        Some(policy) if policy.nosniff => {
+            resp.headers_mut()
+                .insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static(NOSNIFF));
+        }
+        Some(_) => {
+            resp.headers_mut().remove(X_CONTENT_TYPE_OPTIONS);
+        }
+        None => {}
+    }
+    Ok(resp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{append_generated, post_process};
+    use crate::{
+        handler::RequestHandlerOpts,
+        settings::{Advanced, ContentTypeOptions},
+    };
+    use globset::GlobBuilder;
+    use hyper::{Body, Request, Response};
+
+    fn make_opts(policies: &[(&str, bool)]) -> RequestHandlerOpts {
+        let policies = policies
+            .iter()
+            .map(|(source, nosniff)| ContentTypeOptions {
+                source: GlobBuilder::new(source)
+                    .literal_separator(true)
+                    .build()
+                    .unwrap()
+                    .compile_matcher(),
+                nosniff: *nosniff,
+            })
+            .collect();
+        RequestHandlerOpts {
+            advanced_opts: Some(Advanced {
+                content_type_options: Some(policies),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn make_request(uri: &str) -> Request<Body> {
+        Request::builder().uri(uri).body(Body::empty()).unwrap()
+    }
+
+    fn nosniff(resp: &Response<Body>) -> bool {
+        resp.headers()
+            .get("x-content-type-options")
+            .is_some_and(|v| v == "nosniff")
+    }
+
+    #[test]
+    fn test_file_policies() {
+        let opts = make_opts(&[("/legacy/**", false), ("/**/*.{js,css}", true)]);
+
+        let resp = post_process(&opts, &make_request("/assets/app.js"), Response::default());
+        assert!(nosniff(&resp.unwrap()));
+
+        let resp = post_process(&opts, &make_request("/index.html"), Response::default());
+        assert!(!nosniff(&resp.unwrap()));
+
+        // The first matching policy wins, even over the security headers
+        let mut resp = Response::default();
+        resp.headers_mut()
+            .insert("x-content-type-options", "nosniff".parse().unwrap());
+        let resp = post_process(&opts, &make_request("/legacy/app.js"), resp).unwrap();
+        assert!(resp.headers().get("x-content-type-options").is_none());
+    }
+
+    #[test]
+    fn test_generated_responses() {
+        let opts = make_opts(&[("/**", false)]);
+        let mut resp = Response::default();
+        append_generated(&mut resp);
+        let resp = post_process(&opts, &make_request("/missing.js"), resp).unwrap();
+        assert!(nosniff(&resp));
+    }
+}
@@ -0,0 +1,713 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module providing an optional control API on a separate listener
+//! to change the server state at runtime.
+//!
+
+use headers::{ContentType, HeaderMapExt};
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE, HOST, HeaderValue, ORIGIN, WWW_AUTHENTICATE};
+use hyper::server::{Server as HyperServer, accept::Accept, conn::AddrIncoming};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde_json::json;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::audit_log::Trigger;
+use crate::{Context, Result, reload::RuntimeConfig};
+
+#[cfg(unix)]
+use crate::unix_socket;
+
+const SETTINGS_PATH: &str = "/settings";
+const MAINTENANCE_ON_PATH: &str = "/maintenance/on";
+const MAINTENANCE_OFF_PATH: &str = "/maintenance/off";
+const RELOAD_PATH: &str = "/reload";
+const CACHE_FLUSH_PATH: &str = "/cache/flush";
+
+/// Content types a browser can send cross-origin without a preflight request.
+const FORM_CONTENT_TYPES: [&str; 3] = [
+    "application/x-www-form-urlencoded",
+    "multipart/form-data",
+    "text/plain",
+];
+
+/// Control API listener.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Listen {
+    /// Loopback TCP socket address.
+    Tcp(SocketAddr),
+    /// Unix domain socket path.
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl Listen {
+    /// Parses a loopback address or a Unix domain socket path prefixed by `unix:`.
+    pub fn parse(value: &str) -> Result<Self> {
+        if let Some(path) = value.strip_prefix("unix:") {
+            #[cfg(unix)]
+            {
+                if path.is_empty() {
+                    bail!("control api unix domain socket path is empty");
+                }
+                return Ok(Self::Unix(PathBuf::from(path)));
+            }
+            #[cfg(not(unix))]
+            bail!(
+                "control api unix domain socket is not supported on this platform: {}",
+                path
+            );
+        }
+
+        let addr = value
+            .parse::<SocketAddr>()
+            .with_context(|| format!("invalid control api listener address: {value}"))?;
+        if !addr.ip().is_loopback() {
+            bail!("control api listener must be bound to a loopback address: {addr}");
+        }
+        Ok(Self::Tcp(addr))
+    }
+}
+
+/// Access control of the control API requests, so the browsers can't be used to reach it
+/// via a malicious page (E.g. cross-site requests or DNS rebinding).
+#[derive(Debug, Default)]
+struct Access {
+    /// Accepted `Host` header values of a TCP listener, none for a Unix domain socket.
+    hosts: Vec<String>,
+    /// Bearer token required by the requests changing the server state if any.
+    token: Option<String>,
+}
+
+impl Access {
+    fn new(addr: Option<SocketAddr>, token: &str) -> Self {
+        let hosts = match addr {
+            Some(addr) => vec![addr.to_string(), format!("localhost:{}", addr.port())],
+            None => Vec::new(),
+        };
+        let token = Some(token.trim()).filter(|token| !token.is_empty());
+        Self {
+            hosts,
+            token: token.map(str::to_owned),
+        }
+    }
+
+    /// Checks the request origin, returning the error response if it's rejected.
+    fn check_origin<T>(&self, req: &Request<T>) -> Option<Response<Body>> {
+        let headers = req.headers();
+        if headers.contains_key(ORIGIN) {
+            tracing::warn!("control api request rejected: `Origin` header present");
+            return Some(forbidden());
+        }
+        if self.hosts.is_empty() {
+            return None;
+        }
+        let host = headers.get(HOST).and_then(|host| host.to_str().ok());
+        let allowed = host.is_some_and(|host| {
+            self.hosts
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(host))
+        });
+        if !allowed {
+            tracing::warn!("control api request rejected: unexpected host {:?}", host);
+            return Some(forbidden());
+        }
+        None
+    }
+
+    /// Checks the authorization of a request changing the server state,
+    /// returning the error response if it's rejected.
+    fn check_mutation<T>(&self, req: &Request<T>) -> Option<Response<Body>> {
+        let headers = req.headers();
+        if let Some(token) = &self.token {
+            if is_valid_token(headers.get(AUTHORIZATION), token) {
+                return None;
+            }
+            tracing::warn!("control api request rejected: invalid bearer token");
+            let mut resp =
+                json_response(StatusCode::UNAUTHORIZED, json!({ "error": "unauthorized" }));
+            resp.headers_mut()
+                .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            return Some(resp);
+        }
+
+        let content_type = headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim().to_ascii_lowercase())
+            .filter(|value| !value.is_empty());
+        match content_type {
+            Some(value) if !FORM_CONTENT_TYPES.contains(&value.as_str()) => None,
+            _ => {
+                tracing::warn!("control api request rejected: missing or form content type");
+                Some(json_response(
+                    StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                    json!({ "error": "a non-form content type (E.g. application/json) is required" }),
+                ))
+            }
+        }
+    }
+}
+
+/// Checks an `Authorization: Bearer <token>` header in constant time.
+fn is_valid_token(header: Option<&HeaderValue>, token: &str) -> bool {
+    let Some(value) = header.and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    let Some(value) = value.strip_prefix("Bearer ") else {
+        return false;
+    };
+    value.len() == token.len()
+        && value
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn forbidden() -> Response<Body> {
+    json_response(StatusCode::FORBIDDEN, json!({ "error": "forbidden" }))
+}
+
+/// Binds the control API listener and serves it in the background.
+pub(crate) fn spawn(listen: &str, token: &str, config: Arc<RuntimeConfig>) -> Result {
+    match Listen::parse(listen)? {
+        Listen::Tcp(addr) => {
+            let listener = std::net::TcpListener::bind(addr)
+                .with_context(|| format!("failed to bind the control api to {addr} address"))?;
+            // The actual port is used to check the `Host` header (E.g. if zero was given)
+            let addr = listener
+                .local_addr()
+                .with_context(|| "failed to get the control api local address")?;
+            let access = Access::new(Some(addr), token);
+            listener
+                .set_nonblocking(true)
+                .with_context(|| "failed to set TCP non-blocking mode")?;
+            let listener = tokio::net::TcpListener::from_std(listener)
+                .with_context(|| "failed to create tokio::net::TcpListener")?;
+            let incoming = AddrIncoming::from_listener(listener).with_context(
+                || "failed to create an AddrIncoming from the current tokio::net::TcpListener",
+            )?;
+
+            tracing::info!("control api is listening on http://{}", addr);
+            tokio::spawn(serve(incoming, config, access));
+        }
+        #[cfg(unix)]
+        Listen::Unix(path) => {
+            let (listener, socket) = unix_socket::bind(&path)
+                .with_context(|| format!("failed to bind the control api to {}", path.display()))?;
+            // Only the server user is allowed to use the control API
+            unix_socket::set_permissions(&path, Some(0o600), None, None)
+                .with_context(|| "failed to set the control api socket permissions")?;
+            let incoming = unix_socket::incoming(listener);
+            let access = Access::new(None, token);
+
+            tracing::info!("control api is listening on unix:{}", path.display());
+            tokio::spawn(async move {
+                let _socket = socket;
+                serve(incoming, config, access).await;
+            });
+        }
+    }
+    Ok(())
+}
+
+async fn serve<I>(incoming: I, config: Arc<RuntimeConfig>, access: Access)
+where
+    I: Accept<Error = std::io::Error>,
+    I::Conn: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let access = Arc::new(access);
+    let server = HyperServer::builder(incoming).serve(make_service_fn(move |_: &I::Conn| {
+        let config = config.clone();
+        let access = access.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let config = config.clone();
+                let access = access.clone();
+                async move { Ok::<_, Infallible>(handle(&config, &access, &req).await) }
+            }))
+        }
+    }));
+    if let Err(err) = server.await {
+        tracing::error!("control api server failed: {:?}", err);
+    }
+}
+
+/// Handles the control API requests.
+async fn handle<T>(
+    config: &Arc<RuntimeConfig>,
+    access: &Access,
+    req: &Request<T>,
+) -> Response<Body> {
+    let method = req.method();
+    let path = req.uri().path();
+    tracing::info!("control api request: {} {}", method, path);
+
+    if let Some(resp) = access.check_origin(req) {
+        return resp;
+    }
+    if method == Method::POST {
+        if let Some(resp) = access.check_mutation(req) {
+            return resp;
+        }
+    }
+
+    match (method, path) {
+        (&Method::GET, SETTINGS_PATH) => json_response(StatusCode::OK, settings(config)),
+        (&Method::POST, MAINTENANCE_ON_PATH) => maintenance_mode(config, req, true),
+        (&Method::POST, MAINTENANCE_OFF_PATH) => maintenance_mode(config, req, false),
+        (&Method::POST, RELOAD_PATH) => reload(config).await,
+        (&Method::POST, CACHE_FLUSH_PATH) => flush_caches(config),
+        (
+            _,
+            SETTINGS_PATH | MAINTENANCE_ON_PATH | MAINTENANCE_OFF_PATH | RELOAD_PATH
+            | CACHE_FLUSH_PATH,
+        ) => json_response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            json!({ "error": "method not allowed" }),
+        ),
+        _ => json_response(StatusCode::NOT_FOUND, json!({ "error": "not found" })),
+    }
+}
+
+/// Reloads the configuration on the blocking threads, since it reads the configuration
+/// file and the files referenced by it.
+async fn reload(config: &Arc<RuntimeConfig>) -> Response<Body> {
+    let config = config.clone();
+    let result = tokio::task::spawn_blocking(move || config.reload_from_env(Trigger::ControlApi))
+        .await
+        .unwrap_or_else(|err| Err(anyhow!("configuration reload task failed: {err}")));
+    match result {
+        Ok(()) => json_response(StatusCode::OK, json!({ "reloaded": true })),
+        Err(err) => json_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            json!({ "reloaded": false, "error": format!("{err:#}") }),
+        ),
+    }
+}
+
+/// Gets the current `general` settings including the runtime changes.
+fn settings(config: &RuntimeConfig) -> serde_json::Value {
+    let mut general = config.general.load().as_ref().clone();
+    let handler_opts = config.handler_opts.load();
+    general["maintenance-mode"] = handler_opts.maintenance_mode.into();
+    general["maintenance-mode-file"] = handler_opts.maintenance_mode_file.to_string_lossy().into();
+    general
+}
+
+/// Turns the maintenance mode on or off, swapping its HTML file if a `file` query is given.
+/// An empty `file` query means the generic maintenance message.
+fn maintenance_mode<T>(config: &RuntimeConfig, req: &Request<T>, enabled: bool) -> Response<Body> {
+    let query = req.uri().query().unwrap_or_default().as_bytes();
+    let file = form_urlencoded::parse(query)
+        .find(|(key, _)| key == "file")
+        .map(|(_, value)| PathBuf::from(value.as_ref()));
+    let file = match file {
+        Some(file) if !file.as_os_str().is_empty() => match maintenance_file(config, &file) {
+            Ok(file) => Some(file),
+            Err(err) => {
+                return json_response(
+                    StatusCode::BAD_REQUEST,
+                    json!({ "error": format!("{err:#}") }),
+                );
+            }
+        },
+        file => file,
+    };
+
+    let enabled = config.set_maintenance_mode(Some(enabled), file, Trigger::ControlApi);
+    let file = config.handler_opts.load().maintenance_mode_file.clone();
+    tracing::info!(
+        "maintenance mode: enabled={}, file=\"{}\" (control api)",
+        enabled,
+        file.display()
+    );
+    json_response(
+        StatusCode::OK,
+        json!({ "maintenance-mode": enabled, "maintenance-mode-file": file.to_string_lossy() }),
+    )
+}
+
+/// Resolves a maintenance mode file given via the control API.
+///
+/// Since the file is served to every client, it must be located under the root directory
+/// or under the directory of the maintenance mode file of the settings.
+fn maintenance_file(config: &RuntimeConfig, file: &Path) -> Result<PathBuf> {
+    let file = file
+        .canonicalize()
+        .ok()
+        .filter(|file| file.is_file())
+        .with_context(|| format!("maintenance mode file not found: {}", file.display()))?;
+
+    let root_dir = config.handler_opts.load().root_dir.clone();
+    let settings_file = config
+        .general
+        .load()
+        .get("maintenance-mode-file")
+        .and_then(serde_json::Value::as_str)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from);
+    let settings_dir = settings_file.map(|path| match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_owned(),
+        _ => PathBuf::from("."),
+    });
+
+    let allowed = [Some(root_dir), settings_dir]
+        .into_iter()
+        .flatten()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .any(|dir| file.starts_with(dir));
+    if !allowed {
+        bail!(
+            "maintenance mode file {} is not located under the root directory \
+            or the directory of the maintenance mode file",
+            file.display()
+        );
+    }
+    Ok(file)
+}
+
+fn flush_caches(config: &RuntimeConfig) -> Response<Body> {
+    let mut flushed: Vec<&str> = Vec::new();
+    let handler_opts = config.handler_opts.load();
+
+    #[cfg(feature = "directory-listing")]
+    if let Some(dir_size) = &handler_opts.dir_listing_dir_size {
+        dir_size.clear();
+        flushed.push("directory-listing-dir-size");
+    }
+
+    if let Some(not_found) = &handler_opts.not_found_cache {
+        not_found.clear();
+        flushed.push("not-found-cache");
+    }
+
+    #[cfg(feature = "experimental")]
+    if let Some(store) = crate::mem_cache::cache::CACHE_STORE.get() {
+        store.invalidate_all();
+        flushed.push("memory-cache");
+    }
+
+    tracing::info!("caches flushed: {:?} (control api)", flushed);
+    json_response(StatusCode::OK, json!({ "flushed": flushed }))
+}
+
+fn json_response(status: StatusCode, body: serde_json::Value) -> Response<Body> {
+    let mut resp = Response::new(Body::from(body.to_string()));
+    *resp.status_mut() = status;
+    resp.headers_mut().typed_insert(ContentType::json());
+    resp
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Body, Method, Request, Response, StatusCode};
+    use std::sync::Arc;
+
+    use super::{Access, Listen, handle};
+    use crate::reload::{RuntimeConfig, tests::runtime_config};
+    use crate::testing::fixtures::fixture_settings;
+
+    fn call(config: &Arc<RuntimeConfig>, access: &Access, req: &Request<Body>) -> Response<Body> {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(handle(config, access, req))
+    }
+
+    fn request(
+        config: &Arc<RuntimeConfig>,
+        method: Method,
+        path: &str,
+    ) -> (StatusCode, serde_json::Value) {
+        let mut builder = Request::builder().method(&method).uri(path);
+        if method == Method::POST {
+            builder = builder.header("content-type", "application/json");
+        }
+        send(
+            config,
+            &Access::default(),
+            builder.body(Body::empty()).unwrap(),
+        )
+    }
+
+    fn send(
+        config: &Arc<RuntimeConfig>,
+        access: &Access,
+        req: Request<Body>,
+    ) -> (StatusCode, serde_json::Value) {
+        let resp = call(config, access, &req);
+        let status = resp.status();
+        assert_eq!(resp.headers()["content-type"], "application/json");
+        let body = futures_util::FutureExt::now_or_never(hyper::body::to_bytes(resp.into_body()))
+            .unwrap()
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[test]
+    fn test_listen_parse() {
+        assert_eq!(
+            Listen::parse("127.0.0.1:8788").unwrap(),
+            Listen::Tcp("127.0.0.1:8788".parse().unwrap())
+        );
+        assert_eq!(
+            Listen::parse("[::1]:8788").unwrap(),
+            Listen::Tcp("[::1]:8788".parse().unwrap())
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            Listen::parse("unix:/run/sws/control.sock").unwrap(),
+            Listen::Unix("/run/sws/control.sock".into())
+        );
+        assert!(Listen::parse("0.0.0.0:8788").is_err());
+        assert!(Listen::parse("192.168.1.10:8788").is_err());
+        assert!(Listen::parse("localhost").is_err());
+        assert!(Listen::parse("unix:").is_err());
+    }
+
+    #[test]
+    fn test_settings() {
+        let config = Arc::new(runtime_config(fixture_settings("toml/redirects.toml")));
+        let (status, settings) = request(&config, Method::GET, "/settings");
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(settings["root"], "docker/public");
+        assert_eq!(settings["maintenance-mode"], false);
+        assert_eq!(settings["maintenance-mode-status"], 503);
+        assert!(settings.get("basic-auth").is_none());
+    }
+
+    #[test]
+    fn test_maintenance_mode() {
+        let config = Arc::new(runtime_config(fixture_settings("toml/redirects.toml")));
+
+        let (status, body) = request(&config, Method::POST, "/maintenance/on");
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["maintenance-mode"], true);
+        assert!(config.handler_opts.load().maintenance_mode);
+        // Other options are kept
+        assert!(config.handler_opts.load().advanced_opts.is_some());
+        assert_eq!(
+            request(&config, Method::GET, "/settings").1["maintenance-mode"],
+            true
+        );
+
+        request(&config, Method::POST, "/maintenance/off");
+        assert!(!config.handler_opts.load().maintenance_mode);
+    }
+
+    #[test]
+    fn test_maintenance_mode_file() {
+        let mut settings = fixture_settings("toml/redirects.toml");
+        settings.general.root = "tests/fixtures/public".into();
+        let config = Arc::new(runtime_config(settings));
+
+        let (status, body) = request(
+            &config,
+            Method::POST,
+            "/maintenance/on?file=tests/fixtures/public/50x.html",
+        );
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["maintenance-mode"], true);
+        // The file is canonicalized
+        let file = std::fs::canonicalize("tests/fixtures/public/50x.html").unwrap();
+        let file = file.to_str().unwrap();
+        assert_eq!(body["maintenance-mode-file"], file);
+        assert_eq!(
+            config.handler_opts.load().maintenance_mode_file.to_str(),
+            Some(file)
+        );
+        assert_eq!(
+            request(&config, Method::GET, "/settings").1["maintenance-mode-file"],
+            file
+        );
+
+        // The file is kept when not given and an empty one means the generic message
+        let (_, body) = request(&config, Method::POST, "/maintenance/off");
+        assert_eq!(body["maintenance-mode-file"], file);
+        let (_, body) = request(&config, Method::POST, "/maintenance/on?file=");
+        assert_eq!(body["maintenance-mode-file"], "");
+
+        let (status, _) = request(
+            &config,
+            Method::POST,
+            "/maintenance/on?file=tests/fixtures/missing.html",
+        );
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            config.handler_opts.load().maintenance_mode_file.as_os_str(),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_maintenance_mode_file_outside_dirs() {
+        let mut settings = fixture_settings("toml/redirects.toml");
+        settings.general.root = "tests/fixtures/public".into();
+        settings.general.maintenance_mode_file = "tests/fixtures/error_pages/4xx.html".into();
+        let config = Arc::new(runtime_config(settings));
+
+        let outside = std::fs::canonicalize("Cargo.toml").unwrap();
+        for file in [
+            "tests/fixtures/public/../../../Cargo.toml".to_owned(),
+            "tests/fixtures/error_pages/../../../Cargo.toml".to_owned(),
+            outside.to_string_lossy().into_owned(),
+        ] {
+            let (status, body) = request(
+                &config,
+                Method::POST,
+                &format!("/maintenance/on?file={file}"),
+            );
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{file}");
+            assert!(
+                body["error"]
+                    .as_str()
+                    .unwrap()
+                    .contains("is not located under")
+            );
+        }
+        assert!(!config.handler_opts.load().maintenance_mode);
+
+        // Files next to the maintenance mode file of the settings are allowed
+        let (status, _) = request(
+            &config,
+            Method::POST,
+            "/maintenance/on?file=tests/fixtures/error_pages/../error_pages/410.html",
+        );
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_cache_flush() {
+        let config = Arc::new(runtime_config(fixture_settings("toml/redirects.toml")));
+        let (status, body) = request(&config, Method::POST, "/cache/flush");
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["flushed"].is_array());
+    }
+
+    #[test]
+    fn test_access_origin_and_host() {
+        let config = Arc::new(runtime_config(fixture_settings("toml/redirects.toml")));
+        let access = Access::new(Some("127.0.0.1:8788".parse().unwrap()), "");
+        let get = |headers: &[(&str, &str)]| {
+            let mut builder = Request::builder().uri("/settings");
+            for (name, value) in headers {
+                builder = builder.header(*name, *value);
+            }
+            send(&config, &access, builder.body(Body::empty()).unwrap()).0
+        };
+
+        assert_eq!(get(&[("host", "127.0.0.1:8788")]), StatusCode::OK);
+        assert_eq!(get(&[("host", "LOCALHOST:8788")]), StatusCode::OK);
+        // DNS rebinding
+        assert_eq!(
+            get(&[("host", "attacker.example:8788")]),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(get(&[("host", "127.0.0.1:8787")]), StatusCode::FORBIDDEN);
+        assert_eq!(get(&[]), StatusCode::FORBIDDEN);
+        // Cross-origin requests
+        assert_eq!(
+            get(&[
+                ("host", "127.0.0.1:8788"),
+                ("origin", "http://127.0.0.1:8788")
+            ]),
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[test]
+    fn test_access_content_type() {
+        let config = Arc::new(runtime_config(fixture_settings("toml/redirects.toml")));
+        let access = Access::default();
+        let post = |content_type: Option<&str>| {
+            let mut builder = Request::builder()
+                .method(Method::POST)
+                .uri("/maintenance/on");
+            if let Some(content_type) = content_type {
+                builder = builder.header("content-type", content_type);
+            }
+            send(&config, &access, builder.body(Body::empty()).unwrap()).0
+        };
+
+        for content_type in [
+            None,
+            Some("application/x-www-form-urlencoded"),
+            Some("multipart/form-data; boundary=x"),
+            Some("Text/Plain;charset=UTF-8"),
+        ] {
+            assert_eq!(
+                post(content_type),
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "{content_type:?}"
+            );
+        }
+        assert!(!config.handler_opts.load().maintenance_mode);
+
+        assert_eq!(post(Some("application/json")), StatusCode::OK);
+        assert!(config.handler_opts.load().maintenance_mode);
+    }
+
+    #[test]
+    fn test_access_token() {
+        let config = Arc::new(runtime_config(fixture_settings("toml/redirects.toml")));
+        let access = Access::new(None, "secret");
+        let post = |authorization: Option<&str>| {
+            let mut builder = Request::builder()
+                .method(Method::POST)
+                .uri("/maintenance/on")
+                .header("content-type", "application/json");
+            if let Some(authorization) = authorization {
+                builder = builder.header("authorization", authorization);
+            }
+            let req = builder.body(Body::empty()).unwrap();
+            let resp = call(&config, &access, &req);
+            (
+                resp.status(),
+                resp.headers().get("www-authenticate").cloned(),
+            )
+        };
+
+        for authorization in [
+            None,
+            Some("Bearer wrong"),
+            Some("Bearer secre"),
+            Some("secret"),
+        ] {
+            let (status, challenge) = post(authorization);
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{authorization:?}");
+            assert_eq!(challenge.unwrap(), "Bearer");
+        }
+        assert!(!config.handler_opts.load().maintenance_mode);
+
+        assert_eq!(post(Some("Bearer secret")).0, StatusCode::OK);
+        assert!(config.handler_opts.load().maintenance_mode);
+
+        // The reads don't require the token
+        let req = Request::builder()
+            .uri("/settings")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(send(&config, &access, req).0, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        let config = Arc::new(runtime_config(fixture_settings("toml/redirects.toml")));
+        let (status, _) = request(&config, Method::GET, "/maintenance/on");
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        let (status, _) = request(&config, Method::POST, "/settings");
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        let (status, _) = request(&config, Method::GET, "/");
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -6,10 +6,19 @@
 //! It provides an arbitrary `Cache-Control` headers functionality
 //! for incoming requests based on a set of file types.
 //!
+//! The `Cache-Control` policies of the `advanced` options matching the request path
+//! take precedence over the file types heuristic.
+//!
 
-use hyper::{Body, Request, Response};
+use headers::{Expires, HeaderMapExt};
+use hyper::{
+    Body, Request, Response,
+    header::{CACHE_CONTROL, HeaderValue},
+};
+use std::sync::Arc;
+use std::time::SystemTime;
 
-use crate::{Error, handler::RequestHandlerOpts};
+use crate::{Error, Result, handler::RequestHandlerOpts, settings::file, type_map::TypeInfo};
 
 // Cache-Control `max-age` variants
 const MAX_AGE_ONE_HOUR: u64 = 60 * 60;
@@ -26,7 +35,12 @@ const CACHE_EXT_ONE_YEAR: [&str; 32] = [
 
 pub(crate) fn init(enabled: bool, handler_opts: &mut RequestHandlerOpts) {
     handler_opts.cache_control_headers = enabled;
-    tracing::info!("cache control headers: enabled={enabled}");
+    let policies = handler_opts
+        .advanced_opts
+        .as_ref()
+        .and_then(|advanced| advanced.cache_control.as_ref())
+        .map_or(0, Vec::len);
+    tracing::info!("cache control headers: enabled={enabled}, policies={policies}");
 }
 
 /// Appends `Cache-Control` header to a response if necessary
@@ -35,24 +49,85 @@ pub(crate) fn post_process<T>(
     req: &Request<T>,
     mut resp: Response<Body>,
 ) -> Result<Response<Body>, Error> {
-    if opts.cache_control_headers {
-        append_headers(req.uri().path(), &mut resp);
+    let uri_path = req.uri().path();
+    let policy = opts
+        .advanced_opts
+        .as_ref()
+        .and_then(|advanced| advanced.cache_control.as_deref())
+        .and_then(|policies| policies.iter().find(|p| p.source.is_match(uri_path)));
+
+    if let Some(policy) = policy {
+        resp.headers_mut()
+            .insert(CACHE_CONTROL, policy.value.clone());
+        if let Some(expires) = policy.expires {
+            resp.headers_mut()
+                .typed_insert(Expires::from(SystemTime::now() + expires));
+        }
+    } else if opts.cache_control_headers {
+        // The type of the served file is already known if the type map is enabled
+        match resp.extensions().get::<Arc<TypeInfo>>().map(|t| t.max_age) {
+            Some(max_age) => {
+                resp.headers_mut().insert(
+                    CACHE_CONTROL,
+                    format!("max-age={}", cap_seconds(max_age)).parse().unwrap(),
+                );
+            }
+            None => append_headers(uri_path, &mut resp),
+        }
     }
     Ok(resp)
 }
 
+/// Builds the `Cache-Control` header value of a policy from its directives.
+pub(crate) fn policy_value(policy: &file::CacheControl) -> Result<HeaderValue> {
+    let no_store = policy.no_store.unwrap_or_default();
+    let no_cache = policy.no_cache.unwrap_or_default();
+    let immutable = policy.immutable.unwrap_or_default();
+    let stale = policy.stale_while_revalidate.is_some() || policy.stale_if_error.is_some();
+    if no_store && (policy.max_age.is_some() || immutable || stale) {
+        bail!(
+            "the `no-store` directive can not be combined with `max-age`, `immutable` or `stale-*` directives"
+        );
+    }
+
+    let mut directives = Vec::new();
+    if no_store {
+        directives.push("no-store".to_owned());
+    }
+    if no_cache {
+        directives.push("no-cache".to_owned());
+    }
+    if let Some(max_age) = policy.max_age {
+        directives.push(format!("max-age={}", cap_seconds(max_age)));
+    }
+    if immutable {
+        directives.push("immutable".to_owned());
+    }
+    if let Some(secs) = policy.stale_while_revalidate {
+        directives.push(format!("stale-while-revalidate={}", cap_seconds(secs)));
+    }
+    if let Some(secs) = policy.stale_if_error {
+        directives.push(format!("stale-if-error={}", cap_seconds(secs)));
+    }
+    if directives.is_empty() {
+        bail!(
+            "at least one of the `max-age`, `immutable`, `no-cache`, `no-store` or `stale-*` directives is required"
+        );
+    }
+    Ok(HeaderValue::from_str(&directives.join(", "))?)
+}
+
+/// Caps a directive value in seconds at ~136 years.
+pub(crate) fn cap_seconds(secs: u64) -> u64 {
+    std::cmp::min(secs, u32::MAX as u64)
+}
+
 /// It appends a `Cache-Control` header to a response if that one is part of a set of file types.
 pub fn append_headers(uri: &str, resp: &mut Response<Body>) {
     let max_age = get_max_age(uri);
     resp.headers_mut().insert(
         "cache-control",
-        format!(
-            "max-age={}",
-            // It caps value in seconds at ~136 years
-            std::cmp::min(max_age, u32::MAX as u64)
-        )
-        .parse()
-        .unwrap(),
+        format!("max-age={}", cap_seconds(max_age)).parse().unwrap(),
     );
 }
 
@@ -67,16 +142,18 @@ fn get_file_extension(uri: &str) -> Option<&str> {
 #[inline(always)]
 fn get_max_age(uri: &str) -> u64 {
     // Default max-age value in seconds (one day)
-    let mut max_age = MAX_AGE_ONE_DAY;
+    get_file_extension(uri).map_or(MAX_AGE_ONE_DAY, max_age)
+}
 
-    if let Some(extension) = get_file_extension(uri) {
-        if CACHE_EXT_ONE_HOUR.binary_search(&extension).is_ok() {
-            max_age = MAX_AGE_ONE_HOUR;
-        } else if CACHE_EXT_ONE_YEAR.binary_search(&extension).is_ok() {
-            max_age = MAX_AGE_ONE_YEAR;
-        }
+/// Gets the `Cache-Control` max-age in seconds of a file extension.
+pub(crate) fn max_age(extension: &str) -> u64 {
+    if CACHE_EXT_ONE_HOUR.binary_search(&extension).is_ok() {
+        MAX_AGE_ONE_HOUR
+    } else if CACHE_EXT_ONE_YEAR.binary_search(&extension).is_ok() {
+        MAX_AGE_ONE_YEAR
+    } else {
+        MAX_AGE_ONE_DAY
     }
-    max_age
 }
 
 #[cfg(test)]
@@ -85,8 +162,27 @@ mod tests {
 
     use super::{
         CACHE_EXT_ONE_HOUR, CACHE_EXT_ONE_YEAR, MAX_AGE_ONE_DAY, MAX_AGE_ONE_HOUR,
-        MAX_AGE_ONE_YEAR, append_headers, get_file_extension,
+        MAX_AGE_ONE_YEAR, append_headers, get_file_extension, policy_value,
     };
+    use crate::settings::file::CacheControl;
+
+    fn make_policy(
+        max_age: Option<u64>,
+        immutable: bool,
+        no_cache: bool,
+        no_store: bool,
+    ) -> CacheControl {
+        CacheControl {
+            source: "**".to_owned(),
+            max_age,
+            immutable: Some(immutable),
+            no_cache: Some(no_cache),
+            no_store: Some(no_store),
+            stale_while_revalidate: None,
+            stale_if_error: None,
+            expires: None,
+        }
+    }
 
     #[test]
     fn headers_one_hour() {
@@ -143,4 +239,41 @@ mod tests {
         assert_eq!(get_file_extension("/potato."), Some(""));
         assert_eq!(get_file_extension("/"), None);
     }
+
+    #[test]
+    fn policy_directives() {
+        let value = |policy| policy_value(&policy).unwrap();
+        assert_eq!(
+            value(make_policy(Some(31536000), true, false, false)),
+            "max-age=31536000, immutable"
+        );
+        assert_eq!(
+            value(make_policy(Some(0), false, true, false)),
+            "no-cache, max-age=0"
+        );
+        assert_eq!(value(make_policy(None, false, false, true)), "no-store");
+        assert_eq!(
+            value(make_policy(Some(u64::MAX), false, false, false)),
+            format!("max-age={}", u32::MAX)
+        );
+
+        let mut policy = make_policy(Some(600), false, false, false);
+        policy.stale_while_revalidate = Some(30);
+        policy.stale_if_error = Some(86400);
+        assert_eq!(
+            value(policy),
+            "max-age=600, stale-while-revalidate=30, stale-if-error=86400"
+        );
+    }
+
+    #[test]
+    fn policy_invalid_directives() {
+        assert!(policy_value(&make_policy(None, false, false, false)).is_err());
+        assert!(policy_value(&make_policy(Some(60), false, false, true)).is_err());
+        assert!(policy_value(&make_policy(None, true, false, true)).is_err());
+
+        let mut policy = make_policy(None, false, false, true);
+        policy.stale_if_error = Some(60);
+        assert!(policy_value(&policy).is_err());
+    }
 }
@@ -5,6 +5,9 @@
 
 //! CORS module to handle incoming requests.
 //!
+//! A global CORS policy applies to every request unless a policy of the `advanced` options
+//! is scoped to a path prefix of the request, where the longest matching prefix wins.
+//!
 
 // Part of the file is borrowed from https://github.com/seanmonstar/warp/blob/master/src/filters/cors.rs
 
@@ -16,7 +19,13 @@ use http::header;
 use hyper::{Body, Request, Response, StatusCode};
 use std::collections::HashSet;
 
-use crate::{Error, error_page, handler::RequestHandlerOpts};
+use crate::{Error, Result, error_page, handler::RequestHandlerOpts, settings::file};
+
+/// Default allowed methods of the CORS policies.
+const DEFAULT_ALLOW_METHODS: [&str; 3] = ["GET", "HEAD", "OPTIONS"];
+
+/// Default allowed and exposed headers of the CORS policies.
+const DEFAULT_HEADERS: [&str; 2] = ["origin", "content-type"];
 
 /// It defines CORS instance.
 #[derive(Clone, Debug)]
@@ -26,6 +35,7 @@ pub struct Cors {
     max_age: Option<u64>,
     allowed_methods: HashSet<http::Method>,
     origins: Option<HashSet<HeaderValue>>,
+    allow_credentials: bool,
 }
 
 /// It builds a new CORS instance.
@@ -41,7 +51,7 @@ pub fn new(
         let [allow_headers_vec, expose_headers_vec] =
             [allow_headers_str, expose_headers_str].map(|s| {
                 if s.is_empty() {
-                    vec!["origin", "content-type"]
+                    DEFAULT_HEADERS.to_vec()
                 } else {
                     s.split(',').map(|s| s.trim()).collect::<Vec<_>>()
                 }
@@ -54,7 +64,7 @@ pub fn new(
                 cors.allow_any_origin()
                     .allow_headers(allow_headers_vec)
                     .expose_headers(expose_headers_vec)
-                    .allow_methods(vec!["GET", "HEAD", "OPTIONS"]),
+                    .allow_methods(DEFAULT_ALLOW_METHODS),
             )
         } else {
             let hosts = origins_str.split(',').map(|s| s.trim()).collect::<Vec<_>>();
@@ -65,7 +75,7 @@ pub fn new(
                     cors.allow_origins(hosts)
                         .allow_headers(allow_headers_vec)
                         .expose_headers(expose_headers_vec)
-                        .allow_methods(vec!["GET", "HEAD", "OPTIONS"]),
+                        .allow_methods(DEFAULT_ALLOW_METHODS),
                 )
             }
         };
@@ -84,6 +94,81 @@ pub fn new(
     Cors::build(cors)
 }
 
+/// Builds a CORS policy of the `advanced` options, failing on invalid values.
+pub fn policy(entry: &file::Cors) -> Result<Configured> {
+    let origins: Vec<&str> = entry
+        .allow_origins
+        .iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect();
+    if origins.is_empty() {
+        bail!("no allowed origins");
+    }
+    let allow_credentials = entry.allow_credentials.unwrap_or_default();
+
+    let cors = if origins.contains(&"*") {
+        // NOTE: the allowed origin is the one of the request, so credentials
+        // would be allowed for every website
+        if allow_credentials {
+            bail!("credentials can not be allowed along with any origin (`*`)");
+        }
+        Cors::new().allow_any_origin()
+    } else {
+        for origin in &origins {
+            let valid = origin
+                .split_once("://")
+                .is_some_and(|(scheme, rest)| Origin::try_from_parts(scheme, rest, None).is_ok());
+            if !valid {
+                bail!("invalid allowed origin: {origin}");
+            }
+        }
+        Cors::new().allow_origins(origins)
+    };
+
+    let methods = match &entry.allow_methods {
+        Some(methods) => trimmed(methods),
+        None => DEFAULT_ALLOW_METHODS.to_vec(),
+    };
+    if let Some(method) = methods
+        .iter()
+        .find(|m| http::Method::from_bytes(m.as_bytes()).is_err())
+    {
+        bail!("invalid allowed method: {method}");
+    }
+
+    let [allow_headers, expose_headers] =
+        [&entry.allow_headers, &entry.expose_headers].map(|headers| match headers {
+            Some(headers) => trimmed(headers),
+            None => DEFAULT_HEADERS.to_vec(),
+        });
+    if let Some(header) = allow_headers
+        .iter()
+        .chain(&expose_headers)
+        .find(|h| HeaderName::from_bytes(h.as_bytes()).is_err())
+    {
+        bail!("invalid header name: {header}");
+    }
+
+    let mut cors = cors
+        .allow_methods(methods)
+        .allow_headers(allow_headers)
+        .expose_headers(expose_headers)
+        .allow_credentials(allow_credentials);
+    if let Some(max_age) = entry.max_age {
+        cors = cors.max_age(max_age);
+    }
+    Cors::build(Some(cors)).ok_or_else(|| anyhow!("unable to build the cors policy"))
+}
+
+fn trimmed(values: &[String]) -> Vec<&str> {
+    values
+        .iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
 impl Cors {
     /// Creates a new Cors instance.
     pub fn new() -> Self {
@@ -93,9 +178,24 @@ impl Cors {
             exposed_headers: HashSet::new(),
             allowed_methods: HashSet::new(),
             max_age: None,
+            allow_credentials: false,
         }
     }
 
+    /// Sets whether the requests can include credentials like cookies
+    /// via the `Access-Control-Allow-Credentials` header.
+    pub fn allow_credentials(mut self, allow: bool) -> Self {
+        self.allow_credentials = allow;
+        self
+    }
+
+    /// Sets the time in seconds the preflight responses can be cached
+    /// via the `Access-Control-Max-Age` header.
+    pub fn max_age(mut self, seconds: u64) -> Self {
+        self.max_age = Some(seconds);
+        self
+    }
+
     /// Adds multiple methods to the existing list of allowed request methods.
     ///
     /// # Panics
@@ -356,6 +456,12 @@ impl Configured {
         if let Some(max_age) = self.cors.max_age {
             headers.insert(header::ACCESS_CONTROL_MAX_AGE, max_age.into());
         }
+        if self.cors.allow_credentials {
+            headers.insert(
+                header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
     }
 }
 
@@ -389,12 +495,40 @@ pub(crate) fn init(
     );
 }
 
+/// Gets the CORS policy of a request path, which is the policy of the `advanced` options
+/// with the longest path prefix matching it, or the global one otherwise.
+pub(crate) fn policy_for<'a>(
+    opts: &'a RequestHandlerOpts,
+    uri_path: &str,
+) -> Option<&'a Configured> {
+    let policies = opts
+        .advanced_opts
+        .as_ref()
+        .and_then(|advanced| advanced.cors.as_deref())
+        .unwrap_or_default();
+    policies
+        .iter()
+        .filter(|policy| is_path_prefix(&policy.path, uri_path))
+        .max_by_key(|policy| policy.path.len())
+        .map(|policy| &policy.policy)
+        .or(opts.cors.as_ref())
+}
+
+/// Checks if a path prefix without trailing slash matches a request path on a segment boundary.
+pub(crate) fn is_path_prefix(prefix: &str, uri_path: &str) -> bool {
+    prefix == "/"
+        || uri_path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
 /// Rejects requests with wrong CORS headers
 pub(crate) fn pre_process<T>(
+    cors: Option<&Configured>,
     opts: &RequestHandlerOpts,
     req: &Request<T>,
 ) -> Option<Result<Response<Body>, Error>> {
-    let cors = opts.cors.as_ref()?;
+    let cors = cors?;
     match cors.check_request(req.method(), req.headers()) {
         Ok((_, state)) => {
             tracing::debug!("cors state: {:?}", state);
@@ -406,8 +540,7 @@ pub(crate) fn pre_process<T>(
                 req.uri(),
                 req.method(),
                 &StatusCode::FORBIDDEN,
-                &opts.page404,
-                &opts.page50x,
+                &opts.error_pages,
             ))
         }
     }
@@ -415,11 +548,11 @@ pub(crate) fn pre_process<T>(
 
 /// Adds CORS headers to response
 pub(crate) fn post_process<T>(
-    opts: &RequestHandlerOpts,
+    cors: Option<&Configured>,
     req: &Request<T>,
     mut resp: Response<Body>,
 ) -> Result<Response<Body>, Error> {
-    if let Some(cors) = opts.cors.as_ref() {
+    if let Some(cors) = cors {
         if let Ok((headers, _)) = cors.check_request(req.method(), req.headers()) {
             if !headers.is_empty() {
                 for (k, v) in headers.iter() {
@@ -438,8 +571,12 @@ pub(crate) fn post_process<T>(
 
 #[cfg(test)]
 mod tests {
-    use super::{Configured, Cors, post_process, pre_process};
-    use crate::{Error, handler::RequestHandlerOpts};
+    use super::{Configured, Cors, is_path_prefix, policy, policy_for, post_process, pre_process};
+    use crate::{
+        Error,
+        handler::RequestHandlerOpts,
+        settings::{self, Advanced, file},
+    };
     use hyper::{Body, Request, Response, StatusCode};
 
     fn make_request(method: &str, origin: &str) -> Request<Body> {
@@ -486,9 +623,9 @@ mod tests {
         };
         let req = make_request("GET", "https://example.com/");
 
-        assert!(pre_process(&opts, &req).is_none());
+        assert!(pre_process(opts.cors.as_ref(), &opts, &req).is_none());
 
-        let resp = post_process(&opts, &req, make_response())?;
+        let resp = post_process(opts.cors.as_ref(), &req, make_response())?;
         assert_eq!(get_allowed_origin(resp), None);
 
         Ok(())
@@ -502,9 +639,9 @@ mod tests {
         };
         let req = make_request("GET", "");
 
-        assert!(pre_process(&opts, &req).is_none());
+        assert!(pre_process(opts.cors.as_ref(), &opts, &req).is_none());
 
-        let resp = post_process(&opts, &req, make_response())?;
+        let resp = post_process(opts.cors.as_ref(), &req, make_response())?;
         assert_eq!(get_allowed_origin(resp), None);
 
         Ok(())
@@ -518,10 +655,12 @@ mod tests {
         };
 
         assert!(is_403(pre_process(
+            opts.cors.as_ref(),
             &opts,
             &make_request("GET", "https://example.info")
         )));
         assert!(is_403(pre_process(
+            opts.cors.as_ref(),
             &opts,
             &make_request("OPTIONS", "https://example.com")
         )));
@@ -529,7 +668,7 @@ mod tests {
         let mut req = make_request("OPTIONS", "https://example.com");
         req.headers_mut()
             .insert("Access-Control-Request-Method", "POST".try_into().unwrap());
-        assert!(is_403(pre_process(&opts, &req)));
+        assert!(is_403(pre_process(opts.cors.as_ref(), &opts, &req)));
 
         let mut req = make_request("OPTIONS", "https://example.com");
         req.headers_mut()
@@ -538,7 +677,7 @@ mod tests {
             "Access-Control-Request-Headers",
             "X-Forbidden".try_into().unwrap(),
         );
-        assert!(is_403(pre_process(&opts, &req)));
+        assert!(is_403(pre_process(opts.cors.as_ref(), &opts, &req)));
     }
 
     #[test]
@@ -549,9 +688,9 @@ mod tests {
         };
 
         let req = make_request("GET", "https://example.com");
-        assert!(pre_process(&opts, &req).is_none());
+        assert!(pre_process(opts.cors.as_ref(), &opts, &req).is_none());
 
-        let resp = post_process(&opts, &req, make_response())?;
+        let resp = post_process(opts.cors.as_ref(), &req, make_response())?;
         assert_eq!(get_allowed_origin(resp), Some("https://example.com".into()));
 
         let mut req = make_request("GET", "https://example.com");
@@ -561,11 +700,93 @@ mod tests {
             "Access-Control-Request-Headers",
             "X-Allowed".try_into().unwrap(),
         );
-        assert!(pre_process(&opts, &req).is_none());
+        assert!(pre_process(opts.cors.as_ref(), &opts, &req).is_none());
 
-        let resp = post_process(&opts, &req, make_response())?;
+        let resp = post_process(opts.cors.as_ref(), &req, make_response())?;
         assert_eq!(get_allowed_origin(resp), Some("https://example.com".into()));
 
         Ok(())
     }
+
+    fn make_policy(origins: &[&str]) -> file::Cors {
+        file::Cors {
+            path: "/".to_owned(),
+            allow_origins: origins.iter().map(|s| s.to_string()).collect(),
+            allow_methods: None,
+            allow_headers: None,
+            expose_headers: None,
+            allow_credentials: None,
+            max_age: None,
+        }
+    }
+
+    #[test]
+    fn test_policy_validation() {
+        assert!(policy(&make_policy(&["https://example.com", "*"])).is_ok());
+        assert!(policy(&make_policy(&[])).is_err());
+        assert!(policy(&make_policy(&["example.com"])).is_err());
+
+        let mut entry = make_policy(&["*"]);
+        entry.allow_credentials = Some(true);
+        assert!(policy(&entry).is_err());
+
+        let mut entry = make_policy(&["https://example.com"]);
+        entry.allow_methods = Some(vec!["GET".to_owned(), "BAD METHOD".to_owned()]);
+        assert!(policy(&entry).is_err());
+
+        let mut entry = make_policy(&["https://example.com"]);
+        entry.expose_headers = Some(vec!["bad header".to_owned()]);
+        assert!(policy(&entry).is_err());
+    }
+
+    #[test]
+    fn test_path_prefix() {
+        assert!(is_path_prefix("/", "/any/path"));
+        assert!(is_path_prefix("/api", "/api"));
+        assert!(is_path_prefix("/api", "/api/v1/users"));
+        assert!(!is_path_prefix("/api", "/apidocs"));
+        assert!(!is_path_prefix("/api/v1", "/api"));
+    }
+
+    #[test]
+    fn test_policy_selection() {
+        let mut api = make_policy(&["https://api.example.com"]);
+        api.allow_credentials = Some(true);
+        let policies = [("/", make_policy(&["*"])), ("/api", api)]
+            .into_iter()
+            .map(|(path, entry)| settings::Cors {
+                path: path.to_owned(),
+                policy: policy(&entry).unwrap(),
+            })
+            .collect();
+        let opts = RequestHandlerOpts {
+            cors: make_cors_config(),
+            advanced_opts: Some(Advanced {
+                cors: Some(policies),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let req = make_request("GET", "https://api.example.com");
+        let credentials = |uri_path: &str| {
+            let cors = policy_for(&opts, uri_path);
+            let resp = post_process(cors, &req, make_response()).unwrap();
+            resp.headers()
+                .contains_key("access-control-allow-credentials")
+        };
+        assert!(credentials("/api/users"));
+        assert!(!credentials("/apidocs"));
+        assert!(!credentials("/"));
+
+        // The global policy applies when no path prefix matches
+        let opts = RequestHandlerOpts {
+            cors: make_cors_config(),
+            ..Default::default()
+        };
+        let cors = policy_for(&opts, "/api/users");
+        let req = make_request("GET", "https://example.com");
+        let resp = post_process(cors, &req, make_response()).unwrap();
+        assert_eq!(get_allowed_origin(resp), Some("https://example.com".into()));
+    }
 }
@@ -6,10 +6,17 @@
 //! Module to append custom HTTP headers via TOML config file.
 //!
 
-use hyper::{Body, Request, Response};
+use hyper::{Body, Request, Response, header::CONTENT_TYPE, header::HeaderValue};
 use std::{ffi::OsStr, path::PathBuf};
 
-use crate::{Error, handler::RequestHandlerOpts, settings::Headers};
+use crate::{Error, handler::RequestHandlerOpts, redirects::request_host, settings::Headers};
+
+/// Placeholder replaced by the request host in the header values.
+const HOST_VARIABLE: &str = "$host";
+/// Placeholder replaced by the request URI path in the header values.
+const URI_VARIABLE: &str = "$uri";
+/// Placeholder replaced by the name of the served file in the header values.
+const FILE_VARIABLE: &str = "$file";
 
 /// Appends custom HTTP headers to a response if necessary
 pub(crate) fn post_process<T>(
@@ -21,6 +28,7 @@ pub(crate) fn post_process<T>(
     if let Some(advanced) = &opts.advanced_opts {
         append_headers(
             req.uri().path(),
+            &request_host(req),
             advanced.headers.as_deref(),
             &mut resp,
             file_path,
@@ -32,6 +40,7 @@ pub(crate) fn post_process<T>(
 /// Append custom HTTP headers to current response.
 pub fn append_headers(
     uri_path: &str,
+    uri_host: &str,
     headers_opts: Option<&[Headers]>,
     resp: &mut Response<Body>,
     file_path: Option<&PathBuf>,
@@ -46,19 +55,183 @@ pub fn append_headers(
                 _ => [uri_path, "/", name].concat(),
             });
 
-        let uri_path = match uri_path_auto_index {
+        let uri_path_matched = match uri_path_auto_index {
             Some(ref s) => s.as_str(),
             _ => uri_path,
         };
 
+        let file_name = file_path
+            .and_then(|p| p.file_name())
+            .and_then(OsStr::to_str)
+            .unwrap_or_default();
+        let variables = [
+            (HOST_VARIABLE, uri_host),
+            (URI_VARIABLE, uri_path),
+            (FILE_VARIABLE, file_name),
+        ];
+
+        let status = resp.status();
+        let content_type = resp
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| {
+                v.split(';')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_lowercase()
+            });
+
         for headers_entry in headers_vec {
             // Match header glob pattern against request uri
-            if headers_entry.source.is_match(uri_path) {
-                // Add/update headers if uri matches
-                for (name, value) in &headers_entry.headers {
-                    resp.headers_mut().insert(name, value.to_owned());
+            if !headers_entry.source.is_match(uri_path_matched) {
+                continue;
+            }
+            // Match the optional response status and content type conditions
+            if let Some(codes) = &headers_entry.status {
+                if !codes.contains(&status) {
+                    continue;
+                }
+            }
+            if let Some(matcher) = &headers_entry.content_type {
+                if !content_type.as_ref().is_some_and(|v| matcher.is_match(v)) {
+                    continue;
                 }
             }
+
+            for name in &headers_entry.remove {
+                resp.headers_mut().remove(name);
+            }
+            // Add/update headers if uri matches
+            for (name, value) in &headers_entry.headers {
+                match substitute(value, &variables) {
+                    Some(value) => {
+                        resp.headers_mut().insert(name, value);
+                    }
+                    None => tracing::debug!(
+                        "custom header {} skipped because of an invalid value after substitution",
+                        name
+                    ),
+                }
+            }
+        }
+    }
+}
+
+/// Replaces the variables of a header value if any.
+fn substitute(value: &HeaderValue, variables: &[(&str, &str)]) -> Option<HeaderValue> {
+    if !value.as_bytes().contains(&b'$') {
+        return Some(value.to_owned());
+    }
+    let Ok(value_str) = value.to_str() else {
+        return Some(value.to_owned());
+    };
+    let value_str = variables
+        .iter()
+        .fold(value_str.to_owned(), |acc, (name, var)| {
+            acc.replace(name, var)
+        });
+    HeaderValue::from_str(&value_str).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::append_headers;
+    use crate::settings::Headers;
+    use globset::Glob;
+    use headers::HeaderMap;
+    use hyper::{Body, Response, StatusCode, header::HeaderName};
+    use std::path::PathBuf;
+
+    fn make_entry(source: &str, headers: &[(&'static str, &'static str)]) -> Headers {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.insert(*name, value.parse().unwrap());
+        }
+        Headers {
+            source: Glob::new(source).unwrap().compile_matcher(),
+            headers: map,
+            remove: vec![],
+            status: None,
+            content_type: None,
+        }
+    }
+
+    fn make_resp(status: StatusCode, content_type: &str) -> Response<Body> {
+        Response::builder()
+            .status(status)
+            .header("content-type", content_type)
+            .header("server", "sws")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_remove_headers() {
+        let mut entry = make_entry("**", &[("x-frame-options", "DENY")]);
+        entry.remove = vec![HeaderName::from_static("server")];
+        let mut resp = make_resp(StatusCode::OK, "text/html");
+
+        append_headers("/", "localhost", Some(&[entry]), &mut resp, None);
+        assert!(resp.headers().get("server").is_none());
+        assert_eq!(resp.headers()["x-frame-options"], "DENY");
+    }
+
+    #[test]
+    fn test_conditional_headers() {
+        let mut entry = make_entry("**", &[("content-security-policy", "default-src 'self'")]);
+        entry.status = Some(vec![StatusCode::OK]);
+        entry.content_type = Some(Glob::new("text/*").unwrap().compile_matcher());
+        let entries = [entry];
+
+        for (status, content_type, applied) in [
+            (StatusCode::OK, "text/html; charset=utf-8", true),
+            (StatusCode::OK, "TEXT/CSS", true),
+            (StatusCode::NOT_FOUND, "text/html", false),
+            (StatusCode::OK, "application/json", false),
+        ] {
+            let mut resp = make_resp(status, content_type);
+            append_headers("/index.html", "localhost", Some(&entries), &mut resp, None);
+            assert_eq!(
+                resp.headers().contains_key("content-security-policy"),
+                applied,
+                "{status} {content_type}"
+            );
         }
     }
+
+    #[test]
+    fn test_header_variables() {
+        let entry = make_entry(
+            "/docs/**",
+            &[
+                ("content-security-policy", "default-src https://$host"),
+                ("content-disposition", "inline; filename=\"$file\""),
+                ("link", "<https://$host$uri>; rel=\"canonical\""),
+            ],
+        );
+        let file_path = PathBuf::from("/var/www/docs/index.html");
+        let mut resp = make_resp(StatusCode::OK, "text/html");
+
+        append_headers(
+            "/docs/",
+            "example.com",
+            Some(&[entry]),
+            &mut resp,
+            Some(&file_path),
+        );
+        assert_eq!(
+            resp.headers()["content-security-policy"],
+            "default-src https://example.com"
+        );
+        assert_eq!(
+            resp.headers()["content-disposition"],
+            "inline; filename=\"index.html\""
+        );
+        assert_eq!(
+            resp.headers()["link"],
+            "<https://example.com/docs/>; rel=\"canonical\""
+        );
+    }
 }
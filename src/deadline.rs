@@ -0,0 +1,201 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that propagates the deadline of a request to its filesystem operations.
+//!
+//! A request is abandoned once its client disconnects (its handler future gets dropped)
+//! or its request timeout elapses. The pending blocking filesystem work of the request
+//! (E.g. reading the entries of a directory listing) then stops at its next checkpoint,
+//! so the blocking threads are freed promptly under load.
+//!
+
+use hyper::{Body, Response, StatusCode};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::fs::walk::CancelToken;
+use crate::{Error, Result, handler::RequestHandlerOpts};
+
+/// Initializes the request timeout.
+pub(crate) fn init(timeout: u64, handler_opts: &mut RequestHandlerOpts) {
+    handler_opts.request_timeout = (timeout > 0).then(|| Duration::from_secs(timeout));
+    tracing::info!(
+        "request timeout: enabled={}, timeout={timeout}s",
+        timeout > 0
+    );
+}
+
+/// The deadline of a request shared with its filesystem operations.
+#[derive(Clone, Debug, Default)]
+pub struct Deadline {
+    cancel: CancelToken,
+    at: Option<Instant>,
+}
+
+impl Deadline {
+    /// Creates the deadline of a request expiring after the given timeout if any.
+    pub fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            cancel: CancelToken::default(),
+            at: timeout.map(|timeout| Instant::now() + timeout),
+        }
+    }
+
+    /// Whether the request was abandoned or its timeout elapsed.
+    pub fn is_expired(&self) -> bool {
+        self.cancel.is_cancelled() || self.at.is_some_and(|at| Instant::now() >= at)
+    }
+
+    /// Abandons the request, E.g. once its client disconnects.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+}
+
+/// Cancels a deadline when dropped along with the future of its request.
+struct CancelOnDrop<'a>(&'a Deadline);
+
+impl Drop for CancelOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// Checks the deadline of a request if any, failing once expired with a `504 Gateway Timeout`
+/// status if its timeout elapsed or a `408 Request Timeout` one if its client abandoned it.
+pub(crate) fn check(deadline: Option<&Deadline>) -> Result<(), StatusCode> {
+    match deadline {
+        Some(deadline) if deadline.is_expired() => {
+            tracing::debug!("request deadline expired, filesystem work abandoned");
+            if deadline.at.is_some_and(|at| Instant::now() >= at) {
+                Err(StatusCode::GATEWAY_TIMEOUT)
+            } else {
+                Err(StatusCode::REQUEST_TIMEOUT)
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Runs blocking filesystem work of a request on the blocking threads,
+/// which is expected to call [`check`] between its operations.
+/// The work is skipped if the deadline already expired while it was queued.
+#[cfg(any(feature = "directory-listing", feature = "archive-preview"))]
+pub(crate) async fn spawn_blocking<F, R>(
+    deadline: Option<&Deadline>,
+    work: F,
+) -> Result<R, StatusCode>
+where
+    F: FnOnce(Option<&Deadline>) -> Result<R, StatusCode> + Send + 'static,
+    R: Send + 'static,
+{
+    let deadline = deadline.cloned();
+    let task = tokio::task::spawn_blocking(move || {
+        check(deadline.as_ref())?;
+        work(deadline.as_ref())
+    });
+    match task.await {
+        Ok(result) => result,
+        Err(err) => {
+            tracing::error!("blocking filesystem task failed: {:?}", err);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Runs the future of a request until its deadline, responding via `on_timeout` once it elapses.
+/// The deadline gets cancelled when the future completes or is dropped (E.g. on client disconnect).
+pub(crate) async fn run<F, T>(
+    deadline: Deadline,
+    handle: F,
+    on_timeout: T,
+) -> Result<Response<Body>, Error>
+where
+    F: Future<Output = Result<Response<Body>, Error>>,
+    T: FnOnce() -> Result<Response<Body>, Error>,
+{
+    let _guard = CancelOnDrop(&deadline);
+    match deadline.at {
+        Some(at) => match tokio::time::timeout_at(at.into(), handle).await {
+            Ok(result) => result,
+            Err(_) => {
+                deadline.cancel();
+                tracing::warn!("request timeout elapsed, the request was abandoned");
+                on_timeout()
+            }
+        },
+        None => handle.await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Deadline, check, run};
+    use hyper::{Body, Response, StatusCode};
+    use std::time::Duration;
+
+    #[test]
+    fn test_check() {
+        assert!(check(None).is_ok());
+        assert!(check(Some(&Deadline::default())).is_ok());
+        assert!(check(Some(&Deadline::new(Some(Duration::from_secs(60))))).is_ok());
+        assert_eq!(
+            check(Some(&Deadline::new(Some(Duration::ZERO)))),
+            Err(StatusCode::GATEWAY_TIMEOUT)
+        );
+
+        let deadline = Deadline::default();
+        deadline.clone().cancel();
+        assert!(deadline.is_expired());
+        assert_eq!(check(Some(&deadline)), Err(StatusCode::REQUEST_TIMEOUT));
+    }
+
+    #[tokio::test]
+    async fn test_run_timeout() {
+        let deadline = Deadline::new(Some(Duration::from_millis(20)));
+        let shared = deadline.clone();
+        let resp = run(
+            deadline,
+            async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(Response::new(Body::empty()))
+            },
+            || {
+                let mut resp = Response::new(Body::empty());
+                *resp.status_mut() = StatusCode::GATEWAY_TIMEOUT;
+                Ok(resp)
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(shared.is_expired());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_on_drop() {
+        let deadline = Deadline::default();
+        let shared = deadline.clone();
+        let handle = run(
+            deadline,
+            std::future::pending(),
+            || -> crate::Result<Response<Body>> { unreachable!() },
+        );
+        // The client disconnects while the request is pending
+        assert!(
+            tokio::time::timeout(Duration::from_millis(10), handle)
+                .await
+                .is_err()
+        );
+        assert!(shared.is_expired());
+
+        // The pending blocking work is skipped
+        #[cfg(any(feature = "directory-listing", feature = "archive-preview"))]
+        assert_eq!(
+            super::spawn_blocking(Some(&shared), |_| Ok(())).await,
+            Err(StatusCode::REQUEST_TIMEOUT)
+        );
+    }
+}
@@ -0,0 +1,321 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module providing the live-reload of the browsers during development.
+//!
+//! While any browser listens to the Server-Sent Events endpoint, the root directories are
+//! watched for changes (sharing the watcher of the cache invalidation) and a `reload` event
+//! is sent whenever a file is added, modified or removed. A script reloading the page on those events can be injected into HTML responses.
+//!
+
+use bytes::Bytes;
+use futures_util::stream::{self, StreamExt};
+use headers::{ContentLength, HeaderMapExt, HeaderValue};
+use hyper::header::{ACCEPT_RANGES, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_TYPE};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use std::convert::Infallible;
+#[cfg(feature = "cache-invalidation")]
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::Instant;
+
+use crate::{Error, handler::RequestHandlerOpts, http_ext::MethodExt, zero_copy::ZeroCopyFile};
+
+/// Path of the Server-Sent Events endpoint notifying the file changes.
+pub const DEV_RELOAD_PATH: &str = "/__sws/reload";
+
+/// Script reloading the page on the file changes, injected into HTML responses.
+const RELOAD_SCRIPT: &str = "<script>new EventSource(\"/__sws/reload\").addEventListener(\"reload\",function(){location.reload()});</script>";
+
+/// Time without further changes to wait for before reloading the browsers.
+#[cfg(feature = "cache-invalidation")]
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Time between the checks of the browsers listening to the changes.
+#[cfg(feature = "cache-invalidation")]
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Time between the comments keeping the event streams alive.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Maximum size of the HTML responses the reload script is injected into.
+const MAX_INJECT_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Initializes the live-reload of the browsers.
+pub fn init(enabled: bool, inject: bool, handler_opts: &mut RequestHandlerOpts) {
+    handler_opts.dev_reload = enabled.then(|| DevReload::new(inject));
+    #[cfg(not(feature = "cache-invalidation"))]
+    if enabled {
+        tracing::warn!(
+            "dev reload: file changes are not watched, the `cache-invalidation` feature is required"
+        );
+    }
+    tracing::info!(
+        "dev reload: enabled={enabled}, inject={inject}, endpoint=\"{DEV_RELOAD_PATH}\""
+    );
+}
+
+/// Live-reload of the browsers listening to the file changes.
+#[derive(Clone)]
+pub struct DevReload {
+    /// Whether the reload script is injected into HTML responses.
+    inject: bool,
+    shared: Arc<Shared>,
+}
+
+/// State shared by the event streams and the directories watcher.
+struct Shared {
+    sender: broadcast::Sender<()>,
+    /// Whether the directories are being watched.
+    watching: Mutex<bool>,
+}
+
+impl DevReload {
+    /// Creates the live-reload of the browsers, injecting the reload script if requested.
+    pub fn new(inject: bool) -> Self {
+        let (sender, _) = broadcast::channel(16);
+        Self {
+            inject,
+            shared: Arc::new(Shared {
+                sender,
+                watching: Mutex::new(false),
+            }),
+        }
+    }
+
+    /// Subscribes to the changes of the given directories, watching them if nobody does yet.
+    fn subscribe(&self, dirs: Vec<PathBuf>) -> broadcast::Receiver<()> {
+        let mut watching = self
+            .shared
+            .watching
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let receiver = self.shared.sender.subscribe();
+        if !*watching {
+            *watching = true;
+            tokio::spawn(watch(Arc::downgrade(&self.shared), dirs));
+        }
+        receiver
+    }
+}
+
+/// Watches the directories for changes while there are subscribers, notifying them.
+#[cfg(feature = "cache-invalidation")]
+async fn watch(shared: Weak<Shared>, dirs: Vec<PathBuf>) {
+    let mut subscription = match crate::fs::watch::subscribe(&dirs) {
+        Ok(subscription) => subscription,
+        Err(err) => {
+            tracing::error!("dev reload: {err:?}");
+            stop(&shared);
+            return;
+        }
+    };
+    tracing::debug!("dev reload: watching directories {dirs:?}");
+
+    let mut changed_at: Option<Instant> = None;
+    loop {
+        let wait = changed_at.map_or(CHECK_INTERVAL, |at| DEBOUNCE.saturating_sub(at.elapsed()));
+        match tokio::time::timeout(wait, subscription.recv()).await {
+            Ok(Some(path)) if !is_hidden(&dirs, &path) => changed_at = Some(Instant::now()),
+            Ok(Some(_)) | Err(_) => {}
+            Ok(None) => {
+                stop(&shared);
+                return;
+            }
+        }
+
+        // The watcher stops once the options are replaced (E.g. on configuration reload)
+        let Some(shared) = shared.upgrade() else {
+            return;
+        };
+        let mut watching = shared
+            .watching
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if shared.sender.receiver_count() == 0 {
+            *watching = false;
+            tracing::debug!("dev reload: no browsers listening, watching stopped");
+            return;
+        }
+        if changed_at.is_some_and(|at| at.elapsed() >= DEBOUNCE) {
+            tracing::info!("dev reload: file changes detected, reloading the browsers");
+            let _ = shared.sender.send(());
+            changed_at = None;
+        }
+    }
+}
+
+/// The file changes are not watched without the `cache-invalidation` feature.
+#[cfg(not(feature = "cache-invalidation"))]
+async fn watch(_shared: Weak<Shared>, _dirs: Vec<PathBuf>) {}
+
+/// Marks the directories as no longer watched, so the next subscriber watches them again.
+#[cfg(feature = "cache-invalidation")]
+fn stop(shared: &Weak<Shared>) {
+    if let Some(shared) = shared.upgrade() {
+        *shared
+            .watching
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = false;
+    }
+}
+
+/// Checks if a changed path is hidden (E.g. VCS directories or editor swap files).
+#[cfg(feature = "cache-invalidation")]
+fn is_hidden(dirs: &[PathBuf], path: &Path) -> bool {
+    dirs.iter()
+        .filter_map(|dir| path.strip_prefix(dir).ok())
+        .any(|relative| {
+            relative
+                .components()
+                .any(|component| component.as_os_str().as_encoded_bytes().starts_with(b"."))
+        })
+}
+
+/// Checks if a request targets the live-reload events endpoint.
+pub(crate) fn is_dev_reload_endpoint<T>(opts: &RequestHandlerOpts, req: &Request<T>) -> bool {
+    opts.dev_reload.is_some() && req.uri().path() == DEV_RELOAD_PATH
+}
+
+/// Handles the live-reload events endpoint requests.
+pub(crate) fn pre_process<T>(
+    opts: &RequestHandlerOpts,
+    req: &Request<T>,
+) -> Option<Result<Response<Body>, Error>> {
+    let dev_reload = opts.dev_reload.as_ref()?;
+    if req.uri().path() != DEV_RELOAD_PATH || req.method() != Method::GET {
+        return None;
+    }
+
+    let mut dirs = vec![opts.root_dir.clone()];
+    dirs.extend(opts.overlay_dirs.iter().cloned());
+    let receiver = dev_reload.subscribe(dirs);
+    let heartbeat =
+        tokio::time::interval_at(Instant::now() + HEARTBEAT_INTERVAL, HEARTBEAT_INTERVAL);
+
+    let events = stream::unfold(
+        (receiver, heartbeat),
+        |(mut receiver, mut heartbeat)| async move {
+            let event: &'static [u8] = tokio::select! {
+                received = receiver.recv() => match received {
+                    Ok(()) | Err(RecvError::Lagged(_)) => b"event: reload\ndata: reload\n\n",
+                    // The options were replaced, so the browsers reconnect to the new endpoint
+                    Err(RecvError::Closed) => return None,
+                },
+                _ = heartbeat.tick() => b": heartbeat\n\n",
+            };
+            Some((
+                Ok::<_, Infallible>(Bytes::from_static(event)),
+                (receiver, heartbeat),
+            ))
+        },
+    );
+    let body = stream::once(async { Ok(Bytes::from_static(b"retry: 1000\n\n")) }).chain(events);
+
+    let mut resp = Response::new(Body::wrap_stream(body));
+    resp.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+    resp.headers_mut()
+        .insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    Some(Ok(resp))
+}
+
+/// Injects the reload script into the HTML responses if enabled.
+pub(crate) async fn post_process<T>(
+    opts: &RequestHandlerOpts,
+    req: &Request<T>,
+    mut resp: Response<Body>,
+) -> Result<Response<Body>, Error> {
+    if !opts.dev_reload.as_ref().is_some_and(|d| d.inject) {
+        return Ok(resp);
+    }
+
+    let method = req.method();
+    let status = resp.status();
+    if !(method.is_get() || method.is_head())
+        || !(status == StatusCode::OK || status.is_client_error() || status.is_server_error())
+    {
+        return Ok(resp);
+    }
+
+    // Skip pre-compressed variants since their content can not be modified
+    let headers = resp.headers();
+    let is_html = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.trim().to_ascii_lowercase().starts_with("text/html"));
+    if !is_html || headers.contains_key(CONTENT_ENCODING) {
+        return Ok(resp);
+    }
+    let len = headers.typed_get::<ContentLength>().map(|len| len.0);
+    if len.is_some_and(|len| len > MAX_INJECT_SIZE) {
+        return Ok(resp);
+    }
+
+    // The content is modified so it can't be sent with zero-copy nor by ranges
+    resp.extensions_mut().remove::<ZeroCopyFile>();
+    resp.headers_mut().remove(ACCEPT_RANGES);
+
+    if method.is_head() {
+        if let Some(len) = len {
+            resp.headers_mut()
+                .typed_insert(ContentLength(len + RELOAD_SCRIPT.len() as u64));
+        }
+        return Ok(resp);
+    }
+
+    let (mut head, body) = resp.into_parts();
+    let html = inject_script(&hyper::body::to_bytes(body).await?);
+    head.headers.typed_insert(ContentLength(html.len() as u64));
+    Ok(Response::from_parts(head, Body::from(html)))
+}
+
+/// Inserts the reload script before the closing `body` tag or at the end of an HTML document.
+fn inject_script(html: &[u8]) -> Vec<u8> {
+    let at = html
+        .windows(6)
+        .rposition(|tag| tag.eq_ignore_ascii_case(b"</body"))
+        .unwrap_or(html.len());
+    let mut injected = Vec::with_capacity(html.len() + RELOAD_SCRIPT.len());
+    injected.extend_from_slice(&html[..at]);
+    injected.extend_from_slice(RELOAD_SCRIPT.as_bytes());
+    injected.extend_from_slice(&html[at..]);
+    injected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RELOAD_SCRIPT, inject_script};
+
+    #[test]
+    fn test_inject_script() {
+        let html = inject_script(b"<html><body><p>Hi</p></BODY></html>");
+        assert_eq!(
+            String::from_utf8(html).unwrap(),
+            format!("<html><body><p>Hi</p>{RELOAD_SCRIPT}</BODY></html>")
+        );
+        let html = inject_script(b"<p>Hi</p>");
+        assert_eq!(
+            String::from_utf8(html).unwrap(),
+            format!("<p>Hi</p>{RELOAD_SCRIPT}")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "cache-invalidation")]
+    fn test_is_hidden() {
+        use super::is_hidden;
+        use std::path::{Path, PathBuf};
+
+        let dirs = [PathBuf::from("./public"), PathBuf::from("./overlay")];
+        assert!(is_hidden(&dirs, Path::new("./public/.index.html.swp")));
+        assert!(is_hidden(&dirs, Path::new("./overlay/.git/index")));
+        assert!(!is_hidden(&dirs, Path::new("./public/assets/main.css")));
+        assert!(!is_hidden(&dirs, Path::new("./public")));
+    }
+}
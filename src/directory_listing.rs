@@ -8,11 +8,14 @@
 
 use chrono::{DateTime, Local, Utc};
 use clap::ValueEnum;
+use globset::{GlobBuilder, GlobMatcher};
 use headers::{ContentLength, ContentType, HeaderMapExt};
+use hyper::header::{ACCEPT_LANGUAGE, HeaderValue, VARY};
 use hyper::{Body, Method, Response, StatusCode};
 use mime_guess::mime;
 use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, percent_encode};
 use serde::{Serialize, Serializer};
+use std::cmp::Reverse;
 use std::ffi::{OsStr, OsString};
 use std::io;
 use std::path::Path;
@@ -20,7 +23,18 @@ use std::path::Path;
 #[cfg(feature = "directory-listing-download")]
 use crate::directory_listing_download::{DOWNLOAD_PARAM_KEY, DirDownloadFmt};
 
-use crate::{Context, Result, handler::RequestHandlerOpts, http_ext::MethodExt};
+use crate::{
+    Context, Result, content_type_options,
+    deadline::{self, Deadline},
+    directory_listing_locale::{DirListLocale, SizeUnits},
+    directory_listing_readme::DirReadme,
+    directory_listing_size::DirSizeCache,
+    directory_listing_sort::{DirListSort, SortKey},
+    fs::{symlinks::FollowSymlinks, vfs::VfsDirEntry},
+    handler::RequestHandlerOpts,
+    http_ext::MethodExt,
+    ignore_patterns::IgnorePatterns,
+};
 
 /// Non-alphanumeric characters to be percent-encoded
 /// excluding the "unreserved characters" because allowed in a URI.
@@ -31,7 +45,7 @@ const PERCENT_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
     .remove(b'.')
     .remove(b'~');
 
-#[derive(Debug, Serialize, Deserialize, Clone, ValueEnum)]
+#[derive(Debug, Serialize, Deserialize, Clone, ValueEnum, schemars::JsonSchema)]
 #[serde(rename_all = "lowercase")]
 /// Directory listing output format for file entries.
 pub enum DirListFmt {
@@ -51,32 +65,91 @@ pub struct DirListOpts<'a> {
     pub uri_query: Option<&'a str>,
     /// Request file path.
     pub filepath: &'a Path,
-    /// Directory listing order.
-    pub dir_listing_order: u8,
+    /// Directory listing sorting.
+    pub dir_listing_sort: &'a DirListSort,
     /// Directory listing format.
     pub dir_listing_format: &'a DirListFmt,
+    /// Directory sizes cache if enabled.
+    pub dir_size: Option<&'a DirSizeCache>,
+    /// Show entry details like type, symlink target, mode and owner.
+    pub dir_listing_details: bool,
+    /// Locale of the UI strings.
+    pub locale: &'a DirListLocale,
+    /// Whether the locale was selected via the `Accept-Language` header.
+    pub locale_negotiated: bool,
     #[cfg(feature = "directory-listing-download")]
     /// Directory listing download.
     pub dir_listing_download: &'a [DirDownloadFmt],
     /// Ignore hidden files (dotfiles).
     pub ignore_hidden_files: bool,
+    /// Glob patterns of the entries which are not listed.
+    pub ignore_patterns: Option<&'a IgnorePatterns>,
+    /// Glob patterns of the entries which are not listed but still served.
+    pub hide_patterns: Option<&'a IgnorePatterns>,
+    /// Header and readme files rendered above and below the entries.
+    pub readme: Option<&'a DirReadme>,
+    /// Root directory the ignore patterns are relative to.
+    pub root_dir: &'a Path,
     /// Prevent following symlinks for files and directories.
     pub disable_symlinks: bool,
+    /// Policy of the symlinks followed for the header and readme files.
+    pub follow_symlinks: FollowSymlinks,
+    /// Deadline of the request, the entries stop being read once expired.
+    pub deadline: Option<&'a Deadline>,
 }
 
 /// Initializes directory listings.
-pub fn init(enabled: bool, order: u8, format: DirListFmt, handler_opts: &mut RequestHandlerOpts) {
+pub fn init(
+    enabled: bool,
+    paths: &str,
+    format: DirListFmt,
+    details: bool,
+    handler_opts: &mut RequestHandlerOpts,
+) -> Result {
     handler_opts.dir_listing = enabled;
     tracing::info!("directory listing: enabled={enabled}");
 
-    handler_opts.dir_listing_order = order;
-    tracing::info!("directory listing order code: {order}");
+    handler_opts.dir_listing_paths = parse_paths(paths)?;
+    tracing::info!(
+        "directory listing paths: {}",
+        if paths.trim().is_empty() {
+            "all"
+        } else {
+            paths
+        }
+    );
 
     handler_opts.dir_listing_format = format;
     tracing::info!(
         "directory listing format: {:?}",
         handler_opts.dir_listing_format
     );
+
+    handler_opts.dir_listing_details = details;
+    tracing::info!("directory listing details: enabled={details}");
+    Ok(())
+}
+
+/// Parses a comma-separated list of glob patterns of the request paths with directory listing.
+pub(crate) fn parse_paths(value: &str) -> Result<Vec<GlobMatcher>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|pattern| {
+            Ok(GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .with_context(|| format!("invalid directory listing path glob: {pattern}"))?
+                .compile_matcher())
+        })
+        .collect()
+}
+
+/// Checks if the directory listing applies to a request path,
+/// that is if it matches any of the glob patterns or there are none.
+pub(crate) fn is_path_allowed(paths: &[GlobMatcher], uri_path: &str) -> bool {
+    paths.is_empty() || paths.iter().any(|glob| glob.is_match(uri_path))
 }
 
 /// Provides directory listing support for the current request.
@@ -91,28 +164,21 @@ pub fn auto_index(opts: DirListOpts<'_>) -> Result<Response<Body>, StatusCode> {
     let filepath = opts.filepath;
     let parent = filepath.parent().unwrap_or(filepath);
 
+    let mut entry_opts = DirEntryOpts::from(&opts);
+    if let (Some(readme), DirListFmt::Html) = (opts.readme, opts.dir_listing_format) {
+        entry_opts.header = readme.header_html(parent, opts.root_dir, opts.follow_symlinks);
+        entry_opts.readme = readme.readme_html(parent, opts.root_dir, opts.follow_symlinks);
+    }
+
     match std::fs::read_dir(parent) {
-        Ok(dir_reader) => {
-            let dir_opts = DirEntryOpts {
-                dir_reader,
-                base_path: opts.current_path,
-                uri_query: opts.uri_query,
-                is_head: opts.method.is_head(),
-                order_code: opts.dir_listing_order,
-                content_format: opts.dir_listing_format,
-                ignore_hidden_files: opts.ignore_hidden_files,
-                disable_symlinks: opts.disable_symlinks,
-                #[cfg(feature = "directory-listing-download")]
-                download: opts.dir_listing_download,
-            };
-            match read_dir_entries(dir_opts) {
-                Ok(resp) => Ok(resp),
-                Err(err) => {
-                    tracing::error!("error after try to read directory entries: {:?}", err);
-                    Err(StatusCode::INTERNAL_SERVER_ERROR)
-                }
+        Ok(dir_reader) => match read_dir_entries(dir_reader, entry_opts) {
+            Ok(resp) => Ok(resp),
+            Err(err) => {
+                deadline::check(opts.deadline)?;
+                tracing::error!("error after try to read directory entries: {:?}", err);
+                Err(StatusCode::INTERNAL_SERVER_ERROR)
             }
-        }
+        },
         Err(err) => {
             let status = match err.kind() {
                 io::ErrorKind::NotFound => {
@@ -145,8 +211,67 @@ pub fn auto_index(opts: DirListOpts<'_>) -> Result<Response<Body>, StatusCode> {
     }
 }
 
+/// Lists the entries of a directory of a virtual file system (E.g. the embedded assets).
+///
+/// Unlike [`auto_index`], the `filepath` option is the directory path itself,
+/// relative to the root of the file system, and the `root_dir` option is an empty path.
+pub(crate) fn vfs_auto_index(
+    opts: DirListOpts<'_>,
+    entries: Vec<VfsDirEntry>,
+) -> Result<Response<Body>, StatusCode> {
+    let mut dirs_count: usize = 0;
+    let mut files_count: usize = 0;
+    let mut file_entries: Vec<FileEntry> = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        // Check and ignore the current hidden file/directory (dotfile) if feature enabled
+        if opts.ignore_hidden_files && entry.name.starts_with('.') {
+            continue;
+        }
+
+        // Check and ignore the current entry if it matches the ignore or hide patterns
+        let path = opts.filepath.join(&entry.name);
+        if [opts.ignore_patterns, opts.hide_patterns]
+            .into_iter()
+            .flatten()
+            .any(|patterns| patterns.is_ignored_in(opts.root_dir, &path))
+        {
+            continue;
+        }
+
+        let is_dir = entry.metadata.is_dir;
+        let (r#type, size) = if is_dir {
+            dirs_count += 1;
+            (FileType::Directory, None)
+        } else {
+            files_count += 1;
+            (FileType::File, Some(entry.metadata.len))
+        };
+
+        let name = OsString::from(entry.name);
+        file_entries.push(FileEntry {
+            uri: entry_uri(opts.current_path, &name, is_dir),
+            name,
+            mtime: entry.metadata.modified.map(DateTime::<Local>::from),
+            size,
+            r#type,
+            details: None,
+        });
+    }
+
+    render_entries(
+        DirEntryOpts::from(&opts),
+        dirs_count,
+        files_count,
+        file_entries,
+    )
+    .map_err(|err| {
+        tracing::error!("error after try to list the directory entries: {:?}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
 const DATETIME_FORMAT_UTC: &str = "%FT%TZ";
-const DATETIME_FORMAT_LOCAL: &str = "%F %T";
 
 #[derive(Serialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -167,6 +292,8 @@ struct FileEntry {
     r#type: FileType,
     #[serde(skip_serializing)]
     uri: String,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    details: Option<EntryDetails>,
 }
 
 impl FileEntry {
@@ -175,35 +302,125 @@ impl FileEntry {
     }
 }
 
-/// Defines sorting attributes for file entries.
-struct SortingAttr<'a> {
-    name: &'a str,
-    last_modified: &'a str,
-    size: &'a str,
+/// Defines additional file entry details for admin-oriented listings.
+#[derive(Serialize, Default)]
+struct EntryDetails {
+    /// Symlink target path if the entry is a symlink.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
+    /// Unix file mode in `ls -l` format. E.g. `-rw-r--r--`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mode: Option<String>,
+    /// Unix user ID of the owner.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uid: Option<u32>,
+    /// Unix group ID of the owner.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gid: Option<u32>,
+}
+
+impl EntryDetails {
+    /// Gets the details of an entry from its metadata (without following symlinks).
+    fn new(path: &Path, meta: &std::fs::Metadata) -> Self {
+        let target = if meta.file_type().is_symlink() {
+            std::fs::read_link(path)
+                .ok()
+                .map(|p| p.to_string_lossy().into_owned())
+        } else {
+            None
+        };
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            Self {
+                target,
+                mode: Some(format_mode(meta.mode())),
+                uid: Some(meta.uid()),
+                gid: Some(meta.gid()),
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            Self {
+                target,
+                ..Default::default()
+            }
+        }
+    }
+
+    /// Gets the entry type label. E.g. `dir`, `file` or `symlink`
+    fn type_label(&self, is_dir: bool) -> &'static str {
+        match (self.target.is_some(), is_dir) {
+            (true, _) => "symlink",
+            (false, true) => "dir",
+            (false, false) => "file",
+        }
+    }
 }
 
 /// Defines read directory entries.
 struct DirEntryOpts<'a> {
-    dir_reader: std::fs::ReadDir,
     base_path: &'a str,
-    uri_query: Option<&'a str>,
     is_head: bool,
-    order_code: u8,
+    sort: DirListSort,
+    default_sort: &'a DirListSort,
     content_format: &'a DirListFmt,
+    dir_size: Option<&'a DirSizeCache>,
+    details: bool,
+    locale: &'a DirListLocale,
+    locale_negotiated: bool,
     ignore_hidden_files: bool,
+    ignore_patterns: Option<&'a IgnorePatterns>,
+    hide_patterns: Option<&'a IgnorePatterns>,
+    root_dir: &'a Path,
     disable_symlinks: bool,
     #[cfg(feature = "directory-listing-download")]
     download: &'a [DirDownloadFmt],
+    deadline: Option<&'a Deadline>,
+    /// Rendered HTML of the header file shown above the entries.
+    header: Option<String>,
+    /// Rendered HTML of the readme file shown below the entries.
+    readme: Option<String>,
+}
+
+impl<'a> From<&DirListOpts<'a>> for DirEntryOpts<'a> {
+    fn from(opts: &DirListOpts<'a>) -> Self {
+        Self {
+            base_path: opts.current_path,
+            is_head: opts.method.is_head(),
+            sort: opts.dir_listing_sort.with_query(opts.uri_query),
+            default_sort: opts.dir_listing_sort,
+            content_format: opts.dir_listing_format,
+            dir_size: opts.dir_size,
+            details: opts.dir_listing_details,
+            locale: opts.locale,
+            locale_negotiated: opts.locale_negotiated,
+            ignore_hidden_files: opts.ignore_hidden_files,
+            ignore_patterns: opts.ignore_patterns,
+            hide_patterns: opts.hide_patterns,
+            root_dir: opts.root_dir,
+            disable_symlinks: opts.disable_symlinks,
+            #[cfg(feature = "directory-listing-download")]
+            download: opts.dir_listing_download,
+            deadline: opts.deadline,
+            header: None,
+            readme: None,
+        }
+    }
 }
 
 /// It reads a list of directory entries and create an index page content.
 /// Otherwise it returns a status error.
-fn read_dir_entries(mut opt: DirEntryOpts<'_>) -> Result<Response<Body>> {
+fn read_dir_entries(dir_reader: std::fs::ReadDir, opt: DirEntryOpts<'_>) -> Result<Response<Body>> {
     let mut dirs_count: usize = 0;
     let mut files_count: usize = 0;
     let mut file_entries: Vec<FileEntry> = vec![];
 
-    for dir_entry in opt.dir_reader {
+    for dir_entry in dir_reader {
+        if deadline::check(opt.deadline).is_err() {
+            bail!("reading of the directory entries abandoned");
+        }
         let dir_entry = dir_entry.with_context(|| "unable to read directory entry")?;
         let meta = match dir_entry.metadata() {
             Ok(m) => m,
@@ -223,9 +440,19 @@ fn read_dir_entries(mut opt: DirEntryOpts<'_>) -> Result<Response<Body>> {
             continue;
         }
 
+        // Check and ignore the current entry if it matches the ignore or hide patterns
+        let path = dir_entry.path();
+        if [opt.ignore_patterns, opt.hide_patterns]
+            .into_iter()
+            .flatten()
+            .any(|patterns| patterns.is_ignored_in(opt.root_dir, &path))
+        {
+            continue;
+        }
+
         let (r#type, size) = if meta.is_dir() {
             dirs_count += 1;
-            (FileType::Directory, None)
+            (FileType::Directory, opt.dir_size.and_then(|c| c.get(&path)))
         } else if meta.is_file() {
             files_count += 1;
             (FileType::File, Some(meta.len()))
@@ -259,7 +486,10 @@ fn read_dir_entries(mut opt: DirEntryOpts<'_>) -> Result<Response<Body>> {
             };
             if symlink_meta.is_dir() {
                 dirs_count += 1;
-                (FileType::Directory, None)
+                (
+                    FileType::Directory,
+                    opt.dir_size.and_then(|c| c.get(&symlink)),
+                )
             } else {
                 files_count += 1;
                 (FileType::File, Some(symlink_meta.len()))
@@ -268,31 +498,9 @@ fn read_dir_entries(mut opt: DirEntryOpts<'_>) -> Result<Response<Body>> {
             continue;
         };
 
-        let name_encoded = percent_encode(name.as_encoded_bytes(), PERCENT_ENCODE_SET).to_string();
-
-        // NOTE: Use relative paths by default independently of
-        // the "redirect trailing slash" feature.
-        // However, when "redirect trailing slash" is disabled
-        // and a request path doesn't contain a trailing slash then
-        // entries should contain the "parent/entry-name" as a link format.
-        // Otherwise, we just use the "entry-name" as a link (default behavior).
-        // Note that in both cases, we add a trailing slash if the entry is a directory.
-        let mut uri = if !opt.base_path.ends_with('/') && !opt.base_path.is_empty() {
-            let parent = opt
-                .base_path
-                .rsplit_once('/')
-                .map(|(_, parent)| parent)
-                .unwrap_or(opt.base_path);
-            format!("{parent}/{name_encoded}")
-        } else {
-            name_encoded
-        };
-
-        if r#type == FileType::Directory {
-            uri.push('/');
-        }
-
+        let uri = entry_uri(opt.base_path, &name, r#type == FileType::Directory);
         let mtime = meta.modified().ok().map(DateTime::<Local>::from);
+        let details = opt.details.then(|| EntryDetails::new(&path, &meta));
 
         let entry = FileEntry {
             name,
@@ -300,32 +508,50 @@ fn read_dir_entries(mut opt: DirEntryOpts<'_>) -> Result<Response<Body>> {
             size,
             r#type,
             uri,
+            details,
         };
         file_entries.push(entry);
     }
 
-    // Check the query request uri for a sorting type. E.g https://blah/?sort=5
-    if let Some(q) = opt.uri_query {
-        let mut parts = form_urlencoded::parse(q.as_bytes());
-        if parts.count() > 0 {
-            // NOTE: we just pick up the first value (pair)
-            if let Some(sort) = parts.next() {
-                if sort.0 == "sort" && !sort.1.trim().is_empty() {
-                    match sort.1.parse::<u8>() {
-                        Ok(code) => opt.order_code = code,
-                        Err(err) => {
-                            tracing::error!(
-                                "sorting: query value error when converting to u8: {:?}",
-                                err
-                            );
-                        }
-                    }
-                }
-            }
-        }
+    render_entries(opt, dirs_count, files_count, file_entries)
+}
+
+/// Returns the relative link of a directory entry.
+fn entry_uri(base_path: &str, name: &OsStr, is_dir: bool) -> String {
+    let name_encoded = percent_encode(name.as_encoded_bytes(), PERCENT_ENCODE_SET).to_string();
+
+    // NOTE: Use relative paths by default independently of
+    // the "redirect trailing slash" feature.
+    // However, when "redirect trailing slash" is disabled
+    // and a request path doesn't contain a trailing slash then
+    // entries should contain the "parent/entry-name" as a link format.
+    // Otherwise, we just use the "entry-name" as a link (default behavior).
+    // Note that in both cases, we add a trailing slash if the entry is a directory.
+    let mut uri = if !base_path.ends_with('/') && !base_path.is_empty() {
+        let parent = base_path
+            .rsplit_once('/')
+            .map(|(_, parent)| parent)
+            .unwrap_or(base_path);
+        format!("{parent}/{name_encoded}")
+    } else {
+        name_encoded
+    };
+
+    if is_dir {
+        uri.push('/');
     }
+    uri
+}
 
+/// It creates the index page content of the given directory entries.
+fn render_entries(
+    opt: DirEntryOpts<'_>,
+    dirs_count: usize,
+    files_count: usize,
+    mut file_entries: Vec<FileEntry>,
+) -> Result<Response<Body>> {
     let mut resp = Response::new(Body::empty());
+    content_type_options::append_generated(&mut resp);
 
     // Handle directory listing content format
     let content = match opt.content_format {
@@ -334,22 +560,20 @@ fn read_dir_entries(mut opt: DirEntryOpts<'_>) -> Result<Response<Body>> {
             resp.headers_mut()
                 .typed_insert(ContentType::from(mime::APPLICATION_JSON));
 
-            json_auto_index(&mut file_entries, opt.order_code)?
+            json_auto_index(&mut file_entries, &opt.sort)?
         }
         // HTML (default)
         _ => {
             resp.headers_mut()
                 .typed_insert(ContentType::from(mime::TEXT_HTML_UTF_8));
 
-            html_auto_index(
-                opt.base_path,
-                dirs_count,
-                files_count,
-                &mut file_entries,
-                opt.order_code,
-                #[cfg(feature = "directory-listing-download")]
-                opt.download,
-            )
+            // The UI strings vary if the locale is selected via the `Accept-Language` header
+            if opt.locale_negotiated {
+                resp.headers_mut()
+                    .insert(VARY, HeaderValue::from_name(ACCEPT_LANGUAGE));
+            }
+
+            html_auto_index(&opt, dirs_count, files_count, &mut file_entries)
         }
     };
 
@@ -367,8 +591,8 @@ fn read_dir_entries(mut opt: DirEntryOpts<'_>) -> Result<Response<Body>> {
 }
 
 /// Create an auto index in JSON format.
-fn json_auto_index(entries: &mut [FileEntry], order_code: u8) -> Result<String> {
-    sort_file_entries(entries, order_code);
+fn json_auto_index(entries: &mut [FileEntry], sort: &DirListSort) -> Result<String> {
+    sort_file_entries(entries, sort);
 
     Ok(serde_json::to_string(entries)?)
 }
@@ -394,25 +618,25 @@ fn serialize_mtime<S: Serializer>(
 }
 
 /// Create an auto index in HTML format.
-fn html_auto_index<'a>(
-    base_path: &'a str,
+fn html_auto_index(
+    opt: &DirEntryOpts<'_>,
     dirs_count: usize,
     files_count: usize,
-    entries: &'a mut [FileEntry],
-    order_code: u8,
-    #[cfg(feature = "directory-listing-download")] download: &'a [DirDownloadFmt],
+    entries: &mut [FileEntry],
 ) -> String {
-    use maud::{DOCTYPE, html};
+    use maud::{DOCTYPE, PreEscaped, html};
 
-    let sort_attrs = sort_file_entries(entries, order_code);
+    let (base_path, details, locale) = (opt.base_path, opt.details, opt.locale);
+    sort_file_entries(entries, &opt.sort);
+    let sort_link = |key| opt.sort.link(key, opt.default_sort);
     let current_path = percent_decode_str(base_path).decode_utf8_lossy();
 
     #[cfg(feature = "directory-listing-download")]
-    let download_directory_elem = match download.is_empty() {
+    let download_directory_elem = match opt.download.is_empty() {
         true => html! {},
         false => html! {
             ", " a href={ "?" (DOWNLOAD_PARAM_KEY) } {
-                "download tar.gz"
+                (locale.download) " tar.gz"
             }
         },
     };
@@ -421,12 +645,12 @@ fn html_auto_index<'a>(
 
     html! {
         (DOCTYPE)
-        html {
+        html lang=(locale.lang) {
             head {
                 meta charset="utf-8";
                 meta name="viewport" content="width=device-width,minimum-scale=1,initial-scale=1";
                 title {
-                    "Index of " (current_path)
+                    (locale.title) " " (current_path)
                 }
                 style {
                     "html{background-color:#fff;-moz-osx-font-smoothing:grayscale;-webkit-font-smoothing:antialiased;min-width:20rem;text-rendering:optimizeLegibility;-webkit-text-size-adjust:100%;-moz-text-size-adjust:100%;text-size-adjust:100%}:after,:before{box-sizing:border-box;}body{padding:1rem;font-family:Consolas,'Liberation Mono',Menlo,monospace;font-size:.75rem;max-width:70rem;margin:0 auto;color:#4a4a4a;font-weight:400;line-height:1.5}h1{margin:0;padding:0;font-size:1rem;line-height:1.25;margin-bottom:0.5rem;}table{width:100%;table-layout:fixed;border-spacing: 0;}hr{border-style: none;border-bottom: solid 1px gray;}table th,table td{padding:.15rem 0;white-space:nowrap;vertical-align:top}table th a,table td a{display:inline-block;white-space:nowrap;overflow:hidden;text-overflow:ellipsis;max-width:95%;vertical-align:top;}table tr:hover td{background-color:#f5f5f5}footer{padding-top:0.5rem}table tr th{text-align:left;}@media (max-width:30rem){table th:first-child{width:20rem;}}"
@@ -434,41 +658,49 @@ fn html_auto_index<'a>(
             }
             body {
                 h1 {
-                    "Index of " (current_path)
+                    (locale.title) " " (current_path)
                 }
                 p {
                     small {
-                        "directories: " (dirs_count) ", files: " (files_count) (download_directory_elem)
+                        (locale.directories) ": " (dirs_count) ", " (locale.files) ": " (files_count) (download_directory_elem)
                     }
                 }
+                @if let Some(header) = &opt.header {
+                    div class="header" { (PreEscaped(header)) }
+                }
                 hr;
                 div style="overflow-x: auto;" {
                     table {
                         thead {
                             tr {
                                 th {
-                                    a href={ "?sort=" (sort_attrs.name) } {
-                                        "Name"
+                                    a href=(sort_link(SortKey::Name)) {
+                                        (locale.name)
                                     }
                                 }
                                 th style="width:10rem;" {
-                                    a href={ "?sort=" (sort_attrs.last_modified) } {
-                                        "Last modified"
+                                    a href=(sort_link(SortKey::Mtime)) {
+                                        (locale.last_modified)
                                     }
                                 }
                                 th style="width:6rem;text-align:right;" {
-                                    a href={ "?sort=" (sort_attrs.size) } {
-                                        "Size"
+                                    a href=(sort_link(SortKey::Size)) {
+                                        (locale.size)
                                     }
                                 }
+                                @if details {
+                                    th style="width:5rem;padding-left:1rem;" { (locale.r#type) }
+                                    th style="width:7rem;" { (locale.mode) }
+                                    th style="width:6rem;" { (locale.owner) }
+                                }
                             }
                         }
 
                         @if base_path != "/" {
                             tr {
-                                td colspan="3" {
+                                td colspan=(if details { "6" } else { "3" }) {
                                     a href="../" {
-                                        "../"
+                                        (locale.parent)
                                     }
                                 }
                             }
@@ -483,19 +715,36 @@ fn html_auto_index<'a>(
                                             "/"
                                         }
                                     }
+                                    @if let Some(target) = entry.details.as_ref().and_then(|d| d.target.as_ref()) {
+                                        " -> " (target)
+                                    }
                                 }
                                 td {
                                     (entry.mtime.map_or("-".to_owned(), |local_dt| {
-                                        local_dt.format(DATETIME_FORMAT_LOCAL).to_string()
+                                        local_dt.format(&locale.date_format).to_string()
                                     }))
                                 }
                                 td align="right" {
-                                    (entry.size.map(format_file_size).unwrap_or("-".into()))
+                                    (entry.size.map_or("-".into(), |size| format_file_size(size, locale.size_units)))
+                                }
+                                @if let Some(d) = &entry.details {
+                                    td style="padding-left:1rem;" { (d.type_label(entry.is_dir())) }
+                                    td { (d.mode.as_deref().unwrap_or("-")) }
+                                    td {
+                                        @match (d.uid, d.gid) {
+                                            (Some(uid), Some(gid)) => { (uid) ":" (gid) }
+                                            _ => { "-" }
+                                        }
+                                    }
                                 }
                             }
                         }
                     }
                 }
+                @if let Some(readme) = &opt.readme {
+                    hr;
+                    div class="readme" { (PreEscaped(readme)) }
+                }
                 hr;
                 footer {
                     small {
@@ -507,118 +756,163 @@ fn html_auto_index<'a>(
     }.into()
 }
 
-/// Sort a list of file entries by a specific order code.
-fn sort_file_entries(files: &mut [FileEntry], order_code: u8) -> SortingAttr<'_> {
-    // Default sorting type values
-    let mut name = "0";
-    let mut last_modified = "2";
-    let mut size = "4";
-
-    match order_code {
-        0 | 1 => {
-            // Name (asc, desc)
-            files.sort_by_cached_key(|f| f.name.to_string_lossy().to_lowercase());
-            if order_code == 1 {
-                files.reverse();
-            } else {
-                name = "1";
+/// Sorts a list of file entries by the given sort keys, the first key having the highest priority.
+fn sort_file_entries(files: &mut [FileEntry], sort: &DirListSort) {
+    // NOTE: the entries are sorted by every key from the lowest priority one using stable sorts,
+    // so the ties of a key keep the order of the next ones
+    for order in sort.keys().iter().rev() {
+        match (order.key, order.desc) {
+            (SortKey::Name, false) => {
+                files.sort_by_cached_key(|f| f.name.to_string_lossy().to_lowercase())
             }
-        }
-        2 | 3 => {
-            // Modified (asc, desc)
-            files.sort_by_key(|f| f.mtime);
-            if order_code == 3 {
-                files.reverse();
-            } else {
-                last_modified = "3";
+            (SortKey::Name, true) => {
+                files.sort_by_cached_key(|f| Reverse(f.name.to_string_lossy().to_lowercase()))
             }
-        }
-        4 | 5 => {
-            // File size (asc, desc)
-            files.sort_by_key(|f| f.size);
-            if order_code == 5 {
-                files.reverse();
-            } else {
-                size = "5";
-            }
-        }
-        _ => {
-            // Unsorted
+            (SortKey::Mtime, false) => files.sort_by_key(|f| f.mtime),
+            (SortKey::Mtime, true) => files.sort_by_key(|f| Reverse(f.mtime)),
+            (SortKey::Size, false) => files.sort_by_key(|f| f.size),
+            (SortKey::Size, true) => files.sort_by_key(|f| Reverse(f.size)),
         }
     }
-
-    SortingAttr {
-        name,
-        last_modified,
-        size,
+    if sort.is_dirs_first() {
+        files.sort_by_key(|f| !f.is_dir());
     }
 }
 
-/// Formats the file size in bytes to a human-readable string
-fn format_file_size(size: u64) -> String {
-    const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+/// Formats a Unix file mode in `ls -l` format. E.g. `drwxr-xr-x`
+#[cfg(unix)]
+fn format_mode(mode: u32) -> String {
+    const S_IFMT: u32 = 0o170000;
+    let kind = match mode & S_IFMT {
+        0o040000 => 'd',
+        0o120000 => 'l',
+        0o100000 => '-',
+        _ => '?',
+    };
+    let perms = ['r', 'w', 'x'];
+    std::iter::once(kind)
+        .chain((0..9).map(|i| {
+            if mode & (1 << (8 - i)) != 0 {
+                perms[i % 3]
+            } else {
+                '-'
+            }
+        }))
+        .collect()
+}
+
+/// Formats the file size in bytes to a human-readable string using binary or decimal units
+fn format_file_size(size: u64, units: SizeUnits) -> String {
+    let (base, units) = match units {
+        SizeUnits::Binary => (1024, ["B", "KiB", "MiB", "GiB", "TiB", "PiB"]),
+        SizeUnits::Decimal => (1000, ["B", "kB", "MB", "GB", "TB", "PB"]),
+    };
     let mut size_tmp = size;
 
-    if size_tmp < 1024 {
+    if size_tmp < base {
         // return the size with Byte
-        return format!("{} {}", size_tmp, UNITS[0]);
+        return format!("{} {}", size_tmp, units[0]);
     }
 
-    for unit in &UNITS[1..UNITS.len() - 1] {
-        if size_tmp < 1024 * 1024 {
-            // return the size divided by 1024 with the unit
-            return format!("{:.2} {}", size_tmp as f64 / 1024.0, unit);
+    for unit in &units[1..units.len() - 1] {
+        if size_tmp < base * base {
+            // return the size divided by the base with the unit
+            return format!("{:.2} {}", size_tmp as f64 / base as f64, unit);
         }
-        size_tmp >>= 10;
+        size_tmp /= base;
     }
 
     // if size is too large, return the largest unit
-    format!("{:.2} {}", size_tmp as f64 / 1024.0, UNITS[UNITS.len() - 1])
+    format!(
+        "{:.2} {}",
+        size_tmp as f64 / base as f64,
+        units[units.len() - 1]
+    )
 }
 
 #[cfg(test)]
 mod tests {
-    use super::format_file_size;
+    use super::{format_file_size, is_path_allowed, parse_paths};
+    use crate::directory_listing_locale::SizeUnits;
+
+    #[test]
+    fn test_is_path_allowed() {
+        assert!(is_path_allowed(&parse_paths(" ").unwrap(), "/private/"));
+
+        let paths = parse_paths("/downloads/**, /pub/*/").unwrap();
+        assert!(is_path_allowed(&paths, "/downloads/"));
+        assert!(is_path_allowed(&paths, "/downloads/archive/2024/"));
+        assert!(is_path_allowed(&paths, "/pub/docs/"));
+        assert!(!is_path_allowed(&paths, "/pub/docs/old/"));
+        assert!(!is_path_allowed(&paths, "/"));
+        assert!(!is_path_allowed(&paths, "/private/"));
+
+        assert!(parse_paths("/downloads/[").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn handle_mode() {
+        use super::format_mode;
+        assert_eq!(format_mode(0o100644), "-rw-r--r--");
+        assert_eq!(format_mode(0o040755), "drwxr-xr-x");
+        assert_eq!(format_mode(0o120777), "lrwxrwxrwx");
+    }
 
     #[test]
     fn handle_byte() {
         let size = 128;
-        assert_eq!("128 B", format_file_size(size))
+        assert_eq!("128 B", format_file_size(size, SizeUnits::Binary))
     }
 
     #[test]
     fn handle_kibibyte() {
         let size = 1024;
-        assert_eq!("1.00 KiB", format_file_size(size))
+        assert_eq!("1.00 KiB", format_file_size(size, SizeUnits::Binary))
     }
 
     #[test]
     fn handle_mebibyte() {
         let size = 1048576;
-        assert_eq!("1.00 MiB", format_file_size(size))
+        assert_eq!("1.00 MiB", format_file_size(size, SizeUnits::Binary))
     }
 
     #[test]
     fn handle_gibibyte() {
         let size = 1073741824;
-        assert_eq!("1.00 GiB", format_file_size(size))
+        assert_eq!("1.00 GiB", format_file_size(size, SizeUnits::Binary))
     }
 
     #[test]
     fn handle_tebibyte() {
         let size = 1099511627776;
-        assert_eq!("1.00 TiB", format_file_size(size))
+        assert_eq!("1.00 TiB", format_file_size(size, SizeUnits::Binary))
     }
 
     #[test]
     fn handle_pebibyte() {
         let size = 1125899906842624;
-        assert_eq!("1.00 PiB", format_file_size(size))
+        assert_eq!("1.00 PiB", format_file_size(size, SizeUnits::Binary))
     }
 
     #[test]
     fn handle_large() {
         let size = u64::MAX;
-        assert_eq!("16384.00 PiB", format_file_size(size))
+        assert_eq!("16384.00 PiB", format_file_size(size, SizeUnits::Binary))
+    }
+
+    #[test]
+    fn handle_decimal() {
+        assert_eq!("999 B", format_file_size(999, SizeUnits::Decimal));
+        assert_eq!("1.00 kB", format_file_size(1000, SizeUnits::Decimal));
+        assert_eq!("1.50 MB", format_file_size(1_500_000, SizeUnits::Decimal));
+        assert_eq!(
+            "2.00 GB",
+            format_file_size(2_000_000_000, SizeUnits::Decimal)
+        );
+        assert_eq!(
+            "18446.74 PB",
+            format_file_size(u64::MAX, SizeUnits::Decimal)
+        )
     }
 }
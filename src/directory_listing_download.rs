@@ -27,12 +27,15 @@ use tokio_util::compat::TokioAsyncWriteCompatExt;
 use crate::Result;
 use crate::handler::RequestHandlerOpts;
 use crate::http_ext::MethodExt;
+use crate::ignore_patterns::IgnorePatterns;
 
 /// query parameter key to download directory as tar.gz
 pub const DOWNLOAD_PARAM_KEY: &str = "download";
 
 /// Download format for directory
-#[derive(Debug, Serialize, Deserialize, Clone, ValueEnum, Eq, Hash, PartialEq)]
+#[derive(
+    Debug, Serialize, Deserialize, Clone, ValueEnum, Eq, Hash, PartialEq, schemars::JsonSchema,
+)]
 #[serde(rename_all = "lowercase")]
 pub enum DirDownloadFmt {
     /// Gunzip-compressed tarball (.tar.gz)
@@ -53,6 +56,10 @@ pub struct DirDownloadOpts<'a> {
     pub disable_symlinks: bool,
     /// Ignore hidden files (dotfiles).
     pub ignore_hidden_files: bool,
+    /// Glob patterns of the files and directories which are not archived.
+    pub ignore_patterns: Option<&'a IgnorePatterns>,
+    /// Root directory the ignore patterns are relative to.
+    pub root_dir: &'a Path,
 }
 
 /// Initializes directory listing download
@@ -116,6 +123,7 @@ async fn archive(
     cb: ChannelBuffer,
     follow_symlinks: bool,
     ignore_hidden: bool,
+    ignore_patterns: Option<(PathBuf, IgnorePatterns)>,
 ) -> Result {
     let gz = GzipEncoder::with_quality(cb, async_compression::Level::Default);
     let mut a = Builder::new(gz.compat_write());
@@ -141,8 +149,17 @@ async fn archive(
                     continue;
                 }
 
+                // Check and ignore the current entry if it matches the ignore patterns
+                let entry_path = entry.path();
+                if ignore_patterns
+                    .as_ref()
+                    .is_some_and(|(root, patterns)| patterns.is_ignored_in(root, &entry_path))
+                {
+                    continue;
+                }
+
                 let file_type = entry.file_type().await?;
-                stack.push((entry.path(), file_type.is_dir(), file_type.is_symlink()));
+                stack.push((entry_path, file_type.is_dir(), file_type.is_symlink()));
             }
             if dest != Path::new("") {
                 a.append_dir(&dest, &src).await?;
@@ -205,6 +222,8 @@ where
         ChannelBuffer { s: tx },
         !opts.disable_symlinks,
         opts.ignore_hidden_files,
+        opts.ignore_patterns
+            .map(|patterns| (opts.root_dir.to_owned(), patterns.clone())),
     ));
     *resp.body_mut() = body;
 
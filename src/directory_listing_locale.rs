@@ -0,0 +1,442 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! It provides the localized UI strings of the directory listings.
+//!
+//! A locale contains the strings, the date format and the size units of the HTML listings.
+//! Built-in locales can be customized and new ones added via the configuration file.
+//! The locale is selected per virtual host or via the `Accept-Language` request header.
+//!
+
+use chrono::format::{Item, StrftimeItems};
+use headers::{HeaderMap, HeaderMapExt};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::headers_ext::AcceptLanguage;
+use crate::settings::file::DirListingLocale;
+use crate::{Result, handler::RequestHandlerOpts};
+
+/// Locale value selecting the locale via the `Accept-Language` request header.
+pub const AUTO_LOCALE: &str = "auto";
+
+/// Locale used by default or if none of the accepted languages is available.
+pub const DEFAULT_LOCALE: &str = "en";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+/// Units of the file sizes shown in directory listings.
+pub enum SizeUnits {
+    /// Powers of 1024 (E.g. `KiB`, `MiB`).
+    Binary,
+    /// Powers of 1000 (E.g. `kB`, `MB`).
+    Decimal,
+}
+
+/// UI strings and formats of a directory listing locale.
+#[derive(Debug, Clone)]
+pub struct DirListLocale {
+    /// Language tag. E.g. `en`
+    pub lang: Cow<'static, str>,
+    /// Title prefix. E.g. `Index of`
+    pub title: Cow<'static, str>,
+    /// Directories count label.
+    pub directories: Cow<'static, str>,
+    /// Files count label.
+    pub files: Cow<'static, str>,
+    /// Directory download link label.
+    pub download: Cow<'static, str>,
+    /// Name column title.
+    pub name: Cow<'static, str>,
+    /// Last modification column title.
+    pub last_modified: Cow<'static, str>,
+    /// Size column title.
+    pub size: Cow<'static, str>,
+    /// Entry type column title.
+    pub r#type: Cow<'static, str>,
+    /// File mode column title.
+    pub mode: Cow<'static, str>,
+    /// Owner column title.
+    pub owner: Cow<'static, str>,
+    /// Parent directory link label.
+    pub parent: Cow<'static, str>,
+    /// Date format of the last modification times using the `strftime` syntax.
+    pub date_format: Cow<'static, str>,
+    /// Units of the file sizes.
+    pub size_units: SizeUnits,
+}
+
+impl Default for DirListLocale {
+    fn default() -> Self {
+        Self::english().clone()
+    }
+}
+
+impl DirListLocale {
+    /// Gets the built-in English locale used by default.
+    pub fn english() -> &'static Self {
+        &BUILTIN_LOCALES[0]
+    }
+}
+
+/// Creates a built-in locale from its strings in the order of the `DirListLocale` fields.
+const fn builtin(
+    lang: &'static str,
+    strings: [&'static str; 10],
+    date_format: &'static str,
+) -> DirListLocale {
+    let [
+        title,
+        directories,
+        files,
+        download,
+        name,
+        last_modified,
+        size,
+        r#type,
+        mode,
+        owner,
+    ] = strings;
+    DirListLocale {
+        lang: Cow::Borrowed(lang),
+        title: Cow::Borrowed(title),
+        directories: Cow::Borrowed(directories),
+        files: Cow::Borrowed(files),
+        download: Cow::Borrowed(download),
+        name: Cow::Borrowed(name),
+        last_modified: Cow::Borrowed(last_modified),
+        size: Cow::Borrowed(size),
+        r#type: Cow::Borrowed(r#type),
+        mode: Cow::Borrowed(mode),
+        owner: Cow::Borrowed(owner),
+        parent: Cow::Borrowed("../"),
+        date_format: Cow::Borrowed(date_format),
+        size_units: SizeUnits::Binary,
+    }
+}
+
+/// Built-in locales, English being the default one.
+static BUILTIN_LOCALES: [DirListLocale; 5] = [
+    builtin(
+        "en",
+        [
+            "Index of",
+            "directories",
+            "files",
+            "download",
+            "Name",
+            "Last modified",
+            "Size",
+            "Type",
+            "Mode",
+            "Owner",
+        ],
+        "%F %T",
+    ),
+    builtin(
+        "es",
+        [
+            "Índice de",
+            "directorios",
+            "archivos",
+            "descargar",
+            "Nombre",
+            "Última modificación",
+            "Tamaño",
+            "Tipo",
+            "Modo",
+            "Propietario",
+        ],
+        "%d/%m/%Y %T",
+    ),
+    builtin(
+        "de",
+        [
+            "Index von",
+            "Verzeichnisse",
+            "Dateien",
+            "herunterladen",
+            "Name",
+            "Zuletzt geändert",
+            "Größe",
+            "Typ",
+            "Modus",
+            "Besitzer",
+        ],
+        "%d.%m.%Y %T",
+    ),
+    builtin(
+        "fr",
+        [
+            "Index de",
+            "répertoires",
+            "fichiers",
+            "télécharger",
+            "Nom",
+            "Dernière modification",
+            "Taille",
+            "Type",
+            "Mode",
+            "Propriétaire",
+        ],
+        "%d/%m/%Y %T",
+    ),
+    builtin(
+        "pt",
+        [
+            "Índice de",
+            "diretórios",
+            "arquivos",
+            "baixar",
+            "Nome",
+            "Última modificação",
+            "Tamanho",
+            "Tipo",
+            "Modo",
+            "Proprietário",
+        ],
+        "%d/%m/%Y %T",
+    ),
+];
+
+/// Directory listing locales along with the one used by default.
+#[derive(Debug, Clone)]
+pub struct DirListLocales {
+    /// Locale used by default or `auto` to select it via the `Accept-Language` header.
+    default: String,
+    /// Locales by lowercase language tag.
+    locales: HashMap<String, DirListLocale>,
+}
+
+impl Default for DirListLocales {
+    fn default() -> Self {
+        Self {
+            default: DEFAULT_LOCALE.to_owned(),
+            locales: BUILTIN_LOCALES
+                .iter()
+                .map(|locale| (locale.lang.to_string(), locale.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// Initializes the directory listing locales.
+pub fn init(locale: &str, handler_opts: &mut RequestHandlerOpts) -> Result {
+    let advanced = handler_opts.advanced_opts.as_ref();
+    let custom = advanced.and_then(|advanced| advanced.directory_listing_locales.as_ref());
+    let locales = DirListLocales::new(locale, custom)?;
+
+    // Make sure the locales of the virtual hosts are available too
+    let vhosts = advanced.and_then(|advanced| advanced.virtual_hosts.as_deref());
+    for vhost in vhosts.unwrap_or_default() {
+        if let Some(locale) = &vhost.directory_listing_locale {
+            locales.check(locale).map_err(|err| {
+                err.context(format!(
+                    "invalid directory listing locale for virtual host {}",
+                    vhost.host
+                ))
+            })?;
+        }
+    }
+
+    tracing::info!("directory listing locale: {}", locales.default);
+    handler_opts.dir_listing_locales = locales;
+    Ok(())
+}
+
+impl DirListLocales {
+    /// Creates the directory listing locales from the built-in ones and the custom ones.
+    /// Custom locales override the strings of the built-in locale with the same language
+    /// or primary language (E.g. `pt` for `pt-br`), the English one otherwise.
+    pub fn new(default: &str, custom: Option<&BTreeMap<String, DirListingLocale>>) -> Result<Self> {
+        let mut locales = Self::default();
+
+        for (lang, custom) in custom.into_iter().flatten() {
+            let lang = lang.trim().to_lowercase();
+            if lang.is_empty() || lang == AUTO_LOCALE {
+                bail!("invalid directory listing locale name \"{lang}\"");
+            }
+            let primary = lang.split('-').next().unwrap_or_default();
+            let mut locale = locales
+                .locales
+                .get(&lang)
+                .or_else(|| locales.locales.get(primary))
+                .cloned()
+                .unwrap_or_default();
+            locale.lang = Cow::Owned(lang.clone());
+
+            let strings = [
+                (&mut locale.title, &custom.title),
+                (&mut locale.directories, &custom.directories),
+                (&mut locale.files, &custom.files),
+                (&mut locale.download, &custom.download),
+                (&mut locale.name, &custom.name),
+                (&mut locale.last_modified, &custom.last_modified),
+                (&mut locale.size, &custom.size),
+                (&mut locale.r#type, &custom.r#type),
+                (&mut locale.mode, &custom.mode),
+                (&mut locale.owner, &custom.owner),
+                (&mut locale.parent, &custom.parent),
+            ];
+            for (string, value) in strings {
+                if let Some(value) = value {
+                    *string = Cow::Owned(value.to_owned());
+                }
+            }
+            if let Some(date_format) = &custom.date_format {
+                if StrftimeItems::new(date_format).any(|item| item == Item::Error) {
+                    bail!(
+                        "invalid date format \"{date_format}\" for directory listing locale \"{lang}\""
+                    );
+                }
+                locale.date_format = Cow::Owned(date_format.to_owned());
+            }
+            if let Some(size_units) = custom.size_units {
+                locale.size_units = size_units;
+            }
+
+            tracing::debug!("added directory listing locale: {}", lang);
+            locales.locales.insert(lang, locale);
+        }
+
+        let default = default.trim().to_lowercase();
+        locales.check(&default)?;
+        locales.default = default;
+        Ok(locales)
+    }
+
+    /// Checks if a locale is available or if it is the `auto` value.
+    fn check(&self, locale: &str) -> Result {
+        let locale = locale.trim().to_lowercase();
+        if locale != AUTO_LOCALE && !self.locales.contains_key(&locale) {
+            let mut available: Vec<_> = self.locales.keys().map(String::as_str).collect();
+            available.sort_unstable();
+            bail!(
+                "directory listing locale \"{locale}\" not found, use \"{AUTO_LOCALE}\" or one of: {}",
+                available.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// Selects the locale of a directory listing, the given one (E.g. of a virtual host)
+    /// taking precedence over the default one.
+    /// It also returns whether the locale was selected via the `Accept-Language` header.
+    pub fn select(&self, locale: Option<&str>, headers: &HeaderMap) -> (&DirListLocale, bool) {
+        let locale = locale.map_or(self.default.as_str(), str::trim);
+        let negotiated = locale.eq_ignore_ascii_case(AUTO_LOCALE);
+        let lang = if negotiated {
+            headers
+                .typed_get::<AcceptLanguage>()
+                .and_then(|accept| accept.preferred(self.locales.keys().map(String::as_str)))
+                .unwrap_or(DEFAULT_LOCALE)
+                .to_owned()
+        } else {
+            locale.to_lowercase()
+        };
+
+        let locale = self
+            .locales
+            .get(&lang)
+            .or_else(|| self.locales.get(DEFAULT_LOCALE))
+            .unwrap_or(DirListLocale::english());
+        (locale, negotiated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DirListLocales, SizeUnits};
+    use crate::settings::file::DirListingLocale;
+    use headers::HeaderMap;
+    use std::collections::BTreeMap;
+
+    fn custom_locale(title: &str, date_format: Option<&str>) -> DirListingLocale {
+        DirListingLocale {
+            title: Some(title.to_owned()),
+            directories: None,
+            files: None,
+            download: None,
+            name: None,
+            last_modified: None,
+            size: None,
+            r#type: None,
+            mode: None,
+            owner: None,
+            parent: None,
+            date_format: date_format.map(|f| f.to_owned()),
+            size_units: Some(SizeUnits::Decimal),
+        }
+    }
+
+    fn accept_language(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("accept-language", value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_select() {
+        let locales = DirListLocales::new("es", None).unwrap();
+        let headers = accept_language("de");
+
+        let (locale, negotiated) = locales.select(None, &headers);
+        assert_eq!(locale.lang, "es");
+        assert_eq!(locale.title, "Índice de");
+        assert!(!negotiated);
+
+        let (locale, negotiated) = locales.select(Some("fr"), &headers);
+        assert_eq!(locale.lang, "fr");
+        assert!(!negotiated);
+
+        let (locale, negotiated) = locales.select(Some("auto"), &headers);
+        assert_eq!(locale.lang, "de");
+        assert!(negotiated);
+
+        let (locale, negotiated) = locales.select(Some("auto"), &HeaderMap::new());
+        assert_eq!(locale.lang, "en");
+        assert!(negotiated);
+    }
+
+    #[test]
+    fn test_custom_locales() {
+        let custom = BTreeMap::from([
+            ("EN".to_owned(), custom_locale("Listing of", None)),
+            (
+                "pt-BR".to_owned(),
+                custom_locale("Lista de", Some("%d/%m/%Y")),
+            ),
+            ("nl".to_owned(), custom_locale("Index van", None)),
+        ]);
+        let locales = DirListLocales::new("auto", Some(&custom)).unwrap();
+
+        let (locale, _) = locales.select(None, &accept_language("en-US"));
+        assert_eq!(locale.title, "Listing of");
+        assert_eq!(locale.name, "Name");
+        assert_eq!(locale.size_units, SizeUnits::Decimal);
+
+        let (locale, _) = locales.select(None, &accept_language("pt-BR, pt;q=0.9"));
+        assert_eq!(locale.lang, "pt-br");
+        assert_eq!(locale.title, "Lista de");
+        assert_eq!(locale.name, "Nome");
+        assert_eq!(locale.date_format, "%d/%m/%Y");
+
+        let (locale, _) = locales.select(None, &accept_language("nl-BE"));
+        assert_eq!(locale.title, "Index van");
+        assert_eq!(locale.last_modified, "Last modified");
+    }
+
+    #[test]
+    fn test_invalid_locales() {
+        assert!(DirListLocales::new("xx", None).is_err());
+
+        let custom = BTreeMap::from([("nl".to_owned(), custom_locale("Index van", Some("%Q")))]);
+        assert!(DirListLocales::new("en", Some(&custom)).is_err());
+
+        let custom = BTreeMap::from([("auto".to_owned(), custom_locale("Index", None))]);
+        assert!(DirListLocales::new("en", Some(&custom)).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! It provides the hidden entries and the header and readme files of directory listings.
+//!
+//! Like the Apache `IndexIgnore`, `HeaderName` and `ReadmeName` directives, the entries
+//! matching the hide patterns are not listed but they are still served, and the header
+//! and readme files found in a listed directory are rendered above and below its entries.
+//!
+
+use maud::html;
+use std::io::Read;
+use std::path::{Component, Path};
+
+use crate::{
+    Context, Result, fs::symlinks::FollowSymlinks, handler::RequestHandlerOpts,
+    ignore_patterns::IgnorePatterns,
+};
+
+/// Maximum size of a header or readme file rendered in a directory listing.
+const MAX_FILE_SIZE: u64 = 512 * 1024;
+
+/// Initializes the hidden entries and the header and readme files of directory listings.
+pub fn init(
+    hide: &[String],
+    header: &str,
+    readme: &str,
+    handler_opts: &mut RequestHandlerOpts,
+) -> Result {
+    let hide_patterns =
+        IgnorePatterns::new(hide).context("invalid directory listing hide patterns")?;
+    tracing::info!(
+        "directory listing hide: enabled={}, patterns={:?}",
+        !hide_patterns.is_empty(),
+        hide
+    );
+    handler_opts.dir_listing_hide = hide_patterns;
+
+    handler_opts.dir_listing_readme = DirReadme::new(header, readme)?;
+    tracing::info!("directory listing header: \"{header}\", readme: \"{readme}\"");
+    Ok(())
+}
+
+/// File names of the header and readme files rendered in directory listings.
+#[derive(Clone, Debug, Default)]
+pub struct DirReadme {
+    /// File name rendered above the entries, disabled if empty.
+    header: String,
+    /// File name rendered below the entries, disabled if empty.
+    readme: String,
+}
+
+impl DirReadme {
+    /// Creates the header and readme files of directory listings, both disabled if empty.
+    pub fn new(header: &str, readme: &str) -> Result<Self> {
+        Ok(Self {
+            header: file_name(header, "header")?,
+            readme: file_name(readme, "readme")?,
+        })
+    }
+
+    /// Checks if there are neither header nor readme files.
+    pub fn is_empty(&self) -> bool {
+        self.header.is_empty() && self.readme.is_empty()
+    }
+
+    /// Renders the header file of a directory as HTML if any.
+    pub(crate) fn header_html(
+        &self,
+        dir: &Path,
+        root_dir: &Path,
+        follow_symlinks: FollowSymlinks,
+    ) -> Option<String> {
+        render_file(&self.header, dir, root_dir, follow_symlinks)
+    }
+
+    /// Renders the readme file of a directory as HTML if any.
+    pub(crate) fn readme_html(
+        &self,
+        dir: &Path,
+        root_dir: &Path,
+        follow_symlinks: FollowSymlinks,
+    ) -> Option<String> {
+        render_file(&self.readme, dir, root_dir, follow_symlinks)
+    }
+}
+
+/// Validates a header or readme file name, which can't contain a path.
+fn file_name(name: &str, kind: &str) -> Result<String> {
+    let name = name.trim();
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (None, _) => Ok(String::new()),
+        (Some(Component::Normal(_)), None) if !name.contains(['/', '\\']) => Ok(name.to_owned()),
+        _ => bail!("invalid directory listing {kind} file name: {name}"),
+    }
+}
+
+/// Renders a file of a directory as HTML according to its extension.
+///
+/// HTML files are included as they are, markdown files are rendered if the `markdown`
+/// feature is enabled and the rest of them are included as preformatted text.
+fn render_file(
+    name: &str,
+    dir: &Path,
+    root_dir: &Path,
+    follow_symlinks: FollowSymlinks,
+) -> Option<String> {
+    if name.is_empty() {
+        return None;
+    }
+    let path = dir.join(name);
+    if !follow_symlinks.allows(root_dir, &path) {
+        return None;
+    }
+
+    let file = std::fs::File::open(&path).ok()?;
+    let meta = file.metadata().ok()?;
+    if !meta.is_file() {
+        return None;
+    }
+    if meta.len() > MAX_FILE_SIZE {
+        tracing::warn!(
+            "directory listing file `{}` skipped since it exceeds {MAX_FILE_SIZE} bytes",
+            path.display()
+        );
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(meta.len() as usize);
+    if let Err(err) = file.take(MAX_FILE_SIZE).read_to_end(&mut bytes) {
+        tracing::error!(
+            "unable to read directory listing file `{}`: {:?}",
+            path.display(),
+            err
+        );
+        return None;
+    }
+    let content = String::from_utf8_lossy(&bytes);
+
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "html" | "htm" => Some(content.into_owned()),
+        #[cfg(feature = "markdown")]
+        "md" => Some(crate::markdown_render::render_fragment(&content)),
+        _ => Some(html! { pre { (content) } }.into_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DirReadme, file_name};
+    use crate::fs::symlinks::FollowSymlinks;
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name("", "header").unwrap(), "");
+        assert_eq!(file_name(" HEADER.html ", "header").unwrap(), "HEADER.html");
+        assert_eq!(file_name("README.md", "readme").unwrap(), "README.md");
+        assert!(file_name("docs/README.md", "readme").is_err());
+        assert!(file_name("/README.md", "readme").is_err());
+        assert!(file_name("..", "readme").is_err());
+        assert!(file_name(".", "readme").is_err());
+    }
+
+    #[test]
+    fn test_render_files() {
+        let root = std::env::temp_dir().join(format!("sws-dir-readme-{}", std::process::id()));
+        let dir = root.join("pub");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("HEADER.html"), "<p>Mirror <b>header</b></p>").unwrap();
+        std::fs::write(dir.join("README.txt"), "a <b> c").unwrap();
+        std::fs::write(dir.join("README.md"), "# Releases").unwrap();
+        let render = |header: &str, readme: &str| {
+            let files = DirReadme::new(header, readme).unwrap();
+            (
+                files.header_html(&dir, &root, FollowSymlinks::Never),
+                files.readme_html(&dir, &root, FollowSymlinks::Never),
+            )
+        };
+
+        assert_eq!(
+            render("HEADER.html", "README.txt"),
+            (
+                Some("<p>Mirror <b>header</b></p>".to_owned()),
+                Some("<pre>a &lt;b&gt; c</pre>".to_owned())
+            )
+        );
+        #[cfg(feature = "markdown")]
+        assert_eq!(
+            render("", "README.md").1.as_deref().map(str::trim),
+            Some("<h1>Releases</h1>")
+        );
+        // Missing files are skipped
+        assert_eq!(render("HEADER.htm", ""), (None, None));
+        assert!(DirReadme::default().is_empty());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
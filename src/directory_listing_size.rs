@@ -0,0 +1,288 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! It provides the optional directory size computation for directory listings.
+//!
+//! Directory sizes are computed in the background and cached, so a listing never waits
+//! for a directory tree to be walked. A missing size is shown until it gets computed
+//! and an expired size keeps being shown while it gets refreshed.
+//!
+//! Directory trees are walked from several threads, up to the configured concurrency,
+//! and the walks in progress are cancelled when the cache is cleared or dropped.
+//!
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use crate::fs::walk::{self, CancelToken, Visit};
+use crate::handler::RequestHandlerOpts;
+
+/// Maximum number of directory sizes kept in the cache.
+const MAX_CACHED_DIRS: usize = 10_000;
+
+/// Initializes the directory size computation for directory listings.
+pub fn init(
+    enabled: bool,
+    ttl: u64,
+    max_entries: u64,
+    concurrency: usize,
+    handler_opts: &mut RequestHandlerOpts,
+) {
+    let ignore_hidden_files = handler_opts.ignore_hidden_files;
+    handler_opts.dir_listing_dir_size = enabled
+        .then(|| DirSizeCache::new(ttl, max_entries, ignore_hidden_files).concurrency(concurrency));
+    tracing::info!(
+        "directory listing size: enabled={enabled}, ttl={ttl}s, max_entries={max_entries}, concurrency={concurrency}"
+    );
+}
+
+/// Cache of directory sizes computed in the background.
+#[derive(Clone)]
+pub struct DirSizeCache {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    ttl: Duration,
+    max_entries: u64,
+    ignore_hidden_files: bool,
+    /// Maximum number of threads walking a directory tree.
+    concurrency: usize,
+    sizes: Mutex<HashMap<PathBuf, CachedSize>>,
+    /// Token of the walks in progress, replaced when the cache is cleared.
+    cancel: Mutex<CancelToken>,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        // The cache is gone (E.g. after a configuration reload), so are its walks
+        if let Ok(cancel) = self.cancel.get_mut() {
+            cancel.cancel();
+        }
+    }
+}
+
+/// A cached directory size.
+struct CachedSize {
+    /// Total size in bytes or `None` if unknown or the entries limit was exceeded.
+    size: Option<u64>,
+    /// When the size was computed or `None` if it was not computed yet.
+    computed_at: Option<Instant>,
+    /// Whether a computation is in progress.
+    pending: bool,
+}
+
+impl DirSizeCache {
+    /// Creates a new directory size cache.
+    /// Sizes expire after `ttl` seconds and directory trees with more than `max_entries` are skipped.
+    pub fn new(ttl: u64, max_entries: u64, ignore_hidden_files: bool) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                ttl: Duration::from_secs(ttl),
+                max_entries,
+                ignore_hidden_files,
+                concurrency: 1,
+                sizes: Mutex::new(HashMap::new()),
+                cancel: Mutex::new(CancelToken::default()),
+            }),
+        }
+    }
+
+    /// Sets the maximum number of threads walking a directory tree, one by default.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        if let Some(inner) = Arc::get_mut(&mut self.inner) {
+            inner.concurrency = concurrency.max(1);
+        }
+        self
+    }
+
+    /// Gets the cached size of a directory if known.
+    /// A computation is scheduled in the background if the size is missing or expired.
+    pub fn get(&self, dir: &Path) -> Option<u64> {
+        let mut sizes = match self.inner.sizes.lock() {
+            Ok(sizes) => sizes,
+            Err(err) => {
+                tracing::error!("directory size cache lock is poisoned: {:?}", err);
+                return None;
+            }
+        };
+
+        if let Some(cached) = sizes.get_mut(dir) {
+            let expired = cached
+                .computed_at
+                .is_some_and(|t| t.elapsed() >= self.inner.ttl);
+            if expired && !cached.pending {
+                cached.pending = true;
+                self.schedule(dir.to_owned());
+            }
+            return cached.size;
+        }
+
+        if sizes.len() >= MAX_CACHED_DIRS {
+            let ttl = self.inner.ttl;
+            sizes.retain(|_, c| c.pending || c.computed_at.is_some_and(|t| t.elapsed() < ttl));
+            if sizes.len() >= MAX_CACHED_DIRS {
+                tracing::debug!(
+                    "directory size cache is full, size of {} skipped",
+                    dir.display()
+                );
+                return None;
+            }
+        }
+
+        sizes.insert(
+            dir.to_owned(),
+            CachedSize {
+                size: None,
+                computed_at: None,
+                pending: true,
+            },
+        );
+        self.schedule(dir.to_owned());
+
+        None
+    }
+
+    /// Removes all the cached directory sizes, so they get computed again on demand.
+    /// The walks in progress are cancelled.
+    pub fn clear(&self) {
+        match self.inner.cancel.lock() {
+            Ok(mut cancel) => std::mem::take(&mut *cancel).cancel(),
+            Err(err) => tracing::error!("directory size cache lock is poisoned: {:?}", err),
+        }
+        match self.inner.sizes.lock() {
+            Ok(mut sizes) => sizes.clear(),
+            Err(err) => tracing::error!("directory size cache lock is poisoned: {:?}", err),
+        }
+    }
+
+    /// Computes the size of a directory on a blocking thread and caches it.
+    fn schedule(&self, dir: PathBuf) {
+        let Ok(cancel) = self.inner.cancel.lock().map(|cancel| cancel.clone()) else {
+            tracing::error!("directory size cache lock is poisoned");
+            return;
+        };
+        // NOTE: the walk doesn't keep the cache alive, so dropping it cancels the walk
+        let inner = Arc::downgrade(&self.inner);
+        let (max_entries, ignore_hidden_files, concurrency) = (
+            self.inner.max_entries,
+            self.inner.ignore_hidden_files,
+            self.inner.concurrency,
+        );
+        let task = move || {
+            let size = dir_size(&dir, max_entries, ignore_hidden_files, concurrency, &cancel);
+            if cancel.is_cancelled() {
+                tracing::debug!("directory size of {} cancelled", dir.display());
+                return;
+            }
+            tracing::debug!("directory size computed: {} {:?}", dir.display(), size);
+            let Some(inner) = Weak::upgrade(&inner) else {
+                return;
+            };
+            if let Ok(mut sizes) = inner.sizes.lock() {
+                sizes.insert(
+                    dir,
+                    CachedSize {
+                        size,
+                        computed_at: Some(Instant::now()),
+                        pending: false,
+                    },
+                );
+            }
+        };
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(task);
+            }
+            Err(_) => task(),
+        }
+    }
+}
+
+/// Computes the total size in bytes of the files of a directory tree from up to `concurrency` threads.
+/// Symlinks are not followed and `None` is returned if more than `max_entries` are found
+/// or the walk gets cancelled.
+fn dir_size(
+    dir: &Path,
+    max_entries: u64,
+    ignore_hidden_files: bool,
+    concurrency: usize,
+    cancel: &CancelToken,
+) -> Option<u64> {
+    let total = AtomicU64::new(0);
+    let entries = AtomicU64::new(0);
+
+    let completed = walk::walk(dir, concurrency, cancel, |entry, meta| {
+        if ignore_hidden_files
+            && entry
+                .file_name()
+                .as_encoded_bytes()
+                .first()
+                .is_some_and(|c| *c == b'.')
+        {
+            return Visit::Skip;
+        }
+
+        if entries.fetch_add(1, Ordering::Relaxed) >= max_entries {
+            tracing::debug!(
+                "directory {} exceeds {} entries, size not computed",
+                dir.display(),
+                max_entries
+            );
+            return Visit::Stop;
+        }
+
+        if meta.is_file() {
+            total.fetch_add(meta.len(), Ordering::Relaxed);
+        }
+        Visit::Continue
+    });
+
+    completed.then(|| total.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DirSizeCache, dir_size};
+    use crate::fs::walk::CancelToken;
+    use std::path::Path;
+
+    #[test]
+    fn test_dir_size() {
+        let dir = Path::new("tests/fixtures/public/assets");
+        let expected: u64 = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().metadata().unwrap().len())
+            .sum();
+        let cancel = CancelToken::default();
+        assert_eq!(dir_size(dir, 100, false, 1, &cancel), Some(expected));
+        assert_eq!(dir_size(dir, 100, false, 4, &cancel), Some(expected));
+        assert_eq!(dir_size(dir, 1, false, 4, &cancel), None);
+        assert_eq!(
+            dir_size(Path::new("tests/fixtures/none"), 100, false, 4, &cancel),
+            Some(0)
+        );
+
+        cancel.cancel();
+        assert_eq!(dir_size(dir, 100, false, 4, &cancel), None);
+    }
+
+    #[test]
+    fn test_clear_cancels_walks() {
+        let cache = DirSizeCache::new(300, 100, false).concurrency(4);
+        let cancel = cache.inner.cancel.lock().unwrap().clone();
+        cache.clear();
+        assert!(cancel.is_cancelled());
+        assert!(!cache.inner.cancel.lock().unwrap().is_cancelled());
+
+        let cancel = cache.inner.cancel.lock().unwrap().clone();
+        drop(cache);
+        assert!(cancel.is_cancelled());
+    }
+}
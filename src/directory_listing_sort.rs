@@ -0,0 +1,305 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! It provides the sorting of the directory listing entries.
+//!
+//! Entries are sorted by one or more keys (`name`, `mtime` or `size`), each one in ascending
+//! or descending order (E.g. `size:desc,name`), optionally keeping the directories grouped
+//! before the files. The former numeric order codes are still supported.
+//!
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Error, Result, handler::RequestHandlerOpts};
+
+/// Query parameter of the sort keys.
+pub(crate) const SORT_PARAM_KEY: &str = "sort";
+
+/// Query parameter of the directories grouping.
+pub(crate) const DIRS_FIRST_PARAM_KEY: &str = "dirs-first";
+
+/// Initializes the sorting of directory listings.
+///
+/// The sort keys take precedence over the former numeric order code unless they are empty.
+pub fn init(
+    sort: &str,
+    order: u8,
+    dirs_first: bool,
+    handler_opts: &mut RequestHandlerOpts,
+) -> Result {
+    let sort = if sort.trim().is_empty() {
+        DirListSort::from_code(order)
+    } else {
+        sort.parse()?
+    };
+    handler_opts.dir_listing_sort = sort.dirs_first(dirs_first);
+    tracing::info!(
+        "directory listing sort: keys=\"{}\", dirs_first={dirs_first}",
+        handler_opts.dir_listing_sort
+    );
+    Ok(())
+}
+
+/// Attribute of the directory entries to sort them by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// Entry name, case-insensitive.
+    Name,
+    /// Last modification time.
+    Mtime,
+    /// Entry size.
+    Size,
+}
+
+impl SortKey {
+    /// Name of the sort key.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Mtime => "mtime",
+            Self::Size => "size",
+        }
+    }
+}
+
+/// Sort key along with its direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortOrder {
+    /// Attribute to sort the entries by.
+    pub key: SortKey,
+    /// Whether the entries are sorted in descending order.
+    pub desc: bool,
+}
+
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.key.as_str())?;
+        if self.desc {
+            f.write_str(":desc")?;
+        }
+        Ok(())
+    }
+}
+
+/// Sorting of the directory listing entries, unsorted by default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirListSort {
+    /// Sort keys by priority.
+    keys: Vec<SortOrder>,
+    /// Whether the directories are grouped before the files.
+    dirs_first: bool,
+}
+
+impl DirListSort {
+    /// Creates the sorting of a former order code: 0 (Name asc), 1 (Name desc),
+    /// 2 (Last modified asc), 3 (Last modified desc), 4 (Size asc), 5 (Size desc),
+    /// otherwise unsorted.
+    pub fn from_code(code: u8) -> Self {
+        let key = match code {
+            0 | 1 => SortKey::Name,
+            2 | 3 => SortKey::Mtime,
+            4 | 5 => SortKey::Size,
+            _ => return Self::default(),
+        };
+        Self {
+            keys: vec![SortOrder {
+                key,
+                desc: code % 2 == 1,
+            }],
+            dirs_first: false,
+        }
+    }
+
+    /// Sets whether the directories are grouped before the files.
+    pub fn dirs_first(mut self, dirs_first: bool) -> Self {
+        self.dirs_first = dirs_first;
+        self
+    }
+
+    /// Checks whether the directories are grouped before the files.
+    pub fn is_dirs_first(&self) -> bool {
+        self.dirs_first
+    }
+
+    /// Gets the sort keys by priority.
+    pub fn keys(&self) -> &[SortOrder] {
+        &self.keys
+    }
+
+    /// Overrides the sorting with the `sort` and `dirs-first` query parameters if any.
+    /// Invalid values are ignored.
+    pub(crate) fn with_query(&self, query: Option<&str>) -> Self {
+        let mut sort = self.clone();
+        let Some(query) = query else {
+            return sort;
+        };
+        for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+            if key == SORT_PARAM_KEY && !value.trim().is_empty() {
+                match value.parse::<Self>() {
+                    Ok(parsed) => sort.keys = parsed.keys,
+                    Err(err) => tracing::debug!("sorting: invalid query value: {err}"),
+                }
+            } else if key == DIRS_FIRST_PARAM_KEY {
+                match value.parse::<bool>() {
+                    Ok(dirs_first) => sort.dirs_first = dirs_first,
+                    Err(err) => tracing::debug!("sorting: invalid dirs-first query value: {err}"),
+                }
+            }
+        }
+        sort
+    }
+
+    /// Gets the query of a column link sorting by the given key first, toggling its direction
+    /// if it is already the first one, and by the current keys next.
+    ///
+    /// The `dirs-first` parameter is included if it differs from the `default` sorting.
+    pub(crate) fn link(&self, key: SortKey, default: &Self) -> String {
+        let desc = self
+            .keys
+            .first()
+            .is_some_and(|first| first.key == key && !first.desc);
+        let keys = std::iter::once(SortOrder { key, desc })
+            .chain(self.keys.iter().filter(|order| order.key != key).copied())
+            .map(|order| order.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let mut query = format!("?{SORT_PARAM_KEY}={keys}");
+        if self.dirs_first != default.dirs_first {
+            query.push_str(&format!("&{DIRS_FIRST_PARAM_KEY}={}", self.dirs_first));
+        }
+        query
+    }
+}
+
+impl FromStr for DirListSort {
+    type Err = Error;
+
+    /// Parses a comma-separated list of sort keys with an optional `asc` or `desc` direction
+    /// (E.g. `size:desc,name`), or a former numeric order code.
+    fn from_str(s: &str) -> Result<Self> {
+        if let Ok(code) = s.trim().parse::<u8>() {
+            return Ok(Self::from_code(code));
+        }
+
+        let mut keys: Vec<SortOrder> = Vec::new();
+        for part in s.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (name, direction) = part.split_once(':').unwrap_or((part, "asc"));
+            let key = match name.trim().to_ascii_lowercase().as_str() {
+                "name" => SortKey::Name,
+                "mtime" => SortKey::Mtime,
+                "size" => SortKey::Size,
+                _ => bail!("invalid directory listing sort key: {part}"),
+            };
+            let desc = match direction.trim().to_ascii_lowercase().as_str() {
+                "asc" => false,
+                "desc" => true,
+                _ => bail!("invalid directory listing sort direction: {part}"),
+            };
+            if keys.iter().any(|order| order.key == key) {
+                bail!("duplicated directory listing sort key: {part}");
+            }
+            keys.push(SortOrder { key, desc });
+        }
+        Ok(Self {
+            keys,
+            dirs_first: false,
+        })
+    }
+}
+
+impl fmt::Display for DirListSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, order) in self.keys.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            order.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DirListSort, SortKey, SortOrder};
+
+    #[test]
+    fn test_parse() {
+        let sort: DirListSort = "size:desc, name ,mtime:ASC".parse().unwrap();
+        assert_eq!(
+            sort.keys(),
+            [
+                SortOrder {
+                    key: SortKey::Size,
+                    desc: true
+                },
+                SortOrder {
+                    key: SortKey::Name,
+                    desc: false
+                },
+                SortOrder {
+                    key: SortKey::Mtime,
+                    desc: false
+                },
+            ]
+        );
+        assert_eq!(sort.to_string(), "size:desc,name,mtime");
+        assert!("".parse::<DirListSort>().unwrap().keys().is_empty());
+
+        assert!("owner".parse::<DirListSort>().is_err());
+        assert!("name:up".parse::<DirListSort>().is_err());
+        assert!("name,name:desc".parse::<DirListSort>().is_err());
+    }
+
+    #[test]
+    fn test_order_codes() {
+        assert_eq!(DirListSort::from_code(1).to_string(), "name:desc");
+        assert_eq!(DirListSort::from_code(2).to_string(), "mtime");
+        assert_eq!(DirListSort::from_code(5).to_string(), "size:desc");
+        assert!(DirListSort::from_code(6).keys().is_empty());
+        assert_eq!(
+            "3".parse::<DirListSort>().unwrap().to_string(),
+            "mtime:desc"
+        );
+    }
+
+    #[test]
+    fn test_with_query() {
+        let default = "name".parse::<DirListSort>().unwrap().dirs_first(true);
+
+        let sort = default.with_query(Some("sort=size%3Adesc,name&dirs-first=false"));
+        assert_eq!(sort.to_string(), "size:desc,name");
+        assert!(!sort.is_dirs_first());
+
+        // The legacy order codes and invalid values
+        assert_eq!(default.with_query(Some("sort=3")).to_string(), "mtime:desc");
+        let sort = default.with_query(Some("sort=owner&dirs-first=maybe"));
+        assert_eq!(sort, default);
+        assert_eq!(default.with_query(None), default);
+    }
+
+    #[test]
+    fn test_link() {
+        let default = "name".parse::<DirListSort>().unwrap();
+        assert_eq!(default.link(SortKey::Name, &default), "?sort=name:desc");
+        assert_eq!(default.link(SortKey::Size, &default), "?sort=size,name");
+
+        let sort = default.with_query(Some("sort=size:desc,name&dirs-first=true"));
+        assert_eq!(
+            sort.link(SortKey::Name, &default),
+            "?sort=name,size:desc&dirs-first=true"
+        );
+        assert_eq!(
+            sort.link(SortKey::Size, &default),
+            "?sort=size,name&dirs-first=true"
+        );
+        assert_eq!(
+            DirListSort::default().link(SortKey::Mtime, &DirListSort::default()),
+            "?sort=mtime"
+        );
+    }
+}
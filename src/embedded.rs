@@ -0,0 +1,299 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that serves assets embedded into the binary at compile time,
+//! either instead of or overlaid below the root directory.
+//!
+//! The `static-web-server` binary embeds the files of the directory given by the
+//! `SWS_EMBED_DIR` env at build time. Library users can register their own assets via
+//! [`EmbeddedAssets::new`] or any other [`Filesystem`] backend instead.
+//!
+
+use bytes::Bytes;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::fs::vfs::{Filesystem, VfsDirEntry, VfsFuture, VfsMetadata};
+use crate::{Context, Result, handler::RequestHandlerOpts};
+
+/// Files embedded into the `static-web-server` binary at build time.
+static BUILTIN_FILES: &[EmbeddedFile] = include!(concat!(env!("OUT_DIR"), "/embedded_assets.rs"));
+
+/// File system of the embedded assets registered at runtime if any.
+static REGISTERED_SOURCE: OnceLock<Arc<dyn Filesystem>> = OnceLock::new();
+
+#[derive(
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    ValueEnum,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+/// How the embedded assets are served along with the root directory.
+pub enum EmbeddedAssetsMode {
+    /// Don't serve the embedded assets (default).
+    #[default]
+    Disabled,
+    /// Serve the embedded assets not found under the root directory.
+    Overlay,
+    /// Serve only the embedded assets, without accessing the root directory.
+    Only,
+}
+
+impl fmt::Display for EmbeddedAssetsMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Disabled => "disabled",
+            Self::Overlay => "overlay",
+            Self::Only => "only",
+        })
+    }
+}
+
+/// A file embedded into the binary.
+#[derive(Clone, Copy, Debug)]
+pub struct EmbeddedFile {
+    /// Path of the file relative to the root, separated by `/` (E.g. `assets/main.js`).
+    pub path: &'static str,
+    /// Contents of the file.
+    pub contents: &'static [u8],
+    /// Last modification time of the file in seconds since the UNIX epoch if known.
+    pub modified: Option<u64>,
+}
+
+/// Set of files embedded into the binary.
+#[derive(Debug, Default)]
+pub struct EmbeddedAssets {
+    files: HashMap<&'static str, &'static EmbeddedFile>,
+    dirs: HashSet<&'static str>,
+}
+
+impl EmbeddedAssets {
+    /// Creates the set of assets from the given embedded files.
+    pub fn new(files: &'static [EmbeddedFile]) -> Self {
+        let mut assets = Self::default();
+        for file in files {
+            let path = file.path.trim_start_matches('/');
+            assets.files.insert(path, file);
+            let mut parent = path;
+            while let Some((dir, _)) = parent.rsplit_once('/') {
+                assets.dirs.insert(dir);
+                parent = dir;
+            }
+        }
+        assets
+    }
+
+    /// Returns the assets embedded into the `static-web-server` binary at build time if any.
+    pub fn builtin() -> Option<Self> {
+        (!BUILTIN_FILES.is_empty()).then(|| Self::new(BUILTIN_FILES))
+    }
+
+    /// Returns the number of embedded files.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Checks if there are no embedded files.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl EmbeddedFile {
+    /// Returns the metadata of the embedded file.
+    fn metadata(&self) -> VfsMetadata {
+        VfsMetadata {
+            is_dir: false,
+            len: self.contents.len() as u64,
+            modified: self
+                .modified
+                .map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+        }
+    }
+}
+
+impl EmbeddedAssets {
+    /// Returns the metadata of an embedded file or directory if any.
+    fn metadata_of(&self, path: &str) -> Option<VfsMetadata> {
+        match self.files.get(path) {
+            Some(file) => Some(file.metadata()),
+            None if path.is_empty() || self.dirs.contains(path) => Some(VfsMetadata {
+                is_dir: true,
+                ..Default::default()
+            }),
+            None => None,
+        }
+    }
+}
+
+/// Returns the parent directory and the name of a path separated by `/`.
+fn split_parent(path: &str) -> (&str, &str) {
+    path.rsplit_once('/').unwrap_or(("", path))
+}
+
+/// Error of an embedded asset path not found.
+fn not_found(path: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("embedded asset {path} not found"),
+    )
+}
+
+impl Filesystem for EmbeddedAssets {
+    fn metadata<'a>(&'a self, path: &'a str) -> VfsFuture<'a, VfsMetadata> {
+        Box::pin(async move { self.metadata_of(path).ok_or_else(|| not_found(path)) })
+    }
+
+    fn read_range<'a>(&'a self, path: &'a str, range: Range<u64>) -> VfsFuture<'a, Bytes> {
+        Box::pin(async move {
+            let file = self.files.get(path).ok_or_else(|| not_found(path))?;
+            let len = file.contents.len();
+            let start = (range.start as usize).min(len);
+            let end = (range.end as usize).clamp(start, len);
+            Ok(Bytes::from_static(&file.contents[start..end]))
+        })
+    }
+
+    fn read_dir<'a>(&'a self, path: &'a str) -> VfsFuture<'a, Vec<VfsDirEntry>> {
+        Box::pin(async move {
+            if self.files.contains_key(path) || self.metadata_of(path).is_none() {
+                return Err(not_found(path));
+            }
+            let dirs = self.dirs.iter().map(|dir| {
+                let meta = VfsMetadata {
+                    is_dir: true,
+                    ..Default::default()
+                };
+                (*dir, meta)
+            });
+            let files = self
+                .files
+                .iter()
+                .map(|(path, file)| (*path, file.metadata()));
+            Ok(dirs
+                .chain(files)
+                .filter_map(|(entry_path, metadata)| {
+                    let (parent, name) = split_parent(entry_path);
+                    (parent == path).then(|| VfsDirEntry {
+                        name: name.to_owned(),
+                        metadata,
+                    })
+                })
+                .collect())
+        })
+    }
+}
+
+/// Embedded assets options of the request handler.
+#[derive(Clone)]
+pub struct EmbeddedAssetsOpts {
+    /// File system of the embedded assets.
+    pub source: Arc<dyn Filesystem>,
+    /// Whether only the embedded assets are served, without accessing the root directory.
+    pub only: bool,
+}
+
+/// Registers the file system of the embedded assets served instead of the ones embedded
+/// at build time, E.g. by a library user. Only the first registered file system is kept,
+/// so it returns `false` if one was already registered.
+pub fn register<S: Filesystem + 'static>(source: S) -> bool {
+    REGISTERED_SOURCE.set(Arc::new(source)).is_ok()
+}
+
+/// Initializes the embedded assets, either the registered ones or the ones embedded at build time.
+pub(crate) fn init(mode: EmbeddedAssetsMode, handler_opts: &mut RequestHandlerOpts) -> Result {
+    let registered = REGISTERED_SOURCE.get().cloned();
+    let is_registered = registered.is_some();
+    handler_opts.embedded_assets = match mode {
+        EmbeddedAssetsMode::Disabled => None,
+        EmbeddedAssetsMode::Overlay | EmbeddedAssetsMode::Only => {
+            let source = registered
+                .or_else(|| {
+                    EmbeddedAssets::builtin().map(|assets| Arc::new(assets) as Arc<dyn Filesystem>)
+                })
+                .context(
+                    "no assets were embedded into the binary, set the `SWS_EMBED_DIR` env when building it",
+                )?;
+            Some(EmbeddedAssetsOpts {
+                source,
+                only: mode == EmbeddedAssetsMode::Only,
+            })
+        }
+    };
+    tracing::info!(
+        "embedded assets: mode={mode}, source={}",
+        if is_registered {
+            "registered"
+        } else {
+            "builtin"
+        }
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EmbeddedAssets, EmbeddedFile};
+    use crate::fs::vfs::Filesystem;
+    use std::io;
+
+    static FILES: &[EmbeddedFile] = &[
+        EmbeddedFile {
+            path: "index.html",
+            contents: b"<h1>dashboard</h1>",
+            modified: Some(1_700_000_000),
+        },
+        EmbeddedFile {
+            path: "assets/js/main.js",
+            contents: b"alert(1)",
+            modified: None,
+        },
+    ];
+
+    #[tokio::test]
+    async fn test_embedded_assets() {
+        let assets = EmbeddedAssets::new(FILES);
+        assert_eq!(assets.len(), 2);
+
+        let meta = assets.metadata("index.html").await.unwrap();
+        assert_eq!(meta.len, 18);
+        assert!(!meta.is_dir);
+        assert!(meta.modified.is_some());
+        let contents = assets.read_range("index.html", 4..14).await.unwrap();
+        assert_eq!(contents, "dashboard<");
+        let meta = assets.metadata("assets/js/main.js").await.unwrap();
+        assert!(meta.modified.is_none());
+        let err = assets.metadata("missing.js").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(assets.read_range("assets", 0..1).await.is_err());
+
+        assert!(assets.metadata("").await.unwrap().is_dir);
+        assert!(assets.metadata("assets").await.unwrap().is_dir);
+        assert!(assets.metadata("assets/js").await.unwrap().is_dir);
+
+        let entries = assets.read_dir("").await.unwrap();
+        let mut names: Vec<_> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["assets", "index.html"]);
+        let entries = assets.read_dir("assets/js").await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "main.js");
+        assert!(assets.read_dir("index.html").await.is_err());
+        assert!(assets.read_dir("missing").await.is_err());
+    }
+}
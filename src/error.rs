@@ -16,3 +16,93 @@ pub use anyhow::Context;
 /// Just re-export some `anyhow` stuff.
 pub use anyhow::anyhow;
 pub use anyhow::bail;
+
+/// Collects the errors of independent validations tagged by module (E.g. on start up),
+/// so all of them are reported at once instead of failing on the first one.
+#[derive(Debug, Default)]
+pub struct ErrorList {
+    errors: Vec<(&'static str, Error)>,
+}
+
+impl ErrorList {
+    /// Returns the value of a validation result, or records its error tagged by module otherwise.
+    pub fn check<T>(&mut self, module: &'static str, result: Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.push(module, err);
+                None
+            }
+        }
+    }
+
+    /// Records an error tagged by module.
+    pub fn push(&mut self, module: &'static str, err: Error) {
+        self.errors.push((module, err));
+    }
+
+    /// Returns the recorded errors along with their module.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &Error)> {
+        self.errors.iter().map(|(module, err)| (*module, err))
+    }
+
+    /// Returns the number of recorded errors.
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Checks if no errors were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Fails with the recorded errors if any.
+    ///
+    /// A single error is returned as is, while several errors are reported
+    /// together, one per line along with its module and causes.
+    pub fn into_result(mut self) -> Result {
+        match self.errors.len() {
+            0 => Ok(()),
+            1 => Err(self.errors.remove(0).1),
+            len => {
+                let errors: String = self
+                    .errors
+                    .iter()
+                    .map(|(module, err)| format!("\n  [{module}] {err:#}"))
+                    .collect();
+                Err(anyhow!("{len} configuration errors found:{errors}"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorList, anyhow};
+
+    #[test]
+    fn test_error_list() {
+        let mut errors = ErrorList::default();
+        assert_eq!(errors.check("root", Ok(1)), Some(1));
+        assert!(errors.is_empty());
+        assert!(ErrorList::default().into_result().is_ok());
+
+        let err = anyhow!("file not found").context("invalid root");
+        assert_eq!(errors.check::<()>("root", Err(err)), None);
+        errors.push("redirects", anyhow!("invalid glob pattern"));
+        assert_eq!(errors.len(), 2);
+
+        let err = errors.into_result().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "2 configuration errors found:\n  [root] invalid root: file not found\n  [redirects] invalid glob pattern"
+        );
+
+        let mut errors = ErrorList::default();
+        errors.push("tls", anyhow!("cert file missing"));
+        assert_eq!(
+            errors.into_result().unwrap_err().to_string(),
+            "cert file missing"
+        );
+    }
+}
@@ -10,86 +10,149 @@ use headers::{AcceptRanges, ContentLength, ContentType, HeaderMapExt};
 use hyper::{Body, Method, Response, StatusCode, Uri};
 use maud::{DOCTYPE, html};
 use mime_guess::mime;
-use std::path::Path;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher, RandomState};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 
-use crate::{Result, helpers, http_ext::MethodExt};
+use crate::handler::RequestHandlerOpts;
+use crate::settings::ErrorPageStatus;
+use crate::{Result, content_type_options, helpers, http_ext::MethodExt};
 
-/// It returns a HTTP error response which also handles available `404` or `50x` HTML content.
+/// Placeholder replaced by the status code in the error pages.
+const PLACEHOLDER_STATUS: &str = "{{status}}";
+/// Placeholder replaced by the status code reason in the error pages.
+const PLACEHOLDER_REASON: &str = "{{reason}}";
+/// Placeholder replaced by the request path in the error pages.
+const PLACEHOLDER_PATH: &str = "{{path}}";
+/// Placeholder replaced by the request ID in the error pages.
+const PLACEHOLDER_REQUEST_ID: &str = "{{request_id}}";
+/// Placeholder replaced by the current date and time in the error pages.
+const PLACEHOLDER_TIMESTAMP: &str = "{{timestamp}}";
+
+/// Counter making the request IDs unique.
+static REQUEST_ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Custom HTML error pages by status code and status class.
+#[derive(Clone, Debug, Default)]
+pub struct ErrorPages {
+    codes: HashMap<StatusCode, PathBuf>,
+    classes: HashMap<u16, PathBuf>,
+}
+
+impl ErrorPages {
+    /// Creates the error pages with the given `404` and `50x` pages.
+    pub fn new(page404: PathBuf, page50x: PathBuf) -> Self {
+        let mut pages = Self::default();
+        pages.insert(ErrorPageStatus::Code(StatusCode::NOT_FOUND), page404);
+        pages.insert(ErrorPageStatus::Class(5), page50x);
+        pages
+    }
+
+    /// Sets the page of a status code or status class, replacing the previous one if any.
+    pub fn insert(&mut self, status: ErrorPageStatus, page: PathBuf) {
+        match status {
+            ErrorPageStatus::Code(code) => self.codes.insert(code, page),
+            ErrorPageStatus::Class(class) => self.classes.insert(class, page),
+        };
+    }
+
+    /// Resolves the most specific page of a status code, the page of its status class
+    /// is used when the status code has no page of its own or its file doesn't exist.
+    pub fn resolve(&self, status_code: &StatusCode) -> Option<&Path> {
+        let class = status_code.as_u16() / 100;
+        [self.codes.get(status_code), self.classes.get(&class)]
+            .into_iter()
+            .flatten()
+            .find(|page| {
+                let is_file = page.is_file();
+                if !is_file {
+                    tracing::debug!(
+                        "error page file path not found or not a regular file: {}",
+                        page.display()
+                    );
+                }
+                is_file
+            })
+            .map(PathBuf::as_path)
+    }
+}
+
+/// Initializes the error pages, where the `advanced` error pages take precedence
+/// over the `404` and `50x` pages.
+/// NOTE: in the case of relative paths, they're joined to the root directory.
+pub(crate) fn init(page404: &Path, page50x: &Path, handler_opts: &mut RequestHandlerOpts) {
+    let error_pages = handler_opts
+        .advanced_opts
+        .as_ref()
+        .and_then(|advanced| advanced.error_pages.as_deref());
+    let root_dir = &handler_opts.root_dir;
+    let resolve_path = |path: &Path| {
+        if path.is_relative() && !path.starts_with(root_dir) {
+            root_dir.join(path)
+        } else {
+            path.to_owned()
+        }
+    };
+
+    let page404 = resolve_path(page404);
+    if !page404.is_file() {
+        tracing::debug!(
+            "404 file path not found or not a regular file: {}",
+            page404.display()
+        );
+    }
+    let page50x = resolve_path(page50x);
+    if !page50x.is_file() {
+        tracing::debug!(
+            "50x file path not found or not a regular file: {}",
+            page50x.display()
+        );
+    }
+
+    let mut pages = ErrorPages::new(page404, page50x);
+    for error_page in error_pages.unwrap_or_default() {
+        let path = resolve_path(&error_page.path);
+        if !path.is_file() {
+            tracing::warn!(
+                "error page file path for status {} not found or not a regular file: {}",
+                error_page.status,
+                path.display()
+            );
+        }
+        pages.insert(error_page.status, path);
+    }
+    if let Some(error_pages) = error_pages {
+        tracing::info!("error pages: entries={}", error_pages.len());
+    }
+
+    handler_opts.error_pages = pages;
+}
+
+/// It returns a HTTP error response which also handles the HTML content of the matching error page if any.
 pub fn error_response(
     uri: &Uri,
     method: &Method,
     status_code: &StatusCode,
-    page404: &Path,
-    page50x: &Path,
+    error_pages: &ErrorPages,
 ) -> Result<Response<Body>> {
+    // An ID to correlate the error page with its log entry
+    let request_id = request_id();
     tracing::warn!(
         method = ?method, uri = ?uri, status = status_code.as_u16(),
-        error = status_code.canonical_reason().unwrap_or_default()
+        error = status_code.canonical_reason().unwrap_or_default(),
+        request_id = request_id
     );
 
-    // Check for 4xx/50x status codes and handle their corresponding HTML content
+    // Check for the most specific error page of the 4xx/5xx status codes
     let mut page_content = String::new();
-    let status_code = match status_code {
-        // 4xx
-        &StatusCode::BAD_REQUEST
-        | &StatusCode::UNAUTHORIZED
-        | &StatusCode::PAYMENT_REQUIRED
-        | &StatusCode::FORBIDDEN
-        | &StatusCode::NOT_FOUND
-        | &StatusCode::METHOD_NOT_ALLOWED
-        | &StatusCode::NOT_ACCEPTABLE
-        | &StatusCode::PROXY_AUTHENTICATION_REQUIRED
-        | &StatusCode::REQUEST_TIMEOUT
-        | &StatusCode::CONFLICT
-        | &StatusCode::GONE
-        | &StatusCode::LENGTH_REQUIRED
-        | &StatusCode::PRECONDITION_FAILED
-        | &StatusCode::PAYLOAD_TOO_LARGE
-        | &StatusCode::URI_TOO_LONG
-        | &StatusCode::UNSUPPORTED_MEDIA_TYPE
-        | &StatusCode::RANGE_NOT_SATISFIABLE
-        | &StatusCode::EXPECTATION_FAILED => {
-            // Extra check for 404 status code and its HTML content
-            if status_code == &StatusCode::NOT_FOUND {
-                if page404.is_file() {
-                    String::from_utf8_lossy(&helpers::read_bytes_default(page404))
-                        .trim()
-                        .clone_into(&mut page_content);
-                } else {
-                    tracing::debug!(
-                        "page404 file path not found or not a regular file: {}",
-                        page404.display()
-                    );
-                }
-            }
-            status_code
-        }
-        // 50x
-        &StatusCode::INTERNAL_SERVER_ERROR
-        | &StatusCode::NOT_IMPLEMENTED
-        | &StatusCode::BAD_GATEWAY
-        | &StatusCode::SERVICE_UNAVAILABLE
-        | &StatusCode::GATEWAY_TIMEOUT
-        | &StatusCode::HTTP_VERSION_NOT_SUPPORTED
-        | &StatusCode::VARIANT_ALSO_NEGOTIATES
-        | &StatusCode::INSUFFICIENT_STORAGE
-        | &StatusCode::LOOP_DETECTED => {
-            // HTML content check for status codes 50x
-            if page50x.is_file() {
-                String::from_utf8_lossy(&helpers::read_bytes_default(page50x))
-                    .trim()
-                    .clone_into(&mut page_content);
-            } else {
-                tracing::debug!(
-                    "page50x file path not found or not a regular file: {}",
-                    page50x.display()
-                );
-            }
-            status_code
+    if status_code.is_client_error() || status_code.is_server_error() {
+        if let Some(page) = error_pages.resolve(status_code) {
+            let content = helpers::read_bytes_default(page);
+            let content = String::from_utf8_lossy(&content);
+            page_content = render(content.trim(), uri, status_code, &request_id);
         }
-        // other status codes
-        _ => status_code,
-    };
+    }
 
     if page_content.is_empty() {
         let reason = status_code.canonical_reason().unwrap_or_default();
@@ -130,6 +193,166 @@ pub fn error_response(
         .typed_insert(ContentType::from(mime::TEXT_HTML_UTF_8));
     resp.headers_mut().typed_insert(ContentLength(len));
     resp.headers_mut().typed_insert(AcceptRanges::bytes());
+    content_type_options::append_generated(&mut resp);
 
     Ok(resp)
 }
+
+/// Replaces the placeholders of an error page with the request context.
+fn render(content: &str, uri: &Uri, status_code: &StatusCode, request_id: &str) -> String {
+    if !content.contains("{{") {
+        return content.to_owned();
+    }
+
+    let reason = status_code.canonical_reason().unwrap_or_default();
+    let path = html! { (uri.path()) }.into_string();
+    content
+        .replace(PLACEHOLDER_STATUS, status_code.as_str())
+        .replace(PLACEHOLDER_REASON, reason)
+        .replace(PLACEHOLDER_PATH, &path)
+        .replace(PLACEHOLDER_REQUEST_ID, request_id)
+        .replace(PLACEHOLDER_TIMESTAMP, &helpers::rfc3339_now())
+}
+
+/// Generates a unique request ID made of 16 hexadecimal characters.
+fn request_id() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(REQUEST_ID_COUNTER.fetch_add(1, Ordering::Relaxed));
+    format!("{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorPages, error_response, render, request_id};
+    use crate::settings::ErrorPageStatus;
+    use hyper::{Method, StatusCode, Uri};
+    use std::path::{Path, PathBuf};
+
+    const PAGE_404: &str = "tests/fixtures/public/404.html";
+    const PAGE_50X: &str = "tests/fixtures/public/50x.html";
+    const PAGE_4XX: &str = "tests/fixtures/error_pages/4xx.html";
+    const PAGE_410: &str = "tests/fixtures/error_pages/410.html";
+
+    fn make_pages() -> ErrorPages {
+        let mut pages = ErrorPages::new(PathBuf::from(PAGE_404), PathBuf::from(PAGE_50X));
+        pages.insert(ErrorPageStatus::Class(4), PathBuf::from(PAGE_4XX));
+        pages.insert(
+            ErrorPageStatus::Code(StatusCode::GONE),
+            PathBuf::from(PAGE_410),
+        );
+        pages.insert(
+            ErrorPageStatus::Code(StatusCode::SERVICE_UNAVAILABLE),
+            PathBuf::from("tests/fixtures/error_pages/missing.html"),
+        );
+        pages
+    }
+
+    #[test]
+    fn test_resolve_most_specific_page() {
+        let pages = make_pages();
+        let resolve = |status: StatusCode| pages.resolve(&status);
+
+        assert_eq!(resolve(StatusCode::NOT_FOUND), Some(Path::new(PAGE_404)));
+        assert_eq!(resolve(StatusCode::GONE), Some(Path::new(PAGE_410)));
+        assert_eq!(
+            resolve(StatusCode::TOO_MANY_REQUESTS),
+            Some(Path::new(PAGE_4XX))
+        );
+        assert_eq!(
+            resolve(StatusCode::INTERNAL_SERVER_ERROR),
+            Some(Path::new(PAGE_50X))
+        );
+        assert_eq!(resolve(StatusCode::MOVED_PERMANENTLY), None);
+    }
+
+    #[test]
+    fn test_resolve_missing_page() {
+        // The status class page is used when the status code page doesn't exist
+        let pages = make_pages();
+        assert_eq!(
+            pages.resolve(&StatusCode::SERVICE_UNAVAILABLE),
+            Some(Path::new(PAGE_50X))
+        );
+
+        let mut pages = ErrorPages::new(PathBuf::from("404.html"), PathBuf::from("50x.html"));
+        assert_eq!(pages.resolve(&StatusCode::NOT_FOUND), None);
+        pages.insert(ErrorPageStatus::Class(4), PathBuf::from(PAGE_4XX));
+        assert_eq!(
+            pages.resolve(&StatusCode::NOT_FOUND),
+            Some(Path::new(PAGE_4XX))
+        );
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let parse = |value: &str| value.parse::<ErrorPageStatus>().ok();
+        assert_eq!(
+            parse("429"),
+            Some(ErrorPageStatus::Code(StatusCode::TOO_MANY_REQUESTS))
+        );
+        assert_eq!(parse("4xx"), Some(ErrorPageStatus::Class(4)));
+        assert_eq!(parse("5XX"), Some(ErrorPageStatus::Class(5)));
+        assert_eq!(parse("301"), None);
+        assert_eq!(parse("3xx"), None);
+        assert_eq!(parse("600"), None);
+        assert_eq!(parse("not-found"), None);
+    }
+
+    #[test]
+    fn test_replace_page() {
+        let mut pages = make_pages();
+        pages.insert(
+            ErrorPageStatus::Code(StatusCode::NOT_FOUND),
+            PathBuf::from(PAGE_410),
+        );
+        assert_eq!(
+            pages.resolve(&StatusCode::NOT_FOUND),
+            Some(Path::new(PAGE_410))
+        );
+    }
+
+    async fn body(pages: &ErrorPages, status: StatusCode) -> String {
+        let uri = Uri::from_static("/missing");
+        let resp = error_response(&uri, &Method::GET, &status, pages).unwrap();
+        assert_eq!(resp.status(), status);
+        let bytes = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_error_response_page_content() {
+        let pages = ErrorPages::new(PathBuf::from(PAGE_404), PathBuf::from(PAGE_50X));
+
+        let page404 = std::fs::read_to_string(PAGE_404).unwrap();
+        assert_eq!(body(&pages, StatusCode::NOT_FOUND).await, page404.trim());
+        let page50x = std::fs::read_to_string(PAGE_50X).unwrap();
+        assert_eq!(body(&pages, StatusCode::BAD_GATEWAY).await, page50x.trim());
+        // Generated page
+        let content = body(&pages, StatusCode::FORBIDDEN).await;
+        assert!(content.contains("<h1>403 Forbidden</h1>"));
+    }
+
+    #[test]
+    fn test_render_placeholders() {
+        let uri = Uri::from_static("/docs/a&b?page=1");
+        let content = "<h1>{{status}} {{reason}}</h1><p>{{path}}</p><small>{{request_id}}</small>";
+        assert_eq!(
+            render(content, &uri, &StatusCode::NOT_FOUND, "0123456789abcdef"),
+            "<h1>404 Not Found</h1><p>/docs/a&amp;b</p><small>0123456789abcdef</small>"
+        );
+
+        let content = render("<p>{{timestamp}}</p>", &uri, &StatusCode::NOT_FOUND, "");
+        assert!(content.starts_with("<p>20") && content.ends_with("Z</p>"));
+        // Unknown placeholders are left untouched
+        let content = "<p>{{user}}</p>";
+        assert_eq!(render(content, &uri, &StatusCode::NOT_FOUND, ""), content);
+    }
+
+    #[test]
+    fn test_request_id() {
+        let (id1, id2) = (request_id(), request_id());
+        assert_eq!(id1.len(), 16);
+        assert!(id1.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(id1, id2);
+    }
+}
@@ -5,32 +5,131 @@
 
 //! Fallback page module useful for a custom page default.
 //!
+//! Fallback pages can also be assigned to request paths matching glob patterns,
+//! which allows to serve multiple single-page applications under the same root.
+//!
 
+use globset::GlobMatcher;
 use headers::{AcceptRanges, ContentLength, ContentType, HeaderMapExt};
-use hyper::{Body, Request, Response, StatusCode};
+use hyper::{
+    Body, Method, Request, Response, StatusCode,
+    header::{ACCEPT, HeaderValue, VARY},
+};
 use mime_guess::mime;
 use std::path::Path;
 
-use crate::{Error, handler::RequestHandlerOpts, helpers, http_ext::MethodExt};
+use crate::{
+    Error, Result, content_type_options, handler::RequestHandlerOpts, headers_ext::Accept, helpers,
+    settings::Advanced,
+};
+
+/// Response header used to mark a fallback page response as a soft 404 error.
+const SOFT_404_HEADER: &str = "x-soft-404";
+
+/// A fallback page used for the request paths matching a glob pattern.
+#[derive(Clone)]
+pub struct FallbackPage {
+    /// Source pattern glob matcher of the request paths.
+    pub source: GlobMatcher,
+    /// Content of the fallback page.
+    pub content: Vec<u8>,
+}
 
 /// Initializes fallback page processing
-pub(crate) fn init(file_path: &Path, handler_opts: &mut RequestHandlerOpts) {
+pub(crate) fn init(
+    file_path: &Path,
+    soft404: bool,
+    status: StatusCode,
+    methods: &[String],
+    html_only: bool,
+    handler_opts: &mut RequestHandlerOpts,
+) -> Result {
+    if !matches!(status, StatusCode::OK | StatusCode::NOT_FOUND) {
+        bail!(
+            "fallback page status code should be either 200 or 404 but got {}",
+            status.as_u16()
+        );
+    }
     let found = file_path.is_file();
     if found {
-        handler_opts.page_fallback =
-            String::from_utf8_lossy(&helpers::read_bytes_default(file_path))
-                .trim()
-                .as_bytes()
-                .to_owned();
+        handler_opts.page_fallback = read_page(file_path);
     } else {
         tracing::debug!("fallback page path not found or not a regular file");
     }
 
+    handler_opts.page_fallback_soft404 = soft404;
+    handler_opts.page_fallback_status = status;
+    handler_opts.page_fallback_methods = parse_methods(methods)?;
+    handler_opts.page_fallback_html_only = html_only;
+    handler_opts.page_fallbacks = fallback_pages(handler_opts.advanced_opts.as_ref());
+
     tracing::info!(
-        "fallback page: enabled={}, value=\"{}\"",
+        "fallback page: enabled={}, value=\"{}\", soft_404={}, status={}, methods={:?}, html_only={}, path_entries={}",
         found,
-        file_path.display()
+        file_path.display(),
+        soft404,
+        status.as_u16(),
+        handler_opts.page_fallback_methods,
+        html_only,
+        handler_opts.page_fallbacks.len()
     );
+    Ok(())
+}
+
+/// Parses the request methods the fallback page applies to, either `GET` or `HEAD`.
+pub(crate) fn parse_methods(methods: &[String]) -> Result<Vec<Method>> {
+    methods
+        .iter()
+        .map(|method| match method.trim().to_ascii_uppercase().as_str() {
+            "GET" => Ok(Method::GET),
+            "HEAD" => Ok(Method::HEAD),
+            _ => bail!("fallback page method should be either GET or HEAD but got {method}"),
+        })
+        .collect()
+}
+
+/// Reads the fallback pages of the `advanced` options, skipping the ones whose file doesn't exist.
+pub(crate) fn fallback_pages(advanced_opts: Option<&Advanced>) -> Vec<FallbackPage> {
+    let entries = advanced_opts.and_then(|advanced| advanced.fallback_pages.as_deref());
+    entries
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|entry| {
+            if !entry.path.is_file() {
+                tracing::warn!(
+                    "fallback page file path for source {} not found or not a regular file: {}",
+                    entry.source.glob(),
+                    entry.path.display()
+                );
+                return None;
+            }
+            Some(FallbackPage {
+                source: entry.source.clone(),
+                content: read_page(&entry.path),
+            })
+        })
+        .collect()
+}
+
+/// Reads the trimmed content of a fallback page file.
+fn read_page(file_path: &Path) -> Vec<u8> {
+    String::from_utf8_lossy(&helpers::read_bytes_default(file_path))
+        .trim()
+        .as_bytes()
+        .to_owned()
+}
+
+/// Gets the fallback page of the first entry matching the request path,
+/// otherwise the default fallback page if configured.
+fn find_page<'a>(opts: &'a RequestHandlerOpts, uri_path: &str) -> Option<&'a [u8]> {
+    let page = opts
+        .page_fallbacks
+        .iter()
+        .find(|page| page.source.is_match(uri_path))
+        .map_or(opts.page_fallback.as_slice(), |page| {
+            page.content.as_slice()
+        });
+    (!page.is_empty()).then_some(page)
 }
 
 /// Replace 404 Not Found by the configured fallback page
@@ -39,16 +138,57 @@ pub(crate) fn post_process<T>(
     req: &Request<T>,
     resp: Response<Body>,
 ) -> Result<Response<Body>, Error> {
-    Ok(
-        if req.method().is_get()
-            && resp.status() == StatusCode::NOT_FOUND
-            && !opts.page_fallback.is_empty()
-        {
-            fallback_response(&opts.page_fallback)
-        } else {
-            resp
-        },
-    )
+    if !(opts.page_fallback_methods.contains(req.method())
+        && resp.status() == StatusCode::NOT_FOUND)
+    {
+        return Ok(resp);
+    }
+    let Some(page) = find_page(opts, req.uri().path()) else {
+        return Ok(resp);
+    };
+
+    if opts.page_fallback_html_only {
+        let accepts_html = req
+            .headers()
+            .typed_get::<Accept>()
+            .is_some_and(|accept| accept.accepts_html());
+
+        // The response varies depending on the accepted content types
+        if !accepts_html {
+            let mut resp = resp;
+            let value = resp
+                .headers()
+                .get(VARY)
+                .map_or(HeaderValue::from_name(ACCEPT), |h| {
+                    let mut s = h.to_str().unwrap_or_default().to_owned();
+                    s.push(',');
+                    s.push_str(ACCEPT.as_str());
+                    HeaderValue::from_str(s.as_str()).unwrap()
+                });
+            resp.headers_mut().insert(VARY, value);
+            return Ok(resp);
+        }
+    }
+
+    let mut resp = fallback_response(page);
+    *resp.status_mut() = opts.page_fallback_status;
+    if opts.page_fallback_html_only {
+        resp.headers_mut()
+            .insert(VARY, HeaderValue::from_name(ACCEPT));
+    }
+
+    // Mark the response as a soft 404 since the requested path doesn't exist
+    if opts.page_fallback_soft404 && opts.page_fallback_status == StatusCode::OK {
+        tracing::info!(
+            "fallback page served: method={} uri={} soft_404=true",
+            req.method(),
+            req.uri()
+        );
+        resp.headers_mut()
+            .insert(SOFT_404_HEADER, HeaderValue::from_static("true"));
+    }
+
+    Ok(resp)
 }
 
 /// Checks if a fallback response can be generated, i.e. if it is a `GET` request
@@ -65,16 +205,18 @@ pub fn fallback_response(page_fallback: &[u8]) -> Response<Body> {
     resp.headers_mut()
         .typed_insert(ContentType::from(mime::TEXT_HTML_UTF_8));
     resp.headers_mut().typed_insert(AcceptRanges::bytes());
+    content_type_options::append_generated(&mut resp);
 
     resp
 }
 
 #[cfg(test)]
 mod tests {
-    use super::post_process;
-    use crate::{Error, error_page, handler::RequestHandlerOpts};
+    use super::{FallbackPage, parse_methods, post_process};
+    use crate::error_page::{self, ErrorPages};
+    use crate::{Error, handler::RequestHandlerOpts};
+    use globset::Glob;
     use hyper::{Body, Method, Request, Response, StatusCode, Uri};
-    use std::path::PathBuf;
 
     fn make_request(method: &str) -> Request<Body> {
         Request::builder()
@@ -89,8 +231,7 @@ mod tests {
             &Uri::try_from("/").unwrap(),
             &Method::GET,
             status,
-            &PathBuf::new(),
-            &PathBuf::new(),
+            &ErrorPages::default(),
         )
         .unwrap()
     }
@@ -194,7 +335,120 @@ mod tests {
                 .unwrap_or("3"),
             "3"
         );
+        assert!(!resp.headers().contains_key("X-Soft-404"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fallback_soft404() -> Result<(), Error> {
+        let opts = RequestHandlerOpts {
+            page_fallback: vec![1, 2, 3],
+            page_fallback_soft404: true,
+            ..Default::default()
+        };
+        let req = make_request("GET");
+        let resp = make_response(&StatusCode::NOT_FOUND);
+
+        let resp = post_process(&opts, &req, resp)?;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["X-Soft-404"], "true");
+
+        // Existing files are not marked
+        let resp = post_process(&opts, &req, make_response(&StatusCode::OK))?;
+        assert!(!resp.headers().contains_key("X-Soft-404"));
 
         Ok(())
     }
+
+    #[test]
+    fn test_fallback_paths() -> Result<(), Error> {
+        let page = |source: &str, content: &[u8]| FallbackPage {
+            source: Glob::new(source).unwrap().compile_matcher(),
+            content: content.to_vec(),
+        };
+        let mut opts = RequestHandlerOpts {
+            page_fallbacks: vec![page("/app1/**", b"app1"), page("/app2/**", b"app2")],
+            ..Default::default()
+        };
+        let fallback = |opts: &RequestHandlerOpts, uri: &str| {
+            let req = Request::get(uri).body(Body::empty()).unwrap();
+            post_process(opts, &req, make_response(&StatusCode::NOT_FOUND))
+        };
+
+        for (uri, len) in [("/app1/users/1", "4"), ("/app2/settings", "4")] {
+            let resp = fallback(&opts, uri)?;
+            assert_eq!(resp.status(), StatusCode::OK);
+            assert_eq!(resp.headers()["Content-Length"], len);
+        }
+
+        // Other paths use the default fallback page if any
+        let resp = fallback(&opts, "/other")?;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        opts.page_fallback = vec![1, 2, 3];
+        let resp = fallback(&opts, "/other")?;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["Content-Length"], "3");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fallback_status_methods() -> Result<(), Error> {
+        let opts = RequestHandlerOpts {
+            page_fallback: vec![1, 2, 3],
+            page_fallback_status: StatusCode::NOT_FOUND,
+            page_fallback_methods: vec![Method::GET, Method::HEAD],
+            page_fallback_soft404: true,
+            ..Default::default()
+        };
+
+        for method in ["GET", "HEAD"] {
+            let req = make_request(method);
+            let resp = post_process(&opts, &req, make_response(&StatusCode::NOT_FOUND))?;
+            assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+            assert_eq!(resp.headers()["Content-Length"], "3");
+            assert!(!resp.headers().contains_key("X-Soft-404"));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fallback_html_only() -> Result<(), Error> {
+        let opts = RequestHandlerOpts {
+            page_fallback: vec![1, 2, 3],
+            page_fallback_html_only: true,
+            ..Default::default()
+        };
+        let fallback = |accept: Option<&str>| {
+            let mut req = make_request("GET");
+            if let Some(accept) = accept {
+                req.headers_mut().insert("accept", accept.parse().unwrap());
+            }
+            post_process(&opts, &req, make_response(&StatusCode::NOT_FOUND))
+        };
+
+        let resp = fallback(Some("text/html,application/xhtml+xml,*/*;q=0.8"))?;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["Vary"], "accept");
+
+        for accept in [Some("application/json"), Some("*/*"), None] {
+            let resp = fallback(accept)?;
+            assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+            assert_eq!(resp.headers()["Vary"], "accept");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_methods() {
+        let methods = ["get".to_owned(), " HEAD".to_owned()];
+        assert_eq!(
+            parse_methods(&methods).unwrap(),
+            vec![Method::GET, Method::HEAD]
+        );
+        assert!(parse_methods(&["POST".to_owned()]).is_err());
+    }
 }
@@ -0,0 +1,518 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that serves the files of a `.tar` or `.zip` archive given as the root directory.
+//!
+//! The archive members are indexed once at startup, then every request reads the byte range
+//! of its member straight from the archive file, without extracting it.
+//! Only uncompressed `.tar` archives and `.zip` archives of stored or deflated members are supported.
+//!
+
+use async_compression::tokio::bufread::DeflateDecoder;
+use bytes::Bytes;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, BufReader};
+use tokio_util::io::ReaderStream;
+
+use crate::fs::vfs::{Filesystem, VfsDirEntry, VfsFuture, VfsMetadata, VfsStream};
+use crate::{Context, Result, handler::RequestHandlerOpts};
+
+/// Size of a tar header and data block.
+const TAR_BLOCK_SIZE: u64 = 512;
+
+/// Reader of the contents of an archive member.
+type MemberReader = std::pin::Pin<Box<dyn AsyncRead + Send>>;
+
+/// Checks if the given root path is an archive file served as the root directory.
+pub(crate) fn is_archive(path: &Path) -> bool {
+    let is_archive_ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("tar") || ext.eq_ignore_ascii_case("zip"));
+    is_archive_ext && path.is_file()
+}
+
+/// Initializes the archive served as the root directory if the root path is an archive file.
+pub(crate) fn init(root: &Path, handler_opts: &mut RequestHandlerOpts) -> Result {
+    if !is_archive(root) {
+        return Ok(());
+    }
+    let archive = ArchiveFilesystem::open(root)
+        .with_context(|| format!("unable to index the root archive {}", root.display()))?;
+    tracing::info!(
+        "root archive: path={}, members={}",
+        root.display(),
+        archive.len()
+    );
+    handler_opts.root_filesystem = Some(Arc::new(archive));
+    Ok(())
+}
+
+/// Location of a member within an archive file.
+#[derive(Clone, Debug)]
+struct Member {
+    /// Offset of the member data in the archive file.
+    offset: u64,
+    /// Size of the member data in the archive file.
+    stored_len: u64,
+    /// Size of the member contents.
+    len: u64,
+    /// Whether the member data is compressed with deflate.
+    deflated: bool,
+    /// Last modification time if known.
+    modified: Option<SystemTime>,
+}
+
+impl Member {
+    /// Returns the metadata of the member.
+    fn metadata(&self) -> VfsMetadata {
+        VfsMetadata {
+            is_dir: false,
+            len: self.len,
+            modified: self.modified,
+        }
+    }
+}
+
+/// [`Filesystem`] of the members of a `.tar` or `.zip` archive file.
+#[derive(Debug)]
+pub struct ArchiveFilesystem {
+    path: PathBuf,
+    members: HashMap<String, Member>,
+    dirs: HashSet<String>,
+}
+
+impl ArchiveFilesystem {
+    /// Opens an archive file and indexes its members, its format is detected by its extension.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let mut archive = Self {
+            path,
+            members: HashMap::new(),
+            dirs: HashSet::new(),
+        };
+        let mut file = std::fs::File::open(&archive.path)?;
+        let is_zip = archive
+            .path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+        if is_zip {
+            archive.index_zip(file)?;
+        } else {
+            archive.index_tar(&mut file)?;
+        }
+        Ok(archive)
+    }
+
+    /// Returns the number of files of the archive.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Checks if the archive has no files.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Adds a file or a directory along with its parent directories to the index.
+    /// Members with a path escaping the archive root are skipped.
+    fn insert(&mut self, path: &str, member: Option<Member>) {
+        let mut names = Vec::new();
+        for name in path.split('/') {
+            match name {
+                "" | "." => continue,
+                ".." => {
+                    tracing::warn!("archive member {path} escapes the archive root, skipped");
+                    return;
+                }
+                name => names.push(name),
+            }
+        }
+        if names.is_empty() {
+            return;
+        }
+        for depth in 1..names.len() {
+            self.dirs.insert(names[..depth].join("/"));
+        }
+        let path = names.join("/");
+        match member {
+            Some(member) => {
+                self.members.insert(path, member);
+            }
+            None => {
+                self.dirs.insert(path);
+            }
+        }
+    }
+
+    /// Indexes the members of a `.zip` archive.
+    fn index_zip(&mut self, file: std::fs::File) -> io::Result<()> {
+        let mut zip = zip::ZipArchive::new(file).map_err(io::Error::other)?;
+        for index in 0..zip.len() {
+            let entry = zip.by_index_raw(index).map_err(io::Error::other)?;
+            if entry.is_dir() {
+                self.insert(entry.name(), None);
+                continue;
+            }
+            let deflated = match entry.compression() {
+                zip::CompressionMethod::Stored => false,
+                zip::CompressionMethod::Deflated => true,
+                method => {
+                    tracing::warn!(
+                        "archive member {} uses the unsupported {method} compression, skipped",
+                        entry.name()
+                    );
+                    continue;
+                }
+            };
+            if entry.encrypted() {
+                tracing::warn!("archive member {} is encrypted, skipped", entry.name());
+                continue;
+            }
+            let member = Member {
+                offset: entry.data_start(),
+                stored_len: entry.compressed_size(),
+                len: entry.size(),
+                deflated,
+                modified: entry.last_modified().and_then(zip_modified),
+            };
+            let name = entry.name().to_owned();
+            self.insert(&name, Some(member));
+        }
+        Ok(())
+    }
+
+    /// Indexes the members of an uncompressed `.tar` archive,
+    /// including the GNU and PAX long paths.
+    fn index_tar(&mut self, file: &mut std::fs::File) -> io::Result<()> {
+        let archive_len = file.metadata()?.len();
+        let mut offset = 0;
+        let mut long_path = None;
+        let mut header = [0u8; TAR_BLOCK_SIZE as usize];
+        while offset + TAR_BLOCK_SIZE <= archive_len {
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut header)?;
+            // The archive ends with zero blocks
+            if header.iter().all(|byte| *byte == 0) {
+                break;
+            }
+
+            let len = tar_number(&header[124..136])?;
+            let data_offset = offset + TAR_BLOCK_SIZE;
+            offset = data_offset + len.div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE;
+            match header[156] {
+                // GNU long path of the next member
+                b'L' => {
+                    let data = read_data(file, data_offset, len)?;
+                    long_path = Some(tar_string(&data).to_owned());
+                }
+                // PAX extended header of the next member
+                b'x' => long_path = pax_path(&read_data(file, data_offset, len)?),
+                // Regular file
+                0 | b'0' | b'7' => {
+                    let path = long_path.take().unwrap_or_else(|| tar_path(&header));
+                    let member = Member {
+                        offset: data_offset,
+                        stored_len: len,
+                        len,
+                        deflated: false,
+                        modified: tar_number(&header[136..148])
+                            .ok()
+                            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+                    };
+                    if path.ends_with('/') {
+                        self.insert(&path, None);
+                    } else {
+                        self.insert(&path, Some(member));
+                    }
+                }
+                b'5' => {
+                    let path = long_path.take().unwrap_or_else(|| tar_path(&header));
+                    self.insert(&path, None);
+                }
+                // Links and other special members are not served
+                _ => long_path = None,
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the member of a file path.
+    fn member(&self, path: &str) -> io::Result<&Member> {
+        self.members.get(path).ok_or_else(|| not_found(path))
+    }
+
+    /// Returns the metadata of a file or directory if any.
+    fn metadata_of(&self, path: &str) -> Option<VfsMetadata> {
+        match self.members.get(path) {
+            Some(member) => Some(member.metadata()),
+            None if path.is_empty() || self.dirs.contains(path) => Some(VfsMetadata {
+                is_dir: true,
+                ..Default::default()
+            }),
+            None => None,
+        }
+    }
+
+    /// Opens a reader of a range of the contents of a member.
+    async fn reader(&self, member: &Member, range: Range<u64>) -> io::Result<MemberReader> {
+        let start = range.start.min(member.len);
+        let len = range.end.clamp(start, member.len) - start;
+        let mut file = tokio::fs::File::open(&self.path).await?;
+        if !member.deflated {
+            file.seek(SeekFrom::Start(member.offset + start)).await?;
+            return Ok(Box::pin(file.take(len)));
+        }
+
+        // The deflated data is inflated from its beginning up to the range start
+        file.seek(SeekFrom::Start(member.offset)).await?;
+        let mut decoder = DeflateDecoder::new(BufReader::new(file.take(member.stored_len)));
+        let skipped =
+            tokio::io::copy(&mut (&mut decoder).take(start), &mut tokio::io::sink()).await?;
+        if skipped < start {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "archive member is truncated",
+            ));
+        }
+        Ok(Box::pin(decoder.take(len)))
+    }
+}
+
+impl Filesystem for ArchiveFilesystem {
+    fn metadata<'a>(&'a self, path: &'a str) -> VfsFuture<'a, VfsMetadata> {
+        Box::pin(async move { self.metadata_of(path).ok_or_else(|| not_found(path)) })
+    }
+
+    fn open<'a>(&'a self, path: &'a str) -> VfsFuture<'a, VfsStream> {
+        Box::pin(async move {
+            let member = self.member(path)?;
+            let reader = self.reader(member, 0..member.len).await?;
+            Ok(Box::pin(ReaderStream::new(reader)) as VfsStream)
+        })
+    }
+
+    fn read_range<'a>(&'a self, path: &'a str, range: Range<u64>) -> VfsFuture<'a, Bytes> {
+        Box::pin(async move {
+            let mut reader = self.reader(self.member(path)?, range).await?;
+            let mut buf = Vec::new();
+            reader.read_to_end(&mut buf).await?;
+            Ok(buf.into())
+        })
+    }
+
+    fn read_dir<'a>(&'a self, path: &'a str) -> VfsFuture<'a, Vec<VfsDirEntry>> {
+        Box::pin(async move {
+            if self.members.contains_key(path) || self.metadata_of(path).is_none() {
+                return Err(not_found(path));
+            }
+            let dirs = self.dirs.iter().map(|dir| {
+                let meta = VfsMetadata {
+                    is_dir: true,
+                    ..Default::default()
+                };
+                (dir, meta)
+            });
+            let files = self
+                .members
+                .iter()
+                .map(|(path, member)| (path, member.metadata()));
+            Ok(dirs
+                .chain(files)
+                .filter_map(|(entry_path, metadata)| {
+                    let (parent, name) = entry_path.rsplit_once('/').unwrap_or(("", entry_path));
+                    (parent == path).then(|| VfsDirEntry {
+                        name: name.to_owned(),
+                        metadata,
+                    })
+                })
+                .collect())
+        })
+    }
+}
+
+/// Error of an archive member path not found.
+fn not_found(path: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("archive member {path} not found"),
+    )
+}
+
+/// Converts the modification time of a zip member, which has no time zone, taken as UTC.
+fn zip_modified(time: zip::DateTime) -> Option<SystemTime> {
+    let date = chrono::NaiveDate::from_ymd_opt(
+        time.year().into(),
+        time.month().into(),
+        time.day().into(),
+    )?;
+    let datetime = date.and_hms_opt(
+        time.hour().into(),
+        time.minute().into(),
+        time.second().into(),
+    )?;
+    Some(datetime.and_utc().into())
+}
+
+/// Reads the data of a tar member.
+fn read_data(file: &mut std::fs::File, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::new();
+    file.take(len).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Returns a string of a tar header field, terminated by the first NUL byte if any.
+fn tar_string(field: &[u8]) -> &str {
+    let end = field
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(field.len());
+    std::str::from_utf8(&field[..end]).unwrap_or_default()
+}
+
+/// Parses a numeric tar header field, either in octal or in the GNU base-256 encoding.
+fn tar_number(field: &[u8]) -> io::Result<u64> {
+    if field.first().is_some_and(|byte| byte & 0x80 != 0) {
+        let number = field[1..]
+            .iter()
+            .fold(0u64, |number, byte| (number << 8) | u64::from(*byte));
+        return Ok(number);
+    }
+    let digits = tar_string(field).trim_matches(|c: char| c == ' ' || c == '\0');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(digits, 8)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid tar header number"))
+}
+
+/// Returns the path of a tar header, prefixed by the ustar prefix field if any.
+fn tar_path(header: &[u8]) -> String {
+    let name = tar_string(&header[..100]);
+    let prefix = if &header[257..262] == b"ustar" {
+        tar_string(&header[345..500])
+    } else {
+        ""
+    };
+    if prefix.is_empty() {
+        name.to_owned()
+    } else {
+        [prefix, "/", name].concat()
+    }
+}
+
+/// Returns the `path` record of a PAX extended header if any.
+/// Each record has the `<length> <key>=<value>\n` format.
+fn pax_path(data: &[u8]) -> Option<String> {
+    let mut rest = data;
+    while !rest.is_empty() {
+        let space = rest.iter().position(|byte| *byte == b' ')?;
+        let len: usize = std::str::from_utf8(&rest[..space]).ok()?.parse().ok()?;
+        let record = rest.get(space + 1..len)?;
+        let record = record.strip_suffix(b"\n").unwrap_or(record);
+        if let Some(path) = record.strip_prefix(b"path=") {
+            return Some(String::from_utf8_lossy(path).into_owned());
+        }
+        rest = &rest[len..];
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArchiveFilesystem, pax_path, tar_number};
+    use crate::fs::vfs::Filesystem;
+    use futures_util::TryStreamExt;
+    use std::io;
+
+    #[test]
+    fn test_tar_fields() {
+        assert_eq!(tar_number(b"00000001750\0").unwrap(), 1000);
+        assert_eq!(tar_number(b"        \0").unwrap(), 0);
+        assert_eq!(
+            tar_number(&[0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x00]).unwrap(),
+            256
+        );
+        assert!(tar_number(b"9\0").is_err());
+        assert_eq!(
+            pax_path(b"20 mtime=1700000000\n20 path=docs/a.html\n").as_deref(),
+            Some("docs/a.html")
+        );
+        assert_eq!(pax_path(b"20 mtime=1700000000\n"), None);
+    }
+
+    async fn check_archive(path: &str) {
+        let archive = ArchiveFilesystem::open(path).unwrap();
+        assert_eq!(archive.len(), 5);
+
+        let meta = archive.metadata("index.html").await.unwrap();
+        assert!(!meta.is_dir);
+        assert!(meta.modified.is_some());
+        let contents = archive.read_range("index.html", 0..meta.len).await.unwrap();
+        assert!(contents.starts_with(b"<!DOCTYPE html>"));
+        let stream = archive.open("index.html").await.unwrap();
+        let chunks: Vec<_> = stream.try_collect().await.unwrap();
+        assert_eq!(chunks.concat(), contents);
+        let part = archive.read_range("index.html", 5..12).await.unwrap();
+        assert_eq!(part, contents.slice(5..12));
+        let part = archive
+            .read_range("index.html", meta.len - 3..meta.len + 10)
+            .await;
+        assert_eq!(part.unwrap(), contents.slice(meta.len as usize - 3..));
+
+        assert!(archive.metadata("").await.unwrap().is_dir);
+        assert!(archive.metadata("assets").await.unwrap().is_dir);
+        let meta = archive.metadata("assets/js/main.js").await.unwrap();
+        let contents = archive
+            .read_range("assets/js/main.js", 0..meta.len)
+            .await
+            .unwrap();
+        assert_eq!(contents, "console.log('archive');\n");
+        let long_path = archive
+            .members
+            .keys()
+            .find(|path| path.starts_with("assets/docs/release-notes-"))
+            .unwrap();
+        assert!(long_path.len() > 100);
+
+        let mut names: Vec<_> = archive
+            .read_dir("")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.name, entry.metadata.is_dir))
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                ("assets".to_owned(), true),
+                ("index.html".to_owned(), false),
+                ("page.html".to_owned(), false)
+            ]
+        );
+
+        let err = archive.metadata("missing.html").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(archive.read_range("assets", 0..1).await.is_err());
+        assert!(archive.read_dir("index.html").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tar_archive() {
+        check_archive("tests/fixtures/archives/site.tar").await;
+    }
+
+    #[tokio::test]
+    async fn test_zip_archive() {
+        check_archive("tests/fixtures/archives/site.zip").await;
+    }
+}
@@ -0,0 +1,192 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Crash-safe file writes shared by the features writing to disk.
+//!
+//! Files are written to a temporary file next to the destination which is then renamed
+//! over it, so a crash never leaves a partially written file behind.
+//!
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::{Context, Result};
+
+#[derive(
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    ValueEnum,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+/// Policy of the `fsync` calls made when writing files.
+pub enum FsyncPolicy {
+    /// Sync the file contents and its directory entry, so the write survives a power loss (default).
+    #[default]
+    Full,
+    /// Sync the file contents only.
+    File,
+    /// No sync at all, the writes only survive a crash of the process.
+    Off,
+}
+
+/// Writes the contents to a file atomically, either the previous or the new contents
+/// are found at the file path even if the process crashes in the meantime.
+/// The permissions of an existing file are preserved.
+pub fn write(path: &Path, contents: &[u8], fsync: FsyncPolicy) -> Result {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("invalid file path to write: {}", path.display()))?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let temp_path = dir.join(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = write_temp(&temp_path, path, contents, fsync).and_then(|()| {
+        fs::rename(&temp_path, path).with_context(|| {
+            format!(
+                "unable to rename the temporary file {} to {}",
+                temp_path.display(),
+                path.display()
+            )
+        })
+    });
+    if result.is_err() {
+        fs::remove_file(&temp_path).ok();
+    }
+    result?;
+
+    if fsync == FsyncPolicy::Full {
+        sync_dir(dir).with_context(|| format!("unable to sync the directory {}", dir.display()))?;
+    }
+    Ok(())
+}
+
+/// Appends the contents to a file in a single write, creating the file if it doesn't exist.
+pub fn append(path: &Path, contents: &[u8], fsync: FsyncPolicy) -> Result {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("unable to open the file {}", path.display()))?;
+    file.write_all(contents)
+        .with_context(|| format!("unable to write to the file {}", path.display()))?;
+    if fsync != FsyncPolicy::Off {
+        file.sync_data()
+            .with_context(|| format!("unable to sync the file {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn write_temp(temp_path: &Path, path: &Path, contents: &[u8], fsync: FsyncPolicy) -> Result {
+    let mut file = File::create(temp_path).with_context(|| {
+        format!(
+            "unable to create the temporary file {}",
+            temp_path.display()
+        )
+    })?;
+    file.write_all(contents).with_context(|| {
+        format!(
+            "unable to write to the temporary file {}",
+            temp_path.display()
+        )
+    })?;
+    if let Ok(meta) = fs::metadata(path) {
+        file.set_permissions(meta.permissions()).ok();
+    }
+    if fsync != FsyncPolicy::Off {
+        file.sync_all().with_context(|| {
+            format!("unable to sync the temporary file {}", temp_path.display())
+        })?;
+    }
+    Ok(())
+}
+
+/// Syncs a directory entries, which is only supported on Unix-like systems.
+fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FsyncPolicy, append, write};
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sws-atomic-{name}-{}", std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_write_replaces_file() {
+        let dir = temp_dir("write");
+        let path = dir.join("sws.toml");
+
+        for fsync in [FsyncPolicy::Full, FsyncPolicy::File, FsyncPolicy::Off] {
+            write(&path, b"[general]\n", fsync).unwrap();
+            assert_eq!(std::fs::read(&path).unwrap(), b"[general]\n");
+            write(&path, b"[advanced]\n", fsync).unwrap();
+            assert_eq!(std::fs::read(&path).unwrap(), b"[advanced]\n");
+        }
+
+        // No temporary files left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_dir("permissions");
+        let path = dir.join("sws.toml");
+        std::fs::write(&path, b"").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+
+        write(&path, b"[general]\n", FsyncPolicy::Full).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_write_invalid_path() {
+        let dir = temp_dir("invalid");
+        assert!(write(&dir.join("missing").join("sws.toml"), b"", FsyncPolicy::Off).is_err());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_append() {
+        let dir = temp_dir("append");
+        let path = dir.join("audit.log");
+        append(&path, b"a\n", FsyncPolicy::Full).unwrap();
+        append(&path, b"b\n", FsyncPolicy::Off).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"a\nb\n");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,302 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module to serve files via memory maps, so their contents are sent from the page cache
+//! without a read system call nor a copy per chunk.
+//!
+//! A memory-mapped file truncated while being read makes the process crash (`SIGBUS`).
+//! So a file is only mapped once a read lease is taken on it (Linux only), which makes the
+//! other processes opening it for writing or truncating it wait until the lease is released,
+//! that is, until the response chunks referencing the mapping are dropped.
+//!
+//! The kernel breaks a lease anyway after `/proc/sys/fs/lease-break-time` seconds, so the
+//! mapping is only used for the chunks produced during half of that time since the lease
+//! was taken, the rest of the range being read from the file.
+//!
+
+use bytes::Bytes;
+use futures_util::Stream;
+use hyper::Body;
+use memmap2::{Mmap, MmapOptions};
+use std::fs::File;
+use std::io::{self, Seek};
+use std::pin::Pin;
+use std::sync::LazyLock;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use crate::handler::RequestHandlerOpts;
+
+/// Minimum size in bytes of the file ranges served via memory maps,
+/// the smaller ones are read at once anyway.
+const MIN_FILE_SIZE: u64 = 16 * 1024;
+
+/// Size of the chunks of a memory-mapped response body.
+const CHUNK_SIZE: usize = 256 * 1024;
+
+/// Default time in seconds the kernel waits for a lease to be released before breaking it.
+const DEFAULT_LEASE_BREAK_TIME: u64 = 45;
+
+/// Maximum time the mapping of a leased file is used to produce chunks.
+static LEASE_HOLD_TIME: LazyLock<Duration> = LazyLock::new(|| {
+    let secs = std::fs::read_to_string("/proc/sys/fs/lease-break-time")
+        .ok()
+        .and_then(|secs| secs.trim().parse().ok())
+        .unwrap_or(DEFAULT_LEASE_BREAK_TIME);
+    Duration::from_secs(secs) / 2
+});
+
+/// Initializes the memory-mapped file serving.
+pub fn init(max_file_size: u64, handler_opts: &mut RequestHandlerOpts) {
+    handler_opts.mmap_max_size = max_file_size;
+    tracing::info!(
+        "memory-mapped files: enabled={}, max file size={max_file_size} bytes",
+        max_file_size > 0
+    );
+}
+
+/// Creates the body of a file range of `len` bytes from the current file position,
+/// served via a memory map if the range size is within the limits and the file can be leased.
+pub fn file_body(file: &File, len: u64, max_file_size: u64) -> Option<Body> {
+    if !(MIN_FILE_SIZE..=max_file_size).contains(&len) {
+        return None;
+    }
+    let mut position = file;
+    let offset = position.stream_position().ok()?;
+    let lease = Lease::take(file.try_clone().ok()?)
+        .inspect_err(|err| tracing::debug!("memory-mapped files: lease error: {err:?}"))
+        .ok()?;
+    let mmap = map(&lease.file, offset, len)
+        .inspect_err(|err| tracing::debug!("memory-mapped files: map error: {err:?}"))
+        .ok()?;
+    Some(Body::wrap_stream(MmapStream {
+        file: file.try_clone().ok()?,
+        offset,
+        end: offset + len,
+        data: Bytes::from_owner(Mapping {
+            mmap,
+            _lease: lease,
+        }),
+        leased_at: Instant::now(),
+    }))
+}
+
+/// Maps a file range into memory.
+#[allow(unsafe_code)]
+fn map(file: &File, offset: u64, len: u64) -> io::Result<Mmap> {
+    let len = usize::try_from(len).map_err(io::Error::other)?;
+    // SAFETY: the mapping is read-only and the file is leased, so it can't be truncated
+    // until the lease is released along with the mapping, or broken (see the module docs).
+    unsafe { MmapOptions::new().offset(offset).len(len).map(file) }
+}
+
+/// Read lease of a file, released when dropped.
+struct Lease {
+    file: File,
+}
+
+impl Lease {
+    /// Takes a read lease on a file opened read-only. It fails if the file is open for
+    /// writing or not owned by the current user (without the `CAP_LEASE` capability).
+    #[cfg(target_os = "linux")]
+    fn take(file: File) -> io::Result<Self> {
+        static IGNORE_SIGIO: std::sync::Once = std::sync::Once::new();
+        // The lease breaks are notified with `SIGIO`, which terminates the process by default,
+        // whereas the leases are released within the lease break time anyway
+        #[allow(unsafe_code)]
+        // SAFETY: no handler is installed, the signal is only ignored
+        IGNORE_SIGIO.call_once(|| unsafe {
+            libc::signal(libc::SIGIO, libc::SIG_IGN);
+        });
+        set_lease(&file, libc::F_RDLCK)?;
+        Ok(Self { file })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn take(_: File) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "file leases are only supported on Linux",
+        ))
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        #[cfg(target_os = "linux")]
+        if let Err(err) = set_lease(&self.file, libc::F_UNLCK) {
+            tracing::debug!("memory-mapped files: lease release error: {err:?}");
+        }
+    }
+}
+
+/// Sets the lease of a file.
+#[cfg(target_os = "linux")]
+#[allow(unsafe_code)]
+fn set_lease(file: &File, lease: libc::c_int) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    // SAFETY: the file descriptor stays open during the call
+    if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_SETLEASE, lease) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Memory-mapped file range, which keeps its file leased.
+struct Mapping {
+    mmap: Mmap,
+    _lease: Lease,
+}
+
+impl AsRef<[u8]> for Mapping {
+    fn as_ref(&self) -> &[u8] {
+        &self.mmap
+    }
+}
+
+/// Stream of the chunks of a memory-mapped file range.
+struct MmapStream {
+    /// File of the mapping, to read the rest of the range once the lease is held for too long.
+    file: File,
+    /// Position in the file of the next chunk.
+    offset: u64,
+    /// End of the range in the file.
+    end: u64,
+    /// Mapped contents not sent yet, empty once the mapping is not used anymore.
+    data: Bytes,
+    /// Time the lease of the file was taken.
+    leased_at: Instant,
+}
+
+impl Stream for MmapStream {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        let len = (pin.end - pin.offset).min(CHUNK_SIZE as u64) as usize;
+        if len == 0 {
+            return Poll::Ready(None);
+        }
+        if !pin.data.is_empty() && pin.leased_at.elapsed() < *LEASE_HOLD_TIME {
+            pin.offset += len as u64;
+            return Poll::Ready(Some(Ok(pin.data.split_to(len))));
+        }
+
+        // The mapping is dropped, so the lease gets released once the sent chunks are
+        // dropped too, before the kernel breaks it
+        pin.data = Bytes::new();
+        let mut chunk = vec![0; len];
+        match read_exact_at(&pin.file, &mut chunk, pin.offset) {
+            Ok(()) => {
+                pin.offset += len as u64;
+                Poll::Ready(Some(Ok(Bytes::from(chunk))))
+            }
+            Err(err) => {
+                pin.offset = pin.end;
+                Poll::Ready(Some(Err(err)))
+            }
+        }
+    }
+}
+
+/// Reads the exact number of bytes to fill a buffer from a file position.
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    #[cfg(unix)]
+    use std::os::unix::fs::FileExt;
+    #[cfg(windows)]
+    use std::os::windows::fs::FileExt;
+
+    while !buf.is_empty() {
+        #[cfg(unix)]
+        let read = file.read_at(buf, offset)?;
+        #[cfg(windows)]
+        let read = file.seek_read(buf, offset)?;
+        if read == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "file truncated while being served",
+            ));
+        }
+        buf = &mut std::mem::take(&mut buf)[read..];
+        offset += read as u64;
+    }
+    Ok(())
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::{CHUNK_SIZE, MIN_FILE_SIZE, file_body};
+    use bytes::Bytes;
+    use futures_util::TryStreamExt;
+    use std::fs::{File, OpenOptions};
+    use std::io::{Seek, SeekFrom};
+    use std::os::unix::fs::OpenOptionsExt;
+
+    fn temp_file(name: &str, contents: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("sws-mmap-{name}-{}", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_mmap_file_body() {
+        let contents: Vec<u8> = (0..600_000u32).map(|i| (i % 251) as u8).collect();
+        let path = temp_file("body", &contents);
+        let mut file = File::open(&path).unwrap();
+
+        // Out of the size limits
+        assert!(file_body(&file, MIN_FILE_SIZE - 1, 600_000).is_none());
+        assert!(file_body(&file, 600_000, 600_000 - 1).is_none());
+
+        let Some(body) = file_body(&file, 600_000, 600_000) else {
+            // File leases are not available (E.g. some file systems)
+            return std::fs::remove_file(path).unwrap();
+        };
+        let chunks: Vec<Bytes> = body.try_collect().await.unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].len(), CHUNK_SIZE);
+        assert_eq!(chunks.concat(), contents);
+
+        // The ranges are not aligned to the memory pages
+        file.seek(SeekFrom::Start(1_000)).unwrap();
+        let body = file_body(&file, MIN_FILE_SIZE, 600_000).unwrap();
+        let chunks: Vec<Bytes> = body.try_collect().await.unwrap();
+        assert_eq!(
+            chunks.concat(),
+            &contents[1_000..1_000 + MIN_FILE_SIZE as usize]
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mmap_file_lease() {
+        let path = temp_file("lease", &[0; MIN_FILE_SIZE as usize]);
+        let file = File::open(&path).unwrap();
+        let write_nonblock = || {
+            OpenOptions::new()
+                .write(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open(&path)
+        };
+
+        let Some(body) = file_body(&file, MIN_FILE_SIZE, MIN_FILE_SIZE) else {
+            return std::fs::remove_file(&path).unwrap();
+        };
+        // The file can't be opened for writing while its chunks are referenced
+        let chunks: Vec<Bytes> = body.try_collect().await.unwrap();
+        let err = write_nonblock().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+
+        drop(chunks);
+        assert!(write_nonblock().is_ok());
+
+        // Nor mapped while open for writing
+        let _writer = write_nonblock().unwrap();
+        assert!(file_body(&file, MIN_FILE_SIZE, MIN_FILE_SIZE).is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -6,6 +6,24 @@
 //! A module that provides several facilities for working with files.
 //!
 
+#[cfg(feature = "archive-root")]
+#[cfg_attr(docsrs, doc(cfg(feature = "archive-root")))]
+pub mod archive;
+pub mod atomic;
 pub(crate) mod meta;
+#[cfg(feature = "mmap")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mmap")))]
+pub mod mmap;
 pub(crate) mod path;
+#[cfg(feature = "s3-origin")]
+#[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+pub mod s3;
 pub(crate) mod stream;
+pub mod symlinks;
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+pub(crate) mod uring;
+pub mod vfs;
+pub(crate) mod walk;
+#[cfg(feature = "cache-invalidation")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cache-invalidation")))]
+pub(crate) mod watch;
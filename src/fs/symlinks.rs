@@ -0,0 +1,128 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Policy of the symbolic links followed when resolving the paths of the requests.
+//!
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+#[derive(
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    ValueEnum,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+/// Policy of the symbolic links followed when serving files and directories.
+pub enum FollowSymlinks {
+    /// Follow every symlink (default).
+    #[default]
+    Always,
+    /// Follow only the symlinks resolving to a path inside the root directory.
+    SameRoot,
+    /// Never follow symlinks, equivalent to the `disable-symlinks` option.
+    Never,
+}
+
+impl fmt::Display for FollowSymlinks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Always => "always",
+            Self::SameRoot => "same-root",
+            Self::Never => "never",
+        })
+    }
+}
+
+impl FollowSymlinks {
+    /// Returns the effective policy, which is `Never` if symlinks are disabled.
+    pub fn or_disabled(self, disable_symlinks: bool) -> Self {
+        if disable_symlinks { Self::Never } else { self }
+    }
+
+    /// Checks if a path under the base directory can be accessed according to the policy.
+    ///
+    /// With `never`, no path name component below the base directory can be a symlink.
+    /// With `same-root`, the resolved path must stay inside the resolved base directory.
+    /// Only the existing part of the path is resolved, the rest is left to the caller.
+    pub fn allows(self, base: &Path, path: &Path) -> bool {
+        match self {
+            Self::Always => true,
+            Self::SameRoot => is_within(base, path),
+            Self::Never => !has_symlink(base, path),
+        }
+    }
+}
+
+/// Checks if any path name component below the base directory is a symlink.
+fn has_symlink(base: &Path, path: &Path) -> bool {
+    let Ok(rel) = path.strip_prefix(base) else {
+        return path.is_symlink();
+    };
+    let mut current = base.to_path_buf();
+    rel.components().any(|component| {
+        current.push(component);
+        current.is_symlink()
+    })
+}
+
+/// Checks if the deepest existing ancestor of a path resolves inside the base directory.
+fn is_within(base: &Path, path: &Path) -> bool {
+    let Ok(base) = base.canonicalize() else {
+        return false;
+    };
+    path.ancestors()
+        .find_map(|ancestor| ancestor.canonicalize().ok())
+        .is_some_and(|resolved| resolved.starts_with(base))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::FollowSymlinks;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn test_allows() {
+        let dir = std::env::temp_dir().join(format!("sws-symlinks-{}", std::process::id()));
+        let root = dir.join("public");
+        std::fs::create_dir_all(root.join("assets")).unwrap();
+        std::fs::write(root.join("assets/main.js"), "alert(1)").unwrap();
+        std::fs::write(dir.join("secret.txt"), "secret").unwrap();
+        symlink(root.join("assets"), root.join("static")).unwrap();
+        symlink(&dir, root.join("parent")).unwrap();
+
+        let inside = root.join("static/main.js");
+        let outside = root.join("parent/secret.txt");
+        let missing = root.join("parent/missing.txt");
+        let regular = root.join("assets/main.js");
+
+        assert!(FollowSymlinks::Always.allows(&root, &outside));
+
+        assert!(FollowSymlinks::SameRoot.allows(&root, &inside));
+        assert!(FollowSymlinks::SameRoot.allows(&root, &regular));
+        assert!(!FollowSymlinks::SameRoot.allows(&root, &outside));
+        assert!(!FollowSymlinks::SameRoot.allows(&root, &missing));
+        assert!(FollowSymlinks::SameRoot.allows(&root, &root.join("missing/file.txt")));
+
+        assert!(!FollowSymlinks::Never.allows(&root, &inside));
+        assert!(!FollowSymlinks::Never.allows(&root, &outside));
+        assert!(FollowSymlinks::Never.allows(&root, &regular));
+
+        assert_eq!(
+            FollowSymlinks::SameRoot.or_disabled(true),
+            FollowSymlinks::Never
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
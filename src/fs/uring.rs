@@ -0,0 +1,193 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Experimental module to read the files served via io_uring on Linux.
+//!
+//! The reads are submitted by a dedicated thread running a `tokio-uring` runtime,
+//! so they don't block the runtime worker threads. The regular file reads are used instead
+//! if io_uring is not available (E.g. an old kernel or a container blocking its system calls).
+//!
+
+use bytes::Bytes;
+use futures_util::Stream;
+use hyper::Body;
+use std::fs::File;
+use std::io;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// Maximum number of chunks read ahead of the ones written to the client.
+const READ_AHEAD_CHUNKS: usize = 2;
+
+/// Sender of the file reads to the io_uring thread, once started.
+static WORKER: OnceLock<mpsc::UnboundedSender<ReadRequest>> = OnceLock::new();
+
+/// Whether the files are read via io_uring.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Read of a file range submitted to the io_uring thread.
+struct ReadRequest {
+    file: File,
+    offset: u64,
+    len: u64,
+    buf_size: usize,
+    chunks: mpsc::Sender<io::Result<Bytes>>,
+}
+
+/// Initializes the io_uring file reads, falling back to the regular ones
+/// if io_uring is not available.
+pub(crate) fn init(enabled: bool) {
+    if enabled && WORKER.get().is_none() {
+        match start() {
+            Ok(worker) => {
+                let _ = WORKER.set(worker);
+            }
+            Err(err) => {
+                tracing::warn!(
+                    "io_uring: not available, the regular file reads are used instead: {err}"
+                );
+            }
+        }
+    }
+    let enabled = enabled && WORKER.get().is_some();
+    ENABLED.store(enabled, Ordering::Relaxed);
+    tracing::info!("io_uring: enabled={enabled}");
+}
+
+/// Checks if the files are read via io_uring.
+pub(crate) fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Starts the io_uring thread, failing if its runtime can't be created.
+fn start() -> io::Result<mpsc::UnboundedSender<ReadRequest>> {
+    let (worker, mut requests) = mpsc::unbounded_channel::<ReadRequest>();
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    std::thread::Builder::new()
+        .name("sws-io-uring".into())
+        .spawn(move || {
+            let runtime = match tokio_uring::Runtime::new(&tokio_uring::builder()) {
+                Ok(runtime) => {
+                    let _ = ready_tx.send(Ok(()));
+                    runtime
+                }
+                Err(err) => {
+                    let _ = ready_tx.send(Err(err));
+                    return;
+                }
+            };
+            runtime.block_on(async move {
+                while let Some(request) = requests.recv().await {
+                    tokio_uring::spawn(read(request));
+                }
+            });
+        })?;
+    ready_rx
+        .recv()
+        .map_err(|_| io::Error::other("io_uring thread exited unexpectedly"))??;
+    Ok(worker)
+}
+
+/// Reads a file range by chunks until it's done or the client is gone.
+async fn read(request: ReadRequest) {
+    let file = tokio_uring::fs::File::from_std(request.file);
+    let end = request.offset + request.len;
+    let mut offset = request.offset;
+    while offset < end {
+        let len = (end - offset).min(request.buf_size as u64) as usize;
+        let (result, mut buf) = file
+            .read_at(bytes::BytesMut::with_capacity(len), offset)
+            .await;
+        let chunk = match result {
+            Ok(0) => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "file truncated while being read",
+            )),
+            Ok(_) => {
+                buf.truncate(len);
+                offset += buf.len() as u64;
+                Ok(buf.freeze())
+            }
+            Err(err) => Err(err),
+        };
+        let failed = chunk.is_err();
+        if request.chunks.send(chunk).await.is_err() || failed {
+            break;
+        }
+    }
+    let _ = file.close().await;
+}
+
+/// Creates the body of a file range read via io_uring.
+pub(crate) fn file_body(file: File, offset: u64, len: u64, buf_size: usize) -> Body {
+    let (chunks, receiver) = mpsc::channel(READ_AHEAD_CHUNKS);
+    Body::wrap_stream(UringFileStream {
+        request: Some(ReadRequest {
+            file,
+            offset,
+            len,
+            buf_size,
+            chunks,
+        }),
+        receiver,
+    })
+}
+
+/// Stream of the chunks of a file range, which is submitted to the io_uring thread
+/// once its body starts to be written.
+struct UringFileStream {
+    request: Option<ReadRequest>,
+    receiver: mpsc::Receiver<io::Result<Bytes>>,
+}
+
+impl Stream for UringFileStream {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        if let Some(request) = pin.request.take() {
+            let sent = WORKER
+                .get()
+                .is_some_and(|worker| worker.send(request).is_ok());
+            if !sent {
+                return Poll::Ready(Some(Err(io::Error::other(
+                    "io_uring thread is not running",
+                ))));
+            }
+        }
+        pin.receiver.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WORKER, file_body, start};
+    use std::fs::File;
+
+    #[tokio::test]
+    async fn test_file_body() {
+        // The environments blocking io_uring (E.g. some containers) can't run this test
+        let Ok(worker) = start() else {
+            return;
+        };
+        let _ = WORKER.set(worker);
+
+        let path = "tests/fixtures/public/index.htm";
+        let contents = std::fs::read(path).unwrap();
+        let body = file_body(File::open(path).unwrap(), 0, contents.len() as u64, 16);
+        let bytes = hyper::body::to_bytes(body).await.unwrap();
+        assert_eq!(bytes, contents);
+
+        let body = file_body(File::open(path).unwrap(), 10, 20, 8);
+        let bytes = hyper::body::to_bytes(body).await.unwrap();
+        assert_eq!(bytes, contents[10..30]);
+
+        let body = file_body(File::open(path).unwrap(), 0, contents.len() as u64 + 1, 64);
+        assert!(hyper::body::to_bytes(body).await.is_err());
+    }
+}
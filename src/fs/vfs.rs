@@ -0,0 +1,215 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! A module that provides a virtual file system abstraction, so files can be served
+//! from alternative backends (E.g. assets embedded into the binary, archives or object stores).
+//!
+//! The paths of a [`Filesystem`] are relative to its root and separated by `/`,
+//! an empty path being the root itself.
+//!
+
+use bytes::Bytes;
+use futures_util::Stream;
+use hyper::{Body, Response};
+use std::future::Future;
+use std::io;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::pin::Pin;
+use std::time::SystemTime;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+
+/// Future returned by the [`Filesystem`] operations.
+pub type VfsFuture<'a, T> = Pin<Box<dyn Future<Output = io::Result<T>> + Send + 'a>>;
+
+/// Stream of the contents of a [`Filesystem`] file.
+pub type VfsStream = Pin<Box<dyn Stream<Item = io::Result<Bytes>> + Send>>;
+
+/// Metadata of a [`Filesystem`] file or directory.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VfsMetadata {
+    /// Whether it's a directory.
+    pub is_dir: bool,
+    /// Size of the file in bytes, zero for a directory.
+    pub len: u64,
+    /// Last modification time if known.
+    pub modified: Option<SystemTime>,
+}
+
+/// Entry of a [`Filesystem`] directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VfsDirEntry {
+    /// Name of the entry.
+    pub name: String,
+    /// Metadata of the entry.
+    pub metadata: VfsMetadata,
+}
+
+/// Backend of the files served, E.g. the local disk or the assets embedded into the binary.
+///
+/// The operations fail with an [`io::ErrorKind::NotFound`] error if the path doesn't exist.
+pub trait Filesystem: Send + Sync {
+    /// Returns the metadata of a file or directory.
+    fn metadata<'a>(&'a self, path: &'a str) -> VfsFuture<'a, VfsMetadata>;
+
+    /// Opens a file, returning a stream of its whole contents.
+    ///
+    /// By default, the contents are read at once via [`Filesystem::read_range`].
+    fn open<'a>(&'a self, path: &'a str) -> VfsFuture<'a, VfsStream> {
+        Box::pin(async move {
+            let len = self.metadata(path).await?.len;
+            let contents = self.read_range(path, 0..len).await?;
+            Ok(Box::pin(futures_util::stream::iter([Ok(contents)])) as VfsStream)
+        })
+    }
+
+    /// Reads a range of bytes of a file.
+    fn read_range<'a>(&'a self, path: &'a str, range: Range<u64>) -> VfsFuture<'a, Bytes>;
+
+    /// Returns the entries of a directory.
+    fn read_dir<'a>(&'a self, path: &'a str) -> VfsFuture<'a, Vec<VfsDirEntry>>;
+}
+
+/// Marks a response served from a [`Filesystem`], which has no file on disk.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct VfsFile;
+
+/// Checks if a response was served from a [`Filesystem`].
+pub(crate) fn is_vfs_file(resp: &Response<Body>) -> bool {
+    resp.extensions().get::<VfsFile>().is_some()
+}
+
+/// [`Filesystem`] of a directory on the local disk.
+#[derive(Clone, Debug)]
+pub struct LocalFilesystem {
+    root: PathBuf,
+}
+
+impl LocalFilesystem {
+    /// Creates the file system of the given root directory.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Resolves a path under the root directory, rejecting any path escaping it.
+    fn resolve(&self, path: &str) -> io::Result<PathBuf> {
+        let path = Path::new(path);
+        if path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            Ok(self.root.join(path))
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "path out of the root directory",
+            ))
+        }
+    }
+}
+
+impl From<std::fs::Metadata> for VfsMetadata {
+    fn from(meta: std::fs::Metadata) -> Self {
+        Self {
+            is_dir: meta.is_dir(),
+            len: if meta.is_dir() { 0 } else { meta.len() },
+            modified: meta.modified().ok(),
+        }
+    }
+}
+
+impl Filesystem for LocalFilesystem {
+    fn metadata<'a>(&'a self, path: &'a str) -> VfsFuture<'a, VfsMetadata> {
+        Box::pin(async move {
+            let meta = tokio::fs::metadata(self.resolve(path)?).await?;
+            Ok(meta.into())
+        })
+    }
+
+    fn open<'a>(&'a self, path: &'a str) -> VfsFuture<'a, VfsStream> {
+        Box::pin(async move {
+            let file = tokio::fs::File::open(self.resolve(path)?).await?;
+            Ok(Box::pin(ReaderStream::new(file)) as VfsStream)
+        })
+    }
+
+    fn read_range<'a>(&'a self, path: &'a str, range: Range<u64>) -> VfsFuture<'a, Bytes> {
+        Box::pin(async move {
+            let mut file = tokio::fs::File::open(self.resolve(path)?).await?;
+            file.seek(io::SeekFrom::Start(range.start)).await?;
+            let mut buf = Vec::new();
+            file.take(range.end.saturating_sub(range.start))
+                .read_to_end(&mut buf)
+                .await?;
+            Ok(buf.into())
+        })
+    }
+
+    fn read_dir<'a>(&'a self, path: &'a str) -> VfsFuture<'a, Vec<VfsDirEntry>> {
+        Box::pin(async move {
+            let mut reader = tokio::fs::read_dir(self.resolve(path)?).await?;
+            let mut entries = Vec::new();
+            while let Some(entry) = reader.next_entry().await? {
+                // Entries whose metadata can't be resolved (E.g. broken symlinks) are skipped
+                let Ok(meta) = tokio::fs::metadata(entry.path()).await else {
+                    continue;
+                };
+                entries.push(VfsDirEntry {
+                    name: entry.file_name().to_string_lossy().into_owned(),
+                    metadata: meta.into(),
+                });
+            }
+            Ok(entries)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Filesystem, LocalFilesystem};
+    use futures_util::TryStreamExt;
+    use std::io;
+
+    #[tokio::test]
+    async fn test_local_filesystem() {
+        let fs = LocalFilesystem::new("tests/fixtures/overlay");
+
+        let meta = fs.metadata("site/index.html").await.unwrap();
+        assert!(!meta.is_dir);
+        assert!(meta.len > 0);
+        assert!(meta.modified.is_some());
+        assert!(fs.metadata("").await.unwrap().is_dir);
+
+        let contents = fs.read_range("site/index.html", 0..meta.len).await.unwrap();
+        let stream = fs.open("site/index.html").await.unwrap();
+        let chunks: Vec<_> = stream.try_collect().await.unwrap();
+        assert_eq!(chunks.concat(), contents);
+        let part = fs.read_range("site/index.html", 1..4).await.unwrap();
+        assert_eq!(part, contents.slice(1..4));
+
+        let mut names: Vec<_> = fs
+            .read_dir("theme")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.name, entry.metadata.is_dir))
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                ("docs".to_owned(), true),
+                ("style.css".to_owned(), false),
+                ("theme.js".to_owned(), false)
+            ]
+        );
+
+        let err = fs.metadata("missing.html").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let err = fs.metadata("../overlay_roots.rs").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,257 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Parallel walk of directory trees with a concurrency limit and cancellation.
+//!
+//! The directories found are shared between a fixed number of threads, so the walk
+//! of large trees on fast storage (E.g. NVMe) isn't bound by the latency of a single thread.
+//!
+
+use std::fs::{DirEntry, Metadata};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// A token to cancel one or more walks from another thread.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Cancels the walks using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// What to do with an entry visited during a walk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Visit {
+    /// Keep walking, descending into the entry if it's a directory.
+    Continue,
+    /// Skip the entry, not descending into it if it's a directory.
+    Skip,
+    /// Stop the whole walk.
+    #[cfg_attr(not(feature = "directory-listing"), allow(dead_code))]
+    Stop,
+}
+
+/// Directories pending to be read, shared by the walk threads.
+struct Queue {
+    dirs: Vec<PathBuf>,
+    /// Number of threads reading a directory, which may find more directories.
+    busy: usize,
+}
+
+/// State shared by the walk threads.
+struct Walk<'a, F> {
+    queue: Mutex<Queue>,
+    ready: Condvar,
+    stopped: AtomicBool,
+    cancel: &'a CancelToken,
+    visit: &'a F,
+}
+
+/// Walks the directory tree of `root` from up to `concurrency` threads (including the current one),
+/// calling `visit` for every entry along with its metadata. Symlinks are not followed.
+/// Returns `false` if the walk was stopped by `visit` or cancelled via the `cancel` token.
+pub(crate) fn walk<F>(root: &Path, concurrency: usize, cancel: &CancelToken, visit: F) -> bool
+where
+    F: Fn(&DirEntry, &Metadata) -> Visit + Sync,
+{
+    let walk = Walk {
+        queue: Mutex::new(Queue {
+            dirs: vec![root.to_owned()],
+            busy: 0,
+        }),
+        ready: Condvar::new(),
+        stopped: AtomicBool::new(false),
+        cancel,
+        visit: &visit,
+    };
+
+    std::thread::scope(|scope| {
+        for _ in 1..concurrency.max(1) {
+            let spawned = std::thread::Builder::new()
+                .name("sws-dir-walk".into())
+                .spawn_scoped(scope, || walk.work());
+            if let Err(err) = spawned {
+                // The walk goes on with the threads already spawned
+                tracing::warn!("unable to spawn a directory walk thread: {err}");
+                break;
+            }
+        }
+        walk.work();
+    });
+
+    !walk.is_stopped()
+}
+
+impl<F> Walk<'_, F>
+where
+    F: Fn(&DirEntry, &Metadata) -> Visit + Sync,
+{
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed) || self.cancel.is_cancelled()
+    }
+
+    fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        self.ready.notify_all();
+    }
+
+    /// Reads the pending directories until there are none left and no other thread may find more.
+    fn work(&self) {
+        while let Some(dir) = self.next_dir() {
+            let found = self.read_dir(&dir);
+            let Ok(mut queue) = self.queue.lock() else {
+                self.stop();
+                return;
+            };
+            queue.dirs.extend(found);
+            queue.busy -= 1;
+            self.ready.notify_all();
+        }
+    }
+
+    fn next_dir(&self) -> Option<PathBuf> {
+        let mut queue = self.queue.lock().ok()?;
+        loop {
+            if self.is_stopped() {
+                return None;
+            }
+            if let Some(dir) = queue.dirs.pop() {
+                queue.busy += 1;
+                return Some(dir);
+            }
+            if queue.busy == 0 {
+                return None;
+            }
+            queue = self.ready.wait(queue).ok()?;
+        }
+    }
+
+    /// Visits the entries of a directory, returning its subdirectories to walk.
+    fn read_dir(&self, dir: &Path) -> Vec<PathBuf> {
+        let mut found = Vec::new();
+        let reader = match std::fs::read_dir(dir) {
+            Ok(reader) => reader,
+            Err(err) => {
+                tracing::debug!(
+                    "unable to read directory {} (skipped): {err}",
+                    dir.display()
+                );
+                return found;
+            }
+        };
+
+        for entry in reader.flatten() {
+            if self.is_stopped() {
+                break;
+            }
+            // NOTE: the metadata of a directory entry doesn't follow symlinks
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            match (self.visit)(&entry, &meta) {
+                Visit::Continue if meta.is_dir() => found.push(entry.path()),
+                Visit::Continue | Visit::Skip => {}
+                Visit::Stop => {
+                    self.stop();
+                    break;
+                }
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CancelToken, Visit, walk};
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn temp_tree(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("sws-walk-{name}-{}", std::process::id()));
+        std::fs::remove_dir_all(&root).ok();
+        for i in 0..4 {
+            let dir = root.join(format!("dir{i}")).join("sub");
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("file.txt"), b"sws").unwrap();
+            std::fs::write(root.join(format!("dir{i}")).join(".hidden"), b"").unwrap();
+        }
+        root
+    }
+
+    fn walked(root: &Path, concurrency: usize) -> Vec<PathBuf> {
+        let paths = Mutex::new(Vec::new());
+        let completed = walk(root, concurrency, &CancelToken::default(), |entry, _| {
+            if entry.file_name() == ".hidden" {
+                return Visit::Skip;
+            }
+            paths.lock().unwrap().push(entry.path());
+            Visit::Continue
+        });
+        assert!(completed);
+        let mut paths = paths.into_inner().unwrap();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_walk() {
+        let root = temp_tree("all");
+        let paths = walked(&root, 1);
+        // 4 directories with a subdirectory and a file each
+        assert_eq!(paths.len(), 12);
+        assert!(paths.contains(&root.join("dir3").join("sub").join("file.txt")));
+        assert_eq!(walked(&root, 4), paths);
+        assert_eq!(walked(&root, 0), paths);
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_walk_stop() {
+        let root = temp_tree("stop");
+        let visited = AtomicUsize::new(0);
+        let completed = walk(&root, 4, &CancelToken::default(), |_, _| {
+            if visited.fetch_add(1, Ordering::Relaxed) >= 2 {
+                return Visit::Stop;
+            }
+            Visit::Continue
+        });
+        assert!(!completed);
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_walk_cancel() {
+        let root = temp_tree("cancel");
+        let cancel = CancelToken::default();
+        cancel.cancel();
+        let visited = AtomicUsize::new(0);
+        let completed = walk(&root, 2, &cancel, |_, _| {
+            visited.fetch_add(1, Ordering::Relaxed);
+            Visit::Continue
+        });
+        assert!(!completed);
+        assert_eq!(visited.load(Ordering::Relaxed), 0);
+
+        // Missing directories are skipped
+        assert!(walk(
+            &root.join("none"),
+            2,
+            &CancelToken::default(),
+            |_, _| Visit::Continue
+        ));
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
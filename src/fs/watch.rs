@@ -0,0 +1,239 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module watching directories for file changes via the OS file notifications
+//! (E.g. inotify, kqueue, FSEvents or ReadDirectoryChangesW).
+//!
+//! A single watcher is shared by every subscription (E.g. the cache invalidation and the
+//! live-reload of the browsers), so the directories watched by several of them are only
+//! watched once. A directory is unwatched once no subscription needs it anymore.
+//!
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+use crate::{Context, Result};
+
+/// The shared watcher along with the directories it watches.
+static WATCHER: Mutex<Option<(RecommendedWatcher, Vec<PathBuf>)>> = Mutex::new(None);
+
+/// Subscriptions notified of the changes.
+///
+/// NOTE: kept apart from the watcher since the events are handled on the watcher thread,
+/// which some watchers wait for when watching or unwatching a directory.
+static SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+
+/// Identifier of the next subscription.
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A subscription to the changes of some directories.
+struct Subscriber {
+    id: u64,
+    /// Watched directories as given along with their canonical paths.
+    dirs: Vec<(PathBuf, PathBuf)>,
+    sender: UnboundedSender<PathBuf>,
+}
+
+/// Subscription receiving the paths added, modified or removed under some directories.
+///
+/// The paths are relative to the directories as given (E.g. `./public/index.html`).
+/// A directory path means that anything under it may have changed (E.g. a renamed directory).
+/// The directories are unwatched once the subscription is dropped.
+pub(crate) struct Subscription {
+    id: u64,
+    receiver: UnboundedReceiver<PathBuf>,
+}
+
+impl Subscription {
+    /// Receives the next changed path, `None` if the watcher is gone.
+    pub(crate) async fn recv(&mut self) -> Option<PathBuf> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let mut watcher = WATCHER.lock().unwrap_or_else(PoisonError::into_inner);
+        lock_subscribers().retain(|subscriber| subscriber.id != self.id);
+        if let Some((watcher, watched)) = watcher.as_mut() {
+            update_watched_dirs(watcher, watched);
+        }
+    }
+}
+
+/// Subscribes to the changes of the given directories, watching them recursively.
+///
+/// The directories which can't be watched (E.g. not found) are skipped with a warning.
+pub(crate) fn subscribe(dirs: &[PathBuf]) -> Result<Subscription> {
+    let mut watcher = WATCHER.lock().unwrap_or_else(PoisonError::into_inner);
+    if watcher.is_none() {
+        let inner = notify::recommended_watcher(handle_event)
+            .with_context(|| "unable to create the file system watcher")?;
+        *watcher = Some((inner, Vec::new()));
+    }
+
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let (sender, receiver) = unbounded_channel();
+    let dirs = dirs
+        .iter()
+        .filter_map(|dir| match dir.canonicalize() {
+            Ok(canonical) => Some((dir.clone(), canonical)),
+            Err(err) => {
+                tracing::warn!("unable to watch directory {}: {err}", dir.display());
+                None
+            }
+        })
+        .collect();
+    lock_subscribers().push(Subscriber { id, dirs, sender });
+
+    if let Some((watcher, watched)) = watcher.as_mut() {
+        update_watched_dirs(watcher, watched);
+    }
+    Ok(Subscription { id, receiver })
+}
+
+fn lock_subscribers() -> std::sync::MutexGuard<'static, Vec<Subscriber>> {
+    SUBSCRIBERS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Watches the directories of the subscriptions which are not watched yet
+/// and unwatches the ones no longer needed.
+fn update_watched_dirs(watcher: &mut RecommendedWatcher, watched: &mut Vec<PathBuf>) {
+    let mut dirs: Vec<PathBuf> = lock_subscribers()
+        .iter()
+        .flat_map(|subscriber| subscriber.dirs.iter().map(|(_, dir)| dir.clone()))
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+    // The subdirectories of a watched directory are watched along with it
+    let dirs: Vec<PathBuf> = dirs
+        .iter()
+        .filter(|dir| {
+            !dirs
+                .iter()
+                .any(|other| other != *dir && dir.starts_with(other))
+        })
+        .cloned()
+        .collect();
+
+    // NOTE: the directories are unwatched first, since unwatching a directory may also
+    // unwatch its subdirectories (E.g. with inotify)
+    for dir in watched.iter().filter(|dir| !dirs.contains(dir)) {
+        if let Err(err) = watcher.unwatch(dir) {
+            tracing::debug!("unable to unwatch directory {}: {err}", dir.display());
+        }
+    }
+    let mut failed = Vec::new();
+    for dir in dirs.iter().filter(|dir| !watched.contains(dir)) {
+        match watcher.watch(dir, RecursiveMode::Recursive) {
+            Ok(()) => tracing::debug!("watching directory {}", dir.display()),
+            Err(err) => {
+                tracing::warn!("unable to watch directory {}: {err}", dir.display());
+                failed.push(dir.clone());
+            }
+        }
+    }
+    *watched = dirs;
+    watched.retain(|dir| !failed.contains(dir));
+}
+
+/// Sends the changed paths of an event to the subscriptions watching them.
+fn handle_event(event: notify::Result<Event>) {
+    let event = match event {
+        Ok(event) => event,
+        Err(err) => {
+            tracing::warn!("file system watcher error: {err}");
+            return;
+        }
+    };
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+
+    let subscribers = lock_subscribers();
+    for subscriber in subscribers.iter() {
+        for (dir, canonical) in &subscriber.dirs {
+            // Some events may be missing, so everything under the directories may have changed
+            if event.need_rescan() {
+                let _ = subscriber.sender.send(dir.clone());
+                continue;
+            }
+            for path in &event.paths {
+                if let Some(path) = rebase(path, dir, canonical) {
+                    let _ = subscriber.sender.send(path);
+                }
+            }
+        }
+    }
+}
+
+/// Returns a path of the canonical directory relative to the directory as given if it's under it.
+fn rebase(path: &Path, dir: &Path, canonical: &Path) -> Option<PathBuf> {
+    let relative = path.strip_prefix(canonical).ok()?;
+    Some(if relative.as_os_str().is_empty() {
+        dir.to_owned()
+    } else {
+        dir.join(relative)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rebase, subscribe};
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    #[test]
+    fn test_rebase() {
+        let (dir, canonical) = (Path::new("./public"), Path::new("/srv/public"));
+        assert_eq!(
+            rebase(Path::new("/srv/public/assets/main.js"), dir, canonical),
+            Some(PathBuf::from("./public/assets/main.js"))
+        );
+        assert_eq!(
+            rebase(Path::new("/srv/public"), dir, canonical),
+            Some(PathBuf::from("./public"))
+        );
+        assert_eq!(rebase(Path::new("/srv/other"), dir, canonical), None);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe() {
+        let root = std::env::temp_dir().join(format!("sws-watch-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("assets")).unwrap();
+
+        // Both subscriptions share the watch of the root directory
+        let mut all = subscribe(std::slice::from_ref(&root)).unwrap();
+        let mut assets = subscribe(&[root.join("assets")]).unwrap();
+
+        std::fs::write(root.join("assets/main.css"), "p {}").unwrap();
+        for subscription in [&mut all, &mut assets] {
+            let path = tokio::time::timeout(Duration::from_secs(5), subscription.recv())
+                .await
+                .expect("change not notified")
+                .unwrap();
+            assert_eq!(path, root.join("assets/main.css"));
+        }
+
+        // The root directory is still watched once the other subscription is dropped
+        drop(assets);
+        std::fs::write(root.join("index.html"), "<p>Hi</p>").unwrap();
+        loop {
+            let path = tokio::time::timeout(Duration::from_secs(5), all.recv())
+                .await
+                .expect("change not notified")
+                .unwrap();
+            if path == root.join("index.html") {
+                break;
+            }
+        }
+
+        drop(all);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
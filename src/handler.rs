@@ -6,12 +6,15 @@
 //! Request handler module intended to manage incoming HTTP requests.
 //!
 
-use hyper::{Body, Request, Response, StatusCode};
+use arc_swap::ArcSwap;
+use globset::GlobSet;
+use hyper::{Body, Request, Response, StatusCode, header::HeaderValue};
 use std::{
     future::Future,
-    net::{IpAddr, SocketAddr},
+    net::SocketAddr,
     path::PathBuf,
     sync::Arc,
+    time::{Duration, SystemTime},
 };
 
 #[cfg(any(
@@ -23,41 +26,112 @@ use std::{
 ))]
 use crate::{compression, compression_static};
 
+#[cfg(feature = "archive-preview")]
+use crate::archive_preview;
+
 #[cfg(feature = "basic-auth")]
 use crate::basic_auth;
 
+#[cfg(feature = "subresource-integrity")]
+use crate::sri::{self, SriAlgorithm};
+
 #[cfg(feature = "fallback-page")]
 use crate::fallback_page;
 
-#[cfg(all(unix, feature = "experimental"))]
+#[cfg(feature = "markdown")]
+use crate::markdown_render;
+
+#[cfg(feature = "metrics")]
 use crate::metrics;
 
+#[cfg(feature = "time-restrictions")]
+use crate::time_restrictions;
+
 #[cfg(feature = "experimental")]
 use crate::mem_cache::cache::MemCacheOpts;
 
+#[cfg(feature = "cache-invalidation")]
+use crate::cache_invalidation::CacheInvalidationOpts;
+
 use crate::{
-    Error, Result, control_headers, cors, custom_headers, error_page, health,
+    Error, Result,
+    access_log::{self, AccessLog},
+    alt_svc,
+    bandwidth::{self, BandwidthLimit},
+    chunk_cache::ChunkCacheOpts,
+    content_type_options, control_headers, cors, custom_headers,
+    deadline::{self, Deadline},
+    dev_reload::{self, DevReload},
+    embedded::EmbeddedAssetsOpts,
+    error_page::{self, ErrorPages},
+    fs::{
+        symlinks::FollowSymlinks,
+        vfs::{self, Filesystem},
+    },
+    headers_ext::ContentCoding,
+    health,
     http_ext::MethodExt,
-    log_addr, maintenance_mode, redirects, rewrites, security_headers,
+    ignore_patterns::IgnorePatterns,
+    image_variants::{self, ImageFormat},
+    ip_allowlist::IpAllowlist,
+    language_variants::{self, LanguageVariant},
+    log_addr, maintenance_mode, media_streaming, mime_types,
+    not_found_cache::{self, NotFoundCache},
+    probe, redirects, request_limits, rewrites, security_headers, server_ident,
     settings::Advanced,
+    ssi,
     static_files::{self, HandleOpts},
-    virtual_hosts,
+    status_rewrites, try_files,
+    type_map::TypeMap,
+    virtual_hosts, websocket_proxy,
+};
+
+#[cfg(feature = "directory-listing")]
+use {
+    crate::directory_listing::{self, DirListFmt},
+    globset::GlobMatcher,
 };
 
 #[cfg(feature = "directory-listing")]
-use crate::directory_listing::DirListFmt;
+use crate::directory_listing_size::DirSizeCache;
+
+#[cfg(feature = "directory-listing")]
+use crate::directory_listing_locale::DirListLocales;
+
+#[cfg(feature = "directory-listing")]
+use crate::directory_listing_readme::DirReadme;
+
+#[cfg(feature = "directory-listing")]
+use crate::directory_listing_sort::DirListSort;
 
 #[cfg(feature = "directory-listing-download")]
 use crate::directory_listing_download::DirDownloadFmt;
 
 /// It defines options for a request handler.
+#[derive(Clone)]
 pub struct RequestHandlerOpts {
     // General options
     /// Root directory of static files.
     pub root_dir: PathBuf,
+    /// File system serving the root directory instead of the disk, E.g. a root archive.
+    pub root_filesystem: Option<Arc<dyn Filesystem>>,
+    /// Additional root directories overlaid below the root directory in priority order.
+    pub overlay_dirs: Vec<PathBuf>,
+    /// Assets embedded into the binary served instead of or below the root directory.
+    pub embedded_assets: Option<EmbeddedAssetsOpts>,
     #[cfg(feature = "experimental")]
     /// In-memory cache feature (experimental).
     pub memory_cache: Option<MemCacheOpts>,
+    /// Byte-range chunk cache feature.
+    pub chunk_cache: Option<ChunkCacheOpts>,
+    /// Maximum size in bytes of the file ranges served via memory maps, disabled if zero.
+    #[cfg(feature = "mmap")]
+    pub mmap_max_size: u64,
+    /// Cache invalidation on file changes feature.
+    #[cfg(feature = "cache-invalidation")]
+    pub cache_invalidation: Option<CacheInvalidationOpts>,
+    /// Pre-generated type map of the files under the root directory.
+    pub type_map: Option<Arc<TypeMap>>,
     /// Compression feature.
     pub compression: bool,
     #[cfg(any(
@@ -69,20 +143,91 @@ pub struct RequestHandlerOpts {
     ))]
     /// Compression level.
     pub compression_level: crate::settings::CompressionLevel,
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    /// Compression levels per algorithm.
+    pub compression_levels: crate::compression::AlgorithmLevels,
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    /// Compression minimum response size.
+    pub compression_min_size: u64,
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    /// Compression allowed MIME types.
+    pub compression_mime_types: Vec<String>,
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    /// Compression excluded MIME types.
+    pub compression_exclude_mime_types: Vec<String>,
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    /// Adaptive compression governor feature.
+    pub compression_adaptive: bool,
     /// Compression static feature.
     pub compression_static: bool,
+    /// Pre-compressed file variants to look up in priority order.
+    pub compression_static_order: Vec<ContentCoding>,
     /// Directory listing feature.
     #[cfg(feature = "directory-listing")]
     #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
     pub dir_listing: bool,
-    /// Directory listing order feature.
+    /// Glob patterns of the request paths with directory listing, all paths if empty.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub dir_listing_paths: Vec<GlobMatcher>,
+    /// Directory listing sorting feature.
     #[cfg(feature = "directory-listing")]
     #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
-    pub dir_listing_order: u8,
+    pub dir_listing_sort: DirListSort,
     #[cfg(feature = "directory-listing")]
     #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
     /// Directory listing format feature.
     pub dir_listing_format: DirListFmt,
+    /// Directory listing size feature.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub dir_listing_dir_size: Option<DirSizeCache>,
+    /// Directory listing entry details feature.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub dir_listing_details: bool,
+    /// Directory listing locales feature.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub dir_listing_locales: DirListLocales,
+    /// Glob patterns of the entries which are not listed but still served.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub dir_listing_hide: IgnorePatterns,
+    /// Header and readme files rendered in directory listings.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub dir_listing_readme: DirReadme,
     /// Directory listing download feature.
     #[cfg(feature = "directory-listing-download")]
     #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing-download")))]
@@ -93,14 +238,34 @@ pub struct RequestHandlerOpts {
     pub security_headers: bool,
     /// Cache control headers feature.
     pub cache_control_headers: bool,
-    /// Page for 404 errors.
-    pub page404: PathBuf,
-    /// Page for 50x errors.
-    pub page50x: PathBuf,
+    /// Media streaming preset feature.
+    pub media_streaming: bool,
+    /// Custom HTML error pages.
+    pub error_pages: ErrorPages,
     /// Page fallback feature.
     #[cfg(feature = "fallback-page")]
     #[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
     pub page_fallback: Vec<u8>,
+    /// Page fallbacks by request path feature.
+    #[cfg(feature = "fallback-page")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
+    pub page_fallbacks: Vec<fallback_page::FallbackPage>,
+    /// Page fallback soft 404 marking feature.
+    #[cfg(feature = "fallback-page")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
+    pub page_fallback_soft404: bool,
+    /// Page fallback response status code.
+    #[cfg(feature = "fallback-page")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
+    pub page_fallback_status: StatusCode,
+    /// Page fallback request methods.
+    #[cfg(feature = "fallback-page")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
+    pub page_fallback_methods: Vec<hyper::Method>,
+    /// Page fallback restricted to requests accepting HTML.
+    #[cfg(feature = "fallback-page")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
+    pub page_fallback_html_only: bool,
     /// Basic auth feature.
     #[cfg(feature = "basic-auth")]
     #[cfg_attr(docsrs, doc(cfg(feature = "basic-auth")))]
@@ -113,27 +278,106 @@ pub struct RequestHandlerOpts {
     pub log_x_real_ip: bool,
     /// Log the X-Forwarded-For header.
     pub log_forwarded_for: bool,
-    /// Trusted IPs for remote addresses.
-    pub trusted_proxies: Vec<IpAddr>,
+    /// Trusted IPs or host names for remote addresses.
+    pub trusted_proxies: IpAllowlist,
     /// Redirect trailing slash feature.
     pub redirect_trailing_slash: bool,
+    /// Reject the absolute-form request targets of HTTP/1.x requests.
+    pub reject_absolute_form: bool,
     /// Ignore hidden files feature.
     pub ignore_hidden_files: bool,
+    /// Glob patterns of the files and directories which are never served.
+    pub ignore_patterns: IgnorePatterns,
     /// Prevent following symlinks for files and directories.
     pub disable_symlinks: bool,
+    /// Policy of the symlinks followed for files and directories.
+    pub follow_symlinks: FollowSymlinks,
     /// Accept markdown content negotiation feature.
     pub accept_markdown: bool,
+    /// Languages of the language content negotiation, the first one is the default. Disabled if empty.
+    pub language_variants: Vec<LanguageVariant>,
+    /// Image formats of the image format negotiation in priority order. Disabled if empty.
+    pub image_variants: Vec<ImageFormat>,
+    /// Markdown rendering feature.
+    #[cfg(feature = "markdown")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "markdown")))]
+    pub markdown_render: bool,
+    /// Markdown rendering HTML wrapper template.
+    #[cfg(feature = "markdown")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "markdown")))]
+    pub markdown_render_template: String,
+    /// Server-side includes feature.
+    pub ssi: bool,
+    /// Server-side includes file extensions.
+    pub ssi_extensions: Vec<String>,
+    /// Live-reload of the browsers during development if enabled.
+    pub dev_reload: Option<DevReload>,
     /// Health endpoint feature.
     pub health: bool,
-    /// Metrics endpoint feature (experimental).
-    #[cfg(all(unix, feature = "experimental"))]
-    pub experimental_metrics: bool,
+    /// Files that must exist for the health readiness endpoint to succeed.
+    pub health_ready_files: Vec<PathBuf>,
+    /// Default charset for text-based content types. Disabled if empty.
+    pub default_charset: String,
+    /// Probe endpoint path feature. Disabled if empty.
+    pub probe_endpoint: String,
+    /// Value of the `Server` response header if any.
+    pub server_ident: Option<HeaderValue>,
+    /// Value of the `Alt-Svc` response header if any.
+    pub alt_svc: Option<HeaderValue>,
+    /// Maximum request URI length in bytes. Disabled if zero.
+    pub max_uri_length: usize,
+    /// Maximum request headers size in bytes. Disabled if zero.
+    pub max_headers_size: usize,
+    /// Maximum request body size in bytes. Disabled if zero.
+    pub max_body_size: u64,
+    /// Maximum time to resolve the response of a request. Disabled if `None`.
+    pub request_timeout: Option<Duration>,
+    /// Cache of the request paths recently not found. Disabled if `None`.
+    pub not_found_cache: Option<NotFoundCache>,
+    /// Bandwidth limits of the response bodies. Disabled if `None`.
+    pub bandwidth_limit: Option<BandwidthLimit>,
+    /// Archive preview feature.
+    #[cfg(feature = "archive-preview")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "archive-preview")))]
+    pub archive_preview: bool,
+    /// Maximum number of archive members listed or scanned by the archive preview.
+    #[cfg(feature = "archive-preview")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "archive-preview")))]
+    pub archive_preview_max_entries: usize,
+    /// Maximum uncompressed size in bytes of an archive member extracted by the archive preview.
+    #[cfg(feature = "archive-preview")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "archive-preview")))]
+    pub archive_preview_max_size: u64,
+    /// URI path of the Subresource Integrity manifest endpoint. Disabled if empty.
+    #[cfg(feature = "subresource-integrity")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "subresource-integrity")))]
+    pub sri_manifest: String,
+    /// Hash algorithm of the Subresource Integrity digests.
+    #[cfg(feature = "subresource-integrity")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "subresource-integrity")))]
+    pub sri_algorithm: SriAlgorithm,
+    /// File extensions included in the Subresource Integrity manifest.
+    #[cfg(feature = "subresource-integrity")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "subresource-integrity")))]
+    pub sri_extensions: Vec<String>,
+    /// Metrics endpoint feature.
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+    pub metrics: bool,
     /// Maintenance mode feature.
     pub maintenance_mode: bool,
     /// Custom HTTP status for when entering into maintenance mode.
     pub maintenance_mode_status: StatusCode,
     /// Custom maintenance mode HTML file.
     pub maintenance_mode_file: PathBuf,
+    /// Client IPs bypassing the maintenance mode.
+    pub maintenance_mode_allow_ips: IpAllowlist,
+    /// Request path patterns bypassing the maintenance mode.
+    pub maintenance_mode_allow_paths: GlobSet,
+    /// Time when the maintenance mode starts if scheduled.
+    pub maintenance_mode_start: Option<SystemTime>,
+    /// Time when the maintenance mode ends if scheduled.
+    pub maintenance_mode_end: Option<SystemTime>,
 
     /// Advanced options from the config file.
     pub advanced_opts: Option<Advanced>,
@@ -143,8 +387,16 @@ impl Default for RequestHandlerOpts {
     fn default() -> Self {
         Self {
             root_dir: PathBuf::from("./public"),
+            root_filesystem: None,
+            overlay_dirs: Vec::new(),
+            embedded_assets: None,
             compression: true,
             compression_static: false,
+            compression_static_order: vec![
+                ContentCoding::BROTLI,
+                ContentCoding::ZSTD,
+                ContentCoding::GZIP,
+            ],
             #[cfg(any(
                 feature = "compression",
                 feature = "compression-gzip",
@@ -153,40 +405,147 @@ impl Default for RequestHandlerOpts {
                 feature = "compression-deflate"
             ))]
             compression_level: crate::settings::CompressionLevel::Default,
+            #[cfg(any(
+                feature = "compression",
+                feature = "compression-gzip",
+                feature = "compression-brotli",
+                feature = "compression-zstd",
+                feature = "compression-deflate"
+            ))]
+            compression_levels: crate::compression::AlgorithmLevels::default(),
+            #[cfg(any(
+                feature = "compression",
+                feature = "compression-gzip",
+                feature = "compression-brotli",
+                feature = "compression-zstd",
+                feature = "compression-deflate"
+            ))]
+            compression_min_size: 0,
+            #[cfg(any(
+                feature = "compression",
+                feature = "compression-gzip",
+                feature = "compression-brotli",
+                feature = "compression-zstd",
+                feature = "compression-deflate"
+            ))]
+            compression_mime_types: Vec::new(),
+            #[cfg(any(
+                feature = "compression",
+                feature = "compression-gzip",
+                feature = "compression-brotli",
+                feature = "compression-zstd",
+                feature = "compression-deflate"
+            ))]
+            compression_exclude_mime_types: Vec::new(),
+            #[cfg(any(
+                feature = "compression",
+                feature = "compression-gzip",
+                feature = "compression-brotli",
+                feature = "compression-zstd",
+                feature = "compression-deflate"
+            ))]
+            compression_adaptive: false,
             #[cfg(feature = "directory-listing")]
             dir_listing: false,
             #[cfg(feature = "directory-listing")]
-            dir_listing_order: 6, // unordered
+            dir_listing_paths: Vec::new(),
+            #[cfg(feature = "directory-listing")]
+            dir_listing_sort: DirListSort::default(), // unsorted
             #[cfg(feature = "directory-listing")]
             dir_listing_format: DirListFmt::Html,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_dir_size: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_details: false,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_locales: DirListLocales::default(),
+            #[cfg(feature = "directory-listing")]
+            dir_listing_hide: IgnorePatterns::default(),
+            #[cfg(feature = "directory-listing")]
+            dir_listing_readme: DirReadme::default(),
             #[cfg(feature = "directory-listing-download")]
             dir_listing_download: Vec::new(),
             cors: None,
             #[cfg(feature = "experimental")]
             memory_cache: None,
+            chunk_cache: None,
+            #[cfg(feature = "mmap")]
+            mmap_max_size: 0,
+            #[cfg(feature = "cache-invalidation")]
+            cache_invalidation: None,
+            type_map: None,
             security_headers: false,
             cache_control_headers: true,
-            page404: PathBuf::from("./404.html"),
-            page50x: PathBuf::from("./50x.html"),
+            media_streaming: false,
+            error_pages: ErrorPages::new(PathBuf::from("./404.html"), PathBuf::from("./50x.html")),
             #[cfg(feature = "fallback-page")]
             page_fallback: Vec::new(),
+            #[cfg(feature = "fallback-page")]
+            page_fallbacks: Vec::new(),
+            #[cfg(feature = "fallback-page")]
+            page_fallback_soft404: false,
+            #[cfg(feature = "fallback-page")]
+            page_fallback_status: StatusCode::OK,
+            #[cfg(feature = "fallback-page")]
+            page_fallback_methods: vec![hyper::Method::GET],
+            #[cfg(feature = "fallback-page")]
+            page_fallback_html_only: false,
             #[cfg(feature = "basic-auth")]
             basic_auth: String::new(),
             index_files: vec!["index.html".into()],
             log_remote_address: false,
             log_x_real_ip: false,
             log_forwarded_for: false,
-            trusted_proxies: Vec::new(),
+            trusted_proxies: IpAllowlist::default(),
             redirect_trailing_slash: true,
+            reject_absolute_form: false,
             ignore_hidden_files: false,
+            ignore_patterns: IgnorePatterns::default(),
             disable_symlinks: false,
+            follow_symlinks: FollowSymlinks::Always,
             accept_markdown: false,
+            language_variants: Vec::new(),
+            image_variants: Vec::new(),
+            #[cfg(feature = "markdown")]
+            markdown_render: false,
+            #[cfg(feature = "markdown")]
+            markdown_render_template: String::new(),
+            ssi: false,
+            ssi_extensions: vec!["shtml".into()],
+            dev_reload: None,
             health: false,
-            #[cfg(all(unix, feature = "experimental"))]
-            experimental_metrics: false,
+            health_ready_files: Vec::new(),
+            probe_endpoint: String::new(),
+            server_ident: None,
+            alt_svc: None,
+            max_uri_length: 0,
+            max_headers_size: 0,
+            max_body_size: 0,
+            request_timeout: None,
+            not_found_cache: None,
+            bandwidth_limit: None,
+            #[cfg(feature = "archive-preview")]
+            archive_preview: false,
+            #[cfg(feature = "archive-preview")]
+            archive_preview_max_entries: 10_000,
+            #[cfg(feature = "archive-preview")]
+            archive_preview_max_size: 104_857_600,
+            #[cfg(feature = "subresource-integrity")]
+            sri_manifest: String::new(),
+            #[cfg(feature = "subresource-integrity")]
+            sri_algorithm: SriAlgorithm::default(),
+            #[cfg(feature = "subresource-integrity")]
+            sri_extensions: Vec::new(),
+            default_charset: String::new(),
+            #[cfg(feature = "metrics")]
+            metrics: false,
             maintenance_mode: false,
             maintenance_mode_status: StatusCode::SERVICE_UNAVAILABLE,
             maintenance_mode_file: PathBuf::new(),
+            maintenance_mode_allow_ips: IpAllowlist::default(),
+            maintenance_mode_allow_paths: GlobSet::empty(),
+            maintenance_mode_start: None,
+            maintenance_mode_end: None,
             advanced_opts: None,
         }
     }
@@ -194,8 +553,10 @@ impl Default for RequestHandlerOpts {
 
 /// It defines the main request handler used by the Hyper service request.
 pub struct RequestHandler {
-    /// Request handler options.
-    pub opts: Arc<RequestHandlerOpts>,
+    /// Request handler options which can be swapped at runtime (E.g. on configuration reload).
+    pub opts: Arc<ArcSwap<RequestHandlerOpts>>,
+    /// Access-log sinks receiving the records of the handled requests.
+    pub access_log: AccessLog,
 }
 
 impl RequestHandler {
@@ -205,141 +566,316 @@ impl RequestHandler {
         req: &'a mut Request<Body>,
         remote_addr: Option<SocketAddr>,
     ) -> impl Future<Output = Result<Response<Body>, Error>> + Send + 'a {
-        let mut base_path = &self.opts.root_dir;
-        #[cfg(feature = "directory-listing")]
-        let dir_listing = self.opts.dir_listing;
-        #[cfg(feature = "directory-listing")]
-        let dir_listing_order = self.opts.dir_listing_order;
-        #[cfg(feature = "directory-listing")]
-        let dir_listing_format = &self.opts.dir_listing_format;
-        #[cfg(feature = "directory-listing-download")]
-        let dir_listing_download = &self.opts.dir_listing_download;
-        let redirect_trailing_slash = self.opts.redirect_trailing_slash;
-        let compression_static = self.opts.compression_static;
-        let ignore_hidden_files = self.opts.ignore_hidden_files;
-        let disable_symlinks = self.opts.disable_symlinks;
-        let index_files: Vec<&str> = self.opts.index_files.iter().map(|s| s.as_str()).collect();
-        #[cfg(feature = "experimental")]
-        let memory_cache = self.opts.memory_cache.as_ref();
-
-        log_addr::pre_process(&self.opts, req, remote_addr);
-
-        async move {
+        // Take a snapshot of the current options which may be swapped by a configuration reload
+        let opts = self.opts.load_full();
+
+        log_addr::pre_process(&opts, req, remote_addr);
+
+        let access_log = self.access_log.start(req, remote_addr);
+
+        #[cfg(feature = "metrics")]
+        let request_metrics = metrics::RequestMetrics::start(&opts, req);
+
+        let ident = opts.server_ident.clone();
+
+        // The deadline of the request propagated to its filesystem work
+        let deadline = Deadline::new(opts.request_timeout);
+        let fs_deadline = deadline.clone();
+        let (timeout_uri, timeout_method) = (req.uri().clone(), req.method().clone());
+        let timeout_opts = opts.clone();
+
+        // NOTE: the request future is boxed since it's large and moved through the wrappers below
+        let handle = Box::pin(async move {
+            let mut base_path = &opts.root_dir;
+            let mut root_filesystem = opts.root_filesystem.as_deref();
+            let mut overlay_paths = opts.overlay_dirs.as_slice();
+            let mut embedded_assets = opts.embedded_assets.as_ref();
+            #[cfg(feature = "directory-listing")]
+            let dir_listing = opts.dir_listing;
+            #[cfg(feature = "directory-listing")]
+            let dir_listing_sort = &opts.dir_listing_sort;
+            #[cfg(feature = "directory-listing")]
+            let dir_listing_format = &opts.dir_listing_format;
+            #[cfg(feature = "directory-listing")]
+            let dir_listing_dir_size = opts.dir_listing_dir_size.as_ref();
+            #[cfg(feature = "directory-listing")]
+            let dir_listing_details = opts.dir_listing_details;
+            #[cfg(feature = "directory-listing")]
+            let dir_listing_locales = Some(&opts.dir_listing_locales);
+            #[cfg(feature = "directory-listing")]
+            let mut dir_listing_locale = None;
+            #[cfg(feature = "directory-listing")]
+            let dir_listing_hide = Some(&opts.dir_listing_hide).filter(|p| !p.is_empty());
+            #[cfg(feature = "directory-listing")]
+            let dir_listing_readme = Some(&opts.dir_listing_readme).filter(|r| !r.is_empty());
+            #[cfg(feature = "directory-listing-download")]
+            let dir_listing_download = &opts.dir_listing_download;
+            let redirect_trailing_slash = opts.redirect_trailing_slash;
+            let compression_static = opts.compression_static;
+            let compression_static_order = opts.compression_static_order.as_slice();
+            let ignore_hidden_files = opts.ignore_hidden_files;
+            let ignore_patterns = Some(&opts.ignore_patterns).filter(|p| !p.is_empty());
+            let disable_symlinks = opts.disable_symlinks;
+            let follow_symlinks = opts.follow_symlinks;
+            let index_files: Vec<&str> = opts.index_files.iter().map(|s| s.as_str()).collect();
+            #[cfg(feature = "experimental")]
+            let memory_cache = opts.memory_cache.as_ref();
+            let chunk_cache = opts.chunk_cache.as_ref();
+            let type_map = opts.type_map.as_deref();
+
+            // Reject requests exceeding the configured limits
+            if let Some(result) = request_limits::pre_process(&opts, req).await {
+                return result;
+            }
+
             // Reject if the HTTP request method is not allowed
             if !req.method().is_allowed() {
                 return error_page::error_response(
                     req.uri(),
                     req.method(),
                     &StatusCode::METHOD_NOT_ALLOWED,
-                    &self.opts.page404,
-                    &self.opts.page50x,
+                    &opts.error_pages,
                 );
             }
 
             // Health endpoint check
-            if let Some(result) = health::pre_process(&self.opts, req) {
+            if let Some(result) = health::pre_process(&opts, req) {
+                return result;
+            }
+
+            // Probe endpoint check
+            if let Some(result) = probe::pre_process(&opts, req) {
                 return result;
             }
 
             // Metrics endpoint check
-            #[cfg(all(unix, feature = "experimental"))]
-            if let Some(result) = metrics::pre_process(&self.opts, req) {
+            #[cfg(feature = "metrics")]
+            if let Some(result) = metrics::pre_process(&opts, req) {
+                return result;
+            }
+
+            // Live-reload events endpoint check
+            if let Some(result) = dev_reload::pre_process(&opts, req) {
                 return result;
             }
 
             // CORS
-            if let Some(result) = cors::pre_process(&self.opts, req) {
+            let cors = cors::policy_for(&opts, req.uri().path());
+            if let Some(result) = cors::pre_process(cors, &opts, req) {
                 return result;
             }
 
             // `Basic` HTTP Authorization Schema
             #[cfg(feature = "basic-auth")]
-            if let Some(response) = basic_auth::pre_process(&self.opts, req) {
+            if let Some(response) = basic_auth::pre_process(&opts, req) {
                 return response;
             }
 
             // Maintenance Mode
-            if let Some(response) = maintenance_mode::pre_process(&self.opts, req) {
+            if let Some(response) = maintenance_mode::pre_process(&opts, req, remote_addr) {
                 return response;
             }
 
+            // Time-based access restrictions
+            #[cfg(feature = "time-restrictions")]
+            if let Some(result) = time_restrictions::pre_process(&opts, req) {
+                return result;
+            }
+
+            // Subresource Integrity manifest
+            #[cfg(feature = "subresource-integrity")]
+            if let Some(result) = sri::pre_process(&opts, req).await {
+                return result;
+            }
+
             // Redirects
-            if let Some(result) = redirects::pre_process(&self.opts, req) {
+            if let Some(result) = redirects::pre_process(&opts, req) {
                 return result;
             }
 
             // Rewrites
-            if let Some(result) = rewrites::pre_process(&self.opts, req) {
+            if let Some(result) = rewrites::pre_process(&opts, req) {
+                return result;
+            }
+
+            // WebSocket proxy routes
+            if let Some(result) = websocket_proxy::pre_process(&opts, req, remote_addr).await {
                 return result;
             }
 
             // Advanced options
-            if let Some(advanced) = &self.opts.advanced_opts {
+            if let Some(advanced) = &opts.advanced_opts {
                 // If the "Host" header matches any virtual_host, change the root directory
                 if let Some(root) =
                     virtual_hosts::get_real_root(req, advanced.virtual_hosts.as_deref())
                 {
                     base_path = root;
+                    // The root file system, overlay directories and embedded assets
+                    // only apply to the default root directory
+                    root_filesystem = None;
+                    overlay_paths = &[];
+                    embedded_assets = None;
                 }
+
+                // Directory listing locale of the virtual host if any
+                #[cfg(feature = "directory-listing")]
+                if dir_listing {
+                    dir_listing_locale =
+                        virtual_hosts::find(req, advanced.virtual_hosts.as_deref())
+                            .and_then(|vhost| vhost.directory_listing_locale.as_deref());
+                }
+            }
+
+            // Try files
+            if let Some(result) = try_files::pre_process(&opts, req, base_path) {
+                return result;
             }
 
             let index_files = index_files.as_ref();
 
+            // Language content negotiation based on the `Accept-Language` header
+            let language = language_variants::pre_process(&opts, req, base_path, index_files);
+            let uri_path_lang = language.as_ref().and_then(|l| l.uri_path.as_deref());
+
+            // Image format negotiation based on the `Accept` header
+            let image = image_variants::pre_process(&opts, req, base_path);
+            let uri_path_img = image.as_ref().and_then(|i| i.uri_path.as_deref());
+
             // Check for markdown content negotiation (only if enabled)
-            let uri_path_md = if self.opts.accept_markdown {
+            let uri_path_md = if opts.accept_markdown {
                 crate::markdown::pre_process(req, base_path, req.uri().path())
             } else {
                 None
             };
-            let uri_path = uri_path_md.as_deref().unwrap_or(req.uri().path());
+            let uri_path = uri_path_md
+                .as_deref()
+                .or(uri_path_lang)
+                .or(uri_path_img)
+                .unwrap_or(req.uri().path());
+
+            // Directory listing only under the configured request paths if any
+            #[cfg(feature = "directory-listing")]
+            let dir_listing = dir_listing
+                && directory_listing::is_path_allowed(&opts.dir_listing_paths, uri_path);
+
+            // Skip the file system lookup of the paths recently not found
+            let not_found = not_found_cache::lookup(&opts, req, base_path, uri_path);
 
             // Static files
-            let (resp, file_path) = match static_files::handle(&HandleOpts {
-                method: req.method(),
-                headers: req.headers(),
-                #[cfg(feature = "experimental")]
-                memory_cache,
-                base_path,
-                uri_path,
-                uri_query: req.uri().query(),
-                #[cfg(feature = "directory-listing")]
-                dir_listing,
-                #[cfg(feature = "directory-listing")]
-                dir_listing_order,
-                #[cfg(feature = "directory-listing")]
-                dir_listing_format,
-                #[cfg(feature = "directory-listing-download")]
-                dir_listing_download,
-                redirect_trailing_slash,
-                compression_static,
-                ignore_hidden_files,
-                index_files,
-                disable_symlinks,
-            })
-            .await
-            {
-                Ok(result) => (result.resp, Some(result.file_path)),
+            let result = if not_found.is_hit() {
+                Err(StatusCode::NOT_FOUND)
+            } else {
+                static_files::handle(&HandleOpts {
+                    method: req.method(),
+                    headers: req.headers(),
+                    #[cfg(feature = "experimental")]
+                    memory_cache,
+                    chunk_cache,
+                    #[cfg(feature = "mmap")]
+                    mmap_max_size: opts.mmap_max_size,
+                    type_map,
+                    deadline: Some(&fs_deadline),
+                    base_path,
+                    root_filesystem,
+                    overlay_paths,
+                    embedded_assets,
+                    uri_path,
+                    uri_query: req.uri().query(),
+                    #[cfg(feature = "directory-listing")]
+                    dir_listing,
+                    #[cfg(feature = "directory-listing")]
+                    dir_listing_sort,
+                    #[cfg(feature = "directory-listing")]
+                    dir_listing_format,
+                    #[cfg(feature = "directory-listing")]
+                    dir_listing_dir_size,
+                    #[cfg(feature = "directory-listing")]
+                    dir_listing_details,
+                    #[cfg(feature = "directory-listing")]
+                    dir_listing_locales,
+                    #[cfg(feature = "directory-listing")]
+                    dir_listing_locale,
+                    #[cfg(feature = "directory-listing")]
+                    dir_listing_hide,
+                    #[cfg(feature = "directory-listing")]
+                    dir_listing_readme,
+                    #[cfg(feature = "directory-listing-download")]
+                    dir_listing_download,
+                    redirect_trailing_slash,
+                    compression_static,
+                    compression_static_order,
+                    ignore_hidden_files,
+                    ignore_patterns,
+                    index_files,
+                    disable_symlinks,
+                    follow_symlinks,
+                })
+                .await
+            };
+            not_found.store(&result);
+
+            let (resp, file_path) = match result {
+                Ok(result) => {
+                    // The files of a virtual file system have no file on disk to post-process
+                    let file_path = (!vfs::is_vfs_file(&result.resp)).then_some(result.file_path);
+                    (result.resp, file_path)
+                }
                 Err(status) => (
                     error_page::error_response(
                         req.uri(),
                         req.method(),
                         &status,
-                        &self.opts.page404,
-                        &self.opts.page50x,
+                        &opts.error_pages,
                     )?,
                     None,
                 ),
             };
 
+            // Override the MIME type or charset of the file if configured
+            let resp = mime_types::post_process(&opts, req, resp, file_path.as_ref())?;
+
+            // List or extract archive members if requested
+            #[cfg(feature = "archive-preview")]
+            let resp =
+                archive_preview::post_process(&opts, req, resp, file_path.as_ref(), &fs_deadline)
+                    .await?;
+
             // Check for a fallback response
             #[cfg(feature = "fallback-page")]
-            let resp = fallback_page::post_process(&self.opts, req, resp)?;
+            let resp = fallback_page::post_process(&opts, req, resp)?;
+
+            // Rewrite the response status if configured
+            let resp = status_rewrites::post_process(&opts, req, resp)?;
 
             // Append CORS headers if they are present
-            let resp = cors::post_process(&self.opts, req, resp)?;
+            let resp = cors::post_process(cors, req, resp)?;
 
             // Set Content-Type for markdown files
-            let resp = crate::markdown::post_process(uri_path_md.is_some(), &self.opts, resp)?;
+            let resp = crate::markdown::post_process(uri_path_md.is_some(), &opts, resp)?;
+
+            // Render markdown files as HTML if requested
+            #[cfg(feature = "markdown")]
+            let resp = if uri_path_md.is_none() {
+                markdown_render::post_process(&opts, req, resp, file_path.as_ref()).await?
+            } else {
+                resp
+            };
+
+            // Append the `Content-Language` and `Vary` headers of language variants
+            let resp = if uri_path_md.is_none() {
+                language_variants::post_process(language.as_ref(), resp)?
+            } else {
+                resp
+            };
+
+            // Append the `Vary` header of image variants
+            let resp = image_variants::post_process(image.as_ref(), resp)?;
+
+            // Evaluate server-side includes
+            let resp = ssi::post_process(&opts, req, resp, base_path, file_path.as_ref()).await?;
+
+            // Inject the live-reload script into HTML responses
+            let resp = dev_reload::post_process(&opts, req, resp).await?;
 
             // Add a `Vary` header if static compression is used
             #[cfg(any(
@@ -349,7 +885,7 @@ impl RequestHandler {
                 feature = "compression-zstd",
                 feature = "compression-deflate"
             ))]
-            let resp = compression_static::post_process(&self.opts, req, resp)?;
+            let resp = compression_static::post_process(&opts, req, resp)?;
 
             // Auto compression based on the `Accept-Encoding` header
             #[cfg(any(
@@ -359,18 +895,50 @@ impl RequestHandler {
                 feature = "compression-zstd",
                 feature = "compression-deflate"
             ))]
-            let resp = compression::post_process(&self.opts, req, resp)?;
+            let resp = compression::post_process(&opts, req, resp)?;
 
             // Append `Cache-Control` headers for web assets
-            let resp = control_headers::post_process(&self.opts, req, resp)?;
+            let resp = control_headers::post_process(&opts, req, resp)?;
+
+            // Apply the content types and `Cache-Control` headers of media streams
+            let resp = media_streaming::post_process(&opts, req, resp, file_path.as_ref())?;
 
             // Append security headers
-            let resp = security_headers::post_process(&self.opts, req, resp)?;
+            let resp = security_headers::post_process(&opts, req, resp)?;
+
+            // Protect the file responses against MIME type sniffing if configured
+            let resp = content_type_options::post_process(&opts, req, resp)?;
+
+            // Advertise the alternative services
+            let resp = alt_svc::post_process(&opts, req, resp)?;
 
             // Add/update custom headers
-            let resp = custom_headers::post_process(&self.opts, req, resp, file_path.as_ref())?;
+            let resp = custom_headers::post_process(&opts, req, resp, file_path.as_ref())?;
+
+            // Throttle the response body if a bandwidth limit is set
+            let resp = bandwidth::post_process(&opts, resp)?;
 
             Ok(resp)
-        }
+        });
+
+        // Abandon the request once its client disconnects or its timeout elapses
+        let handle = deadline::run(deadline, handle, move || {
+            error_page::error_response(
+                &timeout_uri,
+                &timeout_method,
+                &StatusCode::GATEWAY_TIMEOUT,
+                &timeout_opts.error_pages,
+            )
+        });
+
+        // Identify the server in every response, including the error pages
+        let handle = server_ident::post_process(ident, handle);
+
+        // Record the request metrics once its response is ready
+        #[cfg(feature = "metrics")]
+        let handle = metrics::record(request_metrics, handle);
+
+        // Send the access-log record once the response is ready
+        access_log::record(access_log, handle)
     }
 }
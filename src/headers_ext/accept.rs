@@ -58,10 +58,38 @@ impl Accept {
             .any(|value| value.eq_ignore_ascii_case(media_type))
     }
 
+    /// Returns the most preferred media type of the available ones if any.
+    /// Wildcard media ranges (E.g. `*/*`) are not taken into account.
+    pub(crate) fn preferred<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        self.0
+            .iter_with_quality()
+            .filter(|(_, quality)| *quality > 0)
+            .find_map(|(range, _)| {
+                available
+                    .iter()
+                    .find(|media_type| media_type.eq_ignore_ascii_case(range))
+                    .copied()
+            })
+    }
+
+    /// Returns the quality value of a media type if explicitly accepted.
+    /// Wildcard media ranges (E.g. `image/*`) are not taken into account.
+    pub(crate) fn quality(&self, media_type: &str) -> Option<u16> {
+        self.0
+            .iter_with_quality()
+            .find(|(range, _)| range.eq_ignore_ascii_case(media_type))
+            .map(|(_, quality)| quality)
+    }
+
     /// Returns true if text/markdown is explicitly accepted
     pub(crate) fn accepts_markdown(&self) -> bool {
         self.accepts("text/markdown")
     }
+
+    /// Returns true if text/html is explicitly accepted
+    pub(crate) fn accepts_html(&self) -> bool {
+        self.accepts("text/html")
+    }
 }
 
 #[cfg(test)]
@@ -96,6 +124,35 @@ mod tests {
         assert!(!accept.accepts_markdown());
     }
 
+    #[test]
+    fn accepts_html_explicit() {
+        let val = HeaderValue::from_static("text/html,application/xhtml+xml,*/*;q=0.8");
+        let accept = Accept(val.into());
+        assert!(accept.accepts_html());
+    }
+
+    #[test]
+    fn preferred_media_type() {
+        let available = ["text/plain", "application/json"];
+        let preferred =
+            |value| Accept(HeaderValue::from_static(value).into()).preferred(&available);
+        assert_eq!(
+            preferred("application/json;q=0.5, text/plain;q=0.3"),
+            Some("application/json")
+        );
+        assert_eq!(preferred("text/html, Text/Plain;q=0.8"), Some("text/plain"));
+        assert_eq!(preferred("application/json;q=0, */*"), None);
+    }
+
+    #[test]
+    fn media_type_quality() {
+        let val = HeaderValue::from_static("image/avif, image/webp;q=0.8, image/*;q=0.5");
+        let accept = Accept(val.into());
+        assert_eq!(accept.quality("image/avif"), Some(1000));
+        assert_eq!(accept.quality("IMAGE/WEBP"), Some(800));
+        assert_eq!(accept.quality("image/jxl"), None);
+    }
+
     #[test]
     fn does_not_accept_markdown_html() {
         let val = HeaderValue::from_static("text/html, application/json");
@@ -4,6 +4,7 @@
 
 use headers::{Error, Header};
 use hyper::header::{ACCEPT_ENCODING, HeaderName, HeaderValue};
+use std::str::FromStr;
 
 use super::{ContentCoding, QualityValue};
 
@@ -52,6 +53,20 @@ impl AcceptEncoding {
     pub(crate) fn sorted_encodings(&self) -> impl Iterator<Item = ContentCoding> + '_ {
         self.0.iter().map(ContentCoding::from)
     }
+
+    /// Returns the quality value in thousandths of the given `ContentCoding` if it's accepted.
+    /// It falls back to the quality of the `*` wildcard if the coding is not listed.
+    pub(crate) fn quality(&self, coding: ContentCoding) -> Option<u16> {
+        let mut wildcard = None;
+        for (data, quality) in self.0.iter_with_quality() {
+            if data == "*" {
+                wildcard = wildcard.or(Some(quality));
+            } else if ContentCoding::from_str(data) == Ok(coding) {
+                return Some(quality);
+            }
+        }
+        wildcard
+    }
 }
 
 #[cfg(test)]
@@ -70,4 +85,18 @@ mod tests {
         assert_eq!(encodings.next(), Some(ContentCoding::ZSTD));
         assert_eq!(encodings.next(), None);
     }
+
+    #[test]
+    fn quality() {
+        let val = HeaderValue::from_static("gzip;q=0.8, br, zstd;q=0, *;q=0.1");
+        let accept_enc = AcceptEncoding(val.into());
+
+        assert_eq!(accept_enc.quality(ContentCoding::BROTLI), Some(1000));
+        assert_eq!(accept_enc.quality(ContentCoding::GZIP), Some(800));
+        assert_eq!(accept_enc.quality(ContentCoding::ZSTD), Some(0));
+        assert_eq!(accept_enc.quality(ContentCoding::DEFLATE), Some(100));
+
+        let accept_enc = AcceptEncoding(HeaderValue::from_static("gzip").into());
+        assert_eq!(accept_enc.quality(ContentCoding::BROTLI), None);
+    }
 }
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+use headers::{Error, Header};
+use hyper::header::{ACCEPT_LANGUAGE, HeaderName, HeaderValue};
+
+use super::QualityValue;
+
+/// `Accept-Language` header, defined in
+/// [RFC7231](https://tools.ietf.org/html/rfc7231#section-5.3.5)
+///
+/// The `Accept-Language` header field can be used by user agents to
+/// indicate the set of natural languages that are preferred in the response.
+///
+/// # ABNF
+///
+/// ```text
+/// Accept-Language = 1#( language-range [ weight ] )
+/// language-range  = <language-range, see [RFC4647], Section 2.1>
+/// ```
+///
+/// # Example Values
+///
+/// * `da, en-gb;q=0.8, en;q=0.7`
+/// * `en-us;q=1.0, en;q=0.5, fr`
+///
+#[derive(Clone, Debug)]
+pub(crate) struct AcceptLanguage(QualityValue);
+
+impl Header for AcceptLanguage {
+    fn name() -> &'static HeaderName {
+        &ACCEPT_LANGUAGE
+    }
+
+    fn decode<'i, I>(values: &mut I) -> Result<Self, Error>
+    where
+        I: Iterator<Item = &'i HeaderValue>,
+    {
+        QualityValue::try_from_values(values).map(Self)
+    }
+
+    fn encode<E: Extend<HeaderValue>>(&self, values: &mut E) {
+        values.extend(std::iter::once((&self.0).into()))
+    }
+}
+
+impl AcceptLanguage {
+    /// Returns the most preferred language of the available ones if any.
+    /// A language range also matches its primary language (E.g. `de-CH` matches `de`).
+    pub(crate) fn preferred<'a, I>(&self, available: I) -> Option<&'a str>
+    where
+        I: Iterator<Item = &'a str> + Clone,
+    {
+        self.0
+            .iter_with_quality()
+            .filter(|(range, quality)| *quality > 0 && *range != "*")
+            .find_map(|(range, _)| {
+                let primary = range.split('-').next().unwrap_or(range);
+                available
+                    .clone()
+                    .find(|lang| lang.eq_ignore_ascii_case(range))
+                    .or_else(|| {
+                        available
+                            .clone()
+                            .find(|lang| lang.eq_ignore_ascii_case(primary))
+                    })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AVAILABLE: [&str; 3] = ["en", "es", "pt-br"];
+
+    fn preferred(value: &'static str) -> Option<&'static str> {
+        let accept = AcceptLanguage(HeaderValue::from_static(value).into());
+        accept.preferred(AVAILABLE.into_iter())
+    }
+
+    #[test]
+    fn preferred_by_quality() {
+        assert_eq!(preferred("de, es;q=0.8, en;q=0.9"), Some("en"));
+        assert_eq!(preferred("es-MX, en;q=0.5"), Some("es"));
+        assert_eq!(preferred("PT-BR, pt;q=0.9"), Some("pt-br"));
+    }
+
+    #[test]
+    fn preferred_none() {
+        assert_eq!(preferred("de, fr;q=0.8"), None);
+        assert_eq!(preferred("*"), None);
+        assert_eq!(preferred("es;q=0"), None);
+    }
+}
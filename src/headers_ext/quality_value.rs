@@ -67,6 +67,11 @@ impl<'a> TryFrom<&'a str> for QualityMeta<'a> {
 
 impl QualityValue {
     pub(crate) fn iter(&self) -> impl Iterator<Item = &str> {
+        self.iter_with_quality().map(|(data, _)| data)
+    }
+
+    /// Returns a quality sorted iterator of the values along with their quality in thousandths.
+    pub(crate) fn iter_with_quality(&self) -> impl Iterator<Item = (&str, u16)> {
         let mut items: Vec<_> = self
             .value
             .to_str()
@@ -76,7 +81,7 @@ impl QualityValue {
             .filter_map(|v| QualityMeta::try_from(v).ok())
             .collect();
         items.sort();
-        items.into_iter().map(|pair| pair.data)
+        items.into_iter().map(|pair| (pair.data, pair.quality))
     }
 
     pub(crate) fn try_from_values<'i, I>(values: &mut I) -> Result<Self, Error>
@@ -3,18 +3,31 @@
 // See https://static-web-server.net/ for more information
 // Copyright (C) 2019-present Jose Quintana <joseluisq.net>
 
-//! Module providing the health endpoint.
+//! Module providing the health endpoints.
+//!
+//! - `/health` and `/health/live` (liveness) always succeed while the server is running.
+//! - `/health/ready` (readiness) checks that the root directory is readable
+//!   and that the configured files exist, otherwise it responds with a `503` status code.
 //!
 
 use headers::{ContentType, HeaderMapExt};
-use hyper::{Body, Method, Request, Response};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use std::path::PathBuf;
 
 use crate::{Error, handler::RequestHandlerOpts};
 
-/// Initializes the health endpoint.
-pub fn init(enabled: bool, handler_opts: &mut RequestHandlerOpts) {
+const HEALTH_PATH: &str = "/health";
+const LIVE_PATH: &str = "/health/live";
+const READY_PATH: &str = "/health/ready";
+
+/// Initializes the health endpoints.
+pub fn init(enabled: bool, ready_files: &[PathBuf], handler_opts: &mut RequestHandlerOpts) {
     handler_opts.health = enabled;
-    tracing::info!("health endpoint: enabled={enabled}");
+    handler_opts.health_ready_files = ready_files.to_vec();
+    tracing::info!(
+        "health endpoint: enabled={enabled}, ready_files={:?}",
+        handler_opts.health_ready_files
+    );
 }
 
 /// Handles health requests.
@@ -30,26 +43,71 @@ pub fn pre_process<T>(
         return None;
     }
 
-    let body = match *req.method() {
-        Method::HEAD => Body::empty(),
-        Method::GET => Body::from("OK"),
-        _ => return None,
+    if !matches!(*req.method(), Method::GET | Method::HEAD) {
+        return None;
+    }
+
+    let (status, message) = if req.uri().path() == READY_PATH {
+        match check_readiness(opts) {
+            Ok(()) => (StatusCode::OK, "OK".to_owned()),
+            Err(reason) => {
+                tracing::debug!("health readiness check failed: {reason}");
+                (StatusCode::SERVICE_UNAVAILABLE, reason)
+            }
+        }
+    } else {
+        (StatusCode::OK, "OK".to_owned())
+    };
+
+    let body = if req.method() == Method::HEAD {
+        Body::empty()
+    } else {
+        Body::from(message)
     };
 
     let mut resp = Response::new(body);
+    *resp.status_mut() = status;
     resp.headers_mut().typed_insert(ContentType::html());
     Some(Ok(resp))
 }
 
+/// Checks that the root directory is readable and that all the readiness files exist.
+/// It returns the reason of the first failed check.
+fn check_readiness(opts: &RequestHandlerOpts) -> Result<(), String> {
+    // A root served by a file system (E.g. a root archive) may be a file rather than a directory
+    let readable = if opts.root_filesystem.is_some() {
+        std::fs::metadata(&opts.root_dir).map(drop)
+    } else {
+        std::fs::read_dir(&opts.root_dir).map(drop)
+    };
+    if let Err(err) = readable {
+        return Err(format!(
+            "root directory {} is not readable: {err}",
+            opts.root_dir.display()
+        ));
+    }
+
+    for file in &opts.health_ready_files {
+        // NOTE: absolute paths are kept as they are by `join`
+        let path = opts.root_dir.join(file);
+        if !path.is_file() {
+            return Err(format!("file {} does not exist", file.display()));
+        }
+    }
+
+    Ok(())
+}
+
 pub(crate) fn is_health_endpoint<T>(req: &Request<T>) -> bool {
-    req.uri().path() == "/health"
+    matches!(req.uri().path(), HEALTH_PATH | LIVE_PATH | READY_PATH)
 }
 
 #[cfg(test)]
 mod tests {
     use super::pre_process;
     use crate::handler::RequestHandlerOpts;
-    use hyper::{Body, Request};
+    use hyper::{Body, Request, StatusCode};
+    use std::path::PathBuf;
 
     fn make_request(method: &str, uri: &str) -> Request<Body> {
         Request::builder()
@@ -114,4 +172,57 @@ mod tests {
             .is_some()
         );
     }
+
+    #[test]
+    fn test_liveness() {
+        let opts = RequestHandlerOpts {
+            health: true,
+            root_dir: PathBuf::from("tests/fixtures/unknown"),
+            ..Default::default()
+        };
+        let resp = pre_process(&opts, &make_request("GET", "/health/live"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_readiness() {
+        let mut opts = RequestHandlerOpts {
+            health: true,
+            root_dir: PathBuf::from("tests/fixtures/public"),
+            health_ready_files: vec![PathBuf::from("404.html")],
+            ..Default::default()
+        };
+        let resp = pre_process(&opts, &make_request("GET", "/health/ready"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        opts.health_ready_files.push(PathBuf::from("missing.html"));
+        let resp = pre_process(&opts, &make_request("GET", "/health/ready"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        assert_eq!(body, "file missing.html does not exist");
+
+        let resp = pre_process(&opts, &make_request("HEAD", "/health/ready"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[test]
+    fn test_readiness_unreadable_root() {
+        let opts = RequestHandlerOpts {
+            health: true,
+            root_dir: PathBuf::from("tests/fixtures/unknown"),
+            ..Default::default()
+        };
+        let resp = pre_process(&opts, &make_request("GET", "/health/ready"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
 }
@@ -38,6 +38,17 @@ pub fn read_file(path: &Path) -> Result<String> {
     }
 }
 
+/// Get the current UTC date and time in the RFC 3339 format (E.g. `2024-01-10T17:02:41.216934Z`).
+pub fn rfc3339_now() -> String {
+    use tracing_subscriber::fmt::{format::Writer, time::FormatTime};
+
+    let mut now = String::new();
+    tracing_subscriber::fmt::time::SystemTime
+        .format_time(&mut Writer::new(&mut now))
+        .ok();
+    now
+}
+
 pub fn stringify(dst: &mut String, path: &serde_ignored::Path<'_>) {
     use serde_ignored::Path;
 
@@ -7,11 +7,17 @@
 //!
 
 use headers::{HeaderMapExt, Host};
-use hyper::{Body, Request, Response, StatusCode, header::LOCATION};
+use hyper::{
+    Body, Request, Response, StatusCode,
+    header::{LOCATION, STRICT_TRANSPORT_SECURITY},
+};
 use std::sync::Arc;
 
 use crate::Result;
 
+/// HSTS policy meeting the requirements of the browsers preload lists (2 years max-age).
+const HSTS_PRELOAD: &str = "max-age=63072000; includeSubDomains; preload";
+
 /// HTTPS redirect options.
 pub struct RedirectOpts {
     /// HTTPS hostname to redirect to.
@@ -20,6 +26,24 @@ pub struct RedirectOpts {
     pub https_port: u16,
     /// Hostnames or IPS to redirect from.
     pub allowed_hosts: Vec<String>,
+    /// Redirect status code, either `301` or `308`.
+    pub status: StatusCode,
+    /// Whether to redirect to the requested hostname instead of the HTTPS hostname.
+    pub preserve_host: bool,
+}
+
+/// Gets the redirect status code, only permanent redirects being allowed.
+pub(crate) fn redirect_status(code: u16) -> Result<StatusCode> {
+    match StatusCode::from_u16(code) {
+        Ok(status @ (StatusCode::MOVED_PERMANENTLY | StatusCode::PERMANENT_REDIRECT)) => Ok(status),
+        _ => bail!("invalid https redirect status {code}, it should be either 301 or 308"),
+    }
+}
+
+/// Appends the preload-grade `Strict-Transport-Security` header to an HTTPS response.
+pub(crate) fn append_hsts(resp: &mut Response<Body>) {
+    resp.headers_mut()
+        .insert(STRICT_TRANSPORT_SECURITY, HSTS_PRELOAD.parse().unwrap());
 }
 
 /// It redirects all requests from HTTP to HTTPS.
@@ -38,20 +62,113 @@ pub fn redirect_to_https<T>(
             return Err(StatusCode::BAD_REQUEST);
         }
 
-        let url = format!(
-            "https://{}:{}{}",
-            opts.https_hostname,
-            opts.https_port,
-            req.uri()
-        );
+        let hostname = if opts.preserve_host {
+            from_hostname
+        } else {
+            opts.https_hostname.as_str()
+        };
+        // The default HTTPS port is omitted
+        let port = match opts.https_port {
+            443 => String::new(),
+            port => format!(":{port}"),
+        };
+        let path_and_query = req.uri().path_and_query().map_or("/", |p| p.as_str());
+
+        let url = format!("https://{hostname}{port}{path_and_query}");
         tracing::debug!("https redirect to {}", url);
 
+        let Ok(location) = url.parse() else {
+            tracing::debug!("https redirect location is invalid: {}", url);
+            return Err(StatusCode::BAD_REQUEST);
+        };
         let mut resp = Response::new(Body::empty());
-        *resp.status_mut() = StatusCode::MOVED_PERMANENTLY;
-        resp.headers_mut().insert(LOCATION, url.parse().unwrap());
+        *resp.status_mut() = opts.status;
+        resp.headers_mut().insert(LOCATION, location);
         return Ok(resp);
     }
 
     tracing::debug!("redirect host was not determined!");
     Err(StatusCode::BAD_REQUEST)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{RedirectOpts, redirect_status, redirect_to_https};
+    use hyper::{Body, Request, StatusCode};
+    use std::sync::Arc;
+
+    fn redirect_opts(port: u16, status: StatusCode, preserve_host: bool) -> Arc<RedirectOpts> {
+        Arc::new(RedirectOpts {
+            https_hostname: "example.com".to_owned(),
+            https_port: port,
+            allowed_hosts: vec!["example.com".to_owned(), "www.example.com".to_owned()],
+            status,
+            preserve_host,
+        })
+    }
+
+    fn redirect(
+        uri: &str,
+        host: &str,
+        opts: Arc<RedirectOpts>,
+    ) -> Result<(StatusCode, String), StatusCode> {
+        let req = Request::get(uri)
+            .header("host", host)
+            .body(Body::empty())
+            .unwrap();
+        redirect_to_https(&req, opts).map(|resp| {
+            let location = resp.headers()["location"].to_str().unwrap().to_owned();
+            (resp.status(), location)
+        })
+    }
+
+    #[test]
+    fn test_redirect_to_https() {
+        let opts = redirect_opts(8443, StatusCode::MOVED_PERMANENTLY, false);
+        assert_eq!(
+            redirect("/assets/app.js?v=1", "www.example.com", opts.clone()),
+            Ok((
+                StatusCode::MOVED_PERMANENTLY,
+                "https://example.com:8443/assets/app.js?v=1".to_owned()
+            ))
+        );
+        assert_eq!(
+            redirect(
+                "http://example.com:8080/a?b",
+                "example.com:8080",
+                opts.clone()
+            ),
+            Ok((
+                StatusCode::MOVED_PERMANENTLY,
+                "https://example.com:8443/a?b".to_owned()
+            ))
+        );
+        assert_eq!(
+            redirect("/", "other.com", opts),
+            Err(StatusCode::BAD_REQUEST)
+        );
+    }
+
+    #[test]
+    fn test_redirect_to_https_preserve_host() {
+        let opts = redirect_opts(443, StatusCode::PERMANENT_REDIRECT, true);
+        assert_eq!(
+            redirect("/index.html?q=1", "www.example.com", opts),
+            Ok((
+                StatusCode::PERMANENT_REDIRECT,
+                "https://www.example.com/index.html?q=1".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_redirect_status() {
+        assert_eq!(redirect_status(301).unwrap(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(
+            redirect_status(308).unwrap(),
+            StatusCode::PERMANENT_REDIRECT
+        );
+        assert!(redirect_status(302).is_err());
+        assert!(redirect_status(1000).is_err());
+    }
+}
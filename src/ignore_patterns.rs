@@ -0,0 +1,131 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that prevents serving the files matching some glob patterns.
+//!
+//! Like in `.gitignore` files, a pattern without a slash (E.g. `*.bak`) matches the name
+//! of a file or directory at any level, while a pattern with a slash (E.g. `secret/**`)
+//! matches the path relative to the root directory.
+//!
+
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use std::path::{Component, Path};
+use std::sync::Arc;
+
+use crate::{Context, Result, handler::RequestHandlerOpts};
+
+/// Glob patterns of the files and directories which are never served.
+#[derive(Clone, Debug, Default)]
+pub struct IgnorePatterns {
+    /// Patterns matching a file or directory name at any level.
+    names: Arc<GlobSet>,
+    /// Patterns matching a path relative to the root directory.
+    paths: Arc<GlobSet>,
+}
+
+impl IgnorePatterns {
+    /// Builds the ignore patterns from a list of glob patterns.
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let mut names = GlobSetBuilder::new();
+        let mut paths = GlobSetBuilder::new();
+        for pattern in patterns.iter().map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            let relative = pattern.trim_start_matches('/');
+            if relative.contains('/') {
+                paths.add(build_glob(relative)?);
+            } else {
+                names.add(build_glob(relative)?);
+            }
+        }
+        Ok(Self {
+            names: Arc::new(
+                names
+                    .build()
+                    .context("unable to build the ignore name patterns")?,
+            ),
+            paths: Arc::new(
+                paths
+                    .build()
+                    .context("unable to build the ignore path patterns")?,
+            ),
+        })
+    }
+
+    /// Checks if there are no ignore patterns.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty() && self.paths.is_empty()
+    }
+
+    /// Checks if a path relative to the root directory matches any of the ignore patterns.
+    pub fn is_ignored(&self, rel_path: &Path) -> bool {
+        if rel_path.as_os_str().is_empty() {
+            return false;
+        }
+        self.paths.is_match(rel_path)
+            || rel_path.components().any(|component| {
+                matches!(component, Component::Normal(name) if self.names.is_match(name))
+            })
+    }
+
+    /// Checks if a path under the base directory matches any of the ignore patterns.
+    pub fn is_ignored_in(&self, base: &Path, path: &Path) -> bool {
+        path.strip_prefix(base)
+            .is_ok_and(|rel_path| self.is_ignored(rel_path))
+    }
+}
+
+/// Builds a glob pattern whose wildcards don't match the path separator.
+fn build_glob(pattern: &str) -> Result<Glob> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .with_context(|| format!("invalid ignore glob pattern: {pattern}"))
+}
+
+/// Initializes the ignore patterns.
+pub(crate) fn init(patterns: &[String], handler_opts: &mut RequestHandlerOpts) -> Result {
+    let ignore_patterns = IgnorePatterns::new(patterns)?;
+    tracing::info!(
+        "ignore patterns: enabled={}, patterns={:?}",
+        !ignore_patterns.is_empty(),
+        patterns
+    );
+    handler_opts.ignore_patterns = ignore_patterns;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IgnorePatterns;
+    use std::path::Path;
+
+    #[test]
+    fn test_is_ignored() {
+        let patterns = ["*.bak", "**/.git/**", "/secret/**", " ", "node_modules"].map(String::from);
+        let ignore = IgnorePatterns::new(&patterns).unwrap();
+        assert!(!ignore.is_empty());
+
+        assert!(ignore.is_ignored(Path::new("index.html.bak")));
+        assert!(ignore.is_ignored(Path::new("assets/old/main.js.bak")));
+        assert!(ignore.is_ignored(Path::new(".git/config")));
+        assert!(ignore.is_ignored(Path::new("app/.git/HEAD")));
+        assert!(ignore.is_ignored(Path::new("secret/keys.txt")));
+        assert!(ignore.is_ignored(Path::new("node_modules/pkg/index.js")));
+        assert!(ignore.is_ignored(Path::new("app/node_modules")));
+
+        assert!(!ignore.is_ignored(Path::new("")));
+        assert!(!ignore.is_ignored(Path::new("index.html")));
+        assert!(!ignore.is_ignored(Path::new("app/secret/keys.txt")));
+        assert!(!ignore.is_ignored(Path::new("backup.bak.html")));
+
+        assert!(ignore.is_ignored_in(Path::new("public"), Path::new("public/a.bak")));
+        assert!(!ignore.is_ignored_in(Path::new("public"), Path::new("other/a.bak")));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(IgnorePatterns::new(&["secret/[".to_owned()]).is_err());
+        assert!(IgnorePatterns::new(&[]).unwrap().is_empty());
+    }
+}
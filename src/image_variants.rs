@@ -0,0 +1,213 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Image format negotiation module.
+//!
+//! It serves a modern image format variant of an image (E.g. `photo.jpg.avif` for `photo.jpg`)
+//! when the client accepts it via the `Accept` header and the variant exists on disk.
+//!
+
+use headers::HeaderMapExt;
+use hyper::{
+    Body, Method, Request, Response,
+    header::{ACCEPT, HeaderValue, VARY},
+};
+use mime_guess::mime;
+use std::path::Path;
+
+use crate::fs::{meta::try_metadata, path::sanitize_path};
+use crate::headers_ext::Accept;
+use crate::{Error, Result, handler::RequestHandlerOpts};
+
+/// Image format of the image variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// AVIF format (`.avif` variants).
+    Avif,
+    /// WebP format (`.webp` variants).
+    Webp,
+    /// JPEG XL format (`.jxl` variants).
+    Jxl,
+}
+
+impl ImageFormat {
+    /// Returns the file extension of the image format variants.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Avif => "avif",
+            Self::Webp => "webp",
+            Self::Jxl => "jxl",
+        }
+    }
+
+    /// Returns the media type of the image format.
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            Self::Avif => "image/avif",
+            Self::Webp => "image/webp",
+            Self::Jxl => "image/jxl",
+        }
+    }
+}
+
+/// The image variant negotiated for a request.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Negotiated {
+    /// URI path of the variant to serve, or `None` to serve the requested image.
+    pub uri_path: Option<String>,
+}
+
+/// Parses a comma-separated list of image formats in priority order.
+pub(crate) fn parse(value: &str) -> Result<Vec<ImageFormat>> {
+    let mut formats = Vec::new();
+    for name in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let format = match name.to_ascii_lowercase().as_str() {
+            "avif" => ImageFormat::Avif,
+            "webp" => ImageFormat::Webp,
+            "jxl" => ImageFormat::Jxl,
+            _ => bail!("invalid image variant format: {name}"),
+        };
+        if !formats.contains(&format) {
+            formats.push(format);
+        }
+    }
+    Ok(formats)
+}
+
+/// Initializes the image format negotiation.
+pub(crate) fn init(value: &str, handler_opts: &mut RequestHandlerOpts) -> Result {
+    handler_opts.image_variants = parse(value)?;
+    tracing::info!(
+        "image variants: enabled={}, formats={}",
+        !handler_opts.image_variants.is_empty(),
+        handler_opts
+            .image_variants
+            .iter()
+            .map(ImageFormat::extension)
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+    Ok(())
+}
+
+/// Checks if a URI path is an image which may have variants.
+fn is_image(uri_path: &str, formats: &[ImageFormat]) -> bool {
+    let Some((_, ext)) = uri_path.rsplit_once('.') else {
+        return false;
+    };
+    if ext.contains('/')
+        || formats
+            .iter()
+            .any(|f| f.extension().eq_ignore_ascii_case(ext))
+    {
+        return false;
+    }
+    mime_guess::from_ext(ext)
+        .first()
+        .is_some_and(|mime| mime.type_() == mime::IMAGE)
+}
+
+/// Checks if a URI path maps to an existing file.
+fn is_file(base_path: &Path, uri_path: &str) -> bool {
+    sanitize_path(base_path, uri_path)
+        .ok()
+        .is_some_and(|path| matches!(try_metadata(&path), Ok((_, false))))
+}
+
+/// Negotiates the image variant of the requested image if there are any.
+pub(crate) fn pre_process<T>(
+    opts: &RequestHandlerOpts,
+    req: &Request<T>,
+    base_path: &Path,
+) -> Option<Negotiated> {
+    let formats = &opts.image_variants;
+    if formats.is_empty() || !matches!(*req.method(), Method::GET | Method::HEAD) {
+        return None;
+    }
+
+    let uri_path = req.uri().path();
+    if !is_image(uri_path, formats) {
+        return None;
+    }
+
+    let mut available: Vec<(ImageFormat, String)> = formats
+        .iter()
+        .map(|format| (*format, [uri_path, ".", format.extension()].concat()))
+        .filter(|(_, path)| is_file(base_path, path))
+        .collect();
+    if available.is_empty() {
+        return None;
+    }
+
+    // Variants are sorted by their `Accept` quality values and then by the configured order
+    let accept = req.headers().typed_get::<Accept>();
+    let quality = |format: &ImageFormat| {
+        accept
+            .as_ref()
+            .and_then(|accept| accept.quality(format.media_type()))
+            .unwrap_or(0)
+    };
+    available.retain(|(format, _)| quality(format) > 0);
+    available.sort_by_key(|(format, _)| std::cmp::Reverse(quality(format)));
+
+    let uri_path = available.into_iter().next().map(|(format, path)| {
+        tracing::debug!(
+            "image variants: serving the {} variant {}",
+            format.extension(),
+            path
+        );
+        path
+    });
+    Some(Negotiated { uri_path })
+}
+
+/// Appends the `Vary` header to the response of a request for an image with variants.
+pub(crate) fn post_process(
+    negotiated: Option<&Negotiated>,
+    mut resp: Response<Body>,
+) -> Result<Response<Body>, Error> {
+    if negotiated.is_none() {
+        return Ok(resp);
+    }
+
+    // The response varies depending on the accepted media types
+    let value = resp
+        .headers()
+        .get(VARY)
+        .map_or(HeaderValue::from_name(ACCEPT), |h| {
+            let mut s = h.to_str().unwrap_or_default().to_owned();
+            s.push(',');
+            s.push_str(ACCEPT.as_str());
+            HeaderValue::from_str(s.as_str()).unwrap()
+        });
+    resp.headers_mut().insert(VARY, value);
+    Ok(resp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImageFormat, is_image, parse};
+
+    #[test]
+    fn test_parse() {
+        assert!(parse("").unwrap().is_empty());
+        assert_eq!(
+            parse(" AVIF, webp,avif ").unwrap(),
+            [ImageFormat::Avif, ImageFormat::Webp]
+        );
+        assert!(parse("avif,png").is_err());
+    }
+
+    #[test]
+    fn test_is_image() {
+        let formats = [ImageFormat::Avif, ImageFormat::Webp];
+        assert!(is_image("/images/photo.jpg", &formats));
+        assert!(is_image("/images/logo.PNG", &formats));
+        assert!(!is_image("/images/photo.avif", &formats));
+        assert!(!is_image("/images/photo.webp", &formats));
+        assert!(!is_image("/assets/main.js", &formats));
+        assert!(!is_image("/images.d/photo", &formats));
+    }
+}
@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Network interface module to resolve the address to bind to by interface name.
+//!
+
+use std::net::IpAddr;
+
+use crate::{Context, Result};
+
+/// Resolves the host address to bind to, either from the given network interface name
+/// if specified or by parsing the given host address.
+pub(crate) fn host_address(host: &str, interface: Option<&str>) -> Result<IpAddr> {
+    match interface {
+        Some(name) => resolve(name),
+        None => host
+            .parse::<IpAddr>()
+            .with_context(|| format!("failed to parse {host} address")),
+    }
+}
+
+/// Resolves the address to bind to for the given network interface name.
+///
+/// The interface addresses are resolved at startup, preferring an IPv4 address
+/// over an IPv6 one and skipping IPv6 link-local addresses.
+pub(crate) fn resolve(name: &str) -> Result<IpAddr> {
+    let addrs: Vec<IpAddr> = if_addrs::get_if_addrs()
+        .with_context(|| "failed to get the network interfaces of the system")?
+        .into_iter()
+        .filter(|iface| iface.name == name)
+        .map(|iface| iface.ip())
+        .collect();
+
+    if addrs.is_empty() {
+        bail!("network interface \"{name}\" was not found or has no addresses assigned");
+    }
+
+    tracing::debug!(
+        "network interface \"{}\" addresses: {}",
+        name,
+        addrs
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    match select_address(&addrs) {
+        Some(ip) => {
+            tracing::info!("network interface \"{name}\" resolved to address {ip}");
+            Ok(ip)
+        }
+        None => bail!("network interface \"{name}\" has no suitable address to bind to"),
+    }
+}
+
+/// Selects the address to bind to from the given interface addresses.
+fn select_address(addrs: &[IpAddr]) -> Option<IpAddr> {
+    addrs
+        .iter()
+        .find(|ip| ip.is_ipv4())
+        .or_else(|| addrs.iter().find(|ip| !is_ipv6_link_local(ip)))
+        .copied()
+}
+
+/// Checks whether the address is an IPv6 link-local (`fe80::/10`) address
+/// which can not be bound to without its scope identifier.
+fn is_ipv6_link_local(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V6(ip) => (ip.segments()[0] & 0xffc0) == 0xfe80,
+        IpAddr::V4(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::select_address;
+    use std::net::IpAddr;
+
+    fn ips(addrs: &[&str]) -> Vec<IpAddr> {
+        addrs.iter().map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_select_address() {
+        assert_eq!(
+            select_address(&ips(&["fe80::1", "2001:db8::1", "192.168.1.10"])),
+            Some("192.168.1.10".parse().unwrap())
+        );
+        assert_eq!(
+            select_address(&ips(&["fe80::1", "2001:db8::1"])),
+            Some("2001:db8::1".parse().unwrap())
+        );
+        assert_eq!(select_address(&ips(&["fe80::1"])), None);
+        assert_eq!(select_address(&[]), None);
+    }
+}
@@ -0,0 +1,258 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module providing allowlists of IP addresses which also accept networks and host names.
+//!
+//! Host names are resolved on start up and refreshed periodically in the background,
+//! useful when the IPs of a peer (E.g. a load balancer) change while its host name is stable.
+//!
+
+use arc_swap::ArcSwap;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use crate::Result;
+
+/// An allowlist of IP addresses, networks and host names.
+#[derive(Clone, Debug, Default)]
+pub struct IpAllowlist {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// Literal IP addresses.
+    ips: Vec<IpAddr>,
+    /// Networks in CIDR notation.
+    nets: Vec<IpNet>,
+    /// Host names to resolve.
+    hosts: Vec<String>,
+    /// Last resolved IP addresses of the host names.
+    resolved: ArcSwap<Vec<IpAddr>>,
+}
+
+impl IpAllowlist {
+    /// Creates an allowlist from IP addresses, networks (E.g. `10.0.0.0/8`) and host names,
+    /// where the host names are resolved again every `refresh` seconds unless it's zero.
+    pub fn new(entries: &[String], refresh: u64) -> Result<Self> {
+        let mut ips = Vec::new();
+        let mut nets = Vec::new();
+        let mut hosts = Vec::new();
+        for entry in entries.iter().map(|entry| entry.trim()) {
+            if let Ok(ip) = entry.parse::<IpAddr>() {
+                ips.push(ip.to_canonical());
+            } else if let Some(net) = IpNet::parse(entry) {
+                nets.push(net);
+            } else if is_host_name(entry) {
+                hosts.push(entry.to_lowercase());
+            } else {
+                bail!("invalid IP address, network or host name in allowlist: \"{entry}\"");
+            }
+        }
+
+        let inner = Arc::new(Inner {
+            resolved: ArcSwap::from_pointee(resolve(&hosts)),
+            ips,
+            nets,
+            hosts,
+        });
+        if !inner.hosts.is_empty() && refresh > 0 {
+            spawn_refresh(Arc::downgrade(&inner), Duration::from_secs(refresh));
+        }
+
+        Ok(Self { inner })
+    }
+
+    /// Whether the allowlist has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.inner.ips.is_empty() && self.inner.nets.is_empty() && self.inner.hosts.is_empty()
+    }
+
+    /// Whether the IP address is one of the allowlist, belongs to one of its networks
+    /// or is one of its resolved host names.
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.inner.ips.contains(&ip)
+            || self.inner.nets.iter().any(|net| net.contains(&ip))
+            || self.inner.resolved.load().contains(&ip)
+    }
+}
+
+/// A network of IP addresses in CIDR notation.
+#[derive(Debug, Clone, Copy)]
+struct IpNet {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNet {
+    fn parse(value: &str) -> Option<Self> {
+        let (addr, prefix_len) = value.split_once('/')?;
+        let addr = addr.parse::<IpAddr>().ok()?.to_canonical();
+        let prefix_len = prefix_len.parse::<u8>().ok()?;
+        (prefix_len <= max_prefix_len(&addr)).then_some(Self { addr, prefix_len })
+    }
+
+    /// Whether the canonical IP address belongs to the network.
+    fn contains(&self, ip: &IpAddr) -> bool {
+        let (net, ip) = match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => (u32::from(net) as u128, u32::from(*ip) as u128),
+            (IpAddr::V6(net), IpAddr::V6(ip)) => (u128::from(net), u128::from(*ip)),
+            _ => return false,
+        };
+        // The bits of the host part are shifted out before comparing
+        let host_len = u32::from(max_prefix_len(&self.addr) - self.prefix_len);
+        (net ^ ip).checked_shr(host_len).unwrap_or(0) == 0
+    }
+}
+
+impl std::fmt::Display for IpNet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+fn max_prefix_len(addr: &IpAddr) -> u8 {
+    if addr.is_ipv4() { 32 } else { 128 }
+}
+
+impl std::fmt::Display for IpAllowlist {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ips = self.inner.ips.iter().map(ToString::to_string);
+        let nets = self.inner.nets.iter().map(ToString::to_string);
+        let entries: Vec<_> = ips
+            .chain(nets)
+            .chain(self.inner.hosts.iter().cloned())
+            .collect();
+        write!(f, "[{}]", entries.join(", "))
+    }
+}
+
+/// Refreshes the resolved IPs of the host names until the allowlist is dropped.
+/// NOTE: a dedicated thread is used since the resolution of host names is blocking.
+fn spawn_refresh(inner: Weak<Inner>, interval: Duration) {
+    let spawned = std::thread::Builder::new()
+        .name("sws-ip-allowlist".into())
+        .spawn(move || {
+            loop {
+                std::thread::sleep(interval);
+                let Some(inner) = inner.upgrade() else {
+                    return;
+                };
+                let resolved = resolve(&inner.hosts);
+                if resolved.is_empty() {
+                    // Keep the last known IPs when the host names can't be resolved
+                    continue;
+                }
+                if **inner.resolved.load() != resolved {
+                    tracing::info!(
+                        "allowlist host names {:?} resolved to {:?}",
+                        inner.hosts,
+                        resolved
+                    );
+                    inner.resolved.store(Arc::new(resolved));
+                }
+            }
+        });
+
+    if let Err(err) = spawned {
+        tracing::error!("unable to start the allowlist host names refresh: {err}");
+    }
+}
+
+/// Resolves the IP addresses of the host names.
+fn resolve(hosts: &[String]) -> Vec<IpAddr> {
+    let mut ips = Vec::new();
+    for host in hosts {
+        match (host.as_str(), 0).to_socket_addrs() {
+            Ok(addrs) => ips.extend(addrs.map(|addr| addr.ip().to_canonical())),
+            Err(err) => tracing::warn!("unable to resolve allowlist host name {host}: {err}"),
+        }
+    }
+    ips.sort_unstable();
+    ips.dedup();
+    ips
+}
+
+fn is_host_name(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= 253
+        && value.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IpAllowlist;
+    use std::net::IpAddr;
+
+    fn allowlist(entries: &[&str]) -> IpAllowlist {
+        let entries: Vec<String> = entries.iter().map(|entry| entry.to_string()).collect();
+        IpAllowlist::new(&entries, 0).unwrap()
+    }
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_ips() {
+        let list = allowlist(&["10.0.0.1", "::1"]);
+        assert!(!list.is_empty());
+        assert!(list.contains(&ip("10.0.0.1")));
+        assert!(list.contains(&ip("::ffff:10.0.0.1")));
+        assert!(list.contains(&ip("::1")));
+        assert!(!list.contains(&ip("10.0.0.2")));
+        assert!(allowlist(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_host_names() {
+        let list = allowlist(&["localhost", "10.0.0.1"]);
+        assert!(list.contains(&ip("127.0.0.1")));
+        assert!(list.contains(&ip("10.0.0.1")));
+        assert!(!list.contains(&ip("10.0.0.2")));
+        assert_eq!(list.to_string(), "[10.0.0.1, localhost]");
+    }
+
+    #[test]
+    fn test_networks() {
+        let list = allowlist(&["10.0.0.0/8", "192.168.1.7/32", "fd00::/8"]);
+        assert!(list.contains(&ip("10.1.2.3")));
+        assert!(list.contains(&ip("::ffff:10.1.2.3")));
+        assert!(!list.contains(&ip("11.0.0.1")));
+        assert!(list.contains(&ip("192.168.1.7")));
+        assert!(!list.contains(&ip("192.168.1.8")));
+        assert!(list.contains(&ip("fd12::1")));
+        assert!(!list.contains(&ip("fe80::1")));
+        assert_eq!(list.to_string(), "[10.0.0.0/8, 192.168.1.7/32, fd00::/8]");
+
+        let any = allowlist(&["0.0.0.0/0"]);
+        assert!(any.contains(&ip("203.0.113.9")));
+        assert!(!any.contains(&ip("::1")));
+    }
+
+    #[test]
+    fn test_invalid_entries() {
+        for entry in [
+            "",
+            "lb_1.example.com",
+            "-lb.example.com",
+            "lb..example.com",
+            "10.0.0.0/33",
+            "::/129",
+            "10.0.0.0/",
+            "lb/8",
+        ] {
+            assert!(IpAllowlist::new(&[entry.to_owned()], 0).is_err(), "{entry}");
+        }
+    }
+}
@@ -0,0 +1,249 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Language content negotiation module.
+//!
+//! It serves the language variant of a file (E.g. `page.de.html` for `page.html`)
+//! preferred by the client according to its `Accept-Language` header.
+//!
+
+use headers::HeaderMapExt;
+use hyper::{
+    Body, Method, Request, Response,
+    header::{ACCEPT_LANGUAGE, CONTENT_LANGUAGE, HeaderValue, VARY},
+};
+use std::path::Path;
+
+use crate::fs::{meta::try_metadata, path::sanitize_path};
+use crate::headers_ext::AcceptLanguage;
+use crate::{Error, Result, handler::RequestHandlerOpts};
+
+/// A language of the site along with the file name suffix of its variants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageVariant {
+    /// Language tag sent in the `Content-Language` header (E.g. `pt-BR`).
+    pub tag: String,
+    /// File name suffix of the variants (E.g. `pt` for `page.pt.html`).
+    pub suffix: String,
+}
+
+/// The language variant negotiated for a request.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Negotiated {
+    /// URI path of the variant to serve, or `None` to serve the requested path.
+    pub uri_path: Option<String>,
+    /// Language of the variant to serve.
+    pub language: Option<String>,
+}
+
+/// Parses a comma-separated list of languages like `en,de,pt-BR=pt`
+/// where an optional `=suffix` sets the file name suffix of the variants.
+/// The first language is the default one.
+pub(crate) fn parse(value: &str) -> Result<Vec<LanguageVariant>> {
+    let is_valid =
+        |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+
+    let mut variants = Vec::new();
+    for entry in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let (tag, suffix) = entry
+            .split_once('=')
+            .map_or((entry, entry), |(tag, suffix)| (tag.trim(), suffix.trim()));
+        if !is_valid(tag) {
+            bail!("invalid language tag `{tag}` in language variant `{entry}`");
+        }
+        if !is_valid(suffix) {
+            bail!("invalid file name suffix `{suffix}` in language variant `{entry}`");
+        }
+        if variants
+            .iter()
+            .any(|v: &LanguageVariant| v.tag.eq_ignore_ascii_case(tag))
+        {
+            bail!("duplicated language tag `{tag}` in language variants");
+        }
+        variants.push(LanguageVariant {
+            tag: tag.to_owned(),
+            suffix: suffix.to_owned(),
+        });
+    }
+    Ok(variants)
+}
+
+/// Initializes the language content negotiation.
+pub(crate) fn init(value: &str, handler_opts: &mut RequestHandlerOpts) -> Result {
+    handler_opts.language_variants = parse(value)?;
+    tracing::info!(
+        "language variants: enabled={}, languages={}",
+        !handler_opts.language_variants.is_empty(),
+        value.trim()
+    );
+    Ok(())
+}
+
+/// Returns the URI path of the variant of a file for a file name suffix,
+/// E.g. `/docs/page.de.html` for `/docs/page.html`. Directory paths use the index files.
+fn variant_paths<'a>(
+    uri_path: &'a str,
+    suffix: &'a str,
+    index_files: &'a [&'a str],
+) -> impl Iterator<Item = String> + 'a {
+    let (dir, name) = uri_path.rsplit_once('/').unwrap_or(("", uri_path));
+    let names: Vec<&str> = if name.is_empty() {
+        index_files.to_vec()
+    } else {
+        vec![name]
+    };
+    names.into_iter().filter_map(move |name| {
+        let (stem, ext) = name.rsplit_once('.')?;
+        if stem.is_empty() {
+            return None;
+        }
+        Some(format!("{dir}/{stem}.{suffix}.{ext}"))
+    })
+}
+
+/// Checks if a URI path maps to an existing file.
+fn is_file(base_path: &Path, uri_path: &str) -> bool {
+    sanitize_path(base_path, uri_path)
+        .ok()
+        .is_some_and(|path| matches!(try_metadata(&path), Ok((_, false))))
+}
+
+/// Checks if a URI path maps to an existing file or directory index file.
+fn is_servable(base_path: &Path, uri_path: &str, index_files: &[&str]) -> bool {
+    if uri_path.ends_with('/') {
+        index_files
+            .iter()
+            .any(|index| is_file(base_path, &[uri_path, index].concat()))
+    } else {
+        is_file(base_path, uri_path)
+    }
+}
+
+/// Negotiates the language variant of the requested file if there are any.
+pub(crate) fn pre_process<T>(
+    opts: &RequestHandlerOpts,
+    req: &Request<T>,
+    base_path: &Path,
+    index_files: &[&str],
+) -> Option<Negotiated> {
+    if opts.language_variants.is_empty() || !matches!(*req.method(), Method::GET | Method::HEAD) {
+        return None;
+    }
+
+    let uri_path = req.uri().path();
+    let available: Vec<(&LanguageVariant, String)> = opts
+        .language_variants
+        .iter()
+        .filter_map(|variant| {
+            variant_paths(uri_path, &variant.suffix, index_files)
+                .find(|path| is_file(base_path, path))
+                .map(|path| (variant, path))
+        })
+        .collect();
+    if available.is_empty() {
+        return None;
+    }
+
+    let preferred = req
+        .headers()
+        .typed_get::<AcceptLanguage>()
+        .and_then(|accept| accept.preferred(available.iter().map(|(v, _)| v.tag.as_str())));
+    let selected = match preferred {
+        Some(tag) => available.iter().find(|(v, _)| v.tag == tag),
+        // Serve the default language variant if available, otherwise the requested file
+        // and the first available variant as a last resort
+        None => available
+            .first()
+            .filter(|(v, _)| *v == &opts.language_variants[0])
+            .or_else(|| (!is_servable(base_path, uri_path, index_files)).then(|| &available[0])),
+    };
+
+    tracing::debug!("language variants: selected {:?}", selected);
+    Some(match selected {
+        Some((variant, path)) => Negotiated {
+            uri_path: Some(path.clone()),
+            language: Some(variant.tag.clone()),
+        },
+        None => Negotiated {
+            uri_path: None,
+            language: None,
+        },
+    })
+}
+
+/// Appends the `Content-Language` and `Vary` headers to the response of a negotiated request.
+pub(crate) fn post_process(
+    negotiated: Option<&Negotiated>,
+    mut resp: Response<Body>,
+) -> Result<Response<Body>, Error> {
+    let Some(negotiated) = negotiated else {
+        return Ok(resp);
+    };
+
+    if let Some(language) = &negotiated.language {
+        if resp.status().is_success() {
+            if let Ok(value) = HeaderValue::from_str(language) {
+                resp.headers_mut().insert(CONTENT_LANGUAGE, value);
+            }
+        }
+    }
+
+    // The response varies depending on the accepted languages
+    let value = resp
+        .headers()
+        .get(VARY)
+        .map_or(HeaderValue::from_name(ACCEPT_LANGUAGE), |h| {
+            let mut s = h.to_str().unwrap_or_default().to_owned();
+            s.push(',');
+            s.push_str(ACCEPT_LANGUAGE.as_str());
+            HeaderValue::from_str(s.as_str()).unwrap()
+        });
+    resp.headers_mut().insert(VARY, value);
+    Ok(resp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LanguageVariant, parse, variant_paths};
+
+    #[test]
+    fn test_parse() {
+        assert!(parse("").unwrap().is_empty());
+        assert_eq!(
+            parse(" en, pt-BR=pt ").unwrap(),
+            [
+                LanguageVariant {
+                    tag: "en".into(),
+                    suffix: "en".into()
+                },
+                LanguageVariant {
+                    tag: "pt-BR".into(),
+                    suffix: "pt".into()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse("en,de=").is_err());
+        assert!(parse("en,=de").is_err());
+        assert!(parse("en,de=../de").is_err());
+        assert!(parse("en,de,DE").is_err());
+    }
+
+    #[test]
+    fn test_variant_paths() {
+        let paths = |uri, index_files| variant_paths(uri, "de", index_files).collect::<Vec<_>>();
+        assert_eq!(paths("/docs/page.html", &[]), ["/docs/page.de.html"]);
+        assert_eq!(paths("/app.min.js", &[]), ["/app.min.de.js"]);
+        assert_eq!(
+            paths("/docs/", &["index.html", "index.htm"]),
+            ["/docs/index.de.html", "/docs/index.de.htm"]
+        );
+        assert!(paths("/docs/page", &[]).is_empty());
+        assert!(paths("/.hidden", &[]).is_empty());
+    }
+}
@@ -61,6 +61,7 @@
 //! - Run the server as a [Windows Service](https://docs.microsoft.com/en-us/previous-versions/windows/it-pro/windows-server-2003/cc783643(v=ws.10)).
 //! - Configurable using CLI arguments, environment variables or a TOML file.
 //! - Default and custom error pages.
+//! - Basic server-side includes (SSI) support.
 //! - Custom URL rewrites and redirects via glob patterns.
 //! - Support for serving pre-compressed (Gzip/Brotli) files.
 //! - First-class [Docker](https://docs.docker.com/get-started/overview/) support. [Scratch](https://hub.docker.com/_/scratch) and latest [Alpine Linux](https://hub.docker.com/_/alpine) Docker images.
@@ -92,10 +93,16 @@
 //! `basic-auth` | Activates the Basic HTTP Authorization Schema feature.
 //! [**Fallback Page**](./features/error-pages.md#fallback-page-for-use-with-client-routers) |
 //! `fallback-page` | Activates the Fallback Page feature.
+//! [**Markdown Rendering**](https://static-web-server.net/features/markdown-rendering/) |
+//! `markdown` | Activates the Markdown files rendering feature.
+//! [**Time Restrictions**](https://static-web-server.net/features/time-restrictions/) |
+//! `time-restrictions` | Activates the time-based access restrictions feature.
+//! [**Cache Invalidation**](https://static-web-server.net/features/cache-invalidation/) |
+//! `cache-invalidation` | Activates the cache invalidation on file changes feature.
 //!
 
 #![deny(missing_docs)]
-#![forbid(unsafe_code)]
+#![deny(unsafe_code)]
 #![deny(warnings)]
 #![deny(rust_2018_idioms)]
 #![deny(dead_code)]
@@ -110,9 +117,21 @@ extern crate serde;
 // Public modules
 #[macro_use]
 pub mod logger;
+pub mod access_log;
+pub(crate) mod alt_svc;
+#[cfg(feature = "archive-preview")]
+pub(crate) mod archive_preview;
+pub(crate) mod audit_log;
+pub mod bandwidth;
 #[cfg(feature = "basic-auth")]
 #[cfg_attr(docsrs, doc(cfg(feature = "basic-auth")))]
 pub mod basic_auth;
+pub(crate) mod cache_index;
+#[cfg(feature = "cache-invalidation")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cache-invalidation")))]
+pub mod cache_invalidation;
+pub mod check;
+pub(crate) mod chunk_cache;
 #[cfg(any(
     feature = "compression",
     feature = "compression-gzip",
@@ -138,6 +157,16 @@ pub mod compression;
     feature = "compression-zstd",
     feature = "compression-deflate"
 ))]
+pub(crate) mod compression_adaptive;
+#[cfg(any(feature = "compression", feature = "compression-zstd"))]
+pub(crate) mod compression_pool;
+#[cfg(any(
+    feature = "compression",
+    feature = "compression-gzip",
+    feature = "compression-brotli",
+    feature = "compression-zstd",
+    feature = "compression-deflate"
+))]
 #[cfg_attr(
     docsrs,
     doc(cfg(any(
@@ -150,52 +179,105 @@ pub mod compression;
 )]
 pub mod compression_static;
 pub(crate) mod conditional_headers;
+pub mod connection_limits;
+pub mod content_type_options;
+pub(crate) mod control;
 pub mod control_headers;
 pub mod cors;
 pub mod custom_headers;
+pub mod deadline;
+pub mod dev_reload;
 #[cfg(feature = "directory-listing")]
 #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
 pub mod directory_listing;
 #[cfg(feature = "directory-listing-download")]
 #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing-download")))]
 pub mod directory_listing_download;
+#[cfg(feature = "directory-listing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+pub mod directory_listing_locale;
+#[cfg(feature = "directory-listing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+pub mod directory_listing_readme;
+#[cfg(feature = "directory-listing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+pub mod directory_listing_size;
+#[cfg(feature = "directory-listing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+pub mod directory_listing_sort;
+pub mod embedded;
 pub mod error_page;
 #[cfg(feature = "fallback-page")]
 #[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
 pub mod fallback_page;
-pub(crate) mod fs;
+pub mod fs;
 pub mod handler;
 pub(crate) mod headers_ext;
 pub(crate) mod health;
 #[cfg(feature = "http2")]
 #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
 pub mod https_redirect;
+pub mod ignore_patterns;
+pub mod image_variants;
+pub(crate) mod interface;
+pub mod ip_allowlist;
+pub mod language_variants;
+pub(crate) mod listeners;
 pub(crate) mod log_addr;
 pub mod maintenance_mode;
 pub(crate) mod markdown;
+#[cfg(feature = "markdown")]
+pub(crate) mod markdown_render;
+pub(crate) mod media_streaming;
 #[cfg(feature = "experimental")]
 pub(crate) mod mem_cache;
-#[cfg(all(unix, feature = "experimental"))]
+#[cfg(feature = "metrics")]
 pub(crate) mod metrics;
+pub(crate) mod mime_types;
+pub mod not_found_cache;
+pub(crate) mod probe;
+pub(crate) mod proxy_protocol;
 pub mod redirects;
+pub(crate) mod reload;
+pub(crate) mod request_limits;
+pub(crate) mod request_target;
 pub(crate) mod response;
 pub mod rewrites;
 pub mod security_headers;
+pub mod selftest;
 pub mod server;
+pub(crate) mod server_ident;
 pub mod service;
 pub mod settings;
 #[cfg(any(unix, windows))]
 #[cfg_attr(docsrs, doc(cfg(any(unix, windows))))]
 pub mod signals;
+#[cfg(feature = "subresource-integrity")]
+#[cfg_attr(docsrs, doc(cfg(feature = "subresource-integrity")))]
+pub mod sri;
+pub(crate) mod ssi;
+pub mod startup_summary;
 pub mod static_files;
+pub mod status_rewrites;
+#[cfg(unix)]
+pub(crate) mod systemd;
+#[cfg(feature = "time-restrictions")]
+pub(crate) mod time_restrictions;
 #[cfg(feature = "http2")]
 #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
 pub mod tls;
 pub mod transport;
+pub(crate) mod try_files;
+pub mod type_map;
+#[cfg(unix)]
+pub(crate) mod unix_socket;
 pub(crate) mod virtual_hosts;
+pub mod websocket_proxy;
 #[cfg(windows)]
 #[cfg_attr(docsrs, doc(cfg(windows)))]
 pub mod winservice;
+pub mod workers;
+pub mod zero_copy;
 #[macro_use]
 pub mod error;
 
@@ -206,6 +288,7 @@ mod helpers;
 pub mod http_ext;
 #[doc(hidden)]
 pub mod testing;
+pub(crate) mod timeouts;
 
 // Re-exports
 pub use error::*;
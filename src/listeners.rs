@@ -0,0 +1,324 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module to serve the configured listeners, each one with its own TCP or Unix domain socket address,
+//! HTTP versions, TLS, PROXY protocol and virtual hosts options.
+//!
+
+use hyper::server::{Server as HyperServer, accept::Accept, conn::AddrIncoming};
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+
+use crate::connection_limits::{ConnectionLimitAcceptor, ConnectionLimits};
+use crate::proxy_protocol::ProxyProtocolAcceptor;
+use crate::service::RouterService;
+use crate::settings::{Listener, ListenerAddress};
+use crate::startup_summary::StartupSummary;
+use crate::timeouts::{TimeoutAcceptor, Timeouts};
+use crate::transport::Transport;
+use crate::workers::bind_tcp;
+use crate::zero_copy::{self, ZeroCopyAcceptor};
+use crate::{Context, Result};
+
+#[cfg(feature = "http2")]
+use crate::tls::{TlsAcceptor, TlsConfigBuilder};
+
+#[cfg(unix)]
+use crate::{systemd, unix_socket};
+
+/// Binds the given listeners and serves them until the `signal` future completes,
+/// which makes all of them shut down gracefully.
+/// The startup summary is printed once all the listeners are bound.
+pub(crate) async fn serve<F>(
+    listeners: &[Listener],
+    router_service: &RouterService,
+    timeouts: Timeouts,
+    limits: &ConnectionLimits,
+    signal: F,
+    mut startup_summary: StartupSummary,
+) -> Result
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let mut servers = JoinSet::new();
+    // Socket files removed once all the servers are done
+    #[cfg(unix)]
+    let mut _socket_files = Vec::new();
+
+    for listener in listeners {
+        let router = router_service.for_listener(listener);
+        let mut shutdown_rx = shutdown_rx.clone();
+        let shutdown = async move {
+            shutdown_rx.wait_for(|shutdown| *shutdown).await.ok();
+        };
+
+        match &listener.address {
+            ListenerAddress::Tcp(addr) => {
+                let incoming = bind(*addr)?;
+                startup_summary.tcp_listener(
+                    incoming.local_addr(),
+                    listener.tls.is_some(),
+                    listener.http1,
+                    listener.http2,
+                );
+                spawn(
+                    &mut servers,
+                    ZeroCopyAcceptor::new(incoming),
+                    listener,
+                    timeouts,
+                    limits.clone(),
+                    router,
+                    shutdown,
+                )?;
+            }
+            #[cfg(unix)]
+            ListenerAddress::Unix(path) => {
+                let (unix_listener, socket_file) = unix_socket::bind(path)?;
+                _socket_files.push(socket_file);
+                let owner = listener.socket_owner;
+                unix_socket::set_permissions(
+                    path,
+                    listener.socket_mode,
+                    owner.and_then(|owner| owner.uid),
+                    owner.and_then(|owner| owner.gid),
+                )?;
+                startup_summary.listener(
+                    listener.address.to_string(),
+                    listener.tls.is_some(),
+                    listener.http1,
+                    listener.http2,
+                );
+                warn_zero_copy(listener);
+                let incoming = unix_socket::incoming(unix_listener);
+                spawn(
+                    &mut servers,
+                    incoming,
+                    listener,
+                    timeouts,
+                    limits.clone(),
+                    router,
+                    shutdown,
+                )?;
+            }
+            #[cfg(unix)]
+            ListenerAddress::Systemd(name) => match systemd::take_listener(name)? {
+                systemd::InheritedListener::Tcp(tcp_listener) => {
+                    let incoming = incoming(tcp_listener)?;
+                    startup_summary.tcp_listener(
+                        incoming.local_addr(),
+                        listener.tls.is_some(),
+                        listener.http1,
+                        listener.http2,
+                    );
+                    spawn(
+                        &mut servers,
+                        ZeroCopyAcceptor::new(incoming),
+                        listener,
+                        timeouts,
+                        limits.clone(),
+                        router,
+                        shutdown,
+                    )?;
+                }
+                systemd::InheritedListener::Unix(unix_listener) => {
+                    // NOTE: the socket file is owned by systemd, so it's left in place on shutdown
+                    let unix_listener = unix_socket::from_std(unix_listener)?;
+                    warn_zero_copy(listener);
+                    startup_summary.listener(
+                        listener.address.to_string(),
+                        listener.tls.is_some(),
+                        listener.http1,
+                        listener.http2,
+                    );
+                    spawn(
+                        &mut servers,
+                        unix_socket::incoming(unix_listener),
+                        listener,
+                        timeouts,
+                        limits.clone(),
+                        router,
+                        shutdown,
+                    )?;
+                }
+            },
+        }
+
+        tracing::info!(
+            "listener {} is listening: http1={}, http2={}, tls={}, proxy-protocol={}, vhosts={}",
+            listener.address,
+            listener.http1,
+            listener.http2,
+            listener.tls.is_some(),
+            listener.proxy_protocol,
+            listener
+                .vhosts
+                .as_ref()
+                .map_or_else(|| "all".to_owned(), |vhosts| vhosts.join(",")),
+        );
+        if let Some(redirect) = &listener.https_redirect {
+            tracing::info!(
+                "listener {} redirects all requests to https://{}:{}: status={}, preserve-host={}",
+                listener.address,
+                redirect.host,
+                redirect.port,
+                redirect.status.as_u16(),
+                redirect.preserve_host
+            );
+        }
+    }
+
+    startup_summary.print();
+    #[cfg(unix)]
+    systemd::ready();
+
+    tokio::spawn(async move {
+        signal.await;
+        shutdown_tx.send(true).ok();
+    });
+
+    while let Some(result) = servers.join_next().await {
+        result
+            .with_context(|| "listener server task failed")?
+            .with_context(|| "listener server failed")?;
+    }
+    Ok(())
+}
+
+/// Spawns the server of a listener with its TLS, PROXY protocol and connection limits options.
+fn spawn<I, F>(
+    servers: &mut JoinSet<hyper::Result<()>>,
+    incoming: I,
+    listener: &Listener,
+    timeouts: Timeouts,
+    limits: ConnectionLimits,
+    router: RouterService,
+    shutdown: F,
+) -> Result
+where
+    I: Accept<Error = io::Error> + Unpin + Send + 'static,
+    I::Conn: Transport + Unpin + Send + 'static,
+    F: Future<Output = ()> + Send + 'static,
+{
+    #[cfg(feature = "http2")]
+    if let Some(tls) = &listener.tls {
+        let mut config = TlsConfigBuilder::new()
+            .cert_path(&tls.cert)
+            .key_path(&tls.key)
+            .build()
+            .with_context(|| {
+                format!(
+                    "failed to initialize TLS of listener {} probably because invalid cert or key file",
+                    listener.address
+                )
+            })?;
+        // Only advertise the HTTP versions accepted by the listener
+        config.alpn_protocols.clear();
+        if listener.http2 {
+            config.alpn_protocols.push("h2".into());
+        }
+        if listener.http1 {
+            config.alpn_protocols.push("http/1.1".into());
+        }
+
+        // The connections are limited by their client address, so after the PROXY protocol header
+        if listener.proxy_protocol {
+            let incoming =
+                ConnectionLimitAcceptor::new(ProxyProtocolAcceptor::new(incoming), limits, false);
+            let incoming = TlsAcceptor::new(config, incoming);
+            servers.spawn(serve_incoming(
+                incoming, listener, timeouts, router, shutdown,
+            ));
+        } else {
+            let incoming = TlsAcceptor::new(
+                config,
+                ConnectionLimitAcceptor::new(incoming, limits, false),
+            );
+            servers.spawn(serve_incoming(
+                incoming, listener, timeouts, router, shutdown,
+            ));
+        }
+        return Ok(());
+    }
+
+    // Only the HTTP/1 listeners can answer the rejected connections
+    let respond = listener.http1;
+    if listener.proxy_protocol {
+        let incoming = ProxyProtocolAcceptor::new(incoming);
+        let incoming = ConnectionLimitAcceptor::new(incoming, limits, respond);
+        servers.spawn(serve_incoming(
+            incoming, listener, timeouts, router, shutdown,
+        ));
+    } else {
+        let incoming = ConnectionLimitAcceptor::new(incoming, limits, respond);
+        servers.spawn(serve_incoming(
+            incoming, listener, timeouts, router, shutdown,
+        ));
+    }
+    Ok(())
+}
+
+/// Warns that a Unix domain socket listener doesn't send files with zero-copy.
+#[cfg(unix)]
+fn warn_zero_copy(listener: &Listener) {
+    if zero_copy::is_enabled() {
+        tracing::warn!(
+            "listener {} doesn't send files with zero-copy since it's not a TCP socket",
+            listener.address
+        );
+    }
+}
+
+/// Binds the TCP socket of a listener.
+fn bind(addr: SocketAddr) -> Result<AddrIncoming> {
+    let tcp_listener =
+        bind_tcp(addr).with_context(|| format!("failed to bind to {addr} address"))?;
+    incoming(tcp_listener)
+}
+
+/// Accepts the connections of a bound TCP listener.
+fn incoming(tcp_listener: std::net::TcpListener) -> Result<AddrIncoming> {
+    tcp_listener
+        .set_nonblocking(true)
+        .with_context(|| "failed to set TCP non-blocking mode")?;
+    let tcp_listener = tokio::net::TcpListener::from_std(tcp_listener)
+        .with_context(|| "failed to create tokio::net::TcpListener")?;
+    let mut incoming = AddrIncoming::from_listener(tcp_listener).with_context(
+        || "failed to create an AddrIncoming from the current tokio::net::TcpListener",
+    )?;
+    incoming.set_nodelay(true);
+    Ok(incoming)
+}
+
+/// Serves the connections of a listener with its HTTP versions.
+fn serve_incoming<I, F>(
+    incoming: I,
+    listener: &Listener,
+    timeouts: Timeouts,
+    router: RouterService,
+    shutdown: F,
+) -> impl Future<Output = hyper::Result<()>> + Send + 'static
+where
+    I: Accept<Error = io::Error> + Unpin + Send + 'static,
+    I::Conn: Transport + Unpin + Send + 'static,
+    F: Future<Output = ()> + Send + 'static,
+{
+    let mut builder = timeouts.apply(HyperServer::builder(TimeoutAcceptor::new(
+        incoming, timeouts,
+    )));
+    if !listener.http2 {
+        builder = builder.http1_only(true);
+    } else if !listener.http1 {
+        builder = builder.http2_only(true);
+    }
+    // The body chunks must be written as they are for the zero-copy ones to be recognized
+    if zero_copy::is_enabled() {
+        builder = builder.http1_writev(true);
+    }
+    builder.serve(router).with_graceful_shutdown(shutdown)
+}
@@ -9,7 +9,7 @@
 use hyper::Request;
 use std::net::{IpAddr, SocketAddr};
 
-use crate::{handler::RequestHandlerOpts, health};
+use crate::{dev_reload, handler::RequestHandlerOpts, health, probe};
 
 /// Initializes the log address module.
 pub(crate) fn init(enabled: bool, handler_opts: &mut RequestHandlerOpts) {
@@ -17,7 +17,7 @@ pub(crate) fn init(enabled: bool, handler_opts: &mut RequestHandlerOpts) {
     let trusted = if handler_opts.trusted_proxies.is_empty() {
         "all".to_owned()
     } else {
-        format!("{:?}", handler_opts.trusted_proxies)
+        handler_opts.trusted_proxies.to_string()
     };
 
     tracing::info!("log requests with remote IP addresses: enabled={enabled}");
@@ -73,8 +73,11 @@ pub(crate) fn pre_process<T>(
         }
     }
 
-    // Log incoming requests in debug mode only if the health option is enabled
-    if opts.health && health::is_health_endpoint(req) {
+    // Log incoming requests in debug mode only if the health, probe or live-reload endpoints are enabled
+    if (opts.health && health::is_health_endpoint(req))
+        || probe::is_probe_endpoint(opts, req)
+        || dev_reload::is_dev_reload_endpoint(opts, req)
+    {
         tracing::debug!(
             "incoming request: method={} uri={}{remote_addrs}",
             req.method(),
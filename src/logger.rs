@@ -6,28 +6,54 @@
 //! Provides logging initialization for the web server.
 //!
 
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Mutex;
 use tracing::Level;
-use tracing_subscriber::{filter::Targets, fmt::format::FmtSpan, prelude::*};
+use tracing_subscriber::{
+    filter::Targets,
+    fmt::{format::FmtSpan, writer::BoxMakeWriter},
+    prelude::*,
+};
 
 use crate::{Context, Result};
 
 /// Logging system initialization
 pub fn init(log_level: &str, log_with_ansi: bool) -> Result {
+    init_with_file(log_level, log_with_ansi, None)
+}
+
+/// Logging system initialization appending the log output to a file if given
+/// instead of writing it to the standard error.
+pub fn init_with_file(log_level: &str, log_with_ansi: bool, log_file: Option<&Path>) -> Result {
     let log_level = log_level.to_lowercase();
 
-    configure(&log_level, log_with_ansi).with_context(|| "failed to initialize logging")?;
+    configure(&log_level, log_with_ansi, log_file)
+        .with_context(|| "failed to initialize logging")?;
 
     Ok(())
 }
 
 /// Initialize logging builder with its levels.
-fn configure(level: &str, enable_ansi: bool) -> Result {
+fn configure(level: &str, enable_ansi: bool, log_file: Option<&Path>) -> Result {
     let level = level
         .parse::<Level>()
         .with_context(|| "failed to parse log level")?;
 
+    let writer = match log_file.filter(|path| !path.as_os_str().is_empty()) {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("unable to open the log file {}", path.display()))?;
+            BoxMakeWriter::new(Mutex::new(file))
+        }
+        None => BoxMakeWriter::new(std::io::stderr),
+    };
+
     let filtered_layer = tracing_subscriber::fmt::layer()
-        .with_writer(std::io::stderr)
+        .with_writer(writer)
         .with_span_events(FmtSpan::CLOSE)
         .with_ansi(enable_ansi)
         .with_filter(Targets::default().with_default(level));
@@ -5,13 +5,22 @@
 
 //! Provides maintenance mode functionality.
 //!
+//! The maintenance mode can be limited to a schedule and bypassed by some client IPs
+//! (E.g. the administrators) or request paths (E.g. the health checks).
+//!
 
+use globset::{GlobBuilder, GlobSetBuilder};
 use headers::{AcceptRanges, ContentLength, ContentType, HeaderMapExt};
 use hyper::{Body, Method, Request, Response, StatusCode};
 use mime_guess::mime;
+use std::net::{IpAddr, SocketAddr};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::{Error, Result, handler::RequestHandlerOpts, helpers, http_ext::MethodExt};
+use crate::{
+    Context, Error, Result, handler::RequestHandlerOpts, helpers, http_ext::MethodExt,
+    ip_allowlist::IpAllowlist,
+};
 
 const DEFAULT_BODY_CONTENT: &str = "The server is in maintenance mode.";
 
@@ -39,20 +48,203 @@ pub(crate) fn init(
     );
 }
 
+/// Initializes the client IPs and request paths bypassing the maintenance mode.
+pub(crate) fn init_bypass(
+    allow_ips: &[String],
+    allow_paths: &[String],
+    handler_opts: &mut RequestHandlerOpts,
+) -> Result {
+    let ips = IpAllowlist::new(allow_ips, 0).context("invalid maintenance mode allowed IPs")?;
+
+    let mut paths = GlobSetBuilder::new();
+    for pattern in allow_paths
+        .iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+    {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .with_context(|| format!("invalid maintenance mode allowed path: {pattern}"))?;
+        paths.add(glob);
+    }
+    let paths = paths
+        .build()
+        .context("unable to build the maintenance mode allowed paths")?;
+
+    tracing::info!("maintenance mode allowed ips: {ips}");
+    tracing::info!("maintenance mode allowed paths: {allow_paths:?}");
+    handler_opts.maintenance_mode_allow_ips = ips;
+    handler_opts.maintenance_mode_allow_paths = paths;
+    Ok(())
+}
+
+/// Initializes the schedule of the maintenance mode from optional RFC 3339 timestamps.
+pub(crate) fn init_schedule(
+    start: &str,
+    end: &str,
+    handler_opts: &mut RequestHandlerOpts,
+) -> Result {
+    let parse = |value: &str| {
+        let value = value.trim();
+        (!value.is_empty())
+            .then(|| parse_timestamp(value))
+            .transpose()
+    };
+    let start_time = parse(start).context("invalid maintenance mode start")?;
+    let end_time = parse(end).context("invalid maintenance mode end")?;
+    if let (Some(start_time), Some(end_time)) = (start_time, end_time) {
+        if end_time <= start_time {
+            bail!("the maintenance mode end \"{end}\" is not after its start \"{start}\"");
+        }
+    }
+
+    if start_time.is_some() || end_time.is_some() {
+        tracing::info!("maintenance mode schedule: start=\"{start}\", end=\"{end}\"");
+    }
+    handler_opts.maintenance_mode_start = start_time;
+    handler_opts.maintenance_mode_end = end_time;
+    Ok(())
+}
+
 /// Produces maintenance mode response if necessary
 pub(crate) fn pre_process<T>(
     opts: &RequestHandlerOpts,
     req: &Request<T>,
+    remote_addr: Option<SocketAddr>,
 ) -> Option<Result<Response<Body>, Error>> {
-    if opts.maintenance_mode {
-        Some(get_response(
-            req.method(),
-            &opts.maintenance_mode_status,
-            &opts.maintenance_mode_file,
-        ))
-    } else {
-        None
+    if !is_active(opts, SystemTime::now()) {
+        return None;
     }
+
+    if opts.maintenance_mode_allow_paths.is_match(req.uri().path()) {
+        tracing::debug!("maintenance mode bypassed for path {}", req.uri().path());
+        return None;
+    }
+    if let Some(ip) = client_ip(opts, req, remote_addr) {
+        if opts.maintenance_mode_allow_ips.contains(&ip) {
+            tracing::debug!("maintenance mode bypassed for client ip {ip}");
+            return None;
+        }
+    }
+
+    Some(get_response(
+        req.method(),
+        &opts.maintenance_mode_status,
+        &opts.maintenance_mode_file,
+    ))
+}
+
+/// Checks if the maintenance mode is enabled and within its schedule at the given time.
+fn is_active(opts: &RequestHandlerOpts, now: SystemTime) -> bool {
+    opts.maintenance_mode
+        && opts.maintenance_mode_start.is_none_or(|start| now >= start)
+        && opts.maintenance_mode_end.is_none_or(|end| now < end)
+}
+
+/// Gets the IP of the client, which is the last entry of the `X-Forwarded-For` header
+/// only when the request comes from one of the (explicitly) trusted proxies.
+fn client_ip<T>(
+    opts: &RequestHandlerOpts,
+    req: &Request<T>,
+    remote_addr: Option<SocketAddr>,
+) -> Option<IpAddr> {
+    let peer_ip = remote_addr?.ip();
+    if opts.trusted_proxies.is_empty() || !opts.trusted_proxies.contains(&peer_ip) {
+        return Some(peer_ip);
+    }
+    // The last entry is the one appended by the trusted proxy, so it can't be spoofed
+    let forwarded_for = req
+        .headers()
+        .get_all("X-Forwarded-For")
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|s| s.split(','))
+        .last()
+        .and_then(|s| s.trim().parse::<IpAddr>().ok());
+    forwarded_for.or(Some(peer_ip))
+}
+
+/// Parses an RFC 3339 timestamp like `2025-06-01T22:00:00Z` or `2025-06-01T22:00:00+02:00`.
+fn parse_timestamp(value: &str) -> Result<SystemTime> {
+    let invalid = || anyhow!("invalid RFC 3339 timestamp: \"{value}\"");
+    let num = |start: usize, len: usize| {
+        value
+            .get(start..start + len)
+            .filter(|s| s.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|s| s.parse::<i64>().ok())
+            .ok_or_else(invalid)
+    };
+
+    let separators = [(4, b'-'), (7, b'-'), (13, b':'), (16, b':')];
+    if !separators
+        .iter()
+        .all(|(i, sep)| value.as_bytes().get(*i) == Some(sep))
+        || !matches!(value.as_bytes().get(10), Some(b'T' | b't' | b' '))
+    {
+        return Err(invalid());
+    }
+    let (year, month, day) = (num(0, 4)?, num(5, 2)?, num(8, 2)?);
+    let (hour, minute, second) = (num(11, 2)?, num(14, 2)?, num(17, 2)?);
+    if !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return Err(invalid());
+    }
+
+    // The fraction of seconds is ignored
+    let mut rest = &value[19..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        rest = fraction.trim_start_matches(|c: char| c.is_ascii_digit());
+    }
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.as_bytes().first() {
+                Some(b'+') => 1,
+                Some(b'-') => -1,
+                _ => return Err(invalid()),
+            };
+            if rest.len() != 6 || rest.as_bytes()[3] != b':' {
+                return Err(invalid());
+            }
+            let offset = value.len() - 6;
+            let (hours, minutes) = (num(offset + 1, 2)?, num(offset + 4, 2)?);
+            if hours > 23 || minutes > 59 {
+                return Err(invalid());
+            }
+            sign * (hours * 3600 + minutes * 60)
+        }
+    };
+
+    // A leap second is taken as the last second of its minute
+    let seconds =
+        days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second.min(59)
+            - offset;
+    let seconds = u64::try_from(seconds).map_err(|_| invalid())?;
+    Ok(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Number of days since the Unix epoch of a date of the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
 }
 
 /// Get the a server maintenance mode response.
@@ -96,9 +288,11 @@ pub fn get_response(
 
 #[cfg(test)]
 mod tests {
-    use super::pre_process;
-    use crate::{Error, handler::RequestHandlerOpts};
+    use super::{init_bypass, init_schedule, is_active, parse_timestamp, pre_process};
+    use crate::{Error, handler::RequestHandlerOpts, ip_allowlist::IpAllowlist};
     use hyper::{Body, Request, Response, StatusCode};
+    use std::net::SocketAddr;
+    use std::time::{Duration, UNIX_EPOCH};
 
     fn make_request() -> Request<Body> {
         Request::builder()
@@ -124,7 +318,8 @@ mod tests {
                     maintenance_mode: false,
                     ..Default::default()
                 },
-                &make_request()
+                &make_request(),
+                None
             )
             .is_none()
         );
@@ -138,7 +333,8 @@ mod tests {
                     maintenance_mode: true,
                     ..Default::default()
                 },
-                &make_request()
+                &make_request(),
+                None
             )),
             Some(StatusCode::SERVICE_UNAVAILABLE)
         );
@@ -153,9 +349,125 @@ mod tests {
                     maintenance_mode_status: StatusCode::IM_A_TEAPOT,
                     ..Default::default()
                 },
-                &make_request()
+                &make_request(),
+                None
             )),
             Some(StatusCode::IM_A_TEAPOT)
         );
     }
+
+    #[test]
+    fn test_maintenance_bypass() {
+        let mut opts = RequestHandlerOpts {
+            maintenance_mode: true,
+            trusted_proxies: IpAllowlist::new(&["10.0.0.1".to_owned()], 0).unwrap(),
+            ..Default::default()
+        };
+        init_bypass(
+            &["192.168.1.0/24".to_owned(), "::1".to_owned()],
+            &["/health".to_owned(), "/admin/**".to_owned()],
+            &mut opts,
+        )
+        .unwrap();
+
+        let request = |path: &str, forwarded_for: Option<&str>| {
+            let mut req = Request::builder().uri(path);
+            if let Some(value) = forwarded_for {
+                req = req.header("X-Forwarded-For", value);
+            }
+            req.body(Body::empty()).unwrap()
+        };
+        let blocked = |req: Request<Body>, addr: &str| {
+            let addr: SocketAddr = addr.parse().unwrap();
+            pre_process(&opts, &req, Some(addr)).is_some()
+        };
+
+        assert!(!blocked(request("/health", None), "203.0.113.1:1000"));
+        assert!(!blocked(
+            request("/admin/users/1", None),
+            "203.0.113.1:1000"
+        ));
+        assert!(blocked(request("/admin", None), "203.0.113.1:1000"));
+        assert!(blocked(request("/healthz", None), "203.0.113.1:1000"));
+        assert!(blocked(request("/", None), "203.0.113.1:1000"));
+        assert!(!blocked(request("/", None), "192.168.1.20:1000"));
+        assert!(!blocked(request("/", None), "[::1]:1000"));
+        // The `X-Forwarded-For` header is only honored from the trusted proxies
+        assert!(blocked(
+            request("/", Some("192.168.1.20")),
+            "203.0.113.1:1000"
+        ));
+        assert!(!blocked(
+            request("/", Some("192.168.1.20")),
+            "10.0.0.1:1000"
+        ));
+        assert!(blocked(
+            request("/", Some("192.168.1.20, 203.0.113.1")),
+            "10.0.0.1:1000"
+        ));
+        assert!(pre_process(&opts, &request("/", None), None).is_some());
+
+        assert!(init_bypass(&["192.168.1.0/40".to_owned()], &[], &mut opts).is_err());
+        assert!(init_bypass(&[], &["/admin/[".to_owned()], &mut opts).is_err());
+    }
+
+    #[test]
+    fn test_maintenance_schedule() {
+        let mut opts = RequestHandlerOpts {
+            maintenance_mode: true,
+            ..Default::default()
+        };
+        init_schedule(
+            "2025-06-01T22:00:00Z",
+            "2025-06-02T01:00:00+02:00",
+            &mut opts,
+        )
+        .unwrap();
+        let at = |secs: u64| UNIX_EPOCH + Duration::from_secs(secs);
+        let start = 1_748_815_200;
+
+        assert!(!is_active(&opts, at(start - 1)));
+        assert!(is_active(&opts, at(start)));
+        assert!(is_active(&opts, at(start + 3599)));
+        assert!(!is_active(&opts, at(start + 3600)));
+
+        // An open-ended schedule
+        init_schedule("2025-06-01T22:00:00Z", "", &mut opts).unwrap();
+        assert!(is_active(&opts, at(start + 86_400 * 365)));
+        opts.maintenance_mode = false;
+        assert!(!is_active(&opts, at(start)));
+
+        assert!(init_schedule("2025-06-02T00:00:00Z", "2025-06-01T00:00:00Z", &mut opts).is_err());
+        assert!(init_schedule("tomorrow", "", &mut opts).is_err());
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        let secs = |value: &str| {
+            parse_timestamp(value)
+                .unwrap()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        };
+        assert_eq!(secs("1970-01-01T00:00:00Z"), 0);
+        assert_eq!(secs("2025-06-01T22:00:00+02:00"), 1_748_808_000);
+        assert_eq!(secs("2025-06-01t17:30:00.250-02:30"), 1_748_808_000);
+        assert_eq!(secs("2024-02-29 23:59:59Z"), 1_709_251_199);
+
+        for value in [
+            "",
+            "2025-06-01",
+            "2025-06-01T22:00:00",
+            "2025-06-01T22:00Z",
+            "2025-13-01T00:00:00Z",
+            "2023-02-29T00:00:00Z",
+            "2025-06-01T24:00:00Z",
+            "2025-06-01T22:00:00+2:00",
+            "1969-12-31T23:59:59Z",
+            "2025-06-01T22:00:00Z ",
+        ] {
+            assert!(parse_timestamp(value).is_err(), "{value}");
+        }
+    }
 }
@@ -0,0 +1,236 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Markdown rendering module.
+//!
+//! This module renders `.md` files to HTML when a client requests them
+//! with an `Accept` header that includes `text/html`.
+
+use headers::{ContentLength, ContentType, HeaderMapExt};
+use hyper::{
+    Body, Request, Response, StatusCode,
+    header::{
+        ACCEPT, ACCEPT_RANGES, CONTENT_ENCODING, CONTENT_RANGE, ETAG, HeaderValue, LAST_MODIFIED,
+        VARY,
+    },
+};
+use maud::html;
+use mime_guess::mime;
+use pulldown_cmark::{Options, Parser};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    Error, handler::RequestHandlerOpts, headers_ext::Accept, helpers, http_ext::MethodExt,
+};
+
+/// Placeholder replaced by the document title in the wrapper template.
+const TEMPLATE_TITLE: &str = "{{title}}";
+/// Placeholder replaced by the rendered HTML in the wrapper template.
+const TEMPLATE_CONTENT: &str = "{{content}}";
+
+/// Default HTML wrapper template used when no custom template is provided.
+const DEFAULT_TEMPLATE: &str = "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><meta name=\"viewport\" content=\"width=device-width,minimum-scale=1,initial-scale=1\"><title>{{title}}</title><style>html{color-scheme:light dark;}body{font-family:sans-serif;max-width:50rem;margin:0 auto;padding:1rem;line-height:1.5;}pre{overflow-x:auto;}</style></head><body>{{content}}</body></html>";
+
+/// Initializes markdown rendering.
+pub(crate) fn init(enabled: bool, template: &Path, handler_opts: &mut RequestHandlerOpts) {
+    handler_opts.markdown_render = enabled;
+
+    let has_template = template.is_file();
+    if enabled && has_template {
+        handler_opts.markdown_render_template =
+            String::from_utf8_lossy(&helpers::read_bytes_default(template)).into_owned();
+    } else if enabled && !template.as_os_str().is_empty() {
+        tracing::warn!(
+            "markdown render template path not found or not a regular file, using the default one"
+        );
+    }
+
+    tracing::info!(
+        "markdown render: enabled={enabled}, template=\"{}\"",
+        if has_template {
+            template.display().to_string()
+        } else {
+            "default".to_owned()
+        }
+    );
+}
+
+/// Post-process the response to render a markdown file as HTML if necessary.
+pub(crate) async fn post_process<T>(
+    opts: &RequestHandlerOpts,
+    req: &Request<T>,
+    resp: Response<Body>,
+    file_path: Option<&PathBuf>,
+) -> Result<Response<Body>, Error> {
+    if !opts.markdown_render {
+        return Ok(resp);
+    }
+
+    let method = req.method();
+    // The range and conditional responses are also checked since the `Range`
+    // and validator headers of the request apply to the markdown source and not
+    // to its rendered variant, which is always sent in full
+    let status = resp.status();
+    if !(method.is_get() || method.is_head())
+        || !matches!(
+            status,
+            StatusCode::OK
+                | StatusCode::PARTIAL_CONTENT
+                | StatusCode::NOT_MODIFIED
+                | StatusCode::PRECONDITION_FAILED
+                | StatusCode::RANGE_NOT_SATISFIABLE
+        )
+    {
+        return Ok(resp);
+    }
+
+    // Skip pre-compressed variants since their content is not plain markdown
+    if resp.headers().contains_key(CONTENT_ENCODING) {
+        return Ok(resp);
+    }
+
+    let file_path = match file_path {
+        Some(p) if is_markdown_file(p) => p,
+        _ => return Ok(resp),
+    };
+
+    // The markdown representation varies so use a `Vary` header
+    let mut resp = resp;
+    let value = resp
+        .headers()
+        .get(VARY)
+        .map_or(HeaderValue::from_name(ACCEPT), |h| {
+            let mut s = h.to_str().unwrap_or_default().to_owned();
+            s.push(',');
+            s.push_str(ACCEPT.as_str());
+            HeaderValue::from_str(s.as_str()).unwrap()
+        });
+    resp.headers_mut().insert(VARY, value);
+
+    let accepts_html = req
+        .headers()
+        .typed_get::<Accept>()
+        .map(|accept| accept.accepts_html())
+        .unwrap_or(false);
+    if !accepts_html {
+        return Ok(resp);
+    }
+
+    let source = match tokio::fs::read(file_path).await {
+        Ok(v) => v,
+        Err(err) => {
+            tracing::error!(
+                "markdown render: unable to read file {}: {:?}",
+                file_path.display(),
+                err
+            );
+            return Ok(resp);
+        }
+    };
+
+    let title = file_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let content = render(
+        &String::from_utf8_lossy(&source),
+        &title,
+        &opts.markdown_render_template,
+    );
+
+    tracing::debug!("markdown render: rendered {} as html", file_path.display());
+
+    // The content is generated so validators of the source file can not be used
+    let (mut head, _) = resp.into_parts();
+    head.status = StatusCode::OK;
+    head.headers.remove(ACCEPT_RANGES);
+    head.headers.remove(CONTENT_RANGE);
+    head.headers.remove(LAST_MODIFIED);
+    head.headers.remove(ETAG);
+    head.headers
+        .typed_insert(ContentType::from(mime::TEXT_HTML_UTF_8));
+    head.headers
+        .typed_insert(ContentLength(content.len() as u64));
+
+    let body = if method.is_head() {
+        Body::empty()
+    } else {
+        Body::from(content)
+    };
+
+    Ok(Response::from_parts(head, body))
+}
+
+/// Checks whether the given file path has a markdown file extension.
+fn is_markdown_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+}
+
+/// Renders the markdown source into a HTML page using the given wrapper template.
+/// The default template is used if `template` is empty.
+fn render(source: &str, title: &str, template: &str) -> String {
+    let content = render_fragment(source);
+    let template = if template.is_empty() {
+        DEFAULT_TEMPLATE
+    } else {
+        template
+    };
+    let title = html! { (title) }.into_string();
+
+    template
+        .replace(TEMPLATE_TITLE, &title)
+        .replace(TEMPLATE_CONTENT, &content)
+}
+
+/// Renders the markdown source into a HTML fragment, without any wrapper template.
+pub(crate) fn render_fragment(source: &str) -> String {
+    let parser = Parser::new_ext(
+        source,
+        Options::ENABLE_TABLES
+            | Options::ENABLE_FOOTNOTES
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_TASKLISTS,
+    );
+    let mut content = String::with_capacity(source.len() * 3 / 2);
+    pulldown_cmark::html::push_html(&mut content, parser);
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_markdown_file, render};
+    use std::path::Path;
+
+    #[test]
+    fn test_is_markdown_file() {
+        assert!(is_markdown_file(Path::new("/docs/readme.md")));
+        assert!(is_markdown_file(Path::new("/docs/README.MD")));
+        assert!(!is_markdown_file(Path::new("/docs/readme.html")));
+        assert!(!is_markdown_file(Path::new("/docs/md")));
+    }
+
+    #[test]
+    fn test_render_default_template() {
+        let html = render("# Hello", "doc", "");
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>doc</title>"));
+        assert!(html.contains("<h1>Hello</h1>"));
+    }
+
+    #[test]
+    fn test_render_custom_template() {
+        let html = render(
+            "*hi*",
+            "<doc>",
+            "<main data-title=\"{{title}}\">{{content}}</main>",
+        );
+        assert_eq!(
+            html,
+            "<main data-title=\"&lt;doc&gt;\"><p><em>hi</em></p>\n</main>"
+        );
+    }
+}
@@ -0,0 +1,264 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module providing a preset of rules for segmented media streaming (HLS and DASH).
+//!
+//! Playlists and manifests get their content type and must be revalidated on every request,
+//! since live streams update them, while media segments are cached for a long time.
+//! Segments are only treated as such inside directories containing a playlist or a manifest,
+//! so other files sharing their extensions (E.g. TypeScript `.ts` files) are left untouched.
+//!
+
+use headers::{ContentType, HeaderMapExt, HeaderValue};
+use hyper::{Body, Request, Response, StatusCode, header::CACHE_CONTROL};
+use mime_guess::Mime;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{Error, handler::RequestHandlerOpts};
+
+/// Playlists (HLS) and manifests (DASH) along with their content types.
+const PLAYLISTS: [(&str, &str); 2] = [
+    ("m3u8", "application/vnd.apple.mpegurl"),
+    ("mpd", "application/dash+xml"),
+];
+
+/// Media segments along with their content types.
+const SEGMENTS: [(&str, &str); 8] = [
+    ("aac", "audio/aac"),
+    ("cmfa", "audio/mp4"),
+    ("cmfv", "video/mp4"),
+    ("m4a", "audio/mp4"),
+    ("m4s", "video/iso.segment"),
+    ("m4v", "video/mp4"),
+    ("ts", "video/mp2t"),
+    ("vtt", "text/vtt"),
+];
+
+const PLAYLIST_CACHE_CONTROL: &str = "no-cache";
+const SEGMENT_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// Time a directory is known to contain (or not) a playlist or a manifest.
+const MEDIA_DIR_TTL: Duration = Duration::from_secs(10);
+/// Maximum number of directories kept in the cache.
+const MAX_CACHED_DIRS: usize = 1_000;
+
+/// Directories known to contain (or not) a playlist or a manifest.
+static MEDIA_DIRS: LazyLock<Mutex<HashMap<PathBuf, (bool, Instant)>>> =
+    LazyLock::new(Default::default);
+
+/// Initializes the media streaming preset.
+pub(crate) fn init(enabled: bool, handler_opts: &mut RequestHandlerOpts) {
+    handler_opts.media_streaming = enabled;
+    tracing::info!("media streaming: enabled={enabled}");
+}
+
+/// Applies the content type and `Cache-Control` header of playlists and media segments.
+pub(crate) fn post_process<T>(
+    opts: &RequestHandlerOpts,
+    req: &Request<T>,
+    mut resp: Response<Body>,
+    file_path: Option<&PathBuf>,
+) -> Result<Response<Body>, Error> {
+    if !opts.media_streaming {
+        return Ok(resp);
+    }
+
+    let status = resp.status();
+    if !status.is_success() && status != StatusCode::NOT_MODIFIED {
+        return Ok(resp);
+    }
+
+    let Some(file_path) = file_path else {
+        return Ok(resp);
+    };
+    let Some(ext) = file_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+    else {
+        return Ok(resp);
+    };
+
+    let (content_type, cache_control) = if let Some(content_type) = find(&PLAYLISTS, &ext) {
+        (content_type, PLAYLIST_CACHE_CONTROL)
+    } else if let Some(content_type) = find(&SEGMENTS, &ext) {
+        if !is_media_dir(file_path) {
+            return Ok(resp);
+        }
+        (content_type, SEGMENT_CACHE_CONTROL)
+    } else {
+        return Ok(resp);
+    };
+
+    resp.headers_mut()
+        .insert(CACHE_CONTROL, HeaderValue::from_static(cache_control));
+    if status.is_success() && !has_custom_content_type(opts, req, &ext) {
+        if let Ok(mime) = content_type.parse::<Mime>() {
+            resp.headers_mut().typed_insert(ContentType::from(mime));
+        }
+    }
+
+    Ok(resp)
+}
+
+fn find(entries: &[(&str, &'static str)], ext: &str) -> Option<&'static str> {
+    entries
+        .iter()
+        .find(|(entry_ext, _)| *entry_ext == ext)
+        .map(|(_, content_type)| *content_type)
+}
+
+/// Whether the content type of the file is already given by the `advanced` options.
+fn has_custom_content_type<T>(opts: &RequestHandlerOpts, req: &Request<T>, ext: &str) -> bool {
+    let Some(advanced) = opts.advanced_opts.as_ref() else {
+        return false;
+    };
+    let custom_mime = advanced
+        .mime_types
+        .as_ref()
+        .is_some_and(|mime_types| mime_types.contains_key(ext));
+    let custom_content_type = advanced.content_types.as_ref().is_some_and(|entries| {
+        entries
+            .iter()
+            .any(|entry| entry.content_type.is_some() && entry.source.is_match(req.uri().path()))
+    });
+    custom_mime || custom_content_type
+}
+
+/// Whether the directory of a segment or its parent contain a playlist or a manifest.
+fn is_media_dir(file_path: &Path) -> bool {
+    let Some(dir) = file_path.parent() else {
+        return false;
+    };
+
+    let mut dirs = match MEDIA_DIRS.lock() {
+        Ok(dirs) => dirs,
+        Err(err) => {
+            tracing::error!("media directories cache lock is poisoned: {:?}", err);
+            return false;
+        }
+    };
+    if let Some((is_media, checked_at)) = dirs.get(dir) {
+        if checked_at.elapsed() < MEDIA_DIR_TTL {
+            return *is_media;
+        }
+    }
+
+    let is_media = has_playlist(dir) || dir.parent().is_some_and(has_playlist);
+    if dirs.len() >= MAX_CACHED_DIRS {
+        dirs.retain(|_, (_, checked_at)| checked_at.elapsed() < MEDIA_DIR_TTL);
+        if dirs.len() >= MAX_CACHED_DIRS {
+            dirs.clear();
+        }
+    }
+    dirs.insert(dir.to_owned(), (is_media, Instant::now()));
+    is_media
+}
+
+fn has_playlist(dir: &Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    entries.filter_map(|entry| entry.ok()).any(|entry| {
+        let path = entry.path();
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| find(&PLAYLISTS, &ext.to_lowercase()).is_some())
+            && path.is_file()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PLAYLIST_CACHE_CONTROL, SEGMENT_CACHE_CONTROL, post_process};
+    use crate::handler::RequestHandlerOpts;
+    use crate::settings::Advanced;
+    use hyper::{Body, Request, Response, StatusCode};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn make_opts(media_streaming: bool) -> RequestHandlerOpts {
+        RequestHandlerOpts {
+            media_streaming,
+            ..Default::default()
+        }
+    }
+
+    fn process(opts: &RequestHandlerOpts, path: &str, status: StatusCode) -> Response<Body> {
+        let req = Request::get(path).body(Body::empty()).unwrap();
+        let mut resp = Response::new(Body::empty());
+        *resp.status_mut() = status;
+        let file_path = PathBuf::from("tests/fixtures/media").join(path.trim_start_matches('/'));
+        post_process(opts, &req, resp, Some(&file_path)).unwrap()
+    }
+
+    fn header<'a>(resp: &'a Response<Body>, name: &str) -> Option<&'a str> {
+        resp.headers().get(name).map(|v| v.to_str().unwrap())
+    }
+
+    #[test]
+    fn test_disabled() {
+        let resp = process(&make_opts(false), "/hls/index.m3u8", StatusCode::OK);
+        assert!(header(&resp, "cache-control").is_none());
+    }
+
+    #[test]
+    fn test_playlists() {
+        let opts = make_opts(true);
+        let resp = process(&opts, "/hls/index.m3u8", StatusCode::OK);
+        assert_eq!(
+            header(&resp, "content-type"),
+            Some("application/vnd.apple.mpegurl")
+        );
+        assert_eq!(header(&resp, "cache-control"), Some(PLAYLIST_CACHE_CONTROL));
+
+        let resp = process(&opts, "/dash/manifest.mpd", StatusCode::NOT_MODIFIED);
+        assert!(header(&resp, "content-type").is_none());
+        assert_eq!(header(&resp, "cache-control"), Some(PLAYLIST_CACHE_CONTROL));
+    }
+
+    #[test]
+    fn test_segments() {
+        let opts = make_opts(true);
+        let resp = process(&opts, "/hls/segment0.ts", StatusCode::PARTIAL_CONTENT);
+        assert_eq!(header(&resp, "content-type"), Some("video/mp2t"));
+        assert_eq!(header(&resp, "cache-control"), Some(SEGMENT_CACHE_CONTROL));
+
+        // Segments in a subdirectory of the manifest
+        let resp = process(&opts, "/dash/video/chunk0.m4s", StatusCode::OK);
+        assert_eq!(header(&resp, "content-type"), Some("video/iso.segment"));
+        assert_eq!(header(&resp, "cache-control"), Some(SEGMENT_CACHE_CONTROL));
+
+        // Not a media directory
+        let resp = process(&opts, "/scripts/main.ts", StatusCode::OK);
+        assert!(header(&resp, "content-type").is_none());
+        assert!(header(&resp, "cache-control").is_none());
+
+        // Not found
+        let resp = process(&opts, "/hls/missing.ts", StatusCode::NOT_FOUND);
+        assert!(header(&resp, "cache-control").is_none());
+    }
+
+    #[test]
+    fn test_custom_mime_type() {
+        let opts = RequestHandlerOpts {
+            media_streaming: true,
+            advanced_opts: Some(Advanced {
+                mime_types: Some(HashMap::from([(
+                    "ts".to_owned(),
+                    "video/vnd.dlna.mpeg-tts".parse().unwrap(),
+                )])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let resp = process(&opts, "/hls/segment0.ts", StatusCode::OK);
+        assert!(header(&resp, "content-type").is_none());
+        assert_eq!(header(&resp, "cache-control"), Some(SEGMENT_CACHE_CONTROL));
+    }
+}
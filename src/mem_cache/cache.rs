@@ -18,25 +18,29 @@ use headers::{
 use hyper::{Body, Response, StatusCode};
 use mini_moka::sync::Cache;
 use std::io::{Read, Seek, SeekFrom};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, OnceLock};
 use std::time::Duration;
 use tokio::sync::Semaphore;
 
-use crate::Result;
 use crate::conditional_headers::{ConditionalBody, ConditionalHeaders};
-use crate::fs::stream::FileStream;
+use crate::fs::stream::{FileStream, optimal_buf_size};
 use crate::handler::RequestHandlerOpts;
 use crate::response::{BadRangeError, bytes_range};
+use crate::{Result, cache_index};
 
 /// Global cache that stores all files in memory.
 /// It provides expiration policies like Time to live (TTL) and Time to idle (TTI) support.
 pub(crate) static CACHE_STORE: OnceLock<Cache<CompactString, Arc<MemFile>>> = OnceLock::new();
 
+/// Options of the persisted cache index if enabled.
+static CACHE_INDEX: OnceLock<CacheIndexOpts> = OnceLock::new();
+
 /// A single cache permit to allow reading a file once.
 static CACHE_PERMIT: Semaphore = Semaphore::const_new(1);
 
 /// It defines the in-memory files cache options.
+#[derive(Clone)]
 pub struct MemCacheOpts {
     /// The maximum size per file in bytes.
     pub max_file_size: u64,
@@ -52,6 +56,14 @@ impl MemCacheOpts {
     }
 }
 
+/// It defines the persisted cache index options.
+struct CacheIndexOpts {
+    /// The index file path.
+    path: PathBuf,
+    /// The maximum size per file in bytes.
+    max_file_size: u64,
+}
+
 /// Make sure to initialize the in-memory cache store.
 pub(crate) fn init(handler_opts: &mut RequestHandlerOpts) -> Result {
     if let Some(advanced_opts) = handler_opts.advanced_opts.as_ref() {
@@ -68,21 +80,34 @@ pub(crate) fn init(handler_opts: &mut RequestHandlerOpts) -> Result {
             let max_file_size = opts.max_file_size.unwrap_or(8192);
 
             tracing::info!(
-                "in-memory cache (experimental): enabled=true, capacity={capacity}, ttl={ttl}, tti={tti}, max_file_size={max_file_size}"
+                "in-memory cache (experimental): enabled=true, capacity={capacity}, ttl={ttl}, tti={tti}, max_file_size={max_file_size}, index_file={:?}",
+                opts.index_file
             );
 
             let mem_opts = MemCacheOpts::new(max_file_size);
 
-            let cache = Cache::builder()
-                .max_capacity(capacity)
-                // Time to live (TTL): 30 minutes
-                .time_to_live(Duration::from_secs(ttl))
-                // Time to idle (TTI):  5 minutes
-                .time_to_idle(Duration::from_secs(tti))
-                .build();
-
-            if CACHE_STORE.set(cache).is_err() {
-                bail!("unable to initialize the in-memory cache store")
+            // NOTE: the cache store is initialized once and kept on configuration reload,
+            // so changes to its capacity, TTL, TTI or index file require a server restart
+            if CACHE_STORE.get().is_none() {
+                if let Some(path) = opts.index_file.as_deref() {
+                    let index_opts = CacheIndexOpts {
+                        path: cache_index::index_path(path),
+                        max_file_size: mem_opts.max_file_size,
+                    };
+                    CACHE_INDEX.set(index_opts).ok();
+                }
+
+                let cache = Cache::builder()
+                    .max_capacity(capacity)
+                    // Time to live (TTL): 30 minutes
+                    .time_to_live(Duration::from_secs(ttl))
+                    // Time to idle (TTI):  5 minutes
+                    .time_to_idle(Duration::from_secs(tti))
+                    .build();
+
+                if CACHE_STORE.set(cache).is_err() {
+                    bail!("unable to initialize the in-memory cache store")
+                }
             }
 
             handler_opts.memory_cache = Some(mem_opts);
@@ -96,6 +121,98 @@ pub(crate) fn init(handler_opts: &mut RequestHandlerOpts) -> Result {
     Ok(())
 }
 
+/// Persists the index of the in-memory cache if enabled.
+pub(crate) fn save_index() -> Result {
+    let (Some(store), Some(index_opts)) = (CACHE_STORE.get(), CACHE_INDEX.get()) else {
+        return Ok(());
+    };
+    let index: Vec<String> = store.iter().map(|entry| entry.key().to_string()).collect();
+    cache_index::save(&index_opts.path, &index)?;
+    tracing::info!(
+        "in-memory cache: {} file(s) persisted to the index {}",
+        index.len(),
+        index_opts.path.display()
+    );
+    Ok(())
+}
+
+/// Pre-warms the in-memory cache from its persisted index if enabled.
+pub(crate) fn warm_up() {
+    let (Some(store), Some(index_opts)) = (CACHE_STORE.get(), CACHE_INDEX.get()) else {
+        return;
+    };
+    let index = match cache_index::load::<String>(&index_opts.path) {
+        Ok(index) => index,
+        Err(err) => {
+            tracing::warn!(
+                "in-memory cache: unable to load the index, skipping the warm-up: {err:?}"
+            );
+            return;
+        }
+    };
+
+    let mut loaded = 0;
+    for file_path in index {
+        if store.contains_key::<CompactString>(&file_path.as_str().into()) {
+            continue;
+        }
+        match read_mem_file(Path::new(&file_path), index_opts.max_file_size) {
+            Some(mem_file) => {
+                store.insert(file_path.into(), Arc::new(mem_file));
+                loaded += 1;
+            }
+            None => tracing::debug!("file `{}` of the in-memory cache index skipped", file_path),
+        }
+    }
+    tracing::info!(
+        "in-memory cache: {} file(s) pre-warmed from the index {}",
+        loaded,
+        index_opts.path.display()
+    );
+}
+
+/// Removes the given files or the files under the given directories from the in-memory cache
+/// if enabled, returning whether the cache is enabled.
+#[cfg(feature = "cache-invalidation")]
+pub(crate) fn invalidate(paths: &std::collections::HashSet<PathBuf>) -> bool {
+    let Some(store) = CACHE_STORE.get() else {
+        return false;
+    };
+    let keys: Vec<CompactString> = store
+        .iter()
+        .filter(|entry| {
+            crate::cache_invalidation::is_changed(paths, Path::new(entry.key().as_str()))
+        })
+        .map(|entry| entry.key().clone())
+        .collect();
+    for key in &keys {
+        store.invalidate(key);
+    }
+    true
+}
+
+/// Reads a regular file into memory if it does not exceed the maximum size.
+fn read_mem_file(file_path: &Path, max_file_size: u64) -> Option<MemFile> {
+    let meta = std::fs::metadata(file_path).ok()?;
+    if !meta.is_file() || meta.len() > max_file_size {
+        return None;
+    }
+    let data = std::fs::read(file_path).ok()?;
+    let content_type = ContentType::from(mime_guess::from_path(file_path).first_or_octet_stream());
+    // Same as the file responses, the UNIX epoch is not a valid modification time
+    let last_modified = meta
+        .modified()
+        .ok()
+        .filter(|&t| t != std::time::UNIX_EPOCH)
+        .map(LastModified::from);
+    Some(MemFile::new(
+        Bytes::from(data),
+        optimal_buf_size(&meta),
+        content_type,
+        last_modified,
+    ))
+}
+
 /// Try to get the file in a form of a response from the cache store by a path or
 /// acquires a permit to ensure to hold until the file is read first (once).
 ///
@@ -3,26 +3,148 @@
 // See https://static-web-server.net/ for more information
 // Copyright (C) 2019-present Jose Quintana <joseluisq.net>
 
-//! Module providing the experimental metrics endpoint.
+//! Module providing the Prometheus metrics endpoint.
 //!
 
-use headers::{ContentType, HeaderMapExt};
+use futures_util::TryStreamExt;
+use headers::{ContentType, HeaderMapExt, HeaderValue};
+use hyper::body::HttpBody;
+use hyper::header::{CONTENT_TYPE, VARY};
 use hyper::{Body, Request, Response};
-use prometheus::{Encoder, TextEncoder, default_registry};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, PROTOBUF_FORMAT,
+    ProtobufEncoder, TextEncoder, default_registry,
+};
+use std::sync::LazyLock;
+use std::time::Instant;
 
-use crate::{Error, handler::RequestHandlerOpts, http_ext::MethodExt};
+use crate::{
+    Error, handler::RequestHandlerOpts, headers_ext::Accept, http_ext::MethodExt, virtual_hosts,
+};
+
+#[cfg(any(feature = "compression", feature = "compression-gzip"))]
+use crate::{compression, headers_ext::ContentCoding};
+
+/// Label value of the requests not matching any virtual host.
+const DEFAULT_VHOST: &str = "default";
+/// Label value of the requests using an HTTP method not supported by the server.
+const OTHER_METHOD: &str = "OTHER";
+/// Media type of the Prometheus protobuf exposition format.
+const PROTOBUF_MEDIA_TYPE: &str = "application/vnd.google.protobuf";
+/// Media type of the Prometheus text exposition format.
+const TEXT_MEDIA_TYPE: &str = "text/plain";
+
+/// Server metrics registered in the Prometheus default registry.
+struct Metrics {
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    response_bytes: IntCounterVec,
+    open_connections: IntGauge,
+    rejected_connections: IntCounterVec,
+    chunk_cache_lookups: IntCounterVec,
+    not_found_cache_lookups: IntCounterVec,
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(|| {
+    let metrics = Metrics {
+        requests: IntCounterVec::new(
+            Opts::new(
+                "sws_http_requests_total",
+                "Number of HTTP requests handled.",
+            ),
+            &["method", "status", "vhost"],
+        )
+        .unwrap(),
+        request_duration: HistogramVec::new(
+            HistogramOpts::new(
+                "sws_http_request_duration_seconds",
+                "Time spent handling the HTTP requests until the response headers are ready.",
+            ),
+            &["method", "vhost"],
+        )
+        .unwrap(),
+        response_bytes: IntCounterVec::new(
+            Opts::new(
+                "sws_http_response_bytes_total",
+                "Number of bytes served in HTTP response bodies.",
+            ),
+            &["vhost"],
+        )
+        .unwrap(),
+        open_connections: IntGauge::new("sws_open_connections", "Number of open connections.")
+            .unwrap(),
+        rejected_connections: IntCounterVec::new(
+            Opts::new(
+                "sws_rejected_connections_total",
+                "Number of connections rejected by the exceeded connection limit.",
+            ),
+            &["limit"],
+        )
+        .unwrap(),
+        chunk_cache_lookups: IntCounterVec::new(
+            Opts::new(
+                "sws_chunk_cache_lookups_total",
+                "Number of file block lookups in the chunk cache by result (hit or miss).",
+            ),
+            &["result"],
+        )
+        .unwrap(),
+        not_found_cache_lookups: IntCounterVec::new(
+            Opts::new(
+                "sws_not_found_cache_lookups_total",
+                "Number of request path lookups in the not found cache by result (hit or miss).",
+            ),
+            &["result"],
+        )
+        .unwrap(),
+    };
+
+    let registry = default_registry();
+    registry
+        .register(Box::new(metrics.requests.clone()))
+        .unwrap();
+    registry
+        .register(Box::new(metrics.request_duration.clone()))
+        .unwrap();
+    registry
+        .register(Box::new(metrics.response_bytes.clone()))
+        .unwrap();
+    registry
+        .register(Box::new(metrics.open_connections.clone()))
+        .unwrap();
+    registry
+        .register(Box::new(metrics.rejected_connections.clone()))
+        .unwrap();
+    registry
+        .register(Box::new(metrics.chunk_cache_lookups.clone()))
+        .unwrap();
+    registry
+        .register(Box::new(metrics.not_found_cache_lookups.clone()))
+        .unwrap();
+    metrics
+});
 
 /// Initializes the metrics endpoint.
 pub fn init(enabled: bool, handler_opts: &mut RequestHandlerOpts) {
-    handler_opts.experimental_metrics = enabled;
-    tracing::info!("metrics endpoint (experimental): enabled={enabled}");
+    handler_opts.metrics = enabled;
+    tracing::info!("metrics endpoint: enabled={enabled}");
+}
 
+/// Initializes the Tokio runtime metrics (experimental).
+#[cfg(all(unix, feature = "experimental"))]
+pub fn init_runtime(enabled: bool) {
+    tracing::info!("tokio runtime metrics (experimental): enabled={enabled}");
+
+    // NOTE: the runtime collector is registered once since the options can be reloaded
+    static REGISTER: std::sync::Once = std::sync::Once::new();
     if enabled {
-        default_registry()
-            .register(Box::new(
-                tokio_metrics_collector::default_runtime_collector(),
-            ))
-            .unwrap();
+        REGISTER.call_once(|| {
+            default_registry()
+                .register(Box::new(
+                    tokio_metrics_collector::default_runtime_collector(),
+                ))
+                .unwrap();
+        });
     }
 }
 
@@ -31,7 +153,7 @@ pub fn pre_process<T>(
     opts: &RequestHandlerOpts,
     req: &Request<T>,
 ) -> Option<Result<Response<Body>, Error>> {
-    if !opts.experimental_metrics {
+    if !opts.metrics {
         return None;
     }
 
@@ -45,28 +167,176 @@ pub fn pre_process<T>(
         return None;
     }
 
+    // The text format is used unless the protobuf one is preferred
+    let protobuf = req
+        .headers()
+        .typed_get::<Accept>()
+        .and_then(|accept| accept.preferred(&[PROTOBUF_MEDIA_TYPE, TEXT_MEDIA_TYPE]))
+        == Some(PROTOBUF_MEDIA_TYPE);
+
     let body = if method.is_get() {
-        let encoder = TextEncoder::new();
+        LazyLock::force(&METRICS);
+        let metric_families = default_registry().gather();
         let mut buffer = Vec::new();
-        encoder
-            .encode(&default_registry().gather(), &mut buffer)
-            .unwrap();
-        let data = String::from_utf8(buffer).unwrap();
-        Body::from(data)
+        let encoded = if protobuf {
+            ProtobufEncoder::new().encode(&metric_families, &mut buffer)
+        } else {
+            TextEncoder::new().encode(&metric_families, &mut buffer)
+        };
+        if let Err(err) = encoded {
+            return Some(Err(anyhow!("unable to encode the metrics: {err}")));
+        }
+        Body::from(buffer)
     } else {
         Body::empty()
     };
+
     let mut resp = Response::new(body);
+    if protobuf {
+        resp.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(PROTOBUF_FORMAT));
+    } else {
+        resp.headers_mut()
+            .typed_insert(ContentType::from(mime_guess::mime::TEXT_PLAIN_UTF_8));
+    }
     resp.headers_mut()
-        .typed_insert(ContentType::from(mime_guess::mime::TEXT_PLAIN_UTF_8));
+        .insert(VARY, HeaderValue::from_static("accept, accept-encoding"));
+
+    // Compress the metrics if the compression is enabled and the client accepts gzip
+    #[cfg(any(feature = "compression", feature = "compression-gzip"))]
+    if opts.compression
+        && method.is_get()
+        && compression::get_encodings(req.headers()).contains(&ContentCoding::GZIP)
+    {
+        let (head, body) = resp.into_parts();
+        return Some(Ok(compression::gzip(
+            head,
+            body.into(),
+            opts.compression_level,
+            opts.compression_levels.gzip,
+        )));
+    }
     Some(Ok(resp))
 }
 
+/// Metrics of a request being handled.
+pub(crate) struct RequestMetrics {
+    method: String,
+    vhost: String,
+    started: Instant,
+}
+
+impl RequestMetrics {
+    /// Starts measuring the given request if the metrics are enabled.
+    pub fn start<T>(opts: &RequestHandlerOpts, req: &Request<T>) -> Option<Self> {
+        if !opts.metrics {
+            return None;
+        }
+
+        let method = if req.method().is_allowed() {
+            req.method().as_str()
+        } else {
+            OTHER_METHOD
+        };
+        let vhosts = opts
+            .advanced_opts
+            .as_ref()
+            .and_then(|advanced| advanced.virtual_hosts.as_deref());
+        let vhost = virtual_hosts::find(req, vhosts).map_or(DEFAULT_VHOST, |v| v.host.as_str());
+
+        Some(Self {
+            method: method.to_owned(),
+            vhost: vhost.to_owned(),
+            started: Instant::now(),
+        })
+    }
+
+    /// Records the given response and counts the bytes of its body as they are sent.
+    pub fn finish(self, resp: Response<Body>) -> Response<Body> {
+        let metrics = &*METRICS;
+        metrics
+            .requests
+            .with_label_values(&[&self.method, resp.status().as_str(), &self.vhost])
+            .inc();
+        metrics
+            .request_duration
+            .with_label_values(&[&self.method, &self.vhost])
+            .observe(self.started.elapsed().as_secs_f64());
+
+        let bytes = metrics.response_bytes.with_label_values(&[&self.vhost]);
+        let (parts, body) = resp.into_parts();
+        // Keep bodies of a known size as they are, so their `Content-Length` is still computed
+        let body = match body.size_hint().exact() {
+            Some(len) => {
+                bytes.inc_by(len);
+                body
+            }
+            None => {
+                Body::wrap_stream(body.inspect_ok(move |chunk| bytes.inc_by(chunk.len() as u64)))
+            }
+        };
+        Response::from_parts(parts, body)
+    }
+}
+
+/// Records the metrics of the request handled by the given future.
+pub(crate) async fn record<F>(
+    metrics: Option<RequestMetrics>,
+    handle: F,
+) -> Result<Response<Body>, Error>
+where
+    F: Future<Output = Result<Response<Body>, Error>>,
+{
+    let resp = handle.await?;
+    Ok(match metrics {
+        Some(metrics) => metrics.finish(resp),
+        None => resp,
+    })
+}
+
+/// Counts a new open connection.
+pub(crate) fn connection_opened() {
+    METRICS.open_connections.inc();
+}
+
+/// Discounts a closed connection.
+pub(crate) fn connection_closed() {
+    METRICS.open_connections.dec();
+}
+
+/// Counts a connection rejected by the given connection limit.
+pub(crate) fn connection_rejected(limit: &str) {
+    METRICS
+        .rejected_connections
+        .with_label_values(&[limit])
+        .inc();
+}
+
+/// Counts a file block lookup in the chunk cache.
+pub(crate) fn chunk_cache_lookup(hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    METRICS
+        .chunk_cache_lookups
+        .with_label_values(&[result])
+        .inc();
+}
+
+/// Counts a request path lookup in the not found cache.
+pub(crate) fn not_found_cache_lookup(hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    METRICS
+        .not_found_cache_lookups
+        .with_label_values(&[result])
+        .inc();
+}
+
 #[cfg(test)]
 mod tests {
-    use super::pre_process;
+    use super::{METRICS, PROTOBUF_FORMAT, RequestMetrics, pre_process};
     use crate::handler::RequestHandlerOpts;
-    use hyper::{Body, Request};
+    use crate::settings::{Advanced, VirtualHosts};
+    use headers::HeaderValue;
+    use hyper::{Body, Request, Response, StatusCode};
 
     fn make_request(method: &str, uri: &str) -> Request<Body> {
         Request::builder()
@@ -81,7 +351,7 @@ mod tests {
         assert!(
             pre_process(
                 &RequestHandlerOpts {
-                    experimental_metrics: false,
+                    metrics: false,
                     ..Default::default()
                 },
                 &make_request("GET", "/metrics")
@@ -95,7 +365,7 @@ mod tests {
         assert!(
             pre_process(
                 &RequestHandlerOpts {
-                    experimental_metrics: true,
+                    metrics: true,
                     ..Default::default()
                 },
                 &make_request("GET", "/metrics2")
@@ -109,7 +379,7 @@ mod tests {
         assert!(
             pre_process(
                 &RequestHandlerOpts {
-                    experimental_metrics: true,
+                    metrics: true,
                     ..Default::default()
                 },
                 &make_request("POST", "/metrics")
@@ -123,7 +393,7 @@ mod tests {
         assert!(
             pre_process(
                 &RequestHandlerOpts {
-                    experimental_metrics: true,
+                    metrics: true,
                     ..Default::default()
                 },
                 &make_request("GET", "/metrics")
@@ -131,4 +401,120 @@ mod tests {
             .is_some()
         );
     }
+
+    #[tokio::test]
+    async fn test_content_negotiation() {
+        let opts = RequestHandlerOpts {
+            metrics: true,
+            ..Default::default()
+        };
+        let scrape = |accept: &'static str| {
+            let mut req = make_request("GET", "/metrics");
+            req.headers_mut()
+                .insert("accept", HeaderValue::from_static(accept));
+            pre_process(&opts, &req).unwrap().unwrap()
+        };
+
+        let resp = scrape(
+            "application/vnd.google.protobuf;proto=io.prometheus.client.MetricFamily;encoding=delimited;q=0.7,text/plain;version=0.0.4;q=0.3,*/*;q=0.1",
+        );
+        assert_eq!(resp.headers()["content-type"], PROTOBUF_FORMAT);
+
+        for accept in ["text/plain;version=0.0.4", "*/*", "application/json"] {
+            let resp = scrape(accept);
+            assert_eq!(
+                resp.headers()["content-type"],
+                "text/plain; charset=utf-8",
+                "{accept}"
+            );
+            let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+            assert!(body.starts_with(b"# HELP "), "{accept}");
+        }
+    }
+
+    #[cfg(any(feature = "compression", feature = "compression-gzip"))]
+    #[test]
+    fn test_gzip() {
+        let mut opts = RequestHandlerOpts {
+            metrics: true,
+            ..Default::default()
+        };
+        let mut req = make_request("GET", "/metrics");
+        req.headers_mut()
+            .insert("accept-encoding", HeaderValue::from_static("gzip"));
+
+        opts.compression = false;
+        let resp = pre_process(&opts, &req).unwrap().unwrap();
+        assert!(resp.headers().get("content-encoding").is_none());
+
+        opts.compression = true;
+        let resp = pre_process(&opts, &req).unwrap().unwrap();
+        assert_eq!(resp.headers()["content-encoding"], "gzip");
+    }
+
+    #[test]
+    fn test_request_metrics_disabled() {
+        let opts = RequestHandlerOpts::default();
+        assert!(RequestMetrics::start(&opts, &make_request("GET", "/")).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_request_metrics() {
+        let opts = RequestHandlerOpts {
+            metrics: true,
+            advanced_opts: Some(Advanced {
+                virtual_hosts: Some(vec![VirtualHosts {
+                    host: "metrics.localhost".to_owned(),
+                    root: "tests/fixtures/public".into(),
+                    directory_listing_locale: None,
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let requests = METRICS
+            .requests
+            .with_label_values(&["GET", "200", "metrics.localhost"]);
+        let bytes = METRICS
+            .response_bytes
+            .with_label_values(&["metrics.localhost"]);
+
+        // Body of a known size
+        let req = make_request("GET", "http://metrics.localhost/index.html");
+        let metrics = RequestMetrics::start(&opts, &req).unwrap();
+        metrics.finish(Response::new(Body::from("hello")));
+        assert_eq!(requests.get(), 1);
+        assert_eq!(bytes.get(), 5);
+
+        // Streamed body
+        let metrics = RequestMetrics::start(&opts, &req).unwrap();
+        let chunks: Vec<Result<_, std::io::Error>> = vec![Ok("hello "), Ok("world")];
+        let resp = metrics.finish(Response::new(Body::wrap_stream(
+            futures_util::stream::iter(chunks),
+        )));
+        assert_eq!(requests.get(), 2);
+        assert_eq!(bytes.get(), 5);
+        hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        assert_eq!(bytes.get(), 16);
+
+        // Unknown virtual host and unsupported method
+        let req = make_request("DELETE", "http://unknown.localhost/");
+        let mut resp = Response::new(Body::empty());
+        *resp.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
+        RequestMetrics::start(&opts, &req).unwrap().finish(resp);
+        assert_eq!(
+            METRICS
+                .requests
+                .with_label_values(&["OTHER", "405", "default"])
+                .get(),
+            1
+        );
+        assert_eq!(
+            METRICS
+                .request_duration
+                .with_label_values(&["OTHER", "default"])
+                .get_sample_count(),
+            1
+        );
+    }
 }
@@ -0,0 +1,193 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module to override or add MIME types and charsets by file extension
+//! as well as to force content types or downloads by glob pattern.
+//!
+
+use headers::{ContentType, HeaderMapExt, HeaderValue};
+use hyper::{Body, Request, Response, header::CONTENT_DISPOSITION};
+use mime_guess::{Mime, mime};
+use std::path::PathBuf;
+
+use crate::{Error, Result, handler::RequestHandlerOpts};
+
+/// Initializes the default charset for text-based content types.
+pub(crate) fn init(default_charset: &str, handler_opts: &mut RequestHandlerOpts) -> Result {
+    let default_charset = default_charset.trim();
+    if !default_charset.is_empty() && !is_valid_charset(default_charset) {
+        bail!("invalid default charset: {default_charset}");
+    }
+    handler_opts.default_charset = default_charset.to_owned();
+    tracing::info!(
+        "default charset: enabled={}, charset={}",
+        !default_charset.is_empty(),
+        default_charset
+    );
+    Ok(())
+}
+
+/// Replaces the `Content-Type` of a file response if a custom MIME type or charset applies to it
+/// and forces its download if a matching content type entry requires it.
+pub(crate) fn post_process<T>(
+    opts: &RequestHandlerOpts,
+    req: &Request<T>,
+    mut resp: Response<Body>,
+    file_path: Option<&PathBuf>,
+) -> Result<Response<Body>, Error> {
+    if !resp.status().is_success() {
+        return Ok(resp);
+    }
+
+    let advanced = opts.advanced_opts.as_ref();
+    let ext = file_path
+        .and_then(|p| p.extension())
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+
+    // The first content type entry matching the request path takes precedence
+    let content_type_entry = advanced
+        .and_then(|advanced| advanced.content_types.as_ref())
+        .and_then(|entries| {
+            let uri_path = req.uri().path();
+            entries.iter().find(|entry| entry.source.is_match(uri_path))
+        });
+
+    let custom_mime = content_type_entry
+        .and_then(|entry| entry.content_type.as_ref())
+        .or_else(|| {
+            advanced
+                .and_then(|advanced| advanced.mime_types.as_ref())
+                .zip(ext.as_ref())
+                .and_then(|(mime_types, ext)| mime_types.get(ext))
+        });
+    let custom_charset = advanced
+        .and_then(|advanced| advanced.charsets.as_ref())
+        .zip(ext.as_ref())
+        .and_then(|(charsets, ext)| charsets.get(ext));
+
+    // Skip directories like auto-index pages
+    if (custom_mime.is_some() || custom_charset.is_some() || content_type_entry.is_some())
+        && file_path.is_some_and(|p| p.is_dir())
+    {
+        return Ok(resp);
+    }
+
+    if content_type_entry.is_some_and(|entry| entry.download) {
+        resp.headers_mut()
+            .insert(CONTENT_DISPOSITION, attachment(file_path));
+    }
+
+    let mime = match custom_mime {
+        Some(mime) => mime.to_owned(),
+        None => match resp.headers().typed_get::<ContentType>() {
+            Some(content_type) => Mime::from(content_type),
+            None => return Ok(resp),
+        },
+    };
+
+    let charset = custom_charset.map(String::as_str).or_else(|| {
+        let use_default = !opts.default_charset.is_empty()
+            && mime.get_param(mime::CHARSET).is_none()
+            && is_text(&mime);
+        use_default.then_some(opts.default_charset.as_str())
+    });
+
+    if custom_mime.is_none() && charset.is_none() {
+        return Ok(resp);
+    }
+
+    let mime = match charset {
+        Some(charset) => with_charset(mime, charset),
+        None => mime,
+    };
+    resp.headers_mut().typed_insert(ContentType::from(mime));
+
+    Ok(resp)
+}
+
+/// Builds an `attachment` content disposition using the file name if possible.
+fn attachment(file_path: Option<&PathBuf>) -> HeaderValue {
+    file_path
+        .and_then(|p| p.file_name())
+        .and_then(|name| name.to_str())
+        .filter(|name| !name.contains('"'))
+        .and_then(|name| HeaderValue::from_str(&format!("attachment; filename=\"{name}\"")).ok())
+        .unwrap_or_else(|| HeaderValue::from_static("attachment"))
+}
+
+/// Checks whether a charset name is valid for a `Content-Type` header.
+pub(crate) fn is_valid_charset(charset: &str) -> bool {
+    !charset.is_empty()
+        && charset
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':' | '+'))
+}
+
+/// Checks whether a MIME type is text-based so a charset applies to it.
+fn is_text(mime: &Mime) -> bool {
+    mime.type_() == mime::TEXT
+        || (mime.type_() == mime::APPLICATION && mime.subtype() == mime::JAVASCRIPT)
+}
+
+/// Returns the given MIME type with its charset replaced.
+fn with_charset(mime: Mime, charset: &str) -> Mime {
+    format!("{}; charset={}", mime.essence_str(), charset)
+        .parse::<Mime>()
+        .unwrap_or(mime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{attachment, is_text, is_valid_charset, with_charset};
+    use mime_guess::Mime;
+    use std::path::PathBuf;
+
+    fn mime(s: &str) -> Mime {
+        s.parse::<Mime>().unwrap()
+    }
+
+    #[test]
+    fn test_is_text() {
+        assert!(is_text(&mime("text/html")));
+        assert!(is_text(&mime("text/css")));
+        assert!(is_text(&mime("application/javascript")));
+        assert!(!is_text(&mime("application/json")));
+        assert!(!is_text(&mime("image/png")));
+    }
+
+    #[test]
+    fn test_attachment() {
+        assert_eq!(
+            attachment(Some(&PathBuf::from("/public/src/main.rs"))),
+            "attachment; filename=\"main.rs\""
+        );
+        assert_eq!(
+            attachment(Some(&PathBuf::from("/public/a\"b.rs"))),
+            "attachment"
+        );
+        assert_eq!(attachment(None), "attachment");
+    }
+
+    #[test]
+    fn test_is_valid_charset() {
+        assert!(is_valid_charset("utf-8"));
+        assert!(is_valid_charset("ISO-8859-1"));
+        assert!(!is_valid_charset(""));
+        assert!(!is_valid_charset("utf-8; foo=bar"));
+    }
+
+    #[test]
+    fn test_with_charset() {
+        assert_eq!(
+            with_charset(mime("text/html"), "utf-8"),
+            mime("text/html; charset=utf-8")
+        );
+        assert_eq!(
+            with_charset(mime("text/plain; charset=utf-8"), "iso-8859-1"),
+            mime("text/plain; charset=iso-8859-1")
+        );
+    }
+}
@@ -0,0 +1,260 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that memoizes the request paths recently not found for a short time,
+//! so bursts of requests for nonexistent paths (E.g. vulnerability scanners)
+//! are answered without looking up the file system again.
+//!
+//! The entries are keyed by the root directory of the request, and the cache is created
+//! again on every configuration reload, so a root directory change never serves stale results.
+//!
+
+use headers::HeaderValue;
+use hyper::{Method, Request, StatusCode, header::ACCEPT_ENCODING};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::handler::RequestHandlerOpts;
+
+#[cfg(feature = "metrics")]
+use crate::metrics;
+
+/// Initializes the cache of the request paths not found.
+pub fn init(ttl: u64, capacity: usize, handler_opts: &mut RequestHandlerOpts) {
+    let enabled = ttl > 0 && capacity > 0;
+    handler_opts.not_found_cache = enabled.then(|| NotFoundCache::new(ttl, capacity));
+    tracing::info!("not found cache: enabled={enabled}, ttl={ttl}s, capacity={capacity}");
+}
+
+/// Identifies a lookup of a request path under a root directory.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Key {
+    root: PathBuf,
+    path: String,
+    /// The accepted encodings select the pre-compressed file variants.
+    encoding: Option<HeaderValue>,
+}
+
+/// Cache of the request paths recently not found.
+#[derive(Clone)]
+pub struct NotFoundCache {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    ttl: Duration,
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+/// Entries along with their insertion order, the oldest ones are evicted first.
+#[derive(Default)]
+struct Entries {
+    found_at: HashMap<Key, Instant>,
+    order: VecDeque<Key>,
+}
+
+impl NotFoundCache {
+    /// Creates a new cache of at most `capacity` entries expiring after `ttl` seconds.
+    pub fn new(ttl: u64, capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                ttl: Duration::from_secs(ttl),
+                capacity,
+                entries: Mutex::new(Entries::default()),
+            }),
+        }
+    }
+
+    /// Checks if a lookup recently resulted in a path not found.
+    fn contains(&self, key: &Key) -> bool {
+        self.inner.entries.lock().is_ok_and(|entries| {
+            entries
+                .found_at
+                .get(key)
+                .is_some_and(|t| t.elapsed() < self.inner.ttl)
+        })
+    }
+
+    /// Remembers a lookup resulting in a path not found.
+    fn insert(&self, key: Key) {
+        let Ok(mut entries) = self.inner.entries.lock() else {
+            return;
+        };
+        // An expired entry is renewed in place, keeping its insertion order
+        if entries
+            .found_at
+            .insert(key.clone(), Instant::now())
+            .is_none()
+        {
+            entries.order.push_back(key);
+        }
+        while entries.order.len() > self.inner.capacity {
+            if let Some(oldest) = entries.order.pop_front() {
+                entries.found_at.remove(&oldest);
+            }
+        }
+    }
+
+    /// Returns the number of cached entries, including the expired ones.
+    pub fn len(&self) -> usize {
+        self.inner
+            .entries
+            .lock()
+            .map_or(0, |entries| entries.found_at.len())
+    }
+
+    /// Checks if the cache has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all cached entries.
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.inner.entries.lock() {
+            *entries = Entries::default();
+        }
+    }
+}
+
+/// Result of looking up a request path in the cache.
+pub(crate) enum Lookup<'a> {
+    /// The cache doesn't apply to the request.
+    Skip,
+    /// The path was recently not found.
+    Hit,
+    /// The path is not cached, so it must be looked up in the file system.
+    Miss(&'a NotFoundCache, Key),
+}
+
+impl Lookup<'_> {
+    /// Checks if the path was recently not found.
+    pub(crate) fn is_hit(&self) -> bool {
+        matches!(self, Self::Hit)
+    }
+
+    /// Remembers the path of a missed lookup if it was not found in the file system.
+    pub(crate) fn store<T>(self, result: &Result<T, StatusCode>) {
+        if let Self::Miss(cache, key) = self {
+            if matches!(result, Err(StatusCode::NOT_FOUND)) {
+                cache.insert(key);
+            }
+        }
+    }
+}
+
+/// Looks up the path of a `GET` or `HEAD` request under the given root directory.
+pub(crate) fn lookup<'a, T>(
+    opts: &'a RequestHandlerOpts,
+    req: &Request<T>,
+    base_path: &Path,
+    uri_path: &str,
+) -> Lookup<'a> {
+    let Some(cache) = opts.not_found_cache.as_ref() else {
+        return Lookup::Skip;
+    };
+    if !matches!(*req.method(), Method::GET | Method::HEAD) {
+        return Lookup::Skip;
+    }
+
+    let key = Key {
+        root: base_path.to_owned(),
+        path: uri_path.to_owned(),
+        encoding: opts
+            .compression_static
+            .then(|| req.headers().get(ACCEPT_ENCODING).cloned())
+            .flatten(),
+    };
+    let hit = cache.contains(&key);
+
+    #[cfg(feature = "metrics")]
+    metrics::not_found_cache_lookup(hit);
+
+    if hit {
+        tracing::debug!("path {} recently not found (cached)", uri_path);
+        Lookup::Hit
+    } else {
+        Lookup::Miss(cache, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NotFoundCache, lookup};
+    use crate::handler::RequestHandlerOpts;
+    use hyper::{Body, Request, StatusCode};
+    use std::path::Path;
+
+    fn make_request(method: &str, uri: &str) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn make_opts(capacity: usize) -> RequestHandlerOpts {
+        RequestHandlerOpts {
+            not_found_cache: Some(NotFoundCache::new(60, capacity)),
+            compression_static: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        let opts = make_opts(10);
+        let root = Path::new("public");
+        let req = make_request("GET", "/wp-login.php");
+
+        let result = lookup(&opts, &req, root, "/wp-login.php");
+        assert!(!result.is_hit());
+        result.store::<()>(&Err(StatusCode::NOT_FOUND));
+        assert!(lookup(&opts, &req, root, "/wp-login.php").is_hit());
+        assert!(lookup(&opts, &make_request("HEAD", "/"), root, "/wp-login.php").is_hit());
+
+        // Other root directories or methods are not affected
+        assert!(!lookup(&opts, &req, Path::new("other"), "/wp-login.php").is_hit());
+        assert!(!lookup(&opts, &make_request("POST", "/"), root, "/wp-login.php").is_hit());
+
+        // Other results are not cached
+        lookup(&opts, &req, root, "/index.html").store(&Ok(()));
+        lookup(&opts, &req, root, "/50x.html").store::<()>(&Err(StatusCode::FORBIDDEN));
+        assert_eq!(opts.not_found_cache.as_ref().unwrap().len(), 1);
+
+        opts.not_found_cache.as_ref().unwrap().clear();
+        assert!(!lookup(&opts, &req, root, "/wp-login.php").is_hit());
+    }
+
+    #[test]
+    fn test_capacity() {
+        let opts = make_opts(2);
+        let root = Path::new("public");
+        let req = make_request("GET", "/");
+        for path in ["/a", "/b", "/a", "/c"] {
+            lookup(&opts, &req, root, path).store::<()>(&Err(StatusCode::NOT_FOUND));
+        }
+        // The oldest entry is evicted first
+        assert!(!lookup(&opts, &req, root, "/a").is_hit());
+        assert!(lookup(&opts, &req, root, "/b").is_hit());
+        assert!(lookup(&opts, &req, root, "/c").is_hit());
+        assert_eq!(opts.not_found_cache.as_ref().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_expired() {
+        let cache = NotFoundCache::new(0, 10);
+        let opts = RequestHandlerOpts {
+            not_found_cache: Some(cache),
+            ..Default::default()
+        };
+        let req = make_request("GET", "/");
+        let root = Path::new("public");
+        lookup(&opts, &req, root, "/a").store::<()>(&Err(StatusCode::NOT_FOUND));
+        assert!(!lookup(&opts, &req, root, "/a").is_hit());
+    }
+}
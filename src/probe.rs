@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module providing a body-less probe endpoint (E.g. `/generate_204`).
+//!
+
+use headers::{HeaderMapExt, HeaderValue, Pragma};
+use hyper::{Body, Method, Request, Response, StatusCode, header::CACHE_CONTROL};
+
+use crate::{Error, handler::RequestHandlerOpts};
+
+/// Initializes the probe endpoint.
+pub fn init(path: &str, handler_opts: &mut RequestHandlerOpts) {
+    let path = path.trim();
+    handler_opts.probe_endpoint = match path {
+        "" => String::new(),
+        p if p.starts_with('/') => p.to_owned(),
+        p => ["/", p].concat(),
+    };
+    tracing::info!(
+        "probe endpoint: enabled={}, path=\"{}\"",
+        !handler_opts.probe_endpoint.is_empty(),
+        handler_opts.probe_endpoint
+    );
+}
+
+/// Handles probe requests.
+pub fn pre_process<T>(
+    opts: &RequestHandlerOpts,
+    req: &Request<T>,
+) -> Option<Result<Response<Body>, Error>> {
+    if !is_probe_endpoint(opts, req) {
+        return None;
+    }
+
+    if !matches!(*req.method(), Method::GET | Method::HEAD) {
+        return None;
+    }
+
+    let mut resp = Response::new(Body::empty());
+    *resp.status_mut() = StatusCode::NO_CONTENT;
+    resp.headers_mut().insert(
+        CACHE_CONTROL,
+        HeaderValue::from_static("no-cache, no-store, must-revalidate"),
+    );
+    resp.headers_mut().typed_insert(Pragma::no_cache());
+    Some(Ok(resp))
+}
+
+pub(crate) fn is_probe_endpoint<T>(opts: &RequestHandlerOpts, req: &Request<T>) -> bool {
+    !opts.probe_endpoint.is_empty() && req.uri().path() == opts.probe_endpoint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{init, pre_process};
+    use crate::handler::RequestHandlerOpts;
+    use hyper::{Body, Request, StatusCode};
+
+    fn make_request(method: &str, uri: &str) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn make_opts(path: &str) -> RequestHandlerOpts {
+        let mut opts = RequestHandlerOpts::default();
+        init(path, &mut opts);
+        opts
+    }
+
+    #[test]
+    fn test_probe_disabled() {
+        assert!(pre_process(&make_opts(""), &make_request("GET", "/")).is_none());
+        assert!(pre_process(&make_opts(""), &make_request("GET", "/generate_204")).is_none());
+    }
+
+    #[test]
+    fn test_wrong_uri() {
+        assert!(
+            pre_process(
+                &make_opts("/generate_204"),
+                &make_request("GET", "/generate_205"),
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_wrong_method() {
+        assert!(
+            pre_process(
+                &make_opts("/generate_204"),
+                &make_request("POST", "/generate_204"),
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_correct_request() {
+        for path in ["/generate_204", "generate_204"] {
+            let resp = pre_process(&make_opts(path), &make_request("HEAD", "/generate_204"))
+                .unwrap()
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+            assert_eq!(
+                resp.headers()["cache-control"],
+                "no-cache, no-store, must-revalidate"
+            );
+        }
+    }
+}
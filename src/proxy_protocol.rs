@@ -0,0 +1,378 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! The module handles the [PROXY protocol](https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt)
+//! (versions 1 and 2) header sent by load balancers in front of a listener.
+//!
+
+use bytes::{Buf, BytesMut};
+use hyper::server::accept::Accept;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+use tokio::task::JoinSet;
+
+use crate::transport::Transport;
+use crate::zero_copy::ZeroCopySender;
+
+/// Maximum time to wait for the PROXY protocol header of a new connection.
+const HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximum length of a version 1 header including the trailing CRLF.
+const V1_MAX_LEN: usize = 107;
+
+/// Signature of a version 2 header.
+const V2_SIGNATURE: &[u8; 12] = b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Parses a PROXY protocol header at the beginning of the given buffer.
+///
+/// It returns `None` if more data is needed or the header length along with the
+/// source address of the client if the proxy provided one.
+fn parse_header(buf: &[u8]) -> io::Result<Option<(usize, Option<SocketAddr>)>> {
+    if buf.starts_with(b"PROXY ") {
+        return parse_v1(buf);
+    }
+    let len = buf.len().min(V2_SIGNATURE.len());
+    if buf[..len] == V2_SIGNATURE[..len] {
+        return parse_v2(buf);
+    }
+    if len < b"PROXY ".len() && b"PROXY "[..len] == buf[..len] {
+        return Ok(None);
+    }
+    Err(invalid_header("missing proxy protocol header"))
+}
+
+/// Parses a version 1 (human-readable) header.
+/// E.g. `PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\n`
+fn parse_v1(buf: &[u8]) -> io::Result<Option<(usize, Option<SocketAddr>)>> {
+    let end = match buf.windows(2).position(|w| w == b"\r\n") {
+        Some(end) => end,
+        None if buf.len() < V1_MAX_LEN => return Ok(None),
+        None => return Err(invalid_header("proxy protocol v1 header is too long")),
+    };
+    let line = std::str::from_utf8(&buf[..end])
+        .map_err(|_| invalid_header("invalid proxy protocol v1 header"))?;
+
+    let mut parts = line.split(' ').skip(1);
+    let addr = match parts.next() {
+        Some("UNKNOWN") => None,
+        Some(proto @ ("TCP4" | "TCP6")) => {
+            let (src, _dst, src_port) = (parts.next(), parts.next(), parts.next());
+            let src = src
+                .and_then(|ip| ip.parse::<IpAddr>().ok())
+                .filter(|ip| ip.is_ipv4() == (proto == "TCP4"));
+            let src_port = src_port.and_then(|port| port.parse::<u16>().ok());
+            match (src, src_port, parts.next(), parts.next()) {
+                (Some(ip), Some(port), Some(_dst_port), None) => Some(SocketAddr::new(ip, port)),
+                _ => return Err(invalid_header("invalid proxy protocol v1 addresses")),
+            }
+        }
+        _ => return Err(invalid_header("invalid proxy protocol v1 protocol")),
+    };
+    Ok(Some((end + 2, addr)))
+}
+
+/// Parses a version 2 (binary) header.
+fn parse_v2(buf: &[u8]) -> io::Result<Option<(usize, Option<SocketAddr>)>> {
+    if buf.len() < 16 {
+        return Ok(None);
+    }
+    let version_command = buf[12];
+    if version_command >> 4 != 2 {
+        return Err(invalid_header("unsupported proxy protocol version"));
+    }
+    let len = 16 + u16::from_be_bytes([buf[14], buf[15]]) as usize;
+    if buf.len() < len {
+        return Ok(None);
+    }
+
+    let addresses = &buf[16..len];
+    let addr = match (version_command & 0x0f, buf[13]) {
+        // LOCAL command, e.g. health checks of the proxy itself
+        (0x0, _) => None,
+        // PROXY command over TCP/IPv4
+        (0x1, 0x11) if addresses.len() >= 12 => {
+            let ip = Ipv4Addr::new(addresses[0], addresses[1], addresses[2], addresses[3]);
+            let port = u16::from_be_bytes([addresses[8], addresses[9]]);
+            Some(SocketAddr::new(ip.into(), port))
+        }
+        // PROXY command over TCP/IPv6
+        (0x1, 0x21) if addresses.len() >= 36 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&addresses[..16]);
+            let port = u16::from_be_bytes([addresses[32], addresses[33]]);
+            Some(SocketAddr::new(Ipv6Addr::from(octets).into(), port))
+        }
+        // Other address families (E.g. Unix sockets) are not forwarded
+        (0x1, 0x00 | 0x31 | 0x32) => None,
+        (0x1, _) => return Err(invalid_header("invalid proxy protocol v2 addresses")),
+        _ => return Err(invalid_header("invalid proxy protocol v2 command")),
+    };
+    Ok(Some((len, addr)))
+}
+
+fn invalid_header(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Reads the PROXY protocol header of a connection.
+async fn read_header<T>(mut stream: T) -> io::Result<ProxyProtocolStream<T>>
+where
+    T: AsyncRead + Unpin,
+{
+    let mut buf = BytesMut::with_capacity(V1_MAX_LEN);
+    loop {
+        if stream.read_buf(&mut buf).await? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        if let Some((len, remote_addr)) = parse_header(&buf)? {
+            buf.advance(len);
+            return Ok(ProxyProtocolStream {
+                inner: stream,
+                buffered: buf,
+                remote_addr,
+            });
+        }
+    }
+}
+
+/// Connection stream whose PROXY protocol header was already consumed.
+pub struct ProxyProtocolStream<T> {
+    inner: T,
+    /// Data received after the header.
+    buffered: BytesMut,
+    /// Client address sent by the proxy.
+    remote_addr: Option<SocketAddr>,
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for ProxyProtocolStream<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let pin = self.get_mut();
+        if !pin.buffered.is_empty() {
+            let len = pin.buffered.len().min(buf.remaining());
+            buf.put_slice(&pin.buffered.split_to(len));
+            return Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut pin.inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for ProxyProtocolStream<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+impl<T: Transport + Unpin> Transport for ProxyProtocolStream<T> {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote_addr.or_else(|| self.inner.remote_addr())
+    }
+
+    fn zero_copy(&self) -> Option<ZeroCopySender> {
+        self.inner.zero_copy()
+    }
+}
+
+/// Type to intercept incoming connections in order to read their PROXY protocol header.
+///
+/// The headers are read concurrently, so a slow client doesn't delay the other connections.
+/// Connections without a valid header are closed.
+pub struct ProxyProtocolAcceptor<A: Accept> {
+    incoming: A,
+    pending: JoinSet<io::Result<ProxyProtocolStream<A::Conn>>>,
+    closed: bool,
+}
+
+impl<A: Accept> ProxyProtocolAcceptor<A> {
+    /// Creates a new PROXY protocol interceptor.
+    pub fn new(incoming: A) -> Self {
+        Self {
+            incoming,
+            pending: JoinSet::new(),
+            closed: false,
+        }
+    }
+}
+
+impl<A> Accept for ProxyProtocolAcceptor<A>
+where
+    A: Accept + Unpin,
+    A::Conn: Transport + Unpin + Send + 'static,
+{
+    type Conn = ProxyProtocolStream<A::Conn>;
+    type Error = A::Error;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        let pin = self.get_mut();
+
+        while !pin.closed {
+            match Pin::new(&mut pin.incoming).poll_accept(cx) {
+                Poll::Ready(Some(Ok(conn))) => {
+                    let peer = conn.remote_addr();
+                    pin.pending.spawn(async move {
+                        tokio::time::timeout(HEADER_TIMEOUT, read_header(conn))
+                            .await
+                            .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()))
+                            .inspect_err(|err| {
+                                tracing::debug!(
+                                    "unable to read the proxy protocol header from {:?}: {}",
+                                    peer,
+                                    err
+                                )
+                            })
+                    });
+                }
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => pin.closed = true,
+                Poll::Pending => break,
+            }
+        }
+
+        loop {
+            match pin.pending.poll_join_next(cx) {
+                Poll::Ready(Some(Ok(Ok(stream)))) => return Poll::Ready(Some(Ok(stream))),
+                // Connections with an invalid header are dropped
+                Poll::Ready(Some(_)) => continue,
+                Poll::Ready(None) if pin.closed => return Poll::Ready(None),
+                Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_header, read_header};
+    use crate::transport::Transport;
+    use std::net::SocketAddr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn addr(s: &str) -> Option<SocketAddr> {
+        Some(s.parse().unwrap())
+    }
+
+    #[test]
+    fn test_parse_v1() {
+        let header = b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\nGET /";
+        assert_eq!(
+            parse_header(header).unwrap(),
+            Some((47, addr("192.168.0.1:56324")))
+        );
+
+        let header = b"PROXY TCP6 2001:db8::1 2001:db8::2 56324 443\r\n";
+        assert_eq!(
+            parse_header(header).unwrap(),
+            Some((header.len(), addr("[2001:db8::1]:56324")))
+        );
+
+        let header = b"PROXY UNKNOWN ffff:f...f:ffff ffff:f...f:ffff 65535 65535\r\n";
+        assert_eq!(parse_header(header).unwrap(), Some((header.len(), None)));
+    }
+
+    #[test]
+    fn test_parse_v1_incomplete() {
+        assert_eq!(parse_header(b"PRO").unwrap(), None);
+        assert_eq!(parse_header(b"PROXY TCP4 192.168.0.1").unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_v1_invalid() {
+        assert!(parse_header(b"GET / HTTP/1.1\r\n").is_err());
+        assert!(parse_header(b"PROXY TCP4 2001:db8::1 2001:db8::2 56324 443\r\n").is_err());
+        assert!(parse_header(b"PROXY TCP4 192.168.0.1 192.168.0.11 56324\r\n").is_err());
+        assert!(parse_header(b"PROXY UDP4 192.168.0.1 192.168.0.11 56324 443\r\n").is_err());
+        assert!(parse_header(&[b'P', b'R', b'O', b'X', b'Y', b' ', b'A'].repeat(20)).is_err());
+    }
+
+    #[test]
+    fn test_parse_v2() {
+        let mut header = b"\r\n\r\n\0\r\nQUIT\n\x21\x11\x00\x0c".to_vec();
+        header.extend([192, 168, 0, 1, 192, 168, 0, 11, 0xdc, 0x04, 0x01, 0xbb]);
+        header.extend(b"GET /");
+        assert_eq!(
+            parse_header(&header).unwrap(),
+            Some((28, addr("192.168.0.1:56324")))
+        );
+        assert_eq!(parse_header(&header[..20]).unwrap(), None);
+
+        let mut header = b"\r\n\r\n\0\r\nQUIT\n\x21\x21\x00\x24".to_vec();
+        header.extend(
+            "2001:db8::1"
+                .parse::<std::net::Ipv6Addr>()
+                .unwrap()
+                .octets(),
+        );
+        header.extend([0; 16]);
+        header.extend([0xdc, 0x04, 0x01, 0xbb]);
+        assert_eq!(
+            parse_header(&header).unwrap(),
+            Some((52, addr("[2001:db8::1]:56324")))
+        );
+
+        // LOCAL command
+        let header = b"\r\n\r\n\0\r\nQUIT\n\x20\x00\x00\x00";
+        assert_eq!(parse_header(header).unwrap(), Some((16, None)));
+
+        // Invalid version
+        let header = b"\r\n\r\n\0\r\nQUIT\n\x11\x11\x00\x00";
+        assert!(parse_header(header).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_header() {
+        let (mut client, server) = tokio::io::duplex(1024);
+        client
+            .write_all(b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\nGET / HTTP/1.1\r\n")
+            .await
+            .unwrap();
+        drop(client);
+
+        let mut stream = read_header(server).await.unwrap();
+        assert_eq!(stream.remote_addr, addr("192.168.0.1:56324"));
+        let mut data = String::new();
+        stream.read_to_string(&mut data).await.unwrap();
+        assert_eq!(data, "GET / HTTP/1.1\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_read_header_missing() {
+        let (mut client, server) = tokio::io::duplex(1024);
+        client.write_all(b"GET / HTTP/1.1\r\n").await.unwrap();
+        assert!(read_header(server).await.is_err());
+    }
+
+    #[test]
+    fn test_remote_addr() {
+        let (_, server) = tokio::io::duplex(1);
+        let stream = super::ProxyProtocolStream {
+            inner: crate::transport::LiftIo(server),
+            buffered: Default::default(),
+            remote_addr: addr("192.168.0.1:56324"),
+        };
+        assert_eq!(stream.remote_addr(), addr("192.168.0.1:56324"));
+    }
+}
@@ -7,10 +7,14 @@
 //!
 
 use headers::HeaderValue;
-use hyper::{Body, Request, Response, StatusCode};
+use hyper::{Body, Request, Response, StatusCode, http::uri::Scheme};
 use regex_lite::Regex;
 
-use crate::{Error, error_page, handler::RequestHandlerOpts, settings::Redirects};
+use crate::{
+    Error, error_page,
+    handler::RequestHandlerOpts,
+    settings::{Redirects, RequestConditions},
+};
 
 /// Applies redirect rules to a request if necessary.
 pub(crate) fn pre_process<T>(
@@ -19,18 +23,8 @@ pub(crate) fn pre_process<T>(
 ) -> Option<Result<Response<Body>, Error>> {
     let redirects = opts.advanced_opts.as_ref()?.redirects.as_deref()?;
 
-    let uri = req.uri();
-    let uri_path = uri.path();
-    let host = req
-        .headers()
-        .get(http::header::HOST)
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-    let mut uri_host = uri.host().unwrap_or(host).to_owned();
-    if let Some(uri_port) = uri.port_u16() {
-        uri_host.push_str(&format!(":{uri_port}"));
-    }
-    let matched = get_redirection(&uri_host, uri_path, Some(redirects))?;
+    let uri_path = req.uri().path();
+    let matched = get_redirection(req, Some(redirects))?;
     let dest = match replace_placeholders(uri_path, &matched.source, &matched.destination) {
         Ok(dest) => dest,
         Err(err) => return handle_error(err, opts, req),
@@ -80,7 +74,11 @@ pub(crate) fn replace_placeholders(
     tracing::debug!("url redirects/rewrites glob pattern captures: {caps:?}");
     tracing::debug!("url redirects/rewrites glob pattern destination: {dest_uri:?}");
 
-    let ac = match aho_corasick::AhoCorasick::new(patterns) {
+    // The longest placeholder wins so `$10` isn't replaced as `$1` followed by `0`
+    let ac = match aho_corasick::AhoCorasick::builder()
+        .match_kind(aho_corasick::MatchKind::LeftmostLongest)
+        .build(patterns)
+    {
         Ok(ac) => ac,
         Err(err) => return Err(Error::new(err).context("failed creating Aho-Corasick matcher")),
     };
@@ -93,6 +91,50 @@ pub(crate) fn replace_placeholders(
     }
 }
 
+/// Gets the host of the request URI or its `Host` header along with the URI port if any.
+pub(crate) fn request_host<T>(req: &Request<T>) -> String {
+    let uri = req.uri();
+    let host = req
+        .headers()
+        .get(http::header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    let mut uri_host = uri.host().unwrap_or(host).to_owned();
+    if let Some(uri_port) = uri.port_u16() {
+        uri_host.push_str(&format!(":{uri_port}"));
+    }
+    uri_host
+}
+
+/// Checks whether the request matches the scheme, headers and query conditions
+/// of a redirect or rewrite entry.
+pub(crate) fn matches_conditions<T>(req: &Request<T>, conditions: &RequestConditions) -> bool {
+    if let Some(scheme) = &conditions.scheme {
+        // The scheme of the connection is provided by the service, plain HTTP otherwise
+        let req_scheme = req.extensions().get::<Scheme>().unwrap_or(&Scheme::HTTP);
+        if req_scheme != scheme {
+            return false;
+        }
+    }
+
+    // Every header must be present with at least one value matching its pattern
+    let headers_match = conditions.headers.iter().all(|(name, pattern)| {
+        req.headers()
+            .get_all(name)
+            .iter()
+            .any(|value| value.to_str().is_ok_and(|value| pattern.is_match(value)))
+    });
+    if !headers_match {
+        return false;
+    }
+
+    // Every query parameter must be present with a value matching its pattern
+    let query = req.uri().query().unwrap_or_default().as_bytes();
+    conditions.query.iter().all(|(name, pattern)| {
+        form_urlencoded::parse(query).any(|(key, value)| key == *name && pattern.is_match(&*value))
+    })
+}
+
 /// Logs error and produces an Internal Server Error response.
 pub(crate) fn handle_error<T>(
     err: Error,
@@ -104,18 +146,18 @@ pub(crate) fn handle_error<T>(
         req.uri(),
         req.method(),
         &StatusCode::INTERNAL_SERVER_ERROR,
-        &opts.page404,
-        &opts.page50x,
+        &opts.error_pages,
     ))
 }
 
-/// It returns a redirect's destination path and status code if the current request uri
+/// It returns a redirect's destination path and status code if the current request
 /// matches against the provided redirect's array.
-pub fn get_redirection<'a>(
-    uri_host: &'a str,
-    uri_path: &'a str,
+pub fn get_redirection<'a, T>(
+    req: &Request<T>,
     redirects_opts: Option<&'a [Redirects]>,
 ) -> Option<&'a Redirects> {
+    let uri_host = request_host(req);
+    let uri_path = req.uri().path();
     if let Some(redirects_vec) = redirects_opts {
         for redirect_entry in redirects_vec {
             // Match `host` redirect against `uri_host` if specified
@@ -123,13 +165,15 @@ pub fn get_redirection<'a>(
                 tracing::debug!(
                     "checking host '{host}' redirect entry against uri host '{uri_host}'"
                 );
-                if !host.eq(uri_host) {
+                if !host.eq(&uri_host) {
                     continue;
                 }
             }
 
             // Match source glob pattern against the request uri path
-            if redirect_entry.source.is_match(uri_path) {
+            if redirect_entry.source.is_match(uri_path)
+                && matches_conditions(req, &redirect_entry.conditions)
+            {
                 return Some(redirect_entry);
             }
         }
@@ -140,13 +184,13 @@ pub fn get_redirection<'a>(
 
 #[cfg(test)]
 mod tests {
-    use super::pre_process;
+    use super::{matches_conditions, pre_process, replace_placeholders};
     use crate::{
         Error,
         handler::RequestHandlerOpts,
-        settings::{Advanced, Redirects},
+        settings::{Advanced, Redirects, RequestConditions},
     };
-    use hyper::{Body, Request, Response, StatusCode};
+    use hyper::{Body, Request, Response, StatusCode, header::USER_AGENT, http::uri::Scheme};
     use regex_lite::Regex;
 
     fn make_request(host: &str, uri: &str) -> Request<Body> {
@@ -164,18 +208,21 @@ mod tests {
                 source: Regex::new(r"/source1$").unwrap(),
                 destination: "/destination1".into(),
                 kind: StatusCode::FOUND,
+                conditions: Default::default(),
             },
             Redirects {
                 host: Some("example.com".into()),
                 source: Regex::new(r"/source2$").unwrap(),
                 destination: "/destination2".into(),
                 kind: StatusCode::MOVED_PERMANENTLY,
+                conditions: Default::default(),
             },
             Redirects {
                 host: Some("example.info".into()),
                 source: Regex::new(r"/(prefix/)?(source3)/(.*)").unwrap(),
                 destination: "/destination3/$2/$3".into(),
                 kind: StatusCode::MOVED_PERMANENTLY,
+                conditions: Default::default(),
             },
         ]
     }
@@ -295,4 +342,57 @@ mod tests {
             ))
         );
     }
+
+    #[test]
+    fn test_replace_placeholders() {
+        let regex = Regex::new(r"^/blog/(\d{4})/(.+)$").unwrap();
+        assert_eq!(
+            replace_placeholders("/blog/2023/hello", &regex, "/posts/$1/$2?from=$0").unwrap(),
+            "/posts/2023/hello?from=/blog/2023/hello"
+        );
+        assert!(replace_placeholders("/blog/hello", &regex, "/posts/$1").is_err());
+
+        let regex = Regex::new(r"^/(a)(b)(c)(d)(e)(f)(g)(h)(i)(j)(k)$").unwrap();
+        assert_eq!(
+            replace_placeholders("/abcdefghijk", &regex, "/$11/$10/$1").unwrap(),
+            "/k/j/a"
+        );
+    }
+
+    #[test]
+    fn test_matches_conditions() {
+        let glob = |pattern: &str| globset::Glob::new(pattern).unwrap().compile_matcher();
+        let conditions = RequestConditions {
+            scheme: Some(Scheme::HTTPS),
+            headers: vec![(USER_AGENT, glob("*Mobile*"))],
+            query: vec![("lang".into(), glob("es"))],
+        };
+
+        let mut req = make_request("", "/?lang=es");
+        req.headers_mut()
+            .insert(USER_AGENT, "Mozilla/5.0 Mobile".parse().unwrap());
+        // Plain HTTP unless the service provides the scheme
+        assert!(!matches_conditions(&req, &conditions));
+        req.extensions_mut().insert(Scheme::HTTPS);
+        assert!(matches_conditions(&req, &conditions));
+
+        req.headers_mut()
+            .append(USER_AGENT, "curl/8.0".parse().unwrap());
+        assert!(matches_conditions(&req, &conditions));
+        req.headers_mut().remove(USER_AGENT);
+        assert!(!matches_conditions(&req, &conditions));
+
+        let mut req = make_request("", "/?lang=e%73");
+        req.extensions_mut().insert(Scheme::HTTPS);
+        req.headers_mut()
+            .insert(USER_AGENT, "Mobile".parse().unwrap());
+        assert!(matches_conditions(&req, &conditions));
+        *req.uri_mut() = "/?lang=en".parse().unwrap();
+        assert!(!matches_conditions(&req, &conditions));
+
+        assert!(matches_conditions(
+            &make_request("", "/"),
+            &RequestConditions::default()
+        ));
+    }
 }
@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module to reload the server configuration at runtime without restarting
+//! the server or dropping its connections.
+//!
+
+use arc_swap::ArcSwap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::audit_log::{AuditLog, Trigger};
+use crate::settings::cli::General;
+use crate::{Context, Result, Settings, handler::RequestHandlerOpts, server};
+
+#[cfg(unix)]
+use {crate::systemd, futures_util::stream::StreamExt, signal_hook_tokio::Signals};
+
+/// Server configuration which can be changed at runtime.
+pub(crate) struct RuntimeConfig {
+    /// Request handler options shared with the request handler.
+    pub handler_opts: Arc<ArcSwap<RequestHandlerOpts>>,
+    /// Current `general` settings as JSON.
+    pub general: ArcSwap<serde_json::Value>,
+    /// Audit log of the configuration changes if enabled.
+    pub audit_log: Option<AuditLog>,
+}
+
+impl RuntimeConfig {
+    /// Creates the runtime configuration from the server start up options.
+    pub fn new(handler_opts: RequestHandlerOpts, general: &General) -> Result<Self> {
+        let general_json = general_to_json(general)?;
+        Ok(Self {
+            handler_opts: Arc::new(ArcSwap::from_pointee(handler_opts)),
+            audit_log: AuditLog::new(
+                &general.config_audit_log,
+                general.fsync_policy,
+                &general_json,
+            )?,
+            general: ArcSwap::from_pointee(general_json),
+        })
+    }
+
+    /// Swaps the request handler options with the ones built from the given settings.
+    ///
+    /// Requests in progress keep using the previous options until they are completed.
+    pub fn reload(&self, settings: Settings) -> Result {
+        let opts = server::request_handler_opts(&settings.general, settings.advanced)?;
+        let general = general_to_json(&settings.general)?;
+        self.handler_opts.store(Arc::new(opts));
+        self.general.store(Arc::new(general));
+        Ok(())
+    }
+
+    /// Reads the settings again from the same arguments, environment variables
+    /// and config file used on server start up in order to reload them.
+    /// If they are invalid, the current options are kept.
+    pub fn reload_from_env(&self, trigger: Trigger) -> Result {
+        tracing::info!("reloading the configuration");
+        #[cfg(unix)]
+        systemd::reloading();
+        let result = Settings::get(false).and_then(|settings| self.reload(settings));
+        #[cfg(unix)]
+        systemd::ready();
+        match &result {
+            Ok(()) => tracing::info!("configuration reloaded successfully"),
+            Err(err) => tracing::error!(
+                "unable to reload the configuration, keeping the current one: {:?}",
+                err
+            ),
+        }
+        if let Some(audit_log) = &self.audit_log {
+            audit_log.reload(trigger, &self.general.load(), result.as_ref().err());
+        }
+        result
+    }
+
+    /// Turns the maintenance mode on or off, or toggles it if `enabled` is `None`,
+    /// optionally swapping its HTML file. It returns whether the maintenance mode is enabled.
+    ///
+    /// The change lasts until the next configuration reload.
+    pub fn set_maintenance_mode(
+        &self,
+        enabled: Option<bool>,
+        file: Option<PathBuf>,
+        trigger: Trigger,
+    ) -> bool {
+        let previous = self.handler_opts.rcu(|opts| RequestHandlerOpts {
+            maintenance_mode: enabled.unwrap_or(!opts.maintenance_mode),
+            maintenance_mode_file: file
+                .clone()
+                .unwrap_or_else(|| opts.maintenance_mode_file.clone()),
+            ..RequestHandlerOpts::clone(opts)
+        });
+        let enabled = enabled.unwrap_or(!previous.maintenance_mode);
+        if let Some(audit_log) = &self.audit_log {
+            audit_log.maintenance_mode(trigger, enabled, file.is_some());
+        }
+        enabled
+    }
+}
+
+fn general_to_json(general: &General) -> Result<serde_json::Value> {
+    serde_json::to_value(general).with_context(|| "unable to serialize the general settings")
+}
+
+#[cfg(unix)]
+/// It waits for `SIGHUP` signals in order to reload the configuration.
+pub(crate) async fn wait_for_reload_signals(signals: Signals, config: Arc<RuntimeConfig>) {
+    let mut signals = signals.fuse();
+    while signals.next().await.is_some() {
+        tracing::info!("SIGHUP signal caught");
+        config.reload_from_env(Trigger::Signal).ok();
+    }
+}
+
+#[cfg(unix)]
+/// It waits for `SIGUSR1` signals in order to toggle the maintenance mode.
+pub(crate) async fn wait_for_maintenance_signals(signals: Signals, config: Arc<RuntimeConfig>) {
+    let mut signals = signals.fuse();
+    while signals.next().await.is_some() {
+        let enabled = config.set_maintenance_mode(None, None, Trigger::Signal);
+        tracing::info!("maintenance mode: enabled={} (SIGUSR1)", enabled);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use hyper::{Request, StatusCode};
+    use std::net::SocketAddr;
+
+    use super::RuntimeConfig;
+    use crate::audit_log::Trigger;
+    use crate::testing::fixtures::{REMOTE_ADDR, fixture_settings};
+    use crate::{Settings, handler::RequestHandler, server};
+
+    /// Creates a runtime configuration from the given settings.
+    pub(crate) fn runtime_config(settings: Settings) -> RuntimeConfig {
+        let opts = server::request_handler_opts(&settings.general, settings.advanced).unwrap();
+        RuntimeConfig::new(opts, &settings.general).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reload_swaps_handler_opts() {
+        let config = runtime_config(fixture_settings("toml/charsets.toml"));
+        let req_handler = RequestHandler {
+            opts: config.handler_opts.clone(),
+            access_log: Default::default(),
+        };
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.uri_mut() = "http://localhost/main.css".parse().unwrap();
+        let res = req_handler.handle(&mut req, remote_addr).await.unwrap();
+        assert_ne!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(config.general.load()["root"], "tests/fixtures/public");
+
+        config
+            .reload(fixture_settings("toml/redirects.toml"))
+            .unwrap();
+
+        let mut req = Request::default();
+        *req.uri_mut() = "http://localhost/main.css".parse().unwrap();
+        let res = req_handler.handle(&mut req, remote_addr).await.unwrap();
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(
+            res.headers()["location"],
+            "http://localhost/new-styles/style.css"
+        );
+        assert_eq!(config.general.load()["root"], "docker/public");
+    }
+
+    #[test]
+    fn test_toggle_maintenance_mode() {
+        let config = runtime_config(fixture_settings("toml/redirects.toml"));
+        assert!(config.set_maintenance_mode(None, None, Trigger::Signal));
+        assert!(config.handler_opts.load().maintenance_mode);
+        assert!(!config.set_maintenance_mode(None, None, Trigger::Signal));
+        assert!(!config.handler_opts.load().maintenance_mode);
+
+        // A configuration reload restores the configured maintenance mode
+        config.set_maintenance_mode(Some(true), None, Trigger::ControlApi);
+        config
+            .reload(fixture_settings("toml/redirects.toml"))
+            .unwrap();
+        assert!(!config.handler_opts.load().maintenance_mode);
+    }
+
+    #[tokio::test]
+    async fn test_reload_keeps_opts_on_error() {
+        let config = runtime_config(fixture_settings("toml/redirects.toml"));
+
+        let mut settings = fixture_settings("toml/charsets.toml");
+        settings.general.root = "tests/fixtures/missing-root".into();
+        assert!(config.reload(settings).is_err());
+        assert!(
+            config
+                .handler_opts
+                .load()
+                .root_dir
+                .ends_with("docker/public")
+        );
+        assert_eq!(config.general.load()["root"], "docker/public");
+    }
+}
@@ -0,0 +1,254 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module to reject requests exceeding the configured URI length, headers size or body size limits.
+//!
+
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt, ready};
+use headers::{ContentLength, HeaderMapExt};
+use hyper::body::HttpBody;
+use hyper::header::CONTENT_LENGTH;
+use hyper::{Body, HeaderMap, Request, Response, StatusCode};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::{Error, error_page, handler::RequestHandlerOpts};
+
+/// Initializes the request limits.
+pub(crate) fn init(
+    max_uri_length: usize,
+    max_headers_size: usize,
+    max_body_size: u64,
+    handler_opts: &mut RequestHandlerOpts,
+) {
+    handler_opts.max_uri_length = max_uri_length;
+    handler_opts.max_headers_size = max_headers_size;
+    handler_opts.max_body_size = max_body_size;
+    tracing::info!(
+        "request limits: max_uri_length={max_uri_length}, max_headers_size={max_headers_size}, max_body_size={max_body_size}"
+    );
+}
+
+/// Produces an error response if the request exceeds any of the configured limits.
+pub(crate) async fn pre_process(
+    opts: &RequestHandlerOpts,
+    req: &mut Request<Body>,
+) -> Option<Result<Response<Body>, Error>> {
+    let status = match exceeded_limit(opts, req) {
+        Some(status) => status,
+        None => limit_body(opts.max_body_size, req).await.err()?,
+    };
+
+    Some(error_page::error_response(
+        req.uri(),
+        req.method(),
+        &status,
+        &opts.error_pages,
+    ))
+}
+
+/// Gets the status code for the first limit exceeded by the request if any.
+fn exceeded_limit<T>(opts: &RequestHandlerOpts, req: &Request<T>) -> Option<StatusCode> {
+    if opts.max_uri_length > 0 {
+        let uri_length = req.uri().path_and_query().map_or(0, |p| p.as_str().len());
+        if uri_length > opts.max_uri_length {
+            tracing::debug!("request uri length of {uri_length} bytes exceeds the limit");
+            return Some(StatusCode::URI_TOO_LONG);
+        }
+    }
+
+    if opts.max_headers_size > 0 {
+        let headers_size = headers_size(req.headers());
+        if headers_size > opts.max_headers_size {
+            tracing::debug!("request headers size of {headers_size} bytes exceeds the limit");
+            return Some(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE);
+        }
+    }
+
+    if opts.max_body_size > 0 {
+        let body_size = req.headers().typed_get::<ContentLength>().map(|v| v.0);
+        if body_size.is_some_and(|size| size > opts.max_body_size) {
+            tracing::debug!("request body size exceeds the limit");
+            return Some(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+    }
+
+    None
+}
+
+/// Reads a request body of unknown size (E.g. chunked) through a size-limited stream,
+/// failing with a `413` status code once the limit is exceeded.
+///
+/// The body read is put back into the request, so at most the limit is kept in memory.
+async fn limit_body(max_body_size: u64, req: &mut Request<Body>) -> Result<(), StatusCode> {
+    if max_body_size == 0
+        || req.headers().contains_key(CONTENT_LENGTH)
+        || req.body().is_end_stream()
+    {
+        return Ok(());
+    }
+
+    let mut body = LimitedBody {
+        body: std::mem::take(req.body_mut()),
+        remaining: max_body_size,
+    };
+    let mut buf = Vec::new();
+    while let Some(chunk) = body.next().await {
+        buf.extend_from_slice(&chunk?);
+    }
+    *req.body_mut() = Body::from(buf);
+    Ok(())
+}
+
+/// Stream of a request body failing once its size exceeds the limit.
+struct LimitedBody {
+    body: Body,
+    /// Number of bytes left before exceeding the limit.
+    remaining: u64,
+}
+
+impl Stream for LimitedBody {
+    type Item = Result<Bytes, StatusCode>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        let chunk = match ready!(Pin::new(&mut pin.body).poll_data(cx)) {
+            Some(Ok(chunk)) => chunk,
+            Some(Err(err)) => {
+                tracing::debug!("unable to read the request body: {err}");
+                return Poll::Ready(Some(Err(StatusCode::BAD_REQUEST)));
+            }
+            None => return Poll::Ready(None),
+        };
+        match pin.remaining.checked_sub(chunk.len() as u64) {
+            Some(remaining) => {
+                pin.remaining = remaining;
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            None => {
+                tracing::debug!("request body size exceeds the limit");
+                Poll::Ready(Some(Err(StatusCode::PAYLOAD_TOO_LARGE)))
+            }
+        }
+    }
+}
+
+/// Computes the size of the headers as sent over HTTP/1.1 (E.g. `name: value\r\n`).
+fn headers_size(headers: &HeaderMap) -> usize {
+    headers
+        .iter()
+        .map(|(name, value)| name.as_str().len() + value.len() + 4)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{init, pre_process};
+    use crate::handler::RequestHandlerOpts;
+    use bytes::Bytes;
+    use hyper::{Body, Request, StatusCode};
+
+    fn make_opts(
+        max_uri_length: usize,
+        max_headers_size: usize,
+        max_body_size: u64,
+    ) -> RequestHandlerOpts {
+        let mut opts = RequestHandlerOpts::default();
+        init(max_uri_length, max_headers_size, max_body_size, &mut opts);
+        opts
+    }
+
+    fn make_request(uri: &str, headers: &[(&str, &str)]) -> Request<Body> {
+        let mut builder = Request::builder().uri(uri);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    fn make_chunked_request(chunks: &[&'static str]) -> Request<Body> {
+        let chunks = chunks
+            .iter()
+            .map(|chunk| Ok::<_, std::io::Error>(Bytes::from_static(chunk.as_bytes())))
+            .collect::<Vec<_>>();
+        Request::builder()
+            .uri("/")
+            .header("transfer-encoding", "chunked")
+            .body(Body::wrap_stream(futures_util::stream::iter(chunks)))
+            .unwrap()
+    }
+
+    async fn status(opts: &RequestHandlerOpts, req: &mut Request<Body>) -> Option<StatusCode> {
+        pre_process(opts, req)
+            .await
+            .map(|resp| resp.unwrap().status())
+    }
+
+    #[tokio::test]
+    async fn test_limits_disabled() {
+        let opts = make_opts(0, 0, 0);
+        let mut req = make_request(
+            &format!("/{}", "a".repeat(10_000)),
+            &[("content-length", "1000000")],
+        );
+        assert_eq!(status(&opts, &mut req).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_max_uri_length() {
+        let opts = make_opts(16, 0, 0);
+        let mut req = make_request("/index.html?a=1", &[]);
+        assert_eq!(status(&opts, &mut req).await, None);
+        let mut req = make_request("/index.html?a=1234", &[]);
+        assert_eq!(
+            status(&opts, &mut req).await,
+            Some(StatusCode::URI_TOO_LONG)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_max_headers_size() {
+        let opts = make_opts(0, 32, 0);
+        let mut req = make_request("/", &[("x-a", "b")]);
+        assert_eq!(status(&opts, &mut req).await, None);
+        let mut req = make_request("/", &[("x-a", &"b".repeat(32))]);
+        assert_eq!(
+            status(&opts, &mut req).await,
+            Some(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_max_body_size() {
+        let opts = make_opts(0, 0, 1024);
+        let mut req = make_request("/", &[]);
+        assert_eq!(status(&opts, &mut req).await, None);
+        let mut req = make_request("/", &[("content-length", "1024")]);
+        assert_eq!(status(&opts, &mut req).await, None);
+        let mut req = make_request("/", &[("content-length", "1025")]);
+        assert_eq!(
+            status(&opts, &mut req).await,
+            Some(StatusCode::PAYLOAD_TOO_LARGE)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_max_body_size_chunked() {
+        let opts = make_opts(0, 0, 8);
+
+        // The body within the limit is kept
+        let mut req = make_chunked_request(&["abcd", "efgh"]);
+        assert_eq!(status(&opts, &mut req).await, None);
+        let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+        assert_eq!(body, "abcdefgh");
+
+        let mut req = make_chunked_request(&["abcd", "efgh", "i"]);
+        assert_eq!(
+            status(&opts, &mut req).await,
+            Some(StatusCode::PAYLOAD_TOO_LARGE)
+        );
+    }
+}
@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module to handle the request targets which are meant for proxies instead of being file paths.
+//!
+//! The `CONNECT` method (authority-form) is rejected with a `405` status since it is commonly
+//! sent by scanners looking for open proxies.
+//! The absolute-form of HTTP/1.x requests (E.g. `GET http://example.com/ HTTP/1.1`) is served
+//! by its path and authority as required by RFC 7230, unless its rejection with a `400` status
+//! is enabled via the `reject-absolute-form` option.
+//!
+
+use headers::HeaderMapExt;
+use hyper::{
+    Body, Method, Request, Response, StatusCode, Version,
+    header::{HOST, HeaderValue},
+};
+use std::net::SocketAddr;
+
+use crate::http_ext::HTTP_SUPPORTED_METHODS;
+use crate::{Error, error_page, handler::RequestHandlerOpts};
+
+/// Produces an error response if the request target is not a path to serve.
+///
+/// The `Host` header of accepted absolute-form requests is replaced by the target authority,
+/// since it takes precedence over the header (RFC 7230, section 5.4).
+pub(crate) fn pre_process<T>(
+    opts: &RequestHandlerOpts,
+    req: &mut Request<T>,
+    remote_addr: Option<SocketAddr>,
+) -> Option<Result<Response<Body>, Error>> {
+    let (form, status) = match target_form(req)? {
+        Form::Authority => ("authority-form", StatusCode::METHOD_NOT_ALLOWED),
+        Form::Absolute if opts.reject_absolute_form => ("absolute-form", StatusCode::BAD_REQUEST),
+        Form::Absolute => {
+            let host = req
+                .uri()
+                .authority()
+                .and_then(|authority| HeaderValue::from_str(authority.as_str()).ok());
+            if let Some(host) = host {
+                req.headers_mut().insert(HOST, host);
+            }
+            return None;
+        }
+    };
+
+    tracing::info!(
+        method = ?req.method(),
+        uri = ?req.uri(),
+        request_target = form,
+        remote_addr = remote_addr
+            .filter(|_| opts.log_remote_address)
+            .map(tracing::field::display),
+        "request target rejected"
+    );
+
+    let result = error_page::error_response(req.uri(), req.method(), &status, &opts.error_pages);
+    Some(result.map(|mut resp| {
+        if status == StatusCode::METHOD_NOT_ALLOWED {
+            resp.headers_mut()
+                .typed_insert(headers::Allow::from_iter(HTTP_SUPPORTED_METHODS.clone()));
+        }
+        resp
+    }))
+}
+
+/// Request target forms meant for proxies.
+enum Form {
+    /// The `CONNECT` method target. E.g. `example.com:443`
+    Authority,
+    /// An absolute URL target of HTTP/1.x requests. E.g. `http://example.com/`
+    Absolute,
+}
+
+/// Gets the form of the request target if it is meant for proxies.
+fn target_form<T>(req: &Request<T>) -> Option<Form> {
+    if req.method() == Method::CONNECT {
+        return Some(Form::Authority);
+    }
+
+    // NOTE: the URI of HTTP/2 requests always contains the scheme and authority
+    let http1 = matches!(
+        req.version(),
+        Version::HTTP_09 | Version::HTTP_10 | Version::HTTP_11
+    );
+    if http1 && req.uri().scheme().is_some() {
+        return Some(Form::Absolute);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pre_process;
+    use crate::handler::RequestHandlerOpts;
+    use hyper::{
+        Body, Method, Request, StatusCode, Version,
+        header::{HOST, HeaderValue},
+    };
+
+    fn make_request(method: Method, uri: &str, version: Version) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .version(version)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_connect() {
+        let opts = RequestHandlerOpts::default();
+        for version in [Version::HTTP_11, Version::HTTP_2] {
+            let mut req = make_request(Method::CONNECT, "example.com:443", version);
+            let resp = pre_process(&opts, &mut req, None).unwrap().unwrap();
+            assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+            assert_eq!(resp.headers()["allow"], "OPTIONS, HEAD, GET");
+        }
+    }
+
+    #[test]
+    fn test_absolute_form() {
+        let opts = RequestHandlerOpts::default();
+        for version in [Version::HTTP_10, Version::HTTP_11] {
+            let mut req = make_request(Method::GET, "http://example.com/index.html", version);
+            req.headers_mut()
+                .insert(HOST, HeaderValue::from_static("localhost"));
+            assert!(pre_process(&opts, &mut req, None).is_none());
+            assert_eq!(req.uri().path(), "/index.html");
+            assert_eq!(req.headers()[HOST], "example.com");
+        }
+
+        // HTTP/2 requests always contain the scheme and authority
+        let mut req = make_request(Method::GET, "https://example.com/", Version::HTTP_2);
+        assert!(pre_process(&opts, &mut req, None).is_none());
+    }
+
+    #[test]
+    fn test_absolute_form_rejected() {
+        let opts = RequestHandlerOpts {
+            reject_absolute_form: true,
+            ..Default::default()
+        };
+        for version in [Version::HTTP_10, Version::HTTP_11] {
+            let mut req = make_request(Method::GET, "http://example.com/index.html", version);
+            let resp = pre_process(&opts, &mut req, None).unwrap().unwrap();
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+            assert!(resp.headers().get("allow").is_none());
+        }
+
+        // HTTP/2 requests always contain the scheme and authority
+        let mut req = make_request(Method::GET, "https://example.com/", Version::HTTP_2);
+        assert!(pre_process(&opts, &mut req, None).is_none());
+    }
+
+    #[test]
+    fn test_origin_form() {
+        let opts = RequestHandlerOpts::default();
+        let mut req = make_request(Method::GET, "/index.html?a=1", Version::HTTP_11);
+        assert!(pre_process(&opts, &mut req, None).is_none());
+        let mut req = make_request(Method::OPTIONS, "*", Version::HTTP_11);
+        assert!(pre_process(&opts, &mut req, None).is_none());
+    }
+}
@@ -9,14 +9,24 @@
 use headers::{
     AcceptRanges, ContentLength, ContentRange, ContentType, HeaderMapExt, LastModified, Range,
 };
-use hyper::{Body, Response, StatusCode};
+use hyper::{Body, Response, StatusCode, header::CONTENT_TYPE};
 use std::fs::{File, Metadata};
-use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Take};
 use std::ops::Bound;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use crate::chunk_cache::{ChunkCacheOpts, ChunkCacheStream};
 use crate::conditional_headers::{ConditionalBody, ConditionalHeaders};
 use crate::fs::stream::{FileStream, optimal_buf_size};
+use crate::fs::vfs::{Filesystem, VfsMetadata};
+use crate::type_map::TypeMap;
+use crate::zero_copy::ZeroCopyFile;
+
+#[cfg(feature = "mmap")]
+use crate::fs::mmap;
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+use crate::fs::uring;
 
 #[cfg(feature = "experimental")]
 use {
@@ -29,12 +39,16 @@ use {
 
 /// It converts a file object into a corresponding HTTP response or
 /// returns an error holding an HTTP status code otherwise.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn response_body(
     mut file: File,
     path: &PathBuf,
     meta: &Metadata,
     conditionals: ConditionalHeaders,
     #[cfg(feature = "experimental")] memory_cache: Option<&MemCacheOpts>,
+    chunk_cache: Option<&ChunkCacheOpts>,
+    #[cfg(feature = "mmap")] mmap_max_size: u64,
+    type_map: Option<&TypeMap>,
 ) -> Result<Response<Body>, StatusCode> {
     let mut len = meta.len();
     // If the file's modified time is the UNIX epoch, then it's likely not valid and should
@@ -52,61 +66,105 @@ pub(crate) fn response_body(
 
             bytes_range(range, len)
                 .map(|(start, end)| {
-                    match file.seek(SeekFrom::Start(start)) {
-                        Ok(_) => (),
-                        Err(err) => {
-                            tracing::error!("seek file from start error: {:?}", err);
-                            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-                        }
+                    let sub_len = end - start;
+                    let type_info = type_map.and_then(|map| map.get(path));
+                    let mime = match type_info {
+                        Some(info) => info.mime.clone(),
+                        None => mime_guess::from_path(path).first_or_octet_stream(),
                     };
+                    let content_type = ContentType::from(mime);
 
-                    let sub_len = end - start;
-                    let reader = BufReader::new(file).take(sub_len);
+                    let (body, zero_copy) = match chunk_cache {
+                        // Serve the large files by blocks through the chunk cache
+                        Some(chunk_opts) if len >= chunk_opts.min_file_size => {
+                            let body = Body::wrap_stream(ChunkCacheStream::new(
+                                file, path, meta, chunk_opts, start, end,
+                            ));
+                            (body, None)
+                        }
+                        _ => {
+                        match file.seek(SeekFrom::Start(start)) {
+                            Ok(_) => (),
+                            Err(err) => {
+                                tracing::error!("seek file from start error: {:?}", err);
+                                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                            }
+                        };
 
-                    let mime = mime_guess::from_path(path).first_or_octet_stream();
-                    let content_type = ContentType::from(mime);
+                        // Large files not added to the in-memory cache can be sent with zero-copy
+                        #[cfg(feature = "experimental")]
+                        let zero_copy = match memory_cache {
+                            Some(mem_cache_opts) if len <= mem_cache_opts.max_file_size => None,
+                            _ => ZeroCopyFile::new(&file, start, sub_len),
+                        };
+                        #[cfg(not(feature = "experimental"))]
+                        let zero_copy = ZeroCopyFile::new(&file, start, sub_len);
 
-                    // Add the file to the in-memory cache only under these conditions:
-                    // - if the feature is enabled and
-                    // - if the file size does not exceed the maximum permitted and
-                    // - if the file is not found in the cache store
-                    // TODO: make this a feature
-                    #[cfg(feature = "experimental")]
-                    let body = match memory_cache {
-                        // Cache the file only if does not exceed the max size
-                        Some(mem_cache_opts) if len <= mem_cache_opts.max_file_size => {
-                            match path.to_str() {
-                                Some(path_str) => {
-                                    let content_type = content_type.clone();
-                                    let file_path = path_str.to_owned();
-
-                                    let mem_buf = Some(BytesMut::with_capacity(len as usize));
-                                    let mem_opts = Some(MemFileTempOpts::new(
-                                        file_path,
-                                        content_type,
-                                        modified,
-                                    ));
-                                    tracing::debug!(
-                                        "preparing `{}` to be inserted in-memory cache store",
-                                        path_str,
-                                    );
-                                    Body::wrap_stream(MemCacheFileStream {
+                        let reader = BufReader::new(file).take(sub_len);
+
+                        // Add the file to the in-memory cache only under these conditions:
+                        // - if the feature is enabled and
+                        // - if the file size does not exceed the maximum permitted and
+                        // - if the file is not found in the cache store
+                        // TODO: make this a feature
+                        #[cfg(feature = "experimental")]
+                        let body = match memory_cache {
+                            // Cache the file only if does not exceed the max size
+                            Some(mem_cache_opts) if len <= mem_cache_opts.max_file_size => {
+                                match path.to_str() {
+                                    Some(path_str) => {
+                                        let content_type = content_type.clone();
+                                        let file_path = path_str.to_owned();
+
+                                        let mem_buf = Some(BytesMut::with_capacity(len as usize));
+                                        let mem_opts = Some(MemFileTempOpts::new(
+                                            file_path,
+                                            content_type,
+                                            modified,
+                                        ));
+                                        tracing::debug!(
+                                            "preparing `{}` to be inserted in-memory cache store",
+                                            path_str,
+                                        );
+                                        Body::wrap_stream(MemCacheFileStream {
+                                            reader,
+                                            buf_size,
+                                            mem_opts,
+                                            mem_buf,
+                                        })
+                                    }
+                                    _ => file_body(
                                         reader,
                                         buf_size,
-                                        mem_opts,
-                                        mem_buf,
-                                    })
+                                        #[cfg(feature = "mmap")]
+                                        mmap_max_size,
+                                    ),
                                 }
-                                _ => Body::wrap_stream(FileStream { reader, buf_size }),
                             }
+                            _ => file_body(
+                                        reader,
+                                        buf_size,
+                                        #[cfg(feature = "mmap")]
+                                        mmap_max_size,
+                                    ),
+                        };
+
+                        #[cfg(not(feature = "experimental"))]
+                        let body = file_body(
+                                        reader,
+                                        buf_size,
+                                        #[cfg(feature = "mmap")]
+                                        mmap_max_size,
+                                    );
+
+                            (body, zero_copy)
                         }
-                        _ => Body::wrap_stream(FileStream { reader, buf_size }),
                     };
 
-                    #[cfg(not(feature = "experimental"))]
-                    let body = Body::wrap_stream(FileStream { reader, buf_size });
-
                     let mut resp = Response::new(body);
+                    if let Some(zero_copy) = zero_copy {
+                        resp.extensions_mut().insert(zero_copy);
+                    }
 
                     if sub_len != len {
                         *resp.status_mut() = StatusCode::PARTIAL_CONTENT;
@@ -128,7 +186,14 @@ pub(crate) fn response_body(
                     }
 
                     resp.headers_mut().typed_insert(ContentLength(len));
-                    resp.headers_mut().typed_insert(content_type);
+                    match type_info {
+                        Some(info) => {
+                            resp.headers_mut()
+                                .insert(CONTENT_TYPE, info.content_type.clone());
+                            resp.extensions_mut().insert(info.clone());
+                        }
+                        None => resp.headers_mut().typed_insert(content_type),
+                    }
                     resp.headers_mut().typed_insert(AcceptRanges::bytes());
 
                     if let Some(last_modified) = modified {
@@ -149,6 +214,100 @@ pub(crate) fn response_body(
     }
 }
 
+/// Creates the body of a file read from the current position of its reader.
+fn file_body(
+    reader: Take<BufReader<File>>,
+    buf_size: usize,
+    #[cfg(feature = "mmap")] mmap_max_size: u64,
+) -> Body {
+    #[cfg(feature = "mmap")]
+    if let Some(body) = mmap::file_body(reader.get_ref().get_ref(), reader.limit(), mmap_max_size) {
+        return body;
+    }
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    if uring::is_enabled() {
+        // Nothing was buffered yet, so the reader is positioned at the file position
+        let mut file = reader.get_ref().get_ref();
+        if let Ok(offset) = file.stream_position() {
+            let len = reader.limit();
+            return uring::file_body(reader.into_inner().into_inner(), offset, len, buf_size);
+        }
+    }
+    Body::wrap_stream(FileStream { reader, buf_size })
+}
+
+/// It converts a file of a virtual file system (E.g. an embedded asset)
+/// into a corresponding HTTP response or returns an error holding an HTTP status code otherwise.
+pub(crate) async fn vfs_body(
+    fs: &dyn Filesystem,
+    vfs_path: &str,
+    path: &Path,
+    meta: &VfsMetadata,
+    conditionals: ConditionalHeaders,
+) -> Result<Response<Body>, StatusCode> {
+    let len = meta.len;
+    let modified = meta
+        .modified
+        .filter(|&t| t != std::time::UNIX_EPOCH)
+        .map(LastModified::from);
+
+    let range = match conditionals.check(modified) {
+        ConditionalBody::NoBody(resp) => return Ok(resp),
+        ConditionalBody::WithBody(range) => range,
+    };
+    let content_range = bytes_range(range, len)
+        .ok()
+        .and_then(|(start, end)| Some((start, end, ContentRange::bytes(start..end, len).ok()?)));
+    let Some((start, end, content_range)) = content_range else {
+        let mut resp = Response::new(Body::empty());
+        *resp.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+        resp.headers_mut()
+            .typed_insert(ContentRange::unsatisfied_bytes(len));
+        return Ok(resp);
+    };
+
+    // The whole file is streamed while a range of it is read at once
+    let body = if end - start == len {
+        fs.open(vfs_path).await.map(Body::wrap_stream)
+    } else {
+        fs.read_range(vfs_path, start..end).await.map(Body::from)
+    }
+    .map_err(|err| io_error_status(&err, path))?;
+
+    let mut resp = Response::new(body);
+    if end - start != len {
+        *resp.status_mut() = StatusCode::PARTIAL_CONTENT;
+        resp.headers_mut().typed_insert(content_range);
+    }
+    resp.headers_mut().typed_insert(ContentLength(end - start));
+    resp.headers_mut().typed_insert(ContentType::from(
+        mime_guess::from_path(path).first_or_octet_stream(),
+    ));
+    resp.headers_mut().typed_insert(AcceptRanges::bytes());
+    if let Some(last_modified) = modified {
+        resp.headers_mut().typed_insert(last_modified);
+    }
+    Ok(resp)
+}
+
+/// Maps an I/O error accessing a file to its corresponding HTTP status code.
+pub(crate) fn io_error_status(err: &io::Error, path: &Path) -> StatusCode {
+    match err.kind() {
+        io::ErrorKind::NotFound => {
+            tracing::debug!("file can't be opened or not found: {:?}", path.display());
+            StatusCode::NOT_FOUND
+        }
+        io::ErrorKind::PermissionDenied => {
+            tracing::warn!("file permission denied: {:?}", path.display());
+            StatusCode::FORBIDDEN
+        }
+        _ => {
+            tracing::error!("file open error (path={:?}): {} ", path.display(), err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
 pub(crate) struct BadRangeError;
 
 /// It handles the `Range` header returning the corresponding start/end-range bytes
@@ -12,72 +12,93 @@ use hyper::{Body, Request, Response, StatusCode, Uri, header::HOST};
 use crate::{
     Error,
     handler::RequestHandlerOpts,
-    redirects::{handle_error, replace_placeholders},
+    redirects::{handle_error, matches_conditions, replace_placeholders, request_host},
     settings::{Rewrites, file::RedirectsKind},
 };
 
+/// Maximum number of chained rewrites applied to a request, preventing rewrite loops.
+const MAX_CHAINED_REWRITES: usize = 10;
+
 /// Applies rewrite rules to a request if necessary.
+/// The rewritten URL of a chained rewrite is matched against the rewrite rules again.
 pub(crate) fn pre_process<T>(
     opts: &RequestHandlerOpts,
     req: &mut Request<T>,
 ) -> Option<Result<Response<Body>, Error>> {
     let rewrites = opts.advanced_opts.as_ref()?.rewrites.as_deref()?;
-    let uri_path = req.uri().path();
 
-    let matched = rewrite_uri_path(uri_path, Some(rewrites))?;
-    let dest = match replace_placeholders(uri_path, &matched.source, &matched.destination) {
-        Ok(dest) => dest,
-        Err(err) => return handle_error(err, opts, req),
-    };
+    for _ in 0..=MAX_CHAINED_REWRITES {
+        let matched = rewrite_uri_path(req, Some(rewrites))?;
+        let dest =
+            match replace_placeholders(req.uri().path(), &matched.source, &matched.destination) {
+                Ok(dest) => dest,
+                Err(err) => return handle_error(err, opts, req),
+            };
 
-    if let Some(redirect_type) = &matched.redirect {
-        // Handle redirects
-        let loc = match HeaderValue::from_str(&dest) {
-            Ok(val) => val,
-            Err(err) => {
-                return handle_error(
-                    Error::new(err).context("invalid header value from current uri"),
-                    opts,
-                    req,
-                );
-            }
-        };
-        let mut resp = Response::new(Body::empty());
-        resp.headers_mut().insert(hyper::header::LOCATION, loc);
-        *resp.status_mut() = match redirect_type {
-            RedirectsKind::Permanent => StatusCode::MOVED_PERMANENTLY,
-            RedirectsKind::Temporary => StatusCode::FOUND,
-        };
-        Some(Ok(resp))
-    } else {
-        // Handle internal rewrites
-        *req.uri_mut() = match merge_uris(req.uri(), &dest) {
-            Ok(uri) => uri,
-            Err(err) => {
-                return handle_error(
-                    err.context("invalid rewrite target from current uri"),
+        if let Some(redirect_type) = &matched.redirect {
+            return match redirect(&dest, redirect_type) {
+                Ok(resp) => Some(Ok(resp)),
+                Err(err) => handle_error(
+                    err.context("invalid header value from current uri"),
                     opts,
                     req,
-                );
-            }
-        };
+                ),
+            };
+        }
 
-        // Adjust Host header to allow rewriting to a different virtual host
-        if let Some(host) = req.uri().host() {
-            let mut host = host.to_owned();
-            if let Some(port) = req.uri().port_u16() {
-                host.push_str(&format!(":{port}"));
-            }
-            if let Ok(host) = host.parse() {
-                req.headers_mut().insert(HOST, host);
-            }
+        // Handle internal rewrites
+        if let Err(err) = rewrite_request(req, &dest, matched.append_query) {
+            return handle_error(
+                err.context("invalid rewrite target from current uri"),
+                opts,
+                req,
+            );
+        }
+        if !matched.chain {
+            return None;
         }
+        tracing::debug!("url rewrite chained to: {}", req.uri());
+    }
+
+    handle_error(
+        Error::msg(format!(
+            "url rewrites of {} exceeded the limit of {MAX_CHAINED_REWRITES} chained rewrites",
+            req.uri()
+        )),
+        opts,
+        req,
+    )
+}
+
+fn redirect(dest: &str, redirect_type: &RedirectsKind) -> Result<Response<Body>, Error> {
+    let loc = HeaderValue::from_str(dest)?;
+    let mut resp = Response::new(Body::empty());
+    resp.headers_mut().insert(hyper::header::LOCATION, loc);
+    *resp.status_mut() = match redirect_type {
+        RedirectsKind::Permanent => StatusCode::MOVED_PERMANENTLY,
+        RedirectsKind::Temporary => StatusCode::FOUND,
+    };
+    Ok(resp)
+}
+
+/// Rewrites the request URI and its Host header to the destination.
+fn rewrite_request<T>(req: &mut Request<T>, dest: &str, append_query: bool) -> Result<(), Error> {
+    *req.uri_mut() = merge_uris(req.uri(), dest, append_query)?;
 
-        None
+    // Adjust Host header to allow rewriting to a different virtual host
+    if let Some(host) = req.uri().host() {
+        let mut host = host.to_owned();
+        if let Some(port) = req.uri().port_u16() {
+            host.push_str(&format!(":{port}"));
+        }
+        if let Ok(host) = host.parse() {
+            req.headers_mut().insert(HOST, host);
+        }
     }
+    Ok(())
 }
 
-fn merge_uris(orig_uri: &Uri, new_uri: &str) -> Result<Uri, Error> {
+fn merge_uris(orig_uri: &Uri, new_uri: &str, append_query: bool) -> Result<Uri, Error> {
     let mut parts = new_uri.parse::<Uri>()?.into_parts();
     if parts.scheme.is_none() {
         parts.scheme = orig_uri.scheme().cloned();
@@ -89,26 +110,47 @@ fn merge_uris(orig_uri: &Uri, new_uri: &str) -> Result<Uri, Error> {
         parts.path_and_query = orig_uri.path_and_query().cloned();
     }
     if let Some(path_and_query) = &mut parts.path_and_query {
-        if let (None, Some(query)) = (path_and_query.query(), orig_uri.query()) {
-            *path_and_query = [path_and_query.as_str(), "?", query]
-                .into_iter()
-                .collect::<String>()
-                .parse()?;
+        match (path_and_query.query(), orig_uri.query()) {
+            (None, Some(query)) => {
+                *path_and_query = [path_and_query.as_str(), "?", query]
+                    .into_iter()
+                    .collect::<String>()
+                    .parse()?;
+            }
+            (Some(dest_query), Some(query)) if append_query && !query.is_empty() => {
+                let separator = if dest_query.is_empty() { "" } else { "&" };
+                *path_and_query = [path_and_query.as_str(), separator, query]
+                    .into_iter()
+                    .collect::<String>()
+                    .parse()?;
+            }
+            _ => {}
         }
     }
     Ok(Uri::from_parts(parts)?)
 }
 
-/// It returns a rewrite's destination path if the current request uri
+/// It returns a rewrite's destination path if the current request
 /// matches against the provided rewrites array.
-pub fn rewrite_uri_path<'a>(
-    uri_path: &'a str,
+pub fn rewrite_uri_path<'a, T>(
+    req: &Request<T>,
     rewrites_opts: Option<&'a [Rewrites]>,
 ) -> Option<&'a Rewrites> {
+    let uri_host = request_host(req);
+    let uri_path = req.uri().path();
     if let Some(rewrites_vec) = rewrites_opts {
         for rewrites_entry in rewrites_vec {
+            // Match `host` rewrite against the request host if specified
+            if let Some(host) = &rewrites_entry.host {
+                if *host != uri_host {
+                    continue;
+                }
+            }
+
             // Match source glob pattern against request uri path
-            if rewrites_entry.source.is_match(uri_path) {
+            if rewrites_entry.source.is_match(uri_path)
+                && matches_conditions(req, &rewrites_entry.conditions)
+            {
                 return Some(rewrites_entry);
             }
         }
@@ -139,28 +181,66 @@ mod tests {
     fn get_rewrites() -> Vec<Rewrites> {
         vec![
             Rewrites {
+                host: None,
                 source: Regex::new(r"/source1$").unwrap(),
                 destination: "/destination1".into(),
                 redirect: None,
+                conditions: Default::default(),
+                chain: false,
+                append_query: false,
             },
             Rewrites {
+                host: None,
                 source: Regex::new(r"/source2$").unwrap(),
                 destination: "/destination2".into(),
                 redirect: Some(RedirectsKind::Temporary),
+                conditions: Default::default(),
+                chain: false,
+                append_query: false,
             },
             Rewrites {
+                host: None,
                 source: Regex::new(r"/(prefix/)?(source3)/(.*)").unwrap(),
                 destination: "/destination3/$2/$3".into(),
                 redirect: Some(RedirectsKind::Permanent),
+                conditions: Default::default(),
+                chain: false,
+                append_query: false,
             },
             Rewrites {
+                host: None,
                 source: Regex::new(r"/(source4)/(.*)").unwrap(),
                 destination: "http://example.net:1234/destination4/$1?$2".into(),
                 redirect: None,
+                conditions: Default::default(),
+                chain: false,
+                append_query: false,
             },
         ]
     }
 
+    fn make_rewrite(source: &str, destination: &str, chain: bool) -> Rewrites {
+        Rewrites {
+            host: None,
+            source: Regex::new(source).unwrap(),
+            destination: destination.into(),
+            redirect: None,
+            conditions: Default::default(),
+            chain,
+            append_query: false,
+        }
+    }
+
+    fn make_opts(rewrites: Vec<Rewrites>) -> RequestHandlerOpts {
+        RequestHandlerOpts {
+            advanced_opts: Some(Advanced {
+                rewrites: Some(rewrites),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
     fn is_redirect(result: Option<Result<Response<Body>, Error>>) -> Option<(StatusCode, String)> {
         if let Some(Ok(response)) = result {
             let location = response.headers().get("Location")?.to_str().unwrap().into();
@@ -298,4 +378,79 @@ mod tests {
             "example.net:1234"
         );
     }
+
+    #[test]
+    fn test_chain() {
+        let opts = make_opts(vec![
+            make_rewrite("^/docs/(.+)$", "/site/docs/$1", false),
+            make_rewrite("^/v[0-9]+/(.+)$", "/$1", true),
+            make_rewrite("^/app/.*$", "/index.html", false),
+        ]);
+
+        // Chained rewrites are matched against all the rewrites again
+        let mut req = make_request("", "/v2/docs/guide.html?a=1");
+        assert!(pre_process(&opts, &mut req).is_none());
+        assert_eq!(req.uri(), "/site/docs/guide.html?a=1");
+
+        let mut req = make_request("", "/v2/app/settings");
+        assert!(pre_process(&opts, &mut req).is_none());
+        assert_eq!(req.uri(), "/index.html");
+
+        // The rewritten URL is kept if no other rewrite matches
+        let mut req = make_request("", "/v2/other.html");
+        assert!(pre_process(&opts, &mut req).is_none());
+        assert_eq!(req.uri(), "/other.html");
+
+        // Not chained rewrites are applied once
+        let opts = make_opts(vec![
+            make_rewrite("^/v[0-9]+/(.+)$", "/$1", false),
+            make_rewrite("^/app/.*$", "/index.html", false),
+        ]);
+        let mut req = make_request("", "/v2/app/settings");
+        assert!(pre_process(&opts, &mut req).is_none());
+        assert_eq!(req.uri(), "/app/settings");
+    }
+
+    #[test]
+    fn test_chain_loop() {
+        let opts = make_opts(vec![
+            make_rewrite("^/a$", "/b", true),
+            make_rewrite("^/b$", "/a", true),
+        ]);
+        let mut req = make_request("", "/a");
+        let resp = pre_process(&opts, &mut req).unwrap().unwrap();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_query_string() {
+        let mut rewrites = vec![
+            make_rewrite("^/search/(.+)$", "/search.html?q=$1", false),
+            make_rewrite("^/clear/(.+)$", "/$1?", false),
+        ];
+        let opts = make_opts(rewrites.clone());
+
+        // The destination query string replaces the request one
+        let mut req = make_request("", "/search/sws?page=2");
+        assert!(pre_process(&opts, &mut req).is_none());
+        assert_eq!(req.uri(), "/search.html?q=sws");
+
+        let mut req = make_request("", "/clear/index.html?page=2");
+        assert!(pre_process(&opts, &mut req).is_none());
+        assert_eq!(req.uri().path(), "/index.html");
+        assert_eq!(req.uri().query(), Some(""));
+
+        // Or gets appended to it
+        for rewrite in &mut rewrites {
+            rewrite.append_query = true;
+        }
+        let opts = make_opts(rewrites);
+        let mut req = make_request("", "/search/sws?page=2");
+        assert!(pre_process(&opts, &mut req).is_none());
+        assert_eq!(req.uri(), "/search.html?q=sws&page=2");
+
+        let mut req = make_request("", "/clear/index.html?page=2");
+        assert!(pre_process(&opts, &mut req).is_none());
+        assert_eq!(req.uri(), "/index.html?page=2");
+    }
 }
@@ -5,17 +5,42 @@
 
 //! The module provides several HTTP security headers support.
 //!
+//! The default set of headers can be replaced by a profile defined
+//! in the `[advanced.security]` section.
+//!
 
 use http::header::{
-    CONTENT_SECURITY_POLICY, STRICT_TRANSPORT_SECURITY, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+    CONTENT_SECURITY_POLICY, HeaderName, REFERRER_POLICY, STRICT_TRANSPORT_SECURITY,
+    X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
 };
-use hyper::{Body, Request, Response};
+use hyper::{Body, HeaderMap, Request, Response, header::HeaderValue};
+
+use crate::{Context, Error, Result, handler::RequestHandlerOpts, settings::file};
+
+// Default security headers values
+const DEFAULT_HSTS: &str = "max-age=63072000; includeSubDomains; preload";
+const DEFAULT_FRAME_OPTIONS: &str = "DENY";
+const DEFAULT_CONTENT_TYPE_OPTIONS: &str = "nosniff";
+const DEFAULT_CONTENT_SECURITY_POLICY: &str = "frame-ancestors 'self'";
 
-use crate::{Error, handler::RequestHandlerOpts};
+/// `Permissions-Policy` header name, not provided by the `http` crate.
+static PERMISSIONS_POLICY: HeaderName = HeaderName::from_static("permissions-policy");
 
 pub(crate) fn init(enabled: bool, handler_opts: &mut RequestHandlerOpts) {
     handler_opts.security_headers = enabled;
-    tracing::info!("security headers: enabled={enabled}");
+    let profile = handler_opts
+        .advanced_opts
+        .as_ref()
+        .is_some_and(|advanced| advanced.security.is_some());
+    tracing::info!(
+        "security headers: enabled={enabled}, profile={}",
+        if profile { "custom" } else { "default" }
+    );
+    if profile && !enabled {
+        tracing::warn!(
+            "security headers profile is defined but the security headers are disabled, enable them via the `security-headers` option"
+        );
+    }
 }
 
 /// Appends security headers to a response if necessary
@@ -25,33 +50,145 @@ pub(crate) fn post_process<T>(
     mut resp: Response<Body>,
 ) -> Result<Response<Body>, Error> {
     if opts.security_headers {
-        append_headers(&mut resp);
+        let profile = opts
+            .advanced_opts
+            .as_ref()
+            .and_then(|advanced| advanced.security.as_ref());
+        match profile {
+            Some(headers) => {
+                for (name, value) in headers {
+                    resp.headers_mut().insert(name, value.clone());
+                }
+            }
+            None => append_headers(&mut resp),
+        }
     }
     Ok(resp)
 }
 
+/// Builds the headers of a security headers profile.
+///
+/// The headers missing in the profile keep their default values (if any)
+/// and the headers with an empty value are omitted.
+pub(crate) fn profile(security: &file::Security) -> Result<HeaderMap> {
+    let entries = [
+        (
+            &STRICT_TRANSPORT_SECURITY,
+            &security.hsts,
+            Some(DEFAULT_HSTS),
+        ),
+        (
+            &X_FRAME_OPTIONS,
+            &security.frame_options,
+            Some(DEFAULT_FRAME_OPTIONS),
+        ),
+        (
+            &X_CONTENT_TYPE_OPTIONS,
+            &security.content_type_options,
+            Some(DEFAULT_CONTENT_TYPE_OPTIONS),
+        ),
+        (
+            &CONTENT_SECURITY_POLICY,
+            &security.content_security_policy,
+            Some(DEFAULT_CONTENT_SECURITY_POLICY),
+        ),
+        (&REFERRER_POLICY, &security.referrer_policy, None),
+        (&PERMISSIONS_POLICY, &security.permissions_policy, None),
+    ];
+
+    let mut headers = HeaderMap::new();
+    for (name, value, default) in entries {
+        let value = match value.as_deref().or(default) {
+            Some(value) if !value.trim().is_empty() => value.trim(),
+            _ => continue,
+        };
+        let value = HeaderValue::from_str(value)
+            .with_context(|| format!("invalid `{name}` header value: {value}"))?;
+        headers.insert(name.clone(), value);
+    }
+    Ok(headers)
+}
+
 /// It appends security headers like `Strict-Transport-Security: max-age=63072000; includeSubDomains; preload` (2 years max-age),
 ///`X-Frame-Options: DENY` and `Content-Security-Policy: frame-ancestors 'self'`.
 pub fn append_headers(resp: &mut Response<Body>) {
     // Strict-Transport-Security (HSTS)
     resp.headers_mut().insert(
         STRICT_TRANSPORT_SECURITY,
-        "max-age=63072000; includeSubDomains; preload"
-            .parse()
-            .unwrap(),
+        HeaderValue::from_static(DEFAULT_HSTS),
     );
 
     // X-Frame-Options
-    resp.headers_mut()
-        .insert(X_FRAME_OPTIONS, "DENY".parse().unwrap());
+    resp.headers_mut().insert(
+        X_FRAME_OPTIONS,
+        HeaderValue::from_static(DEFAULT_FRAME_OPTIONS),
+    );
 
     // X-Content-Type-Options
-    resp.headers_mut()
-        .insert(X_CONTENT_TYPE_OPTIONS, "nosniff".parse().unwrap());
+    resp.headers_mut().insert(
+        X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static(DEFAULT_CONTENT_TYPE_OPTIONS),
+    );
 
     // Content Security Policy (CSP)
     resp.headers_mut().insert(
         CONTENT_SECURITY_POLICY,
-        "frame-ancestors 'self'".parse().unwrap(),
+        HeaderValue::from_static(DEFAULT_CONTENT_SECURITY_POLICY),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::profile;
+    use crate::settings::file::Security;
+
+    fn security() -> Security {
+        Security {
+            hsts: None,
+            frame_options: None,
+            content_type_options: None,
+            content_security_policy: None,
+            referrer_policy: None,
+            permissions_policy: None,
+        }
+    }
+
+    #[test]
+    fn test_profile_defaults() {
+        let headers = profile(&security()).unwrap();
+        assert_eq!(headers.len(), 4);
+        assert_eq!(
+            headers["strict-transport-security"],
+            "max-age=63072000; includeSubDomains; preload"
+        );
+        assert_eq!(headers["x-frame-options"], "DENY");
+        assert_eq!(headers["x-content-type-options"], "nosniff");
+        assert_eq!(headers["content-security-policy"], "frame-ancestors 'self'");
+    }
+
+    #[test]
+    fn test_profile_custom() {
+        let headers = profile(&Security {
+            hsts: Some("".to_owned()),
+            frame_options: Some("SAMEORIGIN".to_owned()),
+            content_security_policy: Some("default-src 'self'".to_owned()),
+            referrer_policy: Some("no-referrer".to_owned()),
+            permissions_policy: Some("camera=(), microphone=()".to_owned()),
+            ..security()
+        })
+        .unwrap();
+        assert_eq!(headers.len(), 5);
+        assert!(headers.get("strict-transport-security").is_none());
+        assert_eq!(headers["x-frame-options"], "SAMEORIGIN");
+        assert_eq!(headers["x-content-type-options"], "nosniff");
+        assert_eq!(headers["content-security-policy"], "default-src 'self'");
+        assert_eq!(headers["referrer-policy"], "no-referrer");
+        assert_eq!(headers["permissions-policy"], "camera=(), microphone=()");
+
+        let invalid = Security {
+            content_security_policy: Some("default-src\n'self'".to_owned()),
+            ..security()
+        };
+        assert!(profile(&invalid).is_err());
+    }
+}
@@ -0,0 +1,365 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that checks the server end to end with the current configuration.
+//!
+//! The server is started on an ephemeral loopback port and a battery of requests
+//! is issued against it, printing a pass/fail summary which is useful as a smoke test
+//! in deploy pipelines.
+//!
+
+use hyper::client::HttpConnector;
+use hyper::header::{
+    ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_RANGE, HeaderName, LOCATION, RANGE,
+};
+use hyper::{Body, Client, Request, Response, StatusCode};
+use regex_lite::Regex;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::{Context, Result, Server, Settings};
+
+/// Maximum time to wait for the server to accept connections.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum time to wait for the response of a check request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Outcome of a single check.
+#[derive(Debug)]
+enum Outcome {
+    Pass(String),
+    Fail(String),
+    Skip(String),
+}
+
+/// Expected status code of a check request.
+fn expect_status(req: &str, resp: &Response<Body>, expected: StatusCode) -> Outcome {
+    let status = resp.status();
+    if status == expected {
+        Outcome::Pass(format!("{req} -> {status}"))
+    } else {
+        Outcome::Fail(format!("{req} -> {status}, expected {expected}"))
+    }
+}
+
+/// Starts the server with the given settings on an ephemeral loopback port,
+/// checks it end to end and prints a summary of the checks.
+///
+/// It fails if the server can not be started or if any of the checks fails.
+pub fn run(mut opts: Settings) -> Result {
+    let addr = prepare(&mut opts)?;
+    let general = &opts.general;
+
+    // Checks parameters
+    let index_file = general
+        .index_files
+        .split(',')
+        .map(str::trim)
+        .find(|name| !name.is_empty() && general.root.join(name).is_file())
+        .map(|name| name.to_owned());
+    #[cfg(feature = "basic-auth")]
+    let basic_auth = !general.basic_auth.is_empty();
+    #[cfg(not(feature = "basic-auth"))]
+    let basic_auth = false;
+    #[cfg(feature = "fallback-page")]
+    let fallback_page = !general.page_fallback.as_os_str().is_empty();
+    #[cfg(not(feature = "fallback-page"))]
+    let fallback_page = false;
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    let compression = general.compression.then_some(general.compression_min_size);
+    #[cfg(not(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    )))]
+    let compression = None;
+    let root = general.root.clone();
+    let redirect = opts
+        .advanced
+        .as_ref()
+        .and_then(|advanced| advanced.redirects.as_deref())
+        .map(|redirects| {
+            redirects
+                .iter()
+                .filter(|redirect| {
+                    redirect.host.is_none()
+                        && redirect.conditions.scheme.is_none()
+                        && redirect.conditions.headers.is_empty()
+                        && redirect.conditions.query.is_empty()
+                })
+                .find_map(|redirect| Some((sample_path(&redirect.source)?, redirect.kind)))
+        });
+
+    let server =
+        std::thread::spawn(move || Server::new(opts)?.run_server_on_rt(None, || {}, false));
+    wait_for_server(addr, &server)?;
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let client = Client::new();
+    let base = format!("http://{addr}");
+
+    let checks = rt.block_on(async {
+        let mut checks = Vec::new();
+
+        // Index
+        let req = "GET /".to_owned();
+        let outcome = match get(&client, &base, "/", &[]).await {
+            Ok(resp) if basic_auth => expect_status(&req, &resp, StatusCode::UNAUTHORIZED),
+            Ok(resp) if resp.status().is_success() => {
+                Outcome::Pass(format!("{req} -> {}", resp.status()))
+            }
+            Ok(resp) => Outcome::Fail(format!("{req} -> {}, expected 2xx", resp.status())),
+            Err(err) => Outcome::Fail(format!("{req}: {err:#}")),
+        };
+        checks.push(("index", outcome));
+
+        // Not found
+        let path = "/sws-selftest-not-found.html";
+        let req = format!("GET {path}");
+        let outcome = if basic_auth {
+            Outcome::Skip("basic authentication is enabled".to_owned())
+        } else if fallback_page {
+            Outcome::Skip("a fallback page is configured".to_owned())
+        } else {
+            match get(&client, &base, path, &[]).await {
+                Ok(resp) => expect_status(&req, &resp, StatusCode::NOT_FOUND),
+                Err(err) => Outcome::Fail(format!("{req}: {err:#}")),
+            }
+        };
+        checks.push(("not-found", outcome));
+
+        // Byte range
+        let outcome = match &index_file {
+            _ if basic_auth => Outcome::Skip("basic authentication is enabled".to_owned()),
+            None => Outcome::Skip("no index file found in the root directory".to_owned()),
+            Some(name) => {
+                let req = format!("GET /{name} (range: bytes=0-0)");
+                match get(&client, &base, &format!("/{name}"), &[(RANGE, "bytes=0-0")]).await {
+                    Ok(resp) => match expect_status(&req, &resp, StatusCode::PARTIAL_CONTENT) {
+                        Outcome::Pass(_) if !resp.headers().contains_key(CONTENT_RANGE) => {
+                            Outcome::Fail(format!("{req}: missing `content-range` header"))
+                        }
+                        outcome => outcome,
+                    },
+                    Err(err) => Outcome::Fail(format!("{req}: {err:#}")),
+                }
+            }
+        };
+        checks.push(("range", outcome));
+
+        // Compression
+        let outcome = match (&index_file, compression) {
+            _ if basic_auth => Outcome::Skip("basic authentication is enabled".to_owned()),
+            (_, None) => Outcome::Skip("compression is disabled".to_owned()),
+            (None, _) => Outcome::Skip("no index file found in the root directory".to_owned()),
+            (Some(name), Some(min_size)) if file_len(&root.join(name)) < min_size => {
+                Outcome::Skip(format!("index file is smaller than {min_size} bytes"))
+            }
+            (Some(name), _) => {
+                let req = format!("GET /{name} (accept-encoding: gzip, deflate, br, zstd)");
+                let headers = [(ACCEPT_ENCODING, "gzip, deflate, br, zstd")];
+                match get(&client, &base, &format!("/{name}"), &headers).await {
+                    Ok(resp) => match resp.headers().get(CONTENT_ENCODING) {
+                        Some(encoding) => Outcome::Pass(format!(
+                            "{req} -> {}, content-encoding: {}",
+                            resp.status(),
+                            encoding.to_str().unwrap_or_default()
+                        )),
+                        None => Outcome::Fail(format!(
+                            "{req} -> {}, response not compressed",
+                            resp.status()
+                        )),
+                    },
+                    Err(err) => Outcome::Fail(format!("{req}: {err:#}")),
+                }
+            }
+        };
+        checks.push(("compression", outcome));
+
+        // Redirects sample
+        let outcome = match redirect {
+            None => Outcome::Skip("no redirects configured".to_owned()),
+            Some(None) => Outcome::Skip("no redirect source to sample".to_owned()),
+            Some(Some((path, kind))) => {
+                let req = format!("GET {path}");
+                match get(&client, &base, &path, &[]).await {
+                    Ok(resp) => match expect_status(&req, &resp, kind) {
+                        Outcome::Pass(msg) => match resp.headers().get(LOCATION) {
+                            Some(location) => Outcome::Pass(format!(
+                                "{msg}, location: {}",
+                                location.to_str().unwrap_or_default()
+                            )),
+                            None => Outcome::Fail(format!("{req}: missing `location` header")),
+                        },
+                        outcome => outcome,
+                    },
+                    Err(err) => Outcome::Fail(format!("{req}: {err:#}")),
+                }
+            }
+        };
+        checks.push(("redirect", outcome));
+
+        checks
+    });
+
+    // NOTE: the server is not shut down gracefully on purpose since it's
+    // dropped along with the process, so nothing is persisted on its shutdown
+    report(&checks)
+}
+
+/// Adjusts the settings to serve plain HTTP on an ephemeral loopback port
+/// returning its address.
+fn prepare(opts: &mut Settings) -> Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .with_context(|| "unable to find an ephemeral port for the self-test")?;
+    let addr = listener.local_addr()?;
+    drop(listener);
+
+    let general = &mut opts.general;
+    general.host = addr.ip().to_string();
+    general.port = addr.port();
+    general.fd = None;
+    general.quiet = true;
+    general.worker_processes = 0;
+    general.control_listen.clear();
+    #[cfg(feature = "http2")]
+    {
+        general.http2 = false;
+        general.https_redirect = false;
+    }
+    opts.listeners.clear();
+    Ok(addr)
+}
+
+/// Waits until the server accepts connections or fails to start.
+fn wait_for_server(addr: SocketAddr, server: &JoinHandle<Result>) -> Result {
+    let started = Instant::now();
+    while TcpStream::connect(addr).is_err() {
+        if server.is_finished() {
+            bail!("the server failed to start up, see the logs above");
+        }
+        if started.elapsed() > STARTUP_TIMEOUT {
+            bail!("the server did not accept connections within {STARTUP_TIMEOUT:?}");
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    Ok(())
+}
+
+/// Sends a `GET` request to the server.
+async fn get(
+    client: &Client<HttpConnector>,
+    base: &str,
+    path: &str,
+    headers: &[(HeaderName, &str)],
+) -> Result<Response<Body>> {
+    let mut req = Request::get(format!("{base}{path}"));
+    for (name, value) in headers {
+        req = req.header(name, *value);
+    }
+    let resp = tokio::time::timeout(REQUEST_TIMEOUT, client.request(req.body(Body::empty())?))
+        .await
+        .with_context(|| format!("no response within {REQUEST_TIMEOUT:?}"))??;
+    Ok(resp)
+}
+
+/// Returns the size of a file or zero if it can not be read.
+fn file_len(path: &Path) -> u64 {
+    std::fs::metadata(path).map_or(0, |meta| meta.len())
+}
+
+/// Returns a request path matching a redirect source if it only has wildcards.
+fn sample_path(source: &Regex) -> Option<String> {
+    let pattern = source.as_str().strip_prefix('^')?.strip_suffix('$')?;
+    let pattern = pattern
+        .strip_prefix("(?:")
+        .and_then(|pattern| pattern.strip_suffix(')'))
+        .unwrap_or(pattern)
+        .replace("[^/]*", "selftest")
+        .replace(".*", "selftest");
+
+    let mut path = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => path.push(chars.next()?),
+            '.' | '*' | '+' | '?' | '(' | ')' | '[' | ']' | '{' | '}' | '|' | '^' | '$' => {
+                return None;
+            }
+            c => path.push(c),
+        }
+    }
+    (path.starts_with('/') && source.is_match(&path)).then_some(path)
+}
+
+/// Prints the summary of the checks, failing if any of them failed.
+fn report(checks: &[(&str, Outcome)]) -> Result {
+    let (mut passed, mut failed, mut skipped) = (0, 0, 0);
+    for (name, outcome) in checks {
+        let (label, detail) = match outcome {
+            Outcome::Pass(detail) => {
+                passed += 1;
+                ("PASS", detail)
+            }
+            Outcome::Fail(detail) => {
+                failed += 1;
+                ("FAIL", detail)
+            }
+            Outcome::Skip(detail) => {
+                skipped += 1;
+                ("SKIP", detail)
+            }
+        };
+        println!("{label}  {name:<12} {detail}");
+    }
+    println!("selftest: {passed} passed, {failed} failed, {skipped} skipped");
+
+    if failed > 0 {
+        bail!(
+            "selftest failed: {failed} of {} checks failed",
+            checks.len()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sample_path;
+    use regex_lite::Regex;
+
+    fn glob(source: &str) -> Regex {
+        crate::settings::source_regex(source, false, "redirect").unwrap()
+    }
+
+    #[test]
+    fn test_sample_path() {
+        assert_eq!(sample_path(&glob("/old.html")).unwrap(), "/old.html");
+        assert_eq!(
+            sample_path(&glob("/blog/*/index.html")).unwrap(),
+            "/blog/selftest/index.html"
+        );
+        assert_eq!(sample_path(&glob("/docs/**")).unwrap(), "/docs/selftest");
+        assert!(sample_path(&glob("/{a,b}.html")).is_none());
+
+        let regex = crate::settings::source_regex("/posts/\\d+", true, "redirect").unwrap();
+        assert!(sample_path(&regex).is_none());
+        let regex = crate::settings::source_regex("/feed\\.xml", true, "redirect").unwrap();
+        assert_eq!(sample_path(&regex).unwrap(), "/feed.xml");
+    }
+}
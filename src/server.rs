@@ -6,29 +6,48 @@
 //! Server module intended to construct a multi-threaded HTTP or HTTP/2 web server.
 //!
 
-use hyper::server::Server as HyperServer;
+use hyper::server::{Server as HyperServer, conn::AddrIncoming};
 use listenfd::ListenFd;
-use std::net::{IpAddr, SocketAddr, TcpListener};
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::{Mutex, watch::Receiver};
 
+use crate::connection_limits::{ConnectionLimitAcceptor, ConnectionLimits};
+use crate::error_page;
 use crate::handler::{RequestHandler, RequestHandlerOpts};
-
-#[cfg(all(unix, feature = "experimental"))]
+use crate::ip_allowlist::IpAllowlist;
+use crate::reload::{self, RuntimeConfig};
+use crate::settings::{Advanced, cli::General};
+use crate::startup_summary::StartupSummary;
+use crate::timeouts::{TimeoutAcceptor, Timeouts};
+use crate::workers;
+use crate::zero_copy::{self, ZeroCopyAcceptor};
+
+#[cfg(feature = "metrics")]
 use crate::metrics;
 #[cfg(any(unix, windows))]
 use crate::signals;
+#[cfg(unix)]
+use crate::systemd;
 
 #[cfg(feature = "http2")]
 use {
     crate::tls::{TlsAcceptor, TlsConfigBuilder},
-    crate::{error, error_page, https_redirect},
-    hyper::server::conn::{AddrIncoming, AddrStream},
+    crate::{error, https_redirect},
+    hyper::server::conn::AddrStream,
     hyper::service::{make_service_fn, service_fn},
 };
 
 #[cfg(feature = "directory-listing")]
 use crate::directory_listing;
+#[cfg(feature = "directory-listing")]
+use crate::directory_listing_locale;
+#[cfg(feature = "directory-listing")]
+use crate::directory_listing_readme;
+#[cfg(feature = "directory-listing")]
+use crate::directory_listing_size;
+#[cfg(feature = "directory-listing")]
+use crate::directory_listing_sort;
 
 #[cfg(feature = "directory-listing-download")]
 use crate::directory_listing_download;
@@ -43,17 +62,48 @@ use crate::fallback_page;
     feature = "compression-brotli",
     feature = "compression-zstd",
 ))]
-use crate::{compression, compression_static};
+use crate::{compression, compression_adaptive, compression_static};
+
+#[cfg(feature = "archive-preview")]
+use crate::archive_preview;
+
+#[cfg(feature = "archive-root")]
+use crate::fs::archive;
+
+#[cfg(feature = "s3-origin")]
+use crate::fs::s3;
+
+#[cfg(feature = "mmap")]
+use crate::fs::mmap;
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+use crate::fs::uring;
+
+#[cfg(feature = "subresource-integrity")]
+use crate::sri;
 
 #[cfg(feature = "basic-auth")]
 use crate::basic_auth;
 
+#[cfg(feature = "markdown")]
+use crate::markdown_render;
+
 #[cfg(feature = "experimental")]
 use crate::mem_cache;
 
-use crate::{Context, Result, service::RouterService};
+#[cfg(feature = "cache-invalidation")]
+use crate::cache_invalidation;
+
+use crate::access_log::{AccessLog, AccessLogSink};
+use crate::embedded::EmbeddedAssetsMode;
+use crate::fs::vfs::Filesystem;
+use crate::{Context, ErrorList, Result, anyhow, service::RouterService};
 use crate::{
-    Settings, control_headers, cors, health, helpers, log_addr, maintenance_mode, security_headers,
+    Settings, alt_svc, bandwidth, cache_index, chunk_cache, content_type_options, control,
+    control_headers, cors, deadline, dev_reload, embedded, health, helpers, ignore_patterns,
+    image_variants, interface, language_variants, listeners, log_addr, maintenance_mode,
+    media_streaming, mime_types, not_found_cache, probe, request_limits, security_headers,
+    server_ident, ssi, type_map,
 };
 
 /// Define a multi-threaded HTTP or HTTP/2 web server.
@@ -61,30 +111,62 @@ pub struct Server {
     opts: Settings,
     worker_threads: usize,
     max_blocking_threads: usize,
+    thread_stack_size: usize,
+    access_log: AccessLog,
 }
 
 impl Server {
     /// Create a new multi-threaded server instance.
     pub fn new(opts: Settings) -> Result<Server> {
-        // Configure number of worker threads
-        let cpus = std::thread::available_parallelism()
-            .with_context(|| {
-                "unable to get current platform cpus or lack of permissions to query available parallelism"
-            })?
-            .get();
-        let worker_threads = match opts.general.threads_multiplier {
-            0 | 1 => cpus,
-            n => cpus * n,
+        // Configure number of worker threads, either fixed or multiplied by the CPUs
+        let mut worker_threads = match opts.general.worker_threads {
+            0 => {
+                let cpus = std::thread::available_parallelism()
+                    .with_context(|| {
+                        "unable to get current platform cpus or lack of permissions to query available parallelism"
+                    })?
+                    .get();
+                match opts.general.threads_multiplier {
+                    0 | 1 => cpus,
+                    n => cpus * n,
+                }
+            }
+            n => n,
         };
+        // The worker threads are divided among the worker processes if any
+        if crate::workers::worker_id().is_some() && opts.general.worker_processes > 1 {
+            worker_threads = std::cmp::max(1, worker_threads / opts.general.worker_processes);
+        }
         let max_blocking_threads = opts.general.max_blocking_threads;
+        let thread_stack_size = opts.general.thread_stack_size;
 
         Ok(Server {
             opts,
             worker_threads,
             max_blocking_threads,
+            thread_stack_size,
+            access_log: AccessLog::default(),
         })
     }
 
+    /// Adds a sink receiving a structured record of every handled request,
+    /// in addition to the regular logging. It can be called multiple times.
+    pub fn with_access_log_sink<S: AccessLogSink + 'static>(mut self, sink: S) -> Self {
+        self.access_log.add_sink(sink);
+        self
+    }
+
+    /// Serves the files of the given file system backend (E.g. the assets embedded via
+    /// [`EmbeddedAssets`](crate::embedded::EmbeddedAssets)) according to the `embedded-assets`
+    /// option, instead of the ones embedded at build time.
+    /// Only the first file system registered in the process is kept.
+    pub fn with_embedded_assets<S: Filesystem + 'static>(self, source: S) -> Self {
+        if !embedded::register(source) {
+            tracing::warn!("embedded assets file system already registered, ignoring the new one");
+        }
+        self
+    }
+
     /// Run the multi-threaded `Server` as standalone.
     /// This is a top-level function of [run_server_on_rt](#method.run_server_on_rt).
     ///
@@ -127,18 +209,27 @@ impl Server {
     {
         tracing::debug!(%self.worker_threads, "initializing tokio runtime with multi-threaded scheduler");
 
-        let rt = tokio::runtime::Builder::new_multi_thread()
+        let mut builder = tokio::runtime::Builder::new_multi_thread();
+        builder
             .worker_threads(self.worker_threads)
             .max_blocking_threads(self.max_blocking_threads)
             .thread_name("static-web-server")
-            .enable_all()
-            .build()?;
+            .enable_all();
+        if self.thread_stack_size > 0 {
+            builder.thread_stack_size(self.thread_stack_size);
+        }
+        let rt = builder.build()?;
 
         let res = rt.block_on(async {
             tracing::trace!("tokio runtime initialized");
             self.start_server(cancel_recv, cancel_fn).await
         });
 
+        // Persist the in-memory cache indices once shut down gracefully
+        if res.is_ok() {
+            cache_index::persist();
+        }
+
         if let Err(err) = &res {
             tracing::error!("server failed to start up: {:?}", err);
             if exit_on_error {
@@ -165,7 +256,7 @@ impl Server {
         tracing::info!("log level: {}", general.log_level);
 
         // Config file option
-        let config_file = general.config_file;
+        let config_file = &general.config_file;
         if config_file.is_file() {
             tracing::info!("config file used: {}", config_file.display());
         } else {
@@ -175,32 +266,6 @@ impl Server {
             );
         }
 
-        // Determine TCP listener either file descriptor or TCP socket
-        let (tcp_listener, addr_str);
-        match general.fd {
-            Some(fd) => {
-                addr_str = format!("@FD({fd})");
-                tcp_listener = ListenFd::from_env()
-                    .take_tcp_listener(fd)?
-                    .with_context(|| "failed to convert inherited 'fd' into a 'tcp' listener")?;
-                tracing::info!(
-                    "converted inherited file descriptor {} to a 'tcp' listener",
-                    fd
-                );
-            }
-            None => {
-                let ip = general
-                    .host
-                    .parse::<IpAddr>()
-                    .with_context(|| format!("failed to parse {} address", general.host))?;
-                let addr = SocketAddr::from((ip, general.port));
-                tcp_listener = TcpListener::bind(addr)
-                    .with_context(|| format!("failed to bind to {addr} address"))?;
-                addr_str = addr.to_string();
-                tracing::info!("server bound to tcp socket {}", addr_str);
-            }
-        }
-
         // Number of worker threads option
         let threads = self.worker_threads;
         tracing::info!("runtime worker threads: {}", threads);
@@ -211,178 +276,123 @@ impl Server {
             general.max_blocking_threads
         );
 
-        // Check for a valid root directory
-        let root_dir = helpers::get_valid_dirpath(&general.root)
-            .with_context(|| "root directory was not found or inaccessible")?;
-
-        // Custom HTML error page files
-        // NOTE: in the case of relative paths, they're joined to the root directory
-        let mut page404 = general.page404;
-        if page404.is_relative() && !page404.starts_with(&root_dir) {
-            page404 = root_dir.join(page404);
-        }
-        if !page404.is_file() {
-            tracing::debug!(
-                "404 file path not found or not a regular file: {}",
-                page404.display()
-            );
-        }
-        let mut page50x = general.page50x;
-        if page50x.is_relative() && !page50x.starts_with(&root_dir) {
-            page50x = root_dir.join(page50x);
-        }
-        if !page50x.is_file() {
-            tracing::debug!(
-                "50x file path not found or not a regular file: {}",
-                page50x.display()
+        // Thread stack size option
+        if general.thread_stack_size > 0 {
+            tracing::info!(
+                "runtime thread stack size: {} bytes",
+                general.thread_stack_size
             );
         }
 
-        // Log remote address option
-        let log_remote_address = general.log_remote_address;
-
-        // Log the X-Real-IP header.
-        let log_x_real_ip = general.log_x_real_ip;
-
-        // Log the X-Forwarded-For header.
-        let log_forwarded_for = general.log_forwarded_for;
-
-        // Trusted IPs for remote addresses.
-        let trusted_proxies = general.trusted_proxies;
-
-        // Log redirect trailing slash option
-        let redirect_trailing_slash = general.redirect_trailing_slash;
-        tracing::info!(
-            "redirect trailing slash: enabled={}",
-            redirect_trailing_slash
-        );
-
-        // Ignore hidden files option
-        let ignore_hidden_files = general.ignore_hidden_files;
-        tracing::info!("ignore hidden files: enabled={}", ignore_hidden_files);
-
-        // Disable symlinks option
-        let disable_symlinks = general.disable_symlinks;
-        tracing::info!("disable symlinks: enabled={}", disable_symlinks);
-
         // Grace period option
         let grace_period = general.grace_period;
-        tracing::info!("grace period before graceful shutdown: {}s", grace_period);
-
-        // Index files option
-        let index_files = general
-            .index_files
-            .split(',')
-            .map(|s| s.trim().to_owned())
-            .collect::<Vec<_>>();
-        if index_files.is_empty() {
-            bail!("index files list is empty, provide at least one index file")
-        }
-        tracing::info!("index files: {}", general.index_files);
-
-        // Request handler options, some settings will be filled in by modules
-        let mut handler_opts = RequestHandlerOpts {
-            root_dir,
-            page404: page404.clone(),
-            page50x: page50x.clone(),
-            log_remote_address,
-            log_x_real_ip,
-            log_forwarded_for,
-            trusted_proxies,
-            redirect_trailing_slash,
-            ignore_hidden_files,
-            disable_symlinks,
-            accept_markdown: general.accept_markdown,
-            index_files,
-            advanced_opts,
-            ..Default::default()
-        };
-
-        // Directory listing options
-        #[cfg(feature = "directory-listing")]
-        directory_listing::init(
-            general.directory_listing,
-            general.directory_listing_order,
-            general.directory_listing_format,
-            &mut handler_opts,
+        tracing::info!(
+            "grace period to drain in-flight connections on shutdown: {}s",
+            grace_period
         );
 
-        // Directory listing download options
-        #[cfg(feature = "directory-listing-download")]
-        directory_listing_download::init(&general.directory_listing_download, &mut handler_opts);
-
-        // Fallback page option
-        #[cfg(feature = "fallback-page")]
-        fallback_page::init(&general.page_fallback, &mut handler_opts);
-
-        // Health endpoint option
-        health::init(general.health, &mut handler_opts);
-
-        // Log remote address option
-        log_addr::init(general.log_remote_address, &mut handler_opts);
-
-        // Metrics endpoint option (experimental)
-        #[cfg(all(unix, feature = "experimental"))]
-        metrics::init(general.experimental_metrics, &mut handler_opts);
-
-        // CORS option
-        cors::init(
-            &general.cors_allow_origins,
-            &general.cors_allow_headers,
-            &general.cors_expose_headers,
-            &mut handler_opts,
+        // Connection timeouts options
+        let timeouts = Timeouts::from_secs(
+            general.read_header_timeout,
+            general.idle_timeout,
+            general.write_timeout,
         );
-
-        // `Basic` HTTP Authentication Schema option
-        #[cfg(feature = "basic-auth")]
-        basic_auth::init(&general.basic_auth, &mut handler_opts);
-
-        // Maintenance mode option
-        maintenance_mode::init(
-            general.maintenance_mode,
-            general.maintenance_mode_status,
-            general.maintenance_mode_file,
-            &mut handler_opts,
+        tracing::info!(
+            "connection timeouts: read_header={}s, idle={}s, write={}s",
+            general.read_header_timeout,
+            general.idle_timeout,
+            general.write_timeout
         );
 
-        // Check pre-compressed files based on the `Accept-Encoding` header
-        #[cfg(any(
-            feature = "compression",
-            feature = "compression-deflate",
-            feature = "compression-gzip",
-            feature = "compression-brotli",
-            feature = "compression-zstd",
-        ))]
-        compression_static::init(general.compression_static, &mut handler_opts);
-
-        // Auto compression based on the `Accept-Encoding` header
-        #[cfg(any(
-            feature = "compression",
-            feature = "compression-deflate",
-            feature = "compression-gzip",
-            feature = "compression-brotli",
-            feature = "compression-zstd",
-        ))]
-        compression::init(
-            general.compression,
-            general.compression_level,
-            &mut handler_opts,
+        // Connection limits options
+        let limits = ConnectionLimits::new(general.max_connections, general.max_connections_per_ip);
+        tracing::info!(
+            "connection limits: max_connections={}, max_connections_per_ip={}",
+            general.max_connections,
+            general.max_connections_per_ip
         );
 
-        // Cache control headers option
-        control_headers::init(general.cache_control_headers, &mut handler_opts);
+        // Zero-copy file transmission option
+        zero_copy::init(general.zero_copy)?;
+
+        // Request handler options, they can be reloaded at runtime
+        let runtime_config = Arc::new(RuntimeConfig::new(
+            request_handler_opts(&general, advanced_opts)?,
+            &general,
+        )?);
+        #[cfg(feature = "http2")]
+        let error_pages = Arc::new(runtime_config.handler_opts.load().error_pages.clone());
+        #[cfg(feature = "http2")]
+        let ident = runtime_config.handler_opts.load().server_ident.clone();
 
-        // Security Headers option
-        security_headers::init(general.security_headers, &mut handler_opts);
+        // Pre-warm the in-memory caches from their persisted indices if any
+        cache_index::warm_up();
+
+        // Service manager watchdog if enabled by systemd
+        #[cfg(unix)]
+        systemd::spawn_watchdog();
+
+        // Cache invalidation on file changes, enabling it on configuration reload requires a restart
+        #[cfg(feature = "cache-invalidation")]
+        if runtime_config
+            .handler_opts
+            .load()
+            .cache_invalidation
+            .is_some()
+        {
+            cache_invalidation::spawn(runtime_config.handler_opts.clone());
+        }
 
-        // In-Memory cache option
-        #[cfg(feature = "experimental")]
-        mem_cache::cache::init(&mut handler_opts)?;
+        // Startup summary printed once the server is ready
+        let mut startup_summary =
+            StartupSummary::new(&general, &runtime_config.handler_opts.load());
 
         // Create a service router for Hyper
         let router_service = RouterService::new(RequestHandler {
-            opts: Arc::from(handler_opts),
+            opts: runtime_config.handler_opts.clone(),
+            access_log: self.access_log,
         });
+        // NOTE: the HSTS header is only sent by the HTTPS servers
+        #[cfg(feature = "http2")]
+        let router_service = router_service
+            .with_hsts(general.https_redirect_hsts)
+            .with_tls(general.http2);
+        let shutdown = signals::GracefulShutdown::new(router_service.connections());
+
+        // Configuration reload on `SIGHUP`
+        #[cfg(unix)]
+        let reload_signals = signals::create_reload_signals()
+            .with_context(|| "failed to register configuration reload signals")?;
+        #[cfg(unix)]
+        let reload_handle = reload_signals.handle();
+        #[cfg(unix)]
+        tokio::spawn(reload::wait_for_reload_signals(
+            reload_signals,
+            runtime_config.clone(),
+        ));
+
+        // Maintenance mode toggle on `SIGUSR1`
+        #[cfg(unix)]
+        let maintenance_signals = signals::create_maintenance_signals()
+            .with_context(|| "failed to register maintenance mode signals")?;
+        #[cfg(unix)]
+        let maintenance_handle = maintenance_signals.handle();
+        #[cfg(unix)]
+        tokio::spawn(reload::wait_for_maintenance_signals(
+            maintenance_signals,
+            runtime_config.clone(),
+        ));
+
+        // Control API option
+        let control_listen = general.control_listen.trim();
+        tracing::info!("control api: enabled={}", !control_listen.is_empty());
+        if !control_listen.is_empty() {
+            control::spawn(
+                control_listen,
+                &general.control_token,
+                runtime_config.clone(),
+            )?;
+        }
 
         #[cfg(windows)]
         let (sender, receiver) = tokio::sync::watch::channel(());
@@ -400,6 +410,88 @@ impl Server {
             }
         });
 
+        // Listeners option, they replace the server address and its TLS options
+        let listeners = self.opts.listeners;
+        if !listeners.is_empty() {
+            tracing::info!("listeners: {}", listeners.len());
+
+            #[cfg(unix)]
+            let signals = signals::create_signals()
+                .with_context(|| "failed to register termination signals")?;
+            #[cfg(unix)]
+            let handle = signals.handle();
+            #[cfg(unix)]
+            let signal = shutdown.signal(signals::wait_for_signals(
+                signals,
+                Arc::new(Mutex::new(_cancel_recv)),
+            ));
+
+            #[cfg(windows)]
+            let windows_service = general.windows_service;
+            #[cfg(windows)]
+            let signal = shutdown.signal(async move {
+                let cancel_recv = if windows_service {
+                    Arc::new(Mutex::new(_cancel_recv))
+                } else {
+                    Arc::new(Mutex::new(Some(receiver)))
+                };
+                signals::wait_for_ctrl_c(cancel_recv).await;
+            });
+
+            tracing::info!("press ctrl+c to shut down the servers");
+            shutdown
+                .drain(
+                    listeners::serve(
+                        &listeners,
+                        &router_service,
+                        timeouts,
+                        &limits,
+                        signal,
+                        startup_summary,
+                    ),
+                    grace_period,
+                )
+                .await?;
+
+            #[cfg(windows)]
+            ctrlc_task.await?;
+            #[cfg(windows)]
+            _cancel_fn();
+
+            #[cfg(unix)]
+            handle.close();
+            #[cfg(unix)]
+            reload_handle.close();
+            #[cfg(unix)]
+            maintenance_handle.close();
+
+            tracing::warn!("termination signal caught, shutting down the server execution");
+            return Ok(());
+        }
+
+        // Determine TCP listener either file descriptor or TCP socket
+        let (tcp_listener, addr_str);
+        match general.fd {
+            Some(fd) => {
+                addr_str = format!("@FD({fd})");
+                tcp_listener = ListenFd::from_env()
+                    .take_tcp_listener(fd)?
+                    .with_context(|| "failed to convert inherited 'fd' into a 'tcp' listener")?;
+                tracing::info!(
+                    "converted inherited file descriptor {} to a 'tcp' listener",
+                    fd
+                );
+            }
+            None => {
+                let ip = interface::host_address(&general.host, general.interface.as_deref())?;
+                let addr = SocketAddr::from((ip, general.port));
+                tcp_listener = workers::bind_tcp(addr)
+                    .with_context(|| format!("failed to bind to {addr} address"))?;
+                addr_str = addr.to_string();
+                tracing::info!("server bound to tcp socket {}", addr_str);
+            }
+        }
+
         // Run the corresponding HTTP Server asynchronously with its given options
         #[cfg(feature = "http2")]
         if general.http2 {
@@ -418,6 +510,12 @@ impl Server {
                 "http to https redirect from hosts: {}",
                 general.https_redirect_from_hosts
             );
+            tracing::info!(
+                "http to https redirect: status={}, preserve host={}, hsts={}",
+                general.https_redirect_status,
+                general.https_redirect_preserve_host,
+                general.https_redirect_hsts
+            );
 
             // HTTP/2 + TLS
             tcp_listener
@@ -429,6 +527,7 @@ impl Server {
                 || "failed to create an AddrIncoming from the current tokio::net::TcpListener",
             )?;
             incoming.set_nodelay(true);
+            startup_summary.tcp_listener(incoming.local_addr(), true, true, true);
 
             let http2_tls_cert = match general.http2_tls_cert {
                 Some(v) => v,
@@ -453,8 +552,12 @@ impl Server {
             #[cfg(unix)]
             let handle = signals.handle();
 
-            let http2_server =
-                HyperServer::builder(TlsAcceptor::new(tls, incoming)).serve(router_service);
+            let http2_server = timeouts
+                .apply(HyperServer::builder(TimeoutAcceptor::new(
+                    TlsAcceptor::new(tls, ConnectionLimitAcceptor::new(incoming, limits, false)),
+                    timeouts,
+                )))
+                .serve(router_service);
 
             #[cfg(unix)]
             let http2_cancel_recv = Arc::new(Mutex::new(_cancel_recv));
@@ -462,11 +565,9 @@ impl Server {
             let redirect_cancel_recv = http2_cancel_recv.clone();
 
             #[cfg(unix)]
-            let http2_server = http2_server.with_graceful_shutdown(signals::wait_for_signals(
-                signals,
-                grace_period,
-                http2_cancel_recv,
-            ));
+            let http2_server = http2_server.with_graceful_shutdown(
+                shutdown.signal(signals::wait_for_signals(signals, http2_cancel_recv)),
+            );
 
             #[cfg(windows)]
             let http2_cancel_recv = Arc::new(Mutex::new(_cancel_recv));
@@ -479,13 +580,13 @@ impl Server {
             let redirect_ctrlc_recv = http2_ctrlc_recv.clone();
 
             #[cfg(windows)]
-            let http2_server = http2_server.with_graceful_shutdown(async move {
+            let http2_server = http2_server.with_graceful_shutdown(shutdown.signal(async move {
                 if general.windows_service {
-                    signals::wait_for_ctrl_c(http2_cancel_recv, grace_period).await;
+                    signals::wait_for_ctrl_c(http2_cancel_recv).await;
                 } else {
-                    signals::wait_for_ctrl_c(http2_ctrlc_recv, grace_period).await;
+                    signals::wait_for_ctrl_c(http2_ctrlc_recv).await;
                 }
-            });
+            }));
 
             tracing::info!(
                 parent: tracing::info_span!("Server::start_server", ?addr_str, ?threads),
@@ -495,13 +596,11 @@ impl Server {
 
             // HTTP to HTTPS redirect server
             if general.https_redirect {
-                let ip = general
-                    .host
-                    .parse::<IpAddr>()
-                    .with_context(|| format!("failed to parse {} address", general.host))?;
+                let ip = interface::host_address(&general.host, general.interface.as_deref())?;
                 let addr = SocketAddr::from((ip, general.https_redirect_from_port));
-                let tcp_listener = TcpListener::bind(addr)
+                let tcp_listener = workers::bind_tcp(addr)
                     .with_context(|| format!("failed to bind to {addr} address"))?;
+                startup_summary.tcp_listener(tcp_listener.local_addr()?, false, true, false);
                 tracing::info!(
                     parent: tracing::info_span!("Server::start_server", ?addr, ?threads),
                     "http1 redirect server is listening on http://{}",
@@ -532,50 +631,58 @@ impl Server {
                     https_hostname: general.https_redirect_host,
                     https_port: general.port,
                     allowed_hosts: redirect_allowed_hosts,
+                    status: https_redirect::redirect_status(general.https_redirect_status)?,
+                    preserve_host: general.https_redirect_preserve_host,
                 });
 
-                let server_redirect = HyperServer::from_tcp(tcp_listener)
-                    .unwrap()
-                    .tcp_nodelay(true)
+                let server_redirect = timeouts
+                    .apply(
+                        HyperServer::from_tcp(tcp_listener)
+                            .unwrap()
+                            .tcp_nodelay(true),
+                    )
                     .serve(make_service_fn(move |_: &AddrStream| {
                         let redirect_opts = redirect_opts.clone();
-                        let page404 = page404.clone();
-                        let page50x = page50x.clone();
+                        let error_pages = error_pages.clone();
+                        let ident = ident.clone();
                         async move {
                             Ok::<_, error::Error>(service_fn(move |req| {
                                 let redirect_opts = redirect_opts.clone();
-                                let page404 = page404.clone();
-                                let page50x = page50x.clone();
-                                async move {
+                                let error_pages = error_pages.clone();
+                                let handle = async move {
                                     let uri = req.uri();
                                     let method = req.method();
                                     match https_redirect::redirect_to_https(&req, redirect_opts) {
                                         Ok(resp) => Ok(resp),
                                         Err(status) => error_page::error_response(
-                                            uri, method, &status, &page404, &page50x,
+                                            uri,
+                                            method,
+                                            &status,
+                                            &error_pages,
                                         ),
                                     }
-                                }
+                                };
+                                server_ident::post_process(ident.clone(), handle)
                             }))
                         }
                     }));
 
                 #[cfg(unix)]
                 let server_redirect = server_redirect.with_graceful_shutdown(
-                    signals::wait_for_signals(redirect_signals, grace_period, redirect_cancel_recv),
+                    signals::wait_for_signals(redirect_signals, redirect_cancel_recv),
                 );
                 #[cfg(windows)]
                 let server_redirect = server_redirect.with_graceful_shutdown(async move {
                     if general.windows_service {
-                        signals::wait_for_ctrl_c(redirect_cancel_recv, grace_period).await;
+                        signals::wait_for_ctrl_c(redirect_cancel_recv).await;
                     } else {
-                        signals::wait_for_ctrl_c(redirect_ctrlc_recv, grace_period).await;
+                        signals::wait_for_ctrl_c(redirect_ctrlc_recv).await;
                     }
                 });
 
                 // HTTP/2 server task
                 let server_task = tokio::spawn(async move {
-                    if let Err(err) = http2_server.await {
+                    if let Err(err) = shutdown.drain(http2_server, grace_period).await {
                         tracing::error!("http2 server failed to start up: {:?}", err);
                         std::process::exit(1)
                     }
@@ -589,6 +696,9 @@ impl Server {
                     }
                 });
 
+                startup_summary.print();
+                #[cfg(unix)]
+                systemd::ready();
                 tracing::info!("press ctrl+c to shut down the servers");
 
                 #[cfg(windows)]
@@ -599,12 +709,19 @@ impl Server {
                 #[cfg(unix)]
                 redirect_handle.close();
             } else {
+                startup_summary.print();
+                #[cfg(unix)]
+                systemd::ready();
                 tracing::info!("press ctrl+c to shut down the server");
-                http2_server.await?;
+                shutdown.drain(http2_server, grace_period).await?;
             }
 
             #[cfg(unix)]
             handle.close();
+            #[cfg(unix)]
+            reload_handle.close();
+            #[cfg(unix)]
+            maintenance_handle.close();
 
             #[cfg(windows)]
             _cancel_fn();
@@ -625,23 +742,34 @@ impl Server {
             .set_nonblocking(true)
             .with_context(|| "failed to set TCP non-blocking mode")?;
 
-        let http1_server = HyperServer::from_tcp(tcp_listener)
-            .unwrap()
-            .tcp_nodelay(true)
-            .serve(router_service);
+        let listener = tokio::net::TcpListener::from_std(tcp_listener)
+            .with_context(|| "failed to create tokio::net::TcpListener")?;
+        let mut incoming = AddrIncoming::from_listener(listener).with_context(
+            || "failed to create an AddrIncoming from the current tokio::net::TcpListener",
+        )?;
+        incoming.set_nodelay(true);
+        startup_summary.tcp_listener(incoming.local_addr(), false, true, false);
+
+        let mut http1_builder = timeouts.apply(HyperServer::builder(TimeoutAcceptor::new(
+            ConnectionLimitAcceptor::new(ZeroCopyAcceptor::new(incoming), limits, true),
+            timeouts,
+        )));
+        // The body chunks must be written as they are for the zero-copy ones to be recognized
+        if zero_copy::is_enabled() {
+            http1_builder = http1_builder.http1_writev(true);
+        }
+        let http1_server = http1_builder.serve(router_service);
 
         #[cfg(unix)]
         let http1_cancel_recv = Arc::new(Mutex::new(_cancel_recv));
 
         #[cfg(unix)]
-        let http1_server = http1_server.with_graceful_shutdown(signals::wait_for_signals(
-            signals,
-            grace_period,
-            http1_cancel_recv,
-        ));
+        let http1_server = http1_server.with_graceful_shutdown(
+            shutdown.signal(signals::wait_for_signals(signals, http1_cancel_recv)),
+        );
 
         #[cfg(windows)]
-        let http1_server = http1_server.with_graceful_shutdown(async move {
+        let http1_server = http1_server.with_graceful_shutdown(shutdown.signal(async move {
             let http1_cancel_recv = if general.windows_service {
                 // http1_cancel_recv
                 Arc::new(Mutex::new(_cancel_recv))
@@ -649,8 +777,8 @@ impl Server {
                 // http1_ctrlc_recv
                 Arc::new(Mutex::new(Some(receiver)))
             };
-            signals::wait_for_ctrl_c(http1_cancel_recv, grace_period).await;
-        });
+            signals::wait_for_ctrl_c(http1_cancel_recv).await;
+        }));
 
         tracing::info!(
             parent: tracing::info_span!("Server::start_server", ?addr_str, ?threads),
@@ -658,14 +786,17 @@ impl Server {
             addr_str
         );
 
+        startup_summary.print();
+        #[cfg(unix)]
+        systemd::ready();
         tracing::info!("press ctrl+c to shut down the server");
 
         #[cfg(unix)]
-        http1_server.await?;
+        shutdown.drain(http1_server, grace_period).await?;
 
         #[cfg(windows)]
         let http1_server_task = tokio::spawn(async move {
-            if let Err(err) = http1_server.await {
+            if let Err(err) = shutdown.drain(http1_server, grace_period).await {
                 tracing::error!("http1 server failed to start up: {:?}", err);
                 std::process::exit(1)
             }
@@ -678,8 +809,545 @@ impl Server {
 
         #[cfg(unix)]
         handle.close();
+        #[cfg(unix)]
+        reload_handle.close();
+        #[cfg(unix)]
+        maintenance_handle.close();
 
         tracing::warn!("termination signal caught, shutting down the server execution");
         Ok(())
     }
 }
+
+/// Builds the request handler options from the given server settings.
+///
+/// It's used on server start up and on configuration reload, so it only covers the options
+/// that can be swapped at runtime without restarting the server.
+pub(crate) fn request_handler_opts(
+    general: &General,
+    advanced_opts: Option<Advanced>,
+) -> Result<RequestHandlerOpts> {
+    // Independent validation errors reported all at once
+    let mut errors = ErrorList::default();
+    let handler_opts = build_request_handler_opts(general, advanced_opts, &mut errors);
+    errors.into_result()?;
+
+    Ok(handler_opts)
+}
+
+/// Builds the request handler options recording the validation errors of every option.
+pub(crate) fn build_request_handler_opts(
+    general: &General,
+    advanced_opts: Option<Advanced>,
+    errors: &mut ErrorList,
+) -> RequestHandlerOpts {
+    // Check for a valid root directory, unless only the embedded assets are served
+    // or the root files are served from an archive or an S3 origin
+    #[cfg(feature = "archive-root")]
+    let is_root_archive = archive::is_archive(&general.root);
+    #[cfg(not(feature = "archive-root"))]
+    let is_root_archive = false;
+    #[cfg(feature = "s3-origin")]
+    let is_s3_origin = !general.s3_origin.is_empty();
+    #[cfg(not(feature = "s3-origin"))]
+    let is_s3_origin = false;
+    let root_dir =
+        if general.embedded_assets == EmbeddedAssetsMode::Only || is_root_archive || is_s3_origin {
+            general.root.clone()
+        } else {
+            let root_dir = helpers::get_valid_dirpath(&general.root)
+                .with_context(|| "root directory was not found or inaccessible");
+            errors.check("root", root_dir).unwrap_or_default()
+        };
+
+    // Check for valid overlay root directories
+    let overlay_dirs = general
+        .overlay_roots
+        .iter()
+        .filter_map(|dir| {
+            let dir = helpers::get_valid_dirpath(dir).with_context(|| {
+                format!(
+                    "overlay root directory {} was not found or inaccessible",
+                    dir.display()
+                )
+            });
+            errors.check("overlay-roots", dir)
+        })
+        .collect::<Vec<_>>();
+    if !overlay_dirs.is_empty() {
+        tracing::info!("overlay root directories: {:?}", overlay_dirs);
+    }
+
+    // Log remote address option
+    let log_remote_address = general.log_remote_address;
+
+    // Log the X-Real-IP header.
+    let log_x_real_ip = general.log_x_real_ip;
+
+    // Log the X-Forwarded-For header.
+    let log_forwarded_for = general.log_forwarded_for;
+
+    // Trusted IPs for remote addresses.
+    let trusted_proxies =
+        IpAllowlist::new(&general.trusted_proxies, general.trusted_proxies_refresh)
+            .with_context(|| "invalid trusted proxies");
+    let trusted_proxies = errors
+        .check("trusted-proxies", trusted_proxies)
+        .unwrap_or_default();
+
+    // Log redirect trailing slash option
+    let redirect_trailing_slash = general.redirect_trailing_slash;
+    tracing::info!(
+        "redirect trailing slash: enabled={}",
+        redirect_trailing_slash
+    );
+
+    // Reject absolute-form request targets option
+    let reject_absolute_form = general.reject_absolute_form;
+    tracing::info!("reject absolute-form: enabled={}", reject_absolute_form);
+
+    // Ignore hidden files option
+    let ignore_hidden_files = general.ignore_hidden_files;
+    tracing::info!("ignore hidden files: enabled={}", ignore_hidden_files);
+
+    // Disable symlinks option
+    let disable_symlinks = general.disable_symlinks;
+    tracing::info!("disable symlinks: enabled={}", disable_symlinks);
+
+    // Follow symlinks option, disabling the symlinks takes precedence
+    let follow_symlinks = general.follow_symlinks.or_disabled(disable_symlinks);
+    tracing::info!("follow symlinks: policy={}", follow_symlinks);
+
+    // Index files option
+    let index_files = general
+        .index_files
+        .split(',')
+        .map(|s| s.trim().to_owned())
+        .collect::<Vec<_>>();
+    if index_files.is_empty() {
+        errors.push(
+            "index-files",
+            anyhow!("index files list is empty, provide at least one index file"),
+        );
+    }
+    tracing::info!("index files: {}", general.index_files);
+
+    // Request handler options, some settings will be filled in by modules
+    let mut handler_opts = RequestHandlerOpts {
+        root_dir,
+        overlay_dirs,
+        log_remote_address,
+        log_x_real_ip,
+        log_forwarded_for,
+        trusted_proxies,
+        redirect_trailing_slash,
+        reject_absolute_form,
+        ignore_hidden_files,
+        disable_symlinks,
+        follow_symlinks,
+        accept_markdown: general.accept_markdown,
+        index_files,
+        advanced_opts,
+        ..Default::default()
+    };
+
+    // Custom HTML error pages
+    error_page::init(&general.page404, &general.page50x, &mut handler_opts);
+
+    // Language content negotiation option
+    errors.check(
+        "language-variants",
+        language_variants::init(&general.language_variants, &mut handler_opts),
+    );
+
+    // Image format negotiation option
+    errors.check(
+        "image-variants",
+        image_variants::init(&general.image_variants, &mut handler_opts),
+    );
+
+    // Ignore patterns option
+    errors.check(
+        "ignore-patterns",
+        ignore_patterns::init(&general.ignore_patterns, &mut handler_opts),
+    );
+
+    // Directory listing options
+    #[cfg(feature = "directory-listing")]
+    errors.check(
+        "directory-listing",
+        directory_listing::init(
+            general.directory_listing,
+            &general.directory_listing_paths,
+            general.directory_listing_format.clone(),
+            general.directory_listing_details,
+            &mut handler_opts,
+        ),
+    );
+
+    // Directory listing sorting options
+    #[cfg(feature = "directory-listing")]
+    errors.check(
+        "directory-listing-sort",
+        directory_listing_sort::init(
+            &general.directory_listing_sort,
+            general.directory_listing_order,
+            general.directory_listing_dirs_first,
+            &mut handler_opts,
+        ),
+    );
+
+    // Directory listing size options
+    #[cfg(feature = "directory-listing")]
+    directory_listing_size::init(
+        general.directory_listing_dir_size,
+        general.directory_listing_dir_size_ttl,
+        general.directory_listing_dir_size_max_entries,
+        general.directory_listing_dir_size_concurrency,
+        &mut handler_opts,
+    );
+
+    // Directory listing locale options
+    #[cfg(feature = "directory-listing")]
+    errors.check(
+        "directory-listing-locale",
+        directory_listing_locale::init(&general.directory_listing_locale, &mut handler_opts),
+    );
+
+    // Directory listing hidden entries, header and readme options
+    #[cfg(feature = "directory-listing")]
+    errors.check(
+        "directory-listing-readme",
+        directory_listing_readme::init(
+            &general.directory_listing_hide,
+            &general.directory_listing_header,
+            &general.directory_listing_readme,
+            &mut handler_opts,
+        ),
+    );
+
+    // Directory listing download options
+    #[cfg(feature = "directory-listing-download")]
+    directory_listing_download::init(&general.directory_listing_download, &mut handler_opts);
+
+    // Fallback page option
+    #[cfg(feature = "fallback-page")]
+    errors.check(
+        "fallback-page",
+        fallback_page::init(
+            &general.page_fallback,
+            general.page_fallback_soft404,
+            general.page_fallback_status,
+            &general.page_fallback_methods,
+            general.page_fallback_html_only,
+            &mut handler_opts,
+        ),
+    );
+
+    // Health endpoint option
+    health::init(
+        general.health,
+        &general.health_ready_files,
+        &mut handler_opts,
+    );
+
+    // Probe endpoint option
+    probe::init(&general.probe_endpoint, &mut handler_opts);
+
+    // Server identification option
+    errors.check(
+        "server-ident",
+        server_ident::init(&general.server_ident, &mut handler_opts),
+    );
+
+    // Alternative services option
+    errors.check(
+        "alt-svc",
+        alt_svc::init(&general.alt_svc, general.alt_svc_max_age, &mut handler_opts),
+    );
+
+    // Request limits options
+    request_limits::init(
+        general.max_uri_length,
+        general.max_headers_size,
+        general.max_body_size,
+        &mut handler_opts,
+    );
+
+    // Request timeout option
+    deadline::init(general.request_timeout, &mut handler_opts);
+
+    // Bandwidth limit options
+    bandwidth::init(
+        general.limit_rate,
+        general.limit_rate_global,
+        &mut handler_opts,
+    );
+
+    // Root archive option
+    #[cfg(feature = "archive-root")]
+    if general.embedded_assets != EmbeddedAssetsMode::Only && !is_s3_origin {
+        errors.check("root", archive::init(&general.root, &mut handler_opts));
+    }
+
+    // S3 origin options
+    #[cfg(feature = "s3-origin")]
+    if general.embedded_assets != EmbeddedAssetsMode::Only {
+        let s3_opts = s3::S3OriginOpts {
+            url: general.s3_origin.clone(),
+            region: general.s3_region.clone(),
+            access_key_id: general.s3_access_key_id.clone(),
+            secret_access_key: general.s3_secret_access_key.clone(),
+            cache_dir: general.s3_cache_dir.clone(),
+            cache_ttl: general.s3_cache_ttl,
+            cache_max_size: general.s3_cache_max_size,
+            ca_file: general.s3_ca_file.clone(),
+        };
+        // Options reading the root directory on the local disk instead of the bucket
+        let try_files = handler_opts
+            .advanced_opts
+            .as_ref()
+            .is_some_and(|advanced| advanced.try_files.is_some());
+        let local_features = [
+            ("try-files", try_files),
+            ("language-variants", !general.language_variants.is_empty()),
+            ("image-variants", !general.image_variants.is_empty()),
+            ("accept-markdown", general.accept_markdown),
+            #[cfg(feature = "subresource-integrity")]
+            ("sri-manifest", !general.sri_manifest.is_empty()),
+            ("ssi", general.ssi),
+            ("type-map", general.type_map),
+        ]
+        .into_iter()
+        .filter_map(|(name, enabled)| enabled.then_some(name))
+        .collect::<Vec<_>>();
+        errors.check(
+            "s3-origin",
+            s3::init(s3_opts, &local_features, &mut handler_opts),
+        );
+    }
+
+    // Embedded assets option
+    errors.check(
+        "embedded",
+        embedded::init(general.embedded_assets, &mut handler_opts),
+    );
+
+    // Not found cache options
+    not_found_cache::init(
+        general.not_found_cache_ttl,
+        general.not_found_cache_capacity,
+        &mut handler_opts,
+    );
+
+    // Archive preview options
+    #[cfg(feature = "archive-preview")]
+    archive_preview::init(
+        general.archive_preview,
+        general.archive_preview_max_entries,
+        general.archive_preview_max_size,
+        &mut handler_opts,
+    );
+
+    // Subresource Integrity manifest options
+    #[cfg(feature = "subresource-integrity")]
+    sri::init(
+        &general.sri_manifest,
+        general.sri_algorithm,
+        &general.sri_extensions,
+        &mut handler_opts,
+    );
+
+    // Log remote address option
+    log_addr::init(general.log_remote_address, &mut handler_opts);
+
+    // Metrics endpoint option
+    #[cfg(feature = "metrics")]
+    metrics::init(general.metrics, &mut handler_opts);
+
+    // Tokio runtime metrics option (experimental)
+    #[cfg(all(unix, feature = "experimental"))]
+    metrics::init_runtime(general.experimental_metrics);
+
+    // CORS option
+    cors::init(
+        &general.cors_allow_origins,
+        &general.cors_allow_headers,
+        &general.cors_expose_headers,
+        &mut handler_opts,
+    );
+
+    // `Basic` HTTP Authentication Schema option
+    #[cfg(feature = "basic-auth")]
+    basic_auth::init(&general.basic_auth, &mut handler_opts);
+
+    // Maintenance mode option
+    maintenance_mode::init(
+        general.maintenance_mode,
+        general.maintenance_mode_status,
+        general.maintenance_mode_file.clone(),
+        &mut handler_opts,
+    );
+    errors.check(
+        "maintenance-mode",
+        maintenance_mode::init_bypass(
+            &general.maintenance_mode_allow_ips,
+            &general.maintenance_mode_allow_paths,
+            &mut handler_opts,
+        ),
+    );
+    errors.check(
+        "maintenance-mode",
+        maintenance_mode::init_schedule(
+            &general.maintenance_mode_start,
+            &general.maintenance_mode_end,
+            &mut handler_opts,
+        ),
+    );
+
+    // Markdown rendering option
+    #[cfg(feature = "markdown")]
+    markdown_render::init(
+        general.markdown_render,
+        &general.markdown_render_template,
+        &mut handler_opts,
+    );
+
+    // Default charset option
+    errors.check(
+        "mime-types",
+        mime_types::init(&general.default_charset, &mut handler_opts),
+    );
+
+    // Server-side includes option
+    ssi::init(general.ssi, &general.ssi_extensions, &mut handler_opts);
+
+    // Live-reload option
+    dev_reload::init(
+        general.dev_reload,
+        general.dev_reload_inject,
+        &mut handler_opts,
+    );
+
+    // Check pre-compressed files based on the `Accept-Encoding` header
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-deflate",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+    ))]
+    errors.check(
+        "compression-static",
+        compression_static::init(
+            general.compression_static,
+            &general.compression_static_order,
+            &mut handler_opts,
+        ),
+    );
+
+    // Auto compression based on the `Accept-Encoding` header
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-deflate",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+    ))]
+    errors.check(
+        "compression",
+        compression::init(
+            general.compression,
+            general.compression_level,
+            compression::AlgorithmLevels {
+                gzip: general.compression_level_gzip,
+                brotli: general.compression_level_brotli,
+                zstd: general.compression_level_zstd,
+            },
+            &mut handler_opts,
+        ),
+    );
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-deflate",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+    ))]
+    errors.check(
+        "compression",
+        compression::init_filters(
+            general.compression_min_size,
+            &general.compression_mime_types,
+            &general.compression_exclude_mime_types,
+            &mut handler_opts,
+        ),
+    );
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-deflate",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+    ))]
+    errors.check(
+        "compression-adaptive",
+        compression_adaptive::init(
+            general.compression_adaptive,
+            general.compression_adaptive_high_load,
+            general.compression_adaptive_max_load,
+            &mut handler_opts,
+        ),
+    );
+
+    // io_uring file reads option
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    uring::init(general.io_uring);
+    #[cfg(all(feature = "io-uring", not(target_os = "linux")))]
+    if general.io_uring {
+        tracing::warn!(
+            "io_uring: only supported on Linux, the regular file reads are used instead"
+        );
+    }
+
+    // Memory-mapped files option
+    #[cfg(feature = "mmap")]
+    mmap::init(general.mmap_max_size, &mut handler_opts);
+    #[cfg(all(feature = "mmap", not(target_os = "linux")))]
+    if general.mmap_max_size > 0 {
+        tracing::warn!(
+            "memory-mapped files: only supported on Linux, the regular file reads are used instead"
+        );
+    }
+
+    // Cache control headers option
+    control_headers::init(general.cache_control_headers, &mut handler_opts);
+
+    // Media streaming preset option
+    media_streaming::init(general.media_streaming, &mut handler_opts);
+
+    // Security Headers option
+    security_headers::init(general.security_headers, &mut handler_opts);
+
+    // MIME type sniffing protection option
+    content_type_options::init(&handler_opts);
+
+    // In-Memory cache option
+    #[cfg(feature = "experimental")]
+    errors.check("mem-cache", mem_cache::cache::init(&mut handler_opts));
+
+    // Byte-range chunk cache option
+    errors.check("chunk-cache", chunk_cache::init(&mut handler_opts));
+
+    // Cache invalidation on file changes option (after the cache options)
+    #[cfg(feature = "cache-invalidation")]
+    errors.check(
+        "cache-invalidation",
+        cache_invalidation::init(&mut handler_opts),
+    );
+
+    // Pre-generated type map option (after the compression and MIME types options)
+    type_map::init(general.type_map, &mut handler_opts);
+
+    handler_opts
+}
@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that identifies the server via the `Server` response header.
+//!
+
+use headers::HeaderValue;
+use hyper::{Body, Response, header::SERVER};
+use std::future::Future;
+
+use crate::{Context, Error, Result, handler::RequestHandlerOpts};
+
+/// Parses the server identification option into the `Server` header value if any.
+///
+/// - `off` or empty: no `Server` header (default).
+/// - `name`: the server name only (E.g. `static-web-server`).
+/// - `full`: the server name and version (E.g. `static-web-server/2.40.0`).
+/// - Any other value is sent as is.
+pub(crate) fn parse(value: &str) -> Result<Option<HeaderValue>> {
+    let ident = match value.trim() {
+        "" | "off" => return Ok(None),
+        "name" => env!("CARGO_PKG_NAME").to_owned(),
+        "full" => [env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")].concat(),
+        ident => ident.to_owned(),
+    };
+    HeaderValue::try_from(ident)
+        .map(Some)
+        .with_context(|| format!("invalid server ident value: {value}"))
+}
+
+/// Initializes the server identification.
+pub(crate) fn init(value: &str, handler_opts: &mut RequestHandlerOpts) -> Result {
+    handler_opts.server_ident = parse(value)?;
+    tracing::info!(
+        "server ident: enabled={}, value={:?}",
+        handler_opts.server_ident.is_some(),
+        handler_opts.server_ident
+    );
+    Ok(())
+}
+
+/// Appends the `Server` header to a response unless it already has one (E.g. a custom header).
+pub(crate) fn append_header(ident: Option<&HeaderValue>, resp: &mut Response<Body>) {
+    if let Some(ident) = ident {
+        resp.headers_mut()
+            .entry(SERVER)
+            .or_insert_with(|| ident.clone());
+    }
+}
+
+/// Appends the `Server` header to the response of the given future once it's ready.
+pub(crate) async fn post_process<F>(
+    ident: Option<HeaderValue>,
+    handle: F,
+) -> Result<Response<Body>, Error>
+where
+    F: Future<Output = Result<Response<Body>, Error>>,
+{
+    let mut resp = handle.await?;
+    append_header(ident.as_ref(), &mut resp);
+    Ok(resp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{append_header, parse};
+    use hyper::{Body, Response};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("").unwrap(), None);
+        assert_eq!(parse(" off ").unwrap(), None);
+        assert_eq!(parse("name").unwrap().unwrap(), "static-web-server");
+        assert_eq!(
+            parse("full").unwrap().unwrap(),
+            format!("static-web-server/{}", env!("CARGO_PKG_VERSION"))
+        );
+        assert_eq!(parse("my-cdn").unwrap().unwrap(), "my-cdn");
+        assert!(parse("bad\nvalue").is_err());
+    }
+
+    #[test]
+    fn test_append_header() {
+        let ident = parse("name").unwrap();
+
+        let mut resp = Response::new(Body::empty());
+        append_header(ident.as_ref(), &mut resp);
+        assert_eq!(resp.headers()["server"], "static-web-server");
+
+        // Existing headers are kept
+        let mut resp = Response::builder()
+            .header("server", "custom")
+            .body(Body::empty())
+            .unwrap();
+        append_header(ident.as_ref(), &mut resp);
+        assert_eq!(resp.headers()["server"], "custom");
+
+        let mut resp = Response::new(Body::empty());
+        append_header(None, &mut resp);
+        assert!(resp.headers().get("server").is_none());
+    }
+}
@@ -6,15 +6,29 @@
 //! The module provides a custom [Hyper service](hyper::service::Service).
 //!
 
-use hyper::{Body, Request, Response, service::Service};
+use hyper::{Body, Request, Response, StatusCode, http::uri::Scheme, service::Service};
 use std::convert::Infallible;
 use std::future::{Future, Ready, ready};
 use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::task::{Context, Poll};
+use tokio::sync::Notify;
 
-use crate::{Error, handler::RequestHandler, transport::Transport};
+use crate::settings::Listener;
+use crate::{
+    Error, error_page,
+    handler::RequestHandler,
+    request_target, server_ident,
+    timeouts::{self, ConnectionActivity},
+    transport::Transport,
+    virtual_hosts,
+    zero_copy::{self, ZeroCopySender},
+};
+
+#[cfg(feature = "http2")]
+use crate::https_redirect::{self, RedirectOpts};
 
 /// It defines the router service which is the main entry point for Hyper Server.
 pub struct RouterService {
@@ -28,6 +42,57 @@ impl RouterService {
             builder: RequestServiceBuilder::new(handler),
         }
     }
+
+    /// Gets the counter of the connections served by this router service.
+    pub fn connections(&self) -> ConnectionCounter {
+        self.builder.connections.clone()
+    }
+
+    /// Sends a preload-grade `Strict-Transport-Security` header with every response,
+    /// so it's only meant for HTTPS servers.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn with_hsts(mut self, enabled: bool) -> Self {
+        self.builder.hsts = enabled;
+        self
+    }
+
+    /// Marks the connections as secure, so requests get the `https` scheme
+    /// as a [`Scheme`] request extension.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn with_tls(mut self, enabled: bool) -> Self {
+        self.builder.tls = enabled;
+        self
+    }
+
+    /// Creates a router service for a listener which only serves its virtual hosts
+    /// or redirects all requests to HTTPS.
+    /// It shares the request handler and the connections counter of this router service,
+    /// while the HSTS header is only sent by the TLS listeners.
+    pub(crate) fn for_listener(&self, listener: &Listener) -> Self {
+        Self {
+            builder: RequestServiceBuilder {
+                handler: self.builder.handler.clone(),
+                connections: self.builder.connections.clone(),
+                vhosts: listener.vhosts.clone().map(Arc::from),
+                #[cfg(feature = "http2")]
+                https_redirect: listener.https_redirect.as_ref().map(|redirect| {
+                    Arc::new(RedirectOpts {
+                        https_hostname: redirect.host.clone(),
+                        https_port: redirect.port,
+                        allowed_hosts: redirect.allowed_hosts.clone(),
+                        status: redirect.status,
+                        preserve_host: redirect.preserve_host,
+                    })
+                }),
+                #[cfg(feature = "http2")]
+                hsts: self.builder.hsts && listener.tls.is_some(),
+                #[cfg(feature = "http2")]
+                tls: listener.tls.is_some(),
+            },
+        }
+    }
 }
 
 impl<T: Transport + Send + 'static> Service<&T> for RouterService {
@@ -40,7 +105,10 @@ impl<T: Transport + Send + 'static> Service<&T> for RouterService {
     }
 
     fn call(&mut self, conn: &T) -> Self::Future {
-        ready(Ok(self.builder.build(conn.remote_addr())))
+        let mut service = self.builder.build(conn.remote_addr());
+        service.zero_copy = conn.zero_copy();
+        service.activity = conn.activity();
+        ready(Ok(service))
     }
 }
 
@@ -48,6 +116,19 @@ impl<T: Transport + Send + 'static> Service<&T> for RouterService {
 pub struct RequestService {
     handler: Arc<RequestHandler>,
     remote_addr: Option<SocketAddr>,
+    vhosts: Option<Arc<[String]>>,
+    #[cfg(feature = "http2")]
+    https_redirect: Option<Arc<RedirectOpts>>,
+    #[cfg(feature = "http2")]
+    hsts: bool,
+    #[cfg(feature = "http2")]
+    tls: bool,
+    /// Sender of the files sent with zero-copy if the connection supports it.
+    zero_copy: Option<ZeroCopySender>,
+    /// Activity of the requests of the connection if it has connection timeouts.
+    activity: Option<ConnectionActivity>,
+    /// Guard of the connection, also held by the tunnels of its upgraded requests if any.
+    connection: Arc<ConnectionGuard>,
 }
 
 impl Service<Request<Body>> for RequestService {
@@ -62,13 +143,101 @@ impl Service<Request<Body>> for RequestService {
     fn call(&mut self, mut req: Request<Body>) -> Self::Future {
         let handler = self.handler.clone();
         let remote_addr = self.remote_addr;
-        Box::pin(async move { handler.handle(&mut req, remote_addr).await })
+        let vhosts = self.vhosts.clone();
+        #[cfg(feature = "http2")]
+        let https_redirect = self.https_redirect.clone();
+        #[cfg(feature = "http2")]
+        let hsts = self.hsts;
+
+        // Scheme of the connection, used by the rewrites and redirects conditions
+        #[cfg(feature = "http2")]
+        let scheme = if self.tls {
+            Scheme::HTTPS
+        } else {
+            Scheme::HTTP
+        };
+        #[cfg(not(feature = "http2"))]
+        let scheme = Scheme::HTTP;
+        req.extensions_mut().insert(scheme);
+        req.extensions_mut().insert(self.connection.clone());
+
+        // The responses produced before reaching the handler also identify the server
+        let ident = self.handler.opts.load().server_ident.clone();
+        let zero_copy = self.zero_copy.clone();
+        let method = req.method().clone();
+        let version = req.version();
+
+        let resp = server_ident::post_process(ident, async move {
+            // Targets meant for proxies (E.g. `CONNECT`) are never served as file paths
+            {
+                let opts = handler.opts.load();
+                if let Some(result) = request_target::pre_process(&opts, &mut req, remote_addr) {
+                    return result;
+                }
+            }
+
+            // Listeners redirecting to HTTPS don't serve any request
+            #[cfg(feature = "http2")]
+            if let Some(redirect_opts) = https_redirect {
+                return match https_redirect::redirect_to_https(&req, redirect_opts) {
+                    Ok(resp) => Ok(resp),
+                    Err(status) => {
+                        let opts = handler.opts.load();
+                        error_page::error_response(
+                            req.uri(),
+                            req.method(),
+                            &status,
+                            &opts.error_pages,
+                        )
+                    }
+                };
+            }
+
+            // Requests to the virtual hosts not served by the listener are not found
+            if let Some(vhosts) = vhosts {
+                let opts = handler.opts.load_full();
+                let vhosts_opts = opts
+                    .advanced_opts
+                    .as_ref()
+                    .and_then(|advanced| advanced.virtual_hosts.as_deref());
+                if !virtual_hosts::is_served(&req, vhosts_opts, &vhosts) {
+                    return error_page::error_response(
+                        req.uri(),
+                        req.method(),
+                        &StatusCode::NOT_FOUND,
+                        &opts.error_pages,
+                    );
+                }
+            }
+
+            #[cfg(feature = "http2")]
+            if hsts {
+                let mut resp = handler.handle(&mut req, remote_addr).await?;
+                https_redirect::append_hsts(&mut resp);
+                return Ok(resp);
+            }
+            handler.handle(&mut req, remote_addr).await
+        });
+        let resp = zero_copy::post_process(zero_copy, method, version, resp);
+        Box::pin(timeouts::post_process(self.activity.clone(), resp))
     }
 }
 
 /// It defines a Hyper service request builder.
 pub struct RequestServiceBuilder {
     handler: Arc<RequestHandler>,
+    connections: ConnectionCounter,
+    /// Virtual hosts served by the listener, all of them if not specified.
+    vhosts: Option<Arc<[String]>>,
+    /// HTTPS redirect options of the listener, if it redirects all requests.
+    #[cfg(feature = "http2")]
+    https_redirect: Option<Arc<RedirectOpts>>,
+    /// Whether to send the HSTS header with every response.
+    #[cfg(feature = "http2")]
+    hsts: bool,
+    /// Whether the connections use TLS.
+    #[cfg(feature = "http2")]
+    tls: bool,
 }
 
 impl RequestServiceBuilder {
@@ -76,6 +245,14 @@ impl RequestServiceBuilder {
     pub fn new(handler: RequestHandler) -> Self {
         Self {
             handler: Arc::new(handler),
+            connections: ConnectionCounter::default(),
+            vhosts: None,
+            #[cfg(feature = "http2")]
+            https_redirect: None,
+            #[cfg(feature = "http2")]
+            hsts: false,
+            #[cfg(feature = "http2")]
+            tls: false,
         }
     }
 
@@ -84,6 +261,90 @@ impl RequestServiceBuilder {
         RequestService {
             handler: self.handler.clone(),
             remote_addr,
+            vhosts: self.vhosts.clone(),
+            #[cfg(feature = "http2")]
+            https_redirect: self.https_redirect.clone(),
+            #[cfg(feature = "http2")]
+            hsts: self.hsts,
+            #[cfg(feature = "http2")]
+            tls: self.tls,
+            zero_copy: None,
+            activity: None,
+            connection: Arc::new(ConnectionGuard::new(&self.connections)),
+        }
+    }
+}
+
+/// It counts the open connections, since Hyper builds a request service per connection.
+#[derive(Clone, Default)]
+pub struct ConnectionCounter(Arc<Connections>);
+
+#[derive(Default)]
+struct Connections {
+    active: AtomicUsize,
+    /// Notified once all the connections are closed.
+    closed: Notify,
+    /// Whether the open connections were aborted.
+    aborted: AtomicBool,
+    /// Notified once the open connections are aborted.
+    abort: Notify,
+}
+
+impl ConnectionCounter {
+    /// Gets the number of open connections.
+    pub fn active(&self) -> usize {
+        self.0.active.load(Ordering::Relaxed)
+    }
+
+    /// Waits until all the connections are closed.
+    pub(crate) async fn closed(&self) {
+        loop {
+            let closed = self.0.closed.notified();
+            if self.active() == 0 {
+                return;
+            }
+            closed.await;
+        }
+    }
+
+    /// Aborts the connections left open by Hyper (E.g. the tunnels of the upgraded requests).
+    pub(crate) fn abort(&self) {
+        self.0.aborted.store(true, Ordering::Relaxed);
+        self.0.abort.notify_waiters();
+    }
+}
+
+/// It keeps a connection counted until it gets dropped along with its request service
+/// and the tunnels of its upgraded requests.
+pub(crate) struct ConnectionGuard(ConnectionCounter);
+
+impl ConnectionGuard {
+    pub(crate) fn new(counter: &ConnectionCounter) -> Self {
+        counter.0.active.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        crate::metrics::connection_opened();
+        Self(counter.clone())
+    }
+
+    /// Waits until the open connections are aborted, once the shutdown grace period elapsed.
+    pub(crate) async fn aborted(&self) {
+        let counter = &(self.0).0;
+        loop {
+            let abort = counter.abort.notified();
+            if counter.aborted.load(Ordering::Relaxed) {
+                return;
+            }
+            abort.await;
+        }
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        if (self.0).0.active.fetch_sub(1, Ordering::Relaxed) == 1 {
+            (self.0).0.closed.notify_waiters();
         }
+        #[cfg(feature = "metrics")]
+        crate::metrics::connection_closed();
     }
 }
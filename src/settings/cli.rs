@@ -7,7 +7,15 @@
 
 use clap::Parser;
 use hyper::StatusCode;
-use std::{net::IpAddr, path::PathBuf};
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::embedded::EmbeddedAssetsMode;
+use crate::fs::atomic::FsyncPolicy;
+use crate::fs::symlinks::FollowSymlinks;
+use crate::startup_summary::StartupSummaryFormat;
+
+use super::dump::DumpFormat;
 
 #[cfg(feature = "directory-listing")]
 use crate::directory_listing::DirListFmt;
@@ -15,11 +23,15 @@ use crate::directory_listing::DirListFmt;
 #[cfg(feature = "directory-listing-download")]
 use crate::directory_listing_download::DirDownloadFmt;
 
+#[cfg(feature = "subresource-integrity")]
+use crate::sri::SriAlgorithm;
+
 use crate::Result;
 
 /// General server configuration available in CLI and config file options.
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Serialize)]
 #[command(author, about, long_about)]
+#[serde(rename_all = "kebab-case")]
 pub struct General {
     #[arg(long, short = 'a', default_value = "::", env = "SERVER_HOST")]
     /// Host address (E.g 127.0.0.1 or ::1)
@@ -56,6 +68,12 @@ pub struct General {
     /// static-web-server to be sandboxed more completely.
     pub fd: Option<usize>,
 
+    #[arg(long, env = "SERVER_INTERFACE", conflicts_with_all(&["host", "fd"]))]
+    /// Network interface name to bind to instead of a host address (E.g eth0).
+    /// The interface address is resolved at startup, preferring an IPv4 address over an IPv6 one.
+    /// It takes precedence over the host option.
+    pub interface: Option<String>,
+
     #[cfg_attr(
         not(target_family = "wasm"),
         arg(
@@ -80,6 +98,12 @@ pub struct General {
     /// Number of worker threads result should be a number between 1 and 32,768 though it is advised to keep this value on the smaller side.
     pub threads_multiplier: usize,
 
+    #[arg(long, default_value = "0", env = "SERVER_WORKER_THREADS")]
+    /// Fixed number of worker threads, which takes precedence over the threads multiplier.
+    /// It's useful when the number of system CPUs doesn't reflect the CPU limit of the server (E.g. in containers).
+    /// When the value is 0 then the threads multiplier is used (default).
+    pub worker_threads: usize,
+
     #[cfg_attr(
         not(target_family = "wasm"),
         arg(
@@ -101,10 +125,41 @@ pub struct General {
     /// Maximum number of blocking threads
     pub max_blocking_threads: usize,
 
+    #[arg(long, default_value = "0", env = "SERVER_THREAD_STACK_SIZE")]
+    /// Stack size in bytes of the worker and blocking threads. When the value is 0 then the Tokio default (2 MiB) is used.
+    pub thread_stack_size: usize,
+
+    #[arg(long, default_value = "0", env = "SERVER_WORKER_PROCESSES")]
+    /// Number of worker processes to run, each one with its own runtime and sharing the same TCP
+    /// ports via the `SO_REUSEPORT` socket option (Unix only). The worker processes are supervised
+    /// by the main process which restarts them if they crash. The worker threads are divided among
+    /// the worker processes. When the value is 0 or 1 then a single process is used.
+    pub worker_processes: usize,
+
     #[arg(long, short = 'd', default_value = "./public", env = "SERVER_ROOT")]
-    /// Root directory path of static files.
+    /// Root directory path of static files, or a `.tar` or `.zip` archive file whose files are served instead.
     pub root: PathBuf,
 
+    #[arg(
+        long,
+        require_equals(false),
+        value_delimiter(','),
+        action = clap::ArgAction::Set,
+        env = "SERVER_OVERLAY_ROOTS",
+    )]
+    /// List of additional root directories of static files overlaid below the root directory in priority order. A file not found under the root directory is looked up in each of them until it is found (E.g. a theme directory overridden by a site directory).
+    pub overlay_roots: Vec<PathBuf>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "disabled",
+        env = "SERVER_EMBEDDED_ASSETS",
+        ignore_case(true)
+    )]
+    /// Serve the assets embedded into the binary at build time via the `SWS_EMBED_DIR` env. Values: "disabled", "overlay" or "only". The `overlay` mode serves the embedded assets not found under the root directory, while the `only` mode serves only the embedded assets without accessing the root directory.
+    pub embedded_assets: EmbeddedAssetsMode,
+
     #[arg(long, default_value = "./50x.html", env = "SERVER_ERROR_PAGE_50X")]
     /// HTML file path for 50x errors. If the path is not specified or simply doesn't exist
     /// then the server will use a generic HTML error message.
@@ -123,6 +178,57 @@ pub struct General {
     /// A HTML file path (not relative to the root) used for GET requests when the requested path doesn't exist. The fallback page is served with a 200 status code, useful when using client routers. If the path doesn't exist then the feature is not activated.
     pub page_fallback: PathBuf,
 
+    #[cfg(feature = "fallback-page")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_FALLBACK_PAGE_SOFT_404",
+    )]
+    /// Mark the responses served by the fallback page as soft 404 errors via a `X-Soft-404: true` response header and a `soft_404=true` log field. Useful to let analytics and crawlers know that the requested path doesn't exist.
+    pub page_fallback_soft404: bool,
+
+    #[cfg(feature = "fallback-page")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
+    #[arg(
+        long,
+        default_value = "200",
+        value_parser = value_parser_status_code,
+        env = "SERVER_FALLBACK_PAGE_STATUS"
+    )]
+    /// HTTP status code of the responses served by the fallback page, either 200 or 404 to keep the original status. Default 200.
+    #[serde(serialize_with = "http_serde::status_code::serialize")]
+    pub page_fallback_status: StatusCode,
+
+    #[cfg(feature = "fallback-page")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
+    #[arg(
+        long,
+        default_value = "GET",
+        value_delimiter(','),
+        env = "SERVER_FALLBACK_PAGE_METHODS"
+    )]
+    /// List of request methods (separated by commas) the fallback page applies to, either `GET` or `HEAD`. Default `GET`.
+    pub page_fallback_methods: Vec<String>,
+
+    #[cfg(feature = "fallback-page")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_FALLBACK_PAGE_HTML_ONLY",
+    )]
+    /// Serve the fallback page only for requests whose `Accept` header includes `text/html`, so requests of other content types (E.g. JSON files) get the original 404 response.
+    pub page_fallback_html_only: bool,
+
     #[arg(long, short = 'g', default_value = "error", env = "SERVER_LOG_LEVEL")]
     /// Specify a logging level in lower case. Values: error, warn, info, debug or trace
     pub log_level: String,
@@ -139,6 +245,32 @@ pub struct General {
     /// Enable or disable ANSI escape codes for colors and other text formatting of the log output.
     pub log_with_ansi: bool,
 
+    #[arg(long, env = "SERVER_LOG_FILE")]
+    /// Append the log output to the given file instead of writing it to the standard error, useful when running as a service without a console (E.g. a Windows Service).
+    pub log_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "text",
+        env = "SERVER_STARTUP_SUMMARY",
+        ignore_case(true)
+    )]
+    /// Format of the summary printed to the standard output once the server is ready, listing the listener URLs, TLS, root directories and enabled features. Values: "text" (compact overview) or "json" (single JSON line for scripts).
+    pub startup_summary: StartupSummaryFormat,
+
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_QUIET",
+    )]
+    /// Don't print the startup summary to the standard output.
+    pub quiet: bool,
+
     #[arg(
         long,
         short = 'c',
@@ -240,6 +372,40 @@ pub struct General {
     /// List of host names or IPs allowed to redirect from. HTTP requests must contain the HTTP 'Host' header and match against this list. It depends on "https_redirect" to be enabled.
     pub https_redirect_from_hosts: String,
 
+    #[arg(long, default_value = "301", env = "SERVER_HTTPS_REDIRECT_STATUS")]
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    /// HTTP status code of the HTTPS redirects, either `301` (Moved Permanently) or `308` (Permanent Redirect) which preserves the request method and body.
+    pub https_redirect_status: u16,
+
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_HTTPS_REDIRECT_PRESERVE_HOST"
+    )]
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    /// Redirect to the requested host name (which must be one of the "https_redirect_from_hosts") instead of the "https_redirect_host".
+    pub https_redirect_preserve_host: bool,
+
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_HTTPS_REDIRECT_HSTS"
+    )]
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    /// Send a preload-grade "Strict-Transport-Security: max-age=63072000; includeSubDomains; preload" header with every HTTPS response. It depends on "https_redirect" or a listener redirecting to HTTPS.
+    pub https_redirect_hsts: bool,
+
     #[arg(long, default_value = "index.html", env = "SERVER_INDEX_FILES")]
     /// List of files that will be used as an index for requests ending with the slash character (‘/’).
     /// Files are checked in the specified order.
@@ -313,86 +479,444 @@ pub struct General {
             feature = "compression-deflate"
         )))
     )]
-    #[arg(
-        long,
-        default_value = "false",
-        default_missing_value("true"),
-        num_args(0..=1),
-        require_equals(false),
-        action = clap::ArgAction::Set,
-        env = "SERVER_COMPRESSION_STATIC",
+    #[arg(long, env = "SERVER_COMPRESSION_LEVEL_GZIP")]
+    /// Gzip and Deflate compression level (`0` to `9`) taking precedence over the general compression level.
+    pub compression_level_gzip: Option<i32>,
+
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
     )]
-    /// Look up the pre-compressed file variant (`.gz`, `.br` or `.zst`) on disk of a requested file and serves it directly if available.
-    /// The compression type is determined by the `Accept-Encoding` header.
-    pub compression_static: bool,
+    #[arg(long, env = "SERVER_COMPRESSION_LEVEL_BROTLI")]
+    /// Brotli compression level (`0` to `11`) taking precedence over the general compression level.
+    pub compression_level_brotli: Option<i32>,
 
-    #[cfg(feature = "directory-listing")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
-    #[arg(
-        long,
-        short = 'z',
-        default_value = "false",
-        default_missing_value("true"),
-        num_args(0..=1),
-        require_equals(false),
-        action = clap::ArgAction::Set,
-        env = "SERVER_DIRECTORY_LISTING",
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
     )]
-    /// Enable directory listing for all requests ending with the slash character (‘/’).
-    pub directory_listing: bool,
+    #[arg(long, env = "SERVER_COMPRESSION_LEVEL_ZSTD")]
+    /// Zstd compression level (`1` to `22`) taking precedence over the general compression level.
+    pub compression_level_zstd: Option<i32>,
 
-    #[cfg(feature = "directory-listing")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
-    #[arg(
-        long,
-        requires_if("true", "directory_listing"),
-        default_value = "6",
-        env = "SERVER_DIRECTORY_LISTING_ORDER"
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
     )]
-    /// Specify a default code number to order directory listing entries per `Name`, `Last modified` or `Size` attributes (columns). Code numbers supported: 0 (Name asc), 1 (Name desc), 2 (Last modified asc), 3 (Last modified desc), 4 (Size asc), 5 (Size desc). Default 6 (unordered)
-    pub directory_listing_order: u8,
+    #[arg(long, default_value = "0", env = "SERVER_COMPRESSION_MIN_SIZE")]
+    /// Minimum response body size in bytes to apply on-the-fly compression.
+    /// Responses with a smaller `Content-Length` are not compressed. Use `0` to disable it.
+    pub compression_min_size: u64,
 
-    #[cfg(feature = "directory-listing")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
-    #[arg(
-        long,
-        value_enum,
-        requires_if("true", "directory_listing"),
-        default_value = "html",
-        env = "SERVER_DIRECTORY_LISTING_FORMAT",
-        ignore_case(true)
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
     )]
-    /// Specify a content format for directory listing entries. Formats supported: "html" or "json". Default "html".
-    pub directory_listing_format: DirListFmt,
+    #[arg(long, default_value = "", env = "SERVER_COMPRESSION_MIME_TYPES")]
+    /// Comma-separated list of MIME types allowed for on-the-fly compression (E.g `text/*,application/json`).
+    /// If empty then only text-based MIME types are compressed.
+    pub compression_mime_types: String,
 
-    #[cfg(feature = "directory-listing-download")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing-download")))]
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
+    )]
     #[arg(
         long,
-        value_delimiter(','),
-        value_enum,
-        requires_ifs([
-            ("targz", "directory_listing"),
-        ]),
-        require_equals(true),
-        action = clap::ArgAction::Set,
-        env = "SERVER_DIRECTORY_LISTING_DOWNLOAD",
-        ignore_case(true)
+        default_value = "",
+        env = "SERVER_COMPRESSION_EXCLUDE_MIME_TYPES"
     )]
-    /// Specify list of enabled format(s) for directory download. Format supported: `targz`. Default to empty list (disabled).
-    pub directory_listing_download: Vec<DirDownloadFmt>,
+    /// Comma-separated list of MIME types excluded from on-the-fly compression (E.g `image/*,application/zip`).
+    /// It takes precedence over the allowed MIME types.
+    pub compression_exclude_mime_types: String,
 
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
+    )]
     #[arg(
         long,
         default_value = "false",
-        default_value_if("http2", "true", Some("true")),
         default_missing_value("true"),
         num_args(0..=1),
         require_equals(false),
         action = clap::ArgAction::Set,
-        env = "SERVER_SECURITY_HEADERS",
+        env = "SERVER_COMPRESSION_ADAPTIVE",
     )]
-    /// Enable security headers by default when HTTP/2 feature is activated.
+    /// Enable the adaptive compression governor which monitors the process CPU usage and lowers
+    /// the on-the-fly compression level or disables on-the-fly compression under load. Only supported on Linux.
+    pub compression_adaptive: bool,
+
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
+    )]
+    #[arg(
+        long,
+        default_value = "70",
+        env = "SERVER_COMPRESSION_ADAPTIVE_HIGH_LOAD"
+    )]
+    /// Process CPU usage percentage (`1` to `100`) of all available cores from which the adaptive compression
+    /// governor uses the fastest compression level.
+    pub compression_adaptive_high_load: u8,
+
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
+    )]
+    #[arg(
+        long,
+        default_value = "90",
+        env = "SERVER_COMPRESSION_ADAPTIVE_MAX_LOAD"
+    )]
+    /// Process CPU usage percentage (`1` to `100`) of all available cores from which the adaptive compression
+    /// governor disables on-the-fly compression.
+    pub compression_adaptive_max_load: u8,
+
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
+    )]
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_COMPRESSION_STATIC",
+    )]
+    /// Look up the pre-compressed file variant (`.gz`, `.br` or `.zst`) on disk of a requested file and serves it directly if available.
+    /// The compression type is determined by the `Accept-Encoding` header.
+    pub compression_static: bool,
+
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
+    )]
+    #[arg(
+        long,
+        default_value = "br,zstd,gzip",
+        env = "SERVER_COMPRESSION_STATIC_ORDER"
+    )]
+    /// Comma-separated list of pre-compressed file variants to look up in priority order.
+    /// Supported values are `br`, `zstd` and `gzip` (or their file extensions `zst` and `gz`).
+    /// Variants are selected by the `Accept-Encoding` quality values first and then by this order.
+    pub compression_static_order: String,
+
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    #[arg(
+        long,
+        short = 'z',
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_DIRECTORY_LISTING",
+    )]
+    /// Enable directory listing for all requests ending with the slash character (‘/’).
+    pub directory_listing: bool,
+
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    #[arg(long, default_value = "", env = "SERVER_DIRECTORY_LISTING_PATHS")]
+    /// Comma-separated list of glob patterns of the request paths where the directory listing is enabled (E.g. `/downloads/**`), keeping it disabled for the rest of them. It applies to all request paths if empty (default).
+    pub directory_listing_paths: String,
+
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    #[arg(
+        long,
+        requires_if("true", "directory_listing"),
+        default_value = "6",
+        env = "SERVER_DIRECTORY_LISTING_ORDER"
+    )]
+    /// Specify a default code number to order directory listing entries per `Name`, `Last modified` or `Size` attributes (columns). Code numbers supported: 0 (Name asc), 1 (Name desc), 2 (Last modified asc), 3 (Last modified desc), 4 (Size asc), 5 (Size desc). Default 6 (unordered). Deprecated in favor of `--directory-listing-sort`, which takes precedence if not empty.
+    pub directory_listing_order: u8,
+
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    #[arg(long, default_value = "", env = "SERVER_DIRECTORY_LISTING_SORT")]
+    /// Comma-separated list of keys to sort the directory listing entries by default, by priority. Keys supported: `name`, `mtime` or `size`, optionally followed by `:asc` (default) or `:desc` (E.g. `size:desc,name`). The entries are unsorted if empty (default). It can be changed via the `sort` query parameter.
+    pub directory_listing_sort: String,
+
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_DIRECTORY_LISTING_DIRS_FIRST",
+    )]
+    /// Group the directories before the files in directory listings, both sorted by the sort keys. It can be changed via the `dirs-first` query parameter.
+    pub directory_listing_dirs_first: bool,
+
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    #[arg(
+        long,
+        value_enum,
+        requires_if("true", "directory_listing"),
+        default_value = "html",
+        env = "SERVER_DIRECTORY_LISTING_FORMAT",
+        ignore_case(true)
+    )]
+    /// Specify a content format for directory listing entries. Formats supported: "html" or "json". Default "html".
+    pub directory_listing_format: DirListFmt,
+
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    #[arg(
+        long,
+        requires_if("true", "directory_listing"),
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_DIRECTORY_LISTING_DIR_SIZE",
+    )]
+    /// Show the total size of directories in directory listings. Sizes are computed in the background and cached, so they may be missing or outdated until computed.
+    pub directory_listing_dir_size: bool,
+
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    #[arg(
+        long,
+        default_value = "300",
+        env = "SERVER_DIRECTORY_LISTING_DIR_SIZE_TTL"
+    )]
+    /// Time in seconds after which a cached directory size gets refreshed in the background.
+    pub directory_listing_dir_size_ttl: u64,
+
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    #[arg(
+        long,
+        default_value = "100000",
+        env = "SERVER_DIRECTORY_LISTING_DIR_SIZE_MAX_ENTRIES"
+    )]
+    /// Maximum number of files and directories walked to compute the size of a directory. The size of larger directory trees is not shown.
+    pub directory_listing_dir_size_max_entries: u64,
+
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    #[arg(
+        long,
+        default_value = "4",
+        env = "SERVER_DIRECTORY_LISTING_DIR_SIZE_CONCURRENCY"
+    )]
+    /// Maximum number of threads walking a directory tree in parallel to compute the size of a directory.
+    pub directory_listing_dir_size_concurrency: usize,
+
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    #[arg(
+        long,
+        requires_if("true", "directory_listing"),
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_DIRECTORY_LISTING_DETAILS",
+    )]
+    /// Show additional details of directory listing entries like their type (dir, file or symlink), symlink targets, Unix file modes and owners. Intended for admin-oriented listings since it exposes file system details.
+    pub directory_listing_details: bool,
+
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    #[arg(long, default_value = "en", env = "SERVER_DIRECTORY_LISTING_LOCALE")]
+    /// Locale of the directory listing UI strings, date format and size units. Built-in locales: "en", "es", "de", "fr" or "pt". Custom locales can be defined in the configuration file. Use "auto" to select the locale via the `Accept-Language` request header.
+    pub directory_listing_locale: String,
+
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    #[arg(
+        long,
+        require_equals(false),
+        value_delimiter(','),
+        action = clap::ArgAction::Set,
+        env = "SERVER_DIRECTORY_LISTING_HIDE",
+    )]
+    /// List of glob patterns of the files and directories which are not included in directory listings but still served (E.g. `HEADER.html,README.md,*.sig`). Patterns without a slash match the entry names at any level, otherwise they match the paths relative to the root directory.
+    pub directory_listing_hide: Vec<String>,
+
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    #[arg(long, default_value = "", env = "SERVER_DIRECTORY_LISTING_HEADER")]
+    /// Name of a file rendered above the entries of a directory listing if the listed directory contains it (E.g. `HEADER.html`). HTML files are included as they are, markdown files are rendered and other files are shown as plain text. Disabled if empty (default).
+    pub directory_listing_header: String,
+
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    #[arg(long, default_value = "", env = "SERVER_DIRECTORY_LISTING_README")]
+    /// Name of a file rendered below the entries of a directory listing if the listed directory contains it (E.g. `README.md`). HTML files are included as they are, markdown files are rendered and other files are shown as plain text. Disabled if empty (default).
+    pub directory_listing_readme: String,
+
+    #[cfg(feature = "directory-listing-download")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing-download")))]
+    #[arg(
+        long,
+        value_delimiter(','),
+        value_enum,
+        requires_ifs([
+            ("targz", "directory_listing"),
+        ]),
+        require_equals(true),
+        action = clap::ArgAction::Set,
+        env = "SERVER_DIRECTORY_LISTING_DOWNLOAD",
+        ignore_case(true)
+    )]
+    /// Specify list of enabled format(s) for directory download. Format supported: `targz`. Default to empty list (disabled).
+    pub directory_listing_download: Vec<DirDownloadFmt>,
+
+    #[arg(
+        long,
+        default_value = "false",
+        default_value_if("http2", "true", Some("true")),
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_SECURITY_HEADERS",
+    )]
+    /// Enable security headers by default when HTTP/2 feature is activated.
     /// Headers included: "Strict-Transport-Security: max-age=63072000; includeSubDomains; preload" (2 years max-age),
     /// "X-Frame-Options: DENY" and "Content-Security-Policy: frame-ancestors 'self'".
     pub security_headers: bool,
@@ -416,13 +940,26 @@ pub struct General {
     /// Enable cache control headers for incoming requests based on a set of file types. The file type list can be found on `src/control_headers.rs` file.
     pub cache_control_headers: bool,
 
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_TYPE_MAP",
+    )]
+    /// Pre-generate the content type, compressibility and cache control max-age of the file extensions found under the root directory on start up and on configuration reloads, instead of resolving them per request. Files of unknown extensions containing UTF-8 text are served as `text/plain`.
+    pub type_map: bool,
+
     #[cfg(feature = "basic-auth")]
     /// It provides The "Basic" HTTP Authentication scheme using credentials as "user-id:password" pairs. Password must be encoded using the "BCrypt" password-hashing function.
     #[arg(long, default_value = "", env = "SERVER_BASIC_AUTH")]
+    #[serde(skip)]
     pub basic_auth: String,
 
     #[arg(long, short = 'q', default_value = "0", env = "SERVER_GRACE_PERIOD")]
-    /// Defines a grace period in seconds after a `SIGTERM` signal is caught which will delay the server before to shut it down gracefully. The maximum value is 255 seconds.
+    /// Defines a grace period in seconds to drain in-flight connections after a `SIGTERM` signal is caught. New connections are refused immediately and connections still open once the grace period elapses are aborted. Zero waits indefinitely for in-flight connections. The maximum value is 255 seconds.
     pub grace_period: u8,
 
     #[arg(
@@ -478,8 +1015,12 @@ pub struct General {
         action = clap::ArgAction::Set,
         env = "SERVER_TRUSTED_PROXIES",
     )]
-    /// List of IPs to use X-Forwarded-For from. The default is to trust all
-    pub trusted_proxies: Vec<IpAddr>,
+    /// List of IPs, networks in CIDR notation or host names to use X-Forwarded-For from. The host names are resolved and refreshed periodically. The default is to trust all
+    pub trusted_proxies: Vec<String>,
+
+    #[arg(long, default_value = "300", env = "SERVER_TRUSTED_PROXIES_REFRESH")]
+    /// Interval in seconds to resolve again the host names of the trusted proxies. Use `0` to disable it
+    pub trusted_proxies_refresh: u64,
 
     #[arg(
         long,
@@ -493,6 +1034,18 @@ pub struct General {
     /// Check for a trailing slash in the requested directory URI and redirect permanently (308) to the same path with a trailing slash suffix if it is missing.
     pub redirect_trailing_slash: bool,
 
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_REJECT_ABSOLUTE_FORM",
+    )]
+    /// Reject the HTTP/1.x requests using an absolute URL as target (absolute-form) with a 400 status instead of serving them by their path. Note that servers must accept it per RFC 7230, so only enable it if no client or forward proxy sends it.
+    pub reject_absolute_form: bool,
+
     #[arg(
         long,
         default_value = "false",
@@ -505,6 +1058,16 @@ pub struct General {
     /// Ignore hidden files/directories (dotfiles), preventing them to be served and being included in auto HTML index pages (directory listing).
     pub ignore_hidden_files: bool,
 
+    #[arg(
+        long,
+        require_equals(false),
+        value_delimiter(','),
+        action = clap::ArgAction::Set,
+        env = "SERVER_IGNORE_PATTERNS",
+    )]
+    /// List of glob patterns of the files and directories which are never served, responding with a 404 status code instead, and not included in directory listings (E.g. `*.bak,**/.git/**,secret/**`). Patterns without a slash match the file or directory names at any level, otherwise they match the paths relative to the root directory.
+    pub ignore_patterns: Vec<String>,
+
     #[arg(
         long,
         default_value = "false",
@@ -517,6 +1080,16 @@ pub struct General {
     /// Prevent following files or directories if any path name component is a symbolic link.
     pub disable_symlinks: bool,
 
+    #[arg(
+        long,
+        value_enum,
+        default_value = "always",
+        env = "SERVER_FOLLOW_SYMLINKS",
+        ignore_case(true)
+    )]
+    /// Policy of the symbolic links followed when serving files and directories. Values: "always", "same-root" (only symlinks resolving inside the root directory) or "never". The `--disable-symlinks` option takes precedence, being equivalent to "never".
+    pub follow_symlinks: FollowSymlinks,
+
     #[arg(
         long,
         default_value = "false",
@@ -529,6 +1102,14 @@ pub struct General {
     /// Enable markdown content negotiation. When a client sends Accept: text/markdown, serve .md or .html.md files if available.
     pub accept_markdown: bool,
 
+    #[arg(long, default_value = "", env = "SERVER_LANGUAGE_VARIANTS")]
+    /// Enable language content negotiation via a comma-separated list of languages like `en,de,pt-BR=pt`, where the first one is the default. A request for `page.html` serves the `page.<language>.html` variant preferred by the `Accept-Language` header, an optional `=suffix` sets the file name suffix of the variants of a language. Disabled if empty (default).
+    pub language_variants: String,
+
+    #[arg(long, default_value = "", env = "SERVER_IMAGE_VARIANTS")]
+    /// Enable image format negotiation via a comma-separated list of image formats in priority order, supported `avif`, `webp` and `jxl`. A request for `photo.jpg` serves the `photo.jpg.<format>` variant if it exists and its media type is accepted by the `Accept` header. Disabled if empty (default).
+    pub image_variants: String,
+
     #[arg(
         long,
         default_value = "false",
@@ -538,10 +1119,36 @@ pub struct General {
         action = clap::ArgAction::Set,
         env = "SERVER_HEALTH",
     )]
-    /// Add a /health endpoint that doesn't generate any log entry and returns a 200 status code.
-    /// This is especially useful with Kubernetes liveness and readiness probes.
+    /// Add the /health and /health/live (liveness) endpoints returning a 200 status code, and the /health/ready (readiness)
+    /// endpoint which returns a 503 status code if the root directory is not readable or any file of `--health-ready-files` is missing.
+    /// These endpoints don't generate any log entry. This is especially useful with Kubernetes liveness and readiness probes.
     pub health: bool,
 
+    #[arg(
+        long,
+        require_equals(false),
+        value_delimiter(','),
+        action = clap::ArgAction::Set,
+        env = "SERVER_HEALTH_READY_FILES",
+    )]
+    /// List of files (separated by commas) that must exist for the /health/ready endpoint to report the server as ready.
+    /// Relative paths are resolved against the root directory. It requires the `--health` option.
+    pub health_ready_files: Vec<PathBuf>,
+
+    #[cfg(feature = "metrics")]
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_METRICS",
+    )]
+    /// Add a /metrics endpoint that returns a Prometheus metrics response with the request counts
+    /// by method, status and virtual host, the request latencies, the bytes served and the open connections.
+    pub metrics: bool,
+
     #[cfg(all(unix, feature = "experimental"))]
     #[arg(
         long = "experimental-metrics",
@@ -552,7 +1159,7 @@ pub struct General {
         action = clap::ArgAction::Set,
         env = "SERVER_EXPERIMENTAL_METRICS",
     )]
-    /// Add a /metrics endpoint that returns a Prometheus metrics response.
+    /// Add the Tokio runtime metrics to the /metrics endpoint, which also gets enabled (experimental).
     pub experimental_metrics: bool,
 
     #[arg(
@@ -575,6 +1182,7 @@ pub struct General {
         env = "SERVER_MAINTENANCE_MODE_STATUS"
     )]
     /// Provide a custom HTTP status code when entering into maintenance mode. Default 503.
+    #[serde(serialize_with = "http_serde::status_code::serialize")]
     pub maintenance_mode_status: StatusCode,
 
     #[arg(
@@ -587,6 +1195,390 @@ pub struct General {
     /// Provide a custom maintenance mode HTML file. If not provided then a generic message will be displayed.
     pub maintenance_mode_file: PathBuf,
 
+    #[arg(
+        long,
+        require_equals(false),
+        value_delimiter(','),
+        action = clap::ArgAction::Set,
+        env = "SERVER_MAINTENANCE_MODE_ALLOW_IPS"
+    )]
+    /// List of client IPs, networks in CIDR notation or host names bypassing the maintenance mode (E.g. `10.0.0.0/8,192.168.1.7`). Behind the trusted proxies, the client IP is taken from the X-Forwarded-For header.
+    pub maintenance_mode_allow_ips: Vec<String>,
+
+    #[arg(
+        long,
+        require_equals(false),
+        value_delimiter(','),
+        action = clap::ArgAction::Set,
+        env = "SERVER_MAINTENANCE_MODE_ALLOW_PATHS"
+    )]
+    /// List of glob patterns of the request paths bypassing the maintenance mode (E.g. `/health,/admin/**`).
+    pub maintenance_mode_allow_paths: Vec<String>,
+
+    #[arg(long, default_value = "", env = "SERVER_MAINTENANCE_MODE_START")]
+    /// Time when the maintenance mode starts as an RFC 3339 timestamp (E.g. `2025-06-01T22:00:00Z`). If not provided then it starts right away.
+    pub maintenance_mode_start: String,
+
+    #[arg(long, default_value = "", env = "SERVER_MAINTENANCE_MODE_END")]
+    /// Time when the maintenance mode ends as an RFC 3339 timestamp (E.g. `2025-06-02T02:00:00+02:00`). If not provided then it never ends.
+    pub maintenance_mode_end: String,
+
+    #[cfg(feature = "markdown")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "markdown")))]
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_MARKDOWN_RENDER",
+    )]
+    /// Render markdown files (`.md`) as HTML pages when a client requests them with an `Accept: text/html` header. Otherwise, the raw markdown content is served.
+    pub markdown_render: bool,
+
+    #[cfg(feature = "markdown")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "markdown")))]
+    #[arg(
+        long,
+        default_value = "",
+        value_parser = value_parser_pathbuf,
+        requires_if("true", "markdown_render"),
+        env = "SERVER_MARKDOWN_RENDER_TEMPLATE"
+    )]
+    /// Optional HTML wrapper template file used when rendering markdown files. The `{{title}}` and `{{content}}` placeholders are replaced by the file name and the rendered HTML respectively. If not provided then a default template is used.
+    pub markdown_render_template: PathBuf,
+
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_SSI",
+    )]
+    /// Enable server-side includes (SSI) processing for files matching the SSI extensions. Only the `include` (`virtual` or `file`) and `echo` directives are supported.
+    pub ssi: bool,
+
+    #[arg(long, default_value = "shtml", env = "SERVER_SSI_EXTENSIONS")]
+    /// List of comma-separated file extensions whose files will be processed for server-side includes. It depends on "ssi" to be enabled.
+    pub ssi_extensions: String,
+
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_DEV_RELOAD",
+    )]
+    /// Enable the live-reload of the browsers during development. The root directory is watched while any browser listens to the `/__sws/reload` Server-Sent Events endpoint, which sends a `reload` event whenever a file changes. Not meant for production.
+    pub dev_reload: bool,
+
+    #[arg(
+        long,
+        default_value = "true",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_DEV_RELOAD_INJECT",
+    )]
+    /// Inject a script reloading the page on the file changes into the HTML responses. It depends on "dev-reload" to be enabled.
+    pub dev_reload_inject: bool,
+
+    #[arg(long, default_value = "", env = "SERVER_PROBE_ENDPOINT")]
+    /// Add a body-less endpoint at the given path (E.g. `/generate_204`) that doesn't generate any log entry and returns a 204 status code with no caching. Useful for captive-portal checks and uptime probes. Disabled if empty.
+    pub probe_endpoint: String,
+
+    #[arg(long, default_value = "", env = "SERVER_IDENT")]
+    /// Value of the `Server` response header sent with every response including the error pages. Use `name` for the server name only, `full` for the server name and version or any other value to send it as is. Disabled if empty or `off` (default).
+    pub server_ident: String,
+
+    #[arg(long, default_value = "", env = "SERVER_ALT_SVC")]
+    /// Alternative services advertised via the `Alt-Svc` response header as a comma-separated list of `protocol=[host]:port` entries (E.g. `h3=:443,h2=alt.example.com:8443`), useful to advertise an HTTP/3 endpoint or a different host or port fronting the server. Use `clear` to invalidate the alternative services previously advertised. Disabled if empty (default).
+    pub alt_svc: String,
+
+    #[arg(long, default_value = "86400", env = "SERVER_ALT_SVC_MAX_AGE")]
+    /// Number of seconds the alternative services of the `--alt-svc` option are considered fresh by the clients.
+    pub alt_svc_max_age: u64,
+
+    #[arg(long, default_value = "", env = "SERVER_DEFAULT_CHARSET")]
+    /// Charset (E.g. `utf-8`) appended to `text/*` and `application/javascript` content types which don't specify one. Disabled if empty.
+    pub default_charset: String,
+
+    #[arg(long, default_value = "0", env = "SERVER_MAX_URI_LENGTH")]
+    /// Maximum length in bytes of the request URI (path and query). Longer request URIs are rejected with a 414 status code. Disabled if zero (default).
+    pub max_uri_length: usize,
+
+    #[arg(long, default_value = "0", env = "SERVER_MAX_HEADERS_SIZE")]
+    /// Maximum size in bytes of all request headers. Requests with larger headers are rejected with a 431 status code. Disabled if zero (default).
+    pub max_headers_size: usize,
+
+    #[arg(long, default_value = "0", env = "SERVER_MAX_BODY_SIZE")]
+    /// Maximum size in bytes of the request body according to its `Content-Length` header. Larger requests are rejected with a 413 status code. Disabled if zero (default).
+    pub max_body_size: u64,
+
+    #[arg(long, default_value = "0", env = "SERVER_LIMIT_RATE")]
+    /// Maximum bandwidth in bytes per second of every response body. Disabled if zero (default).
+    pub limit_rate: u64,
+
+    #[arg(long, default_value = "0", env = "SERVER_LIMIT_RATE_GLOBAL")]
+    /// Maximum bandwidth in bytes per second of all the response bodies together, which share it in turns. Disabled if zero (default).
+    pub limit_rate_global: u64,
+
+    #[arg(long, default_value = "0", env = "SERVER_MAX_CONNECTIONS")]
+    /// Maximum number of concurrent connections. Connections exceeding it are closed once accepted, after a 503 response on the plain HTTP/1 listeners. Disabled if zero (default).
+    pub max_connections: usize,
+
+    #[arg(long, default_value = "0", env = "SERVER_MAX_CONNECTIONS_PER_IP")]
+    /// Maximum number of concurrent connections per client IP address. Connections exceeding it are closed once accepted, after a 503 response on the plain HTTP/1 listeners. Disabled if zero (default).
+    pub max_connections_per_ip: usize,
+
+    #[cfg(feature = "archive-preview")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "archive-preview")))]
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_ARCHIVE_PREVIEW",
+    )]
+    /// Enable the preview of `.zip`, `.tar.gz` and `.tgz` archives. A `list` query parameter lists the archive members (`list=json` for JSON) and an `extract=<member>` query parameter streams a single member.
+    pub archive_preview: bool,
+
+    #[cfg(feature = "archive-preview")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "archive-preview")))]
+    #[arg(
+        long,
+        default_value = "10000",
+        env = "SERVER_ARCHIVE_PREVIEW_MAX_ENTRIES"
+    )]
+    /// Maximum number of archive members listed or scanned when previewing an archive.
+    pub archive_preview_max_entries: usize,
+
+    #[cfg(feature = "archive-preview")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "archive-preview")))]
+    #[arg(
+        long,
+        default_value = "104857600",
+        env = "SERVER_ARCHIVE_PREVIEW_MAX_SIZE"
+    )]
+    /// Maximum uncompressed size in bytes of an archive member that can be extracted. Larger members are rejected with a 403 status.
+    pub archive_preview_max_size: u64,
+
+    #[cfg(feature = "subresource-integrity")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "subresource-integrity")))]
+    #[arg(long, default_value = "", env = "SERVER_SRI_MANIFEST")]
+    /// URI path of the Subresource Integrity manifest endpoint (E.g. `/sri.json`), which responds with the `integrity` values of the matching assets under the root directory as JSON. Disabled if empty (default).
+    pub sri_manifest: String,
+
+    #[cfg(feature = "subresource-integrity")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "subresource-integrity")))]
+    #[arg(
+        long,
+        value_enum,
+        default_value = "sha384",
+        env = "SERVER_SRI_ALGORITHM"
+    )]
+    /// Hash algorithm of the Subresource Integrity digests.
+    pub sri_algorithm: SriAlgorithm,
+
+    #[cfg(feature = "subresource-integrity")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "subresource-integrity")))]
+    #[arg(long, default_value = "js,mjs,css", env = "SERVER_SRI_EXTENSIONS")]
+    /// Comma-separated list of the file extensions included in the Subresource Integrity manifest.
+    pub sri_extensions: String,
+
+    #[cfg(feature = "s3-origin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+    #[arg(long, default_value = "", env = "SERVER_S3_ORIGIN")]
+    /// Path-style URL of an S3-compatible bucket whose objects are served instead of the root directory, optionally followed by a key prefix (E.g. `https://s3.eu-west-1.amazonaws.com/my-bucket/site`). The objects are cached on the local disk. Disabled if empty (default).
+    pub s3_origin: String,
+
+    #[cfg(feature = "s3-origin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+    #[arg(long, default_value = "us-east-1", env = "SERVER_S3_REGION")]
+    /// Region of the S3 origin bucket, used to sign the origin requests.
+    pub s3_region: String,
+
+    #[cfg(feature = "s3-origin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+    #[arg(long, default_value = "", env = "SERVER_S3_ACCESS_KEY_ID")]
+    /// Access key ID of the S3 origin. The origin requests are not signed if empty (E.g. for a public bucket).
+    pub s3_access_key_id: String,
+
+    #[cfg(feature = "s3-origin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+    #[arg(long, default_value = "", env = "SERVER_S3_SECRET_ACCESS_KEY")]
+    #[serde(skip)]
+    /// Secret access key of the S3 origin.
+    pub s3_secret_access_key: String,
+
+    #[cfg(feature = "s3-origin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+    #[arg(
+        long,
+        default_value = "",
+        value_parser = value_parser_pathbuf,
+        env = "SERVER_S3_CACHE_DIR"
+    )]
+    /// Directory where the objects of the S3 origin are cached, only accessible by the current user. A `sws-s3-cache-<uid>` directory under the system temporary directory is used if empty (default).
+    pub s3_cache_dir: PathBuf,
+
+    #[cfg(feature = "s3-origin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+    #[arg(long, default_value = "60", env = "SERVER_S3_CACHE_TTL")]
+    /// Time in seconds an object of the S3 origin is served from the disk cache before being revalidated with a conditional request.
+    pub s3_cache_ttl: u64,
+
+    #[cfg(feature = "s3-origin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+    #[arg(long, default_value = "1073741824", env = "SERVER_S3_CACHE_MAX_SIZE")]
+    /// Maximum size in bytes of the objects of the S3 origin kept in the disk cache. The least recently used objects are evicted beyond it. A `0` value disables the limit.
+    pub s3_cache_max_size: u64,
+
+    #[cfg(feature = "s3-origin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+    #[arg(
+        long,
+        default_value = "",
+        value_parser = value_parser_pathbuf,
+        env = "SERVER_S3_CA_FILE"
+    )]
+    /// PEM bundle of the CA certificates trusted by the `https` S3 origin requests. The system bundle is used if empty (default).
+    pub s3_ca_file: PathBuf,
+
+    #[arg(long, default_value = "0", env = "SERVER_READ_HEADER_TIMEOUT")]
+    /// Maximum time in seconds to read the headers of an HTTP/1 request. Connections exceeding it are closed. Disabled if zero (default).
+    pub read_header_timeout: u64,
+
+    #[arg(long, default_value = "0", env = "SERVER_IDLE_TIMEOUT")]
+    /// Maximum time in seconds a connection can stay without reading or writing any data between requests, including idle keep-alive connections. Connections exceeding it are closed. Disabled if zero (default).
+    pub idle_timeout: u64,
+
+    #[arg(long, default_value = "0", env = "SERVER_WRITE_TIMEOUT")]
+    /// Maximum time in seconds to write the data of a response to the client since its first byte until it's flushed. Connections exceeding it are closed. Disabled if zero (default).
+    pub write_timeout: u64,
+
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_ZERO_COPY",
+    )]
+    /// Send large files with zero-copy (`sendfile`) on the plain HTTP/1 connections, when their responses are not compressed. Only supported on Linux.
+    pub zero_copy: bool,
+
+    #[cfg(feature = "io-uring")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "io-uring")))]
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_IO_URING",
+    )]
+    /// Read the files served via io_uring (experimental). The regular file reads are used instead if io_uring is not available. Only supported on Linux.
+    pub io_uring: bool,
+
+    #[cfg(feature = "mmap")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mmap")))]
+    #[arg(long, default_value = "0", env = "SERVER_MMAP_MAX_SIZE")]
+    /// Maximum size in bytes of the files (or ranges) served via memory maps instead of being read by chunks. The files are leased while being mapped, so they can't be truncated, and read as usual if they can't be leased. Only supported on Linux. Disabled if zero (default).
+    pub mmap_max_size: u64,
+
+    #[arg(long, default_value = "0", env = "SERVER_REQUEST_TIMEOUT")]
+    /// Maximum time in seconds to resolve the response of a request, not including writing its body. Requests exceeding it get a 504 status code and their pending filesystem work is abandoned. Disabled if zero (default).
+    pub request_timeout: u64,
+
+    #[arg(long, default_value = "0", env = "SERVER_NOT_FOUND_CACHE_TTL")]
+    /// Time in seconds to remember the request paths not found, so repeated requests for them (E.g. from vulnerability scanners) get a 404 status code without looking up the file system again. Disabled if zero (default).
+    pub not_found_cache_ttl: u64,
+
+    #[arg(long, default_value = "10000", env = "SERVER_NOT_FOUND_CACHE_CAPACITY")]
+    /// Maximum number of request paths remembered by the not found cache. The oldest ones are evicted first.
+    pub not_found_cache_capacity: usize,
+
+    #[arg(long, default_value = "", env = "SERVER_CONTROL_LISTEN")]
+    /// Enable the control API on a separate listener, either a loopback address (E.g. `127.0.0.1:8788`) or a Unix domain socket path prefixed by `unix:` (E.g. `unix:/run/sws/control.sock`). It allows toggling the maintenance mode, reloading the configuration, flushing caches and dumping the current settings at runtime. Disabled if empty.
+    pub control_listen: String,
+
+    #[arg(long, default_value = "", env = "SERVER_CONTROL_TOKEN")]
+    #[serde(skip)]
+    /// Bearer token required by the control API requests changing the server state, sent via the `Authorization: Bearer <token>` header. If empty (default), these requests require a non-form `Content-Type` header (E.g. `application/json`) instead.
+    pub control_token: String,
+
+    #[arg(
+        long,
+        default_value = "",
+        value_parser = value_parser_pathbuf,
+        env = "SERVER_CONFIG_AUDIT_LOG"
+    )]
+    /// File path of an append-only audit log recording the configuration reloads and runtime changes as JSON lines, along with what triggered them and the settings that changed. Disabled if empty.
+    pub config_audit_log: PathBuf,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "full",
+        env = "SERVER_FSYNC_POLICY",
+        ignore_case(true)
+    )]
+    /// Policy of the `fsync` calls when writing files like the config audit log or the `schema` and `migrate-config` command outputs, which are always written atomically. Values: "full" (file and directory), "file" (file contents only) or "off".
+    pub fsync_policy: FsyncPolicy,
+
+    #[cfg(unix)]
+    #[arg(long, env = "SERVER_SOCKET")]
+    /// Listen on a Unix domain socket path (E.g. `/run/sws.sock`) in addition to the TCP address, or instead of it along with the `--socket-only` option.
+    pub socket: Option<PathBuf>,
+
+    #[cfg(unix)]
+    #[arg(long, env = "SERVER_SOCKET_MODE")]
+    /// Permissions of the Unix domain socket file in octal notation (E.g. `660`). The default ones are given by the process umask.
+    pub socket_mode: Option<String>,
+
+    #[cfg(unix)]
+    #[arg(long, env = "SERVER_SOCKET_OWNER")]
+    /// Owner of the Unix domain socket file as numeric user and group IDs in the form `UID[:GID]` or `:GID` (E.g. `1000:33`).
+    pub socket_owner: Option<String>,
+
+    #[cfg(unix)]
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_SOCKET_ONLY",
+    )]
+    /// Listen only on the Unix domain socket of the `--socket` option without binding the TCP address.
+    pub socket_only: bool,
+
+    #[arg(
+        long,
+        default_value = "false",
+        default_missing_value("true"),
+        num_args(0..=1),
+        require_equals(false),
+        action = clap::ArgAction::Set,
+        env = "SERVER_MEDIA_STREAMING",
+    )]
+    /// Enable the media streaming preset for HLS (`.m3u8`) and DASH (`.mpd`) streams.
+    /// Playlists and manifests are sent with their content types and "Cache-Control: no-cache",
+    /// while media segments next to them (E.g. `.ts` or `.m4s`) are sent with their content types and a long-term "Cache-Control" header.
+    pub media_streaming: bool,
+
     //
     // Windows specific arguments and commands
     //
@@ -607,6 +1599,7 @@ pub struct General {
     // Subcommands
     #[command(subcommand)]
     /// Subcommands for additional maintenance tasks, like installing and uninstalling the SWS Windows Service and generation of completions and man pages
+    #[serde(skip)]
     pub commands: Option<Commands>,
 
     #[arg(
@@ -617,6 +1610,7 @@ pub struct General {
     )]
     #[doc(hidden)]
     /// Print version info and exit.
+    #[serde(skip)]
     pub version: bool,
 }
 
@@ -633,6 +1627,11 @@ pub enum Commands {
     #[command(name = "uninstall")]
     Uninstall {},
 
+    /// Manage the Windows Service of the web server: install, uninstall, start, stop or show its status.
+    #[cfg(windows)]
+    #[command(name = "service", subcommand)]
+    Service(ServiceCommands),
+
     /// Generate man pages and shell completions
     #[command(name = "generate")]
     Generate {
@@ -645,6 +1644,108 @@ pub enum Commands {
         /// Path to write generated artifacts to
         out_dir: PathBuf,
     },
+
+    /// Print the JSON Schema of the TOML configuration file
+    #[command(name = "schema")]
+    Schema {
+        /// Optional file path to write the JSON Schema to instead of printing it
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+
+    /// Start the server on an ephemeral local port and check it end to end with a battery of requests
+    #[command(name = "selftest")]
+    Selftest {},
+
+    /// Validate the configuration without starting the server, exiting with an error if it's invalid
+    #[command(name = "check", visible_alias = "validate")]
+    Check {},
+
+    /// Rewrite the configuration file replacing deprecated keys with their current names
+    #[command(name = "migrate-config")]
+    MigrateConfig {
+        /// Optional file path to write the migrated configuration to instead of printing it
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+
+    /// Inspect the effective configuration of the web server
+    #[command(name = "config", subcommand)]
+    Config(ConfigCommands),
+}
+
+#[derive(Debug, clap::Subcommand)]
+/// Subcommands inspecting the configuration of the web server
+pub enum ConfigCommands {
+    /// Print the configuration resolved from the arguments, environment variables, config file and defaults, with the secrets redacted
+    #[command(name = "dump")]
+    Dump {
+        /// Output format of the configuration
+        #[arg(long, short = 'f', value_enum, default_value_t)]
+        format: DumpFormat,
+        /// Optional file path to write the configuration to instead of printing it
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+}
+
+#[cfg(windows)]
+#[derive(Debug, clap::Subcommand)]
+/// Subcommands managing the Windows Service of the web server
+pub enum ServiceCommands {
+    /// Install the Windows Service running the web server with the current configuration file.
+    #[command(name = "install")]
+    Install(ServiceInstallOpts),
+
+    /// Stop and uninstall the Windows Service.
+    #[command(name = "uninstall")]
+    Uninstall {},
+
+    /// Start the Windows Service and wait for it to be running.
+    #[command(name = "start")]
+    Start {},
+
+    /// Stop the Windows Service and wait for it to be stopped.
+    #[command(name = "stop")]
+    Stop {},
+
+    /// Show the state and the configuration of the Windows Service.
+    #[command(name = "status")]
+    Status {},
+}
+
+#[cfg(windows)]
+#[derive(Debug, Default, clap::Args)]
+/// Options of the Windows Service installation
+pub struct ServiceInstallOpts {
+    /// How the service is started: `auto` on system boot, `manual` on demand or `disabled`.
+    #[arg(long, value_enum, default_value_t)]
+    pub startup: ServiceStartup,
+
+    /// File to append the log output of the service to, since the services have no console.
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
+
+    /// Restart the service on failure after the given number of seconds. Not restarted if not provided.
+    #[arg(long)]
+    pub restart_delay: Option<u64>,
+
+    /// Number of seconds without failures after which the failure count is reset, so the restarts apply again.
+    #[arg(long, default_value_t = 86400, requires = "restart_delay")]
+    pub restart_reset: u64,
+}
+
+#[cfg(windows)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+/// Start type of the Windows Service
+pub enum ServiceStartup {
+    /// Started on system boot
+    Auto,
+    /// Started on demand
+    #[default]
+    Manual,
+    /// Can't be started
+    Disabled,
 }
 
 fn value_parser_pathbuf(s: &str) -> Result<PathBuf, String> {
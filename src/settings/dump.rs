@@ -0,0 +1,192 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Dump of the effective server configuration.
+//!
+//! The `general` settings resolved from the arguments, the environment variables,
+//! the config file and the defaults are printed along with the `advanced` and `listeners`
+//! options of the config file and its included files, with the secret values redacted.
+//! The `advanced` and `listeners` options are printed before the environment variables
+//! interpolation, so the values of the variables referenced are never revealed.
+//!
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::Settings;
+use super::include::read_toml_files_verbatim;
+use crate::{Context, Result};
+
+/// Value replacing the secrets of the configuration dump.
+pub const REDACTED: &str = "[redacted]";
+
+/// Keys whose values are secrets, compared case-insensitively (E.g. header names).
+const SECRET_KEYS: [&str; 5] = [
+    "basic-auth",
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+/// Words of the keys whose values are secrets (E.g. `s3-secret-access-key`).
+const SECRET_WORDS: [&str; 3] = ["secret", "password", "token"];
+
+/// Output format of the configuration dump.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DumpFormat {
+    /// TOML with the same layout as the config file
+    #[default]
+    Toml,
+    /// Pretty-printed JSON
+    Json,
+}
+
+/// Effective configuration with the sections in the order of the config file.
+#[derive(Serialize)]
+struct Config {
+    general: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    advanced: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    listeners: Option<Value>,
+}
+
+/// Returns the effective configuration of the given settings in the given format.
+pub fn dump(settings: &Settings, format: DumpFormat) -> Result<String> {
+    let general = &settings.general;
+
+    let mut config = Config {
+        general: serde_json::to_value(general)
+            .with_context(|| "unable to serialize the general settings")?,
+        advanced: None,
+        listeners: None,
+    };
+
+    // The secrets are not serialized, so they are only reported as set
+    #[cfg(feature = "basic-auth")]
+    if !general.basic_auth.is_empty() {
+        config.general["basic-auth"] = REDACTED.into();
+    }
+    #[cfg(feature = "s3-origin")]
+    if !general.s3_secret_access_key.is_empty() {
+        config.general["s3-secret-access-key"] = REDACTED.into();
+    }
+    if !general.control_token.is_empty() {
+        config.general["control-token"] = REDACTED.into();
+    }
+
+    // The advanced options and the listeners are only defined by the config files,
+    // whose `${VAR}` references are kept since any of them may be a secret
+    if general.config_file.is_file() {
+        let file = read_toml_files_verbatim(&general.config_file)?;
+        for (key, section) in [
+            ("advanced", &mut config.advanced),
+            ("listeners", &mut config.listeners),
+        ] {
+            if let Some(value) = file.get(key) {
+                let value = serde_json::to_value(value)
+                    .with_context(|| format!("unable to serialize the {key} settings"))?;
+                *section = Some(value);
+            }
+        }
+    }
+
+    for section in [
+        Some(&mut config.general),
+        config.advanced.as_mut(),
+        config.listeners.as_mut(),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        redact(section);
+        remove_nulls(section);
+    }
+
+    match format {
+        DumpFormat::Toml => toml::to_string_pretty(&config)
+            .with_context(|| "unable to serialize the configuration as toml"),
+        DumpFormat::Json => serde_json::to_string_pretty(&config)
+            .map(|json| json + "\n")
+            .with_context(|| "unable to serialize the configuration as json"),
+    }
+}
+
+/// Checks if the value of the given key is a secret.
+fn is_secret(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SECRET_KEYS.contains(&key.as_str()) || SECRET_WORDS.iter().any(|word| key.contains(word))
+}
+
+/// Replaces the secret values found recursively by the redacted placeholder.
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match value {
+                    Value::Null => {}
+                    Value::String(text) if text.is_empty() => {}
+                    _ if is_secret(key) => *value = REDACTED.into(),
+                    _ => redact(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Removes the unset options recursively, since TOML has no null values.
+fn remove_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, value| !value.is_null());
+            map.values_mut().for_each(remove_nulls);
+        }
+        Value::Array(values) => values.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{REDACTED, is_secret, redact, remove_nulls};
+
+    #[test]
+    fn test_is_secret() {
+        assert!(is_secret("basic-auth"));
+        assert!(is_secret("Authorization"));
+        assert!(is_secret("s3-secret-access-key"));
+        assert!(is_secret("X-Api-Token"));
+        assert!(!is_secret("s3-access-key-id"));
+        assert!(!is_secret("http2-tls-key"));
+    }
+
+    #[test]
+    fn test_redact() {
+        let mut value = json!({
+            "general": {"basic-auth": "user:hash", "root": "./public", "log-file": null},
+            "advanced": {
+                "headers": [{"source": "**", "headers": {"Authorization": "Bearer abc", "X-Frame-Options": "DENY"}}],
+                "virtual-hosts": [{"host": "localhost", "basic-auth": ""}],
+            },
+        });
+        redact(&mut value);
+        remove_nulls(&mut value);
+        assert_eq!(
+            value,
+            json!({
+                "general": {"basic-auth": REDACTED, "root": "./public"},
+                "advanced": {
+                    "headers": [{"source": "**", "headers": {"Authorization": REDACTED, "X-Frame-Options": "DENY"}}],
+                    "virtual-hosts": [{"host": "localhost", "basic-auth": ""}],
+                },
+            })
+        );
+    }
+}
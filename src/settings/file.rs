@@ -6,21 +6,34 @@
 //! The server configuration file options (manifest)
 
 use headers::HeaderMap;
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
 use serde::Deserialize;
 use serde_repr::{Deserialize_repr, Serialize_repr};
-use std::net::IpAddr;
 use std::path::Path;
-use std::{collections::BTreeSet, path::PathBuf};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
 
 #[cfg(feature = "directory-listing")]
 use crate::directory_listing::DirListFmt;
+#[cfg(feature = "directory-listing")]
+use crate::directory_listing_locale::SizeUnits;
 
 #[cfg(feature = "directory-listing-download")]
 use crate::directory_listing_download::DirDownloadFmt;
 
+#[cfg(feature = "subresource-integrity")]
+use crate::sri::SriAlgorithm;
+
+use crate::embedded::EmbeddedAssetsMode;
+use crate::fs::atomic::FsyncPolicy;
+use crate::fs::symlinks::FollowSymlinks;
+use crate::startup_summary::StartupSummaryFormat;
 use crate::{Context, Result, helpers};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 /// Log level variants.
 pub enum LogLevel {
@@ -66,7 +79,7 @@ impl LogLevel {
         feature = "compression-deflate"
     )))
 )]
-#[derive(clap::ValueEnum, Debug, Serialize, Deserialize, Copy, Clone)]
+#[derive(clap::ValueEnum, Debug, Serialize, Deserialize, Copy, Clone, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 /// Compression level settings.
 pub enum CompressionLevel {
@@ -97,8 +110,15 @@ pub enum CompressionLevel {
 )]
 impl CompressionLevel {
     /// Converts to a library-specific compression level specification, using
-    /// given numeric level as default.
-    pub(crate) fn into_algorithm_level(self, default: i32) -> async_compression::Level {
+    /// the given algorithm-specific level if any or the given numeric level as default.
+    pub(crate) fn into_algorithm_level(
+        self,
+        algorithm_level: Option<i32>,
+        default: i32,
+    ) -> async_compression::Level {
+        if let Some(level) = algorithm_level {
+            return async_compression::Level::Precise(level);
+        }
         match self {
             Self::Fastest => async_compression::Level::Fastest,
             Self::Best => async_compression::Level::Best,
@@ -107,15 +127,26 @@ impl CompressionLevel {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 /// Represents an HTTP headers map.
 pub struct Headers {
     /// Header source.
     pub source: String,
-    #[serde(rename(deserialize = "headers"), with = "http_serde::header_map")]
+    #[serde(
+        rename(deserialize = "headers"),
+        with = "http_serde::header_map",
+        default
+    )]
+    #[schemars(with = "std::collections::BTreeMap<String, String>")]
     /// headers list.
     pub headers: HeaderMap,
+    /// Optional names of the headers to remove.
+    pub remove: Option<Vec<String>>,
+    /// Optional response status codes the entry applies to.
+    pub status: Option<Vec<u16>>,
+    /// Optional glob pattern of the response content type the entry applies to.
+    pub content_type: Option<String>,
 }
 
 #[derive(Debug, Serialize_repr, Deserialize_repr, Clone)]
@@ -128,7 +159,22 @@ pub enum RedirectsKind {
     Temporary = 302,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+// The kinds are (de)serialized as their status codes instead of their names
+impl JsonSchema for RedirectsKind {
+    fn schema_name() -> Cow<'static, str> {
+        "RedirectsKind".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "Redirect type either 301 (Moved Permanently) or 302 (Found).",
+            "type": "integer",
+            "enum": [301, 302]
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 /// Represents redirects types.
 pub struct Redirects {
@@ -136,25 +182,179 @@ pub struct Redirects {
     pub host: Option<String>,
     /// Source of the redirect.
     pub source: String,
+    /// Whether the source is a regex pattern instead of a glob pattern.
+    pub regex: Option<bool>,
     /// Redirect destination.
     pub destination: String,
     /// Redirect type either 301 (Moved Permanently) or 302 (Found).
     pub kind: RedirectsKind,
+    /// Optional scheme (`http` or `https`) the request should use.
+    pub scheme: Option<String>,
+    /// Optional request headers whose values should match their glob patterns.
+    pub headers: Option<BTreeMap<String, String>>,
+    /// Optional query parameters whose values should match their glob patterns.
+    pub query: Option<BTreeMap<String, String>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 /// Represents rewrites types.
 pub struct Rewrites {
+    /// Optional host to match against an incoming URI host if specified
+    pub host: Option<String>,
     /// Source of the rewrite.
     pub source: String,
+    /// Whether the source is a regex pattern instead of a glob pattern.
+    pub regex: Option<bool>,
     /// Rewrite destination.
     pub destination: String,
     /// Optional redirect type either 301 (Moved Permanently) or 302 (Found).
     pub redirect: Option<RedirectsKind>,
+    /// Optional scheme (`http` or `https`) the request should use.
+    pub scheme: Option<String>,
+    /// Optional request headers whose values should match their glob patterns.
+    pub headers: Option<BTreeMap<String, String>>,
+    /// Optional query parameters whose values should match their glob patterns.
+    pub query: Option<BTreeMap<String, String>>,
+    /// Whether the rewritten URL is matched against the rewrites again.
+    pub chain: Option<bool>,
+    /// Whether the request query string is appended to the destination query string.
+    pub append_query: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+/// Represents response status rewrites.
+pub struct StatusRewrites {
+    /// Source of the status rewrite.
+    pub source: String,
+    /// Response status code to match.
+    pub status: u16,
+    /// Response status code to rewrite to. It must be a 4xx or 5xx status code.
+    pub to: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+/// Represents forced content types and downloads by glob pattern.
+pub struct ContentTypes {
+    /// Source glob pattern matched against the request URI path.
+    pub source: String,
+    /// Optional MIME type forced for the matching files.
+    pub content_type: Option<String>,
+    /// Optional flag to force the download of the matching files.
+    pub download: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+/// Represents the resolution of request paths by trying candidate paths in order.
+pub struct TryFiles {
+    /// Source glob pattern matched against the request URI path.
+    pub source: String,
+    /// Candidate paths tried in order, `$uri` being replaced by the request URI path.
+    pub files: Vec<String>,
+    /// Optional path used if none of the candidate paths exists.
+    pub fallback: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+/// Represents a `Cache-Control` policy for the request URI paths matching a glob pattern.
+pub struct CacheControl {
+    /// Source glob pattern matched against the request URI path.
+    pub source: String,
+    /// The `max-age` directive in seconds.
+    pub max_age: Option<u64>,
+    /// The `immutable` directive.
+    pub immutable: Option<bool>,
+    /// The `no-cache` directive.
+    pub no_cache: Option<bool>,
+    /// The `no-store` directive.
+    pub no_store: Option<bool>,
+    /// The `stale-while-revalidate` directive in seconds.
+    pub stale_while_revalidate: Option<u64>,
+    /// The `stale-if-error` directive in seconds.
+    pub stale_if_error: Option<u64>,
+    /// Whether to append an `Expires` header matching the `max-age` directive.
+    pub expires: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+/// Represents the MIME type sniffing protection of the files matching a glob pattern.
+pub struct ContentTypeOptions {
+    /// Source glob pattern matched against the request URI path.
+    pub source: String,
+    /// Whether to send the `X-Content-Type-Options: nosniff` header.
+    pub nosniff: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+/// Represents a CORS policy for the request URI paths under a path prefix.
+pub struct Cors {
+    /// Request URI path prefix where the policy applies. E.g. `/api`
+    pub path: String,
+    /// Allowed origins (E.g. `https://example.com`) or `*` for any origin.
+    pub allow_origins: Vec<String>,
+    /// Allowed request methods. Default `GET`, `HEAD` and `OPTIONS`.
+    pub allow_methods: Option<Vec<String>>,
+    /// Allowed request headers. Default `origin` and `content-type`.
+    pub allow_headers: Option<Vec<String>>,
+    /// Exposed response headers. Default `origin` and `content-type`.
+    pub expose_headers: Option<Vec<String>>,
+    /// Whether the requests can include credentials like cookies. Default `false`.
+    pub allow_credentials: Option<bool>,
+    /// Time in seconds the preflight responses can be cached via the `Access-Control-Max-Age` header.
+    pub max_age: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+/// Represents a route proxying the WebSocket connections under a path prefix to an upstream server.
+pub struct WebSocketProxy {
+    /// Request URI path prefix of the proxied WebSocket connections. E.g. `/ws`
+    pub path: String,
+    /// Upstream server URL with the `http` or `ws` scheme. E.g. `http://127.0.0.1:8080`
+    pub upstream: String,
+    /// Maximum time in seconds to connect to the upstream server and get its handshake response. Default `10`.
+    pub timeout: Option<u64>,
+}
+
+#[cfg(feature = "time-restrictions")]
+#[cfg_attr(docsrs, doc(cfg(feature = "time-restrictions")))]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+/// Represents a time window out of which the access to the matching request paths is restricted.
+pub struct TimeRestrictions {
+    /// Optional host to match against an incoming URI host if specified.
+    pub host: Option<String>,
+    /// Source glob pattern matched against the request URI path.
+    pub source: String,
+    /// Local start time of the allowed window. E.g. `08:00`
+    pub start: String,
+    /// Local end time (exclusive) of the allowed window, which ends the next day if earlier than the start. E.g. `18:30`
+    pub end: String,
+    /// Optional week days the allowed window starts on. E.g. `["mon", "tue"]`, all of them by default.
+    pub days: Option<Vec<String>>,
+    /// Optional URL to redirect to out of the allowed window, otherwise the access is forbidden.
+    pub redirect: Option<String>,
+}
+
+#[cfg(feature = "fallback-page")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+/// Represents a fallback page used for the request URI paths matching a glob pattern.
+pub struct FallbackPages {
+    /// Source glob pattern matched against the request URI path.
+    pub source: String,
+    /// HTML file path of the fallback page.
+    pub path: PathBuf,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 /// Represents virtual hosts with different root directories
 pub struct VirtualHosts {
@@ -162,10 +362,45 @@ pub struct VirtualHosts {
     pub host: String,
     /// The root directory for this virtual host
     pub root: Option<PathBuf>,
+    /// Optional directory listing locale for this virtual host
+    pub directory_listing_locale: Option<String>,
+}
+
+#[cfg(feature = "directory-listing")]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+/// Represents the UI strings and formats of a directory listing locale.
+pub struct DirListingLocale {
+    /// Title prefix of the listing. E.g. `Index of`
+    pub title: Option<String>,
+    /// Directories count label.
+    pub directories: Option<String>,
+    /// Files count label.
+    pub files: Option<String>,
+    /// Directory download link label.
+    pub download: Option<String>,
+    /// Name column title.
+    pub name: Option<String>,
+    /// Last modification column title.
+    pub last_modified: Option<String>,
+    /// Size column title.
+    pub size: Option<String>,
+    /// Entry type column title.
+    pub r#type: Option<String>,
+    /// File mode column title.
+    pub mode: Option<String>,
+    /// Owner column title.
+    pub owner: Option<String>,
+    /// Parent directory link label.
+    pub parent: Option<String>,
+    /// Date format of the last modification times using the `strftime` syntax. E.g. `%d/%m/%Y %H:%M`
+    pub date_format: Option<String>,
+    /// Units of the file sizes, either `binary` (KiB, MiB) or `decimal` (kB, MB).
+    pub size_units: Option<SizeUnits>,
 }
 
 #[cfg(feature = "experimental")]
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 /// Represents the in-memory file cache feature.
 pub struct MemoryCache {
@@ -177,10 +412,96 @@ pub struct MemoryCache {
     pub tti: Option<u64>,
     /// Maximum size in bytes for a file entry to be cached.
     pub max_file_size: Option<u64>,
+    /// File to persist the cache index on shutdown and to pre-warm the cache on start.
+    pub index_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+/// Represents the security headers profile, an empty value omits the header.
+pub struct Security {
+    /// `Strict-Transport-Security` header value.
+    pub hsts: Option<String>,
+    /// `X-Frame-Options` header value.
+    pub frame_options: Option<String>,
+    /// `X-Content-Type-Options` header value.
+    pub content_type_options: Option<String>,
+    /// `Content-Security-Policy` header value.
+    pub content_security_policy: Option<String>,
+    /// `Referrer-Policy` header value.
+    pub referrer_policy: Option<String>,
+    /// `Permissions-Policy` header value.
+    pub permissions_policy: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+/// Represents the byte-range chunk cache feature.
+pub struct ChunkCache {
+    /// Size in kilobytes of each cached file block.
+    pub block_size: Option<u64>,
+    /// Maximum number of cached blocks.
+    pub capacity: Option<u64>,
+    /// Minimum size in megabytes for a file to be served through the chunk cache.
+    pub min_file_size: Option<u64>,
+    /// File to persist the cache index on shutdown and to pre-warm the cache on start.
+    pub index_file: Option<PathBuf>,
+}
+
+#[cfg(feature = "cache-invalidation")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cache-invalidation")))]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+/// Represents the cache invalidation on file changes feature.
+pub struct CacheInvalidation {
+    /// Time in milliseconds without further changes to wait for before invalidating the caches.
+    pub debounce: Option<u64>,
+    /// Subtrees of the root directories to watch (E.g. `/assets`), the whole root directories by default.
+    pub paths: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+/// HTTP versions supported by a listener.
+pub enum ListenerProtocol {
+    /// HTTP/1.1
+    Http1,
+    /// HTTP/2 over TLS
+    Http2,
+    /// HTTP/2 over cleartext TCP with prior knowledge
+    H2c,
+    /// HTTP/3 over QUIC (not supported yet)
+    H3,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+/// Represents a listener which accepts connections on its own address.
+pub struct Listener {
+    /// Socket address (E.g. `0.0.0.0:443`) or Unix domain socket path prefixed by `unix:` (E.g. `unix:/run/sws.sock`) to listen on.
+    pub address: String,
+    /// HTTP versions accepted by the listener.
+    pub protocols: Option<Vec<ListenerProtocol>>,
+    /// TLS certificate file path.
+    pub tls_cert: Option<PathBuf>,
+    /// TLS private key file path.
+    pub tls_key: Option<PathBuf>,
+    /// Whether the connections start with a PROXY protocol (v1 or v2) header.
+    pub proxy_protocol: Option<bool>,
+    /// Virtual hosts served by the listener, `default` being the requests not matching any virtual host.
+    pub vhosts: Option<Vec<String>>,
+    /// Permissions of the Unix domain socket file in octal notation. E.g. `660`
+    pub socket_mode: Option<String>,
+    /// Owner of the Unix domain socket file as numeric IDs in the form `UID[:GID]` or `:GID`.
+    pub socket_owner: Option<String>,
+    /// Whether the listener redirects all requests to HTTPS instead of serving them.
+    pub https_redirect: Option<bool>,
+    /// HTTPS port to redirect to, the port of the first TLS listener by default.
+    pub https_redirect_port: Option<u16>,
 }
 
 /// Advanced server options only available in configuration file mode.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub struct Advanced {
     /// Headers
@@ -191,14 +512,50 @@ pub struct Advanced {
     pub redirects: Option<Vec<Redirects>>,
     /// Name-based virtual hosting
     pub virtual_hosts: Option<Vec<VirtualHosts>>,
+    /// Response status rewrites
+    pub status_rewrites: Option<Vec<StatusRewrites>>,
+    /// MIME types overrides and additions by file extension
+    pub mime_types: Option<BTreeMap<String, String>>,
+    /// Charsets by file extension
+    pub charsets: Option<BTreeMap<String, String>>,
+    /// Forced content types and downloads by glob pattern
+    pub content_types: Option<Vec<ContentTypes>>,
+    /// Error pages by status code (E.g. `404`) or status class (E.g. `5xx`)
+    pub error_pages: Option<BTreeMap<String, PathBuf>>,
+    /// Candidate paths tried in order by glob pattern
+    pub try_files: Option<Vec<TryFiles>>,
+    /// `Cache-Control` policies by glob pattern
+    pub cache_control: Option<Vec<CacheControl>>,
+    /// MIME type sniffing protection of the files by glob pattern
+    pub content_type_options: Option<Vec<ContentTypeOptions>>,
+    /// CORS policies by request path prefix
+    pub cors: Option<Vec<Cors>>,
+    /// WebSocket proxy routes by request path prefix
+    pub websocket_proxy: Option<Vec<WebSocketProxy>>,
+    /// Time-based access restrictions by glob pattern
+    #[cfg(feature = "time-restrictions")]
+    pub time_restrictions: Option<Vec<TimeRestrictions>>,
+    /// Fallback pages by glob pattern, taking precedence over the `page-fallback` one
+    #[cfg(feature = "fallback-page")]
+    pub fallback_pages: Option<Vec<FallbackPages>>,
+    /// Directory listing locales by language tag (E.g. `es` or `pt-br`)
+    #[cfg(feature = "directory-listing")]
+    pub directory_listing_locales: Option<BTreeMap<String, DirListingLocale>>,
     #[cfg(feature = "experimental")]
     /// In-memory cache feature (experimental).
     pub memory_cache: Option<MemoryCache>,
+    /// Byte-range chunk cache feature.
+    pub chunk_cache: Option<ChunkCache>,
+    /// Cache invalidation on file changes feature.
+    #[cfg(feature = "cache-invalidation")]
+    pub cache_invalidation: Option<CacheInvalidation>,
+    /// Security headers profile.
+    pub security: Option<Security>,
 }
 
 /// General server options available in configuration file mode.
 /// Note that the `--config-file` option is excluded from itself.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub struct General {
     /// Server address.
@@ -207,15 +564,29 @@ pub struct General {
     pub port: Option<u16>,
     /// Root directory path.
     pub root: Option<PathBuf>,
+    /// Additional root directories overlaid below the root directory in priority order.
+    pub overlay_roots: Option<Vec<PathBuf>>,
+    /// Serve the assets embedded into the binary at build time.
+    pub embedded_assets: Option<EmbeddedAssetsMode>,
 
     /// Logging level.
     pub log_level: Option<LogLevel>,
     /// Enable/disable ANSI escape codes for log output.
     pub log_with_ansi: Option<bool>,
+    /// File to append the log output to instead of the standard error.
+    pub log_file: Option<PathBuf>,
+
+    /// Format of the startup summary.
+    pub startup_summary: Option<StartupSummaryFormat>,
+    /// Don't print the startup summary.
+    pub quiet: Option<bool>,
 
     /// Cache Control headers.
     pub cache_control_headers: Option<bool>,
 
+    /// Pre-generated type map of the files under the root directory.
+    pub type_map: Option<bool>,
+
     /// Compression.
     #[cfg(any(
         feature = "compression",
@@ -256,6 +627,186 @@ pub struct General {
     )]
     pub compression_level: Option<CompressionLevel>,
 
+    /// Gzip and Deflate compression level.
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
+    )]
+    pub compression_level_gzip: Option<i32>,
+
+    /// Brotli compression level.
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
+    )]
+    pub compression_level_brotli: Option<i32>,
+
+    /// Zstd compression level.
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
+    )]
+    pub compression_level_zstd: Option<i32>,
+
+    /// Compression minimum size.
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
+    )]
+    pub compression_min_size: Option<u64>,
+
+    /// Compression allowed MIME types.
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
+    )]
+    pub compression_mime_types: Option<String>,
+
+    /// Compression excluded MIME types.
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
+    )]
+    pub compression_exclude_mime_types: Option<String>,
+
+    /// Adaptive compression governor.
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
+    )]
+    pub compression_adaptive: Option<bool>,
+
+    /// Adaptive compression high CPU load percentage.
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
+    )]
+    pub compression_adaptive_high_load: Option<u8>,
+
+    /// Adaptive compression maximum CPU load percentage.
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
+    )]
+    pub compression_adaptive_max_load: Option<u8>,
+
     /// Check for a pre-compressed file on disk.
     #[cfg(any(
         feature = "compression",
@@ -276,6 +827,26 @@ pub struct General {
     )]
     pub compression_static: Option<bool>,
 
+    /// Pre-compressed file variants to look up in priority order.
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))
+    )]
+    pub compression_static_order: Option<String>,
+
     /// Error 404 pages.
     pub page404: Option<PathBuf>,
     /// Error 50x pages.
@@ -310,6 +881,18 @@ pub struct General {
     #[cfg(feature = "http2")]
     #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
     pub https_redirect_from_hosts: Option<String>,
+    /// HTTP status code of the HTTPS redirects.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub https_redirect_status: Option<u16>,
+    /// Redirect to the requested host name instead of the HTTPS redirect host.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub https_redirect_preserve_host: Option<bool>,
+    /// Preload-grade HSTS header for HTTPS responses.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub https_redirect_hsts: Option<bool>,
 
     /// Security headers.
     pub security_headers: Option<bool>,
@@ -328,14 +911,62 @@ pub struct General {
     #[cfg(feature = "directory-listing")]
     #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
     pub directory_listing: Option<bool>,
+    /// Directory listing request path glob patterns.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub directory_listing_paths: Option<String>,
     /// Directory listing order feature.
     #[cfg(feature = "directory-listing")]
     #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
     pub directory_listing_order: Option<u8>,
+    /// Directory listing sort keys.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub directory_listing_sort: Option<String>,
+    /// Directory listing directories grouped before the files.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub directory_listing_dirs_first: Option<bool>,
     /// Directory listing format feature.
     #[cfg(feature = "directory-listing")]
     #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
     pub directory_listing_format: Option<DirListFmt>,
+    /// Directory listing size feature.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub directory_listing_dir_size: Option<bool>,
+    /// Directory listing size refresh time in seconds.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub directory_listing_dir_size_ttl: Option<u64>,
+    /// Directory listing size maximum entries to walk.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub directory_listing_dir_size_max_entries: Option<u64>,
+    /// Directory listing size maximum threads walking a directory tree.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub directory_listing_dir_size_concurrency: Option<usize>,
+    /// Directory listing entry details feature.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub directory_listing_details: Option<bool>,
+    /// Directory listing locale feature.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub directory_listing_locale: Option<String>,
+    /// Directory listing hidden entries feature.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub directory_listing_hide: Option<Vec<String>>,
+    /// Directory listing header file name.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub directory_listing_header: Option<String>,
+    /// Directory listing readme file name.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub directory_listing_readme: Option<String>,
 
     /// Directory listing download feature.
     #[cfg(feature = "directory-listing-download")]
@@ -350,13 +981,25 @@ pub struct General {
     /// File descriptor binding feature.
     pub fd: Option<usize>,
 
+    /// Network interface name to bind to.
+    pub interface: Option<String>,
+
     /// Worker threads.
     pub threads_multiplier: Option<usize>,
 
+    /// Fixed number of worker threads.
+    pub worker_threads: Option<usize>,
+
     /// Max blocking threads feature.
     pub max_blocking_threads: Option<usize>,
 
-    /// Grace period feature.
+    /// Stack size in bytes of the runtime threads.
+    pub thread_stack_size: Option<usize>,
+
+    /// Number of worker processes.
+    pub worker_processes: Option<usize>,
+
+    /// Grace period to drain in-flight connections on shutdown.
     pub grace_period: Option<u8>,
 
     /// Page fallback feature.
@@ -364,6 +1007,26 @@ pub struct General {
     #[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
     pub page_fallback: Option<PathBuf>,
 
+    /// Page fallback soft 404 marking feature.
+    #[cfg(feature = "fallback-page")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
+    pub page_fallback_soft404: Option<bool>,
+
+    /// Page fallback response status code.
+    #[cfg(feature = "fallback-page")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
+    pub page_fallback_status: Option<u16>,
+
+    /// Page fallback request methods.
+    #[cfg(feature = "fallback-page")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
+    pub page_fallback_methods: Option<Vec<String>>,
+
+    /// Page fallback restricted to HTML requests.
+    #[cfg(feature = "fallback-page")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
+    pub page_fallback_html_only: Option<bool>,
+
     /// Log remote address feature.
     pub log_remote_address: Option<bool>,
 
@@ -373,26 +1036,51 @@ pub struct General {
     /// Log the X-Forwarded-For header.
     pub log_forwarded_for: Option<bool>,
 
-    /// Trusted IPs for remote addresses.
-    pub trusted_proxies: Option<Vec<IpAddr>>,
+    /// Trusted IPs or host names for remote addresses.
+    pub trusted_proxies: Option<Vec<String>>,
+
+    /// Interval in seconds to resolve again the host names of the trusted proxies.
+    pub trusted_proxies_refresh: Option<u64>,
 
     /// Redirect trailing slash feature.
     pub redirect_trailing_slash: Option<bool>,
 
+    /// Reject the absolute-form request targets of HTTP/1.x requests.
+    pub reject_absolute_form: Option<bool>,
+
     /// Ignore hidden files feature.
     pub ignore_hidden_files: Option<bool>,
 
+    /// Glob patterns of the files and directories which are never served.
+    pub ignore_patterns: Option<Vec<String>>,
+
     /// Prevent following symbolic links of files or directories.
     pub disable_symlinks: Option<bool>,
 
+    /// Policy of the symbolic links followed when serving files and directories.
+    pub follow_symlinks: Option<FollowSymlinks>,
+
     /// Health endpoint feature.
     pub health: Option<bool>,
 
+    /// Files that must exist for the health readiness endpoint to succeed.
+    pub health_ready_files: Option<Vec<PathBuf>>,
+
     /// Accept markdown content negotiation feature.
     pub accept_markdown: Option<bool>,
 
+    /// Languages of the language content negotiation, the first one is the default.
+    pub language_variants: Option<String>,
+
+    /// Image formats of the image format negotiation in priority order.
+    pub image_variants: Option<String>,
+
+    #[cfg(feature = "metrics")]
+    /// Metrics endpoint feature.
+    pub metrics: Option<bool>,
+
     #[cfg(all(unix, feature = "experimental"))]
-    /// Metrics endpoint feature (experimental).
+    /// Tokio runtime metrics feature (experimental).
     pub experimental_metrics: Option<bool>,
 
     /// Maintenance mode feature.
@@ -404,6 +1092,206 @@ pub struct General {
     /// Custom maintenance mode HTML file.
     pub maintenance_mode_file: Option<PathBuf>,
 
+    /// Client IPs, networks or host names bypassing the maintenance mode.
+    pub maintenance_mode_allow_ips: Option<Vec<String>>,
+
+    /// Glob patterns of the request paths bypassing the maintenance mode.
+    pub maintenance_mode_allow_paths: Option<Vec<String>>,
+
+    /// RFC 3339 timestamp when the maintenance mode starts.
+    pub maintenance_mode_start: Option<String>,
+
+    /// RFC 3339 timestamp when the maintenance mode ends.
+    pub maintenance_mode_end: Option<String>,
+
+    #[cfg(feature = "markdown")]
+    /// Markdown rendering feature.
+    pub markdown_render: Option<bool>,
+
+    #[cfg(feature = "markdown")]
+    /// Markdown rendering HTML wrapper template file.
+    pub markdown_render_template: Option<PathBuf>,
+
+    /// Server-side includes feature.
+    pub ssi: Option<bool>,
+
+    /// Server-side includes file extensions.
+    pub ssi_extensions: Option<String>,
+
+    /// Live-reload of the browsers during development.
+    pub dev_reload: Option<bool>,
+
+    /// Inject the live-reload script into the HTML responses.
+    pub dev_reload_inject: Option<bool>,
+
+    /// Probe endpoint path.
+    pub probe_endpoint: Option<String>,
+
+    /// Value of the `Server` response header.
+    pub server_ident: Option<String>,
+
+    /// Alternative services advertised via the `Alt-Svc` response header.
+    pub alt_svc: Option<String>,
+
+    /// Freshness lifetime in seconds of the alternative services.
+    pub alt_svc_max_age: Option<u64>,
+
+    /// Default charset for text-based content types.
+    pub default_charset: Option<String>,
+
+    /// Maximum request URI length in bytes.
+    pub max_uri_length: Option<usize>,
+
+    /// Maximum request headers size in bytes.
+    pub max_headers_size: Option<usize>,
+
+    /// Maximum request body size in bytes.
+    pub max_body_size: Option<u64>,
+
+    /// Maximum bandwidth in bytes per second of every response body.
+    pub limit_rate: Option<u64>,
+
+    /// Maximum bandwidth in bytes per second of all the response bodies together.
+    pub limit_rate_global: Option<u64>,
+
+    /// Maximum number of concurrent connections.
+    pub max_connections: Option<usize>,
+
+    /// Maximum number of concurrent connections per client IP address.
+    pub max_connections_per_ip: Option<usize>,
+
+    #[cfg(feature = "archive-preview")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "archive-preview")))]
+    /// Archive preview feature.
+    pub archive_preview: Option<bool>,
+
+    #[cfg(feature = "archive-preview")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "archive-preview")))]
+    /// Maximum number of archive members listed or scanned by the archive preview.
+    pub archive_preview_max_entries: Option<usize>,
+
+    #[cfg(feature = "archive-preview")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "archive-preview")))]
+    /// Maximum uncompressed size in bytes of an archive member extracted by the archive preview.
+    pub archive_preview_max_size: Option<u64>,
+
+    #[cfg(feature = "subresource-integrity")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "subresource-integrity")))]
+    /// URI path of the Subresource Integrity manifest endpoint.
+    pub sri_manifest: Option<String>,
+
+    #[cfg(feature = "subresource-integrity")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "subresource-integrity")))]
+    /// Hash algorithm of the Subresource Integrity digests.
+    pub sri_algorithm: Option<SriAlgorithm>,
+
+    #[cfg(feature = "subresource-integrity")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "subresource-integrity")))]
+    /// File extensions included in the Subresource Integrity manifest.
+    pub sri_extensions: Option<String>,
+
+    #[cfg(feature = "s3-origin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+    /// Path-style URL of the S3 origin bucket.
+    pub s3_origin: Option<String>,
+
+    #[cfg(feature = "s3-origin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+    /// Region of the S3 origin bucket.
+    pub s3_region: Option<String>,
+
+    #[cfg(feature = "s3-origin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+    /// Access key ID of the S3 origin.
+    pub s3_access_key_id: Option<String>,
+
+    #[cfg(feature = "s3-origin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+    /// Secret access key of the S3 origin.
+    pub s3_secret_access_key: Option<String>,
+
+    #[cfg(feature = "s3-origin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+    /// Directory where the objects of the S3 origin are cached.
+    pub s3_cache_dir: Option<PathBuf>,
+
+    #[cfg(feature = "s3-origin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+    /// Time in seconds an object of the S3 origin is served before being revalidated.
+    pub s3_cache_ttl: Option<u64>,
+
+    #[cfg(feature = "s3-origin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+    /// Maximum size in bytes of the objects of the S3 origin kept in the disk cache.
+    pub s3_cache_max_size: Option<u64>,
+
+    #[cfg(feature = "s3-origin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "s3-origin")))]
+    /// PEM bundle of the CA certificates trusted by the S3 origin requests.
+    pub s3_ca_file: Option<PathBuf>,
+
+    /// Maximum time in seconds to read the headers of an HTTP/1 request.
+    pub read_header_timeout: Option<u64>,
+
+    /// Maximum time in seconds a connection can stay without any read or write activity.
+    pub idle_timeout: Option<u64>,
+
+    /// Maximum time in seconds to write a response.
+    pub write_timeout: Option<u64>,
+
+    /// Send large files with zero-copy on the plain HTTP/1 connections.
+    pub zero_copy: Option<bool>,
+
+    #[cfg(feature = "io-uring")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "io-uring")))]
+    /// Read the files served via io_uring.
+    pub io_uring: Option<bool>,
+
+    #[cfg(feature = "mmap")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mmap")))]
+    /// Maximum size in bytes of the files served via memory maps.
+    pub mmap_max_size: Option<u64>,
+
+    /// Maximum time in seconds to resolve the response of a request.
+    pub request_timeout: Option<u64>,
+
+    /// Time in seconds to remember the request paths not found.
+    pub not_found_cache_ttl: Option<u64>,
+
+    /// Maximum number of request paths remembered by the not found cache.
+    pub not_found_cache_capacity: Option<usize>,
+
+    /// Control API listener address or Unix domain socket path.
+    pub control_listen: Option<String>,
+
+    /// Bearer token required by the control API requests changing the server state.
+    pub control_token: Option<String>,
+
+    /// Audit log file path of the configuration reloads and runtime changes.
+    pub config_audit_log: Option<PathBuf>,
+
+    /// Policy of the `fsync` calls when writing files.
+    pub fsync_policy: Option<FsyncPolicy>,
+
+    #[cfg(unix)]
+    /// Unix domain socket path to listen on.
+    pub socket: Option<PathBuf>,
+
+    #[cfg(unix)]
+    /// Unix domain socket file permissions in octal notation.
+    pub socket_mode: Option<String>,
+
+    #[cfg(unix)]
+    /// Unix domain socket file owner user and group IDs.
+    pub socket_owner: Option<String>,
+
+    #[cfg(unix)]
+    /// Listen only on the Unix domain socket.
+    pub socket_only: Option<bool>,
+
+    /// Media streaming preset.
+    pub media_streaming: Option<bool>,
+
     #[cfg(feature = "experimental")]
     /// In-memory files cache feature.
     pub memory_cache: Option<bool>,
@@ -414,13 +1302,20 @@ pub struct General {
 }
 
 /// Full server configuration
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub struct Settings {
+    /// Path patterns of the config files merged into this one, relative to this file.
+    pub include: Option<Vec<String>>,
+    /// Replace the `${VAR}` references of the string values of this file and its included
+    /// files by the environment variables. Default `false`.
+    pub interpolate: Option<bool>,
     /// General settings.
     pub general: Option<General>,
     /// Advanced settings.
     pub advanced: Option<Advanced>,
+    /// Listeners settings.
+    pub listeners: Option<Vec<Listener>>,
 }
 
 impl Settings {
@@ -433,14 +1328,21 @@ impl Settings {
         }
 
         // TODO: validate minimal TOML file structure needed
-        let toml =
-            read_toml_file(config_file).with_context(|| "error reading toml configuration file")?;
+        let mut toml = super::include::read_toml_files(config_file)
+            .with_context(|| "error reading toml configuration file")?;
+
+        // Replace deprecated keys with their current names
+        for deprecation in super::migration::migrate_toml(&mut toml) {
+            eprintln!("{deprecation}");
+        }
+
         let mut unused = BTreeSet::new();
         let manifest: Settings = serde_ignored::deserialize(toml, |path| {
             let mut key = String::new();
             helpers::stringify(&mut key, &path);
             unused.insert(key);
         })
+        .map_err(|err| locate_error(config_file).unwrap_or_else(|| err.into()))
         .with_context(|| "error during toml configuration file deserialization")?;
 
         for key in unused {
@@ -449,10 +1351,32 @@ impl Settings {
 
         Ok(manifest)
     }
+
+    /// Generate the JSON Schema of the server TOML configuration file.
+    pub fn json_schema() -> Result<String> {
+        let schema = schemars::schema_for!(Settings);
+        serde_json::to_string_pretty(&schema)
+            .with_context(|| "unable to serialize the configuration json schema")
+    }
+}
+
+/// Deserializes the config files one by one to find the one causing a deserialization error
+/// of the merged configuration, so the error points to the line and column of the invalid value.
+fn locate_error(config_file: &Path) -> Option<anyhow::Error> {
+    for path in super::include::config_files(config_file).ok()? {
+        let source = helpers::read_file(&path).ok()?;
+        if let Err(err) = toml::from_str::<Settings>(&source) {
+            return Some(
+                anyhow::Error::from(err)
+                    .context(format!("invalid config file \"{}\"", path.display())),
+            );
+        }
+    }
+    None
 }
 
 /// Read and parse a TOML file from an specific path.
-fn read_toml_file(path: &Path) -> Result<toml::Value> {
+pub(crate) fn read_toml_file(path: &Path) -> Result<toml::Value> {
     let toml_str = helpers::read_file(path).with_context(|| {
         format!(
             "error trying to deserialize toml configuration file at \"{}\"",
@@ -0,0 +1,274 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Deprecated configuration keys and environment variables handling.
+//!
+//! It allows the server to keep accepting old option names while reporting
+//! structured deprecation warnings, and to rewrite a configuration file
+//! to the current schema.
+
+use clap::CommandFactory;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::Path;
+
+use crate::{Context, Result};
+
+use super::cli::General;
+
+/// Kind of deprecation of a configuration option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeprecationKind {
+    /// The option was renamed to the given name.
+    Renamed(&'static str),
+    /// The option is no longer supported. It includes a hint for users.
+    Removed(&'static str),
+}
+
+/// A deprecated configuration option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deprecation {
+    /// Old option name. A dotted path for config file keys (E.g. `general.assets`)
+    /// or the variable name for environment variables.
+    pub name: &'static str,
+    /// Kind of deprecation.
+    pub kind: DeprecationKind,
+    /// Server version since the option is deprecated.
+    pub since: &'static str,
+}
+
+/// Deprecated configuration file keys.
+pub const DEPRECATED_KEYS: &[Deprecation] = &[];
+
+/// Deprecated environment variables.
+pub const DEPRECATED_ENVS: &[Deprecation] = &[Deprecation {
+    name: "SERVER_ASSETS",
+    kind: DeprecationKind::Removed("static files are served from the `root` directory"),
+    since: "2.0.0",
+}];
+
+/// Where a deprecated option was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeprecationSource {
+    /// A configuration file key.
+    ConfigKey,
+    /// An environment variable.
+    EnvVar,
+}
+
+/// A deprecation warning reported for a deprecated option in use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeprecationWarning {
+    /// Where the deprecated option was found.
+    pub source: DeprecationSource,
+    /// The deprecated option.
+    pub deprecation: Deprecation,
+    /// Whether the option value was ignored.
+    /// E.g. when the option was removed or its replacement is also defined.
+    pub ignored: bool,
+}
+
+impl fmt::Display for DeprecationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = match self.source {
+            DeprecationSource::ConfigKey => "config key",
+            DeprecationSource::EnvVar => "env",
+        };
+        let Deprecation { name, kind, since } = self.deprecation;
+        write!(f, "Deprecated: {source} \"{name}\" ")?;
+        match kind {
+            DeprecationKind::Renamed(new_name) if self.ignored => write!(
+                f,
+                "is deprecated since v{since} and was ignored because \"{new_name}\" is also defined"
+            ),
+            DeprecationKind::Renamed(new_name) => write!(
+                f,
+                "is deprecated since v{since}, use \"{new_name}\" instead"
+            ),
+            DeprecationKind::Removed(hint) => write!(
+                f,
+                "is no longer supported since v{since} and was ignored ({hint})"
+            ),
+        }
+    }
+}
+
+/// Replace deprecated keys of a parsed TOML configuration with their current names.
+/// Removed keys are dropped and a key is never overwritten if its new name is already defined.
+pub fn migrate_toml(value: &mut toml::Value) -> Vec<DeprecationWarning> {
+    let mut warnings = Vec::new();
+
+    for deprecation in DEPRECATED_KEYS {
+        let Some(old_value) = remove_key(value, deprecation.name) else {
+            continue;
+        };
+
+        let ignored = match deprecation.kind {
+            DeprecationKind::Renamed(new_name) => !insert_key(value, new_name, old_value),
+            DeprecationKind::Removed(_) => true,
+        };
+
+        warnings.push(DeprecationWarning {
+            source: DeprecationSource::ConfigKey,
+            deprecation: *deprecation,
+            ignored,
+        });
+    }
+
+    warnings
+}
+
+/// Read a TOML configuration file and return its content migrated to the current schema
+/// along with the deprecation warnings found.
+///
+/// Note that comments and formatting of the original file are not preserved.
+pub fn migrate_file(path: &Path) -> Result<(String, Vec<DeprecationWarning>)> {
+    if !path.is_file() {
+        bail!(
+            "configuration file \"{}\" was not found or is not a file",
+            path.display()
+        );
+    }
+
+    let mut value = super::file::read_toml_file(path)?;
+    let warnings = migrate_toml(&mut value);
+    let content = toml::to_string_pretty(&value)
+        .with_context(|| "unable to serialize the migrated toml configuration")?;
+
+    Ok((content, warnings))
+}
+
+/// Map deprecated environment variables to their current command line arguments.
+///
+/// It returns the given arguments with the ones derived from deprecated environment variables
+/// inserted right after the binary name, unless the current variable or argument is already provided.
+pub(crate) fn migrate_env_args<F>(
+    args: Vec<OsString>,
+    get_env: F,
+) -> (Vec<OsString>, Vec<DeprecationWarning>)
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let mut warnings = Vec::new();
+    let mut extra_args = Vec::new();
+    let cmd = General::command();
+
+    for deprecation in DEPRECATED_ENVS {
+        let Some(env_value) = get_env(deprecation.name) else {
+            continue;
+        };
+
+        let ignored = match deprecation.kind {
+            DeprecationKind::Renamed(new_name) => {
+                let long = cmd
+                    .get_arguments()
+                    .find(|arg| arg.get_env().is_some_and(|env| env == new_name))
+                    .and_then(|arg| arg.get_long());
+                match long {
+                    Some(long) if get_env(new_name).is_none() && !has_arg(&args, long) => {
+                        let mut arg = OsString::from(format!("--{long}="));
+                        arg.push(env_value);
+                        extra_args.push(arg);
+                        false
+                    }
+                    _ => true,
+                }
+            }
+            DeprecationKind::Removed(_) => true,
+        };
+
+        warnings.push(DeprecationWarning {
+            source: DeprecationSource::EnvVar,
+            deprecation: *deprecation,
+            ignored,
+        });
+    }
+
+    let mut args = args.into_iter();
+    let all_args = args
+        .next()
+        .into_iter()
+        .chain(extra_args)
+        .chain(args)
+        .collect();
+
+    (all_args, warnings)
+}
+
+/// Check if a long argument is present in the given command line arguments.
+fn has_arg(args: &[OsString], long: &str) -> bool {
+    let flag = format!("--{long}");
+    let flag_eq = format!("--{long}=");
+    args.iter()
+        .filter_map(|arg| arg.to_str())
+        .any(|arg| arg == flag || arg.starts_with(&flag_eq))
+}
+
+/// Remove a key by its dotted path returning its value if present.
+fn remove_key(value: &mut toml::Value, path: &str) -> Option<toml::Value> {
+    let (parent, key) = match path.rsplit_once('.') {
+        Some((parent, key)) => (get_table_mut(value, parent)?, key),
+        None => (value.as_table_mut()?, path),
+    };
+    parent.remove(key)
+}
+
+/// Insert a value by its dotted path unless the key is already defined.
+/// It returns `true` if the value was inserted.
+fn insert_key(value: &mut toml::Value, path: &str, new_value: toml::Value) -> bool {
+    let (parent, key) = match path.rsplit_once('.') {
+        Some((parent, key)) => match get_table_mut(value, parent) {
+            Some(table) => (table, key),
+            None => return false,
+        },
+        None => match value.as_table_mut() {
+            Some(table) => (table, path),
+            None => return false,
+        },
+    };
+    if parent.contains_key(key) {
+        return false;
+    }
+    parent.insert(key.to_owned(), new_value);
+    true
+}
+
+/// Get a mutable reference to a nested table by its dotted path.
+fn get_table_mut<'a>(value: &'a mut toml::Value, path: &str) -> Option<&'a mut toml::Table> {
+    path.split('.')
+        .try_fold(value, |value, key| value.get_mut(key))?
+        .as_table_mut()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> toml::Value {
+        toml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn migrate_toml_no_deprecations() {
+        let mut value = parse("[general]\nroot = \"./public\"\n");
+        assert!(migrate_toml(&mut value).is_empty());
+        assert_eq!(value, parse("[general]\nroot = \"./public\"\n"));
+    }
+
+    #[test]
+    fn migrate_env_removed_var() {
+        let args = vec![OsString::from("static-web-server")];
+        let (new_args, warnings) = migrate_env_args(args.clone(), |name| {
+            (name == "SERVER_ASSETS").then(|| OsString::from("./assets"))
+        });
+
+        assert_eq!(new_args, args);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].to_string(),
+            "Deprecated: env \"SERVER_ASSETS\" is no longer supported since v2.0.0 and was ignored (static files are served from the `root` directory)"
+        );
+    }
+}
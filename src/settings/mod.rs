@@ -8,26 +8,45 @@
 
 use clap::Parser;
 use globset::{Glob, GlobBuilder, GlobMatcher};
-use headers::HeaderMap;
+use headers::{HeaderMap, HeaderName, HeaderValue};
 use hyper::StatusCode;
+use hyper::http::uri::{PathAndQuery, Scheme};
+use mime_guess::Mime;
 use regex_lite::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 
-use crate::{Context, Result, helpers, logger};
+use crate::{
+    Context, ErrorList, Result, anyhow, control_headers, cors, helpers, logger, security_headers,
+    try_files, websocket_proxy,
+};
+
+#[cfg(feature = "time-restrictions")]
+use crate::time_restrictions;
 
 pub mod cli;
 #[doc(hidden)]
 pub mod cli_output;
+pub mod dump;
 pub mod file;
+pub mod include;
+pub mod migration;
 
 pub use cli::Commands;
+#[cfg(windows)]
+pub use cli::{ServiceCommands, ServiceInstallOpts, ServiceStartup};
 
 use cli::General;
 
 #[cfg(feature = "experimental")]
 use self::file::MemoryCache;
 
-use self::file::{RedirectsKind, Settings as FileSettings};
+#[cfg(feature = "cache-invalidation")]
+use self::file::CacheInvalidation;
+
+use self::file::{ChunkCache, ListenerProtocol, RedirectsKind, Settings as FileSettings};
 
 #[cfg(any(
     feature = "compression",
@@ -39,24 +58,41 @@ use self::file::{RedirectsKind, Settings as FileSettings};
 pub use file::CompressionLevel;
 
 /// The `headers` file options.
+#[derive(Clone)]
 pub struct Headers {
     /// Source pattern glob matcher
     pub source: GlobMatcher,
     /// Map of custom HTTP headers
     pub headers: HeaderMap,
+    /// Names of the HTTP headers to remove
+    pub remove: Vec<HeaderName>,
+    /// Optional response status codes the entry applies to
+    pub status: Option<Vec<StatusCode>>,
+    /// Optional response content type glob matcher
+    pub content_type: Option<GlobMatcher>,
 }
 
 /// The `Rewrites` file options.
+#[derive(Clone)]
 pub struct Rewrites {
+    /// Optional host to match against an incoming URI host if specified
+    pub host: Option<String>,
     /// Source pattern Regex matcher
     pub source: Regex,
     /// A local file that must exist
     pub destination: String,
     /// Optional redirect type either 301 (Moved Permanently) or 302 (Found).
     pub redirect: Option<RedirectsKind>,
+    /// Request conditions besides the source and host
+    pub conditions: RequestConditions,
+    /// Whether the rewritten URL is matched against the rewrites again
+    pub chain: bool,
+    /// Whether the request query string is appended to the destination one
+    pub append_query: bool,
 }
 
 /// The `Redirects` file options.
+#[derive(Clone)]
 pub struct Redirects {
     /// Optional host to match against an incoming URI host if specified
     pub host: Option<String>,
@@ -66,18 +102,270 @@ pub struct Redirects {
     pub destination: String,
     /// Redirection type either 301 (Moved Permanently) or 302 (Found)
     pub kind: StatusCode,
+    /// Request conditions besides the source and host
+    pub conditions: RequestConditions,
+}
+
+/// The request conditions of a rewrite or redirect entry, all of them must match.
+#[derive(Clone, Default)]
+pub struct RequestConditions {
+    /// Scheme of the connection the request should use
+    pub scheme: Option<Scheme>,
+    /// Request headers along with their value glob matchers
+    pub headers: Vec<(HeaderName, GlobMatcher)>,
+    /// Query parameters along with their value glob matchers
+    pub query: Vec<(String, GlobMatcher)>,
+}
+
+/// The `StatusRewrites` file options.
+#[derive(Clone)]
+pub struct StatusRewrites {
+    /// Source pattern glob matcher
+    pub source: GlobMatcher,
+    /// Response status code to match
+    pub status: StatusCode,
+    /// Response status code to rewrite to
+    pub to: StatusCode,
+}
+
+/// The `ContentTypes` file options.
+#[derive(Clone)]
+pub struct ContentTypes {
+    /// Source pattern glob matcher
+    pub source: GlobMatcher,
+    /// Optional MIME type forced for the matching files
+    pub content_type: Option<Mime>,
+    /// Whether to force the download of the matching files
+    pub download: bool,
+}
+
+/// The `TryFiles` file options.
+#[derive(Clone)]
+pub struct TryFiles {
+    /// Source pattern glob matcher
+    pub source: GlobMatcher,
+    /// Candidate paths tried in order
+    pub files: Vec<String>,
+    /// Optional path used if none of the candidate paths exists
+    pub fallback: Option<String>,
+}
+
+/// The `CacheControl` file options.
+#[derive(Clone)]
+pub struct CacheControl {
+    /// Source pattern glob matcher
+    pub source: GlobMatcher,
+    /// Value of the `Cache-Control` header
+    pub value: HeaderValue,
+    /// Optional lifetime of the responses used for the `Expires` header
+    pub expires: Option<std::time::Duration>,
+}
+
+/// The `ContentTypeOptions` file options.
+#[derive(Clone)]
+pub struct ContentTypeOptions {
+    /// Source pattern glob matcher
+    pub source: GlobMatcher,
+    /// Whether to send the `X-Content-Type-Options: nosniff` header
+    pub nosniff: bool,
+}
+
+/// The `Cors` file options.
+#[derive(Clone)]
+pub struct Cors {
+    /// Request URI path prefix without trailing slash
+    pub path: String,
+    /// CORS policy of the path prefix
+    pub policy: cors::Configured,
+}
+
+/// The `WebSocketProxy` file options.
+#[derive(Clone)]
+pub struct WebSocketProxy {
+    /// Request URI path prefix without trailing slash
+    pub path: String,
+    /// Upstream server of the path prefix
+    pub upstream: websocket_proxy::Upstream,
+    /// Maximum time to connect to the upstream server and get its handshake response
+    pub timeout: std::time::Duration,
+}
+
+/// The `TimeRestrictions` file options.
+#[cfg(feature = "time-restrictions")]
+#[cfg_attr(docsrs, doc(cfg(feature = "time-restrictions")))]
+#[derive(Clone)]
+pub struct TimeRestrictions {
+    /// Optional host to match against an incoming URI host if specified
+    pub host: Option<String>,
+    /// Source pattern glob matcher
+    pub source: GlobMatcher,
+    /// Local start time of the allowed window
+    pub start: chrono::NaiveTime,
+    /// Local end time (exclusive) of the allowed window
+    pub end: chrono::NaiveTime,
+    /// Week days the allowed window starts on, all of them if empty
+    pub days: Vec<chrono::Weekday>,
+    /// Optional redirect location out of the allowed window
+    pub redirect: Option<HeaderValue>,
+}
+
+/// The `FallbackPages` file options.
+#[cfg(feature = "fallback-page")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fallback-page")))]
+#[derive(Clone)]
+pub struct FallbackPages {
+    /// Source pattern glob matcher
+    pub source: GlobMatcher,
+    /// HTML file path of the fallback page
+    pub path: PathBuf,
 }
 
 /// The `VirtualHosts` file options.
+#[derive(Clone)]
 pub struct VirtualHosts {
     /// The value to check for in the "Host" header
     pub host: String,
     /// The root directory for this virtual host
     pub root: PathBuf,
+    /// Optional directory listing locale for this virtual host
+    pub directory_listing_locale: Option<String>,
+}
+
+/// The `error-pages` file options.
+#[derive(Clone, Debug)]
+pub struct ErrorPage {
+    /// Status code or status class the page is used for
+    pub status: ErrorPageStatus,
+    /// HTML file path of the page
+    pub path: PathBuf,
+}
+
+/// The status code or status class of an error page.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorPageStatus {
+    /// A single status code (E.g. `404`)
+    Code(StatusCode),
+    /// A status class given by its first digit (E.g. `5` for `5xx`)
+    Class(u16),
+}
+
+impl std::fmt::Display for ErrorPageStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Code(status) => write!(f, "{}", status.as_u16()),
+            Self::Class(class) => write!(f, "{class}xx"),
+        }
+    }
+}
+
+impl std::str::FromStr for ErrorPageStatus {
+    type Err = crate::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let status = value.trim().to_lowercase();
+        if let Some(class) = status.strip_suffix("xx") {
+            return match class {
+                "4" => Ok(Self::Class(4)),
+                "5" => Ok(Self::Class(5)),
+                _ => bail!("invalid error page status class `{value}`, use either `4xx` or `5xx`"),
+            };
+        }
+        match status
+            .parse::<u16>()
+            .ok()
+            .and_then(|code| StatusCode::from_u16(code).ok())
+        {
+            Some(code) if code.is_client_error() || code.is_server_error() => Ok(Self::Code(code)),
+            _ => {
+                bail!("invalid error page status `{value}`, use a status code between 400 and 599")
+            }
+        }
+    }
+}
+
+/// The `listeners` file options.
+#[derive(Clone, Debug)]
+pub struct Listener {
+    /// Address to listen on
+    pub address: ListenerAddress,
+    /// Whether HTTP/1.1 is accepted
+    pub http1: bool,
+    /// Whether HTTP/2 is accepted, over TLS or over cleartext TCP (h2c) otherwise
+    pub http2: bool,
+    /// TLS certificate and private key file paths
+    pub tls: Option<ListenerTls>,
+    /// Whether the connections start with a PROXY protocol header
+    pub proxy_protocol: bool,
+    /// Virtual hosts served by the listener, all of them if not specified
+    pub vhosts: Option<Vec<String>>,
+    /// Permissions of the Unix domain socket file
+    pub socket_mode: Option<u32>,
+    /// Owner of the Unix domain socket file
+    pub socket_owner: Option<SocketOwner>,
+    /// HTTP to HTTPS redirect options, the listener redirects all requests if specified
+    pub https_redirect: Option<ListenerRedirect>,
+}
+
+/// The HTTP to HTTPS redirect options of a listener.
+#[derive(Clone, Debug)]
+pub struct ListenerRedirect {
+    /// HTTPS host name to redirect to
+    pub host: String,
+    /// HTTPS port to redirect to
+    pub port: u16,
+    /// Host names or IPs allowed to redirect from
+    pub allowed_hosts: Vec<String>,
+    /// Redirect status code
+    pub status: StatusCode,
+    /// Whether to redirect to the requested host name instead
+    pub preserve_host: bool,
+}
+
+/// The address of a listener.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ListenerAddress {
+    /// TCP socket address
+    Tcp(SocketAddr),
+    /// Unix domain socket path
+    #[cfg(unix)]
+    Unix(PathBuf),
+    /// Socket passed by systemd given by its name or index
+    #[cfg(unix)]
+    Systemd(String),
+}
+
+impl std::fmt::Display for ListenerAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{addr}"),
+            #[cfg(unix)]
+            Self::Unix(path) => write!(f, "unix:{}", path.display()),
+            #[cfg(unix)]
+            Self::Systemd(name) => write!(f, "systemd:{name}"),
+        }
+    }
+}
+
+/// The owner user and group IDs of a Unix domain socket file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SocketOwner {
+    /// User ID, unchanged if not specified
+    pub uid: Option<u32>,
+    /// Group ID, unchanged if not specified
+    pub gid: Option<u32>,
+}
+
+/// The TLS options of a listener.
+#[derive(Clone, Debug)]
+pub struct ListenerTls {
+    /// TLS certificate file path
+    pub cert: PathBuf,
+    /// TLS private key file path
+    pub key: PathBuf,
 }
 
 /// The `advanced` file options.
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct Advanced {
     /// Headers list.
     pub headers: Option<Vec<Headers>>,
@@ -87,9 +375,45 @@ pub struct Advanced {
     pub redirects: Option<Vec<Redirects>>,
     /// Name-based virtual hosting
     pub virtual_hosts: Option<Vec<VirtualHosts>>,
+    /// Response status rewrites list.
+    pub status_rewrites: Option<Vec<StatusRewrites>>,
+    /// MIME types by lowercase file extension (without the leading dot).
+    pub mime_types: Option<HashMap<String, Mime>>,
+    /// Charsets by lowercase file extension (without the leading dot).
+    pub charsets: Option<HashMap<String, String>>,
+    /// Forced content types and downloads list.
+    pub content_types: Option<Vec<ContentTypes>>,
+    /// Error pages list.
+    pub error_pages: Option<Vec<ErrorPage>>,
+    /// Try files list.
+    pub try_files: Option<Vec<TryFiles>>,
+    /// `Cache-Control` policies list.
+    pub cache_control: Option<Vec<CacheControl>>,
+    /// MIME type sniffing protection policies list.
+    pub content_type_options: Option<Vec<ContentTypeOptions>>,
+    /// CORS policies list.
+    pub cors: Option<Vec<Cors>>,
+    /// WebSocket proxy routes list.
+    pub websocket_proxy: Option<Vec<WebSocketProxy>>,
+    /// Time-based access restrictions list.
+    #[cfg(feature = "time-restrictions")]
+    pub time_restrictions: Option<Vec<TimeRestrictions>>,
+    /// Fallback pages list.
+    #[cfg(feature = "fallback-page")]
+    pub fallback_pages: Option<Vec<FallbackPages>>,
+    /// Directory listing locales by language tag.
+    #[cfg(feature = "directory-listing")]
+    pub directory_listing_locales: Option<BTreeMap<String, file::DirListingLocale>>,
     #[cfg(feature = "experimental")]
     /// In-memory cache feature (experimental).
     pub memory_cache: Option<MemoryCache>,
+    /// Byte-range chunk cache feature.
+    pub chunk_cache: Option<ChunkCache>,
+    /// Cache invalidation on file changes feature.
+    #[cfg(feature = "cache-invalidation")]
+    pub cache_invalidation: Option<CacheInvalidation>,
+    /// Security headers profile replacing the default security headers.
+    pub security: Option<HeaderMap>,
 }
 
 /// The full server CLI and File options.
@@ -98,6 +422,8 @@ pub struct Settings {
     pub general: General,
     /// Advanced server options
     pub advanced: Option<Advanced>,
+    /// Listeners replacing the server address and its TLS options if any
+    pub listeners: Vec<Listener>,
 }
 
 impl Settings {
@@ -115,20 +441,36 @@ impl Settings {
     }
 
     fn parse_from(log_init: bool, args: Option<&[&str]>) -> Result<Settings> {
-        let opts = match args {
-            Some(v) => General::parse_from(v),
-            None => General::parse(),
+        let args: Vec<OsString> = match args {
+            Some(v) => v.iter().map(OsString::from).collect(),
+            None => std::env::args_os().collect(),
         };
 
+        // Map deprecated environment variables to their current arguments
+        let (args, deprecations) = migration::migrate_env_args(args, |name| std::env::var_os(name));
+        let opts = General::parse_from(args);
+        for deprecation in deprecations {
+            eprintln!("{deprecation}");
+        }
+
+        // Independent validation errors reported all at once
+        let mut errors = ErrorList::default();
+
         // Define the general CLI/file options
         let version = opts.version;
         let mut host = opts.host;
         let mut port = opts.port;
         let mut root = opts.root;
+        let mut overlay_roots = opts.overlay_roots;
+        let mut embedded_assets = opts.embedded_assets;
         let mut log_level = opts.log_level;
         let mut log_with_ansi = opts.log_with_ansi;
+        let mut log_file = opts.log_file;
+        let mut startup_summary = opts.startup_summary;
+        let mut quiet = opts.quiet;
         let mut config_file = opts.config_file.clone();
         let mut cache_control_headers = opts.cache_control_headers;
+        let mut type_map = opts.type_map;
 
         #[cfg(any(
             feature = "compression",
@@ -153,7 +495,87 @@ impl Settings {
             feature = "compression-zstd",
             feature = "compression-deflate"
         ))]
+        let mut compression_level_gzip = opts.compression_level_gzip;
+        #[cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        ))]
+        let mut compression_level_brotli = opts.compression_level_brotli;
+        #[cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        ))]
+        let mut compression_level_zstd = opts.compression_level_zstd;
+        #[cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        ))]
+        let mut compression_min_size = opts.compression_min_size;
+        #[cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        ))]
+        let mut compression_mime_types = opts.compression_mime_types;
+        #[cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        ))]
+        let mut compression_exclude_mime_types = opts.compression_exclude_mime_types;
+        #[cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        ))]
+        let mut compression_adaptive = opts.compression_adaptive;
+        #[cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        ))]
+        let mut compression_adaptive_high_load = opts.compression_adaptive_high_load;
+        #[cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        ))]
+        let mut compression_adaptive_max_load = opts.compression_adaptive_max_load;
+        #[cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        ))]
         let mut compression_static = opts.compression_static;
+        #[cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        ))]
+        let mut compression_static_order = opts.compression_static_order;
 
         let mut page404 = opts.page404;
         let mut page50x = opts.page50x;
@@ -172,6 +594,12 @@ impl Settings {
         let mut https_redirect_from_port = opts.https_redirect_from_port;
         #[cfg(feature = "http2")]
         let mut https_redirect_from_hosts = opts.https_redirect_from_hosts;
+        #[cfg(feature = "http2")]
+        let mut https_redirect_status = opts.https_redirect_status;
+        #[cfg(feature = "http2")]
+        let mut https_redirect_preserve_host = opts.https_redirect_preserve_host;
+        #[cfg(feature = "http2")]
+        let mut https_redirect_hsts = opts.https_redirect_hsts;
 
         let mut security_headers = opts.security_headers;
         let mut cors_allow_origins = opts.cors_allow_origins;
@@ -181,9 +609,35 @@ impl Settings {
         #[cfg(feature = "directory-listing")]
         let mut directory_listing = opts.directory_listing;
         #[cfg(feature = "directory-listing")]
+        let mut directory_listing_paths = opts.directory_listing_paths;
+        #[cfg(feature = "directory-listing")]
         let mut directory_listing_order = opts.directory_listing_order;
         #[cfg(feature = "directory-listing")]
+        let mut directory_listing_sort = opts.directory_listing_sort;
+        #[cfg(feature = "directory-listing")]
+        let mut directory_listing_dirs_first = opts.directory_listing_dirs_first;
+        #[cfg(feature = "directory-listing")]
         let mut directory_listing_format = opts.directory_listing_format;
+        #[cfg(feature = "directory-listing")]
+        let mut directory_listing_dir_size = opts.directory_listing_dir_size;
+        #[cfg(feature = "directory-listing")]
+        let mut directory_listing_dir_size_ttl = opts.directory_listing_dir_size_ttl;
+        #[cfg(feature = "directory-listing")]
+        let mut directory_listing_dir_size_max_entries =
+            opts.directory_listing_dir_size_max_entries;
+        #[cfg(feature = "directory-listing")]
+        let mut directory_listing_dir_size_concurrency =
+            opts.directory_listing_dir_size_concurrency;
+        #[cfg(feature = "directory-listing")]
+        let mut directory_listing_details = opts.directory_listing_details;
+        #[cfg(feature = "directory-listing")]
+        let mut directory_listing_locale = opts.directory_listing_locale;
+        #[cfg(feature = "directory-listing")]
+        let mut directory_listing_hide = opts.directory_listing_hide;
+        #[cfg(feature = "directory-listing")]
+        let mut directory_listing_header = opts.directory_listing_header;
+        #[cfg(feature = "directory-listing")]
+        let mut directory_listing_readme = opts.directory_listing_readme;
 
         #[cfg(feature = "directory-listing-download")]
         let mut directory_listing_download = opts.directory_listing_download;
@@ -192,30 +646,161 @@ impl Settings {
         let mut basic_auth = opts.basic_auth;
 
         let mut fd = opts.fd;
+        let mut interface = opts.interface;
         let mut threads_multiplier = opts.threads_multiplier;
+        let mut worker_threads = opts.worker_threads;
         let mut max_blocking_threads = opts.max_blocking_threads;
+        let mut thread_stack_size = opts.thread_stack_size;
+        let mut worker_processes = opts.worker_processes;
         let mut grace_period = opts.grace_period;
 
         #[cfg(feature = "fallback-page")]
         let mut page_fallback = opts.page_fallback;
+        #[cfg(feature = "fallback-page")]
+        let mut page_fallback_soft404 = opts.page_fallback_soft404;
+        #[cfg(feature = "fallback-page")]
+        let mut page_fallback_status = opts.page_fallback_status;
+        #[cfg(feature = "fallback-page")]
+        let mut page_fallback_methods = opts.page_fallback_methods;
+        #[cfg(feature = "fallback-page")]
+        let mut page_fallback_html_only = opts.page_fallback_html_only;
 
         let mut log_remote_address = opts.log_remote_address;
         let mut log_x_real_ip = opts.log_x_real_ip;
         let mut log_forwarded_for = opts.log_forwarded_for;
         let mut trusted_proxies = opts.trusted_proxies;
+        let mut trusted_proxies_refresh = opts.trusted_proxies_refresh;
         let mut redirect_trailing_slash = opts.redirect_trailing_slash;
+        let mut reject_absolute_form = opts.reject_absolute_form;
         let mut ignore_hidden_files = opts.ignore_hidden_files;
+        let mut ignore_patterns = opts.ignore_patterns;
         let mut disable_symlinks = opts.disable_symlinks;
+        let mut follow_symlinks = opts.follow_symlinks;
         let mut accept_markdown = opts.accept_markdown;
+        let mut language_variants = opts.language_variants;
+        let mut image_variants = opts.image_variants;
         let mut index_files = opts.index_files;
         let mut health = opts.health;
+        let mut health_ready_files = opts.health_ready_files;
 
+        #[cfg(feature = "metrics")]
+        let mut metrics = opts.metrics;
         #[cfg(all(unix, feature = "experimental"))]
         let mut experimental_metrics = opts.experimental_metrics;
 
         let mut maintenance_mode = opts.maintenance_mode;
         let mut maintenance_mode_status = opts.maintenance_mode_status;
         let mut maintenance_mode_file = opts.maintenance_mode_file;
+        let mut maintenance_mode_allow_ips = opts.maintenance_mode_allow_ips;
+        let mut maintenance_mode_allow_paths = opts.maintenance_mode_allow_paths;
+        let mut maintenance_mode_start = opts.maintenance_mode_start;
+        let mut maintenance_mode_end = opts.maintenance_mode_end;
+
+        #[cfg(feature = "markdown")]
+        let mut markdown_render = opts.markdown_render;
+
+        #[cfg(feature = "markdown")]
+        let mut markdown_render_template = opts.markdown_render_template;
+
+        let mut ssi = opts.ssi;
+
+        let mut ssi_extensions = opts.ssi_extensions;
+        let mut dev_reload = opts.dev_reload;
+        let mut dev_reload_inject = opts.dev_reload_inject;
+
+        let mut probe_endpoint = opts.probe_endpoint;
+
+        let mut server_ident = opts.server_ident;
+
+        let mut alt_svc = opts.alt_svc;
+        let mut alt_svc_max_age = opts.alt_svc_max_age;
+
+        let mut default_charset = opts.default_charset;
+
+        let mut max_uri_length = opts.max_uri_length;
+
+        let mut max_headers_size = opts.max_headers_size;
+
+        let mut max_body_size = opts.max_body_size;
+        let mut limit_rate = opts.limit_rate;
+        let mut limit_rate_global = opts.limit_rate_global;
+        let mut max_connections = opts.max_connections;
+        let mut max_connections_per_ip = opts.max_connections_per_ip;
+
+        #[cfg(feature = "archive-preview")]
+        let mut archive_preview = opts.archive_preview;
+
+        #[cfg(feature = "archive-preview")]
+        let mut archive_preview_max_entries = opts.archive_preview_max_entries;
+
+        #[cfg(feature = "archive-preview")]
+        let mut archive_preview_max_size = opts.archive_preview_max_size;
+
+        #[cfg(feature = "subresource-integrity")]
+        let mut sri_manifest = opts.sri_manifest;
+
+        #[cfg(feature = "subresource-integrity")]
+        let mut sri_algorithm = opts.sri_algorithm;
+
+        #[cfg(feature = "subresource-integrity")]
+        let mut sri_extensions = opts.sri_extensions;
+
+        #[cfg(feature = "s3-origin")]
+        let mut s3_origin = opts.s3_origin;
+
+        #[cfg(feature = "s3-origin")]
+        let mut s3_region = opts.s3_region;
+
+        #[cfg(feature = "s3-origin")]
+        let mut s3_access_key_id = opts.s3_access_key_id;
+
+        #[cfg(feature = "s3-origin")]
+        let mut s3_secret_access_key = opts.s3_secret_access_key;
+
+        #[cfg(feature = "s3-origin")]
+        let mut s3_cache_dir = opts.s3_cache_dir;
+
+        #[cfg(feature = "s3-origin")]
+        let mut s3_cache_ttl = opts.s3_cache_ttl;
+
+        #[cfg(feature = "s3-origin")]
+        let mut s3_cache_max_size = opts.s3_cache_max_size;
+
+        #[cfg(feature = "s3-origin")]
+        let mut s3_ca_file = opts.s3_ca_file;
+
+        let mut read_header_timeout = opts.read_header_timeout;
+
+        let mut idle_timeout = opts.idle_timeout;
+
+        let mut write_timeout = opts.write_timeout;
+        let mut zero_copy = opts.zero_copy;
+        #[cfg(feature = "io-uring")]
+        let mut io_uring = opts.io_uring;
+        #[cfg(feature = "mmap")]
+        let mut mmap_max_size = opts.mmap_max_size;
+        let mut request_timeout = opts.request_timeout;
+        let mut not_found_cache_ttl = opts.not_found_cache_ttl;
+        let mut not_found_cache_capacity = opts.not_found_cache_capacity;
+
+        let mut control_listen = opts.control_listen;
+        let mut control_token = opts.control_token;
+        let mut config_audit_log = opts.config_audit_log;
+        let mut fsync_policy = opts.fsync_policy;
+
+        #[cfg(unix)]
+        let mut socket = opts.socket;
+
+        #[cfg(unix)]
+        let mut socket_mode = opts.socket_mode;
+
+        #[cfg(unix)]
+        let mut socket_owner = opts.socket_owner;
+
+        #[cfg(unix)]
+        let mut socket_only = opts.socket_only;
+
+        let mut media_streaming = opts.media_streaming;
 
         // Windows-only options
         #[cfg(windows)]
@@ -223,6 +808,7 @@ impl Settings {
 
         // Define the advanced file options
         let mut settings_advanced: Option<Advanced> = None;
+        let mut settings_listeners: Vec<Listener> = Vec::new();
 
         let to_use_config_file = match Path::new("./config.toml").is_file() {
             true => {
@@ -251,15 +837,33 @@ impl Settings {
                 if let Some(v) = general.root {
                     root = v
                 }
+                if let Some(v) = general.overlay_roots {
+                    overlay_roots = v
+                }
+                if let Some(v) = general.embedded_assets {
+                    embedded_assets = v
+                }
                 if let Some(ref v) = general.log_level {
                     log_level = v.name().to_lowercase();
                 }
                 if let Some(v) = general.log_with_ansi {
                     log_with_ansi = v;
                 }
+                if let Some(v) = general.log_file {
+                    log_file = Some(v);
+                }
+                if let Some(v) = general.startup_summary {
+                    startup_summary = v
+                }
+                if let Some(v) = general.quiet {
+                    quiet = v
+                }
                 if let Some(v) = general.cache_control_headers {
                     cache_control_headers = v
                 }
+                if let Some(v) = general.type_map {
+                    type_map = v
+                }
                 #[cfg(any(
                     feature = "compression",
                     feature = "compression-gzip",
@@ -287,30 +891,130 @@ impl Settings {
                     feature = "compression-zstd",
                     feature = "compression-deflate"
                 ))]
-                if let Some(v) = general.compression_static {
-                    compression_static = v
+                if let Some(v) = general.compression_level_gzip {
+                    compression_level_gzip = Some(v)
                 }
-                if let Some(v) = general.page404 {
-                    page404 = v
-                }
-                if let Some(v) = general.page50x {
-                    page50x = v
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                if let Some(v) = general.compression_level_brotli {
+                    compression_level_brotli = Some(v)
                 }
-                #[cfg(feature = "http2")]
-                if let Some(v) = general.http2 {
-                    http2 = v
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                if let Some(v) = general.compression_level_zstd {
+                    compression_level_zstd = Some(v)
                 }
-                #[cfg(feature = "http2")]
-                if let Some(v) = general.http2_tls_cert {
-                    http2_tls_cert = Some(v)
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                if let Some(v) = general.compression_min_size {
+                    compression_min_size = v
                 }
-                #[cfg(feature = "http2")]
-                if let Some(v) = general.http2_tls_key {
-                    http2_tls_key = Some(v)
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                if let Some(v) = general.compression_mime_types {
+                    compression_mime_types = v
                 }
-                #[cfg(feature = "http2")]
-                if let Some(v) = general.https_redirect {
-                    https_redirect = v
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                if let Some(v) = general.compression_exclude_mime_types {
+                    compression_exclude_mime_types = v
+                }
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                if let Some(v) = general.compression_adaptive {
+                    compression_adaptive = v
+                }
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                if let Some(v) = general.compression_adaptive_high_load {
+                    compression_adaptive_high_load = v
+                }
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                if let Some(v) = general.compression_adaptive_max_load {
+                    compression_adaptive_max_load = v
+                }
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                if let Some(v) = general.compression_static {
+                    compression_static = v
+                }
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                if let Some(v) = general.compression_static_order {
+                    compression_static_order = v
+                }
+                if let Some(v) = general.page404 {
+                    page404 = v
+                }
+                if let Some(v) = general.page50x {
+                    page50x = v
+                }
+                #[cfg(feature = "http2")]
+                if let Some(v) = general.http2 {
+                    http2 = v
+                }
+                #[cfg(feature = "http2")]
+                if let Some(v) = general.http2_tls_cert {
+                    http2_tls_cert = Some(v)
+                }
+                #[cfg(feature = "http2")]
+                if let Some(v) = general.http2_tls_key {
+                    http2_tls_key = Some(v)
+                }
+                #[cfg(feature = "http2")]
+                if let Some(v) = general.https_redirect {
+                    https_redirect = v
                 }
                 #[cfg(feature = "http2")]
                 if let Some(v) = general.https_redirect_host {
@@ -325,6 +1029,18 @@ impl Settings {
                     https_redirect_from_hosts = v
                 }
                 #[cfg(feature = "http2")]
+                if let Some(v) = general.https_redirect_status {
+                    https_redirect_status = v
+                }
+                #[cfg(feature = "http2")]
+                if let Some(v) = general.https_redirect_preserve_host {
+                    https_redirect_preserve_host = v
+                }
+                #[cfg(feature = "http2")]
+                if let Some(v) = general.https_redirect_hsts {
+                    https_redirect_hsts = v
+                }
+                #[cfg(feature = "http2")]
                 match general.security_headers {
                     Some(v) => security_headers = v,
                     _ => {
@@ -351,13 +1067,61 @@ impl Settings {
                     directory_listing = v
                 }
                 #[cfg(feature = "directory-listing")]
+                if let Some(v) = general.directory_listing_paths {
+                    directory_listing_paths = v
+                }
+                #[cfg(feature = "directory-listing")]
                 if let Some(v) = general.directory_listing_order {
                     directory_listing_order = v
                 }
                 #[cfg(feature = "directory-listing")]
+                if let Some(v) = general.directory_listing_sort {
+                    directory_listing_sort = v
+                }
+                #[cfg(feature = "directory-listing")]
+                if let Some(v) = general.directory_listing_dirs_first {
+                    directory_listing_dirs_first = v
+                }
+                #[cfg(feature = "directory-listing")]
                 if let Some(v) = general.directory_listing_format {
                     directory_listing_format = v
                 }
+                #[cfg(feature = "directory-listing")]
+                if let Some(v) = general.directory_listing_dir_size {
+                    directory_listing_dir_size = v
+                }
+                #[cfg(feature = "directory-listing")]
+                if let Some(v) = general.directory_listing_dir_size_ttl {
+                    directory_listing_dir_size_ttl = v
+                }
+                #[cfg(feature = "directory-listing")]
+                if let Some(v) = general.directory_listing_dir_size_max_entries {
+                    directory_listing_dir_size_max_entries = v
+                }
+                #[cfg(feature = "directory-listing")]
+                if let Some(v) = general.directory_listing_dir_size_concurrency {
+                    directory_listing_dir_size_concurrency = v
+                }
+                #[cfg(feature = "directory-listing")]
+                if let Some(v) = general.directory_listing_details {
+                    directory_listing_details = v
+                }
+                #[cfg(feature = "directory-listing")]
+                if let Some(v) = general.directory_listing_locale {
+                    directory_listing_locale = v
+                }
+                #[cfg(feature = "directory-listing")]
+                if let Some(v) = general.directory_listing_hide {
+                    directory_listing_hide = v
+                }
+                #[cfg(feature = "directory-listing")]
+                if let Some(v) = general.directory_listing_header {
+                    directory_listing_header = v
+                }
+                #[cfg(feature = "directory-listing")]
+                if let Some(v) = general.directory_listing_readme {
+                    directory_listing_readme = v
+                }
                 #[cfg(feature = "directory-listing-download")]
                 if let Some(v) = general.directory_listing_download {
                     directory_listing_download = v
@@ -369,12 +1133,24 @@ impl Settings {
                 if let Some(v) = general.fd {
                     fd = Some(v)
                 }
+                if let Some(v) = general.interface {
+                    interface = Some(v)
+                }
                 if let Some(v) = general.threads_multiplier {
                     threads_multiplier = v
                 }
+                if let Some(v) = general.worker_threads {
+                    worker_threads = v
+                }
                 if let Some(v) = general.max_blocking_threads {
                     max_blocking_threads = v
                 }
+                if let Some(v) = general.thread_stack_size {
+                    thread_stack_size = v
+                }
+                if let Some(v) = general.worker_processes {
+                    worker_processes = v
+                }
                 if let Some(v) = general.grace_period {
                     grace_period = v
                 }
@@ -382,6 +1158,23 @@ impl Settings {
                 if let Some(v) = general.page_fallback {
                     page_fallback = v
                 }
+                #[cfg(feature = "fallback-page")]
+                if let Some(v) = general.page_fallback_soft404 {
+                    page_fallback_soft404 = v
+                }
+                #[cfg(feature = "fallback-page")]
+                if let Some(v) = general.page_fallback_status {
+                    page_fallback_status =
+                        StatusCode::from_u16(v).with_context(|| "invalid HTTP status code")?
+                }
+                #[cfg(feature = "fallback-page")]
+                if let Some(v) = general.page_fallback_methods {
+                    page_fallback_methods = v
+                }
+                #[cfg(feature = "fallback-page")]
+                if let Some(v) = general.page_fallback_html_only {
+                    page_fallback_html_only = v
+                }
                 if let Some(v) = general.log_remote_address {
                     log_remote_address = v
                 }
@@ -394,21 +1187,46 @@ impl Settings {
                 if let Some(v) = general.trusted_proxies {
                     trusted_proxies = v
                 }
+                if let Some(v) = general.trusted_proxies_refresh {
+                    trusted_proxies_refresh = v
+                }
                 if let Some(v) = general.redirect_trailing_slash {
                     redirect_trailing_slash = v
                 }
+                if let Some(v) = general.reject_absolute_form {
+                    reject_absolute_form = v
+                }
                 if let Some(v) = general.ignore_hidden_files {
                     ignore_hidden_files = v
                 }
+                if let Some(v) = general.ignore_patterns {
+                    ignore_patterns = v
+                }
                 if let Some(v) = general.disable_symlinks {
                     disable_symlinks = v
                 }
+                if let Some(v) = general.follow_symlinks {
+                    follow_symlinks = v
+                }
                 if let Some(v) = general.health {
                     health = v
                 }
+                if let Some(v) = general.health_ready_files {
+                    health_ready_files = v
+                }
                 if let Some(v) = general.accept_markdown {
                     accept_markdown = v
                 }
+                if let Some(v) = general.language_variants {
+                    language_variants = v
+                }
+                if let Some(v) = general.image_variants {
+                    image_variants = v
+                }
+                #[cfg(feature = "metrics")]
+                if let Some(v) = general.metrics {
+                    metrics = v
+                }
                 #[cfg(all(unix, feature = "experimental"))]
                 if let Some(v) = general.experimental_metrics {
                     experimental_metrics = v
@@ -426,6 +1244,190 @@ impl Settings {
                 if let Some(v) = general.maintenance_mode_file {
                     maintenance_mode_file = v
                 }
+                if let Some(v) = general.maintenance_mode_allow_ips {
+                    maintenance_mode_allow_ips = v
+                }
+                if let Some(v) = general.maintenance_mode_allow_paths {
+                    maintenance_mode_allow_paths = v
+                }
+                if let Some(v) = general.maintenance_mode_start {
+                    maintenance_mode_start = v
+                }
+                if let Some(v) = general.maintenance_mode_end {
+                    maintenance_mode_end = v
+                }
+                #[cfg(feature = "markdown")]
+                if let Some(v) = general.markdown_render {
+                    markdown_render = v
+                }
+                #[cfg(feature = "markdown")]
+                if let Some(v) = general.markdown_render_template {
+                    markdown_render_template = v
+                }
+                if let Some(v) = general.ssi {
+                    ssi = v
+                }
+                if let Some(v) = general.ssi_extensions {
+                    ssi_extensions = v
+                }
+                if let Some(v) = general.dev_reload {
+                    dev_reload = v
+                }
+                if let Some(v) = general.dev_reload_inject {
+                    dev_reload_inject = v
+                }
+                if let Some(v) = general.probe_endpoint {
+                    probe_endpoint = v
+                }
+                if let Some(v) = general.server_ident {
+                    server_ident = v
+                }
+                if let Some(v) = general.alt_svc {
+                    alt_svc = v
+                }
+                if let Some(v) = general.alt_svc_max_age {
+                    alt_svc_max_age = v
+                }
+                if let Some(v) = general.default_charset {
+                    default_charset = v
+                }
+                if let Some(v) = general.max_uri_length {
+                    max_uri_length = v
+                }
+                if let Some(v) = general.max_headers_size {
+                    max_headers_size = v
+                }
+                if let Some(v) = general.max_body_size {
+                    max_body_size = v
+                }
+                if let Some(v) = general.limit_rate {
+                    limit_rate = v
+                }
+                if let Some(v) = general.limit_rate_global {
+                    limit_rate_global = v
+                }
+                if let Some(v) = general.max_connections {
+                    max_connections = v
+                }
+                if let Some(v) = general.max_connections_per_ip {
+                    max_connections_per_ip = v
+                }
+                #[cfg(feature = "archive-preview")]
+                if let Some(v) = general.archive_preview {
+                    archive_preview = v
+                }
+                #[cfg(feature = "archive-preview")]
+                if let Some(v) = general.archive_preview_max_entries {
+                    archive_preview_max_entries = v
+                }
+                #[cfg(feature = "archive-preview")]
+                if let Some(v) = general.archive_preview_max_size {
+                    archive_preview_max_size = v
+                }
+                #[cfg(feature = "subresource-integrity")]
+                if let Some(v) = general.sri_manifest {
+                    sri_manifest = v
+                }
+                #[cfg(feature = "subresource-integrity")]
+                if let Some(v) = general.sri_algorithm {
+                    sri_algorithm = v
+                }
+                #[cfg(feature = "subresource-integrity")]
+                if let Some(v) = general.sri_extensions {
+                    sri_extensions = v
+                }
+                #[cfg(feature = "s3-origin")]
+                if let Some(v) = general.s3_origin {
+                    s3_origin = v
+                }
+                #[cfg(feature = "s3-origin")]
+                if let Some(v) = general.s3_region {
+                    s3_region = v
+                }
+                #[cfg(feature = "s3-origin")]
+                if let Some(v) = general.s3_access_key_id {
+                    s3_access_key_id = v
+                }
+                #[cfg(feature = "s3-origin")]
+                if let Some(v) = general.s3_secret_access_key {
+                    s3_secret_access_key = v
+                }
+                #[cfg(feature = "s3-origin")]
+                if let Some(v) = general.s3_cache_dir {
+                    s3_cache_dir = v
+                }
+                #[cfg(feature = "s3-origin")]
+                if let Some(v) = general.s3_cache_ttl {
+                    s3_cache_ttl = v
+                }
+                #[cfg(feature = "s3-origin")]
+                if let Some(v) = general.s3_cache_max_size {
+                    s3_cache_max_size = v
+                }
+                #[cfg(feature = "s3-origin")]
+                if let Some(v) = general.s3_ca_file {
+                    s3_ca_file = v
+                }
+                if let Some(v) = general.read_header_timeout {
+                    read_header_timeout = v
+                }
+                if let Some(v) = general.idle_timeout {
+                    idle_timeout = v
+                }
+                if let Some(v) = general.write_timeout {
+                    write_timeout = v
+                }
+                if let Some(v) = general.zero_copy {
+                    zero_copy = v
+                }
+                #[cfg(feature = "io-uring")]
+                if let Some(v) = general.io_uring {
+                    io_uring = v
+                }
+                #[cfg(feature = "mmap")]
+                if let Some(v) = general.mmap_max_size {
+                    mmap_max_size = v
+                }
+                if let Some(v) = general.request_timeout {
+                    request_timeout = v
+                }
+                if let Some(v) = general.not_found_cache_ttl {
+                    not_found_cache_ttl = v
+                }
+                if let Some(v) = general.not_found_cache_capacity {
+                    not_found_cache_capacity = v
+                }
+                if let Some(v) = general.control_listen {
+                    control_listen = v
+                }
+                if let Some(v) = general.control_token {
+                    control_token = v
+                }
+                if let Some(v) = general.config_audit_log {
+                    config_audit_log = v
+                }
+                if let Some(v) = general.fsync_policy {
+                    fsync_policy = v
+                }
+                #[cfg(unix)]
+                if let Some(v) = general.socket {
+                    socket = Some(v)
+                }
+                #[cfg(unix)]
+                if let Some(v) = general.socket_mode {
+                    socket_mode = Some(v)
+                }
+                #[cfg(unix)]
+                if let Some(v) = general.socket_owner {
+                    socket_owner = Some(v)
+                }
+                #[cfg(unix)]
+                if let Some(v) = general.socket_only {
+                    socket_only = v
+                }
+                if let Some(v) = general.media_streaming {
+                    media_streaming = v
+                }
 
                 // Windows-only options
                 #[cfg(windows)]
@@ -436,7 +1438,7 @@ impl Settings {
 
             // Logging system initialization in config file context
             if log_init {
-                logger::init(log_level.as_str(), log_with_ansi)?;
+                logger::init_with_file(log_level.as_str(), log_with_ansi, log_file.as_deref())?;
             }
 
             tracing::debug!("config file read successfully");
@@ -458,18 +1460,61 @@ impl Settings {
 
                         // Compile a glob pattern for each header sources entry
                         for headers_entry in headers_entries.iter() {
-                            let source = Glob::new(&headers_entry.source)
-                                .with_context(|| {
-                                    format!(
-                                        "can not compile glob pattern for header source: {}",
-                                        &headers_entry.source
-                                    )
-                                })?
-                                .compile_matcher();
+                            let source = Glob::new(&headers_entry.source).with_context(|| {
+                                format!(
+                                    "can not compile glob pattern for header source: {}",
+                                    &headers_entry.source
+                                )
+                            });
+                            let Some(source) = errors.check("headers", source) else {
+                                continue;
+                            };
+                            let source = source.compile_matcher();
+
+                            let remove = headers_entry
+                                .remove
+                                .iter()
+                                .flatten()
+                                .map(|name| {
+                                    HeaderName::try_from(name.as_str()).with_context(|| {
+                                        format!("invalid header name to remove: {name}")
+                                    })
+                                })
+                                .collect::<Result<Vec<_>>>()?;
+
+                            let status = match &headers_entry.status {
+                                Some(codes) => Some(
+                                    codes
+                                        .iter()
+                                        .map(|code| {
+                                            StatusCode::from_u16(*code).with_context(|| {
+                                                format!("invalid header status code: {code}")
+                                            })
+                                        })
+                                        .collect::<Result<Vec<_>>>()?,
+                                ),
+                                None => None,
+                            };
+
+                            let content_type = match &headers_entry.content_type {
+                                Some(content_type) => Some(
+                                    Glob::new(&content_type.to_lowercase())
+                                        .with_context(|| {
+                                            format!(
+                                                "can not compile glob pattern for header content type: {content_type}"
+                                            )
+                                        })?
+                                        .compile_matcher(),
+                                ),
+                                None => None,
+                            };
 
                             headers_vec.push(Headers {
                                 source,
                                 headers: headers_entry.headers.to_owned(),
+                                remove,
+                                status,
+                                content_type,
                             });
                         }
                         Some(headers_vec)
@@ -482,44 +1527,44 @@ impl Settings {
                     Some(rewrites_entries) => {
                         let mut rewrites_vec: Vec<Rewrites> = Vec::new();
 
-                        // Compile a glob pattern for each rewrite sources entry
+                        // Compile a glob or regex pattern for each rewrite sources entry
                         for rewrites_entry in rewrites_entries.iter() {
-                            let source = GlobBuilder::new(&rewrites_entry.source)
-                                .literal_separator(true)
-                                .build()
-                                .with_context(|| {
-                                    format!(
-                                        "can not compile glob pattern for rewrite source: {}",
-                                        &rewrites_entry.source
-                                    )
-                                })?
-                                .compile_matcher();
-
-                            let pattern = source
-                                .glob()
-                                .regex()
-                                .trim_start_matches("(?-u)")
-                                .replace("?:.*", ".*")
-                                .replace("?:", "")
-                                .replace(".*.*", ".*")
-                                .to_owned();
-                            tracing::debug!(
-                                "url rewrites glob pattern: {}",
-                                &rewrites_entry.source
+                            let source = source_regex(
+                                &rewrites_entry.source,
+                                rewrites_entry.regex.unwrap_or_default(),
+                                "rewrite",
                             );
-                            tracing::debug!("url rewrites regex equivalent: {}", pattern);
-
-                            let source = Regex::new(&pattern).with_context(|| {
-                                    format!(
-                                        "can not compile regex pattern equivalent for rewrite source: {}",
-                                        &pattern
-                                    )
-                                })?;
+                            let conditions = request_conditions(
+                                rewrites_entry.scheme.as_deref(),
+                                rewrites_entry.headers.as_ref(),
+                                rewrites_entry.query.as_ref(),
+                                "rewrite",
+                            );
+                            let chain = rewrites_entry.chain.unwrap_or_default();
+                            if chain && rewrites_entry.redirect.is_some() {
+                                errors.push(
+                                    "rewrites",
+                                    anyhow!(
+                                        "rewrite source \"{}\" can't be chained since it's a redirect",
+                                        rewrites_entry.source
+                                    ),
+                                );
+                            }
+                            let (Some(source), Some(conditions)) = (
+                                errors.check("rewrites", source),
+                                errors.check("rewrites", conditions),
+                            ) else {
+                                continue;
+                            };
 
                             rewrites_vec.push(Rewrites {
+                                host: rewrites_entry.host.to_owned(),
                                 source,
                                 destination: rewrites_entry.destination.to_owned(),
                                 redirect: rewrites_entry.redirect.to_owned(),
+                                conditions,
+                                chain,
+                                append_query: rewrites_entry.append_query.unwrap_or_default(),
                             });
                         }
                         Some(rewrites_vec)
@@ -532,48 +1577,37 @@ impl Settings {
                     Some(redirects_entries) => {
                         let mut redirects_vec: Vec<Redirects> = Vec::new();
 
-                        // Compile a glob pattern for each redirect sources entry
+                        // Compile a glob or regex pattern for each redirect sources entry
                         for redirects_entry in redirects_entries.iter() {
-                            let source = GlobBuilder::new(&redirects_entry.source)
-                                .literal_separator(true)
-                                .build()
-                                .with_context(|| {
-                                    format!(
-                                        "can not compile glob pattern for redirect source: {}",
-                                        &redirects_entry.source
-                                    )
-                                })?
-                                .compile_matcher();
-
-                            let pattern = source
-                                .glob()
-                                .regex()
-                                .trim_start_matches("(?-u)")
-                                .replace("?:.*", ".*")
-                                .replace("?:", "")
-                                .replace(".*.*", ".*")
-                                .to_owned();
-                            tracing::debug!(
-                                "url redirects glob pattern: {}",
-                                &redirects_entry.source
+                            let source = source_regex(
+                                &redirects_entry.source,
+                                redirects_entry.regex.unwrap_or_default(),
+                                "redirect",
                             );
-                            tracing::debug!("url redirects regex equivalent: {}", pattern);
-
-                            let source = Regex::new(&pattern).with_context(|| {
-                                    format!(
-                                        "can not compile regex pattern equivalent for redirect source: {}",
-                                        &pattern
-                                    )
-                                })?;
-
                             let status_code = redirects_entry.kind.to_owned() as u16;
+                            let kind = StatusCode::from_u16(status_code).with_context(|| {
+                                format!("invalid redirect status code: {status_code}")
+                            });
+                            let conditions = request_conditions(
+                                redirects_entry.scheme.as_deref(),
+                                redirects_entry.headers.as_ref(),
+                                redirects_entry.query.as_ref(),
+                                "redirect",
+                            );
+                            let (Some(source), Some(kind), Some(conditions)) = (
+                                errors.check("redirects", source),
+                                errors.check("redirects", kind),
+                                errors.check("redirects", conditions),
+                            ) else {
+                                continue;
+                            };
+
                             redirects_vec.push(Redirects {
                                 host: redirects_entry.host.to_owned(),
                                 source,
                                 destination: redirects_entry.destination.to_owned(),
-                                kind: StatusCode::from_u16(status_code).with_context(|| {
-                                    format!("invalid redirect status code: {status_code}")
-                                })?,
+                                kind,
+                                conditions,
                             });
                         }
                         Some(redirects_vec)
@@ -589,8 +1623,15 @@ impl Settings {
                         for vhosts_entry in vhosts_entries.iter() {
                             if let Some(root) = vhosts_entry.root.to_owned() {
                                 // Make sure path is valid
-                                let root_dir = helpers::get_valid_dirpath(&root)
-                                    .with_context(|| "root directory for virtual host was not found or inaccessible")?;
+                                let root_dir = helpers::get_valid_dirpath(&root).with_context(|| {
+                                    format!(
+                                        "root directory for virtual host {} was not found or inaccessible",
+                                        vhosts_entry.host
+                                    )
+                                });
+                                let Some(root_dir) = errors.check("virtual-hosts", root_dir) else {
+                                    continue;
+                                };
                                 tracing::debug!(
                                     "added virtual host: {} -> {}",
                                     vhosts_entry.host,
@@ -599,6 +1640,9 @@ impl Settings {
                                 vhosts_vec.push(VirtualHosts {
                                     host: vhosts_entry.host.to_owned(),
                                     root: root_dir,
+                                    directory_listing_locale: vhosts_entry
+                                        .directory_listing_locale
+                                        .to_owned(),
                                 });
                             }
                         }
@@ -607,54 +1651,748 @@ impl Settings {
                     _ => None,
                 };
 
-                settings_advanced = Some(Advanced {
-                    headers: headers_entries,
-                    rewrites: rewrites_entries,
-                    redirects: redirects_entries,
-                    virtual_hosts: vhosts_entries,
-                    #[cfg(feature = "experimental")]
-                    memory_cache: advanced.memory_cache,
-                });
-            }
-        } else if log_init {
-            // Logging system initialization on demand
-            logger::init(log_level.as_str(), log_with_ansi)?;
-        }
+                // 4. MIME types assignment
+                let mime_types_entries = match advanced.mime_types {
+                    Some(mime_types_entries) => {
+                        let mut mime_types_map: HashMap<String, Mime> = HashMap::new();
 
-        Ok(Settings {
-            general: General {
-                version,
-                host,
-                port,
-                root,
-                log_level,
-                log_with_ansi,
-                config_file,
-                cache_control_headers,
-                #[cfg(any(
-                    feature = "compression",
-                    feature = "compression-gzip",
-                    feature = "compression-brotli",
-                    feature = "compression-zstd",
-                    feature = "compression-deflate"
-                ))]
-                compression,
-                #[cfg(any(
-                    feature = "compression",
-                    feature = "compression-gzip",
-                    feature = "compression-brotli",
-                    feature = "compression-zstd",
-                    feature = "compression-deflate"
-                ))]
-                compression_level,
-                #[cfg(any(
-                    feature = "compression",
-                    feature = "compression-gzip",
-                    feature = "compression-brotli",
+                        for (ext, content_type) in mime_types_entries.iter() {
+                            let ext = ext.trim().trim_start_matches('.').to_lowercase();
+                            if ext.is_empty() {
+                                bail!("empty file extension found in mime types");
+                            }
+                            let mime = content_type.parse::<Mime>().with_context(|| {
+                                format!("invalid mime type \"{content_type}\" for extension: {ext}")
+                            })?;
+                            tracing::debug!("added mime type: .{} -> {}", ext, mime);
+                            mime_types_map.insert(ext, mime);
+                        }
+                        Some(mime_types_map)
+                    }
+                    _ => None,
+                };
+
+                // 5. Charsets assignment
+                let charsets_entries = match advanced.charsets {
+                    Some(charsets_entries) => {
+                        let mut charsets_map: HashMap<String, String> = HashMap::new();
+
+                        for (ext, charset) in charsets_entries.iter() {
+                            let ext = ext.trim().trim_start_matches('.').to_lowercase();
+                            if ext.is_empty() {
+                                bail!("empty file extension found in charsets");
+                            }
+                            let charset = charset.trim();
+                            if !crate::mime_types::is_valid_charset(charset) {
+                                bail!("invalid charset \"{charset}\" for extension: {ext}");
+                            }
+                            tracing::debug!("added charset: .{} -> {}", ext, charset);
+                            charsets_map.insert(ext, charset.to_owned());
+                        }
+                        Some(charsets_map)
+                    }
+                    _ => None,
+                };
+
+                // 6. Status rewrites assignment
+                let status_rewrites_entries = match advanced.status_rewrites {
+                    Some(status_rewrites_entries) => {
+                        let mut status_rewrites_vec: Vec<StatusRewrites> = Vec::new();
+
+                        // Compile a glob pattern for each status rewrite sources entry
+                        for status_rewrites_entry in status_rewrites_entries.iter() {
+                            let source = GlobBuilder::new(&status_rewrites_entry.source)
+                                .literal_separator(true)
+                                .build()
+                                .with_context(|| {
+                                    format!(
+                                        "can not compile glob pattern for status rewrite source: {}",
+                                        &status_rewrites_entry.source
+                                    )
+                                })?
+                                .compile_matcher();
+
+                            let status = StatusCode::from_u16(status_rewrites_entry.status)
+                                .with_context(|| {
+                                    format!(
+                                        "invalid status rewrite status code: {}",
+                                        status_rewrites_entry.status
+                                    )
+                                })?;
+                            let to = StatusCode::from_u16(status_rewrites_entry.to)
+                                .ok()
+                                .filter(|s| s.is_client_error() || s.is_server_error())
+                                .with_context(|| {
+                                    format!(
+                                        "invalid status rewrite status code: {}, only 4xx or 5xx status codes are supported",
+                                        status_rewrites_entry.to
+                                    )
+                                })?;
+
+                            tracing::debug!(
+                                "added status rewrite: {} {} -> {}",
+                                &status_rewrites_entry.source,
+                                status.as_u16(),
+                                to.as_u16()
+                            );
+                            status_rewrites_vec.push(StatusRewrites { source, status, to });
+                        }
+                        Some(status_rewrites_vec)
+                    }
+                    _ => None,
+                };
+
+                // 7. Content types assignment
+                let content_types_entries = match advanced.content_types {
+                    Some(content_types_entries) => {
+                        let mut content_types_vec: Vec<ContentTypes> = Vec::new();
+
+                        // Compile a glob pattern for each content types sources entry
+                        for content_types_entry in content_types_entries.iter() {
+                            let source = GlobBuilder::new(&content_types_entry.source)
+                                .literal_separator(true)
+                                .build()
+                                .with_context(|| {
+                                    format!(
+                                        "can not compile glob pattern for content type source: {}",
+                                        &content_types_entry.source
+                                    )
+                                })?
+                                .compile_matcher();
+
+                            let content_type = match &content_types_entry.content_type {
+                                Some(content_type) => {
+                                    Some(content_type.parse::<Mime>().with_context(|| {
+                                        format!(
+                                            "invalid content type for source {}: {}",
+                                            &content_types_entry.source, content_type
+                                        )
+                                    })?)
+                                }
+                                None => None,
+                            };
+                            let download = content_types_entry.download.unwrap_or_default();
+                            if content_type.is_none() && !download {
+                                bail!(
+                                    "content type entry for source {} requires a content type or download option",
+                                    &content_types_entry.source
+                                );
+                            }
+
+                            tracing::debug!(
+                                "added content type: {} {} download={}",
+                                &content_types_entry.source,
+                                content_type.as_ref().map_or("-", |m| m.as_ref()),
+                                download
+                            );
+                            content_types_vec.push(ContentTypes {
+                                source,
+                                content_type,
+                                download,
+                            });
+                        }
+                        Some(content_types_vec)
+                    }
+                    _ => None,
+                };
+
+                // 8. Error pages assignment
+                let error_pages_entries = match advanced.error_pages {
+                    Some(error_pages_entries) => {
+                        let mut error_pages_vec: Vec<ErrorPage> = Vec::new();
+                        for (status, path) in error_pages_entries {
+                            let status = status.parse::<ErrorPageStatus>()?;
+                            tracing::debug!("added error page: {} {}", status, path.display());
+                            error_pages_vec.push(ErrorPage { status, path });
+                        }
+                        Some(error_pages_vec)
+                    }
+                    _ => None,
+                };
+
+                // 9. Try files assignment
+                let try_files_entries = match advanced.try_files {
+                    Some(try_files_entries) => {
+                        let mut try_files_vec: Vec<TryFiles> = Vec::new();
+
+                        // Compile a glob pattern for each try files sources entry
+                        for try_files_entry in try_files_entries {
+                            let source = GlobBuilder::new(&try_files_entry.source)
+                                .literal_separator(true)
+                                .build()
+                                .with_context(|| {
+                                    format!(
+                                        "can not compile glob pattern for try files source: {}",
+                                        &try_files_entry.source
+                                    )
+                                })?
+                                .compile_matcher();
+
+                            if try_files_entry.files.is_empty()
+                                && try_files_entry.fallback.is_none()
+                            {
+                                bail!(
+                                    "try files entry for source {} requires candidate files or a fallback",
+                                    &try_files_entry.source
+                                );
+                            }
+                            for path in try_files_entry
+                                .files
+                                .iter()
+                                .chain(try_files_entry.fallback.iter())
+                            {
+                                if !is_valid_try_files_path(path) {
+                                    bail!(
+                                        "invalid try files path for source {}: {}, it should start with `/` or `{}` and contain no query string",
+                                        &try_files_entry.source,
+                                        path,
+                                        try_files::URI_PLACEHOLDER
+                                    );
+                                }
+                            }
+
+                            tracing::debug!(
+                                "added try files: {} {:?} fallback={}",
+                                &try_files_entry.source,
+                                &try_files_entry.files,
+                                try_files_entry.fallback.as_deref().unwrap_or("-")
+                            );
+                            try_files_vec.push(TryFiles {
+                                source,
+                                files: try_files_entry.files,
+                                fallback: try_files_entry.fallback,
+                            });
+                        }
+                        Some(try_files_vec)
+                    }
+                    _ => None,
+                };
+
+                // 10. Fallback pages assignment
+                #[cfg(feature = "fallback-page")]
+                let fallback_pages_entries = match advanced.fallback_pages {
+                    Some(fallback_pages_entries) => {
+                        let mut fallback_pages_vec: Vec<FallbackPages> = Vec::new();
+
+                        // Compile a glob pattern for each fallback pages sources entry
+                        for fallback_page_entry in fallback_pages_entries {
+                            let source = GlobBuilder::new(&fallback_page_entry.source)
+                                .literal_separator(true)
+                                .build()
+                                .with_context(|| {
+                                    format!(
+                                        "can not compile glob pattern for fallback page source: {}",
+                                        &fallback_page_entry.source
+                                    )
+                                })?
+                                .compile_matcher();
+
+                            tracing::debug!(
+                                "added fallback page: {} {}",
+                                &fallback_page_entry.source,
+                                fallback_page_entry.path.display()
+                            );
+                            fallback_pages_vec.push(FallbackPages {
+                                source,
+                                path: fallback_page_entry.path,
+                            });
+                        }
+                        Some(fallback_pages_vec)
+                    }
+                    _ => None,
+                };
+
+                // 11. Cache-Control policies assignment
+                let cache_control_entries = match advanced.cache_control {
+                    Some(cache_control_entries) => {
+                        let mut cache_control_vec: Vec<CacheControl> = Vec::new();
+
+                        // Compile a glob pattern for each cache control sources entry
+                        for cache_control_entry in cache_control_entries {
+                            let source = GlobBuilder::new(&cache_control_entry.source)
+                                .literal_separator(true)
+                                .build()
+                                .with_context(|| {
+                                    format!(
+                                        "can not compile glob pattern for cache control source: {}",
+                                        &cache_control_entry.source
+                                    )
+                                })?
+                                .compile_matcher();
+                            let value = control_headers::policy_value(&cache_control_entry)
+                                .with_context(|| {
+                                    format!(
+                                        "invalid cache control policy for source: {}",
+                                        &cache_control_entry.source
+                                    )
+                                })?;
+
+                            let expires = if cache_control_entry.expires.unwrap_or_default() {
+                                match cache_control_entry.max_age {
+                                    Some(max_age) => Some(std::time::Duration::from_secs(
+                                        control_headers::cap_seconds(max_age),
+                                    )),
+                                    None => bail!(
+                                        "cache control policy for source {} requires `max-age` to use `expires`",
+                                        &cache_control_entry.source
+                                    ),
+                                }
+                            } else {
+                                None
+                            };
+
+                            tracing::debug!(
+                                "added cache control policy: {} {:?} expires={}",
+                                &cache_control_entry.source,
+                                value,
+                                expires.is_some()
+                            );
+                            cache_control_vec.push(CacheControl {
+                                source,
+                                value,
+                                expires,
+                            });
+                        }
+                        Some(cache_control_vec)
+                    }
+                    _ => None,
+                };
+
+                // 12. Time-based access restrictions assignment
+                #[cfg(feature = "time-restrictions")]
+                let time_restrictions_entries = match advanced.time_restrictions {
+                    Some(time_restrictions_entries) => {
+                        let mut time_restrictions_vec: Vec<TimeRestrictions> = Vec::new();
+
+                        // Compile a glob pattern for each time restrictions sources entry
+                        for entry in time_restrictions_entries {
+                            let source = GlobBuilder::new(&entry.source)
+                                .literal_separator(true)
+                                .build()
+                                .with_context(|| {
+                                    format!(
+                                        "can not compile glob pattern for time restrictions source: {}",
+                                        &entry.source
+                                    )
+                                })?
+                                .compile_matcher();
+                            let restriction = TimeRestrictions {
+                                host: entry.host,
+                                source,
+                                start: time_restrictions::parse_time(&entry.start)?,
+                                end: time_restrictions::parse_time(&entry.end)?,
+                                days: time_restrictions::parse_days(
+                                    entry.days.as_deref().unwrap_or_default(),
+                                )?,
+                                redirect: match &entry.redirect {
+                                    Some(redirect) => Some(
+                                        HeaderValue::from_str(redirect).with_context(|| {
+                                            format!(
+                                                "invalid time restrictions redirect: {redirect}"
+                                            )
+                                        })?,
+                                    ),
+                                    None => None,
+                                },
+                            };
+
+                            tracing::debug!(
+                                "added time restrictions: {} {}-{} days={:?}",
+                                &entry.source,
+                                restriction.start,
+                                restriction.end,
+                                restriction.days
+                            );
+                            time_restrictions_vec.push(restriction);
+                        }
+                        Some(time_restrictions_vec)
+                    }
+                    _ => None,
+                };
+
+                // 13. Security headers profile assignment
+                let security_entries = match advanced.security {
+                    Some(security) => Some(
+                        security_headers::profile(&security)
+                            .with_context(|| "invalid security headers profile")?,
+                    ),
+                    _ => None,
+                };
+
+                // 14. MIME type sniffing protection policies assignment
+                let content_type_options_entries = match advanced.content_type_options {
+                    Some(content_type_options_entries) => {
+                        let mut content_type_options_vec: Vec<ContentTypeOptions> = Vec::new();
+
+                        // Compile a glob pattern for each content type options sources entry
+                        for entry in content_type_options_entries {
+                            let source = GlobBuilder::new(&entry.source)
+                                .literal_separator(true)
+                                .build()
+                                .with_context(|| {
+                                    format!(
+                                        "can not compile glob pattern for content type options source: {}",
+                                        &entry.source
+                                    )
+                                })?
+                                .compile_matcher();
+
+                            tracing::debug!(
+                                "added content type options policy: {} nosniff={}",
+                                &entry.source,
+                                entry.nosniff
+                            );
+                            content_type_options_vec.push(ContentTypeOptions {
+                                source,
+                                nosniff: entry.nosniff,
+                            });
+                        }
+                        Some(content_type_options_vec)
+                    }
+                    _ => None,
+                };
+
+                // 15. CORS policies assignment
+                let cors_entries = match advanced.cors {
+                    Some(cors_entries) => {
+                        let mut cors_vec: Vec<Cors> = Vec::new();
+
+                        for entry in cors_entries {
+                            let path = entry.path.trim();
+                            if !path.starts_with('/') {
+                                bail!("cors policy path must start with a slash: {}", path);
+                            }
+                            let path = match path.trim_end_matches('/') {
+                                "" => "/",
+                                path => path,
+                            };
+                            let policy = cors::policy(&entry)
+                                .with_context(|| format!("invalid cors policy for path: {path}"))?;
+
+                            tracing::debug!(
+                                "added cors policy: {} allow_origins={:?} allow_credentials={}",
+                                path,
+                                entry.allow_origins,
+                                entry.allow_credentials.unwrap_or_default()
+                            );
+                            cors_vec.push(Cors {
+                                path: path.to_owned(),
+                                policy,
+                            });
+                        }
+                        Some(cors_vec)
+                    }
+                    _ => None,
+                };
+
+                // 16. WebSocket proxy routes assignment
+                let websocket_proxy_entries = match advanced.websocket_proxy {
+                    Some(websocket_proxy_entries) => {
+                        let mut websocket_proxy_vec: Vec<WebSocketProxy> = Vec::new();
+
+                        for entry in websocket_proxy_entries {
+                            let path = entry.path.trim();
+                            if !path.starts_with('/') {
+                                bail!("websocket proxy path must start with a slash: {}", path);
+                            }
+                            let path = match path.trim_end_matches('/') {
+                                "" => "/",
+                                path => path,
+                            };
+                            if websocket_proxy_vec.iter().any(|route| route.path == path) {
+                                bail!("duplicated websocket proxy path: {}", path);
+                            }
+                            let upstream = entry.upstream.parse().with_context(|| {
+                                format!("invalid websocket proxy upstream for path: {path}")
+                            })?;
+                            let timeout = match entry.timeout {
+                                Some(0) => bail!("websocket proxy timeout can't be zero: {}", path),
+                                Some(secs) => std::time::Duration::from_secs(secs),
+                                None => websocket_proxy::DEFAULT_TIMEOUT,
+                            };
+
+                            tracing::debug!(
+                                "added websocket proxy route: {} -> {}",
+                                path,
+                                entry.upstream.trim()
+                            );
+                            websocket_proxy_vec.push(WebSocketProxy {
+                                path: path.to_owned(),
+                                upstream,
+                                timeout,
+                            });
+                        }
+                        Some(websocket_proxy_vec)
+                    }
+                    _ => None,
+                };
+
+                settings_advanced = Some(Advanced {
+                    headers: headers_entries,
+                    rewrites: rewrites_entries,
+                    redirects: redirects_entries,
+                    virtual_hosts: vhosts_entries,
+                    status_rewrites: status_rewrites_entries,
+                    mime_types: mime_types_entries,
+                    charsets: charsets_entries,
+                    content_types: content_types_entries,
+                    error_pages: error_pages_entries,
+                    try_files: try_files_entries,
+                    cache_control: cache_control_entries,
+                    content_type_options: content_type_options_entries,
+                    cors: cors_entries,
+                    websocket_proxy: websocket_proxy_entries,
+                    #[cfg(feature = "time-restrictions")]
+                    time_restrictions: time_restrictions_entries,
+                    #[cfg(feature = "fallback-page")]
+                    fallback_pages: fallback_pages_entries,
+                    #[cfg(feature = "directory-listing")]
+                    directory_listing_locales: advanced.directory_listing_locales,
+                    #[cfg(feature = "experimental")]
+                    memory_cache: advanced.memory_cache,
+                    chunk_cache: advanced.chunk_cache,
+                    #[cfg(feature = "cache-invalidation")]
+                    cache_invalidation: advanced.cache_invalidation,
+                    security: security_entries,
+                });
+            }
+
+            // File-based "listeners" options
+            if let Some(listeners) = settings.listeners {
+                #[cfg(feature = "http2")]
+                if http2 || https_redirect {
+                    bail!(
+                        "listeners can't be used along with the `http2` or `https-redirect` options"
+                    );
+                }
+                if fd.is_some() {
+                    bail!("listeners can't be used along with the `fd` option");
+                }
+                settings_listeners = listener_entries(
+                    listeners,
+                    settings_advanced.as_ref(),
+                    #[cfg(feature = "http2")]
+                    &ListenerRedirect {
+                        host: https_redirect_host.clone(),
+                        port: 0,
+                        allowed_hosts: https_redirect_from_hosts
+                            .split(',')
+                            .map(|s| s.trim().to_owned())
+                            .filter(|s| !s.is_empty())
+                            .collect(),
+                        status: crate::https_redirect::redirect_status(https_redirect_status)?,
+                        preserve_host: https_redirect_preserve_host,
+                    },
+                )?;
+            }
+        } else if log_init {
+            // Logging system initialization on demand
+            logger::init_with_file(log_level.as_str(), log_with_ansi, log_file.as_deref())?;
+        }
+
+        // Unix domain socket listener, served along with the TCP address unless `socket-only`
+        #[cfg(unix)]
+        if let Some(path) = &socket {
+            #[cfg(feature = "http2")]
+            if http2 || https_redirect {
+                bail!("socket can't be used along with the `http2` or `https-redirect` options");
+            }
+            if fd.is_some() {
+                bail!("socket can't be used along with the `fd` option");
+            }
+
+            let address = ListenerAddress::Unix(path.clone());
+            if settings_listeners.iter().any(|l| l.address == address) {
+                bail!("socket {address} is already defined in the listeners");
+            }
+            // NOTE: the file-based listeners already replace the TCP address
+            if settings_listeners.is_empty() && !socket_only {
+                let ip = crate::interface::host_address(&host, interface.as_deref())?;
+                settings_listeners.push(Listener {
+                    address: ListenerAddress::Tcp(SocketAddr::from((ip, port))),
+                    http1: true,
+                    http2: false,
+                    tls: None,
+                    proxy_protocol: false,
+                    vhosts: None,
+                    socket_mode: None,
+                    socket_owner: None,
+                    https_redirect: None,
+                });
+            }
+            settings_listeners.push(Listener {
+                address,
+                http1: true,
+                http2: false,
+                tls: None,
+                proxy_protocol: false,
+                vhosts: None,
+                socket_mode: socket_mode.as_deref().map(parse_socket_mode).transpose()?,
+                socket_owner: socket_owner
+                    .as_deref()
+                    .map(parse_socket_owner)
+                    .transpose()?,
+                https_redirect: None,
+            });
+        }
+
+        // HTTPS redirect options of the redirect server and the listeners redirecting to HTTPS
+        #[cfg(feature = "http2")]
+        {
+            crate::https_redirect::redirect_status(https_redirect_status)?;
+            let redirects = (http2 && https_redirect)
+                || settings_listeners
+                    .iter()
+                    .any(|listener| listener.https_redirect.is_some());
+            if https_redirect_hsts && !redirects {
+                bail!(
+                    "https redirect hsts requires the `https-redirect` option or a listener redirecting to HTTPS"
+                );
+            }
+        }
+
+        // TLS certificate and key files of the HTTP/2 server
+        #[cfg(feature = "http2")]
+        if http2 {
+            for (kind, path) in [("cert", &http2_tls_cert), ("key", &http2_tls_key)] {
+                let result = match path {
+                    Some(path) => std::fs::File::open(path).map(drop).with_context(|| {
+                        format!("TLS {kind} file {} can not be read", path.display())
+                    }),
+                    None => Err(anyhow!("TLS {kind} file is missing")),
+                };
+                errors.check("tls", result);
+            }
+        }
+
+        errors.into_result()?;
+
+        // The Tokio runtime metrics are served by the metrics endpoint
+        #[cfg(all(unix, feature = "experimental"))]
+        if experimental_metrics {
+            metrics = true;
+        }
+
+        Ok(Settings {
+            general: General {
+                version,
+                host,
+                port,
+                root,
+                overlay_roots,
+                embedded_assets,
+                log_level,
+                log_with_ansi,
+                log_file,
+                startup_summary,
+                quiet,
+                config_file,
+                cache_control_headers,
+                type_map,
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                compression,
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                compression_level,
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                compression_level_gzip,
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                compression_level_brotli,
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                compression_level_zstd,
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                compression_min_size,
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                compression_mime_types,
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                compression_exclude_mime_types,
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                compression_adaptive,
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                compression_adaptive_high_load,
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                compression_adaptive_max_load,
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
                     feature = "compression-zstd",
                     feature = "compression-deflate"
                 ))]
                 compression_static,
+                #[cfg(any(
+                    feature = "compression",
+                    feature = "compression-gzip",
+                    feature = "compression-brotli",
+                    feature = "compression-zstd",
+                    feature = "compression-deflate"
+                ))]
+                compression_static_order,
                 page404,
                 page50x,
                 #[cfg(feature = "http2")]
@@ -671,6 +2409,12 @@ impl Settings {
                 https_redirect_from_port,
                 #[cfg(feature = "http2")]
                 https_redirect_from_hosts,
+                #[cfg(feature = "http2")]
+                https_redirect_status,
+                #[cfg(feature = "http2")]
+                https_redirect_preserve_host,
+                #[cfg(feature = "http2")]
+                https_redirect_hsts,
                 security_headers,
                 cors_allow_origins,
                 cors_allow_headers,
@@ -678,34 +2422,155 @@ impl Settings {
                 #[cfg(feature = "directory-listing")]
                 directory_listing,
                 #[cfg(feature = "directory-listing")]
+                directory_listing_paths,
+                #[cfg(feature = "directory-listing")]
                 directory_listing_order,
                 #[cfg(feature = "directory-listing")]
+                directory_listing_sort,
+                #[cfg(feature = "directory-listing")]
+                directory_listing_dirs_first,
+                #[cfg(feature = "directory-listing")]
                 directory_listing_format,
+                #[cfg(feature = "directory-listing")]
+                directory_listing_dir_size,
+                #[cfg(feature = "directory-listing")]
+                directory_listing_dir_size_ttl,
+                #[cfg(feature = "directory-listing")]
+                directory_listing_dir_size_max_entries,
+                #[cfg(feature = "directory-listing")]
+                directory_listing_dir_size_concurrency,
+                #[cfg(feature = "directory-listing")]
+                directory_listing_details,
+                #[cfg(feature = "directory-listing")]
+                directory_listing_locale,
+                #[cfg(feature = "directory-listing")]
+                directory_listing_hide,
+                #[cfg(feature = "directory-listing")]
+                directory_listing_header,
+                #[cfg(feature = "directory-listing")]
+                directory_listing_readme,
                 #[cfg(feature = "directory-listing-download")]
                 directory_listing_download,
                 #[cfg(feature = "basic-auth")]
                 basic_auth,
                 fd,
+                interface,
                 threads_multiplier,
+                worker_threads,
                 max_blocking_threads,
+                thread_stack_size,
+                worker_processes,
                 grace_period,
                 #[cfg(feature = "fallback-page")]
                 page_fallback,
+                #[cfg(feature = "fallback-page")]
+                page_fallback_soft404,
+                #[cfg(feature = "fallback-page")]
+                page_fallback_status,
+                #[cfg(feature = "fallback-page")]
+                page_fallback_methods,
+                #[cfg(feature = "fallback-page")]
+                page_fallback_html_only,
                 log_remote_address,
                 log_x_real_ip,
                 log_forwarded_for,
                 trusted_proxies,
+                trusted_proxies_refresh,
                 redirect_trailing_slash,
+                reject_absolute_form,
                 ignore_hidden_files,
+                ignore_patterns,
                 disable_symlinks,
+                follow_symlinks,
                 accept_markdown,
+                language_variants,
+                image_variants,
                 index_files,
                 health,
+                health_ready_files,
+                #[cfg(feature = "metrics")]
+                metrics,
                 #[cfg(all(unix, feature = "experimental"))]
                 experimental_metrics,
                 maintenance_mode,
                 maintenance_mode_status,
                 maintenance_mode_file,
+                maintenance_mode_allow_ips,
+                maintenance_mode_allow_paths,
+                maintenance_mode_start,
+                maintenance_mode_end,
+                #[cfg(feature = "markdown")]
+                markdown_render,
+                #[cfg(feature = "markdown")]
+                markdown_render_template,
+                ssi,
+                ssi_extensions,
+                dev_reload,
+                dev_reload_inject,
+                probe_endpoint,
+                server_ident,
+                alt_svc,
+                alt_svc_max_age,
+                default_charset,
+                max_uri_length,
+                max_headers_size,
+                max_body_size,
+                limit_rate,
+                limit_rate_global,
+                max_connections,
+                max_connections_per_ip,
+                #[cfg(feature = "archive-preview")]
+                archive_preview,
+                #[cfg(feature = "archive-preview")]
+                archive_preview_max_entries,
+                #[cfg(feature = "archive-preview")]
+                archive_preview_max_size,
+                #[cfg(feature = "subresource-integrity")]
+                sri_manifest,
+                #[cfg(feature = "subresource-integrity")]
+                sri_algorithm,
+                #[cfg(feature = "subresource-integrity")]
+                sri_extensions,
+                #[cfg(feature = "s3-origin")]
+                s3_origin,
+                #[cfg(feature = "s3-origin")]
+                s3_region,
+                #[cfg(feature = "s3-origin")]
+                s3_access_key_id,
+                #[cfg(feature = "s3-origin")]
+                s3_secret_access_key,
+                #[cfg(feature = "s3-origin")]
+                s3_cache_dir,
+                #[cfg(feature = "s3-origin")]
+                s3_cache_ttl,
+                #[cfg(feature = "s3-origin")]
+                s3_cache_max_size,
+                #[cfg(feature = "s3-origin")]
+                s3_ca_file,
+                read_header_timeout,
+                idle_timeout,
+                write_timeout,
+                zero_copy,
+                #[cfg(feature = "io-uring")]
+                io_uring,
+                #[cfg(feature = "mmap")]
+                mmap_max_size,
+                request_timeout,
+                not_found_cache_ttl,
+                not_found_cache_capacity,
+                control_listen,
+                control_token,
+                config_audit_log,
+                fsync_policy,
+                #[cfg(unix)]
+                socket,
+                #[cfg(unix)]
+                socket_mode,
+                #[cfg(unix)]
+                socket_owner,
+                #[cfg(unix)]
+                socket_only,
+                media_streaming,
 
                 // Windows-only options and commands
                 #[cfg(windows)]
@@ -713,8 +2578,320 @@ impl Settings {
                 commands: opts.commands,
             },
             advanced: settings_advanced,
+            listeners: settings_listeners,
+        })
+    }
+}
+
+/// Validates the listeners file options.
+/// Compiles the source of a rewrite or redirect entry (`kind`) into a regex matching
+/// the whole URI path, either from a glob pattern or from a regex pattern if `regex` is set.
+pub(crate) fn source_regex(source: &str, regex: bool, kind: &str) -> Result<Regex> {
+    if regex {
+        tracing::debug!("url {kind}s regex pattern: {source}");
+        // Anchored so the pattern matches the whole URI path like the glob patterns
+        return Regex::new(&format!("^(?:{source})$"))
+            .with_context(|| format!("can not compile regex pattern for {kind} source: {source}"));
+    }
+
+    let glob = GlobBuilder::new(source)
+        .literal_separator(true)
+        .build()
+        .with_context(|| format!("can not compile glob pattern for {kind} source: {source}"))?
+        .compile_matcher();
+
+    let pattern = glob
+        .glob()
+        .regex()
+        .trim_start_matches("(?-u)")
+        .replace("?:.*", ".*")
+        .replace("?:", "")
+        .replace(".*.*", ".*")
+        .to_owned();
+    tracing::debug!("url {kind}s glob pattern: {source}");
+    tracing::debug!("url {kind}s regex equivalent: {pattern}");
+
+    Regex::new(&pattern).with_context(|| {
+        format!("can not compile regex pattern equivalent for {kind} source: {pattern}")
+    })
+}
+
+/// Compiles the scheme, headers and query conditions of a rewrite or redirect entry (`kind`).
+fn request_conditions(
+    scheme: Option<&str>,
+    headers: Option<&BTreeMap<String, String>>,
+    query: Option<&BTreeMap<String, String>>,
+    kind: &str,
+) -> Result<RequestConditions> {
+    let scheme = match scheme.map(|scheme| scheme.to_lowercase()).as_deref() {
+        None => None,
+        Some("http") => Some(Scheme::HTTP),
+        Some("https") => Some(Scheme::HTTPS),
+        Some(scheme) => {
+            bail!("invalid {kind} scheme condition \"{scheme}\", use \"http\" or \"https\"")
+        }
+    };
+
+    let value_matcher = |value: &str| -> Result<GlobMatcher> {
+        Ok(Glob::new(value)
+            .with_context(|| format!("can not compile glob pattern for {kind} condition: {value}"))?
+            .compile_matcher())
+    };
+
+    let mut header_conditions = Vec::new();
+    for (name, value) in headers.into_iter().flatten() {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .with_context(|| format!("invalid {kind} header condition name: {name}"))?;
+        header_conditions.push((name, value_matcher(value)?));
+    }
+
+    let mut query_conditions = Vec::new();
+    for (name, value) in query.into_iter().flatten() {
+        query_conditions.push((name.to_owned(), value_matcher(value)?));
+    }
+
+    Ok(RequestConditions {
+        scheme,
+        headers: header_conditions,
+        query: query_conditions,
+    })
+}
+
+/// Checks if a try files path is an absolute URI path once its placeholder is replaced.
+fn is_valid_try_files_path(path: &str) -> bool {
+    let sample = path.replace(try_files::URI_PLACEHOLDER, "/");
+    sample.starts_with('/') && !sample.contains('?') && sample.parse::<PathAndQuery>().is_ok()
+}
+
+fn listener_entries(
+    listeners: Vec<file::Listener>,
+    advanced: Option<&Advanced>,
+    #[cfg(feature = "http2")] redirect: &ListenerRedirect,
+) -> Result<Vec<Listener>> {
+    let mut entries: Vec<Listener> = Vec::with_capacity(listeners.len());
+    // HTTPS port to redirect to by default
+    #[cfg(feature = "http2")]
+    let tls_port = listeners
+        .iter()
+        .filter(|listener| listener.tls_cert.is_some())
+        .find_map(|listener| match parse_listener_address(&listener.address) {
+            Ok(ListenerAddress::Tcp(addr)) => Some(addr.port()),
+            _ => None,
+        });
+
+    for listener in listeners {
+        let address = parse_listener_address(&listener.address)?;
+        if entries.iter().any(|entry| entry.address == address) {
+            bail!("listener address {address} is duplicated");
+        }
+
+        let (socket_mode, socket_owner) = match address {
+            ListenerAddress::Tcp(_) => {
+                if listener.socket_mode.is_some() || listener.socket_owner.is_some() {
+                    bail!(
+                        "listener {address} can't use the `socket-mode` or `socket-owner` options, they are only supported by Unix domain sockets"
+                    );
+                }
+                (None, None)
+            }
+            #[cfg(unix)]
+            ListenerAddress::Unix(_) => (
+                listener
+                    .socket_mode
+                    .as_deref()
+                    .map(parse_socket_mode)
+                    .transpose()?,
+                listener
+                    .socket_owner
+                    .as_deref()
+                    .map(parse_socket_owner)
+                    .transpose()?,
+            ),
+            #[cfg(unix)]
+            ListenerAddress::Systemd(_) => {
+                if listener.socket_mode.is_some() || listener.socket_owner.is_some() {
+                    bail!(
+                        "listener {address} can't use the `socket-mode` or `socket-owner` options, the socket is created by systemd"
+                    );
+                }
+                (None, None)
+            }
+        };
+
+        let tls = match (listener.tls_cert, listener.tls_key) {
+            (Some(cert), Some(key)) => Some(ListenerTls { cert, key }),
+            (None, None) => None,
+            _ => bail!("listener {address} requires both `tls-cert` and `tls-key` options"),
+        };
+        #[cfg(not(feature = "http2"))]
+        if tls.is_some() {
+            bail!("listener {address} requires the `http2` feature to use TLS");
+        }
+
+        let protocols = listener.protocols.unwrap_or_else(|| match tls {
+            Some(_) => vec![ListenerProtocol::Http1, ListenerProtocol::Http2],
+            None => vec![ListenerProtocol::Http1],
+        });
+        if protocols.is_empty() {
+            bail!("listener {address} requires at least one protocol");
+        }
+        for protocol in &protocols {
+            match protocol {
+                ListenerProtocol::Http2 if tls.is_none() => bail!(
+                    "listener {address} requires `tls-cert` and `tls-key` for the http2 protocol, use h2c for HTTP/2 over cleartext"
+                ),
+                ListenerProtocol::H2c if tls.is_some() => bail!(
+                    "listener {address} can't use the h2c protocol along with TLS, use http2 instead"
+                ),
+                ListenerProtocol::H3 => {
+                    bail!(
+                        "listener {address} can't use the h3 protocol, HTTP/3 is not supported yet"
+                    )
+                }
+                _ => {}
+            }
+        }
+
+        let vhosts = match listener.vhosts {
+            Some(vhosts) => {
+                if vhosts.is_empty() {
+                    bail!("listener {address} requires at least one virtual host");
+                }
+                let known_vhosts = advanced.and_then(|advanced| advanced.virtual_hosts.as_deref());
+                for vhost in &vhosts {
+                    let known = vhost == "default"
+                        || known_vhosts.is_some_and(|known| known.iter().any(|v| &v.host == vhost));
+                    if !known {
+                        bail!(
+                            "listener {address} virtual host \"{vhost}\" is not defined in `advanced.virtual-hosts`"
+                        );
+                    }
+                }
+                Some(vhosts)
+            }
+            None => None,
+        };
+
+        let https_redirect = match (
+            listener.https_redirect.unwrap_or_default(),
+            listener.https_redirect_port,
+        ) {
+            (false, None) => None,
+            (false, Some(_)) => {
+                bail!(
+                    "listener {address} requires the `https-redirect` option to use `https-redirect-port`"
+                )
+            }
+            (true, _) if tls.is_some() => {
+                bail!("listener {address} can't redirect to HTTPS along with TLS")
+            }
+            #[cfg(not(feature = "http2"))]
+            (true, _) => {
+                bail!("listener {address} requires the `http2` feature to redirect to HTTPS")
+            }
+            #[cfg(feature = "http2")]
+            (true, port) => {
+                let Some(port) = port.or(tls_port) else {
+                    bail!(
+                        "listener {address} requires a TLS listener or the `https-redirect-port` option to redirect to HTTPS"
+                    )
+                };
+                if redirect.allowed_hosts.is_empty() {
+                    bail!("https redirect allowed hosts is empty, provide at least one host or IP")
+                }
+                Some(ListenerRedirect {
+                    port,
+                    ..redirect.clone()
+                })
+            }
+        };
+
+        entries.push(Listener {
+            address,
+            http1: protocols.contains(&ListenerProtocol::Http1),
+            http2: protocols.contains(&ListenerProtocol::Http2)
+                || protocols.contains(&ListenerProtocol::H2c),
+            tls,
+            proxy_protocol: listener.proxy_protocol.unwrap_or_default(),
+            vhosts,
+            socket_mode,
+            socket_owner,
+            https_redirect,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Parses a listener address, either a socket address or a Unix domain socket path prefixed by `unix:`.
+fn parse_listener_address(value: &str) -> Result<ListenerAddress> {
+    let value = value.trim();
+    if let Some(path) = value.strip_prefix("unix:") {
+        #[cfg(unix)]
+        {
+            if path.is_empty() {
+                bail!("listener unix domain socket path is empty");
+            }
+            return Ok(ListenerAddress::Unix(PathBuf::from(path)));
+        }
+        #[cfg(not(unix))]
+        bail!("listener unix domain socket is not supported on this platform: {path}");
+    }
+    if let Some(name) = value.strip_prefix("systemd:") {
+        #[cfg(unix)]
+        {
+            if name.is_empty() {
+                bail!("listener systemd socket name is empty");
+            }
+            return Ok(ListenerAddress::Systemd(name.to_owned()));
+        }
+        #[cfg(not(unix))]
+        bail!("listener systemd socket is not supported on this platform: {name}");
+    }
+
+    let addr = value
+        .parse::<SocketAddr>()
+        .with_context(|| format!("invalid listener address: {value}"))?;
+    Ok(ListenerAddress::Tcp(addr))
+}
+
+/// Parses the permissions of a Unix domain socket file in octal notation. E.g. `660` or `0o660`.
+#[cfg(unix)]
+fn parse_socket_mode(value: &str) -> Result<u32> {
+    let digits = value.trim();
+    let digits = digits.strip_prefix("0o").unwrap_or(digits);
+    match u32::from_str_radix(digits, 8) {
+        Ok(mode) if mode <= 0o777 => Ok(mode),
+        _ => {
+            bail!("invalid unix domain socket mode \"{value}\", use the octal notation (E.g. 660)")
+        }
+    }
+}
+
+/// Parses the owner of a Unix domain socket file in the form `UID[:GID]` or `:GID`.
+#[cfg(unix)]
+fn parse_socket_owner(value: &str) -> Result<SocketOwner> {
+    let (uid, gid) = match value.trim().split_once(':') {
+        Some((uid, gid)) => (uid, Some(gid)),
+        None => (value.trim(), None),
+    };
+    let parse_id = |id: &str| {
+        id.parse::<u32>().with_context(|| {
+            format!("invalid unix domain socket owner \"{value}\", use numeric IDs as `UID[:GID]`")
         })
+    };
+
+    let owner = SocketOwner {
+        uid: match uid {
+            "" => None,
+            uid => Some(parse_id(uid)?),
+        },
+        gid: gid.map(parse_id).transpose()?,
+    };
+    if owner.uid.is_none() && owner.gid.is_none() {
+        bail!("invalid unix domain socket owner \"{value}\", use numeric IDs as `UID[:GID]`");
     }
+    Ok(owner)
 }
 
 fn read_file_settings(config_file: &Path) -> Result<Option<(FileSettings, PathBuf)>> {
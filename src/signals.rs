@@ -6,10 +6,13 @@
 //! The module provides signals support like `SIGTERM`, `SIGINT` and `SIGQUIT`.
 //!
 
-use std::sync::Arc;
-use tokio::sync::{Mutex, watch::Receiver};
+use std::future::{Future, pending};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::{Mutex, Notify, watch::Receiver};
 use tokio::time::{Duration, sleep};
 
+use crate::service::ConnectionCounter;
+
 #[cfg(unix)]
 use {
     crate::Result, futures_util::stream::StreamExt, signal_hook::consts::signal::*,
@@ -24,13 +27,25 @@ pub fn create_signals() -> Result<Signals> {
     Ok(Signals::new([SIGHUP, SIGTERM, SIGINT, SIGQUIT])?)
 }
 
+#[cfg(unix)]
+#[cfg_attr(docsrs, doc(cfg(unix)))]
+#[inline]
+/// It creates a signals stream for `SIGHUP` to be observed in order to reload the configuration.
+pub fn create_reload_signals() -> Result<Signals> {
+    Ok(Signals::new([SIGHUP])?)
+}
+
+#[cfg(unix)]
+#[cfg_attr(docsrs, doc(cfg(unix)))]
+#[inline]
+/// It creates a signals stream for `SIGUSR1` to be observed in order to toggle the maintenance mode.
+pub fn create_maintenance_signals() -> Result<Signals> {
+    Ok(Signals::new([SIGUSR1])?)
+}
+
 #[cfg(unix)]
 /// It waits for a specific type of incoming signals included `ctrl+c`.
-pub async fn wait_for_signals(
-    signals: Signals,
-    grace_period_secs: u8,
-    cancel_recv: Arc<Mutex<Option<Receiver<()>>>>,
-) {
+pub async fn wait_for_signals(signals: Signals, cancel_recv: Arc<Mutex<Option<Receiver<()>>>>) {
     let (first_tx, mut base_rx) = tokio::sync::mpsc::channel(1);
     let last_tx = first_tx.clone();
 
@@ -39,8 +54,8 @@ pub async fn wait_for_signals(
         while let Some(signal) = signals.next().await {
             match signal {
                 SIGHUP => {
-                    // NOTE: for now we don't do something for SIGHUPs
-                    tracing::debug!("SIGHUP caught, nothing to do about")
+                    // NOTE: SIGHUPs are handled by the configuration reload
+                    tracing::debug!("SIGHUP caught, not a termination signal")
                 }
                 SIGTERM | SIGINT | SIGQUIT => {
                     tracing::info!("SIGTERM, SIGINT or SIGQUIT signal caught");
@@ -63,30 +78,186 @@ pub async fn wait_for_signals(
     base_rx.recv().await.take();
 
     // NOTE: once loop above is done then an upstream graceful shutdown should come next.
-    delay_graceful_shutdown(grace_period_secs).await;
     tracing::info!("delegating server's graceful shutdown");
 }
 
-/// Function intended to delay the server's graceful shutdown providing a grace period in seconds.
-async fn delay_graceful_shutdown(grace_period_secs: u8) {
-    if grace_period_secs > 0 {
-        tracing::info!(
-            "grace period of {}s after the SIGTERM started",
-            grace_period_secs
-        );
-        sleep(Duration::from_secs(grace_period_secs.into())).await;
-        tracing::info!("grace period has elapsed");
+/// It tracks the graceful shutdown of a server in order to drain its in-flight connections.
+#[derive(Clone)]
+pub struct GracefulShutdown {
+    inner: Arc<GracefulShutdownInner>,
+}
+
+struct GracefulShutdownInner {
+    connections: ConnectionCounter,
+    started: Notify,
+    /// Number of in-flight connections when the graceful shutdown started.
+    in_flight: OnceLock<usize>,
+}
+
+impl GracefulShutdown {
+    /// Creates a new graceful shutdown tracker for the given server connections.
+    pub fn new(connections: ConnectionCounter) -> Self {
+        Self {
+            inner: Arc::new(GracefulShutdownInner {
+                connections,
+                started: Notify::new(),
+                in_flight: OnceLock::new(),
+            }),
+        }
+    }
+
+    /// Wraps a termination signal future in order to mark the graceful shutdown as started once it completes.
+    pub fn signal<F: Future<Output = ()>>(&self, signal: F) -> impl Future<Output = ()> + use<F> {
+        let inner = self.inner.clone();
+        async move {
+            signal.await;
+            #[cfg(unix)]
+            crate::systemd::stopping();
+            let in_flight = *inner.in_flight.get_or_init(|| inner.connections.active());
+            tracing::info!(
+                "graceful shutdown started, draining {} in-flight connection(s)",
+                in_flight
+            );
+            inner.started.notify_one();
+        }
+    }
+
+    /// Drives a server with a graceful shutdown triggered by [`GracefulShutdown::signal`].
+    ///
+    /// Once the graceful shutdown starts, the server stops accepting new connections and waits
+    /// up to `grace_period_secs` (indefinitely if zero) for the in-flight connections to complete,
+    /// including the tunnels of the upgraded ones (E.g. WebSocket proxy).
+    /// Connections still open afterwards are aborted.
+    pub async fn drain<S, E>(&self, server: S, grace_period_secs: u8) -> std::result::Result<(), E>
+    where
+        S: Future<Output = std::result::Result<(), E>>,
+    {
+        let deadline = async {
+            self.inner.started.notified().await;
+            if grace_period_secs > 0 {
+                sleep(Duration::from_secs(grace_period_secs.into())).await;
+            } else {
+                pending::<()>().await;
+            }
+        };
+
+        let server = async {
+            let result = server.await;
+            // The tunnels of the upgraded connections outlive the server connections
+            if self.inner.in_flight.get().is_some() {
+                self.inner.connections.closed().await;
+            }
+            result
+        };
+
+        tokio::select! {
+            result = server => {
+                if let Some(in_flight) = self.inner.in_flight.get() {
+                    tracing::info!(
+                        "graceful shutdown completed: {} connection(s) drained, 0 aborted",
+                        in_flight
+                    );
+                }
+                result
+            }
+            _ = deadline => {
+                let in_flight = self.inner.in_flight.get().copied().unwrap_or_default();
+                let aborted = self.inner.connections.active();
+                tracing::warn!(
+                    "grace period of {}s elapsed: {} connection(s) drained, {} aborted",
+                    grace_period_secs,
+                    in_flight.saturating_sub(aborted),
+                    aborted
+                );
+                self.inner.connections.abort();
+                Ok(())
+            }
+        }
     }
 }
 
 #[cfg(windows)]
 #[cfg_attr(docsrs, doc(cfg(windows)))]
 /// It waits for an incoming `ctrl+c` signal on Windows.
-pub async fn wait_for_ctrl_c(cancel_recv: Arc<Mutex<Option<Receiver<()>>>>, grace_period_secs: u8) {
+pub async fn wait_for_ctrl_c(cancel_recv: Arc<Mutex<Option<Receiver<()>>>>) {
     if let Some(receiver) = &mut *cancel_recv.lock().await {
         receiver.changed().await.ok();
     }
 
-    delay_graceful_shutdown(grace_period_secs).await;
     tracing::info!("delegating server's graceful shutdown");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::GracefulShutdown;
+    use crate::service::{ConnectionCounter, ConnectionGuard};
+    use std::future::{pending, ready};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn test_drain_server_completed() {
+        let shutdown = GracefulShutdown::new(ConnectionCounter::default());
+        let server = async {
+            shutdown.signal(ready(())).await;
+            Err::<(), _>("server error")
+        };
+        assert_eq!(shutdown.drain(server, 1).await, Err("server error"));
+    }
+
+    #[tokio::test]
+    async fn test_drain_grace_period_elapsed() {
+        let shutdown = GracefulShutdown::new(ConnectionCounter::default());
+        let server = async {
+            shutdown.signal(ready(())).await;
+            // An in-flight connection which never completes
+            pending::<Result<(), ()>>().await
+        };
+        let started = Instant::now();
+        assert_eq!(shutdown.drain(server, 1).await, Ok(()));
+        assert!(started.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_drain_upgraded_connections() {
+        let connections = ConnectionCounter::default();
+        let shutdown = GracefulShutdown::new(connections.clone());
+        let tunnel = Arc::new(ConnectionGuard::new(&connections));
+        let server = async {
+            shutdown.signal(ready(())).await;
+            Ok::<(), ()>(())
+        };
+
+        // The server completes but its upgraded connection is still open until the tunnel closes
+        let closing = tunnel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            drop(closing);
+        });
+        drop(tunnel);
+        let started = Instant::now();
+        assert_eq!(shutdown.drain(server, 5).await, Ok(()));
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert_eq!(connections.active(), 0);
+
+        // The tunnels left open after the grace period are aborted
+        let shutdown = GracefulShutdown::new(connections.clone());
+        let tunnel = ConnectionGuard::new(&connections);
+        let server = async {
+            shutdown.signal(ready(())).await;
+            Ok::<(), ()>(())
+        };
+        let (drained, ()) = tokio::join!(shutdown.drain(server, 1), tunnel.aborted());
+        assert_eq!(drained, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_drain_without_shutdown_signal() {
+        let shutdown = GracefulShutdown::new(ConnectionCounter::default());
+        let server = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok::<(), ()>(())
+        };
+        assert_eq!(shutdown.drain(server, 0).await, Ok(()));
+    }
+}
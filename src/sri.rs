@@ -0,0 +1,402 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module providing a Subresource Integrity (SRI) manifest endpoint.
+//!
+//! The manifest maps the URL paths of the assets under the root directory
+//! to their `integrity` attribute values, so the pages can use SRI without
+//! a separate build step. The digests are cached until the files change.
+//!
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use clap::ValueEnum;
+use headers::{ContentType, HeaderMapExt, HeaderValue};
+use hyper::{Body, Method, Request, Response, StatusCode, header::CACHE_CONTROL};
+use ring::digest::{self, Algorithm};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::fs::symlinks::FollowSymlinks;
+use crate::fs::walk::{self, CancelToken, Visit};
+use crate::ignore_patterns::IgnorePatterns;
+use crate::{Error, error_page, handler::RequestHandlerOpts};
+
+/// Number of threads used to walk the root directory.
+const WALK_CONCURRENCY: usize = 4;
+
+/// Digests of the last manifest by file path, reused while the files don't change.
+static DIGESTS: Mutex<Option<HashMap<PathBuf, CachedDigest>>> = Mutex::new(None);
+
+#[derive(
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    ValueEnum,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+/// Hash algorithm of the Subresource Integrity digests.
+pub enum SriAlgorithm {
+    /// SHA-256 digests.
+    Sha256,
+    /// SHA-384 digests (default).
+    #[default]
+    Sha384,
+}
+
+impl SriAlgorithm {
+    /// Returns the algorithm prefix of the `integrity` attribute values.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha384 => "sha384",
+        }
+    }
+
+    fn digest_algorithm(&self) -> &'static Algorithm {
+        match self {
+            Self::Sha256 => &digest::SHA256,
+            Self::Sha384 => &digest::SHA384,
+        }
+    }
+}
+
+/// A digest along with the file version it belongs to.
+struct CachedDigest {
+    algorithm: SriAlgorithm,
+    modified: Option<SystemTime>,
+    len: u64,
+    integrity: String,
+}
+
+/// Options excluding the files which are not served from the manifest.
+#[derive(Debug, Default)]
+struct Exclusions {
+    ignore_hidden_files: bool,
+    ignore_patterns: IgnorePatterns,
+    follow_symlinks: FollowSymlinks,
+}
+
+/// The JSON manifest of the Subresource Integrity digests.
+#[derive(Debug, Serialize)]
+struct Manifest<'a> {
+    algorithm: &'a str,
+    files: BTreeMap<String, String>,
+}
+
+/// Initializes the Subresource Integrity manifest endpoint.
+pub(crate) fn init(
+    path: &str,
+    algorithm: SriAlgorithm,
+    extensions: &str,
+    handler_opts: &mut RequestHandlerOpts,
+) {
+    handler_opts.sri_manifest = match path.trim() {
+        "" => String::new(),
+        p if p.starts_with('/') => p.to_owned(),
+        p => ["/", p].concat(),
+    };
+    handler_opts.sri_algorithm = algorithm;
+    handler_opts.sri_extensions = parse_extensions(extensions);
+    tracing::info!(
+        "sri manifest: enabled={}, path=\"{}\", algorithm={}, extensions={}",
+        !handler_opts.sri_manifest.is_empty(),
+        handler_opts.sri_manifest,
+        algorithm.name(),
+        handler_opts.sri_extensions.join(",")
+    );
+}
+
+/// Parses a comma-separated list of file extensions.
+pub(crate) fn parse_extensions(extensions: &str) -> Vec<String> {
+    extensions
+        .split(',')
+        .map(|ext| ext.trim().trim_start_matches('.').to_ascii_lowercase())
+        .filter(|ext| !ext.is_empty())
+        .collect()
+}
+
+/// Handles the Subresource Integrity manifest requests.
+pub(crate) async fn pre_process<T>(
+    opts: &RequestHandlerOpts,
+    req: &Request<T>,
+) -> Option<Result<Response<Body>, Error>> {
+    if opts.sri_manifest.is_empty() || req.uri().path() != opts.sri_manifest {
+        return None;
+    }
+    let method = req.method();
+    if !matches!(*method, Method::GET | Method::HEAD) {
+        return None;
+    }
+
+    let root = opts.root_dir.clone();
+    let algorithm = opts.sri_algorithm;
+    let extensions = opts.sri_extensions.clone();
+    let exclusions = Exclusions {
+        ignore_hidden_files: opts.ignore_hidden_files,
+        ignore_patterns: opts.ignore_patterns.clone(),
+        follow_symlinks: opts.follow_symlinks,
+    };
+    let files = tokio::task::spawn_blocking(move || {
+        manifest_files(&root, algorithm, &extensions, &exclusions)
+    })
+    .await;
+
+    let files = match files {
+        Ok(files) => files,
+        Err(err) => {
+            tracing::error!("unable to build the sri manifest: {:?}", err);
+            return Some(error_page::error_response(
+                req.uri(),
+                method,
+                &StatusCode::INTERNAL_SERVER_ERROR,
+                &opts.error_pages,
+            ));
+        }
+    };
+    let manifest = Manifest {
+        algorithm: algorithm.name(),
+        files,
+    };
+    let body = match serde_json::to_vec(&manifest) {
+        Ok(body) => body,
+        Err(err) => return Some(Err(err.into())),
+    };
+
+    let mut resp = Response::new(match *method {
+        Method::HEAD => Body::empty(),
+        _ => Body::from(body),
+    });
+    resp.headers_mut().typed_insert(ContentType::json());
+    resp.headers_mut()
+        .insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    Some(Ok(resp))
+}
+
+/// Computes the `integrity` values of the files under the root directory
+/// with the given extensions by their URL path.
+///
+/// Like the directory listing, the hidden files, the ignore patterns and the symlink policy
+/// are honoured. The symlinks to files are followed if allowed, but not the ones to directories.
+fn manifest_files(
+    root: &Path,
+    algorithm: SriAlgorithm,
+    extensions: &[String],
+    exclusions: &Exclusions,
+) -> BTreeMap<String, String> {
+    // NOTE: the lock is held during the whole walk, so concurrent requests reuse the digests
+    let mut cache = DIGESTS.lock().unwrap_or_else(|err| err.into_inner());
+    let previous = cache.take().unwrap_or_default();
+    let found = Mutex::new(HashMap::new());
+
+    walk::walk(
+        root,
+        WALK_CONCURRENCY,
+        &CancelToken::default(),
+        |entry, meta| {
+            let name = entry.file_name();
+            if exclusions.ignore_hidden_files && name.as_encoded_bytes().starts_with(b".") {
+                return Visit::Skip;
+            }
+            let path = entry.path();
+            if exclusions.ignore_patterns.is_ignored_in(root, &path) {
+                return Visit::Skip;
+            }
+            let target;
+            let meta = if meta.is_symlink() {
+                if !exclusions.follow_symlinks.allows(root, &path) {
+                    return Visit::Skip;
+                }
+                target = match std::fs::metadata(&path) {
+                    Ok(target) => target,
+                    Err(_) => return Visit::Skip,
+                };
+                &target
+            } else {
+                meta
+            };
+            if !meta.is_file() {
+                return Visit::Continue;
+            }
+            let matches = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)));
+            if !matches {
+                return Visit::Continue;
+            }
+
+            let modified = meta.modified().ok();
+            let cached = previous.get(&path).filter(|cached| {
+                cached.algorithm == algorithm
+                    && cached.modified == modified
+                    && cached.len == meta.len()
+            });
+            let integrity = match cached {
+                Some(cached) => cached.integrity.clone(),
+                None => match integrity(&path, algorithm) {
+                    Ok(integrity) => integrity,
+                    Err(err) => {
+                        tracing::warn!(
+                            "unable to compute the sri digest of {}: {err}",
+                            path.display()
+                        );
+                        return Visit::Continue;
+                    }
+                },
+            };
+            let digest = CachedDigest {
+                algorithm,
+                modified,
+                len: meta.len(),
+                integrity,
+            };
+            if let Ok(mut found) = found.lock() {
+                found.insert(path, digest);
+            }
+            Visit::Continue
+        },
+    );
+
+    let found = found.into_inner().unwrap_or_default();
+    let files = found
+        .iter()
+        .filter_map(|(path, digest)| Some((url_path(root, path)?, digest.integrity.clone())))
+        .collect();
+    *cache = Some(found);
+    files
+}
+
+/// Returns the URL path of a file under the root directory.
+fn url_path(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let mut url = String::new();
+    for component in relative.components() {
+        url.push('/');
+        url.push_str(component.as_os_str().to_str()?);
+    }
+    Some(url)
+}
+
+/// Computes the `integrity` attribute value of a file (E.g. `sha384-<base64 digest>`).
+pub(crate) fn integrity(path: &Path, algorithm: SriAlgorithm) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut context = digest::Context::new(algorithm.digest_algorithm());
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        context.update(&buf[..n]);
+    }
+    let digest = STANDARD.encode(context.finish());
+    Ok(format!("{}-{digest}", algorithm.name()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Exclusions, SriAlgorithm, integrity, manifest_files, parse_extensions};
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_extensions() {
+        assert_eq!(parse_extensions(" js, .CSS,,mjs "), ["js", "css", "mjs"]);
+        assert!(parse_extensions("").is_empty());
+    }
+
+    #[test]
+    fn test_integrity() {
+        let dir = std::env::temp_dir().join(format!("sws-sri-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.js");
+        std::fs::write(&path, "alert('Hello world.');").unwrap();
+
+        // Digests computed with `openssl dgst -binary | openssl base64 -A`
+        assert_eq!(
+            integrity(&path, SriAlgorithm::Sha256).unwrap(),
+            "sha256-pkWDkvDS/E+dKqzLkzXP8oPhxswpUr6T2+yIelFTPX4="
+        );
+        assert_eq!(
+            integrity(&path, SriAlgorithm::Sha384).unwrap(),
+            "sha384-aA8frZhXPBNWPumWay93MM5GbFCOqHPIGoTJ7ki59l8AhN+quBpVXXdSYf2AhtDE"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_manifest_files() {
+        let root = Path::new("tests/fixtures/public");
+        let extensions = parse_extensions("js,css");
+        let exclusions = Exclusions::default();
+        let files = manifest_files(root, SriAlgorithm::Sha384, &extensions, &exclusions);
+        assert_eq!(
+            files["/assets/main.js"],
+            integrity(&root.join("assets/main.js"), SriAlgorithm::Sha384).unwrap()
+        );
+        assert!(files.contains_key("/assets/main.css"));
+        assert!(
+            files
+                .keys()
+                .all(|path| path.ends_with(".js") || path.ends_with(".css"))
+        );
+
+        // Cached digests are reused
+        assert_eq!(
+            manifest_files(root, SriAlgorithm::Sha384, &extensions, &exclusions),
+            files
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_manifest_files_exclusions() {
+        use crate::fs::symlinks::FollowSymlinks;
+        use crate::ignore_patterns::IgnorePatterns;
+        use std::os::unix::fs::symlink;
+
+        let dir = std::env::temp_dir().join(format!("sws-sri-exclusions-{}", std::process::id()));
+        let root = dir.join("public");
+        std::fs::create_dir_all(root.join("private")).unwrap();
+        std::fs::write(root.join("app.js"), "alert(1)").unwrap();
+        std::fs::write(root.join(".hidden.js"), "alert(2)").unwrap();
+        std::fs::write(root.join("private/key.js"), "alert(3)").unwrap();
+        std::fs::write(root.join("app.map.js"), "alert(4)").unwrap();
+        std::fs::write(dir.join("outside.js"), "alert(5)").unwrap();
+        symlink(root.join("app.js"), root.join("link.js")).unwrap();
+        symlink(dir.join("outside.js"), root.join("outside.js")).unwrap();
+
+        let extensions = parse_extensions("js");
+        let manifest = |follow_symlinks| {
+            let exclusions = Exclusions {
+                ignore_hidden_files: true,
+                ignore_patterns: IgnorePatterns::new(&["private".into(), "*.map.js".into()])
+                    .unwrap(),
+                follow_symlinks,
+            };
+            let files = manifest_files(&root, SriAlgorithm::Sha256, &extensions, &exclusions);
+            files.into_keys().collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            manifest(FollowSymlinks::Always),
+            ["/app.js", "/link.js", "/outside.js"]
+        );
+        assert_eq!(manifest(FollowSymlinks::SameRoot), ["/app.js", "/link.js"]);
+        assert_eq!(manifest(FollowSymlinks::Never), ["/app.js"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
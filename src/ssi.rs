@@ -0,0 +1,350 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Server-side includes (SSI) module.
+//!
+//! It supports a minimal SSI subset for files matching the configured extensions:
+//! `<!--#include virtual="..." -->`, `<!--#include file="..." -->` and `<!--#echo var="..." -->`.
+//!
+
+use headers::{ContentLength, ContentType, HeaderMap, HeaderMapExt, LastModified};
+use hyper::{
+    Body, Request, Response, StatusCode,
+    header::{ACCEPT_RANGES, CONTENT_ENCODING, CONTENT_RANGE, ETAG, LAST_MODIFIED},
+};
+use maud::html;
+use regex_lite::{Captures, Regex};
+use std::{
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::SystemTime,
+};
+
+use crate::{
+    Error,
+    fs::path::{PathExt, sanitize_path},
+    fs::symlinks::FollowSymlinks,
+    handler::RequestHandlerOpts,
+    http_ext::MethodExt,
+    ignore_patterns::IgnorePatterns,
+    mime_types,
+};
+
+/// Maximum depth of nested includes.
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// Message used in place of a directive that could not be processed.
+const ERROR_MESSAGE: &str = "[an error occurred while processing this directive]";
+
+/// Value used for unknown variables.
+const UNSET_VALUE: &str = "(none)";
+
+/// SSI directive pattern. E.g. `<!--#include virtual="/header.html" -->`
+static DIRECTIVE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"<!--#([a-z]+)((?:\s+[a-z]+\s*=\s*"[^"]*")*)\s*-->"#).unwrap());
+
+/// SSI directive attribute pattern. E.g. `virtual="/header.html"`
+static ATTRIBUTE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"([a-z]+)\s*=\s*"([^"]*)""#).unwrap());
+
+/// Initializes server-side includes.
+pub(crate) fn init(enabled: bool, extensions: &str, handler_opts: &mut RequestHandlerOpts) {
+    handler_opts.ssi = enabled;
+    handler_opts.ssi_extensions = extensions
+        .split(',')
+        .map(|s| s.trim().trim_start_matches('.').to_lowercase())
+        .filter(|s| !s.is_empty())
+        .collect();
+    tracing::info!(
+        "server-side includes: enabled={enabled}, extensions={}",
+        handler_opts.ssi_extensions.join(",")
+    );
+}
+
+/// Post-process the response to evaluate the SSI directives of the file if necessary.
+pub(crate) async fn post_process<T>(
+    opts: &RequestHandlerOpts,
+    req: &Request<T>,
+    resp: Response<Body>,
+    base_path: &Path,
+    file_path: Option<&PathBuf>,
+) -> Result<Response<Body>, Error> {
+    if !opts.ssi {
+        return Ok(resp);
+    }
+
+    let method = req.method();
+    // The range and conditional responses are also checked since the `Range`
+    // and validator headers of the request apply to the SSI source and not to
+    // its evaluated content, which is always sent in full
+    let status = resp.status();
+    if !(method.is_get() || method.is_head())
+        || !matches!(
+            status,
+            StatusCode::OK
+                | StatusCode::PARTIAL_CONTENT
+                | StatusCode::NOT_MODIFIED
+                | StatusCode::PRECONDITION_FAILED
+                | StatusCode::RANGE_NOT_SATISFIABLE
+        )
+    {
+        return Ok(resp);
+    }
+
+    // Skip pre-compressed variants since their content can not be parsed
+    if resp.headers().contains_key(CONTENT_ENCODING) {
+        return Ok(resp);
+    }
+
+    let file_path = match file_path {
+        Some(p) if has_ssi_extension(p, &opts.ssi_extensions) => p,
+        _ => return Ok(resp),
+    };
+
+    let source = match tokio::fs::read(file_path).await {
+        Ok(v) => v,
+        Err(err) => {
+            tracing::error!(
+                "ssi: unable to read file {}: {:?}",
+                file_path.display(),
+                err
+            );
+            return Ok(resp);
+        }
+    };
+
+    // The bodiless responses lack the `Last-Modified` header of the source
+    let last_modified = match resp.headers().get(LAST_MODIFIED) {
+        Some(value) => value.to_str().ok().map(ToOwned::to_owned),
+        None => tokio::fs::metadata(file_path)
+            .await
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(http_date),
+    };
+    let ctx = Context {
+        base_path: base_path.to_owned(),
+        ignore_hidden_files: opts.ignore_hidden_files,
+        ignore_patterns: opts.ignore_patterns.clone(),
+        follow_symlinks: opts.follow_symlinks.or_disabled(opts.disable_symlinks),
+        document_uri: req.uri().path().to_owned(),
+        query: req.uri().query().map(ToOwned::to_owned),
+        last_modified,
+    };
+    // The included files are read while evaluating the directives
+    let content = tokio::task::spawn_blocking(move || {
+        ctx.process(&String::from_utf8_lossy(&source), &ctx.document_uri, 0)
+    })
+    .await?;
+
+    // The content is generated so validators of the source file can not be used
+    let (mut head, _) = resp.into_parts();
+    head.status = StatusCode::OK;
+    head.headers.remove(ACCEPT_RANGES);
+    head.headers.remove(CONTENT_RANGE);
+    head.headers.remove(LAST_MODIFIED);
+    head.headers.remove(ETAG);
+    head.headers
+        .typed_insert(ContentLength(content.len() as u64));
+
+    let body = if method.is_head() {
+        Body::empty()
+    } else {
+        Body::from(content)
+    };
+    let mut resp = Response::from_parts(head, body);
+
+    // The bodiless responses also lack the content type of the source
+    if status.is_success() {
+        return Ok(resp);
+    }
+    let mime = mime_guess::from_path(file_path).first_or_octet_stream();
+    resp.headers_mut().typed_insert(ContentType::from(mime));
+    mime_types::post_process(opts, req, resp, Some(file_path))
+}
+
+/// Checks whether the given file path has one of the SSI extensions.
+fn has_ssi_extension(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+}
+
+/// SSI processing context of a request.
+struct Context {
+    base_path: PathBuf,
+    ignore_hidden_files: bool,
+    ignore_patterns: IgnorePatterns,
+    follow_symlinks: FollowSymlinks,
+    document_uri: String,
+    query: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl Context {
+    /// Evaluates the SSI directives of a document located at the given URI path.
+    fn process(&self, source: &str, uri_path: &str, depth: usize) -> String {
+        DIRECTIVE
+            .replace_all(source, |caps: &Captures<'_>| {
+                let attrs = caps.get(2).map_or("", |m| m.as_str());
+                let result = match &caps[1] {
+                    "include" => self.include(attrs, uri_path, depth),
+                    "echo" => self.echo(attrs),
+                    directive => Err(format!("unsupported directive \"{directive}\"")),
+                };
+                result.unwrap_or_else(|err| {
+                    tracing::error!("ssi: {err} in {uri_path}");
+                    ERROR_MESSAGE.to_owned()
+                })
+            })
+            .into_owned()
+    }
+
+    /// Evaluates an `include` directive.
+    fn include(&self, attrs: &str, uri_path: &str, depth: usize) -> Result<String, String> {
+        if depth >= MAX_INCLUDE_DEPTH {
+            return Err(format!(
+                "maximum include depth of {MAX_INCLUDE_DEPTH} exceeded"
+            ));
+        }
+
+        let (kind, target) = attribute(attrs, "virtual")
+            .map(|v| ("virtual", v))
+            .or_else(|| attribute(attrs, "file").map(|v| ("file", v)))
+            .ok_or("include directive without a virtual or file attribute")?;
+
+        // A `file` include is always relative to the current document
+        // while a `virtual` one can also be relative to the root directory
+        if kind == "file" && (target.starts_with('/') || target.contains("..")) {
+            return Err(format!("invalid include file \"{target}\""));
+        }
+        let include_uri = resolve_uri(uri_path, target);
+
+        let path = sanitize_path(&self.base_path, &include_uri)
+            .map_err(|_| format!("invalid include path \"{target}\""))?;
+        if self.ignore_hidden_files && path.is_hidden() {
+            return Err(format!("include path \"{target}\" is hidden"));
+        }
+        if self.ignore_patterns.is_ignored_in(&self.base_path, &path) {
+            return Err(format!("include path \"{target}\" is ignored"));
+        }
+        if !self.follow_symlinks.allows(&self.base_path, &path) {
+            return Err(format!("include path \"{target}\" is a disallowed symlink"));
+        }
+
+        let content =
+            std::fs::read(&path).map_err(|err| format!("unable to include \"{target}\": {err}"))?;
+
+        Ok(self.process(&String::from_utf8_lossy(&content), &include_uri, depth + 1))
+    }
+
+    /// Evaluates an `echo` directive.
+    fn echo(&self, attrs: &str) -> Result<String, String> {
+        let var = attribute(attrs, "var").ok_or("echo directive without a var attribute")?;
+        let value = match var {
+            "DOCUMENT_NAME" => self
+                .document_uri
+                .rsplit('/')
+                .next()
+                .filter(|s| !s.is_empty())
+                .map(ToOwned::to_owned),
+            "DOCUMENT_URI" => Some(self.document_uri.clone()),
+            "QUERY_STRING" => self.query.clone(),
+            "DATE_GMT" => http_date(SystemTime::now()),
+            "LAST_MODIFIED" => self.last_modified.clone(),
+            _ => None,
+        };
+        let value = value.unwrap_or_else(|| UNSET_VALUE.to_owned());
+
+        Ok(html! { (value) }.into_string())
+    }
+}
+
+/// Gets the value of an attribute of a directive.
+fn attribute<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    ATTRIBUTE
+        .captures_iter(attrs)
+        .find(|caps| &caps[1] == name)
+        .and_then(|caps| caps.get(2))
+        .map(|m| m.as_str())
+}
+
+/// Resolves an include target against the URI path of the current document.
+/// Dot segments are resolved without going beyond the root.
+fn resolve_uri(uri_path: &str, target: &str) -> String {
+    let joined = if target.starts_with('/') {
+        target.to_owned()
+    } else {
+        let dir = &uri_path[..uri_path.rfind('/').map_or(0, |i| i + 1)];
+        format!("{dir}{target}")
+    };
+
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in joined.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            s => segments.push(s),
+        }
+    }
+
+    format!("/{}", segments.join("/"))
+}
+
+/// Formats a time as an HTTP date. E.g. `Sun, 06 Nov 1994 08:49:37 GMT`
+fn http_date(time: SystemTime) -> Option<String> {
+    let mut headers = HeaderMap::new();
+    headers.typed_insert(LastModified::from(time));
+    headers
+        .get(LAST_MODIFIED)
+        .and_then(|v| v.to_str().ok())
+        .map(ToOwned::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{attribute, has_ssi_extension, resolve_uri};
+    use std::path::Path;
+
+    #[test]
+    fn test_has_ssi_extension() {
+        let extensions = vec!["shtml".to_owned()];
+        assert!(has_ssi_extension(Path::new("/index.shtml"), &extensions));
+        assert!(has_ssi_extension(Path::new("/INDEX.SHTML"), &extensions));
+        assert!(!has_ssi_extension(Path::new("/index.html"), &extensions));
+        assert!(!has_ssi_extension(Path::new("/shtml"), &extensions));
+    }
+
+    #[test]
+    fn test_attribute() {
+        let attrs = r#" virtual="/header.html"  file = "footer.html""#;
+        assert_eq!(attribute(attrs, "virtual"), Some("/header.html"));
+        assert_eq!(attribute(attrs, "file"), Some("footer.html"));
+        assert_eq!(attribute(attrs, "var"), None);
+    }
+
+    #[test]
+    fn test_resolve_uri() {
+        assert_eq!(
+            resolve_uri("/docs/page.shtml", "/header.html"),
+            "/header.html"
+        );
+        assert_eq!(
+            resolve_uri("/docs/page.shtml", "nav.html"),
+            "/docs/nav.html"
+        );
+        assert_eq!(resolve_uri("/docs/", "./nav.html"), "/docs/nav.html");
+        assert_eq!(
+            resolve_uri("/docs/page.shtml", "../header.html"),
+            "/header.html"
+        );
+        assert_eq!(
+            resolve_uri("/page.shtml", "../../../etc/passwd"),
+            "/etc/passwd"
+        );
+    }
+}
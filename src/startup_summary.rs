@@ -0,0 +1,316 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module to print a summary of the server once it's ready to accept connections,
+//! either as a compact overview for humans or as a single JSON line for orchestration scripts.
+//!
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use crate::handler::RequestHandlerOpts;
+use crate::settings::cli::General;
+
+#[derive(
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    ValueEnum,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+/// Format of the startup summary.
+pub enum StartupSummaryFormat {
+    /// A compact overview for humans (default).
+    #[default]
+    Text,
+    /// A single JSON line for scripts.
+    Json,
+}
+
+/// A listener the server accepts connections on.
+#[derive(Debug, Serialize)]
+struct ListenerSummary {
+    url: String,
+    tls: bool,
+    http1: bool,
+    http2: bool,
+}
+
+/// Summary of the server printed to the standard output once it's ready.
+#[derive(Debug, Serialize)]
+pub(crate) struct StartupSummary {
+    #[serde(skip)]
+    format: Option<StartupSummaryFormat>,
+    event: &'static str,
+    version: &'static str,
+    pid: u32,
+    listeners: Vec<ListenerSummary>,
+    roots: Vec<PathBuf>,
+    features: Vec<&'static str>,
+}
+
+impl StartupSummary {
+    /// Creates the summary of the server roots and enabled features.
+    /// Nothing is printed if the `quiet` option is enabled.
+    pub fn new(general: &General, handler_opts: &RequestHandlerOpts) -> Self {
+        let mut roots = vec![handler_opts.root_dir.clone()];
+        roots.extend(handler_opts.overlay_dirs.iter().cloned());
+        let vhosts = handler_opts
+            .advanced_opts
+            .as_ref()
+            .and_then(|advanced| advanced.virtual_hosts.as_deref())
+            .unwrap_or_default();
+        for vhost in vhosts {
+            if !roots.contains(&vhost.root) {
+                roots.push(vhost.root.clone());
+            }
+        }
+
+        let mut features = enabled_features(handler_opts);
+        if !general.control_listen.trim().is_empty() {
+            features.push("control-api");
+        }
+
+        Self {
+            format: (!general.quiet).then_some(general.startup_summary),
+            event: "ready",
+            version: env!("CARGO_PKG_VERSION"),
+            pid: std::process::id(),
+            listeners: Vec::new(),
+            roots,
+            features,
+        }
+    }
+
+    /// Adds a TCP listener with its bound address.
+    pub fn tcp_listener(&mut self, addr: SocketAddr, tls: bool, http1: bool, http2: bool) {
+        let scheme = if tls { "https" } else { "http" };
+        self.listener(format!("{scheme}://{addr}"), tls, http1, http2);
+    }
+
+    /// Adds a listener given by its URL.
+    pub fn listener(&mut self, url: String, tls: bool, http1: bool, http2: bool) {
+        self.listeners.push(ListenerSummary {
+            url,
+            tls,
+            http1,
+            http2,
+        });
+    }
+
+    /// Prints the summary to the standard output in its format.
+    pub fn print(&self) {
+        let Some(format) = self.format else {
+            return;
+        };
+        let summary = match format {
+            StartupSummaryFormat::Text => self.to_text(),
+            StartupSummaryFormat::Json => match serde_json::to_string(self) {
+                Ok(json) => json,
+                Err(err) => {
+                    tracing::error!("unable to serialize the startup summary: {err}");
+                    return;
+                }
+            },
+        };
+
+        // The standard output may be closed, which must not make the server fail
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{summary}").ok();
+        stdout.flush().ok();
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = format!("{} {} is ready", env!("CARGO_PKG_NAME"), self.version);
+        for listener in &self.listeners {
+            let mut protocols = Vec::new();
+            if listener.http1 {
+                protocols.push("http1");
+            }
+            if listener.http2 {
+                protocols.push("http2");
+            }
+            if listener.tls {
+                protocols.push("tls");
+            }
+            text.push_str(&format!(
+                "\n  listening on {} ({})",
+                listener.url,
+                protocols.join(", ")
+            ));
+        }
+        for root in &self.roots {
+            text.push_str(&format!("\n  root: {}", root.display()));
+        }
+        if !self.features.is_empty() {
+            text.push_str(&format!("\n  features: {}", self.features.join(", ")));
+        }
+        text
+    }
+}
+
+/// Gets the names of the opt-in features enabled for the requests.
+fn enabled_features(opts: &RequestHandlerOpts) -> Vec<&'static str> {
+    let mut features = Vec::new();
+    let mut feature = |enabled: bool, name: &'static str| {
+        if enabled {
+            features.push(name);
+        }
+    };
+
+    feature(opts.compression, "compression");
+    feature(opts.compression_static, "compression-static");
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    feature(opts.compression_adaptive, "compression-adaptive");
+    #[cfg(feature = "directory-listing")]
+    feature(opts.dir_listing, "directory-listing");
+    feature(
+        opts.cors.is_some()
+            || opts
+                .advanced_opts
+                .as_ref()
+                .is_some_and(|advanced| advanced.cors.is_some()),
+        "cors",
+    );
+    #[cfg(feature = "basic-auth")]
+    feature(!opts.basic_auth.is_empty(), "basic-auth");
+    feature(opts.security_headers, "security-headers");
+    feature(opts.cache_control_headers, "cache-control-headers");
+    feature(opts.media_streaming, "media-streaming");
+    #[cfg(feature = "fallback-page")]
+    feature(
+        !opts.page_fallback.is_empty() || !opts.page_fallbacks.is_empty(),
+        "page-fallback",
+    );
+    feature(opts.accept_markdown, "accept-markdown");
+    #[cfg(feature = "markdown")]
+    feature(opts.markdown_render, "markdown-render");
+    feature(opts.ssi, "ssi");
+    feature(opts.dev_reload.is_some(), "dev-reload");
+    feature(opts.health, "health");
+    feature(!opts.probe_endpoint.is_empty(), "probe-endpoint");
+    #[cfg(feature = "metrics")]
+    feature(opts.metrics, "metrics");
+    #[cfg(feature = "archive-preview")]
+    feature(opts.archive_preview, "archive-preview");
+    #[cfg(feature = "subresource-integrity")]
+    feature(!opts.sri_manifest.is_empty(), "sri-manifest");
+    #[cfg(feature = "experimental")]
+    feature(opts.memory_cache.is_some(), "memory-cache");
+    feature(opts.chunk_cache.is_some(), "chunk-cache");
+    #[cfg(feature = "cache-invalidation")]
+    feature(opts.cache_invalidation.is_some(), "cache-invalidation");
+    feature(opts.type_map.is_some(), "type-map");
+    feature(opts.maintenance_mode, "maintenance-mode");
+    features
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StartupSummary, StartupSummaryFormat};
+    use crate::handler::RequestHandlerOpts;
+    use crate::settings::cli::General;
+    use clap::Parser;
+
+    fn summary(args: &[&str], handler_opts: &RequestHandlerOpts) -> StartupSummary {
+        let mut argv = vec!["static-web-server"];
+        argv.extend_from_slice(args);
+        let general = General::parse_from(argv);
+        let mut summary = StartupSummary::new(&general, handler_opts);
+        summary.tcp_listener("127.0.0.1:8787".parse().unwrap(), false, true, false);
+        summary.tcp_listener("[::1]:8443".parse().unwrap(), true, true, true);
+        summary
+    }
+
+    /// Handler options without the features enabled by default.
+    fn handler_opts() -> RequestHandlerOpts {
+        #[allow(unused_mut)]
+        let mut handler_opts = RequestHandlerOpts {
+            root_dir: "./public".into(),
+            cache_control_headers: false,
+            ..Default::default()
+        };
+        #[cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        ))]
+        {
+            handler_opts.compression = false;
+        }
+        handler_opts
+    }
+
+    #[test]
+    fn test_summary_json() {
+        let handler_opts = RequestHandlerOpts {
+            security_headers: true,
+            health: true,
+            ..handler_opts()
+        };
+        let summary = summary(
+            &[
+                "--startup-summary",
+                "json",
+                "--control-listen",
+                "127.0.0.1:9090",
+            ],
+            &handler_opts,
+        );
+        assert_eq!(summary.format, Some(StartupSummaryFormat::Json));
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["event"], "ready");
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["listeners"][0]["url"], "http://127.0.0.1:8787");
+        assert_eq!(json["listeners"][1]["url"], "https://[::1]:8443");
+        assert_eq!(json["listeners"][1]["tls"], true);
+        assert_eq!(json["roots"], serde_json::json!(["./public"]));
+        assert_eq!(
+            json["features"],
+            serde_json::json!(["security-headers", "health", "control-api"])
+        );
+        assert!(json.get("format").is_none());
+    }
+
+    #[test]
+    fn test_summary_text() {
+        let summary = summary(&[], &handler_opts());
+        assert_eq!(summary.format, Some(StartupSummaryFormat::Text));
+        assert_eq!(
+            summary.to_text(),
+            format!(
+                "static-web-server {} is ready\
+                \n  listening on http://127.0.0.1:8787 (http1)\
+                \n  listening on https://[::1]:8443 (http1, http2, tls)\
+                \n  root: ./public",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn test_summary_quiet() {
+        let summary = summary(&["--quiet"], &RequestHandlerOpts::default());
+        assert!(summary.format.is_none());
+    }
+}
@@ -13,14 +13,22 @@ use headers::{AcceptRanges, HeaderMap, HeaderMapExt, HeaderValue};
 use hyper::{Body, Method, Response, StatusCode, header::CONTENT_ENCODING, header::CONTENT_LENGTH};
 use std::fs::{File, Metadata};
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::Result;
+use crate::chunk_cache::ChunkCacheOpts;
 use crate::conditional_headers::ConditionalHeaders;
+use crate::deadline::{self, Deadline};
+use crate::embedded::EmbeddedAssetsOpts;
 use crate::fs::meta::{FileMetadata, try_metadata, try_metadata_with_html_suffix};
 use crate::fs::path::{PathExt, sanitize_path};
+use crate::fs::symlinks::FollowSymlinks;
+use crate::fs::vfs::{Filesystem, VfsFile};
+use crate::headers_ext::ContentCoding;
 use crate::http_ext::{HTTP_SUPPORTED_METHODS, MethodExt};
-use crate::response::response_body;
+use crate::ignore_patterns::IgnorePatterns;
+use crate::response::{io_error_status, response_body, vfs_body};
+use crate::type_map::TypeMap;
 
 #[cfg(feature = "experimental")]
 use crate::mem_cache::{cache, cache::MemCacheOpts};
@@ -38,6 +46,10 @@ use crate::compression_static;
 use crate::{
     directory_listing,
     directory_listing::{DirListFmt, DirListOpts},
+    directory_listing_locale::{DirListLocale, DirListLocales},
+    directory_listing_readme::DirReadme,
+    directory_listing_size::DirSizeCache,
+    directory_listing_sort::DirListSort,
 };
 
 #[cfg(feature = "directory-listing-download")]
@@ -54,10 +66,26 @@ pub struct HandleOpts<'a> {
     /// In-memory files cache feature (experimental).
     #[cfg(feature = "experimental")]
     pub memory_cache: Option<&'a MemCacheOpts>,
+    /// Byte-range chunk cache feature.
+    pub chunk_cache: Option<&'a ChunkCacheOpts>,
+    /// Maximum size in bytes of the file ranges served via memory maps.
+    #[cfg(feature = "mmap")]
+    pub mmap_max_size: u64,
+    /// Pre-generated type map of the files.
+    pub type_map: Option<&'a TypeMap>,
+    /// Deadline of the request, its filesystem work is abandoned once expired.
+    pub deadline: Option<&'a Deadline>,
     /// Request headers.
     pub headers: &'a HeaderMap<HeaderValue>,
     /// Request base path.
     pub base_path: &'a PathBuf,
+    /// File system serving the base path instead of the disk if any, E.g. a root archive.
+    pub root_filesystem: Option<&'a dyn Filesystem>,
+    /// Root directories overlaid below the base path in priority order,
+    /// looked up when a file is not found under the base path.
+    pub overlay_paths: &'a [PathBuf],
+    /// Assets embedded into the binary served instead of or below the base path.
+    pub embedded_assets: Option<&'a EmbeddedAssetsOpts>,
     /// Request base path.
     pub uri_path: &'a str,
     /// Index files.
@@ -68,14 +96,38 @@ pub struct HandleOpts<'a> {
     #[cfg(feature = "directory-listing")]
     #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
     pub dir_listing: bool,
-    /// Directory listing order feature.
+    /// Directory listing sorting feature.
     #[cfg(feature = "directory-listing")]
     #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
-    pub dir_listing_order: u8,
+    pub dir_listing_sort: &'a DirListSort,
     /// Directory listing format feature.
     #[cfg(feature = "directory-listing")]
     #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
     pub dir_listing_format: &'a DirListFmt,
+    /// Directory listing size feature.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub dir_listing_dir_size: Option<&'a DirSizeCache>,
+    /// Directory listing entry details feature.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub dir_listing_details: bool,
+    /// Directory listing locales feature, the built-in English locale if not specified.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub dir_listing_locales: Option<&'a DirListLocales>,
+    /// Directory listing locale taking precedence over the default one (E.g. of a virtual host).
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub dir_listing_locale: Option<&'a str>,
+    /// Glob patterns of the entries which are not listed but still served.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub dir_listing_hide: Option<&'a IgnorePatterns>,
+    /// Header and readme files rendered in directory listings.
+    #[cfg(feature = "directory-listing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing")))]
+    pub dir_listing_readme: Option<&'a DirReadme>,
     /// Directory listing download feature.
     #[cfg(feature = "directory-listing-download")]
     #[cfg_attr(docsrs, doc(cfg(feature = "directory-listing-download")))]
@@ -84,10 +136,16 @@ pub struct HandleOpts<'a> {
     pub redirect_trailing_slash: bool,
     /// Compression static feature.
     pub compression_static: bool,
+    /// Pre-compressed file variants to look up in priority order.
+    pub compression_static_order: &'a [ContentCoding],
     /// Ignore hidden files feature.
     pub ignore_hidden_files: bool,
+    /// Glob patterns of the files and directories which are never served.
+    pub ignore_patterns: Option<&'a IgnorePatterns>,
     /// Prevent following symlinks for files and directories.
     pub disable_symlinks: bool,
+    /// Policy of the symlinks followed for files and directories, `never` if symlinks are disabled.
+    pub follow_symlinks: FollowSymlinks,
 }
 
 /// Static file response type with additional data.
@@ -100,7 +158,53 @@ pub struct StaticFileResponse {
 
 /// The server entry point to handle incoming requests which map to specific files
 /// on file system and return a file response.
+///
+/// A file not found under the base path (or its root file system if any) is looked up
+/// under the overlay paths in order, then in the embedded assets if any.
 pub async fn handle(opts: &HandleOpts<'_>) -> Result<StaticFileResponse, StatusCode> {
+    let embedded = opts.embedded_assets;
+    if let Some(embedded) = embedded.filter(|embedded| embedded.only) {
+        return handle_vfs(opts, embedded.source.as_ref()).await;
+    }
+
+    let mut result = match opts.root_filesystem {
+        Some(fs) => handle_vfs(opts, fs).await,
+        None => handle_root(opts, opts.base_path).await,
+    };
+    for base_path in opts.overlay_paths {
+        if !matches!(result, Err(StatusCode::NOT_FOUND)) {
+            break;
+        }
+        tracing::trace!(
+            "file not found, looking up the overlay root {}",
+            base_path.display()
+        );
+        result = handle_root(opts, base_path).await;
+    }
+    match embedded {
+        Some(embedded) if matches!(result, Err(StatusCode::NOT_FOUND)) => {
+            handle_vfs(opts, embedded.source.as_ref()).await
+        }
+        _ => result,
+    }
+}
+
+/// Handles a request which maps to a file of the given virtual file system,
+/// marking its response since it has no file on disk.
+async fn handle_vfs(
+    opts: &HandleOpts<'_>,
+    fs: &dyn Filesystem,
+) -> Result<StaticFileResponse, StatusCode> {
+    let mut result = handle_filesystem(opts, fs).await?;
+    result.resp.extensions_mut().insert(VfsFile);
+    Ok(result)
+}
+
+/// Handles a request which maps to a file under the given root directory.
+async fn handle_root(
+    opts: &HandleOpts<'_>,
+    base_path: &Path,
+) -> Result<StaticFileResponse, StatusCode> {
     let method = opts.method;
     let uri_path = opts.uri_path;
 
@@ -108,9 +212,11 @@ pub async fn handle(opts: &HandleOpts<'_>) -> Result<StaticFileResponse, StatusC
     if !method.is_allowed() {
         return Err(StatusCode::METHOD_NOT_ALLOWED);
     }
+    deadline::check(opts.deadline)?;
 
     let headers_opt = opts.headers;
-    let mut file_path = sanitize_path(opts.base_path, uri_path)?;
+    let follow_symlinks = opts.follow_symlinks.or_disabled(opts.disable_symlinks);
+    let mut file_path = sanitize_path(base_path, uri_path)?;
 
     // In-memory file cache feature with eviction policy
     #[cfg(feature = "experimental")]
@@ -130,6 +236,15 @@ pub async fn handle(opts: &HandleOpts<'_>) -> Result<StaticFileResponse, StatusC
         }
     }
 
+    // Prevent symlinks access according to the symlinks policy
+    if !follow_symlinks.allows(base_path, &file_path) {
+        tracing::warn!(
+            "file path {} is not allowed by the symlinks policy, access denied",
+            file_path.display()
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     let FileMetadata {
         file_path,
         metadata,
@@ -139,51 +254,53 @@ pub async fn handle(opts: &HandleOpts<'_>) -> Result<StaticFileResponse, StatusC
         &mut file_path,
         headers_opt,
         opts.compression_static,
+        opts.compression_static_order,
         opts.index_files,
-        opts.disable_symlinks,
+        opts.deadline,
     )?;
 
+    // The resolved file (E.g. an index file or a pre-compressed variant) must be allowed as well
+    let variant_path = precompressed_variant.as_ref().map(|(path, _)| path);
+    if let Some(path) = std::iter::once(file_path)
+        .chain(variant_path)
+        .find(|path| !follow_symlinks.allows(base_path, path))
+    {
+        tracing::warn!(
+            "file path {} is not allowed by the symlinks policy, access denied",
+            path.display()
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     // Check for a hidden file/directory (dotfile) and ignore it if feature enabled
     if opts.ignore_hidden_files && file_path.is_hidden() {
         return Err(StatusCode::NOT_FOUND);
     }
 
+    // Check for a file/directory matching the ignore patterns if any
+    if opts
+        .ignore_patterns
+        .is_some_and(|patterns| patterns.is_ignored_in(base_path, file_path))
+    {
+        tracing::debug!("file path {} is ignored", file_path.display());
+        return Err(StatusCode::NOT_FOUND);
+    }
+
     let resp_file_path = file_path.to_owned();
 
     // Check for a trailing slash on the current directory path
     // and redirect if that path doesn't end with the slash char
     if is_dir && opts.redirect_trailing_slash && !uri_path.ends_with('/') {
-        let query = opts.uri_query.map_or(String::new(), |s| ["?", s].concat());
-        let uri = [uri_path, "/", query.as_str()].concat();
-        let loc = match HeaderValue::from_str(uri.as_str()) {
-            Ok(val) => val,
-            Err(err) => {
-                tracing::error!("invalid header value from current uri: {:?}", err);
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
-        };
-
-        let mut resp = Response::new(Body::empty());
-        resp.headers_mut().insert(hyper::header::LOCATION, loc);
-        *resp.status_mut() = StatusCode::PERMANENT_REDIRECT;
-
-        tracing::trace!("uri doesn't end with a slash so redirecting permanently");
         return Ok(StaticFileResponse {
-            resp,
+            resp: trailing_slash_redirect(uri_path, opts.uri_query)?,
             file_path: resp_file_path,
         });
     }
 
     // Respond with the permitted communication methods
     if method.is_options() {
-        let mut resp = Response::new(Body::empty());
-        *resp.status_mut() = StatusCode::NO_CONTENT;
-        resp.headers_mut()
-            .typed_insert(headers::Allow::from_iter(HTTP_SUPPORTED_METHODS.clone()));
-        resp.headers_mut().typed_insert(AcceptRanges::bytes());
-
         return Ok(StaticFileResponse {
-            resp,
+            resp: options_response(),
             file_path: resp_file_path,
         });
     }
@@ -213,8 +330,10 @@ pub async fn handle(opts: &HandleOpts<'_>) -> Result<StaticFileResponse, StatusC
                         &fp,
                         DirDownloadOpts {
                             method,
-                            disable_symlinks: opts.disable_symlinks,
+                            disable_symlinks: follow_symlinks != FollowSymlinks::Always,
                             ignore_hidden_files: opts.ignore_hidden_files,
+                            ignore_patterns: opts.ignore_patterns,
+                            root_dir: base_path,
                         },
                     );
                     return Ok(StaticFileResponse {
@@ -228,18 +347,55 @@ pub async fn handle(opts: &HandleOpts<'_>) -> Result<StaticFileResponse, StatusC
             }
         }
 
-        let resp = directory_listing::auto_index(DirListOpts {
-            method,
-            current_path: uri_path,
-            uri_query: opts.uri_query,
-            filepath: file_path,
-            dir_listing_order: opts.dir_listing_order,
-            dir_listing_format: opts.dir_listing_format,
-            ignore_hidden_files: opts.ignore_hidden_files,
-            disable_symlinks: opts.disable_symlinks,
-            #[cfg(feature = "directory-listing-download")]
-            dir_listing_download: opts.dir_listing_download,
-        })?;
+        let (locale, locale_negotiated) = match opts.dir_listing_locales {
+            Some(locales) => locales.select(opts.dir_listing_locale, headers_opt),
+            None => (DirListLocale::english(), false),
+        };
+
+        // The directory entries are read on the blocking threads until the request deadline
+        let method = method.clone();
+        let current_path = uri_path.to_owned();
+        let uri_query = opts.uri_query.map(str::to_owned);
+        let filepath = file_path.to_owned();
+        let dir_listing_sort = opts.dir_listing_sort.clone();
+        let dir_listing_format = opts.dir_listing_format.clone();
+        let dir_size = opts.dir_listing_dir_size.cloned();
+        let dir_listing_details = opts.dir_listing_details;
+        let locale = locale.clone();
+        let ignore_hidden_files = opts.ignore_hidden_files;
+        let ignore_patterns = opts.ignore_patterns.cloned();
+        let hide_patterns = opts.dir_listing_hide.cloned();
+        let readme = opts.dir_listing_readme.cloned();
+        let root_dir = base_path.to_owned();
+        let disable_symlinks = follow_symlinks == FollowSymlinks::Never;
+        #[cfg(feature = "directory-listing-download")]
+        let dir_listing_download = opts.dir_listing_download.to_vec();
+
+        let resp = deadline::spawn_blocking(opts.deadline, move |deadline| {
+            directory_listing::auto_index(DirListOpts {
+                method: &method,
+                current_path: &current_path,
+                uri_query: uri_query.as_deref(),
+                filepath: &filepath,
+                dir_listing_sort: &dir_listing_sort,
+                dir_listing_format: &dir_listing_format,
+                dir_size: dir_size.as_ref(),
+                dir_listing_details,
+                locale: &locale,
+                locale_negotiated,
+                ignore_hidden_files,
+                ignore_patterns: ignore_patterns.as_ref(),
+                hide_patterns: hide_patterns.as_ref(),
+                readme: readme.as_ref(),
+                root_dir: &root_dir,
+                disable_symlinks,
+                follow_symlinks,
+                #[cfg(feature = "directory-listing-download")]
+                dir_listing_download: &dir_listing_download,
+                deadline,
+            })
+        })
+        .await?;
 
         return Ok(StaticFileResponse {
             resp,
@@ -247,6 +403,8 @@ pub async fn handle(opts: &HandleOpts<'_>) -> Result<StaticFileResponse, StatusC
         });
     }
 
+    deadline::check(opts.deadline)?;
+
     // Check for a pre-compressed file variant if present under the `opts.compression_static` context
     if let Some(precompressed_meta) = precompressed_variant {
         let (precomp_path, precomp_encoding) = precompressed_meta;
@@ -257,6 +415,10 @@ pub async fn handle(opts: &HandleOpts<'_>) -> Result<StaticFileResponse, StatusC
             Some(precomp_path),
             #[cfg(feature = "experimental")]
             opts.memory_cache,
+            opts.chunk_cache,
+            #[cfg(feature = "mmap")]
+            opts.mmap_max_size,
+            opts.type_map,
         )?;
 
         // Prepare corresponding headers to let know how to decode the payload
@@ -279,11 +441,18 @@ pub async fn handle(opts: &HandleOpts<'_>) -> Result<StaticFileResponse, StatusC
         });
     }
 
-    #[cfg(feature = "experimental")]
-    let resp = file_reply(headers_opt, file_path, &metadata, None, opts.memory_cache)?;
-
-    #[cfg(not(feature = "experimental"))]
-    let resp = file_reply(headers_opt, file_path, &metadata, None)?;
+    let resp = file_reply(
+        headers_opt,
+        file_path,
+        &metadata,
+        None,
+        #[cfg(feature = "experimental")]
+        opts.memory_cache,
+        opts.chunk_cache,
+        #[cfg(feature = "mmap")]
+        opts.mmap_max_size,
+        opts.type_map,
+    )?;
 
     Ok(StaticFileResponse {
         resp,
@@ -291,6 +460,189 @@ pub async fn handle(opts: &HandleOpts<'_>) -> Result<StaticFileResponse, StatusC
     })
 }
 
+/// Handles a request which maps to a file of the given virtual file system.
+async fn handle_filesystem(
+    opts: &HandleOpts<'_>,
+    fs: &dyn Filesystem,
+) -> Result<StaticFileResponse, StatusCode> {
+    let method = opts.method;
+    let uri_path = opts.uri_path;
+
+    // Check if current HTTP method for incoming request is supported
+    if !method.is_allowed() {
+        return Err(StatusCode::METHOD_NOT_ALLOWED);
+    }
+    deadline::check(opts.deadline)?;
+
+    // The file system paths are relative to its root and separated by `/`
+    let rel_path = sanitize_path(Path::new(""), uri_path)?;
+    let rel_path = rel_path
+        .components()
+        .filter_map(|component| component.as_os_str().to_str())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    if opts.ignore_hidden_files && rel_path.split('/').any(|name| name.starts_with('.')) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if opts
+        .ignore_patterns
+        .is_some_and(|patterns| patterns.is_ignored(Path::new(&rel_path)))
+    {
+        tracing::debug!("file system path {} is ignored", rel_path);
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let (vfs_path, metadata) = match fs.metadata(&rel_path).await {
+        Ok(metadata) if metadata.is_dir => {
+            if opts.redirect_trailing_slash && !uri_path.ends_with('/') {
+                return Ok(StaticFileResponse {
+                    resp: trailing_slash_redirect(uri_path, opts.uri_query)?,
+                    file_path: PathBuf::from(rel_path),
+                });
+            }
+
+            // Try every index file variant in order
+            let index_files = if opts.index_files.is_empty() {
+                DEFAULT_INDEX_FILES
+            } else {
+                opts.index_files
+            };
+            let mut index = None;
+            for index_file in index_files {
+                deadline::check(opts.deadline)?;
+                let path = match rel_path.as_str() {
+                    "" => (*index_file).to_owned(),
+                    dir => [dir, "/", index_file].concat(),
+                };
+                match fs.metadata(&path).await {
+                    Ok(metadata) if !metadata.is_dir => {
+                        index = Some((path, metadata));
+                        break;
+                    }
+                    _ => continue,
+                }
+            }
+            match index {
+                Some(index) => index,
+                #[cfg(feature = "directory-listing")]
+                None if opts.dir_listing => return vfs_dir_listing(opts, fs, rel_path).await,
+                None => return Err(StatusCode::NOT_FOUND),
+            }
+        }
+        Ok(metadata) => (rel_path, metadata),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            // Fallback to the path suffixed with `.html` like for the file system
+            let html_path = [rel_path.as_str(), ".html"].concat();
+            match fs.metadata(&html_path).await {
+                Ok(metadata) if !metadata.is_dir => (html_path, metadata),
+                _ => return Err(StatusCode::NOT_FOUND),
+            }
+        }
+        Err(err) => return Err(io_error_status(&err, Path::new(&rel_path))),
+    };
+
+    let file_path = PathBuf::from(&vfs_path);
+    let resp = if method.is_options() {
+        options_response()
+    } else {
+        vfs_body(
+            fs,
+            &vfs_path,
+            &file_path,
+            &metadata,
+            ConditionalHeaders::new(opts.headers),
+        )
+        .await?
+    };
+    Ok(StaticFileResponse { resp, file_path })
+}
+
+/// Lists the entries of a directory of the given virtual file system.
+#[cfg(feature = "directory-listing")]
+async fn vfs_dir_listing(
+    opts: &HandleOpts<'_>,
+    fs: &dyn Filesystem,
+    rel_path: String,
+) -> Result<StaticFileResponse, StatusCode> {
+    let file_path = PathBuf::from(rel_path);
+    let resp = if opts.method.is_options() {
+        options_response()
+    } else {
+        let entries = fs
+            .read_dir(&file_path.to_string_lossy())
+            .await
+            .map_err(|err| io_error_status(&err, &file_path))?;
+        deadline::check(opts.deadline)?;
+
+        let (locale, locale_negotiated) = match opts.dir_listing_locales {
+            Some(locales) => locales.select(opts.dir_listing_locale, opts.headers),
+            None => (DirListLocale::english(), false),
+        };
+        directory_listing::vfs_auto_index(
+            DirListOpts {
+                method: opts.method,
+                current_path: opts.uri_path,
+                uri_query: opts.uri_query,
+                filepath: &file_path,
+                dir_listing_sort: opts.dir_listing_sort,
+                dir_listing_format: opts.dir_listing_format,
+                // The directory sizes, entry details and downloads are only supported on disk
+                dir_size: None,
+                dir_listing_details: false,
+                locale,
+                locale_negotiated,
+                ignore_hidden_files: opts.ignore_hidden_files,
+                ignore_patterns: opts.ignore_patterns,
+                hide_patterns: opts.dir_listing_hide,
+                // The header and readme files are only supported on disk
+                readme: None,
+                root_dir: Path::new(""),
+                disable_symlinks: false,
+                follow_symlinks: FollowSymlinks::Always,
+                #[cfg(feature = "directory-listing-download")]
+                dir_listing_download: &[],
+                deadline: opts.deadline,
+            },
+            entries,
+        )?
+    };
+    Ok(StaticFileResponse { resp, file_path })
+}
+
+/// Redirects a directory request path to the same path with a trailing slash.
+fn trailing_slash_redirect(
+    uri_path: &str,
+    uri_query: Option<&str>,
+) -> Result<Response<Body>, StatusCode> {
+    let query = uri_query.map_or(String::new(), |s| ["?", s].concat());
+    let uri = [uri_path, "/", query.as_str()].concat();
+    let loc = match HeaderValue::from_str(uri.as_str()) {
+        Ok(val) => val,
+        Err(err) => {
+            tracing::error!("invalid header value from current uri: {:?}", err);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let mut resp = Response::new(Body::empty());
+    resp.headers_mut().insert(hyper::header::LOCATION, loc);
+    *resp.status_mut() = StatusCode::PERMANENT_REDIRECT;
+
+    tracing::trace!("uri doesn't end with a slash so redirecting permanently");
+    Ok(resp)
+}
+
+/// Responds with the permitted communication methods.
+fn options_response() -> Response<Body> {
+    let mut resp = Response::new(Body::empty());
+    *resp.status_mut() = StatusCode::NO_CONTENT;
+    resp.headers_mut()
+        .typed_insert(headers::Allow::from_iter(HTTP_SUPPORTED_METHODS.clone()));
+    resp.headers_mut().typed_insert(AcceptRanges::bytes());
+    resp
+}
+
 /// Returns the final composed metadata containing
 /// the current `file_path` with its file metadata
 /// as well as its optional pre-compressed variant.
@@ -298,20 +650,12 @@ fn get_composed_file_metadata<'a>(
     mut file_path: &'a mut PathBuf,
     _headers: &'a HeaderMap<HeaderValue>,
     _compression_static: bool,
+    _compression_static_order: &'a [ContentCoding],
     mut index_files: &'a [&'a str],
-    disable_symlinks: bool,
+    deadline: Option<&Deadline>,
 ) -> Result<FileMetadata<'a>, StatusCode> {
     tracing::trace!("getting metadata for file {}", file_path.display());
 
-    // Prevent symlinks access if option is enabled
-    if disable_symlinks && file_path.is_symlink() {
-        tracing::warn!(
-            "file path {} is a symlink, access denied",
-            file_path.display()
-        );
-        return Err(StatusCode::FORBIDDEN);
-    }
-
     // Try to find the file path on the file system
     match try_metadata(file_path) {
         Ok((mut metadata, is_dir)) => {
@@ -322,6 +666,8 @@ fn get_composed_file_metadata<'a>(
                 }
                 let mut index_found = false;
                 for index in index_files {
+                    deadline::check(deadline)?;
+
                     // Append a HTML index page by default if it's a directory path (`autoindex`)
                     tracing::debug!("dir: appending {} to the directory path", index);
                     file_path.push(index);
@@ -335,9 +681,11 @@ fn get_composed_file_metadata<'a>(
                         feature = "compression-zstd"
                     ))]
                     if _compression_static {
-                        if let Some(p) =
-                            compression_static::precompressed_variant(file_path, _headers)
-                        {
+                        if let Some(p) = compression_static::precompressed_variant(
+                            file_path,
+                            _headers,
+                            _compression_static_order,
+                        ) {
                             return Ok(FileMetadata {
                                 file_path,
                                 metadata: p.metadata,
@@ -382,8 +730,11 @@ fn get_composed_file_metadata<'a>(
                     feature = "compression-zstd"
                 ))]
                 if _compression_static {
-                    if let Some(p) = compression_static::precompressed_variant(file_path, _headers)
-                    {
+                    if let Some(p) = compression_static::precompressed_variant(
+                        file_path,
+                        _headers,
+                        _compression_static_order,
+                    ) {
                         return Ok(FileMetadata {
                             file_path,
                             metadata: p.metadata,
@@ -411,7 +762,11 @@ fn get_composed_file_metadata<'a>(
                 feature = "compression-zstd"
             ))]
             if _compression_static {
-                if let Some(p) = compression_static::precompressed_variant(file_path, _headers) {
+                if let Some(p) = compression_static::precompressed_variant(
+                    file_path,
+                    _headers,
+                    _compression_static_order,
+                ) {
                     return Ok(FileMetadata {
                         file_path,
                         metadata: p.metadata,
@@ -446,9 +801,11 @@ fn get_composed_file_metadata<'a>(
                 _ => {
                     // Last pre-compressed variant check or the suffixed file not found
                     if _compression_static {
-                        if let Some(p) =
-                            compression_static::precompressed_variant(file_path, _headers)
-                        {
+                        if let Some(p) = compression_static::precompressed_variant(
+                            file_path,
+                            _headers,
+                            _compression_static_order,
+                        ) {
                             return Ok(FileMetadata {
                                 file_path,
                                 metadata: p.metadata,
@@ -480,42 +837,36 @@ fn get_composed_file_metadata<'a>(
 /// the `meta` param value should corresponds to it.
 /// However, if `path_precompressed` contains some value then
 /// the `meta` param  value will belong to the `path_precompressed` (precompressed file variant).
+#[allow(clippy::too_many_arguments)]
 fn file_reply<'a>(
     headers: &'a HeaderMap<HeaderValue>,
     path: &'a PathBuf,
     meta: &'a Metadata,
     path_precompressed: Option<PathBuf>,
     #[cfg(feature = "experimental")] memory_cache: Option<&'a MemCacheOpts>,
+    chunk_cache: Option<&'a ChunkCacheOpts>,
+    #[cfg(feature = "mmap")] mmap_max_size: u64,
+    type_map: Option<&'a TypeMap>,
 ) -> Result<Response<Body>, StatusCode> {
     let conditionals = ConditionalHeaders::new(headers);
     let file_path = path_precompressed.as_ref().unwrap_or(path);
+    // NOTE: the cached blocks are identified by the requested file path,
+    // so pre-compressed variants are always read from the file system
+    let chunk_cache = chunk_cache.filter(|_| path_precompressed.is_none());
 
     match File::open(file_path) {
-        Ok(file) => {
+        Ok(file) => response_body(
+            file,
+            path,
+            meta,
+            conditionals,
             #[cfg(feature = "experimental")]
-            let resp = response_body(file, path, meta, conditionals, memory_cache);
-
-            #[cfg(not(feature = "experimental"))]
-            let resp = response_body(file, path, meta, conditionals);
-
-            resp
-        }
-        Err(err) => {
-            let status = match err.kind() {
-                io::ErrorKind::NotFound => {
-                    tracing::debug!("file can't be opened or not found: {:?}", path.display());
-                    StatusCode::NOT_FOUND
-                }
-                io::ErrorKind::PermissionDenied => {
-                    tracing::warn!("file permission denied: {:?}", path.display());
-                    StatusCode::FORBIDDEN
-                }
-                _ => {
-                    tracing::error!("file open error (path={:?}): {} ", path.display(), err);
-                    StatusCode::INTERNAL_SERVER_ERROR
-                }
-            };
-            Err(status)
-        }
+            memory_cache,
+            chunk_cache,
+            #[cfg(feature = "mmap")]
+            mmap_max_size,
+            type_map,
+        ),
+        Err(err) => Err(io_error_status(&err, path)),
     }
 }
@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module to rewrite response status codes via TOML config file.
+//!
+
+use hyper::{Body, Request, Response, StatusCode};
+
+use crate::{Error, error_page, handler::RequestHandlerOpts, settings::StatusRewrites};
+
+/// Rewrites the response status code and re-renders its error page if necessary.
+pub(crate) fn post_process<T>(
+    opts: &RequestHandlerOpts,
+    req: &Request<T>,
+    resp: Response<Body>,
+) -> Result<Response<Body>, Error> {
+    let status_rewrites = opts
+        .advanced_opts
+        .as_ref()
+        .and_then(|advanced| advanced.status_rewrites.as_deref());
+
+    match get_status_rewrite(req.uri().path(), resp.status(), status_rewrites) {
+        Some(status) => {
+            tracing::debug!(
+                "status rewrite: {} -> {} for {}",
+                resp.status().as_u16(),
+                status.as_u16(),
+                req.uri().path()
+            );
+            error_page::error_response(req.uri(), req.method(), &status, &opts.error_pages)
+        }
+        None => Ok(resp),
+    }
+}
+
+/// Gets the status code to rewrite to if a status rewrite entry matches.
+/// Only the first matching entry is used.
+pub fn get_status_rewrite(
+    uri_path: &str,
+    status: StatusCode,
+    status_rewrites_opts: Option<&[StatusRewrites]>,
+) -> Option<StatusCode> {
+    status_rewrites_opts?
+        .iter()
+        .find(|entry| entry.status == status && entry.source.is_match(uri_path))
+        .map(|entry| entry.to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::get_status_rewrite;
+    use crate::settings::StatusRewrites;
+    use globset::GlobBuilder;
+    use hyper::StatusCode;
+
+    fn status_rewrite(source: &str, status: StatusCode, to: StatusCode) -> StatusRewrites {
+        StatusRewrites {
+            source: GlobBuilder::new(source)
+                .literal_separator(true)
+                .build()
+                .unwrap()
+                .compile_matcher(),
+            status,
+            to,
+        }
+    }
+
+    #[test]
+    fn test_get_status_rewrite() {
+        let entries = [
+            status_rewrite("/api/**", StatusCode::NOT_FOUND, StatusCode::GONE),
+            status_rewrite("/**", StatusCode::FORBIDDEN, StatusCode::NOT_FOUND),
+        ];
+
+        assert_eq!(
+            get_status_rewrite("/api/v1/users", StatusCode::NOT_FOUND, Some(&entries)),
+            Some(StatusCode::GONE)
+        );
+        assert_eq!(
+            get_status_rewrite("/assets/app.js", StatusCode::NOT_FOUND, Some(&entries)),
+            None
+        );
+        assert_eq!(
+            get_status_rewrite("/private/file.txt", StatusCode::FORBIDDEN, Some(&entries)),
+            Some(StatusCode::NOT_FOUND)
+        );
+        assert_eq!(
+            get_status_rewrite("/api/v1/users", StatusCode::OK, Some(&entries)),
+            None
+        );
+        assert_eq!(
+            get_status_rewrite("/api/v1/users", StatusCode::NOT_FOUND, None),
+            None
+        );
+    }
+}
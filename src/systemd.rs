@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module to integrate the server with the systemd service manager.
+//!
+//! It takes the sockets passed by systemd on socket activation (`LISTEN_FDS`) for the
+//! `systemd:` listeners and notifies the service manager about the server state
+//! (`sd_notify`), so the server can run as a `Type=notify` service with a watchdog.
+//!
+
+use listenfd::ListenFd;
+use std::ffi::OsStr;
+use std::io;
+use std::net::TcpListener;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram, UnixListener};
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::Duration;
+
+use crate::{Context, Result};
+
+/// Environment variable holding the path of the service manager notification socket.
+const NOTIFY_SOCKET_ENV: &str = "NOTIFY_SOCKET";
+
+/// Environment variable holding the watchdog timeout in microseconds.
+const WATCHDOG_USEC_ENV: &str = "WATCHDOG_USEC";
+
+/// Environment variable holding the process ID expected to ping the watchdog.
+const WATCHDOG_PID_ENV: &str = "WATCHDOG_PID";
+
+/// Environment variable holding the colon-separated names of the passed sockets.
+const LISTEN_FDNAMES_ENV: &str = "LISTEN_FDNAMES";
+
+/// Environment variables which only concern the current process, so they are not inherited
+/// by the worker processes.
+pub(crate) const PROCESS_ENVS: [&str; 3] = [NOTIFY_SOCKET_ENV, WATCHDOG_USEC_ENV, WATCHDOG_PID_ENV];
+
+/// Sockets passed by systemd along with their names.
+struct Sockets {
+    fds: ListenFd,
+    names: Vec<String>,
+}
+
+/// Sockets passed by systemd, which are taken once by the listeners using them.
+static SOCKETS: OnceLock<Mutex<Sockets>> = OnceLock::new();
+
+/// A listener socket passed by systemd.
+pub(crate) enum InheritedListener {
+    /// TCP socket (`ListenStream=` with a port or an address)
+    Tcp(TcpListener),
+    /// Unix domain socket (`ListenStream=` with a path)
+    Unix(UnixListener),
+}
+
+/// Takes the listener socket passed by systemd given by its name (`FileDescriptorName=`)
+/// or its index among the passed sockets.
+pub(crate) fn take_listener(name: &str) -> Result<InheritedListener> {
+    let sockets = SOCKETS.get_or_init(|| {
+        // NOTE: the names are read first since the socket activation variables are
+        // removed from the environment once the sockets are taken
+        let names = std::env::var(LISTEN_FDNAMES_ENV)
+            .map(|names| names.split(':').map(str::to_owned).collect())
+            .unwrap_or_default();
+        Mutex::new(Sockets {
+            fds: ListenFd::from_env(),
+            names,
+        })
+    });
+    let mut sockets = sockets.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let available = sockets.fds.len();
+    let index = match sockets.names.iter().position(|socket| socket == name) {
+        Some(index) => index,
+        None => match name.parse::<usize>() {
+            Ok(index) if index < available => index,
+            _ => bail!(
+                "socket {name} was not passed by systemd, {available} socket(s) available, \
+                check the `FileDescriptorName` option of the socket unit"
+            ),
+        },
+    };
+
+    if let Ok(Some(listener)) = sockets.fds.take_tcp_listener(index) {
+        return Ok(InheritedListener::Tcp(listener));
+    }
+    match sockets.fds.take_unix_listener(index) {
+        Ok(Some(listener)) => Ok(InheritedListener::Unix(listener)),
+        Ok(None) => bail!("socket {name} passed by systemd is already used by another listener"),
+        Err(err) => Err(err).with_context(|| {
+            format!("socket {name} passed by systemd is not a TCP or Unix domain stream socket")
+        }),
+    }
+}
+
+/// Notifies the service manager about the given state if it expects notifications.
+pub(crate) fn notify(state: &str) {
+    let Some(path) = std::env::var_os(NOTIFY_SOCKET_ENV) else {
+        return;
+    };
+    match send(&path, state) {
+        Ok(()) => tracing::debug!("service manager notified: {}", state.replace('\n', ", ")),
+        Err(err) => tracing::warn!(
+            "unable to notify the service manager via {}: {}",
+            path.to_string_lossy(),
+            err
+        ),
+    }
+}
+
+/// Notifies the service manager that the server is ready to accept connections.
+pub(crate) fn ready() {
+    notify("READY=1\nSTATUS=Accepting connections");
+}
+
+/// Notifies the service manager that the server configuration is being reloaded.
+///
+/// The server is expected to notify that it's ready again once the reload is done.
+pub(crate) fn reloading() {
+    #[cfg(target_os = "linux")]
+    {
+        let now = rustix::time::clock_gettime(rustix::time::ClockId::Monotonic);
+        let usec = now.tv_sec as u64 * 1_000_000 + now.tv_nsec as u64 / 1_000;
+        notify(&format!(
+            "RELOADING=1\nSTATUS=Reloading the configuration\nMONOTONIC_USEC={usec}"
+        ));
+    }
+    #[cfg(not(target_os = "linux"))]
+    notify("RELOADING=1\nSTATUS=Reloading the configuration");
+}
+
+/// Notifies the service manager that the server is shutting down.
+pub(crate) fn stopping() {
+    notify("STOPPING=1\nSTATUS=Shutting down");
+}
+
+/// Notifies the service manager that the server is still alive.
+pub(crate) fn watchdog() {
+    notify("WATCHDOG=1");
+}
+
+/// Returns the interval to ping the service manager watchdog at if it's enabled
+/// for the current process, which is half of the watchdog timeout.
+pub(crate) fn watchdog_interval() -> Option<Duration> {
+    std::env::var_os(NOTIFY_SOCKET_ENV)?;
+    if let Ok(pid) = std::env::var(WATCHDOG_PID_ENV) {
+        if pid.parse::<u32>().ok()? != std::process::id() {
+            return None;
+        }
+    }
+    let timeout = std::env::var(WATCHDOG_USEC_ENV).ok()?.parse::<u64>().ok()?;
+    if timeout == 0 {
+        return None;
+    }
+    Some(Duration::from_micros(timeout / 2))
+}
+
+/// Spawns a task pinging the service manager watchdog while the runtime is alive, if enabled.
+pub(crate) fn spawn_watchdog() {
+    let Some(interval) = watchdog_interval() else {
+        return;
+    };
+    tracing::info!(
+        "systemd watchdog: enabled=true, interval={}ms",
+        interval.as_millis()
+    );
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            watchdog();
+        }
+    });
+}
+
+/// Sends a notification datagram to the given socket path,
+/// prefixed by `@` if it's an abstract socket address.
+fn send(path: &OsStr, state: &str) -> io::Result<()> {
+    let addr = match path.as_bytes().strip_prefix(b"@") {
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            SocketAddr::from_abstract_name(name)?
+        }
+        #[cfg(not(target_os = "linux"))]
+        Some(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "abstract socket addresses are not supported on this platform",
+            ));
+        }
+        None => SocketAddr::from_pathname(path)?,
+    };
+    let socket = UnixDatagram::unbound()?;
+    socket.send_to_addr(state.as_bytes(), &addr)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receive(socket: &UnixDatagram) -> String {
+        let mut buf = [0; 256];
+        let len = socket.recv(&mut buf).unwrap();
+        String::from_utf8_lossy(&buf[..len]).into_owned()
+    }
+
+    #[test]
+    fn test_send() {
+        let dir = std::env::temp_dir().join(format!("sws-systemd-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("notify.sock");
+        std::fs::remove_file(&path).ok();
+        let socket = UnixDatagram::bind(&path).unwrap();
+
+        send(path.as_os_str(), "READY=1\nSTATUS=Accepting connections").unwrap();
+        assert_eq!(receive(&socket), "READY=1\nSTATUS=Accepting connections");
+
+        std::fs::remove_dir_all(&dir).ok();
+        assert!(send(path.as_os_str(), "WATCHDOG=1").is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_send_abstract() {
+        use std::os::linux::net::SocketAddrExt;
+
+        let name = format!("sws-systemd-{}", std::process::id());
+        let addr = SocketAddr::from_abstract_name(name.as_bytes()).unwrap();
+        let socket = UnixDatagram::bind_addr(&addr).unwrap();
+
+        send(OsStr::new(&format!("@{name}")), "STOPPING=1").unwrap();
+        assert_eq!(receive(&socket), "STOPPING=1");
+    }
+}
@@ -9,11 +9,14 @@
 /// SWS fixtures module.
 #[doc(hidden)]
 pub mod fixtures {
+    use arc_swap::ArcSwap;
     use std::{path::PathBuf, sync::Arc};
 
     use crate::{
         Settings,
+        error_page::ErrorPages,
         handler::{RequestHandler, RequestHandlerOpts},
+        ip_allowlist::IpAllowlist,
         settings::Advanced,
         settings::cli::General,
     };
@@ -53,6 +56,14 @@ pub mod fixtures {
             feature = "compression-deflate"
         )))]
         let compression_static = false;
+        #[cfg(not(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        )))]
+        let compression_static_order = vec![];
         #[cfg(any(
             feature = "compression",
             feature = "compression-gzip",
@@ -69,11 +80,32 @@ pub mod fixtures {
             feature = "compression-deflate"
         ))]
         let compression_static = general.compression_static;
+        #[cfg(any(
+            feature = "compression",
+            feature = "compression-gzip",
+            feature = "compression-brotli",
+            feature = "compression-zstd",
+            feature = "compression-deflate"
+        ))]
+        let compression_static_order =
+            crate::compression_static::parse_order(&general.compression_static_order)
+                .unwrap_or_default();
+
+        let mut error_pages = ErrorPages::new(general.page404, general.page50x);
+        let advanced_error_pages = advanced.as_ref().and_then(|a| a.error_pages.as_ref());
+        for error_page in advanced_error_pages.into_iter().flatten() {
+            error_pages.insert(error_page.status, error_page.path.clone());
+        }
 
-        RequestHandlerOpts {
+        let type_map = general.type_map;
+        let mut opts = RequestHandlerOpts {
             root_dir: general.root,
+            root_filesystem: None,
+            overlay_dirs: general.overlay_roots,
+            embedded_assets: None,
             compression,
             compression_static,
+            compression_static_order,
             #[cfg(any(
                 feature = "compression",
                 feature = "compression-gzip",
@@ -82,50 +114,235 @@ pub mod fixtures {
                 feature = "compression-deflate"
             ))]
             compression_level: general.compression_level,
+            #[cfg(any(
+                feature = "compression",
+                feature = "compression-gzip",
+                feature = "compression-brotli",
+                feature = "compression-zstd",
+                feature = "compression-deflate"
+            ))]
+            compression_levels: crate::compression::AlgorithmLevels {
+                gzip: general.compression_level_gzip,
+                brotli: general.compression_level_brotli,
+                zstd: general.compression_level_zstd,
+            },
+            #[cfg(any(
+                feature = "compression",
+                feature = "compression-gzip",
+                feature = "compression-brotli",
+                feature = "compression-zstd",
+                feature = "compression-deflate"
+            ))]
+            compression_min_size: general.compression_min_size,
+            #[cfg(any(
+                feature = "compression",
+                feature = "compression-gzip",
+                feature = "compression-brotli",
+                feature = "compression-zstd",
+                feature = "compression-deflate"
+            ))]
+            compression_mime_types: crate::compression::parse_mime_types(
+                &general.compression_mime_types,
+            )
+            .unwrap_or_default(),
+            #[cfg(any(
+                feature = "compression",
+                feature = "compression-gzip",
+                feature = "compression-brotli",
+                feature = "compression-zstd",
+                feature = "compression-deflate"
+            ))]
+            compression_exclude_mime_types: crate::compression::parse_mime_types(
+                &general.compression_exclude_mime_types,
+            )
+            .unwrap_or_default(),
+            #[cfg(any(
+                feature = "compression",
+                feature = "compression-gzip",
+                feature = "compression-brotli",
+                feature = "compression-zstd",
+                feature = "compression-deflate"
+            ))]
+            compression_adaptive: general.compression_adaptive,
             #[cfg(feature = "directory-listing")]
             dir_listing: general.directory_listing,
             #[cfg(feature = "directory-listing")]
-            dir_listing_order: general.directory_listing_order,
+            dir_listing_paths: crate::directory_listing::parse_paths(
+                &general.directory_listing_paths,
+            )
+            .unwrap(),
+            #[cfg(feature = "directory-listing")]
+            dir_listing_sort: if general.directory_listing_sort.trim().is_empty() {
+                crate::directory_listing_sort::DirListSort::from_code(
+                    general.directory_listing_order,
+                )
+            } else {
+                general.directory_listing_sort.parse().unwrap()
+            }
+            .dirs_first(general.directory_listing_dirs_first),
             #[cfg(feature = "directory-listing")]
             dir_listing_format: general.directory_listing_format,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_details: general.directory_listing_details,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_locales: crate::directory_listing_locale::DirListLocales::new(
+                &general.directory_listing_locale,
+                advanced
+                    .as_ref()
+                    .and_then(|a| a.directory_listing_locales.as_ref()),
+            )
+            .unwrap(),
+            #[cfg(feature = "directory-listing")]
+            dir_listing_hide: crate::ignore_patterns::IgnorePatterns::new(
+                &general.directory_listing_hide,
+            )
+            .unwrap(),
+            #[cfg(feature = "directory-listing")]
+            dir_listing_readme: crate::directory_listing_readme::DirReadme::new(
+                &general.directory_listing_header,
+                &general.directory_listing_readme,
+            )
+            .unwrap(),
+            #[cfg(feature = "directory-listing")]
+            dir_listing_dir_size: general.directory_listing_dir_size.then(|| {
+                crate::directory_listing_size::DirSizeCache::new(
+                    general.directory_listing_dir_size_ttl,
+                    general.directory_listing_dir_size_max_entries,
+                    general.ignore_hidden_files,
+                )
+                .concurrency(general.directory_listing_dir_size_concurrency)
+            }),
             #[cfg(feature = "directory-listing-download")]
             dir_listing_download: general.directory_listing_download,
             // TODO: add support or `cors` when required
             cors: None,
             security_headers: general.security_headers,
             cache_control_headers: general.cache_control_headers,
-            page404: general.page404,
-            page50x: general.page50x,
+            media_streaming: general.media_streaming,
+            error_pages,
             // TODO: add support or `page_fallback` when required
             #[cfg(feature = "fallback-page")]
             page_fallback: vec![],
+            #[cfg(feature = "fallback-page")]
+            page_fallbacks: crate::fallback_page::fallback_pages(advanced.as_ref()),
+            #[cfg(feature = "fallback-page")]
+            page_fallback_soft404: general.page_fallback_soft404,
+            #[cfg(feature = "fallback-page")]
+            page_fallback_status: general.page_fallback_status,
+            #[cfg(feature = "fallback-page")]
+            page_fallback_methods: crate::fallback_page::parse_methods(
+                &general.page_fallback_methods,
+            )
+            .unwrap(),
+            #[cfg(feature = "fallback-page")]
+            page_fallback_html_only: general.page_fallback_html_only,
             #[cfg(feature = "basic-auth")]
             basic_auth: general.basic_auth,
             log_remote_address: general.log_remote_address,
             log_x_real_ip: general.log_x_real_ip,
             log_forwarded_for: general.log_forwarded_for,
-            trusted_proxies: general.trusted_proxies,
+            trusted_proxies: IpAllowlist::new(
+                &general.trusted_proxies,
+                general.trusted_proxies_refresh,
+            )
+            .unwrap(),
             redirect_trailing_slash: general.redirect_trailing_slash,
+            reject_absolute_form: general.reject_absolute_form,
             ignore_hidden_files: general.ignore_hidden_files,
+            ignore_patterns: crate::ignore_patterns::IgnorePatterns::new(&general.ignore_patterns)
+                .unwrap(),
             disable_symlinks: general.disable_symlinks,
+            follow_symlinks: general
+                .follow_symlinks
+                .or_disabled(general.disable_symlinks),
             accept_markdown: general.accept_markdown,
+            language_variants: crate::language_variants::parse(&general.language_variants).unwrap(),
+            image_variants: crate::image_variants::parse(&general.image_variants).unwrap(),
             index_files: vec![general.index_files],
             health: general.health,
-            #[cfg(all(unix, feature = "experimental"))]
-            experimental_metrics: general.experimental_metrics,
+            health_ready_files: general.health_ready_files,
+            #[cfg(feature = "metrics")]
+            metrics: general.metrics,
             maintenance_mode: general.maintenance_mode,
             maintenance_mode_status: general.maintenance_mode_status,
             maintenance_mode_file: general.maintenance_mode_file,
+            maintenance_mode_allow_ips: IpAllowlist::default(),
+            maintenance_mode_allow_paths: globset::GlobSet::empty(),
+            maintenance_mode_start: None,
+            maintenance_mode_end: None,
+            #[cfg(feature = "markdown")]
+            markdown_render: general.markdown_render,
+            // TODO: add support or `markdown_render_template` when required
+            #[cfg(feature = "markdown")]
+            markdown_render_template: String::new(),
+            ssi: general.ssi,
+            ssi_extensions: vec![general.ssi_extensions],
+            dev_reload: general
+                .dev_reload
+                .then(|| crate::dev_reload::DevReload::new(general.dev_reload_inject)),
+            probe_endpoint: general.probe_endpoint,
+            server_ident: crate::server_ident::parse(&general.server_ident).unwrap(),
+            alt_svc: crate::alt_svc::parse(&general.alt_svc, general.alt_svc_max_age).unwrap(),
+            max_uri_length: general.max_uri_length,
+            max_headers_size: general.max_headers_size,
+            max_body_size: general.max_body_size,
+            request_timeout: (general.request_timeout > 0)
+                .then(|| std::time::Duration::from_secs(general.request_timeout)),
+            not_found_cache: (general.not_found_cache_ttl > 0).then(|| {
+                crate::not_found_cache::NotFoundCache::new(
+                    general.not_found_cache_ttl,
+                    general.not_found_cache_capacity,
+                )
+            }),
+            bandwidth_limit: (general.limit_rate > 0 || general.limit_rate_global > 0).then(|| {
+                crate::bandwidth::BandwidthLimit::new(general.limit_rate, general.limit_rate_global)
+            }),
+            #[cfg(feature = "archive-preview")]
+            archive_preview: general.archive_preview,
+            #[cfg(feature = "archive-preview")]
+            archive_preview_max_entries: general.archive_preview_max_entries,
+            #[cfg(feature = "archive-preview")]
+            archive_preview_max_size: general.archive_preview_max_size,
+            #[cfg(feature = "subresource-integrity")]
+            sri_manifest: general.sri_manifest,
+            #[cfg(feature = "subresource-integrity")]
+            sri_algorithm: general.sri_algorithm,
+            #[cfg(feature = "subresource-integrity")]
+            sri_extensions: crate::sri::parse_extensions(&general.sri_extensions),
+            default_charset: general.default_charset,
             #[cfg(feature = "experimental")]
             memory_cache: None,
+            chunk_cache: None,
+            #[cfg(feature = "mmap")]
+            mmap_max_size: 0,
+            #[cfg(feature = "cache-invalidation")]
+            cache_invalidation: None,
+            type_map: None,
             advanced_opts: advanced,
-        }
+        };
+        crate::type_map::init(type_map, &mut opts);
+        crate::maintenance_mode::init_bypass(
+            &general.maintenance_mode_allow_ips,
+            &general.maintenance_mode_allow_paths,
+            &mut opts,
+        )
+        .unwrap();
+        crate::maintenance_mode::init_schedule(
+            &general.maintenance_mode_start,
+            &general.maintenance_mode_end,
+            &mut opts,
+        )
+        .unwrap();
+        #[cfg(feature = "archive-root")]
+        crate::fs::archive::init(&opts.root_dir.clone(), &mut opts).unwrap();
+        opts
     }
 
     /// Create a `RequestHandler` from a custom TOML config file (fixture).
     pub fn fixture_req_handler(req_handler_opts: RequestHandlerOpts) -> RequestHandler {
         RequestHandler {
-            opts: Arc::from(req_handler_opts),
+            opts: Arc::new(ArcSwap::from_pointee(req_handler_opts)),
+            access_log: Default::default(),
         }
     }
 }
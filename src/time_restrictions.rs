@@ -0,0 +1,196 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that restricts the access to request paths out of allowed local time windows.
+//!
+//! The access is allowed if any of the entries matching the request allows it at the
+//! current local time, otherwise the first matching entry either redirects the request
+//! or forbids the access.
+//!
+
+use chrono::{Datelike, Local, NaiveDateTime, NaiveTime, Weekday};
+use hyper::{Body, Request, Response, StatusCode, header::LOCATION};
+
+use crate::{
+    Context, Error, Result, error_page, handler::RequestHandlerOpts, redirects::request_host,
+    settings::TimeRestrictions,
+};
+
+/// Format of the local times of the allowed windows.
+const TIME_FORMAT: &str = "%H:%M";
+
+/// Parses a local time of an allowed window. E.g. `08:30`
+pub(crate) fn parse_time(time: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(time.trim(), TIME_FORMAT)
+        .with_context(|| format!("invalid time restrictions time \"{time}\", expected HH:MM"))
+}
+
+/// Parses the week days of an allowed window. E.g. `mon` or `monday`
+pub(crate) fn parse_days(days: &[String]) -> Result<Vec<Weekday>> {
+    days.iter()
+        .map(|day| {
+            day.trim()
+                .parse::<Weekday>()
+                .map_err(|_| anyhow!("invalid time restrictions day \"{day}\""))
+        })
+        .collect()
+}
+
+/// Checks if the entry allows the access at the given local date and time.
+fn is_allowed(entry: &TimeRestrictions, now: NaiveDateTime) -> bool {
+    let time = now.time();
+    let day = now.weekday();
+    let starts_on = |day: Weekday| entry.days.is_empty() || entry.days.contains(&day);
+
+    if entry.start == entry.end {
+        // The window lasts the whole day
+        starts_on(day)
+    } else if entry.start < entry.end {
+        starts_on(day) && time >= entry.start && time < entry.end
+    } else {
+        // The window ends the next day
+        (starts_on(day) && time >= entry.start) || (starts_on(day.pred()) && time < entry.end)
+    }
+}
+
+/// Restricts the access to the request if out of the allowed time windows.
+pub(crate) fn pre_process<T>(
+    opts: &RequestHandlerOpts,
+    req: &Request<T>,
+) -> Option<Result<Response<Body>, Error>> {
+    check(opts, req, Local::now().naive_local())
+}
+
+/// Restricts the access to the request at the given local date and time.
+fn check<T>(
+    opts: &RequestHandlerOpts,
+    req: &Request<T>,
+    now: NaiveDateTime,
+) -> Option<Result<Response<Body>, Error>> {
+    let entries = opts.advanced_opts.as_ref()?.time_restrictions.as_deref()?;
+    let uri_host = request_host(req);
+    let uri_path = req.uri().path();
+    let mut matching = entries.iter().filter(|entry| {
+        entry.host.as_ref().is_none_or(|host| *host == uri_host) && entry.source.is_match(uri_path)
+    });
+
+    let first = matching.next()?;
+    if is_allowed(first, now) || matching.any(|entry| is_allowed(entry, now)) {
+        return None;
+    }
+
+    tracing::debug!(
+        "time restrictions: access to {} not allowed at {}",
+        uri_path,
+        now.format("%a %H:%M")
+    );
+    match &first.redirect {
+        Some(location) => {
+            let mut resp = Response::new(Body::empty());
+            resp.headers_mut().insert(LOCATION, location.clone());
+            *resp.status_mut() = StatusCode::FOUND;
+            Some(Ok(resp))
+        }
+        None => Some(error_page::error_response(
+            req.uri(),
+            req.method(),
+            &StatusCode::FORBIDDEN,
+            &opts.error_pages,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, parse_days, parse_time};
+    use crate::{
+        handler::RequestHandlerOpts,
+        settings::{Advanced, TimeRestrictions},
+    };
+    use chrono::{NaiveDate, NaiveDateTime, Weekday};
+    use globset::Glob;
+    use hyper::{Body, Request, StatusCode};
+
+    fn make_entry(source: &str, start: &str, end: &str, days: &[&str]) -> TimeRestrictions {
+        let days = days.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+        TimeRestrictions {
+            host: None,
+            source: Glob::new(source).unwrap().compile_matcher(),
+            start: parse_time(start).unwrap(),
+            end: parse_time(end).unwrap(),
+            days: parse_days(&days).unwrap(),
+            redirect: None,
+        }
+    }
+
+    fn make_opts(entries: Vec<TimeRestrictions>) -> RequestHandlerOpts {
+        RequestHandlerOpts {
+            advanced_opts: Some(Advanced {
+                time_restrictions: Some(entries),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Gets the response status at the given time of the first week of 2024 (Monday 1st).
+    fn status(opts: &RequestHandlerOpts, uri: &str, day: u32, time: &str) -> Option<StatusCode> {
+        let req = Request::get(uri).body(Body::empty()).unwrap();
+        let now = NaiveDateTime::new(
+            NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            parse_time(time).unwrap(),
+        );
+        check(opts, &req, now).map(|resp| resp.unwrap().status())
+    }
+
+    #[test]
+    fn test_office_hours() {
+        let opts = make_opts(vec![make_entry(
+            "/intranet/**",
+            "08:00",
+            "18:00",
+            &["mon", "tue", "wed", "thu", "friday"],
+        )]);
+        let uri = "/intranet/report.pdf";
+
+        assert_eq!(status(&opts, uri, 1, "08:00"), None);
+        assert_eq!(status(&opts, uri, 5, "17:59"), None);
+        assert_eq!(status(&opts, uri, 1, "07:59"), Some(StatusCode::FORBIDDEN));
+        assert_eq!(status(&opts, uri, 1, "18:00"), Some(StatusCode::FORBIDDEN));
+        assert_eq!(status(&opts, uri, 6, "12:00"), Some(StatusCode::FORBIDDEN));
+
+        // Other paths are not restricted
+        assert_eq!(status(&opts, "/index.html", 6, "12:00"), None);
+    }
+
+    #[test]
+    fn test_overnight_windows() {
+        let mut entry = make_entry("/backups/**", "22:00", "06:00", &["fri"]);
+        entry.redirect = Some("/closed.html".parse().unwrap());
+        let opts = make_opts(vec![
+            entry,
+            make_entry("/backups/**", "12:00", "12:00", &["sun"]),
+        ]);
+        let uri = "/backups/db.tar.gz";
+
+        assert_eq!(status(&opts, uri, 5, "23:00"), None);
+        assert_eq!(status(&opts, uri, 6, "05:59"), None);
+        assert_eq!(status(&opts, uri, 7, "10:00"), None);
+        assert_eq!(status(&opts, uri, 6, "06:00"), Some(StatusCode::FOUND));
+        assert_eq!(status(&opts, uri, 5, "05:00"), Some(StatusCode::FOUND));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(parse_time("8:30").is_ok());
+        assert!(parse_time("24:00").is_err());
+        assert!(parse_time("8am").is_err());
+        assert_eq!(
+            parse_days(&["Mon".to_owned(), "sunday".to_owned()]).unwrap(),
+            vec![Weekday::Mon, Weekday::Sun]
+        );
+        assert!(parse_days(&["weekend".to_owned()]).is_err());
+    }
+}
@@ -0,0 +1,475 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module to close connections that stay idle or take too long writing a response.
+//!
+//! The timeouts are enforced on the connection I/O, so they also apply to clients
+//! that stop reading a response (E.g. Slowloris-style clients).
+//!
+
+use hyper::header::CONTENT_TYPE;
+use hyper::server::{Builder, accept::Accept};
+use hyper::{Body, Response, StatusCode};
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::time::{Instant, Sleep, sleep};
+
+use crate::Error;
+use crate::transport::Transport;
+use crate::zero_copy::ZeroCopySender;
+
+/// Connection timeouts.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timeouts {
+    /// Maximum time to read the headers of an HTTP/1 request.
+    pub read_header: Option<Duration>,
+    /// Maximum time without any read or write activity on a connection between requests.
+    pub idle: Option<Duration>,
+    /// Maximum time to write a response data since its first byte until it's flushed.
+    pub write: Option<Duration>,
+}
+
+impl Timeouts {
+    /// Creates the connection timeouts from seconds, where zero disables a timeout.
+    pub fn from_secs(read_header: u64, idle: u64, write: u64) -> Self {
+        let secs = |v| (v > 0).then(|| Duration::from_secs(v));
+        Self {
+            read_header: secs(read_header),
+            idle: secs(idle),
+            write: secs(write),
+        }
+    }
+
+    /// Applies the request headers read timeout to a server builder.
+    pub fn apply<I>(&self, builder: Builder<I>) -> Builder<I> {
+        match self.read_header {
+            Some(timeout) => builder.http1_header_read_timeout(timeout),
+            None => builder,
+        }
+    }
+}
+
+/// Type to intercept incoming connections and apply the connection timeouts.
+pub struct TimeoutAcceptor<A> {
+    incoming: A,
+    timeouts: Timeouts,
+}
+
+impl<A> TimeoutAcceptor<A> {
+    /// Creates a new connection timeouts interceptor.
+    pub fn new(incoming: A, timeouts: Timeouts) -> Self {
+        Self { incoming, timeouts }
+    }
+}
+
+impl<A: Accept + Unpin> Accept for TimeoutAcceptor<A> {
+    type Conn = TimeoutStream<A::Conn>;
+    type Error = A::Error;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        let pin = self.get_mut();
+        Pin::new(&mut pin.incoming)
+            .poll_accept(cx)
+            .map_ok(|conn| TimeoutStream::new(conn, pin.timeouts))
+    }
+}
+
+/// Activity of the requests of a connection, shared by its stream and its request service,
+/// so the idle timeout only applies between requests.
+#[derive(Clone, Debug, Default)]
+pub struct ConnectionActivity(Arc<ActivityState>);
+
+#[derive(Debug, Default)]
+struct ActivityState {
+    /// Number of requests being handled.
+    requests: AtomicUsize,
+    /// Whether the connection was upgraded or streams events, which can be quiet for long.
+    upgraded: AtomicBool,
+}
+
+impl ConnectionActivity {
+    /// Counts a request as being handled until the returned guard gets dropped.
+    pub fn start_request(&self) -> RequestActivity {
+        self.0.requests.fetch_add(1, Ordering::Relaxed);
+        RequestActivity(self.clone())
+    }
+
+    /// Exempts the connection from the idle timeout for the rest of its lifetime.
+    pub fn upgrade(&self) {
+        self.0.upgraded.store(true, Ordering::Relaxed);
+    }
+
+    /// Checks if the connection is between requests, so it can be idle.
+    fn is_idle(&self) -> bool {
+        self.0.requests.load(Ordering::Relaxed) == 0 && !self.0.upgraded.load(Ordering::Relaxed)
+    }
+}
+
+/// It keeps a request counted as being handled until it gets dropped.
+pub struct RequestActivity(ConnectionActivity);
+
+impl Drop for RequestActivity {
+    fn drop(&mut self) {
+        (self.0).0.requests.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Counts a request as being handled on its connection until its response is resolved,
+/// exempting the connection from the idle timeout once upgraded (E.g. to WebSocket)
+/// or streaming Server-Sent Events.
+pub(crate) async fn post_process<F>(
+    activity: Option<ConnectionActivity>,
+    handle: F,
+) -> Result<Response<Body>, Error>
+where
+    F: Future<Output = Result<Response<Body>, Error>>,
+{
+    let Some(activity) = activity else {
+        return handle.await;
+    };
+    let _request = activity.start_request();
+    let resp = handle.await?;
+    let is_event_stream = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"text/event-stream"));
+    if resp.status() == StatusCode::SWITCHING_PROTOCOLS || is_event_stream {
+        activity.upgrade();
+    }
+    Ok(resp)
+}
+
+/// Connection stream which fails with a `TimedOut` error once a timeout elapses.
+pub struct TimeoutStream<T> {
+    inner: T,
+    timeouts: Timeouts,
+    activity: ConnectionActivity,
+    idle: Pin<Box<Sleep>>,
+    write: Pin<Box<Sleep>>,
+    /// Whether a response is being written, that is, data was written since the last
+    /// read or flush.
+    writing: bool,
+}
+
+impl<T> TimeoutStream<T> {
+    /// Creates a new connection stream with timeouts.
+    pub fn new(inner: T, timeouts: Timeouts) -> Self {
+        let far_future = || Box::pin(sleep(Duration::from_secs(86400 * 365)));
+        let mut stream = Self {
+            inner,
+            timeouts,
+            activity: ConnectionActivity::default(),
+            idle: far_future(),
+            write: far_future(),
+            writing: false,
+        };
+        stream.reset_idle();
+        stream
+    }
+
+    fn reset_idle(&mut self) {
+        if let Some(idle) = self.timeouts.idle {
+            self.idle.as_mut().reset(Instant::now() + idle);
+        }
+    }
+
+    /// Checks the timeouts while the connection is waiting for I/O.
+    fn poll_timeouts(&mut self, cx: &mut Context<'_>) -> Poll<io::Error> {
+        if self.timeouts.idle.is_some() {
+            // The idle time only counts between requests
+            if !self.activity.is_idle() {
+                self.reset_idle();
+            } else if self.idle.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "connection idle timeout elapsed",
+                ));
+            }
+        }
+        if self.writing && self.timeouts.write.is_some() && self.write.as_mut().poll(cx).is_ready()
+        {
+            return Poll::Ready(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "response write timeout elapsed",
+            ));
+        }
+        Poll::Pending
+    }
+
+    /// Starts the write timeout if a response is starting to be written.
+    fn start_writing(&mut self) {
+        if !self.writing {
+            self.writing = true;
+            if let Some(write) = self.timeouts.write {
+                self.write.as_mut().reset(Instant::now() + write);
+            }
+        }
+    }
+
+    /// Fails if the write timeout of the current response has elapsed.
+    fn check_write_deadline(&self) -> io::Result<()> {
+        if self.writing && self.timeouts.write.is_some() && Instant::now() >= self.write.deadline()
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "response write timeout elapsed",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for TimeoutStream<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let pin = self.get_mut();
+        let filled = buf.filled().len();
+        match Pin::new(&mut pin.inner).poll_read(cx, buf) {
+            Poll::Ready(result) => {
+                if buf.filled().len() > filled {
+                    pin.writing = false;
+                    pin.reset_idle();
+                }
+                Poll::Ready(result)
+            }
+            Poll::Pending => pin.poll_timeouts(cx).map(Err),
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for TimeoutStream<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let pin = self.get_mut();
+        pin.start_writing();
+        pin.check_write_deadline()?;
+        match Pin::new(&mut pin.inner).poll_write(cx, buf) {
+            Poll::Ready(result) => {
+                pin.reset_idle();
+                Poll::Ready(result)
+            }
+            Poll::Pending => pin.poll_timeouts(cx).map(Err),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let pin = self.get_mut();
+        pin.check_write_deadline()?;
+        match Pin::new(&mut pin.inner).poll_flush(cx) {
+            Poll::Ready(result) => {
+                // The written data was sent, so the write timeout of the next one starts over
+                if result.is_ok() {
+                    pin.writing = false;
+                }
+                Poll::Ready(result)
+            }
+            Poll::Pending => pin.poll_timeouts(cx).map(Err),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+impl<T: Transport + Unpin> Transport for TimeoutStream<T> {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        self.inner.remote_addr()
+    }
+
+    fn zero_copy(&self) -> Option<ZeroCopySender> {
+        self.inner.zero_copy()
+    }
+
+    fn activity(&self) -> Option<ConnectionActivity> {
+        Some(self.activity.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConnectionActivity, TimeoutStream, Timeouts, post_process};
+    use hyper::{Body, Response, StatusCode};
+    use std::io::ErrorKind;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, duplex};
+
+    fn timeouts(idle: u64, write: u64) -> Timeouts {
+        let millis = |v| (v > 0).then(|| Duration::from_millis(v));
+        Timeouts {
+            read_header: None,
+            idle: millis(idle),
+            write: millis(write),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout() {
+        let (client, server) = duplex(64);
+        let mut stream = TimeoutStream::new(server, timeouts(50, 0));
+        let mut buf = [0; 8];
+
+        let err = stream.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        drop(client);
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout_reset_by_activity() {
+        let (mut client, server) = duplex(64);
+        let mut stream = TimeoutStream::new(server, timeouts(100, 0));
+        let mut buf = [0; 8];
+
+        for _ in 0..3 {
+            tokio::time::sleep(Duration::from_millis(60)).await;
+            client.write_all(b"ping").await.unwrap();
+            assert_eq!(stream.read(&mut buf).await.unwrap(), 4);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_timeout() {
+        let (mut client, server) = duplex(16);
+        let mut stream = TimeoutStream::new(server, timeouts(0, 50));
+
+        // The client sends a request but never reads the response
+        client.write_all(b"GET").await.unwrap();
+        let mut buf = [0; 8];
+        assert_eq!(stream.read(&mut buf).await.unwrap(), 3);
+
+        let err = stream.write_all(&[0; 1024]).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn test_write_timeout_ends_on_flush() {
+        let (mut client, server) = duplex(64);
+        let mut stream = TimeoutStream::new(server, timeouts(0, 50));
+
+        // The response is flushed, so the idle keep-alive connection stays open
+        client.write_all(b"GET").await.unwrap();
+        let mut buf = [0; 8];
+        assert_eq!(stream.read(&mut buf).await.unwrap(), 3);
+        stream.write_all(b"200").await.unwrap();
+        stream.flush().await.unwrap();
+        assert_eq!(client.read(&mut buf).await.unwrap(), 3);
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(120)).await;
+            client.write_all(b"GET").await.unwrap();
+            client
+        });
+        assert_eq!(stream.read(&mut buf).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout_keep_alive() {
+        let (mut client, server) = duplex(64);
+        let mut stream = TimeoutStream::new(server, timeouts(50, 0));
+        let activity = stream.activity.clone();
+
+        client.write_all(b"GET").await.unwrap();
+        let mut buf = [0; 8];
+        let request = activity.start_request();
+        assert_eq!(stream.read(&mut buf).await.unwrap(), 3);
+        drop(request);
+        stream.write_all(b"200").await.unwrap();
+        stream.flush().await.unwrap();
+
+        // The keep-alive connection is closed once idle between requests
+        let err = stream.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout_slow_handler() {
+        let (mut client, server) = duplex(64);
+        let mut stream = TimeoutStream::new(server, timeouts(50, 0));
+        let activity = stream.activity.clone();
+
+        // The connection is read while the request is handled for longer than the idle timeout
+        let request = activity.start_request();
+        let handler = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(120)).await;
+            drop(request);
+            client.write_all(b"GET").await.unwrap();
+            client
+        });
+        let mut buf = [0; 8];
+        assert_eq!(stream.read(&mut buf).await.unwrap(), 3);
+        let _client = handler.await.unwrap();
+
+        // Then the idle timeout applies again between requests
+        let err = stream.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout_upgraded() {
+        let (mut client, server) = duplex(64);
+        let mut stream = TimeoutStream::new(server, timeouts(50, 0));
+        stream.activity.clone().upgrade();
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(120)).await;
+            client.write_all(b"ping").await.unwrap();
+            client
+        });
+        let mut buf = [0; 8];
+        assert_eq!(stream.read(&mut buf).await.unwrap(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_post_process_upgrade() {
+        let activity = ConnectionActivity::default();
+        let resp = post_process(Some(activity.clone()), async {
+            assert!(!activity.is_idle());
+            Ok(Response::new(Body::empty()))
+        })
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(activity.is_idle());
+
+        let resp = post_process(Some(activity.clone()), async {
+            let mut resp = Response::new(Body::empty());
+            *resp.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+            Ok(resp)
+        })
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::SWITCHING_PROTOCOLS);
+        assert!(!activity.is_idle());
+    }
+
+    #[tokio::test]
+    async fn test_timeouts_disabled() {
+        let (mut client, server) = duplex(64);
+        let mut stream = TimeoutStream::new(server, Timeouts::from_secs(0, 0, 0));
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            client.write_all(b"ping").await.unwrap();
+        });
+        let mut buf = [0; 8];
+        assert_eq!(stream.read(&mut buf).await.unwrap(), 4);
+    }
+}
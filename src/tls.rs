@@ -186,28 +186,28 @@ impl Read for LazyFile {
     }
 }
 
-impl Transport for TlsStream {
+impl<T: Transport + Unpin> Transport for TlsStream<T> {
     fn remote_addr(&self) -> Option<SocketAddr> {
-        Some(self.remote_addr)
+        self.remote_addr
     }
 }
 
-enum State {
-    Handshaking(tokio_rustls::Accept<AddrStream>),
-    Streaming(tokio_rustls::server::TlsStream<AddrStream>),
+enum State<T> {
+    Handshaking(tokio_rustls::Accept<T>),
+    Streaming(tokio_rustls::server::TlsStream<T>),
 }
 
 /// TlsStream implements AsyncRead/AsyncWrite handshaking tokio_rustls::Accept first.
 ///
 /// tokio_rustls::server::TlsStream doesn't expose constructor methods,
 /// so we have to TlsAcceptor::accept and handshake to have access to it.
-pub struct TlsStream {
-    state: State,
-    remote_addr: SocketAddr,
+pub struct TlsStream<T = AddrStream> {
+    state: State<T>,
+    remote_addr: Option<SocketAddr>,
 }
 
-impl TlsStream {
-    fn new(stream: AddrStream, config: Arc<ServerConfig>) -> TlsStream {
+impl<T: Transport + Unpin> TlsStream<T> {
+    fn new(stream: T, config: Arc<ServerConfig>) -> TlsStream<T> {
         let remote_addr = stream.remote_addr();
         let accept = tokio_rustls::TlsAcceptor::from(config).accept(stream);
         TlsStream {
@@ -217,7 +217,7 @@ impl TlsStream {
     }
 }
 
-impl AsyncRead for TlsStream {
+impl<T: AsyncRead + AsyncWrite + Unpin> AsyncRead for TlsStream<T> {
     fn poll_read(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
@@ -238,7 +238,7 @@ impl AsyncRead for TlsStream {
     }
 }
 
-impl AsyncWrite for TlsStream {
+impl<T: AsyncRead + AsyncWrite + Unpin> AsyncWrite for TlsStream<T> {
     fn poll_write(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
@@ -274,14 +274,14 @@ impl AsyncWrite for TlsStream {
 }
 
 /// Type to intercept Tls incoming connections.
-pub struct TlsAcceptor {
+pub struct TlsAcceptor<A = AddrIncoming> {
     config: Arc<ServerConfig>,
-    incoming: AddrIncoming,
+    incoming: A,
 }
 
-impl TlsAcceptor {
+impl<A> TlsAcceptor<A> {
     /// Creates a new Tls interceptor.
-    pub fn new(config: ServerConfig, incoming: AddrIncoming) -> TlsAcceptor {
+    pub fn new(config: ServerConfig, incoming: A) -> TlsAcceptor<A> {
         TlsAcceptor {
             config: Arc::new(config),
             incoming,
@@ -289,8 +289,12 @@ impl TlsAcceptor {
     }
 }
 
-impl Accept for TlsAcceptor {
-    type Conn = TlsStream;
+impl<A> Accept for TlsAcceptor<A>
+where
+    A: Accept<Error = io::Error> + Unpin,
+    A::Conn: Transport + Unpin,
+{
+    type Conn = TlsStream<A::Conn>;
     type Error = io::Error;
 
     fn poll_accept(
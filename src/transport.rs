@@ -16,10 +16,23 @@ use std::task::{Context, Poll};
 use hyper::server::conn::AddrStream;
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
+use crate::timeouts::ConnectionActivity;
+use crate::zero_copy::ZeroCopySender;
+
 /// Transport trait that supports the remote (peer) address.
 pub trait Transport: AsyncRead + AsyncWrite {
     /// Returns the remote (peer) address of this connection.
     fn remote_addr(&self) -> Option<SocketAddr>;
+
+    /// Returns the sender of the files sent with zero-copy on this connection if it supports it.
+    fn zero_copy(&self) -> Option<ZeroCopySender> {
+        None
+    }
+
+    /// Returns the activity of the requests of this connection if it has connection timeouts.
+    fn activity(&self) -> Option<ConnectionActivity> {
+        None
+    }
 }
 
 impl Transport for AddrStream {
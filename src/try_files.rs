@@ -0,0 +1,177 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that allows to resolve request paths by trying a list of candidate paths in order.
+//!
+//! It works like the `try_files` directive of other web servers, so for example `/about`
+//! can be served from an `about.html` file by trying the `$uri` and `$uri.html` candidates.
+//!
+
+use hyper::{Body, Request, Response, Uri, http::uri::PathAndQuery};
+use std::path::Path;
+
+use crate::{
+    Error,
+    fs::path::{PathExt, sanitize_path},
+    handler::RequestHandlerOpts,
+    redirects::handle_error,
+    settings::TryFiles,
+};
+
+/// Placeholder replaced by the request URI path in the candidate paths.
+pub(crate) const URI_PLACEHOLDER: &str = "$uri";
+
+/// Rewrites the request URI to the first existing candidate path of the first
+/// try-files entry matching the request, or to its fallback path if none exists.
+pub(crate) fn pre_process<T>(
+    opts: &RequestHandlerOpts,
+    req: &mut Request<T>,
+    base_path: &Path,
+) -> Option<Result<Response<Body>, Error>> {
+    let try_files = opts.advanced_opts.as_ref()?.try_files.as_deref()?;
+    let uri_path = req.uri().path();
+    let entry = try_files
+        .iter()
+        .find(|entry| entry.source.is_match(uri_path))?;
+
+    let dest = match resolve(entry, uri_path, base_path, opts) {
+        Some(dest) => dest,
+        None => {
+            tracing::debug!("try files: no candidate found for {}", uri_path);
+            return None;
+        }
+    };
+    tracing::debug!("try files: {} resolved to {}", uri_path, dest);
+
+    if let Err(err) = rewrite_path(req, &dest) {
+        return handle_error(
+            err.context("invalid try files path from current uri"),
+            opts,
+            req,
+        );
+    }
+    None
+}
+
+/// Gets the first candidate path of an entry which exists in the root directory,
+/// otherwise its fallback path if any.
+fn resolve(
+    entry: &TryFiles,
+    uri_path: &str,
+    base_path: &Path,
+    opts: &RequestHandlerOpts,
+) -> Option<String> {
+    for file in &entry.files {
+        let candidate = file.replace(URI_PLACEHOLDER, uri_path);
+        let Ok(path) = sanitize_path(base_path, &candidate) else {
+            continue;
+        };
+        if opts.ignore_hidden_files && path.is_hidden() {
+            continue;
+        }
+        if opts.ignore_patterns.is_ignored_in(base_path, &path) {
+            continue;
+        }
+        if !opts
+            .follow_symlinks
+            .or_disabled(opts.disable_symlinks)
+            .allows(base_path, &path)
+        {
+            continue;
+        }
+        // A candidate ending with a slash stands for a directory
+        let found = if candidate.ends_with('/') {
+            path.is_dir()
+        } else {
+            path.is_file()
+        };
+        if found {
+            return Some(candidate);
+        }
+    }
+    entry
+        .fallback
+        .as_ref()
+        .map(|fallback| fallback.replace(URI_PLACEHOLDER, uri_path))
+}
+
+/// Replaces the request URI path, keeping its query string.
+fn rewrite_path<T>(req: &mut Request<T>, path: &str) -> Result<(), Error> {
+    let path_and_query = match req.uri().query() {
+        Some(query) => [path, "?", query].concat(),
+        None => path.to_owned(),
+    };
+    let mut parts = req.uri().clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse::<PathAndQuery>()?);
+    *req.uri_mut() = Uri::from_parts(parts)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pre_process;
+    use crate::{
+        handler::RequestHandlerOpts,
+        settings::{Advanced, TryFiles},
+    };
+    use globset::Glob;
+    use hyper::{Body, Request};
+    use std::path::Path;
+
+    fn make_opts(try_files: Vec<TryFiles>) -> RequestHandlerOpts {
+        RequestHandlerOpts {
+            advanced_opts: Some(Advanced {
+                try_files: Some(try_files),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn make_entry(source: &str, files: &[&str], fallback: Option<&str>) -> TryFiles {
+        TryFiles {
+            source: Glob::new(source).unwrap().compile_matcher(),
+            files: files.iter().map(|f| f.to_string()).collect(),
+            fallback: fallback.map(|f| f.to_owned()),
+        }
+    }
+
+    fn resolve(opts: &RequestHandlerOpts, uri: &str) -> String {
+        let mut req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        assert!(pre_process(opts, &mut req, Path::new("tests/fixtures/public")).is_none());
+        req.uri().to_string()
+    }
+
+    #[test]
+    fn test_try_files() {
+        let opts = make_opts(vec![
+            make_entry("/assets/**", &["$uri", "$uri/", "$uri.html"], None),
+            make_entry("/**", &["$uri", "$uri.html"], Some("/404.html")),
+        ]);
+
+        assert_eq!(resolve(&opts, "/assets/main.js"), "/assets/main.js");
+        assert_eq!(resolve(&opts, "/assets/index"), "/assets/index.html");
+        assert_eq!(resolve(&opts, "/50x?a=1"), "/50x.html?a=1");
+        assert_eq!(resolve(&opts, "/unknown"), "/404.html");
+
+        // The first matching entry is used even if no candidate exists
+        assert_eq!(resolve(&opts, "/assets/unknown"), "/assets/unknown");
+    }
+
+    #[test]
+    fn test_try_files_directories() {
+        let opts = make_opts(vec![make_entry("/**", &["$uri", "$uri/"], None)]);
+        assert_eq!(resolve(&opts, "/assets"), "/assets/");
+        assert_eq!(resolve(&opts, "/index.htm"), "/index.htm");
+    }
+
+    #[test]
+    fn test_try_files_hidden() {
+        let mut opts = make_opts(vec![make_entry("/**", &["/.dotfile", "$uri"], None)]);
+        assert_eq!(resolve(&opts, "/50x.html"), "/.dotfile");
+        opts.ignore_hidden_files = true;
+        assert_eq!(resolve(&opts, "/50x.html"), "/50x.html");
+    }
+}
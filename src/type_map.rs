@@ -0,0 +1,229 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that pre-generates a map of the file types found under the root directory.
+//!
+//! The content type, compressibility and `Cache-Control` max-age of every file extension
+//! are resolved once on start up (and on every configuration reload) instead of per request.
+//! The extensions unknown to the MIME types database get a `text/plain` content type
+//! when their files contain UTF-8 text.
+//!
+
+use headers::HeaderValue;
+use mime_guess::{Mime, mime};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::control_headers;
+use crate::fs::walk::{self, CancelToken, Visit};
+use crate::handler::RequestHandlerOpts;
+
+/// Number of threads used to walk the root directory.
+const WALK_CONCURRENCY: usize = 4;
+
+/// Number of bytes read to check if a file contains text.
+const SNIFF_LEN: usize = 1024;
+
+/// Pre-generated information of a file type.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TypeInfo {
+    /// MIME type of the files.
+    pub mime: Mime,
+    /// `Content-Type` header value of the MIME type.
+    pub content_type: HeaderValue,
+    /// Whether the content type can be auto-compressed.
+    pub compressible: bool,
+    /// `Cache-Control` max-age in seconds.
+    pub max_age: u64,
+}
+
+/// Map of the file types by their lowercase file extension.
+#[derive(Debug, Default)]
+pub struct TypeMap {
+    types: HashMap<String, Arc<TypeInfo>>,
+}
+
+impl TypeMap {
+    /// Returns the type information of a file path if its extension is known.
+    pub fn get(&self, path: &Path) -> Option<&Arc<TypeInfo>> {
+        let ext = path.extension()?.to_str()?;
+        self.types
+            .get(ext)
+            .or_else(|| self.types.get(&ext.to_ascii_lowercase()))
+    }
+
+    /// Returns the number of file types.
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    /// Checks if the map has no file types.
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+}
+
+/// Initializes the type map by analyzing the files under the root directory.
+pub(crate) fn init(enabled: bool, handler_opts: &mut RequestHandlerOpts) {
+    handler_opts.type_map = enabled.then(|| Arc::new(build(handler_opts)));
+    tracing::info!(
+        "type map: enabled={}, types={}",
+        enabled,
+        handler_opts.type_map.as_ref().map_or(0, |map| map.len())
+    );
+}
+
+/// Builds the type map from the files under the root directory.
+fn build(opts: &RequestHandlerOpts) -> TypeMap {
+    let mime_types = opts
+        .advanced_opts
+        .as_ref()
+        .and_then(|advanced| advanced.mime_types.as_ref());
+    let found = Mutex::new(HashMap::new());
+
+    walk::walk(
+        &opts.root_dir,
+        WALK_CONCURRENCY,
+        &CancelToken::default(),
+        |entry, meta| {
+            let name = entry.file_name();
+            if opts.ignore_hidden_files && name.as_encoded_bytes().starts_with(b".") {
+                return Visit::Skip;
+            }
+            if !meta.is_file() {
+                return Visit::Continue;
+            }
+            let path = entry.path();
+            let Some(ext) = path.extension().and_then(|ext| ext.to_str()) else {
+                return Visit::Continue;
+            };
+            let ext = ext.to_ascii_lowercase();
+            if found.lock().is_ok_and(|found| found.contains_key(&ext)) {
+                return Visit::Continue;
+            }
+
+            let mime = mime_types
+                .and_then(|mime_types| mime_types.get(&ext).cloned())
+                .or_else(|| mime_guess::from_ext(&ext).first())
+                .unwrap_or_else(|| sniff(&path));
+            let info = type_info(opts, &ext, mime);
+            if let Ok(mut found) = found.lock() {
+                found.entry(ext).or_insert_with(|| Arc::new(info));
+            }
+            Visit::Continue
+        },
+    );
+
+    TypeMap {
+        types: found.into_inner().unwrap_or_default(),
+    }
+}
+
+/// Resolves the type information of a file extension and its MIME type.
+fn type_info(opts: &RequestHandlerOpts, ext: &str, mime: Mime) -> TypeInfo {
+    #[cfg(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    ))]
+    let compressible = crate::compression::is_compressible_mime(opts, &mime);
+    #[cfg(not(any(
+        feature = "compression",
+        feature = "compression-gzip",
+        feature = "compression-brotli",
+        feature = "compression-zstd",
+        feature = "compression-deflate"
+    )))]
+    let compressible = {
+        let _ = opts;
+        false
+    };
+
+    TypeInfo {
+        content_type: HeaderValue::from_str(mime.as_ref())
+            .unwrap_or_else(|_| HeaderValue::from_static("application/octet-stream")),
+        mime,
+        compressible,
+        max_age: control_headers::max_age(ext),
+    }
+}
+
+/// Guesses the MIME type of a file of an unknown extension from its first bytes.
+fn sniff(path: &Path) -> Mime {
+    let mut buf = Vec::with_capacity(SNIFF_LEN);
+    let is_text = File::open(path)
+        .and_then(|file| file.take(SNIFF_LEN as u64).read_to_end(&mut buf))
+        .is_ok_and(|n| n > 0 && is_text(&buf));
+    if is_text {
+        mime::TEXT_PLAIN_UTF_8
+    } else {
+        mime::APPLICATION_OCTET_STREAM
+    }
+}
+
+/// Checks if the bytes are UTF-8 text, which may be truncated in the middle of a character.
+fn is_text(buf: &[u8]) -> bool {
+    !buf.contains(&0)
+        && match std::str::from_utf8(buf) {
+            Ok(_) => true,
+            Err(err) => err.error_len().is_none(),
+        }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build, is_text};
+    use crate::handler::RequestHandlerOpts;
+    use std::path::Path;
+
+    #[test]
+    fn test_is_text() {
+        assert!(is_text(b"key = value\n"));
+        assert!(is_text("olá".as_bytes()));
+        // Truncated in the middle of a character
+        assert!(is_text(&"olá".as_bytes()[..3]));
+        assert!(!is_text(b"\x00\x01\x02"));
+        assert!(!is_text(b"\xff\xfe"));
+    }
+
+    #[test]
+    fn test_build() {
+        let dir = std::env::temp_dir().join(format!("sws-type-map-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("assets")).unwrap();
+        std::fs::write(dir.join("assets/main.JS"), "alert(1)").unwrap();
+        std::fs::write(dir.join("notes.conf2"), "key = value").unwrap();
+        std::fs::write(dir.join("blob.bin2"), [0u8, 1, 2]).unwrap();
+        std::fs::write(dir.join(".hidden.txt"), "hidden").unwrap();
+
+        let opts = RequestHandlerOpts {
+            root_dir: dir.clone(),
+            ignore_hidden_files: true,
+            ..Default::default()
+        };
+        let map = build(&opts);
+        assert_eq!(map.len(), 3);
+
+        let js = map.get(Path::new("/app/main.js")).unwrap();
+        assert_eq!(js.content_type, "text/javascript");
+        assert_eq!(js.max_age, 31536000);
+
+        let conf = map.get(Path::new("x.CONF2")).unwrap();
+        assert_eq!(conf.content_type, "text/plain; charset=utf-8");
+        assert_eq!(conf.max_age, 86400);
+
+        let bin = map.get(Path::new("x.bin2")).unwrap();
+        assert_eq!(bin.content_type, "application/octet-stream");
+        assert!(!bin.compressible);
+
+        assert!(map.get(Path::new("x.txt")).is_none());
+        assert!(map.get(Path::new("README")).is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
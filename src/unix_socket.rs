@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module providing the Unix domain socket support of the listeners and the control API.
+//!
+
+use hyper::server::accept::{self, Accept};
+use std::io;
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::transport::LiftIo;
+use crate::{Context, Result};
+
+/// Unix domain socket file removed once dropped.
+pub(crate) struct SocketFile(PathBuf);
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.0).ok();
+    }
+}
+
+/// Binds a Unix domain socket, replacing the socket file left behind by a previous server process if any.
+pub(crate) fn bind(path: &Path) -> Result<(UnixListener, SocketFile)> {
+    if let Ok(meta) = std::fs::symlink_metadata(path) {
+        if !meta.file_type().is_socket() {
+            bail!(
+                "unix domain socket path exists and is not a socket: {}",
+                path.display()
+            );
+        }
+        std::fs::remove_file(path)
+            .with_context(|| format!("failed to remove the stale socket: {}", path.display()))?;
+    }
+
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("failed to bind to unix:{}", path.display()))?;
+    Ok((listener, SocketFile(path.to_owned())))
+}
+
+/// Converts an already bound Unix domain socket, E.g. inherited from the parent process.
+pub(crate) fn from_std(listener: std::os::unix::net::UnixListener) -> Result<UnixListener> {
+    listener
+        .set_nonblocking(true)
+        .with_context(|| "failed to set unix domain socket non-blocking mode")?;
+    UnixListener::from_std(listener).with_context(|| "failed to create tokio::net::UnixListener")
+}
+
+/// Sets the permissions and optionally the owner user and group IDs of a socket file.
+pub(crate) fn set_permissions(
+    path: &Path,
+    mode: Option<u32>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+) -> Result {
+    if let Some(mode) = mode {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+            .with_context(|| format!("failed to set the permissions of unix:{}", path.display()))?;
+    }
+    if uid.is_some() || gid.is_some() {
+        std::os::unix::fs::chown(path, uid, gid)
+            .with_context(|| format!("failed to set the owner of unix:{}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Accepts the connections of a Unix domain socket, which have no remote address.
+pub(crate) fn incoming(
+    listener: UnixListener,
+) -> impl Accept<Conn = LiftIo<UnixStream>, Error = io::Error> + Unpin + Send {
+    accept::poll_fn(move |cx| {
+        listener
+            .poll_accept(cx)
+            .map(|result| Some(result.map(|(stream, _)| LiftIo(stream))))
+    })
+}
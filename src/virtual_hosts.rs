@@ -17,6 +17,33 @@ pub(crate) fn get_real_root<'a, T>(
     req: &mut Request<T>,
     vhosts_opts: Option<&'a [VirtualHosts]>,
 ) -> Option<&'a PathBuf> {
+    let vhost = find(req, vhosts_opts)?;
+    tracing::info!(
+        "virtual host matched: vhost={} vhost_root={} method={} uri={}",
+        vhost.host,
+        vhost.root.display(),
+        req.method(),
+        req.uri(),
+    );
+    Some(&vhost.root)
+}
+
+/// It checks if the request targets one of the given served virtual hosts,
+/// `default` being the requests not matching any virtual host.
+pub(crate) fn is_served<T>(
+    req: &Request<T>,
+    vhosts_opts: Option<&[VirtualHosts]>,
+    served: &[String],
+) -> bool {
+    let host = find(req, vhosts_opts).map_or("default", |vhost| vhost.host.as_str());
+    served.iter().any(|served| served == host)
+}
+
+/// It returns the virtual host matching the "Host" header if any.
+pub(crate) fn find<'a, T>(
+    req: &Request<T>,
+    vhosts_opts: Option<&'a [VirtualHosts]>,
+) -> Option<&'a VirtualHosts> {
     let vhosts = vhosts_opts?;
     if vhosts.is_empty() {
         return None;
@@ -41,19 +68,7 @@ pub(crate) fn get_real_root<'a, T>(
             .unwrap_or(host_header)
     };
 
-    for vhost in vhosts {
-        if vhost.host == request_host_str {
-            tracing::info!(
-                "virtual host matched: vhost={} vhost_root={} method={} uri={}",
-                vhost.host,
-                vhost.root.display(),
-                req.method(),
-                req.uri(),
-            );
-            return Some(&vhost.root);
-        }
-    }
-    None
+    vhosts.iter().find(|vhost| vhost.host == request_host_str)
 }
 
 #[cfg(test)]
@@ -65,6 +80,7 @@ mod tests {
         VirtualHosts {
             host: host.to_string(),
             root: PathBuf::from(root),
+            directory_listing_locale: None,
         }
     }
 
@@ -145,4 +161,26 @@ mod tests {
         let result = get_real_root(&mut req, Some(&[]));
         assert_eq!(result, None);
     }
+
+    #[test]
+    fn test_is_served() {
+        let vhosts = [create_vhost("example.com", "/var/www/example")];
+        let served = ["example.com".to_owned()];
+        let served_default = ["default".to_owned()];
+
+        let req = Request::builder()
+            .header(HOST, "example.com:8443")
+            .body(Body::empty())
+            .unwrap();
+        assert!(is_served(&req, Some(&vhosts), &served));
+        assert!(!is_served(&req, Some(&vhosts), &served_default));
+
+        let req = Request::builder()
+            .header(HOST, "other.com")
+            .body(Body::empty())
+            .unwrap();
+        assert!(!is_served(&req, Some(&vhosts), &served));
+        assert!(is_served(&req, Some(&vhosts), &served_default));
+        assert!(is_served(&req, None, &served_default));
+    }
 }
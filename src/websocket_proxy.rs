@@ -0,0 +1,467 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module to proxy the WebSocket connections of some request path prefixes to upstream servers.
+//!
+//! The WebSocket handshake requests under a configured path prefix are forwarded to its
+//! upstream server and, once the upstream switches protocols, the client and upstream
+//! connections are tunneled in both directions until any of them gets closed.
+//! The tunnels count as open connections of the server, so they are subject to the
+//! connection limits and drained on shutdown, and their data sent to the clients is
+//! throttled by the bandwidth limits.
+//!
+
+use headers::{HeaderMap, HeaderValue};
+use hyper::client::HttpConnector;
+use hyper::http::uri::{Authority, Scheme};
+use hyper::{Body, Client, Method, Request, Response, StatusCode, Uri, Version, header};
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{
+    Context, Error, Result, bandwidth, cors, error_page, handler::RequestHandlerOpts,
+    ip_allowlist::IpAllowlist, service::ConnectionGuard, settings::WebSocketProxy,
+};
+
+/// Default maximum time to connect to an upstream server and get its handshake response.
+pub(crate) const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Upstream server of the proxied WebSocket connections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Upstream {
+    /// Host and port of the upstream server.
+    authority: Authority,
+    /// Path replacing the route path prefix without trailing slash if any,
+    /// otherwise the request paths are forwarded as they are.
+    path: Option<String>,
+}
+
+impl Upstream {
+    /// Gets the upstream request path of a request path under a route path prefix.
+    fn request_path(&self, prefix: &str, uri_path: &str) -> String {
+        let Some(path) = &self.path else {
+            return uri_path.to_owned();
+        };
+        let rest = if prefix == "/" {
+            uri_path
+        } else {
+            uri_path.get(prefix.len()..).unwrap_or_default()
+        };
+        match format!("{path}{rest}") {
+            path if path.is_empty() => "/".to_owned(),
+            path => path,
+        }
+    }
+}
+
+impl FromStr for Upstream {
+    type Err = Error;
+
+    /// Parses an upstream URL with the `http` or `ws` scheme (E.g. `http://127.0.0.1:8080/ws`).
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let uri: Uri = s
+            .parse()
+            .with_context(|| format!("invalid websocket proxy upstream url: {s}"))?;
+        match uri.scheme_str() {
+            Some("http" | "ws") => {}
+            Some("https" | "wss") => {
+                bail!("tls websocket proxy upstreams are not supported: {s}")
+            }
+            _ => bail!("websocket proxy upstream must use the `http` or `ws` scheme: {s}"),
+        }
+        let Some(authority) = uri.authority().cloned() else {
+            bail!("websocket proxy upstream has no host: {s}");
+        };
+        if uri.query().is_some() {
+            bail!("websocket proxy upstream can't have a query: {s}");
+        }
+        // The parsed URI always has a path, so check whether the URL has one
+        let has_path = s
+            .split_once("://")
+            .is_some_and(|(_, rest)| rest.contains('/'));
+        Ok(Self {
+            authority,
+            path: has_path.then(|| uri.path().trim_end_matches('/').to_owned()),
+        })
+    }
+}
+
+/// Finds the WebSocket proxy route with the longest path prefix matching a request path.
+fn route_for<'a>(opts: &'a RequestHandlerOpts, uri_path: &str) -> Option<&'a WebSocketProxy> {
+    opts.advanced_opts
+        .as_ref()?
+        .websocket_proxy
+        .as_deref()?
+        .iter()
+        .filter(|route| cors::is_path_prefix(&route.path, uri_path))
+        .max_by_key(|route| route.path.len())
+}
+
+/// Checks if a request asks to upgrade its connection to the WebSocket protocol.
+fn is_upgrade_request<T>(req: &Request<T>) -> bool {
+    let has_token = |name: header::HeaderName, token: &str| {
+        req.headers().get_all(name).iter().any(|value| {
+            value.to_str().is_ok_and(|value| {
+                value
+                    .split(',')
+                    .any(|item| item.trim().eq_ignore_ascii_case(token))
+            })
+        })
+    };
+    req.method() == Method::GET
+        && req.version() == Version::HTTP_11
+        && has_token(header::CONNECTION, "upgrade")
+        && has_token(header::UPGRADE, "websocket")
+}
+
+/// Checks if a header only applies to a single connection, so it can't be forwarded.
+fn is_hop_by_hop(name: &header::HeaderName) -> bool {
+    matches!(
+        name.as_str(),
+        "connection"
+            | "keep-alive"
+            | "proxy-connection"
+            | "proxy-authenticate"
+            | "proxy-authorization"
+            | "te"
+            | "trailer"
+            | "transfer-encoding"
+            | "upgrade"
+    )
+}
+
+/// Copies the end-to-end headers, skipping the hop-by-hop ones and the ones listed
+/// in the `Connection` header.
+fn end_to_end_headers(headers: &HeaderMap) -> HeaderMap {
+    let listed: Vec<String> = headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|name| name.trim().to_ascii_lowercase())
+        .collect();
+    let mut copied = HeaderMap::with_capacity(headers.len());
+    for (name, value) in headers {
+        if !is_hop_by_hop(name) && !listed.iter().any(|listed| listed == name.as_str()) {
+            copied.append(name, value.clone());
+        }
+    }
+    copied
+}
+
+/// Builds the handshake request forwarded to the upstream server.
+/// The `X-Forwarded-For` header of the request is only kept if it comes from a trusted proxy.
+fn upstream_request<T>(
+    req: &Request<T>,
+    route: &WebSocketProxy,
+    remote_addr: Option<SocketAddr>,
+    trusted_proxies: &IpAllowlist,
+) -> Result<Request<Body>> {
+    let path = route.upstream.request_path(&route.path, req.uri().path());
+    let path_and_query = match req.uri().query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path,
+    };
+    let uri = Uri::builder()
+        .scheme(Scheme::HTTP)
+        .authority(route.upstream.authority.clone())
+        .path_and_query(path_and_query)
+        .build()?;
+
+    let mut headers = end_to_end_headers(req.headers());
+    // The client sets the `Host` header of the upstream server
+    let host = headers.remove(header::HOST);
+    headers.insert(header::CONNECTION, HeaderValue::from_static("upgrade"));
+    headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+
+    let forwarded_for = headers.remove("x-forwarded-for");
+    if let Some(ip) = remote_addr.map(|addr| addr.ip().to_canonical()) {
+        let forwarded_for = match forwarded_for.filter(|_| trusted_proxies.contains(&ip)) {
+            Some(value) => format!("{}, {ip}", value.to_str().unwrap_or_default()),
+            None => ip.to_string(),
+        };
+        headers.insert("x-forwarded-for", HeaderValue::from_str(&forwarded_for)?);
+    }
+    if let Some(host) = host {
+        headers.insert("x-forwarded-host", host);
+    }
+    let scheme = req.extensions().get::<Scheme>().unwrap_or(&Scheme::HTTP);
+    headers.insert("x-forwarded-proto", HeaderValue::from_str(scheme.as_str())?);
+
+    let mut upstream_req = Request::builder()
+        .method(Method::GET)
+        .uri(uri)
+        .version(Version::HTTP_11)
+        .body(Body::empty())?;
+    *upstream_req.headers_mut() = headers;
+    Ok(upstream_req)
+}
+
+/// Proxies the WebSocket connections under the configured path prefixes to their upstream servers.
+pub(crate) async fn pre_process(
+    opts: &RequestHandlerOpts,
+    req: &mut Request<Body>,
+    remote_addr: Option<SocketAddr>,
+) -> Option<Result<Response<Body>, Error>> {
+    let route = route_for(opts, req.uri().path())?;
+
+    if !is_upgrade_request(req) {
+        let resp = error_page::error_response(
+            req.uri(),
+            req.method(),
+            &StatusCode::UPGRADE_REQUIRED,
+            &opts.error_pages,
+        );
+        return Some(resp.map(|mut resp| {
+            if req.version() <= Version::HTTP_11 {
+                resp.headers_mut()
+                    .insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+            }
+            resp
+        }));
+    }
+
+    let upstream_req = match upstream_request(req, route, remote_addr, &opts.trusted_proxies) {
+        Ok(upstream_req) => upstream_req,
+        Err(err) => return Some(Err(err)),
+    };
+    let upstream_uri = upstream_req.uri().clone();
+
+    let client: Client<HttpConnector> = Client::builder().build(HttpConnector::new());
+    let mut upstream_resp =
+        match tokio::time::timeout(route.timeout, client.request(upstream_req)).await {
+            Ok(Ok(resp)) => resp,
+            Ok(Err(err)) => {
+                tracing::error!("websocket proxy: unable to reach upstream {upstream_uri}: {err}");
+                return Some(error_page::error_response(
+                    req.uri(),
+                    req.method(),
+                    &StatusCode::BAD_GATEWAY,
+                    &opts.error_pages,
+                ));
+            }
+            Err(_) => {
+                tracing::error!("websocket proxy: upstream {upstream_uri} handshake timed out");
+                return Some(error_page::error_response(
+                    req.uri(),
+                    req.method(),
+                    &StatusCode::GATEWAY_TIMEOUT,
+                    &opts.error_pages,
+                ));
+            }
+        };
+
+    let mut headers = end_to_end_headers(upstream_resp.headers());
+    if upstream_resp.status() != StatusCode::SWITCHING_PROTOCOLS {
+        // The upstream rejected the handshake, so its response is sent as it is
+        tracing::debug!(
+            "websocket proxy: upstream {upstream_uri} rejected the handshake with status {}",
+            upstream_resp.status()
+        );
+        let (mut parts, body) = upstream_resp.into_parts();
+        parts.headers = headers;
+        return Some(Ok(Response::from_parts(parts, body)));
+    }
+
+    let upstream_upgrade = hyper::upgrade::on(&mut upstream_resp);
+    let client_upgrade = hyper::upgrade::on(&mut *req);
+    // The tunnel keeps the client connection counted until it gets closed
+    let connection = req.extensions().get::<Arc<ConnectionGuard>>().cloned();
+    let bandwidth_limit = opts.bandwidth_limit.clone();
+    tokio::spawn(async move {
+        let (client_io, upstream_io) = match tokio::try_join!(client_upgrade, upstream_upgrade) {
+            Ok(upgraded) => upgraded,
+            Err(err) => {
+                tracing::error!("websocket proxy: unable to upgrade the connections: {err}");
+                return;
+            }
+        };
+        let (mut client_reader, mut client_writer) = tokio::io::split(client_io);
+        let (mut upstream_reader, mut upstream_writer) = tokio::io::split(upstream_io);
+        let tunnel = async {
+            tokio::try_join!(
+                bandwidth::copy(None, &mut client_reader, &mut upstream_writer),
+                bandwidth::copy(
+                    bandwidth_limit.as_ref(),
+                    &mut upstream_reader,
+                    &mut client_writer
+                ),
+            )
+        };
+        let aborted = async {
+            match &connection {
+                Some(connection) => connection.aborted().await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            result = tunnel => match result {
+                Ok((sent, received)) => tracing::debug!(
+                    "websocket proxy: tunnel to {upstream_uri} closed, sent={sent} bytes, received={received} bytes"
+                ),
+                Err(err) => {
+                    tracing::debug!("websocket proxy: tunnel to {upstream_uri} failed: {err}")
+                }
+            },
+            _ = aborted => tracing::debug!(
+                "websocket proxy: tunnel to {upstream_uri} aborted after the shutdown grace period"
+            ),
+        }
+    });
+
+    headers.insert(header::CONNECTION, HeaderValue::from_static("upgrade"));
+    headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+    let mut resp = Response::new(Body::empty());
+    *resp.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+    *resp.headers_mut() = headers;
+    Some(Ok(resp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Upstream, end_to_end_headers, is_upgrade_request, upstream_request};
+    use crate::ip_allowlist::IpAllowlist;
+    use crate::settings::WebSocketProxy;
+    use hyper::{Body, Request, Version};
+    use std::time::Duration;
+
+    fn route(path: &str, upstream: &str) -> WebSocketProxy {
+        WebSocketProxy {
+            path: path.to_owned(),
+            upstream: upstream.parse().unwrap(),
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    fn upgrade_request(uri: &str) -> Request<Body> {
+        Request::get(uri)
+            .header("host", "example.com")
+            .header("connection", "keep-alive, Upgrade")
+            .header("upgrade", "websocket")
+            .header("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ==")
+            .header("sec-websocket-version", "13")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_upstream() {
+        let upstream: Upstream = "http://127.0.0.1:8080".parse().unwrap();
+        assert_eq!(upstream.authority, "127.0.0.1:8080");
+        assert_eq!(upstream.path, None);
+        let upstream: Upstream = " ws://backend/socket/ ".parse().unwrap();
+        assert_eq!(upstream.authority, "backend");
+        assert_eq!(upstream.path.as_deref(), Some("/socket"));
+        let upstream: Upstream = "http://backend/".parse().unwrap();
+        assert_eq!(upstream.path.as_deref(), Some(""));
+
+        assert!("https://backend".parse::<Upstream>().is_err());
+        assert!("wss://backend".parse::<Upstream>().is_err());
+        assert!("backend:8080".parse::<Upstream>().is_err());
+        assert!("/socket".parse::<Upstream>().is_err());
+        assert!("http://backend/?a=1".parse::<Upstream>().is_err());
+    }
+
+    #[test]
+    fn test_request_path() {
+        let unchanged: Upstream = "http://backend".parse().unwrap();
+        assert_eq!(unchanged.request_path("/ws", "/ws/chat"), "/ws/chat");
+
+        let root: Upstream = "http://backend/".parse().unwrap();
+        assert_eq!(root.request_path("/ws", "/ws"), "/");
+        assert_eq!(root.request_path("/ws", "/ws/chat"), "/chat");
+
+        let socket: Upstream = "http://backend/socket".parse().unwrap();
+        assert_eq!(socket.request_path("/ws", "/ws"), "/socket");
+        assert_eq!(socket.request_path("/ws", "/ws/chat"), "/socket/chat");
+        assert_eq!(socket.request_path("/", "/chat"), "/socket/chat");
+    }
+
+    #[test]
+    fn test_is_upgrade_request() {
+        assert!(is_upgrade_request(&upgrade_request("/ws")));
+
+        let mut req = upgrade_request("/ws");
+        *req.version_mut() = Version::HTTP_2;
+        assert!(!is_upgrade_request(&req));
+
+        let req = Request::get("/ws")
+            .header("upgrade", "websocket")
+            .body(Body::empty())
+            .unwrap();
+        assert!(!is_upgrade_request(&req));
+
+        let req = Request::get("/ws")
+            .header("connection", "upgrade")
+            .header("upgrade", "h2c")
+            .body(Body::empty())
+            .unwrap();
+        assert!(!is_upgrade_request(&req));
+    }
+
+    #[test]
+    fn test_end_to_end_headers() {
+        let req = Request::get("/")
+            .header("connection", "keep-alive, x-hop")
+            .header("keep-alive", "timeout=5")
+            .header("x-hop", "1")
+            .header("transfer-encoding", "chunked")
+            .header("cookie", "a=1")
+            .body(())
+            .unwrap();
+        let headers = end_to_end_headers(req.headers());
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["cookie"], "a=1");
+    }
+
+    #[test]
+    fn test_upstream_request() {
+        let req = upgrade_request("/ws/chat?room=1");
+        let upstream_req = upstream_request(
+            &req,
+            &route("/ws", "http://127.0.0.1:9000/"),
+            Some("[::ffff:10.0.0.1]:4000".parse().unwrap()),
+            &IpAllowlist::default(),
+        )
+        .unwrap();
+
+        assert_eq!(upstream_req.uri(), "http://127.0.0.1:9000/chat?room=1");
+        let headers = upstream_req.headers();
+        assert_eq!(headers["connection"], "upgrade");
+        assert_eq!(headers["upgrade"], "websocket");
+        assert_eq!(headers["sec-websocket-key"], "dGhlIHNhbXBsZSBub25jZQ==");
+        assert_eq!(headers["x-forwarded-for"], "10.0.0.1");
+        assert_eq!(headers["x-forwarded-host"], "example.com");
+        assert_eq!(headers["x-forwarded-proto"], "http");
+        assert!(headers.get("host").is_none());
+    }
+
+    #[test]
+    fn test_upstream_request_forwarded_for() {
+        let mut req = upgrade_request("/ws");
+        req.headers_mut()
+            .insert("x-forwarded-for", "203.0.113.7".parse().unwrap());
+        let route = route("/ws", "http://127.0.0.1:9000");
+        let trusted_proxies = IpAllowlist::new(&["10.0.0.1".to_owned()], 0).unwrap();
+        let forwarded_for = |remote_addr: &str| {
+            let remote_addr = Some(remote_addr.parse().unwrap());
+            let upstream_req =
+                upstream_request(&req, &route, remote_addr, &trusted_proxies).unwrap();
+            upstream_req.headers()["x-forwarded-for"].clone()
+        };
+
+        // The client header is only extended for the trusted proxies
+        assert_eq!(forwarded_for("10.0.0.1:4000"), "203.0.113.7, 10.0.0.1");
+        assert_eq!(forwarded_for("10.0.0.2:4000"), "10.0.0.2");
+
+        // And dropped for the connections without address (E.g. Unix sockets)
+        let upstream_req = upstream_request(&req, &route, None, &trusted_proxies).unwrap();
+        assert!(upstream_req.headers().get("x-forwarded-for").is_none());
+    }
+}
@@ -6,22 +6,25 @@
 //! Module that lets SWS to run in a "Windows Service" context
 //!
 
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{env, path::Path};
 
 use windows_service::{
     define_windows_service,
     service::{
-        ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
-        ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+        Service, ServiceAccess, ServiceAction, ServiceActionType, ServiceControl,
+        ServiceControlAccept, ServiceErrorControl, ServiceExitCode, ServiceFailureActions,
+        ServiceFailureResetPeriod, ServiceInfo, ServiceStartType, ServiceState, ServiceStatus,
+        ServiceType,
     },
     service_control_handler::{self, ServiceControlHandlerResult, ServiceStatusHandle},
     service_dispatcher,
     service_manager::{ServiceManager, ServiceManagerAccess},
 };
 
+use crate::settings::{ServiceCommands, ServiceInstallOpts, ServiceStartup};
 use crate::{Context, Result, Server, Settings, helpers};
 
 const SERVICE_NAME: &str = "static-web-server";
@@ -31,6 +34,13 @@ const SERVICE_DESC: &str =
     "A cross-platform, high-performance and asynchronous web server for static files-serving";
 const SERVICE_DISPLAY_NAME: &str = "Static Web Server";
 
+/// Windows error code of a service which is not installed.
+const ERROR_SERVICE_DOES_NOT_EXIST: i32 = 1060;
+/// Maximum time to wait for the service to be running or stopped.
+const STATE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Number of failures restarting the service, the last action being repeated for the next ones.
+const RESTART_ACTIONS: usize = 3;
+
 // Generate the Windows Service boilerplate.
 // The boilerplate contains the low-level service entry function (ffi_service_main)
 // that parses incoming service arguments into Vec<OsString> and passes them to
@@ -47,6 +57,7 @@ fn custom_service_main(_args: Vec<OsString>) {
 fn set_service_state(
     status_handle: &ServiceStatusHandle,
     current_state: ServiceState,
+    exit_code: ServiceExitCode,
     checkpoint: u32,
     wait_hint: Duration,
 ) -> Result {
@@ -58,7 +69,7 @@ fn set_service_state(
         // Accept stop events when running
         controls_accepted: ServiceControlAccept::STOP,
         // Used to report an error when starting or stopping only, otherwise must be zero
-        exit_code: ServiceExitCode::Win32(0),
+        exit_code,
         // Only used for pending states, otherwise must be zero
         checkpoint,
         // Only used for pending states, otherwise must be zero
@@ -111,6 +122,7 @@ fn run_service() -> Result {
     set_service_state(
         &status_handle,
         ServiceState::Running,
+        ServiceExitCode::Win32(0),
         1,
         Duration::default(),
     )?;
@@ -121,6 +133,7 @@ fn run_service() -> Result {
         match set_service_state(
             &status_handle,
             ServiceState::StopPending,
+            ServiceExitCode::Win32(0),
             2,
             Duration::from_secs(3),
         ) {
@@ -133,24 +146,32 @@ fn run_service() -> Result {
     };
 
     // Starting web server
-    match Server::new(opts) {
-        Ok(server) => {
-            if let Err(err) = server.run_as_service(Some(shutdown_rx), stop_handler) {
+    let failed = match Server::new(opts) {
+        Ok(server) => match server.run_as_service(Some(shutdown_rx), stop_handler) {
+            Ok(()) => false,
+            Err(err) => {
                 tracing::error!(
                     "windows service: error after starting the server: {:?}",
                     err
                 );
+                true
             }
-        }
+        },
         Err(err) => {
-            tracing::info!("windows service: error starting the server: {:?}", err);
+            tracing::error!("windows service: error starting the server: {:?}", err);
+            true
         }
-    }
+    };
 
-    // Service is stopped
+    // Service is stopped, a failure exit code lets the recovery actions restart it
+    let exit_code = match failed {
+        true => ServiceExitCode::ServiceSpecific(1),
+        false => ServiceExitCode::Win32(0),
+    };
     set_service_state(
         &status_handle,
         ServiceState::Stopped,
+        exit_code,
         3,
         Duration::from_secs(3),
     )?;
@@ -173,8 +194,19 @@ pub fn run_server_as_service() -> Result {
     Ok(())
 }
 
+/// Runs a Windows Service management subcommand.
+pub fn run_service_command(command: ServiceCommands, config_file: &Path) -> Result {
+    match command {
+        ServiceCommands::Install(opts) => install_service(config_file, &opts),
+        ServiceCommands::Uninstall {} => uninstall_service(),
+        ServiceCommands::Start {} => start_service(),
+        ServiceCommands::Stop {} => stop_service(),
+        ServiceCommands::Status {} => service_status(),
+    }
+}
+
 /// Install a Windows Service for SWS.
-pub fn install_service(config_file: &Path) -> Result {
+pub fn install_service(config_file: &Path, opts: &ServiceInstallOpts) -> Result {
     let manager_access = ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE;
     let service_manager = ServiceManager::local_computer(None::<&str>, manager_access)?;
 
@@ -190,12 +222,28 @@ pub fn install_service(config_file: &Path) -> Result {
         service_binary_arguments.push(OsString::from(["--config-file=", &f].concat()));
     }
 
+    // Append a `--log-file` path to the binary arguments if present,
+    // made absolute since the service runs from the executable directory
+    if let Some(log_file) = &opts.log_file {
+        let log_file = std::path::absolute(log_file)
+            .with_context(|| format!("invalid log file path {}", log_file.display()))?;
+        let mut arg = OsString::from("--log-file=");
+        arg.push(log_file);
+        service_binary_arguments.push(arg);
+    }
+
+    let start_type = match opts.startup {
+        ServiceStartup::Auto => ServiceStartType::AutoStart,
+        ServiceStartup::Manual => ServiceStartType::OnDemand,
+        ServiceStartup::Disabled => ServiceStartType::Disabled,
+    };
+
     // Run the current service as `System` type
     let service_info = ServiceInfo {
         name: OsString::from(SERVICE_NAME),
         display_name: OsString::from(SERVICE_DISPLAY_NAME),
         service_type: SERVICE_TYPE,
-        start_type: ServiceStartType::OnDemand,
+        start_type,
         error_control: ServiceErrorControl::Normal,
         executable_path: service_binary_path,
         launch_arguments: service_binary_arguments,
@@ -204,16 +252,34 @@ pub fn install_service(config_file: &Path) -> Result {
         account_password: None,
     };
 
-    let service = service_manager.create_service(&service_info, ServiceAccess::CHANGE_CONFIG)?;
+    // NOTE: the restart recovery actions require the service start access
+    let service_access = ServiceAccess::CHANGE_CONFIG | ServiceAccess::START;
+    let service = service_manager.create_service(&service_info, service_access)?;
     service.set_description(SERVICE_DESC)?;
 
+    if let Some(delay) = opts.restart_delay {
+        let actions = (0..RESTART_ACTIONS)
+            .map(|_| ServiceAction {
+                action_type: ServiceActionType::Restart,
+                delay: Duration::from_secs(delay),
+            })
+            .collect();
+        service.update_failure_actions(ServiceFailureActions {
+            reset_period: ServiceFailureResetPeriod::After(Duration::from_secs(opts.restart_reset)),
+            reboot_msg: None,
+            command: None,
+            actions: Some(actions),
+        })?;
+        // The server errors stop the service with a failure exit code instead of crashing it
+        service.set_failure_actions_on_non_crash_failures(true)?;
+    }
+
     println!(
         "Windows Service ({}) is installed successfully!",
         SERVICE_NAME
     );
     println!(
-        "Start the service typing: sc.exe start \"{}\" (it requires administrator privileges) or using the 'services.msc' application.",
-        SERVICE_NAME
+        "Start the service typing: static-web-server.exe service start (it requires administrator privileges) or using the 'services.msc' application."
     );
 
     Ok(())
@@ -221,17 +287,13 @@ pub fn install_service(config_file: &Path) -> Result {
 
 /// Uninstall the current Windows Service for SWS.
 pub fn uninstall_service() -> Result {
-    let manager_access = ServiceManagerAccess::CONNECT;
-    let service_manager = ServiceManager::local_computer(None::<&str>, manager_access)?;
-
     let service_access = ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE;
-    let service = service_manager.open_service(SERVICE_NAME, service_access)?;
+    let service = open_service(service_access)?;
 
     let service_status = service.query_status()?;
     if service_status.current_state != ServiceState::Stopped {
         service.stop()?;
-        // Wait for service to stop
-        thread::sleep(Duration::from_secs(1));
+        wait_for_state(&service, ServiceState::Stopped)?;
     }
 
     service.delete()?;
@@ -240,3 +302,99 @@ pub fn uninstall_service() -> Result {
 
     Ok(())
 }
+
+/// Start the current Windows Service for SWS, waiting for it to be running.
+pub fn start_service() -> Result {
+    let service = open_service(ServiceAccess::QUERY_STATUS | ServiceAccess::START)?;
+
+    if service.query_status()?.current_state == ServiceState::Running {
+        println!("Windows Service ({}) is already running.", SERVICE_NAME);
+        return Ok(());
+    }
+
+    service.start::<&OsStr>(&[])?;
+    wait_for_state(&service, ServiceState::Running)?;
+
+    println!("Windows Service ({}) is running!", SERVICE_NAME);
+
+    Ok(())
+}
+
+/// Stop the current Windows Service for SWS, waiting for it to be stopped.
+pub fn stop_service() -> Result {
+    let service = open_service(ServiceAccess::QUERY_STATUS | ServiceAccess::STOP)?;
+
+    if service.query_status()?.current_state == ServiceState::Stopped {
+        println!("Windows Service ({}) is already stopped.", SERVICE_NAME);
+        return Ok(());
+    }
+
+    service.stop()?;
+    wait_for_state(&service, ServiceState::Stopped)?;
+
+    println!("Windows Service ({}) is stopped!", SERVICE_NAME);
+
+    Ok(())
+}
+
+/// Print the state and the configuration of the current Windows Service for SWS.
+pub fn service_status() -> Result {
+    let manager_access = ServiceManagerAccess::CONNECT;
+    let service_manager = ServiceManager::local_computer(None::<&str>, manager_access)?;
+    let service_access = ServiceAccess::QUERY_STATUS | ServiceAccess::QUERY_CONFIG;
+    let service = match service_manager.open_service(SERVICE_NAME, service_access) {
+        Ok(service) => service,
+        Err(windows_service::Error::Winapi(err))
+            if err.raw_os_error() == Some(ERROR_SERVICE_DOES_NOT_EXIST) =>
+        {
+            println!("Windows Service ({}) is not installed.", SERVICE_NAME);
+            return Ok(());
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let status = service.query_status()?;
+    let config = service.query_config()?;
+
+    println!("Windows Service ({})", SERVICE_NAME);
+    println!("  state:      {:?}", status.current_state);
+    if let Some(pid) = status.process_id {
+        println!("  pid:        {pid}");
+    }
+    println!("  start type: {:?}", config.start_type);
+    println!("  command:    {}", config.executable_path.display());
+
+    Ok(())
+}
+
+/// Opens the current Windows Service for SWS with the given access.
+fn open_service(service_access: ServiceAccess) -> Result<Service> {
+    let manager_access = ServiceManagerAccess::CONNECT;
+    let service_manager = ServiceManager::local_computer(None::<&str>, manager_access)?;
+    service_manager
+        .open_service(SERVICE_NAME, service_access)
+        .with_context(|| format!("unable to open the Windows Service ({SERVICE_NAME})"))
+}
+
+/// Waits for the service to reach the given state, failing if it takes too long.
+fn wait_for_state(service: &Service, state: ServiceState) -> Result {
+    let started = Instant::now();
+    loop {
+        let current = service.query_status()?.current_state;
+        if current == state {
+            return Ok(());
+        }
+        // A service failing to start is stopped right away
+        if state == ServiceState::Running && current == ServiceState::Stopped {
+            bail!(
+                "the Windows Service ({SERVICE_NAME}) stopped while starting, check its log output"
+            );
+        }
+        if started.elapsed() > STATE_TIMEOUT {
+            bail!(
+                "the Windows Service ({SERVICE_NAME}) didn't reach the {state:?} state in time, it's {current:?}"
+            );
+        }
+        thread::sleep(Duration::from_millis(250));
+    }
+}
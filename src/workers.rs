@@ -0,0 +1,277 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module that runs the server in multiple worker processes supervised by a parent process.
+//!
+//! Every worker process runs its own runtime and binds the same TCP listeners using the
+//! `SO_REUSEPORT` socket option, so the kernel shards the incoming connections among them.
+//! The parent process restarts the workers which crash and shuts all of them down gracefully
+//! on termination signals.
+//!
+
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+
+#[cfg(unix)]
+use {
+    crate::{Context, Result, Settings, settings::ListenerAddress, systemd},
+    rustix::process::{Pid, Signal, kill_process},
+    signal_hook::{consts::signal::*, iterator::Signals},
+    socket2::{Domain, Protocol, Socket, Type},
+    std::io::Read,
+    std::process::{Child, Command, ExitStatus, Stdio},
+    std::time::{Duration, Instant},
+    tokio::sync::watch::{self, Receiver},
+};
+
+/// Environment variable holding the identifier of a worker process.
+const WORKER_ID_ENV: &str = "SWS_WORKER_ID";
+
+/// Interval used by the parent process to check its workers and the termination signals.
+#[cfg(unix)]
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Workers failing before running this long are not restarted since they likely fail on startup.
+#[cfg(unix)]
+const MIN_UPTIME: Duration = Duration::from_secs(5);
+
+/// Maximum length of the pending connections queue of the worker sockets.
+#[cfg(unix)]
+const BACKLOG: i32 = 1024;
+
+/// Returns the identifier of the current worker process if running as such.
+pub fn worker_id() -> Option<usize> {
+    std::env::var(WORKER_ID_ENV).ok()?.parse().ok()
+}
+
+/// Binds a TCP listener, sharing its address with the other worker processes if any.
+pub(crate) fn bind_tcp(addr: SocketAddr) -> io::Result<TcpListener> {
+    #[cfg(unix)]
+    if worker_id().is_some() {
+        return bind_reuse_port(addr);
+    }
+    TcpListener::bind(addr)
+}
+
+/// Binds a TCP listener with the `SO_REUSEPORT` socket option enabled.
+#[cfg(unix)]
+fn bind_reuse_port(addr: SocketAddr) -> io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    socket.set_reuse_address(true)?;
+    socket.set_reuse_port(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(BACKLOG)?;
+    Ok(socket.into())
+}
+
+/// Returns a receiver notified once the parent process of the current worker exits
+/// or asks it to shut down, which is signaled by closing the worker standard input.
+#[cfg(unix)]
+#[cfg_attr(docsrs, doc(cfg(unix)))]
+pub fn parent_shutdown() -> Receiver<()> {
+    let (sender, receiver) = watch::channel(());
+    std::thread::spawn(move || {
+        let mut buf = [0; 64];
+        let mut stdin = io::stdin();
+        while let Ok(1..) = stdin.read(&mut buf) {}
+        tracing::info!(
+            "worker process {}: shutdown requested",
+            worker_id().unwrap_or_default()
+        );
+        sender.send(()).ok();
+    });
+    receiver
+}
+
+/// A worker process of the supervisor.
+#[cfg(unix)]
+struct Worker {
+    id: usize,
+    child: Child,
+    started: Instant,
+}
+
+#[cfg(unix)]
+impl Worker {
+    /// Spawns a worker process running the current executable with the same arguments.
+    fn spawn(id: usize) -> Result<Worker> {
+        let exe =
+            std::env::current_exe().with_context(|| "unable to get the current executable path")?;
+        let mut command = Command::new(exe);
+        command
+            .args(std::env::args_os().skip(1))
+            .env(WORKER_ID_ENV, id.to_string())
+            .stdin(Stdio::piped());
+        // Only the supervisor notifies the service manager
+        for name in systemd::PROCESS_ENVS {
+            command.env_remove(name);
+        }
+        let child = command
+            .spawn()
+            .with_context(|| format!("failed to spawn worker process {id}"))?;
+        tracing::info!("worker process {} started: pid={}", id, child.id());
+
+        Ok(Worker {
+            id,
+            child,
+            started: Instant::now(),
+        })
+    }
+
+    /// Sends a signal to the worker process.
+    fn signal(&self, signal: Signal) -> io::Result<()> {
+        kill_process(Pid::from_child(&self.child), signal)?;
+        Ok(())
+    }
+
+    /// Asks the worker to shut down gracefully and waits for it.
+    fn shutdown(mut self) -> io::Result<ExitStatus> {
+        // Closing the standard input makes the worker shut down
+        drop(self.child.stdin.take());
+        self.child.wait()
+    }
+}
+
+/// Checks that the settings can be served by multiple worker processes.
+#[cfg(unix)]
+fn validate(opts: &Settings) -> Result {
+    if opts.general.fd.is_some() {
+        bail!("worker processes can not be used along with an inherited file descriptor");
+    }
+    if !opts.general.control_listen.is_empty() {
+        bail!("worker processes can not be used along with the control api");
+    }
+    if opts
+        .listeners
+        .iter()
+        .any(|listener| matches!(listener.address, ListenerAddress::Unix(_)))
+    {
+        bail!("worker processes can not be used along with unix domain socket listeners");
+    }
+    if opts
+        .listeners
+        .iter()
+        .any(|listener| matches!(listener.address, ListenerAddress::Systemd(_)))
+    {
+        bail!("worker processes can not be used along with systemd socket listeners");
+    }
+    Ok(())
+}
+
+/// Runs the given number of worker processes until a termination signal is caught,
+/// restarting the workers which crash.
+#[cfg(unix)]
+#[cfg_attr(docsrs, doc(cfg(unix)))]
+pub fn supervise(opts: &Settings) -> Result {
+    validate(opts)?;
+
+    let processes = opts.general.worker_processes;
+    // NOTE: SIGHUP and SIGUSR1 are forwarded to the workers, which reload the configuration
+    // and toggle the maintenance mode on their own.
+    let mut signals = Signals::new([SIGHUP, SIGUSR1, SIGTERM, SIGINT, SIGQUIT])
+        .with_context(|| "failed to register termination signals")?;
+
+    tracing::info!("supervising {} worker processes", processes);
+    let mut workers = Vec::with_capacity(processes);
+    for id in 1..=processes {
+        workers.push(Some(Worker::spawn(id)?));
+    }
+    // NOTE: the workers bind their listeners on their own, so the supervisor is ready
+    // once they are spawned
+    systemd::ready();
+    let watchdog_interval = systemd::watchdog_interval();
+    let mut watchdog_pinged = Instant::now();
+
+    let result = 'supervise: loop {
+        if let Some(interval) = watchdog_interval {
+            if watchdog_pinged.elapsed() >= interval {
+                systemd::watchdog();
+                watchdog_pinged = Instant::now();
+            }
+        }
+
+        for signal in signals.pending() {
+            let forwarded = match signal {
+                SIGHUP => Signal::HUP,
+                SIGUSR1 => Signal::USR1,
+                _ => {
+                    tracing::info!("SIGTERM, SIGINT or SIGQUIT signal caught");
+                    break 'supervise Ok(());
+                }
+            };
+            tracing::info!(
+                "signal {} caught, forwarding it to the worker processes",
+                signal
+            );
+            for worker in workers.iter().flatten() {
+                if let Err(err) = worker.signal(forwarded) {
+                    tracing::error!(
+                        "failed to send signal {} to worker process {}: {}",
+                        signal,
+                        worker.id,
+                        err
+                    );
+                }
+            }
+        }
+
+        for slot in workers.iter_mut() {
+            let Some(worker) = slot else {
+                continue;
+            };
+            let status = match worker.child.try_wait() {
+                Ok(Some(status)) => status,
+                Ok(None) => continue,
+                Err(err) => break 'supervise Err(err.into()),
+            };
+
+            let id = worker.id;
+            if status.success() {
+                tracing::info!("worker process {} exited", id);
+                *slot = None;
+            } else if worker.started.elapsed() < MIN_UPTIME {
+                break 'supervise Err(anyhow!("worker process {id} failed on startup: {status}"));
+            } else {
+                tracing::error!("worker process {} crashed: {}, restarting it", id, status);
+                match Worker::spawn(id) {
+                    Ok(worker) => *slot = Some(worker),
+                    Err(err) => break 'supervise Err(err),
+                }
+            }
+        }
+
+        if workers.iter().all(Option::is_none) {
+            break Ok(());
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+
+    systemd::stopping();
+    tracing::info!("shutting down the worker processes");
+    for worker in workers.into_iter().flatten() {
+        let id = worker.id;
+        match worker.shutdown() {
+            Ok(status) => tracing::info!("worker process {} stopped: {}", id, status),
+            Err(err) => tracing::error!("failed to wait for worker process {}: {}", id, err),
+        }
+    }
+    result
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::bind_reuse_port;
+
+    #[test]
+    fn test_bind_reuse_port() {
+        let first = bind_reuse_port("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = first.local_addr().unwrap();
+        let second = bind_reuse_port(addr).unwrap();
+        assert_eq!(second.local_addr().unwrap(), addr);
+
+        // Regular listeners can't share the address
+        assert!(std::net::TcpListener::bind(addr).is_err());
+    }
+}
@@ -0,0 +1,415 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+// This file is part of Static Web Server.
+// See https://static-web-server.net/ for more information
+// Copyright (C) 2019-present Jose Quintana <joseluisq.net>
+
+//! Module to send large files with zero-copy (`sendfile`) on Linux, so their contents go
+//! from the page cache to the socket without being copied through userspace buffers.
+//!
+//! Hyper still writes the HTTP/1 responses: the body of such a file is replaced with
+//! placeholder chunks pointing to a static buffer, which the connection stream recognizes
+//! and replaces with the same amount of bytes sent from the file.
+//!
+
+use bytes::Bytes;
+use futures_util::Stream;
+use headers::{ContentLength, HeaderMapExt};
+use hyper::header::{ACCEPT_RANGES, CONTENT_ENCODING};
+use hyper::server::accept::Accept;
+use hyper::server::conn::{AddrIncoming, AddrStream};
+use hyper::{Body, Method, Response, Version};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+
+use crate::{Error, Result, transport::Transport};
+
+/// Minimum size in bytes of the file ranges sent with zero-copy,
+/// the smaller ones are not worth it.
+const MIN_FILE_SIZE: u64 = 256 * 1024;
+
+/// Size of the placeholder chunks of a response body,
+/// that is, the maximum number of bytes sent by a `sendfile` call.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Buffer the placeholder chunks point to, so they are recognized by their address.
+static PLACEHOLDER: [u8; CHUNK_SIZE] = [0; CHUNK_SIZE];
+
+/// Whether the files are sent with zero-copy.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Initializes the zero-copy file transmission.
+pub(crate) fn init(enabled: bool) -> Result {
+    if !enabled {
+        tracing::info!("zero-copy: enabled=false");
+        return Ok(());
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    bail!("zero-copy is only supported on Linux");
+
+    #[cfg(target_os = "linux")]
+    {
+        ENABLED.store(true, Ordering::Relaxed);
+        tracing::info!("zero-copy: enabled=true, min file size={MIN_FILE_SIZE} bytes");
+        Ok(())
+    }
+}
+
+/// Checks if the files are sent with zero-copy.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Range of a file which can be sent with zero-copy, attached to its response as an extension.
+#[derive(Clone, Debug)]
+pub(crate) struct ZeroCopyFile {
+    file: Arc<File>,
+    offset: u64,
+    len: u64,
+}
+
+impl ZeroCopyFile {
+    /// Prepares a range of a file to be sent with zero-copy
+    /// if it's enabled and the range is large enough.
+    pub(crate) fn new(file: &File, offset: u64, len: u64) -> Option<Self> {
+        if !is_enabled() || len < MIN_FILE_SIZE {
+            return None;
+        }
+        let file = file.try_clone().ok()?;
+        Some(Self {
+            file: Arc::new(file),
+            offset,
+            len,
+        })
+    }
+}
+
+/// Files being sent with zero-copy on a connection, in the order of their responses.
+#[derive(Clone, Debug, Default)]
+pub struct ZeroCopySender(Arc<Mutex<VecDeque<ZeroCopyFile>>>);
+
+impl ZeroCopySender {
+    /// Creates the body of placeholder chunks of a file sent with zero-copy.
+    fn body(&self, file: ZeroCopyFile) -> Body {
+        Body::wrap_stream(PlaceholderStream {
+            sender: self.clone(),
+            remaining: file.len,
+            file: Some(file),
+        })
+    }
+}
+
+/// Stream of the placeholder chunks of a file, which is queued on the connection
+/// once its body starts to be written.
+struct PlaceholderStream {
+    sender: ZeroCopySender,
+    file: Option<ZeroCopyFile>,
+    remaining: u64,
+}
+
+impl Stream for PlaceholderStream {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        if let Some(file) = pin.file.take() {
+            let mut files = pin.sender.0.lock().unwrap_or_else(PoisonError::into_inner);
+            files.push_back(file);
+        }
+        if pin.remaining == 0 {
+            return Poll::Ready(None);
+        }
+        let len = pin.remaining.min(CHUNK_SIZE as u64) as usize;
+        pin.remaining -= len as u64;
+        Poll::Ready(Some(Ok(Bytes::from_static(&PLACEHOLDER[..len]))))
+    }
+}
+
+/// Sends the body of a response with zero-copy if the connection supports it and the body
+/// is still the contents of a large file, that is, it wasn't compressed nor generated.
+pub(crate) async fn post_process<F>(
+    sender: Option<ZeroCopySender>,
+    method: Method,
+    version: Version,
+    handle: F,
+) -> Result<Response<Body>, Error>
+where
+    F: Future<Output = Result<Response<Body>, Error>>,
+{
+    let mut resp = handle.await?;
+    let file = resp.extensions_mut().remove::<ZeroCopyFile>();
+    if let (Some(sender), Some(file)) = (sender, file) {
+        if is_sendable(&method, version, &resp, file.len) {
+            *resp.body_mut() = sender.body(file);
+        }
+    }
+    Ok(resp)
+}
+
+/// Checks if a response body can be replaced with a file range of the given length.
+fn is_sendable(method: &Method, version: Version, resp: &Response<Body>, len: u64) -> bool {
+    let headers = resp.headers();
+    // HTTP/2 frames copy the body, so only HTTP/1 bodies are written as they are
+    method != Method::HEAD
+        && matches!(version, Version::HTTP_10 | Version::HTTP_11)
+        && !headers.contains_key(CONTENT_ENCODING)
+        // The generated contents (E.g. rendered Markdown) don't accept ranges
+        && headers.contains_key(ACCEPT_RANGES)
+        && headers.typed_get::<ContentLength>() == Some(ContentLength(len))
+}
+
+/// Type to intercept incoming TCP connections in order to send files with zero-copy on them.
+pub struct ZeroCopyAcceptor {
+    incoming: AddrIncoming,
+}
+
+impl ZeroCopyAcceptor {
+    /// Creates a new zero-copy connections interceptor.
+    pub fn new(incoming: AddrIncoming) -> Self {
+        Self { incoming }
+    }
+}
+
+impl Accept for ZeroCopyAcceptor {
+    type Conn = ZeroCopyStream;
+    type Error = io::Error;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        Pin::new(&mut self.get_mut().incoming)
+            .poll_accept(cx)
+            .map_ok(ZeroCopyStream::new)
+    }
+}
+
+/// TCP connection stream which sends the placeholder chunks of the files with `sendfile`.
+pub struct ZeroCopyStream {
+    inner: TcpStream,
+    remote_addr: SocketAddr,
+    sender: Option<ZeroCopySender>,
+}
+
+impl ZeroCopyStream {
+    /// Creates a new connection stream, sending files with zero-copy if it's enabled.
+    pub fn new(conn: AddrStream) -> Self {
+        Self {
+            remote_addr: conn.remote_addr(),
+            inner: conn.into_inner(),
+            sender: is_enabled().then(ZeroCopySender::default),
+        }
+    }
+}
+
+impl AsyncRead for ZeroCopyStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for ZeroCopyStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let pin = self.get_mut();
+        #[cfg(target_os = "linux")]
+        if let Some(sender) = &pin.sender {
+            if is_placeholder(buf) {
+                return poll_sendfile(&pin.inner, sender, cx, buf.len());
+            }
+        }
+        Pin::new(&mut pin.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+impl Transport for ZeroCopyStream {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        Some(self.remote_addr)
+    }
+
+    fn zero_copy(&self) -> Option<ZeroCopySender> {
+        self.sender.clone()
+    }
+}
+
+/// Checks if a buffer written to the connection is a placeholder chunk.
+#[cfg(target_os = "linux")]
+fn is_placeholder(buf: &[u8]) -> bool {
+    let start = PLACEHOLDER.as_ptr() as usize;
+    let ptr = buf.as_ptr() as usize;
+    !buf.is_empty() && ptr >= start && ptr + buf.len() <= start + CHUNK_SIZE
+}
+
+/// Sends up to `count` bytes of the current file of the connection with `sendfile`.
+#[cfg(target_os = "linux")]
+fn poll_sendfile(
+    stream: &TcpStream,
+    sender: &ZeroCopySender,
+    cx: &mut Context<'_>,
+    count: usize,
+) -> Poll<io::Result<usize>> {
+    let mut files = sender.0.lock().unwrap_or_else(PoisonError::into_inner);
+    let Some(file) = files.front_mut() else {
+        return Poll::Ready(Err(io::Error::other("no file to send with zero-copy")));
+    };
+    let count = usize::try_from(file.len).map_or(count, |len| len.min(count));
+    loop {
+        std::task::ready!(stream.poll_write_ready(cx))?;
+        let mut offset = file.offset;
+        let sent = stream.try_io(tokio::io::Interest::WRITABLE, || {
+            rustix::fs::sendfile(stream, file.file.as_ref(), Some(&mut offset), count)
+                .map_err(io::Error::from)
+        });
+        match sent {
+            Ok(0) => {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "file truncated while being sent with zero-copy",
+                )));
+            }
+            Ok(n) => {
+                file.offset += n as u64;
+                file.len -= n as u64;
+                if file.len == 0 {
+                    files.pop_front();
+                }
+                return Poll::Ready(Ok(n));
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+            Err(err) => return Poll::Ready(Err(err)),
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::{ZeroCopyFile, ZeroCopySender, ZeroCopyStream, is_sendable, post_process};
+    use headers::{ContentLength, HeaderMapExt};
+    use hyper::header::{ACCEPT_RANGES, CONTENT_ENCODING};
+    use hyper::server::conn::Http;
+    use hyper::service::service_fn;
+    use hyper::{Body, Client, Method, Response, Version};
+    use std::fs::File;
+    use std::sync::Arc;
+
+    fn file_response(file: &Arc<File>, offset: u64, len: u64) -> Response<Body> {
+        let mut resp = Response::new(Body::empty());
+        resp.headers_mut().typed_insert(ContentLength(len));
+        resp.headers_mut()
+            .insert(ACCEPT_RANGES, "bytes".parse().unwrap());
+        resp.extensions_mut().insert(ZeroCopyFile {
+            file: file.clone(),
+            offset,
+            len,
+        });
+        resp
+    }
+
+    #[test]
+    fn test_is_sendable() {
+        let file = Arc::new(File::open("tests/fixtures/public/404.html").unwrap());
+        let resp = file_response(&file, 0, 10);
+        assert!(is_sendable(&Method::GET, Version::HTTP_11, &resp, 10));
+        assert!(is_sendable(&Method::GET, Version::HTTP_10, &resp, 10));
+        assert!(!is_sendable(&Method::HEAD, Version::HTTP_11, &resp, 10));
+        assert!(!is_sendable(&Method::GET, Version::HTTP_2, &resp, 10));
+        assert!(!is_sendable(&Method::GET, Version::HTTP_11, &resp, 20));
+
+        let mut compressed = file_response(&file, 0, 10);
+        compressed
+            .headers_mut()
+            .insert(CONTENT_ENCODING, "gzip".parse().unwrap());
+        assert!(!is_sendable(
+            &Method::GET,
+            Version::HTTP_11,
+            &compressed,
+            10
+        ));
+
+        let mut generated = file_response(&file, 0, 10);
+        generated.headers_mut().remove(ACCEPT_RANGES);
+        assert!(!is_sendable(&Method::GET, Version::HTTP_11, &generated, 10));
+    }
+
+    #[tokio::test]
+    async fn test_sendfile() {
+        let contents: Vec<u8> = (0..600_000u32).map(|i| (i % 251) as u8).collect();
+        let path = std::env::temp_dir().join(format!("sws-zero-copy-{}", std::process::id()));
+        std::fs::write(&path, &contents).unwrap();
+        let file = Arc::new(File::open(&path).unwrap());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (stream, remote_addr) = listener.accept().await.unwrap();
+                let sender = ZeroCopySender::default();
+                let conn = ZeroCopyStream {
+                    inner: stream,
+                    remote_addr,
+                    sender: Some(sender.clone()),
+                };
+                let file = file.clone();
+                let service = service_fn(move |req| {
+                    // The second half of the file is requested as a range
+                    let (offset, len) = match req.uri().path() {
+                        "/range" => (300_000, 300_000),
+                        _ => (0, 600_000),
+                    };
+                    let resp = file_response(&file, offset, len);
+                    post_process(
+                        Some(sender.clone()),
+                        req.method().clone(),
+                        req.version(),
+                        async move { Ok(resp) },
+                    )
+                });
+                tokio::spawn(
+                    Http::new()
+                        .http1_only(true)
+                        .http1_writev(true)
+                        .serve_connection(conn, service),
+                );
+            }
+        });
+
+        let client = Client::new();
+        for (uri, expected) in [
+            ("/", &contents[..]),
+            ("/range", &contents[300_000..]),
+            ("/", &contents[..]),
+        ] {
+            let uri = format!("http://{addr}{uri}").parse().unwrap();
+            let resp = client.get(uri).await.unwrap();
+            let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+            assert_eq!(body.len(), expected.len());
+            assert!(body == expected);
+        }
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,64 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::{Method, Request, StatusCode, Version};
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+
+    use static_web_server::access_log::AccessLogRecord;
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    #[tokio::test]
+    async fn access_log_sinks() {
+        let opts = fixture_settings("toml/charsets.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let mut req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let records = Arc::new(Mutex::new(Vec::<AccessLogRecord>::new()));
+        let sink_records = records.clone();
+        req_handler
+            .access_log
+            .add_sink(move |record: &AccessLogRecord| {
+                sink_records.lock().unwrap().push(record.clone())
+            });
+
+        for (method, uri) in [
+            (Method::GET, "http://localhost/assets/main.js?v=1"),
+            (Method::HEAD, "http://localhost/missing.html"),
+        ] {
+            let mut req = Request::default();
+            *req.method_mut() = method;
+            *req.uri_mut() = uri.parse().unwrap();
+            req.headers_mut()
+                .insert("user-agent", "sws-test".parse().unwrap());
+            if let Err(err) = req_handler.handle(&mut req, remote_addr).await {
+                panic!("unexpected error: {err}")
+            }
+        }
+
+        let records = records.lock().unwrap();
+        assert_eq!(records.len(), 2);
+
+        let record = &records[0];
+        assert_eq!(record.method, Method::GET);
+        assert_eq!(record.uri, "http://localhost/assets/main.js?v=1");
+        assert_eq!(record.version, Version::HTTP_11);
+        assert_eq!(record.host.as_deref(), Some("localhost"));
+        assert_eq!(record.remote_addr, remote_addr);
+        assert_eq!(record.user_agent.as_deref(), Some("sws-test"));
+        assert_eq!(record.referer, None);
+        assert_eq!(record.status, StatusCode::OK);
+        assert!(record.content_length.is_some_and(|len| len > 0));
+
+        let record = &records[1];
+        assert_eq!(record.method, Method::HEAD);
+        assert_eq!(record.status, StatusCode::NOT_FOUND);
+    }
+}
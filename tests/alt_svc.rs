@@ -0,0 +1,36 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::Request;
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    #[tokio::test]
+    async fn alt_svc_header() {
+        let opts = fixture_settings("toml/alt_svc.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+        let alt_svc = "h3=\":443\"; ma=3600, h2=\"alt.example.com:8443\"; ma=3600";
+
+        for (uri, status, expected) in [
+            ("http://localhost/index.htm", 200, alt_svc),
+            // Custom headers take precedence
+            ("http://localhost/assets/main.js", 200, "clear"),
+        ] {
+            let mut req = Request::default();
+            *req.uri_mut() = uri.parse().unwrap();
+
+            let res = req_handler.handle(&mut req, remote_addr).await.unwrap();
+            assert_eq!(res.status(), status, "{uri}");
+            assert_eq!(res.headers()["alt-svc"], expected, "{uri}");
+        }
+    }
+}
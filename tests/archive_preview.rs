@@ -0,0 +1,123 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(all(test, feature = "archive-preview"))]
+pub mod tests {
+    use hyper::{Body, Method, Request, Response, StatusCode};
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    async fn request(method: Method, uri: &str) -> Response<Body> {
+        let opts = fixture_settings("toml/archive_preview.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.method_mut() = method;
+        *req.uri_mut() = uri.parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    async fn body_string(res: Response<Body>) -> String {
+        let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        String::from_utf8(body_bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn archive_preview_list_json() {
+        for archive in ["build.zip", "build.tar.gz"] {
+            let res = request(
+                Method::GET,
+                &format!("http://localhost/{archive}?list=json"),
+            )
+            .await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(res.headers()["content-type"], "application/json");
+
+            let listing: serde_json::Value = serde_json::from_str(&body_string(res).await).unwrap();
+            assert_eq!(listing["truncated"], false);
+            let entries = listing["entries"].as_array().unwrap();
+            assert_eq!(entries.len(), 4, "{archive}");
+            assert!(
+                entries
+                    .iter()
+                    .any(|e| e["name"] == "README.txt" && e["size"] == 16 && e["type"] == "file")
+            );
+            assert!(entries.iter().any(|e| e["type"] == "dir"));
+        }
+    }
+
+    #[tokio::test]
+    async fn archive_preview_list_html() {
+        let res = request(Method::GET, "http://localhost/build.tar.gz?list").await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["content-type"], "text/html; charset=utf-8");
+        assert!(res.headers().get("accept-ranges").is_none());
+
+        let body = body_string(res).await;
+        assert!(body.contains("<title>Contents of build.tar.gz</title>"));
+        assert!(body.contains("<a href=\"?extract=docs%2Findex.html\">docs/index.html</a>"));
+    }
+
+    #[tokio::test]
+    async fn archive_preview_extract() {
+        for archive in ["build.zip", "build.tar.gz"] {
+            let res = request(
+                Method::GET,
+                &format!("http://localhost/{archive}?extract=docs%2Findex.html"),
+            )
+            .await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(res.headers()["content-type"], "text/html");
+            assert_eq!(res.headers()["content-length"], "14");
+            assert_eq!(body_string(res).await, "<h1>Docs</h1>\n");
+
+            let res = request(
+                Method::HEAD,
+                &format!("http://localhost/{archive}?extract=README.txt"),
+            )
+            .await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(res.headers()["content-length"], "16");
+            assert_eq!(body_string(res).await, "");
+        }
+    }
+
+    #[tokio::test]
+    async fn archive_preview_extract_rejected() {
+        for archive in ["build.zip", "build.tar.gz"] {
+            let res = request(
+                Method::GET,
+                &format!("http://localhost/{archive}?extract=missing.txt"),
+            )
+            .await;
+            assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+            // Larger than the configured `archive-preview-max-size`
+            let res = request(
+                Method::GET,
+                &format!("http://localhost/{archive}?extract=bin%2Fdata.bin"),
+            )
+            .await;
+            assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        }
+    }
+
+    #[tokio::test]
+    async fn archive_preview_without_query() {
+        let res = request(Method::GET, "http://localhost/build.zip").await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["content-type"], "application/zip");
+        assert_eq!(res.headers()["content-length"], "732");
+    }
+}
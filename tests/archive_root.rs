@@ -0,0 +1,92 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(all(test, feature = "archive-root"))]
+pub mod tests {
+    use hyper::{Body, Request, Response};
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    async fn request(fixture: &str, uri: &str, range: Option<&str>) -> Response<Body> {
+        let opts = fixture_settings(fixture);
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.uri_mut() = uri.parse().unwrap();
+        if let Some(range) = range {
+            req.headers_mut().insert("range", range.parse().unwrap());
+        }
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    async fn body(res: Response<Body>) -> String {
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        String::from_utf8_lossy(&body).into_owned()
+    }
+
+    #[tokio::test]
+    async fn archive_root_files() {
+        for fixture in ["toml/archive_root_tar.toml", "toml/archive_root_zip.toml"] {
+            let res = request(fixture, "http://localhost/", None).await;
+            assert_eq!(res.status(), 200, "{fixture}");
+            assert_eq!(res.headers()["content-type"], "text/html");
+            assert_eq!(res.headers()["content-length"], "461");
+            assert!(res.headers().contains_key("last-modified"));
+            assert!(body(res).await.contains("<h1>Served from an archive</h1>"));
+
+            let res = request(fixture, "http://localhost/assets/js/main.js", None).await;
+            assert_eq!(res.status(), 200, "{fixture}");
+            assert_eq!(body(res).await, "console.log('archive');\n");
+
+            // The `.html` suffix fallback applies to the archive members too
+            let res = request(fixture, "http://localhost/page", None).await;
+            assert_eq!(res.status(), 200, "{fixture}");
+            assert_eq!(body(res).await, "<!DOCTYPE html>\n<h1>Page</h1>\n");
+
+            let res = request(fixture, "http://localhost/assets", None).await;
+            assert_eq!(res.status(), 308, "{fixture}");
+            assert_eq!(res.headers()["location"], "/assets/");
+
+            let res = request(fixture, "http://localhost/missing.html", None).await;
+            assert_eq!(res.status(), 404, "{fixture}");
+            let res = request(fixture, "http://localhost/../site.tar", None).await;
+            assert_eq!(res.status(), 404, "{fixture}");
+        }
+    }
+
+    #[tokio::test]
+    async fn archive_root_range() {
+        for fixture in ["toml/archive_root_tar.toml", "toml/archive_root_zip.toml"] {
+            // The index page is a deflated member of the zip archive
+            let res = request(fixture, "http://localhost/index.html", Some("bytes=0-14")).await;
+            assert_eq!(res.status(), 206, "{fixture}");
+            assert_eq!(res.headers()["content-range"], "bytes 0-14/461");
+            assert_eq!(body(res).await, "<!DOCTYPE html>");
+
+            let res = request(fixture, "http://localhost/index.html", Some("bytes=-8")).await;
+            assert_eq!(res.status(), 206, "{fixture}");
+            assert_eq!(res.headers()["content-range"], "bytes 453-460/461");
+            assert_eq!(body(res).await, "</html>\n");
+        }
+    }
+
+    #[tokio::test]
+    async fn archive_root_overlay() {
+        // Files not found in the archive are looked up under the overlay roots
+        let fixture = "toml/archive_root_zip.toml";
+        let res = request(fixture, "http://localhost/theme.js", None).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(body(res).await, "console.log(\"theme\");\n");
+    }
+}
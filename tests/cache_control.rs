@@ -0,0 +1,58 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::Request;
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    #[tokio::test]
+    async fn cache_control_policies() {
+        let opts = fixture_settings("toml/cache_control.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        for (uri, cache_control) in [
+            (
+                "http://localhost/assets/main.js",
+                "max-age=31536000, immutable",
+            ),
+            (
+                "http://localhost/assets/main.css",
+                "max-age=31536000, immutable",
+            ),
+            (
+                "http://localhost/50x.html",
+                "max-age=60, stale-while-revalidate=30, stale-if-error=86400",
+            ),
+            ("http://localhost/index.htm", "no-cache"),
+            ("http://localhost/assets/index.html", "no-cache"),
+            // Paths matching no policy use the file types heuristic
+            ("http://localhost/main.js.zst", "max-age=86400"),
+        ] {
+            let mut req = Request::default();
+            *req.uri_mut() = uri.parse().unwrap();
+
+            match req_handler.handle(&mut req, remote_addr).await {
+                Ok(res) => {
+                    assert_eq!(res.headers()["cache-control"], cache_control, "{uri}");
+                    assert_eq!(
+                        res.headers().contains_key("expires"),
+                        uri.ends_with("/50x.html"),
+                        "{uri}"
+                    );
+                }
+                Err(err) => {
+                    panic!("unexpected error: {err}")
+                }
+            };
+        }
+    }
+}
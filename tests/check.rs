@@ -0,0 +1,25 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use static_web_server::{check, testing::fixtures::fixture_settings};
+
+    #[test]
+    fn check_passes() {
+        let opts = fixture_settings("toml/redirects.toml");
+        check::run(opts).unwrap();
+    }
+
+    #[test]
+    fn check_fails() {
+        let opts = fixture_settings("toml/check_invalid.toml");
+        let err = check::run(opts).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "configuration check failed with 4 error(s)"
+        );
+    }
+}
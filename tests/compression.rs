@@ -82,4 +82,133 @@ pub mod tests {
             Err(err) => panic!("unexpected error: {err}"),
         };
     }
+
+    async fn compression_encoding(
+        uri: &str,
+        min_size: u64,
+        mime_types: &str,
+        exclude_mime_types: &str,
+    ) -> Option<HeaderValue> {
+        let opts = fixture_settings("toml/handler_fixtures.toml");
+        let general = General {
+            compression: true,
+            compression_min_size: min_size,
+            compression_mime_types: mime_types.to_owned(),
+            compression_exclude_mime_types: exclude_mime_types.to_owned(),
+            ..opts.general
+        };
+        let req_handler_opts = fixture_req_handler_opts(general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.method_mut() = hyper::Method::GET;
+        *req.uri_mut() = uri.parse().unwrap();
+        req.headers_mut()
+            .insert(http::header::ACCEPT_ENCODING, "gzip".parse().unwrap());
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => {
+                assert_eq!(res.status(), 200);
+                res.headers().get("content-encoding").cloned()
+            }
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn compression_min_size() {
+        // The `main.css` file size is 171 bytes
+        let uri = "http://localhost/assets/main.css";
+        assert_eq!(compression_encoding(uri, 200, "", "").await, None);
+        assert_eq!(
+            compression_encoding(uri, 100, "", "").await,
+            Some(HeaderValue::from_static("gzip"))
+        );
+    }
+
+    #[tokio::test]
+    async fn compression_mime_types() {
+        let css = "http://localhost/assets/main.css";
+        let js = "http://localhost/assets/main.js";
+
+        // Excluded MIME types
+        assert_eq!(compression_encoding(css, 0, "", "text/css").await, None);
+        assert_eq!(
+            compression_encoding(js, 0, "", "text/css").await,
+            Some(HeaderValue::from_static("gzip"))
+        );
+
+        // Allowed MIME types
+        assert_eq!(
+            compression_encoding(css, 0, "text/javascript", "").await,
+            None
+        );
+        assert_eq!(
+            compression_encoding(js, 0, "text/javascript", "").await,
+            Some(HeaderValue::from_static("gzip"))
+        );
+
+        // Excluded MIME types take precedence
+        assert_eq!(
+            compression_encoding(css, 0, "text/*", "text/css").await,
+            None
+        );
+    }
+
+    #[cfg(any(feature = "compression", feature = "compression-gzip"))]
+    #[tokio::test]
+    async fn compression_streaming() {
+        use hyper::body::{Body, HttpBody};
+        use static_web_server::{compression, settings::CompressionLevel};
+        use std::sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        };
+
+        let (mut sender, body) = Body::channel();
+        let (head, _) = hyper::Response::new(Body::empty()).into_parts();
+        let resp = compression::gzip(head, body.into(), CompressionLevel::Default, None);
+        assert_eq!(resp.headers()["content-encoding"], "gzip");
+        assert!(resp.headers().get("content-length").is_none());
+
+        // Send never-ending incompressible data so the body can only be consumed in a streaming fashion
+        let sent = Arc::new(AtomicUsize::new(0));
+        let sent_producer = sent.clone();
+        tokio::spawn(async move {
+            let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+            loop {
+                let chunk: Vec<u8> = (0..16 * 1024)
+                    .map(|_| {
+                        seed ^= seed << 13;
+                        seed ^= seed >> 7;
+                        seed ^= seed << 17;
+                        seed as u8
+                    })
+                    .collect();
+                let len = chunk.len();
+                if sender.send_data(chunk.into()).await.is_err() {
+                    break;
+                }
+                sent_producer.fetch_add(len, Ordering::SeqCst);
+            }
+        });
+
+        let mut body = resp.into_body();
+        let mut received = 0;
+        while received < 1024 * 1024 {
+            let chunk = body
+                .data()
+                .await
+                .expect("unexpected end of the compressed body")
+                .expect("unexpected error during body compression");
+            assert!(!chunk.is_empty() && chunk.len() <= 8 * 1024);
+            received += chunk.len();
+
+            // The inner body is only read as the compressed one is consumed (backpressure),
+            // so the data pending compression stays bounded
+            let pending = sent.load(Ordering::SeqCst).saturating_sub(received);
+            assert!(pending <= 128 * 1024, "{pending} bytes pending compression");
+        }
+    }
 }
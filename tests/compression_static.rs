@@ -260,4 +260,65 @@ mod tests {
             Err(err) => panic!("unexpected error: {err}"),
         }
     }
+
+    #[cfg(feature = "compression")]
+    async fn compression_static_variant(order: &str, accept_encoding: &'static str) -> String {
+        let opts = fixture_settings("toml/handler_fixtures.toml");
+        let general = General {
+            root: PathBuf::from("tests/fixtures/compression_static"),
+            compression: false,
+            compression_static: true,
+            compression_static_order: order.to_owned(),
+            ..opts.general
+        };
+        let req_handler_opts = fixture_req_handler_opts(general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+
+        let mut req = Request::default();
+        *req.method_mut() = hyper::Method::GET;
+        *req.uri_mut() = "http://localhost/app.js".parse().unwrap();
+        req.headers_mut().insert(
+            http::header::ACCEPT_ENCODING,
+            accept_encoding.parse().unwrap(),
+        );
+
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => {
+                assert_eq!(res.status(), 200);
+                res.headers()
+                    .get("content-encoding")
+                    .map(|v| v.to_str().unwrap().to_owned())
+                    .unwrap_or_default()
+            }
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn compression_static_variants_order() {
+        // Default order prefers zstd over gzip
+        assert_eq!(
+            compression_static_variant("br,zstd,gzip", "gzip, zstd").await,
+            "zstd"
+        );
+        // Custom order for equal quality values
+        assert_eq!(
+            compression_static_variant("gzip,zstd", "gzip, zstd").await,
+            "gzip"
+        );
+        // Quality values take precedence over the configured order
+        assert_eq!(
+            compression_static_variant("zstd,gzip", "gzip, zstd;q=0.5").await,
+            "gzip"
+        );
+        // Variants with a zero quality value are not acceptable
+        assert_eq!(
+            compression_static_variant("zstd,gzip", "gzip;q=0.2, zstd;q=0").await,
+            "gzip"
+        );
+        // Variants not configured are not looked up
+        assert_eq!(compression_static_variant("zstd", "gzip").await, "");
+    }
 }
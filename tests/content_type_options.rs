@@ -0,0 +1,63 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::{Body, Request, Response};
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    async fn request(uri: &str) -> Response<Body> {
+        let opts = fixture_settings("toml/content_type_options.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.uri_mut() = uri.parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    fn content_type_options(res: &Response<Body>) -> Option<&str> {
+        res.headers()
+            .get("x-content-type-options")
+            .map(|v| v.to_str().unwrap())
+    }
+
+    #[tokio::test]
+    async fn content_type_options_file_policies() {
+        for (uri, expected) in [
+            ("http://localhost/assets/main.js", Some("nosniff")),
+            ("http://localhost/assets/main.css", None),
+            ("http://localhost/index.htm", None),
+        ] {
+            let res = request(uri).await;
+            assert_eq!(res.status(), 200, "{uri}");
+            assert_eq!(content_type_options(&res), expected, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn content_type_options_generated_responses() {
+        for (uri, status) in [
+            ("http://localhost/unknown.html", 404),
+            // Even if a policy disables the header for the matching files
+            ("http://localhost/assets/unknown.css", 404),
+            // Directory listing
+            ("http://localhost/sp%C3%A9cial-direct%C3%B6ry.net/", 200),
+        ] {
+            let res = request(uri).await;
+            assert_eq!(res.status(), status, "{uri}");
+            assert_eq!(content_type_options(&res), Some("nosniff"), "{uri}");
+        }
+    }
+}
@@ -270,4 +270,105 @@ mod tests {
             };
         }
     }
+
+    async fn policy_request(
+        method: Method,
+        uri: &str,
+        headers: &[(&'static str, &str)],
+    ) -> hyper::Response<hyper::Body> {
+        let settings = fixture_settings("toml/cors_policies.toml");
+        let req_handler_opts = fixture_req_handler_opts(settings.general, settings.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.method_mut() = method;
+        *req.uri_mut() = uri.parse().unwrap();
+        for (name, value) in headers {
+            req.headers_mut()
+                .insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        req_handler.handle(&mut req, remote_addr).await.unwrap()
+    }
+
+    fn header<'a>(resp: &'a hyper::Response<hyper::Body>, name: &str) -> Option<&'a str> {
+        resp.headers().get(name).map(|v| v.to_str().unwrap())
+    }
+
+    #[tokio::test]
+    async fn handler_path_policies() {
+        // The root policy allows any origin without credentials
+        let resp = policy_request(
+            Method::GET,
+            "http://localhost/index.htm",
+            &[("origin", "https://any.example.com")],
+        )
+        .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            header(&resp, "access-control-allow-origin"),
+            Some("https://any.example.com")
+        );
+        assert_eq!(header(&resp, "access-control-allow-credentials"), None);
+
+        // The longest path prefix policy allows credentials for its origin only
+        let resp = policy_request(
+            Method::GET,
+            "http://localhost/assets/main.js",
+            &[("origin", "https://docs.example.com")],
+        )
+        .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            header(&resp, "access-control-allow-origin"),
+            Some("https://docs.example.com")
+        );
+        assert_eq!(
+            header(&resp, "access-control-allow-credentials"),
+            Some("true")
+        );
+        assert_eq!(header(&resp, "access-control-expose-headers"), Some("etag"));
+
+        let resp = policy_request(
+            Method::GET,
+            "http://localhost/assets/main.js",
+            &[("origin", "https://any.example.com")],
+        )
+        .await;
+        assert_eq!(resp.status(), 403);
+    }
+
+    #[tokio::test]
+    async fn handler_path_policy_preflight() {
+        let resp = policy_request(
+            Method::OPTIONS,
+            "http://localhost/assets/main.js",
+            &[
+                ("origin", "https://docs.example.com"),
+                ("access-control-request-method", "POST"),
+                ("access-control-request-headers", "authorization"),
+            ],
+        )
+        .await;
+        assert_eq!(header(&resp, "access-control-max-age"), Some("600"));
+        assert_eq!(
+            header(&resp, "access-control-allow-credentials"),
+            Some("true")
+        );
+        let allow_methods = header(&resp, "access-control-allow-methods").unwrap();
+        assert!(allow_methods.contains("POST"), "{allow_methods}");
+
+        // The headers not allowed by the policy are forbidden
+        let resp = policy_request(
+            Method::OPTIONS,
+            "http://localhost/assets/main.js",
+            &[
+                ("origin", "https://docs.example.com"),
+                ("access-control-request-method", "GET"),
+                ("access-control-request-headers", "x-custom"),
+            ],
+        )
+        .await;
+        assert_eq!(resp.status(), 403);
+    }
 }
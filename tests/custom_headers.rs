@@ -0,0 +1,60 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::Request;
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    #[tokio::test]
+    async fn custom_headers_conditions_and_variables() {
+        let opts = fixture_settings("toml/custom_headers.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        // HTML responses get the CSP header but not the `Cache-Control` one
+        let mut req = Request::default();
+        *req.uri_mut() = "http://localhost/assets/".parse().unwrap();
+        let res = req_handler.handle(&mut req, remote_addr).await.unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(
+            res.headers()["content-security-policy"],
+            "default-src 'self' https://cdn.localhost"
+        );
+        assert_eq!(
+            res.headers()["link"],
+            "<https://localhost/assets/>; rel=\"canonical\""
+        );
+        assert!(res.headers().get("cache-control").is_none());
+
+        // Other statuses and content types are left untouched
+        for uri in [
+            "http://localhost/not-found.html",
+            "http://localhost/assets/main.js",
+        ] {
+            let mut req = Request::default();
+            *req.uri_mut() = uri.parse().unwrap();
+            let res = req_handler.handle(&mut req, remote_addr).await.unwrap();
+            assert!(
+                res.headers().get("content-security-policy").is_none(),
+                "{uri}"
+            );
+            assert!(res.headers().get("cache-control").is_some(), "{uri}");
+        }
+
+        let mut req = Request::default();
+        *req.uri_mut() = "http://localhost/assets/main.js".parse().unwrap();
+        let res = req_handler.handle(&mut req, remote_addr).await.unwrap();
+        assert_eq!(
+            res.headers()["content-disposition"],
+            "inline; filename=\"main.js\""
+        );
+    }
+}
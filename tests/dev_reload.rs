@@ -0,0 +1,116 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::body::HttpBody;
+    use hyper::{Body, Method, Request, Response};
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    use static_web_server::handler::{RequestHandler, RequestHandlerOpts};
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    fn handler_opts() -> RequestHandlerOpts {
+        let opts = fixture_settings("toml/dev_reload.toml");
+        fixture_req_handler_opts(opts.general, opts.advanced)
+    }
+
+    async fn request(req_handler: &RequestHandler, method: Method, uri: &str) -> Response<Body> {
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+        let mut req = Request::default();
+        *req.method_mut() = method;
+        *req.uri_mut() = uri.parse().unwrap();
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    /// Reads the next chunk of an event stream.
+    async fn next_event(body: &mut Body) -> String {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), body.data())
+            .await
+            .expect("event stream timed out")
+            .unwrap()
+            .unwrap();
+        String::from_utf8_lossy(&chunk).into_owned()
+    }
+
+    #[tokio::test]
+    async fn dev_reload_script_injection() {
+        let req_handler = fixture_req_handler(handler_opts());
+        let res = request(&req_handler, Method::GET, "http://localhost/index.htm").await;
+        assert_eq!(res.status(), 200);
+        assert!(!res.headers().contains_key("accept-ranges"));
+        let len = res.headers()["content-length"].to_str().unwrap().to_owned();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(len, body.len().to_string());
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.starts_with("<h1>this is a custom index file</h1>"));
+        assert!(body.contains("<script>new EventSource(\"/__sws/reload\")"));
+
+        // The HEAD responses announce the length of the injected content
+        let res = request(&req_handler, Method::HEAD, "http://localhost/index.htm").await;
+        assert_eq!(res.headers()["content-length"], len.as_str());
+
+        // The error pages get the script as well
+        let res = request(&req_handler, Method::GET, "http://localhost/missing.html").await;
+        assert_eq!(res.status(), 404);
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("/__sws/reload"));
+
+        // Other content types are not modified
+        let res = request(&req_handler, Method::GET, "http://localhost/assets/main.js").await;
+        assert_eq!(res.status(), 200);
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert!(!String::from_utf8_lossy(&body).contains("/__sws/reload"));
+    }
+
+    #[tokio::test]
+    async fn dev_reload_without_injection() {
+        let mut opts = handler_opts();
+        static_web_server::dev_reload::init(true, false, &mut opts);
+        let req_handler = fixture_req_handler(opts);
+        let res = request(&req_handler, Method::GET, "http://localhost/index.htm").await;
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert!(!String::from_utf8_lossy(&body).contains("/__sws/reload"));
+    }
+
+    #[tokio::test]
+    async fn dev_reload_events() {
+        let root =
+            std::env::temp_dir().join(format!("sws-dev-reload-events-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("index.html"), "<p>v1</p>").unwrap();
+
+        let mut opts = handler_opts();
+        opts.root_dir = root.clone();
+        let req_handler = fixture_req_handler(opts);
+
+        let res = request(&req_handler, Method::GET, "http://localhost/__sws/reload").await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "text/event-stream");
+        assert_eq!(res.headers()["cache-control"], "no-cache");
+        let mut body = res.into_body();
+        assert_eq!(next_event(&mut body).await, "retry: 1000\n\n");
+
+        // The file changes are watched with the `cache-invalidation` feature only
+        #[cfg(feature = "cache-invalidation")]
+        {
+            // Let the watcher start watching the directories before changing the files
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            std::fs::write(root.join("about.html"), "<p>about</p>").unwrap();
+            assert_eq!(
+                next_event(&mut body).await,
+                "event: reload\ndata: reload\n\n"
+            );
+        }
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
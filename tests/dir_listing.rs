@@ -13,6 +13,10 @@ mod tests {
 
     use static_web_server::{
         directory_listing::DirListFmt,
+        directory_listing_locale::DirListLocales,
+        directory_listing_size::DirSizeCache,
+        directory_listing_sort::DirListSort,
+        fs::symlinks::FollowSymlinks,
         static_files::{self, HandleOpts},
     };
 
@@ -44,16 +48,33 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir("tests/fixtures/public"),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/symlink",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 dir_listing: true,
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 dir_listing_format: &DirListFmt::Html,
+                dir_listing_dir_size: None,
+                dir_listing_details: false,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
                 #[cfg(feature = "directory-listing-download")]
@@ -81,16 +102,33 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir("docs/"),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/content/",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 dir_listing: true,
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 dir_listing_format: &DirListFmt::Html,
+                dir_listing_dir_size: None,
+                dir_listing_details: false,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
                 #[cfg(feature = "directory-listing-download")]
@@ -128,16 +166,33 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir("docs/"),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/content",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 dir_listing: true,
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 dir_listing_format: &DirListFmt::Html,
+                dir_listing_dir_size: None,
+                dir_listing_details: false,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
                 redirect_trailing_slash: false,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
                 #[cfg(feature = "directory-listing-download")]
@@ -175,16 +230,33 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir("docs/"),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/README.md",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 dir_listing: true,
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 dir_listing_format: &DirListFmt::Html,
+                dir_listing_dir_size: None,
+                dir_listing_details: false,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
                 redirect_trailing_slash: false,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
                 #[cfg(feature = "directory-listing-download")]
@@ -212,16 +284,33 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir("tests/fixtures/public/"),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 dir_listing: true,
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 dir_listing_format: &DirListFmt::Html,
+                dir_listing_dir_size: None,
+                dir_listing_details: false,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
                 #[cfg(feature = "directory-listing-download")]
@@ -270,16 +359,33 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir("tests/fixtures/public/"),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 dir_listing: true,
-                dir_listing_order: 1,
+                dir_listing_sort: &DirListSort::from_code(1),
                 dir_listing_format: &DirListFmt::Json,
+                dir_listing_dir_size: None,
+                dir_listing_details: false,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: true,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
                 #[cfg(feature = "directory-listing-download")]
@@ -324,6 +430,320 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn dir_listing_json_format_dir_size() {
+        #[derive(Serialize, Deserialize)]
+        struct FileEntry {
+            name: String,
+            #[serde(rename = "type")]
+            typed: String,
+            mtime: String,
+            size: Option<u64>,
+        }
+
+        let assets_size: u64 = std::fs::read_dir("tests/fixtures/public/assets")
+            .unwrap()
+            .map(|e| e.unwrap().metadata().unwrap().len())
+            .sum();
+        let dir_size = DirSizeCache::new(300, 1000, true);
+
+        // Sizes are computed in the background so the first listings may not include them yet
+        let mut size = None;
+        for _ in 0..100 {
+            let result = static_files::handle(&HandleOpts {
+                method: &Method::GET,
+                headers: &HeaderMap::new(),
+                base_path: &root_dir("tests/fixtures/public/"),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
+                uri_path: "/",
+                uri_query: None,
+                #[cfg(feature = "experimental")]
+                memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
+                dir_listing: true,
+                dir_listing_sort: &DirListSort::from_code(1),
+                dir_listing_format: &DirListFmt::Json,
+                dir_listing_dir_size: Some(&dir_size),
+                dir_listing_details: false,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
+                redirect_trailing_slash: true,
+                compression_static: false,
+                compression_static_order: &[],
+                ignore_hidden_files: true,
+                ignore_patterns: None,
+                disable_symlinks: false,
+                index_files: &[],
+                #[cfg(feature = "directory-listing-download")]
+                dir_listing_download: &[],
+            })
+            .await
+            .expect("unexpected directory listing error");
+
+            let mut res = result.resp;
+            assert_eq!(res.status(), 200);
+
+            let body = hyper::body::to_bytes(res.body_mut())
+                .await
+                .expect("unexpected bytes error during `body` conversion");
+            let entries: Vec<FileEntry> = serde_json::from_slice(&body).unwrap();
+            let assets = entries.iter().find(|e| e.name == "assets").unwrap();
+            assert_eq!(assets.typed, "directory");
+            assert!(!assets.mtime.is_empty());
+
+            size = assets.size;
+            if size.is_some() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+
+        assert_eq!(size, Some(assets_size));
+    }
+
+    #[tokio::test]
+    async fn dir_listing_json_format_sort_keys() {
+        #[derive(Serialize, Deserialize)]
+        struct FileEntry {
+            name: String,
+            #[serde(rename = "type")]
+            typed: String,
+            size: Option<u64>,
+        }
+
+        let sort: DirListSort = "size:desc,name".parse().unwrap();
+        let sort = sort.dirs_first(true);
+
+        for (uri_query, dirs_first) in [(None, true), (Some("dirs-first=false"), false)] {
+            let result = static_files::handle(&HandleOpts {
+                method: &Method::GET,
+                headers: &HeaderMap::new(),
+                base_path: &root_dir("tests/fixtures/public/"),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
+                uri_path: "/",
+                uri_query,
+                #[cfg(feature = "experimental")]
+                memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
+                dir_listing: true,
+                dir_listing_sort: &sort,
+                dir_listing_format: &DirListFmt::Json,
+                dir_listing_dir_size: None,
+                dir_listing_details: false,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
+                redirect_trailing_slash: true,
+                compression_static: false,
+                compression_static_order: &[],
+                ignore_hidden_files: true,
+                ignore_patterns: None,
+                disable_symlinks: false,
+                index_files: &[],
+                #[cfg(feature = "directory-listing-download")]
+                dir_listing_download: &[],
+            })
+            .await
+            .expect("unexpected directory listing error");
+
+            let mut res = result.resp;
+            assert_eq!(res.status(), 200);
+            let body = hyper::body::to_bytes(res.body_mut())
+                .await
+                .expect("unexpected bytes error during `body` conversion");
+            let entries: Vec<FileEntry> = serde_json::from_slice(&body).unwrap();
+
+            // The files are sorted by size in descending order
+            let files: Vec<&FileEntry> = entries.iter().filter(|e| e.typed == "file").collect();
+            assert!(files.windows(2).all(|w| w[0].size >= w[1].size));
+
+            // The directories have no size so they are sorted by name
+            let dirs: Vec<&str> = entries
+                .iter()
+                .filter(|e| e.typed == "directory")
+                .map(|e| e.name.as_str())
+                .collect();
+            let mut sorted_dirs = dirs.clone();
+            sorted_dirs.sort_by_key(|name| name.to_lowercase());
+            assert_eq!(dirs, sorted_dirs);
+
+            let first_file = entries.iter().position(|e| e.typed == "file").unwrap();
+            if dirs_first {
+                assert_eq!(first_file, dirs.len());
+            } else {
+                assert_eq!(first_file, 0);
+                assert_eq!(entries.last().unwrap().typed, "directory");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn dir_listing_details() {
+        #[derive(Serialize, Deserialize)]
+        struct FileEntry {
+            name: String,
+            #[serde(rename = "type")]
+            typed: String,
+            target: Option<String>,
+            mode: Option<String>,
+            uid: Option<u32>,
+        }
+
+        for format in [DirListFmt::Json, DirListFmt::Html] {
+            let result = static_files::handle(&HandleOpts {
+                method: &Method::GET,
+                headers: &HeaderMap::new(),
+                base_path: &root_dir("tests/fixtures/public/"),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
+                uri_path: "/",
+                uri_query: None,
+                #[cfg(feature = "experimental")]
+                memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
+                dir_listing: true,
+                dir_listing_sort: &DirListSort::from_code(1),
+                dir_listing_format: &format,
+                dir_listing_dir_size: None,
+                dir_listing_details: true,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
+                redirect_trailing_slash: true,
+                compression_static: false,
+                compression_static_order: &[],
+                ignore_hidden_files: true,
+                ignore_patterns: None,
+                disable_symlinks: false,
+                index_files: &[],
+                #[cfg(feature = "directory-listing-download")]
+                dir_listing_download: &[],
+            })
+            .await
+            .expect("unexpected directory listing error");
+
+            let mut res = result.resp;
+            assert_eq!(res.status(), 200);
+
+            let body = hyper::body::to_bytes(res.body_mut())
+                .await
+                .expect("unexpected bytes error during `body` conversion");
+            let body_str = std::str::from_utf8(&body).unwrap();
+
+            match format {
+                DirListFmt::Json => {
+                    let entries: Vec<FileEntry> = serde_json::from_str(body_str).unwrap();
+
+                    let symlink = entries.iter().find(|e| e.name == "symlink").unwrap();
+                    assert_eq!(symlink.typed, "directory");
+                    assert_eq!(symlink.target.as_deref(), Some("./spécial-directöry.net"));
+
+                    let file = entries.iter().find(|e| e.name == "404.html").unwrap();
+                    assert_eq!(file.typed, "file");
+                    assert!(file.target.is_none());
+
+                    #[cfg(unix)]
+                    {
+                        assert!(symlink.mode.as_ref().unwrap().starts_with('l'));
+                        assert!(file.mode.as_ref().unwrap().starts_with('-'));
+                        assert!(file.uid.is_some());
+                    }
+                }
+                DirListFmt::Html => {
+                    assert!(
+                        body_str.contains("<th style=\"width:5rem;padding-left:1rem;\">Type</th>")
+                    );
+                    assert!(body_str.contains(" -&gt; ./spécial-directöry.net"));
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn dir_listing_locale() {
+        let locales = DirListLocales::new("auto", None).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("accept-language", "fr-CH, de;q=0.8".parse().unwrap());
+
+        for (locale, title, vary) in [
+            (None, "Index de /assets/", true),
+            (Some("de"), "Index von /assets/", false),
+        ] {
+            let result = static_files::handle(&HandleOpts {
+                method: &Method::GET,
+                headers: &headers,
+                base_path: &root_dir("tests/fixtures/public/"),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
+                uri_path: "/assets/",
+                uri_query: None,
+                #[cfg(feature = "experimental")]
+                memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
+                dir_listing: true,
+                dir_listing_sort: &DirListSort::from_code(1),
+                dir_listing_format: &DirListFmt::Html,
+                dir_listing_dir_size: None,
+                dir_listing_details: false,
+                dir_listing_locales: Some(&locales),
+                dir_listing_locale: locale,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
+                redirect_trailing_slash: true,
+                compression_static: false,
+                compression_static_order: &[],
+                ignore_hidden_files: true,
+                ignore_patterns: None,
+                disable_symlinks: false,
+                index_files: &["none.html"],
+                #[cfg(feature = "directory-listing-download")]
+                dir_listing_download: &[],
+            })
+            .await
+            .expect("unexpected directory listing error");
+
+            let mut res = result.resp;
+            assert_eq!(res.status(), 200);
+            assert_eq!(res.headers().contains_key("vary"), vary);
+
+            let body = hyper::body::to_bytes(res.body_mut())
+                .await
+                .expect("unexpected bytes error during `body` conversion");
+            let body_str = std::str::from_utf8(&body).unwrap();
+            assert!(body_str.contains(&format!("<h1>{title}</h1>")));
+        }
+    }
+
     #[tokio::test]
     async fn dir_listing_json_format_empty() {
         #[derive(Serialize, Deserialize)]
@@ -346,16 +766,33 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir(&empty_dir),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 dir_listing: true,
-                dir_listing_order: 1,
+                dir_listing_sort: &DirListSort::from_code(1),
                 dir_listing_format: &DirListFmt::Json,
+                dir_listing_dir_size: None,
+                dir_listing_details: false,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
                 #[cfg(feature = "directory-listing-download")]
@@ -395,16 +832,33 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir("tests/fixtures/public"),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 dir_listing: true,
-                dir_listing_order: 1,
+                dir_listing_sort: &DirListSort::from_code(1),
                 dir_listing_format: &DirListFmt::Html,
+                dir_listing_dir_size: None,
+                dir_listing_details: false,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: true,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
                 #[cfg(feature = "directory-listing-download")]
@@ -444,16 +898,33 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir("tests/fixtures/public"),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 dir_listing: true,
-                dir_listing_order: 1,
+                dir_listing_sort: &DirListSort::from_code(1),
                 dir_listing_format: &DirListFmt::Html,
+                dir_listing_dir_size: None,
+                dir_listing_details: false,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: true,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
                 dir_listing_download: &[DirDownloadFmt::Targz],
@@ -492,16 +963,33 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir("tests/fixtures/public"),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 dir_listing: true,
-                dir_listing_order: 1,
+                dir_listing_sort: &DirListSort::from_code(1),
                 dir_listing_format: &DirListFmt::Html,
+                dir_listing_dir_size: None,
+                dir_listing_details: false,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: true,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
                 dir_listing_download: &[],
@@ -22,6 +22,8 @@ mod tests {
     use static_web_server::{
         directory_listing::DirListFmt,
         directory_listing_download::DirDownloadOpts,
+        directory_listing_sort::DirListSort,
+        fs::symlinks::FollowSymlinks,
         static_files::{self, HandleOpts},
     };
 
@@ -128,16 +130,33 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &base_path,
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/",
                 uri_query: Some(DOWNLOAD_PARAM_KEY),
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 dir_listing: true,
-                dir_listing_order: 1,
+                dir_listing_sort: &DirListSort::from_code(1),
                 dir_listing_format: &DirListFmt::Html,
+                dir_listing_dir_size: None,
+                dir_listing_details: false,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks,
                 index_files: &[],
                 dir_listing_download: &[DirDownloadFmt::Targz],
@@ -170,6 +189,8 @@ mod tests {
                                 method: &method,
                                 disable_symlinks,
                                 ignore_hidden_files: false,
+                                ignore_patterns: None,
+                                root_dir: &base_path,
                             },
                         )
                         .await;
@@ -200,16 +221,33 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &base_path,
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/",
                 uri_query: Some(DOWNLOAD_PARAM_KEY),
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 dir_listing: true,
-                dir_listing_order: 1,
+                dir_listing_sort: &DirListSort::from_code(1),
                 dir_listing_format: &DirListFmt::Html,
+                dir_listing_dir_size: None,
+                dir_listing_details: false,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: true,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
                 dir_listing_download: &[DirDownloadFmt::Targz],
@@ -261,16 +299,33 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &base_path,
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/",
                 uri_query: Some(DOWNLOAD_PARAM_KEY),
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 dir_listing: true,
-                dir_listing_order: 1,
+                dir_listing_sort: &DirListSort::from_code(1),
                 dir_listing_format: &DirListFmt::Html,
+                dir_listing_dir_size: None,
+                dir_listing_details: false,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks,
                 index_files: &[],
                 dir_listing_download: &[DirDownloadFmt::Targz],
@@ -303,6 +358,8 @@ mod tests {
                                 method: &method,
                                 disable_symlinks,
                                 ignore_hidden_files: false,
+                                ignore_patterns: None,
+                                root_dir: &base_path,
                             },
                         )
                         .await;
@@ -332,16 +389,33 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir("tests/fixtures/public"),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/",
                 uri_query: Some(DOWNLOAD_PARAM_KEY),
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 dir_listing: true,
-                dir_listing_order: 1,
+                dir_listing_sort: &DirListSort::from_code(1),
                 dir_listing_format: &DirListFmt::Html,
+                dir_listing_dir_size: None,
+                dir_listing_details: false,
+                dir_listing_locales: None,
+                dir_listing_locale: None,
+                dir_listing_hide: None,
+                dir_listing_readme: None,
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
                 dir_listing_download: &[],
@@ -0,0 +1,55 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(feature = "directory-listing")]
+#[cfg(test)]
+pub mod tests {
+    use hyper::{Body, Request, Response};
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    async fn request(uri: &str) -> Response<Body> {
+        let opts = fixture_settings("toml/directory_listing_paths.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.uri_mut() = uri.parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn dir_listing_paths_allowed() {
+        for uri in [
+            "http://localhost/downloads/",
+            "http://localhost/downloads/archive/",
+        ] {
+            let res = request(uri).await;
+            assert_eq!(res.status(), 200, "{uri}");
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            assert!(String::from_utf8_lossy(&body).contains("Index of"), "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn dir_listing_paths_disallowed() {
+        for uri in ["http://localhost/", "http://localhost/private/"] {
+            let res = request(uri).await;
+            assert_eq!(res.status(), 404, "{uri}");
+        }
+
+        // Files are served anyway
+        let res = request("http://localhost/private/notes.txt").await;
+        assert_eq!(res.status(), 200);
+    }
+}
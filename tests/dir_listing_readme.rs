@@ -0,0 +1,78 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(feature = "directory-listing")]
+#[cfg(test)]
+pub mod tests {
+    use hyper::{Body, Request, Response};
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    async fn request(uri: &str) -> Response<Body> {
+        let opts = fixture_settings("toml/directory_listing_readme.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.uri_mut() = uri.parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn dir_listing_hide_patterns() {
+        let res = request("http://localhost/mirror/").await;
+        assert_eq!(res.status(), 200);
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("href=\"release.tar.gz\""));
+        assert!(!body.contains("release.tar.gz.sig"));
+        assert!(!body.contains("href=\"HEADER.html\""));
+        assert!(!body.contains("href=\"README.md\""));
+
+        // Hidden entries are served anyway
+        for uri in [
+            "http://localhost/mirror/release.tar.gz.sig",
+            "http://localhost/mirror/README.md",
+        ] {
+            let res = request(uri).await;
+            assert_eq!(res.status(), 200, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn dir_listing_header_readme() {
+        let res = request("http://localhost/mirror/").await;
+        assert_eq!(res.status(), 200);
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let body = String::from_utf8_lossy(&body);
+
+        let header = body
+            .find("<p class=\"mirror\">Official release mirror</p>")
+            .expect("missing header");
+        let entries = body.find("href=\"release.tar.gz\"").unwrap();
+        assert!(header < entries);
+
+        #[cfg(feature = "markdown")]
+        let readme = body.find("<h1>Releases</h1>").expect("missing readme");
+        #[cfg(not(feature = "markdown"))]
+        let readme = body.find("<pre># Releases").expect("missing readme");
+        assert!(entries < readme);
+
+        // Directories without header and readme files are listed as usual
+        let res = request("http://localhost/downloads/").await;
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(!body.contains("class=\"header\""));
+        assert!(!body.contains("class=\"readme\""));
+    }
+}
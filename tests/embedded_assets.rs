@@ -0,0 +1,95 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use headers::HeaderMapExt;
+    use hyper::{Body, Request, Response};
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    use static_web_server::embedded::{EmbeddedAssets, EmbeddedAssetsOpts, EmbeddedFile};
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    static FILES: &[EmbeddedFile] = &[
+        EmbeddedFile {
+            path: "style.css",
+            contents: b"body { color: red; }\n",
+            modified: None,
+        },
+        EmbeddedFile {
+            path: "dashboard/index.html",
+            contents: b"<h1>dashboard</h1>\n",
+            modified: Some(1_700_000_000),
+        },
+    ];
+
+    async fn request(uri: &str, only: bool, range: Option<&str>) -> Response<Body> {
+        let opts = fixture_settings("toml/embedded_assets.toml");
+        let mut req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        req_handler_opts.embedded_assets = Some(EmbeddedAssetsOpts {
+            source: Arc::new(EmbeddedAssets::new(FILES)),
+            only,
+        });
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.uri_mut() = uri.parse().unwrap();
+        if let Some(range) = range {
+            req.headers_mut().insert("range", range.parse().unwrap());
+        }
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    async fn body(res: Response<Body>) -> String {
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        String::from_utf8_lossy(&body).into_owned()
+    }
+
+    #[tokio::test]
+    async fn embedded_assets_overlay() {
+        // The root directory overrides the embedded assets
+        let res = request("http://localhost/style.css", false, None).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(body(res).await, "body { color: black; }\n");
+
+        let res = request("http://localhost/dashboard/", false, None).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "text/html");
+        assert!(res.headers().typed_get::<headers::LastModified>().is_some());
+        assert_eq!(body(res).await, "<h1>dashboard</h1>\n");
+
+        let res = request("http://localhost/dashboard", false, None).await;
+        assert_eq!(res.status(), 308);
+        assert_eq!(res.headers()["location"], "/dashboard/");
+
+        let res = request("http://localhost/missing.js", false, None).await;
+        assert_eq!(res.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn embedded_assets_only() {
+        let res = request("http://localhost/style.css", true, None).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "text/css");
+        assert_eq!(body(res).await, "body { color: red; }\n");
+
+        // The root directory is not accessed
+        let res = request("http://localhost/index.html", true, None).await;
+        assert_eq!(res.status(), 404);
+
+        let res = request("http://localhost/style.css", true, Some("bytes=0-3")).await;
+        assert_eq!(res.status(), 206);
+        assert_eq!(res.headers()["content-range"], "bytes 0-3/21");
+        assert_eq!(body(res).await, "body");
+    }
+}
@@ -0,0 +1,76 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::{Method, Request, StatusCode};
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    async fn response(method: Method, uri: &str) -> (StatusCode, String) {
+        let opts = fixture_settings("toml/error_pages.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.method_mut() = method;
+        *req.uri_mut() = uri.parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => {
+                let status = res.status();
+                let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+                (status, String::from_utf8_lossy(&body_bytes).into_owned())
+            }
+            Err(err) => {
+                panic!("unexpected error: {err}")
+            }
+        }
+    }
+
+    fn page(path: &str) -> String {
+        std::fs::read_to_string(path).unwrap().trim().to_owned()
+    }
+
+    #[tokio::test]
+    async fn error_pages_status_code() {
+        let (status, body) = response(Method::GET, "http://localhost/archive/2019.html").await;
+        assert_eq!(status, StatusCode::GONE);
+        assert_eq!(body, page("tests/fixtures/error_pages/410.html"));
+    }
+
+    #[tokio::test]
+    async fn error_pages_status_class() {
+        let (status, body) = response(Method::POST, "http://localhost/index.html").await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        assert!(body.contains("<title>405 Method Not Allowed</title>"));
+        assert!(body.contains("Something is wrong with the request to /index.html"));
+        assert!(!body.contains("{{"));
+    }
+
+    #[tokio::test]
+    async fn error_pages_template_request_id() {
+        let (_, body) = response(Method::POST, "http://localhost/index.html").await;
+        let request_id = body
+            .split("<code>")
+            .nth(1)
+            .and_then(|s| s.split("</code>").next())
+            .unwrap();
+        assert_eq!(request_id.len(), 16);
+        assert!(request_id.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[tokio::test]
+    async fn error_pages_page404_precedence() {
+        // The `404` page is more specific than the `4xx` one
+        let (status, body) = response(Method::GET, "http://localhost/missing.html").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body, page("tests/fixtures/public/404.html"));
+    }
+}
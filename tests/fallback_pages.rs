@@ -0,0 +1,133 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(feature = "fallback-page")]
+#[cfg(test)]
+pub mod tests {
+    use hyper::{Body, Method, Request, Response, StatusCode};
+    use std::net::SocketAddr;
+
+    use static_web_server::handler::RequestHandler;
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    fn req_handler(html_only: bool, status: StatusCode) -> RequestHandler {
+        let mut opts = fixture_settings("toml/fallback_pages.toml");
+        opts.general.page_fallback_html_only = html_only;
+        opts.general.page_fallback_status = status;
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        fixture_req_handler(req_handler_opts)
+    }
+
+    async fn response(
+        req_handler: &RequestHandler,
+        method: Method,
+        uri: &str,
+        accept: Option<&str>,
+    ) -> Response<Body> {
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.method_mut() = method;
+        *req.uri_mut() = uri.parse().unwrap();
+        if let Some(accept) = accept {
+            req.headers_mut().insert("accept", accept.parse().unwrap());
+        }
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("unexpected error: {err}")
+            }
+        }
+    }
+
+    async fn body(res: Response<Body>) -> String {
+        let body = hyper::body::to_bytes(res.into_body())
+            .await
+            .expect("unexpected bytes error during `body` conversion");
+        String::from_utf8_lossy(&body).into_owned()
+    }
+
+    fn varies_by_accept(res: &Response<Body>) -> bool {
+        let vary = res.headers()["vary"].to_str().unwrap();
+        vary.split(',').any(|name| name.trim() == "accept")
+    }
+
+    #[tokio::test]
+    async fn fallback_pages_by_path() {
+        let req_handler = req_handler(false, StatusCode::OK);
+        for (uri, app) in [
+            ("http://localhost/app1/users/1", "app1"),
+            ("http://localhost/app2/", "app2"),
+            ("http://localhost/app2/settings?tab=2", "app2"),
+        ] {
+            let res = response(&req_handler, Method::GET, uri, None).await;
+            assert_eq!(res.status(), 200, "{uri}");
+            assert_eq!(res.headers()["content-type"], "text/html; charset=utf-8");
+            assert!(body(res).await.contains(&format!("id=\"{app}\"")), "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn fallback_pages_not_found() {
+        let req_handler = req_handler(false, StatusCode::OK);
+
+        // Paths matching no entry and other methods are not replaced
+        let res = response(
+            &req_handler,
+            Method::GET,
+            "http://localhost/app3/users",
+            None,
+        )
+        .await;
+        assert_eq!(res.status(), 404);
+        let res = response(
+            &req_handler,
+            Method::HEAD,
+            "http://localhost/app1/users",
+            None,
+        )
+        .await;
+        assert_eq!(res.status(), 404);
+        let res = response(
+            &req_handler,
+            Method::POST,
+            "http://localhost/app1/users",
+            None,
+        )
+        .await;
+        assert_eq!(res.status(), 405);
+
+        // Existing files are served as usual
+        let res = response(
+            &req_handler,
+            Method::GET,
+            "http://localhost/assets/main.js",
+            None,
+        )
+        .await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "text/javascript");
+    }
+
+    #[tokio::test]
+    async fn fallback_pages_html_only() {
+        let req_handler = req_handler(true, StatusCode::NOT_FOUND);
+        let uri = "http://localhost/app1/data/users.json";
+
+        let res = response(&req_handler, Method::GET, uri, Some("application/json")).await;
+        assert_eq!(res.status(), 404);
+        assert!(varies_by_accept(&res));
+        assert!(!body(res).await.contains("id=\"app1\""));
+
+        let accept = Some("text/html,application/xhtml+xml,*/*;q=0.8");
+        let res = response(&req_handler, Method::GET, uri, accept).await;
+        assert_eq!(res.status(), 404);
+        assert!(varies_by_accept(&res));
+        assert!(body(res).await.contains("id=\"app1\""));
+    }
+}
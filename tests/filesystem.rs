@@ -0,0 +1,177 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use bytes::Bytes;
+    use hyper::{Body, Request, Response};
+    use std::io;
+    use std::net::SocketAddr;
+    use std::ops::Range;
+    use std::sync::Arc;
+
+    use static_web_server::embedded::EmbeddedAssetsOpts;
+    use static_web_server::fs::vfs::{
+        Filesystem, LocalFilesystem, VfsDirEntry, VfsFuture, VfsMetadata,
+    };
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    /// File system backend holding a single `/hello.txt` file in memory.
+    struct HelloFilesystem;
+
+    const HELLO: &[u8] = b"hello from memory\n";
+
+    impl Filesystem for HelloFilesystem {
+        fn metadata<'a>(&'a self, path: &'a str) -> VfsFuture<'a, VfsMetadata> {
+            Box::pin(async move {
+                match path {
+                    "" => Ok(VfsMetadata {
+                        is_dir: true,
+                        ..Default::default()
+                    }),
+                    "hello.txt" => Ok(VfsMetadata {
+                        len: HELLO.len() as u64,
+                        ..Default::default()
+                    }),
+                    _ => Err(io::ErrorKind::NotFound.into()),
+                }
+            })
+        }
+
+        fn read_range<'a>(&'a self, path: &'a str, range: Range<u64>) -> VfsFuture<'a, Bytes> {
+            Box::pin(async move {
+                match path {
+                    "hello.txt" => Ok(Bytes::from_static(
+                        &HELLO[range.start as usize..range.end as usize],
+                    )),
+                    _ => Err(io::ErrorKind::NotFound.into()),
+                }
+            })
+        }
+
+        fn read_dir<'a>(&'a self, path: &'a str) -> VfsFuture<'a, Vec<VfsDirEntry>> {
+            Box::pin(async move {
+                match path {
+                    "" => Ok(vec![VfsDirEntry {
+                        name: "hello.txt".to_owned(),
+                        metadata: self.metadata("hello.txt").await?,
+                    }]),
+                    _ => Err(io::ErrorKind::NotFound.into()),
+                }
+            })
+        }
+    }
+
+    async fn request<F: Filesystem + 'static>(
+        fs: F,
+        uri: &str,
+        range: Option<&str>,
+    ) -> Response<Body> {
+        let opts = fixture_settings("toml/filesystem.toml");
+        let mut req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        req_handler_opts.embedded_assets = Some(EmbeddedAssetsOpts {
+            source: Arc::new(fs),
+            only: true,
+        });
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.uri_mut() = uri.parse().unwrap();
+        if let Some(range) = range {
+            req.headers_mut().insert("range", range.parse().unwrap());
+        }
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    async fn body(res: Response<Body>) -> String {
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        String::from_utf8_lossy(&body).into_owned()
+    }
+
+    fn theme() -> LocalFilesystem {
+        LocalFilesystem::new("tests/fixtures/overlay/theme")
+    }
+
+    #[tokio::test]
+    async fn filesystem_local() {
+        let res = request(theme(), "http://localhost/theme.js", None).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-length"], "22");
+        assert!(res.headers().contains_key("last-modified"));
+        assert_eq!(body(res).await, "console.log(\"theme\");\n");
+
+        let res = request(theme(), "http://localhost/theme.js", Some("bytes=0-6")).await;
+        assert_eq!(res.status(), 206);
+        assert_eq!(res.headers()["content-range"], "bytes 0-6/22");
+        assert_eq!(body(res).await, "console");
+
+        let res = request(theme(), "http://localhost/docs/", None).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(body(res).await, "<h1>theme docs</h1>\n");
+
+        // The root directory is not accessed
+        let res = request(theme(), "http://localhost/index.html", None).await;
+        assert_eq!(res.status(), 404);
+        let res = request(theme(), "http://localhost/../site/index.html", None).await;
+        assert_eq!(res.status(), 404);
+    }
+
+    #[cfg(feature = "directory-listing")]
+    #[tokio::test]
+    async fn filesystem_dir_listing() {
+        let res = request(theme(), "http://localhost/", None).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "application/json");
+        let entries: serde_json::Value = serde_json::from_str(&body(res).await).unwrap();
+        let mut names: Vec<_> = entries
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| {
+                (
+                    entry["name"].as_str().unwrap(),
+                    entry["type"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                ("docs", "directory"),
+                ("style.css", "file"),
+                ("theme.js", "file")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn filesystem_custom_backend() {
+        let res = request(HelloFilesystem, "http://localhost/hello.txt", None).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "text/plain");
+        assert!(!res.headers().contains_key("last-modified"));
+        assert_eq!(body(res).await, "hello from memory\n");
+
+        let res = request(HelloFilesystem, "http://localhost/hello", None).await;
+        assert_eq!(res.status(), 404);
+
+        #[cfg(feature = "directory-listing")]
+        {
+            let res = request(HelloFilesystem, "http://localhost/", None).await;
+            assert_eq!(res.status(), 200);
+            let body = body(res).await;
+            assert!(body.contains("\"name\":\"hello.txt\""), "{body}");
+            assert!(body.contains("\"size\":18"), "{body}");
+        }
+    }
+}
@@ -0,0 +1 @@
+console.log("pre-compressed variants");
@@ -0,0 +1 @@
+G
\ No newline at end of file
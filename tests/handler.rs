@@ -8,6 +8,7 @@ pub mod tests {
     use headers::HeaderValue;
     use hyper::{Method, Request};
     use std::net::SocketAddr;
+    use std::path::PathBuf;
 
     use static_web_server::http_ext::MethodExt;
     use static_web_server::testing::fixtures::{
@@ -153,4 +154,62 @@ pub mod tests {
             };
         }
     }
+
+    #[tokio::test]
+    async fn media_streaming_preset() {
+        let opts = fixture_settings("toml/handler.toml");
+        let mut req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        req_handler_opts.root_dir = PathBuf::from("tests/fixtures/media");
+        req_handler_opts.media_streaming = true;
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let cases = [
+            (
+                "/hls/index.m3u8",
+                "application/vnd.apple.mpegurl",
+                Some("no-cache"),
+            ),
+            (
+                "/dash/manifest.mpd",
+                "application/dash+xml",
+                Some("no-cache"),
+            ),
+            (
+                "/hls/segment0.ts",
+                "video/mp2t",
+                Some("public, max-age=31536000, immutable"),
+            ),
+            (
+                "/dash/video/chunk0.m4s",
+                "video/iso.segment",
+                Some("public, max-age=31536000, immutable"),
+            ),
+        ];
+        for (path, content_type, cache_control) in cases {
+            let mut req = Request::default();
+            *req.uri_mut() = format!("http://localhost{path}").parse().unwrap();
+
+            match req_handler.handle(&mut req, remote_addr).await {
+                Ok(res) => {
+                    assert_eq!(res.status(), 200, "{path}");
+                    assert_eq!(
+                        res.headers().get("content-type"),
+                        Some(&HeaderValue::from_static(content_type)),
+                        "{path}"
+                    );
+                    assert_eq!(
+                        res.headers()
+                            .get("cache-control")
+                            .map(|v| v.to_str().unwrap()),
+                        cache_control,
+                        "{path}"
+                    );
+                }
+                Err(err) => {
+                    panic!("unexpected error: {err}")
+                }
+            };
+        }
+    }
 }
@@ -0,0 +1,71 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::{Body, Request, Response};
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    async fn request(uri: &str) -> Response<Body> {
+        let opts = fixture_settings("toml/ignore_patterns.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.uri_mut() = uri.parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn ignore_patterns_not_found() {
+        for uri in [
+            "http://localhost/private/notes.txt",
+            "http://localhost/downloads/release.txt.bak",
+            "http://localhost/downloads/archive/old.txt",
+            "http://localhost/downloads/../downloads/release.txt.bak",
+        ] {
+            let res = request(uri).await;
+            assert_eq!(res.status(), 404, "{uri}");
+        }
+
+        let res = request("http://localhost/downloads/release.txt").await;
+        assert_eq!(res.status(), 200);
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "directory-listing")]
+    async fn ignore_patterns_dir_listing() {
+        for uri in [
+            "http://localhost/private/",
+            "http://localhost/downloads/archive/",
+        ] {
+            let res = request(uri).await;
+            assert_eq!(res.status(), 404, "{uri}");
+        }
+
+        let res = request("http://localhost/").await;
+        assert_eq!(res.status(), 200);
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("downloads/"));
+        assert!(!body.contains("private"));
+
+        let res = request("http://localhost/downloads/").await;
+        assert_eq!(res.status(), 200);
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("release.txt"));
+        assert!(!body.contains("release.txt.bak"));
+    }
+}
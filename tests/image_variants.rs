@@ -0,0 +1,116 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::{Body, Method, Request, Response};
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    async fn request(method: Method, uri: &str, accept: Option<&str>) -> Response<Body> {
+        let opts = fixture_settings("toml/image_variants.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.method_mut() = method;
+        *req.uri_mut() = uri.parse().unwrap();
+        if let Some(value) = accept {
+            req.headers_mut().insert("accept", value.parse().unwrap());
+        }
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    fn vary(res: &Response<Body>) -> &str {
+        res.headers()
+            .get("vary")
+            .map_or("", |v| v.to_str().unwrap())
+    }
+
+    async fn body(res: Response<Body>) -> String {
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn image_variants_accepted() {
+        for (accept, content_type, expected) in [
+            ("image/avif,image/webp,*/*", "image/avif", "avif\n"),
+            ("image/webp,image/avif;q=0.8", "image/webp", "webp\n"),
+            ("image/webp,*/*;q=0.8", "image/webp", "webp\n"),
+            ("image/avif;q=0,image/webp;q=0.5", "image/webp", "webp\n"),
+        ] {
+            let res = request(
+                Method::GET,
+                "http://localhost/photos/photo.jpg",
+                Some(accept),
+            )
+            .await;
+            assert_eq!(res.status(), 200, "{accept}");
+            assert_eq!(res.headers()["content-type"], content_type, "{accept}");
+            assert!(vary(&res).contains("accept"));
+            assert_eq!(body(res).await, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn image_variants_not_accepted() {
+        for accept in [None, Some("*/*"), Some("image/*"), Some("image/avif;q=0")] {
+            let res = request(Method::GET, "http://localhost/photos/photo.jpg", accept).await;
+            assert_eq!(res.status(), 200, "{accept:?}");
+            assert_eq!(res.headers()["content-type"], "image/jpeg");
+            assert!(vary(&res).contains("accept"));
+            assert_eq!(body(res).await, "jpeg\n");
+        }
+    }
+
+    #[tokio::test]
+    async fn image_variants_partial() {
+        let res = request(
+            Method::HEAD,
+            "http://localhost/photos/logo.png",
+            Some("image/avif,image/webp"),
+        )
+        .await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "image/webp");
+        assert!(vary(&res).contains("accept"));
+    }
+
+    #[tokio::test]
+    async fn image_variants_none() {
+        let res = request(
+            Method::GET,
+            "http://localhost/photos/plain.gif",
+            Some("image/avif,image/webp"),
+        )
+        .await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "image/gif");
+        assert!(!vary(&res).split(',').any(|v| v.trim() == "accept"));
+        assert_eq!(body(res).await, "gif\n");
+    }
+
+    #[tokio::test]
+    async fn image_variants_direct_request() {
+        let res = request(
+            Method::GET,
+            "http://localhost/photos/photo.jpg.webp",
+            Some("image/avif"),
+        )
+        .await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "image/webp");
+        assert_eq!(body(res).await, "webp\n");
+    }
+}
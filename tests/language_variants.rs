@@ -0,0 +1,101 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::{Body, Request, Response};
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    async fn request(uri: &str, accept_language: Option<&str>) -> Response<Body> {
+        let opts = fixture_settings("toml/language_variants.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.uri_mut() = uri.parse().unwrap();
+        if let Some(value) = accept_language {
+            req.headers_mut()
+                .insert("accept-language", value.parse().unwrap());
+        }
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    fn vary(res: &Response<Body>) -> &str {
+        res.headers()
+            .get("vary")
+            .map_or("", |v| v.to_str().unwrap())
+    }
+
+    async fn body(res: Response<Body>) -> String {
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn language_variants_preferred() {
+        for (accept_language, language, expected) in [
+            ("de-CH, en;q=0.5", "de", "<h1>Hallo</h1>\n"),
+            ("fr, en;q=0.8", "en", "<h1>Hello</h1>\n"),
+            ("pt-BR", "pt-BR", "<h1>Olá</h1>\n"),
+        ] {
+            let res = request("http://localhost/docs/page.html", Some(accept_language)).await;
+            assert_eq!(res.status(), 200, "{accept_language}");
+            assert_eq!(res.headers()["content-language"], language);
+            assert!(vary(&res).contains("accept-language"));
+            assert_eq!(body(res).await, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn language_variants_default() {
+        // The default language variant is served if no accepted language is available
+        let res = request("http://localhost/docs/page.html", Some("ja")).await;
+        assert_eq!(res.headers()["content-language"], "en");
+        assert_eq!(body(res).await, "<h1>Hello</h1>\n");
+
+        let res = request("http://localhost/docs/page.html", None).await;
+        assert_eq!(res.headers()["content-language"], "en");
+
+        // Otherwise the requested file or the first available variant
+        let res = request("http://localhost/docs/", Some("ja")).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-language"], "de");
+        assert!(vary(&res).contains("accept-language"));
+
+        let res = request("http://localhost/docs/guide.html", None).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-language"], "fr");
+        assert_eq!(body(res).await, "<h1>Bienvenue</h1>\n");
+    }
+
+    #[tokio::test]
+    async fn language_variants_directory_index() {
+        let res = request("http://localhost/docs/", Some("de")).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-language"], "de");
+        assert_eq!(body(res).await, "<h1>Willkommen</h1>\n");
+    }
+
+    #[tokio::test]
+    async fn language_variants_none() {
+        let res = request("http://localhost/index.html", Some("de")).await;
+        assert_eq!(res.status(), 200);
+        assert!(res.headers().get("content-language").is_none());
+        assert!(!vary(&res).contains("accept-language"));
+
+        let res = request("http://localhost/docs/missing.html", Some("de")).await;
+        assert_eq!(res.status(), 404);
+        assert!(!vary(&res).contains("accept-language"));
+    }
+}
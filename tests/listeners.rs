@@ -0,0 +1,242 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use static_web_server::Settings;
+    use static_web_server::settings::ListenerAddress;
+
+    fn settings(fixture_toml: &str) -> static_web_server::Result<Settings> {
+        let config_file = format!("tests/fixtures/toml/{fixture_toml}");
+        Settings::get_unparsed(false, &["static-web-server", "--config-file", &config_file])
+    }
+
+    #[cfg(feature = "http2")]
+    #[test]
+    fn listeners_parsing() {
+        let listeners = settings("listeners.toml").unwrap().listeners;
+        assert_eq!(listeners.len(), 2);
+
+        let listener = &listeners[0];
+        assert_eq!(
+            listener.address,
+            ListenerAddress::Tcp("127.0.0.1:8787".parse().unwrap())
+        );
+        assert!(listener.http1);
+        assert!(listener.http2);
+        assert!(listener.tls.is_none());
+        assert!(!listener.proxy_protocol);
+        assert_eq!(listener.vhosts, Some(vec!["default".to_owned()]));
+
+        // HTTP/1.1 and HTTP/2 by default over TLS
+        let listener = &listeners[1];
+        assert_eq!(
+            listener.address,
+            ListenerAddress::Tcp("[::1]:8443".parse().unwrap())
+        );
+        assert!(listener.http1);
+        assert!(listener.http2);
+        assert!(listener.tls.is_some());
+        assert!(listener.proxy_protocol);
+        assert_eq!(listener.vhosts, Some(vec!["assets.localhost".to_owned()]));
+    }
+
+    #[test]
+    fn listeners_none() {
+        let settings = settings("redirects.toml").unwrap();
+        assert!(settings.listeners.is_empty());
+    }
+
+    #[test]
+    fn listeners_http2_without_tls() {
+        let err = settings("listeners_invalid.toml").err().unwrap();
+        assert!(format!("{err:#}").contains("use h2c for HTTP/2 over cleartext"));
+    }
+
+    #[test]
+    fn listeners_unknown_vhost() {
+        let err = settings("listeners_unknown_vhost.toml").err().unwrap();
+        assert!(format!("{err:#}").contains("is not defined in `advanced.virtual-hosts`"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn listeners_unix_socket() {
+        let listeners = settings("listeners_unix.toml").unwrap().listeners;
+        assert_eq!(listeners.len(), 2);
+
+        let listener = &listeners[1];
+        assert_eq!(
+            listener.address,
+            ListenerAddress::Unix("/run/sws/sws.sock".into())
+        );
+        assert!(listener.http1);
+        assert!(!listener.http2);
+        assert!(listener.proxy_protocol);
+        assert_eq!(listener.socket_mode, Some(0o660));
+        let owner = listener.socket_owner.unwrap();
+        assert_eq!((owner.uid, owner.gid), (None, Some(33)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn listeners_systemd_socket() {
+        let listeners = settings("listeners_systemd.toml").unwrap().listeners;
+        assert_eq!(listeners.len(), 2);
+        assert_eq!(
+            listeners[0].address,
+            ListenerAddress::Systemd("http".to_owned())
+        );
+        assert_eq!(listeners[0].address.to_string(), "systemd:http");
+
+        let listener = &listeners[1];
+        assert_eq!(listener.address, ListenerAddress::Systemd("1".to_owned()));
+        assert!(listener.http1);
+        assert!(listener.proxy_protocol);
+        assert!(listener.socket_mode.is_none());
+    }
+
+    #[test]
+    fn listeners_socket_options_on_tcp() {
+        let err = settings("listeners_unix_invalid.toml").err().unwrap();
+        assert!(format!("{err:#}").contains("only supported by Unix domain sockets"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn socket_in_addition_to_tcp() {
+        let settings = Settings::get_unparsed(
+            false,
+            &[
+                "static-web-server",
+                "--port",
+                "8787",
+                "--socket",
+                "/run/sws.sock",
+                "--socket-mode",
+                "0o600",
+                "--socket-owner",
+                "1000:33",
+            ],
+        )
+        .unwrap();
+        let listeners = settings.listeners;
+        assert_eq!(listeners.len(), 2);
+        assert_eq!(
+            listeners[0].address,
+            ListenerAddress::Tcp("[::]:8787".parse().unwrap())
+        );
+        assert_eq!(
+            listeners[1].address,
+            ListenerAddress::Unix("/run/sws.sock".into())
+        );
+        assert_eq!(listeners[1].socket_mode, Some(0o600));
+        let owner = listeners[1].socket_owner.unwrap();
+        assert_eq!((owner.uid, owner.gid), (Some(1000), Some(33)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn socket_only() {
+        let settings = Settings::get_unparsed(
+            false,
+            &[
+                "static-web-server",
+                "--socket",
+                "/run/sws.sock",
+                "--socket-only",
+            ],
+        )
+        .unwrap();
+        assert_eq!(settings.listeners.len(), 1);
+        assert_eq!(
+            settings.listeners[0].address,
+            ListenerAddress::Unix("/run/sws.sock".into())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn socket_invalid_options() {
+        for (option, value) in [
+            ("--socket-mode", "999"),
+            ("--socket-mode", "1777"),
+            ("--socket-owner", "www-data"),
+            ("--socket-owner", ":"),
+        ] {
+            let result = Settings::get_unparsed(
+                false,
+                &[
+                    "static-web-server",
+                    "--socket",
+                    "/run/sws.sock",
+                    option,
+                    value,
+                ],
+            );
+            assert!(result.is_err(), "{option}={value}");
+        }
+    }
+
+    #[cfg(feature = "http2")]
+    #[test]
+    fn listeners_https_redirect() {
+        let listeners = settings("listeners_redirect.toml").unwrap().listeners;
+        assert_eq!(listeners.len(), 3);
+
+        // Redirect to the port of the TLS listener by default
+        let redirect = listeners[0].https_redirect.as_ref().unwrap();
+        assert_eq!(redirect.host, "example.com");
+        assert_eq!(redirect.port, 8443);
+        assert_eq!(redirect.allowed_hosts, vec!["localhost", "example.com"]);
+
+        assert!(listeners[1].https_redirect.is_none());
+
+        let redirect = listeners[2].https_redirect.as_ref().unwrap();
+        assert_eq!(redirect.port, 443);
+        assert_eq!(redirect.status, 301);
+        assert!(!redirect.preserve_host);
+    }
+
+    #[cfg(feature = "http2")]
+    #[test]
+    fn listeners_https_redirect_hsts() {
+        let settings = settings("listeners_redirect_hsts.toml").unwrap();
+        assert!(settings.general.https_redirect_hsts);
+
+        let redirect = settings.listeners[0].https_redirect.as_ref().unwrap();
+        assert_eq!(redirect.host, "example.com");
+        assert_eq!(redirect.port, 8443);
+        assert_eq!(redirect.status, 308);
+        assert!(redirect.preserve_host);
+    }
+
+    #[cfg(feature = "http2")]
+    #[test]
+    fn https_redirect_invalid_options() {
+        let err = Settings::get_unparsed(
+            false,
+            &["static-web-server", "--https-redirect-status", "302"],
+        )
+        .err()
+        .unwrap();
+        assert!(format!("{err:#}").contains("it should be either 301 or 308"));
+
+        let err = Settings::get_unparsed(false, &["static-web-server", "--https-redirect-hsts"])
+            .err()
+            .unwrap();
+        assert!(format!("{err:#}").contains("https redirect hsts requires"));
+    }
+
+    #[cfg(feature = "http2")]
+    #[test]
+    fn listeners_https_redirect_without_tls() {
+        let err = settings("listeners_redirect_invalid.toml").err().unwrap();
+        assert!(
+            format!("{err:#}")
+                .contains("requires a TLS listener or the `https-redirect-port` option")
+        );
+    }
+}
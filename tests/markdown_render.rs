@@ -0,0 +1,215 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(all(test, feature = "markdown"))]
+pub mod tests {
+    use headers::HeaderValue;
+    use hyper::Request;
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    #[tokio::test]
+    async fn markdown_render_accept_html() {
+        let opts = fixture_settings("toml/markdown_render.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.method_mut() = hyper::Method::GET;
+        *req.uri_mut() = "http://localhost/doc.md".parse().unwrap();
+        req.headers_mut().insert(
+            hyper::header::ACCEPT,
+            HeaderValue::from_static("text/html,application/xhtml+xml;q=0.9,*/*;q=0.8"),
+        );
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => {
+                assert_eq!(res.status(), 200);
+                assert_eq!(
+                    res.headers().get("content-type"),
+                    Some(&HeaderValue::from_static("text/html; charset=utf-8"))
+                );
+                assert_eq!(
+                    res.headers().get("vary"),
+                    Some(&HeaderValue::from_static("accept"))
+                );
+                assert!(res.headers().get("accept-ranges").is_none());
+
+                let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+                let body_str = String::from_utf8(body_bytes.to_vec()).unwrap();
+                assert!(body_str.contains("<title>doc</title>"));
+                assert!(body_str.contains("<h1>Documentation</h1>"));
+            }
+            Err(err) => {
+                panic!("unexpected error: {err}")
+            }
+        };
+    }
+
+    #[tokio::test]
+    async fn markdown_render_conditional_request_not_modified_source() {
+        let opts = fixture_settings("toml/markdown_render.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        // Get the validators of the markdown source
+        let mut req = Request::default();
+        *req.method_mut() = hyper::Method::GET;
+        *req.uri_mut() = "http://localhost/doc.md".parse().unwrap();
+        let res = req_handler.handle(&mut req, remote_addr).await.unwrap();
+        assert_eq!(res.status(), 200);
+        let last_modified = res.headers().get("last-modified").cloned().unwrap();
+
+        // A conditional request for the rendered variant gets the whole content
+        let mut req = Request::default();
+        *req.method_mut() = hyper::Method::GET;
+        *req.uri_mut() = "http://localhost/doc.md".parse().unwrap();
+        req.headers_mut()
+            .insert(hyper::header::ACCEPT, HeaderValue::from_static("text/html"));
+        req.headers_mut()
+            .insert(hyper::header::IF_MODIFIED_SINCE, last_modified.clone());
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => {
+                assert_eq!(res.status(), 200);
+                assert_eq!(
+                    res.headers().get("content-type"),
+                    Some(&HeaderValue::from_static("text/html; charset=utf-8"))
+                );
+                assert!(res.headers().get("etag").is_none());
+                assert!(res.headers().get("last-modified").is_none());
+
+                let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+                let body_str = String::from_utf8(body_bytes.to_vec()).unwrap();
+                assert!(body_str.contains("<h1>Documentation</h1>"));
+            }
+            Err(err) => {
+                panic!("unexpected error: {err}")
+            }
+        };
+
+        // The markdown source keeps being validated
+        let mut req = Request::default();
+        *req.method_mut() = hyper::Method::GET;
+        *req.uri_mut() = "http://localhost/doc.md".parse().unwrap();
+        req.headers_mut()
+            .insert(hyper::header::IF_MODIFIED_SINCE, last_modified);
+        let res = req_handler.handle(&mut req, remote_addr).await.unwrap();
+        assert_eq!(res.status(), 304);
+    }
+
+    #[tokio::test]
+    async fn markdown_render_ignore_range_and_conditional_headers() {
+        let opts = fixture_settings("toml/markdown_render.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        // The headers apply to the markdown source, so the rendered content is sent in full
+        for (name, value) in [
+            ("range", "bytes=0-9"),
+            ("range", "bytes=100000-"),
+            ("if-unmodified-since", "Thu, 01 Jan 1970 00:00:00 GMT"),
+        ] {
+            let mut req = Request::default();
+            *req.method_mut() = hyper::Method::GET;
+            *req.uri_mut() = "http://localhost/doc.md".parse().unwrap();
+            req.headers_mut()
+                .insert(hyper::header::ACCEPT, HeaderValue::from_static("text/html"));
+            req.headers_mut().insert(name, value.parse().unwrap());
+
+            match req_handler.handle(&mut req, remote_addr).await {
+                Ok(res) => {
+                    assert_eq!(res.status(), 200, "{name}: {value}");
+                    assert!(res.headers().get("content-range").is_none());
+                    assert_eq!(
+                        res.headers().get("content-type"),
+                        Some(&HeaderValue::from_static("text/html; charset=utf-8"))
+                    );
+
+                    let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+                    let body_str = String::from_utf8(body_bytes.to_vec()).unwrap();
+                    assert!(body_str.contains("<h1>Documentation</h1>"));
+                }
+                Err(err) => {
+                    panic!("unexpected error: {err}")
+                }
+            };
+        }
+
+        // The markdown source keeps being served by ranges
+        let mut req = Request::default();
+        *req.method_mut() = hyper::Method::GET;
+        *req.uri_mut() = "http://localhost/doc.md".parse().unwrap();
+        req.headers_mut()
+            .insert(hyper::header::RANGE, HeaderValue::from_static("bytes=0-9"));
+        let res = req_handler.handle(&mut req, remote_addr).await.unwrap();
+        assert_eq!(res.status(), 206);
+    }
+
+    #[tokio::test]
+    async fn markdown_render_head_empty_body() {
+        let opts = fixture_settings("toml/markdown_render.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.method_mut() = hyper::Method::HEAD;
+        *req.uri_mut() = "http://localhost/doc.md".parse().unwrap();
+        req.headers_mut()
+            .insert(hyper::header::ACCEPT, HeaderValue::from_static("text/html"));
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => {
+                assert_eq!(res.status(), 200);
+                assert_eq!(
+                    res.headers().get("content-type"),
+                    Some(&HeaderValue::from_static("text/html; charset=utf-8"))
+                );
+
+                let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+                assert!(body_bytes.is_empty());
+            }
+            Err(err) => {
+                panic!("unexpected error: {err}")
+            }
+        };
+    }
+
+    #[tokio::test]
+    async fn markdown_render_without_accept_html_returns_source() {
+        let opts = fixture_settings("toml/markdown_render.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.method_mut() = hyper::Method::GET;
+        *req.uri_mut() = "http://localhost/doc.md".parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => {
+                assert_eq!(res.status(), 200);
+                assert_ne!(
+                    res.headers().get("content-type"),
+                    Some(&HeaderValue::from_static("text/html; charset=utf-8"))
+                );
+
+                let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+                let body_str = String::from_utf8(body_bytes.to_vec()).unwrap();
+                assert!(body_str.starts_with("# Documentation"));
+            }
+            Err(err) => {
+                panic!("unexpected error: {err}")
+            }
+        };
+    }
+}
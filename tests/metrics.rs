@@ -0,0 +1,66 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(feature = "metrics")]
+pub mod tests {
+    use hyper::{Body, Request};
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    fn request(method: hyper::Method, uri: &str, host: &str) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("host", host)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn metrics_enabled() {
+        let opts = fixture_settings("toml/metrics.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = request(hyper::Method::GET, "/index.htm", "localhost");
+        let res = req_handler.handle(&mut req, remote_addr).await.unwrap();
+        assert_eq!(res.status(), 200);
+        let index = hyper::body::to_bytes(res.into_body()).await.unwrap();
+
+        let mut req = request(hyper::Method::GET, "/missing.html", "assets.localhost:8787");
+        let res = req_handler.handle(&mut req, remote_addr).await.unwrap();
+        assert_eq!(res.status(), 404);
+        hyper::body::to_bytes(res.into_body()).await.unwrap();
+
+        let mut req = request(hyper::Method::GET, "/metrics", "localhost");
+        let res = req_handler.handle(&mut req, remote_addr).await.unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "text/plain; charset=utf-8");
+
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let body_str = std::str::from_utf8(&body).unwrap();
+
+        assert!(
+            body_str.contains(
+                r#"sws_http_requests_total{method="GET",status="200",vhost="default"} 1"#
+            )
+        );
+        assert!(body_str.contains(
+            r#"sws_http_requests_total{method="GET",status="404",vhost="assets.localhost"} 1"#
+        ));
+        assert!(body_str.contains(
+            r#"sws_http_request_duration_seconds_count{method="GET",vhost="default"} 1"#
+        ));
+        assert!(body_str.contains(&format!(
+            r#"sws_http_response_bytes_total{{vhost="default"}} {}"#,
+            index.len()
+        )));
+        assert!(body_str.contains("sws_open_connections 0"));
+    }
+}
@@ -0,0 +1,116 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use headers::{HeaderMap, HeaderValue};
+    use hyper::Request;
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    async fn response_headers(fixture: &str, uri: &str) -> HeaderMap {
+        let opts = fixture_settings(fixture);
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.method_mut() = hyper::Method::GET;
+        *req.uri_mut() = uri.parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => {
+                assert_eq!(res.status(), 200);
+                res.headers().clone()
+            }
+            Err(err) => {
+                panic!("unexpected error: {err}")
+            }
+        }
+    }
+
+    async fn content_type(fixture: &str, uri: &str) -> Option<HeaderValue> {
+        response_headers(fixture, uri)
+            .await
+            .get("content-type")
+            .cloned()
+    }
+
+    #[tokio::test]
+    async fn mime_types_override() {
+        assert_eq!(
+            content_type("toml/mime_types.toml", "http://localhost/assets/main.css").await,
+            Some(HeaderValue::from_static("text/x-custom-css"))
+        );
+        assert_eq!(
+            content_type("toml/mime_types.toml", "http://localhost/assets/main.js").await,
+            Some(HeaderValue::from_static("text/javascript; charset=utf-8"))
+        );
+    }
+
+    #[tokio::test]
+    async fn mime_types_default() {
+        assert_eq!(
+            content_type("toml/mime_types.toml", "http://localhost/assets/index.html").await,
+            Some(HeaderValue::from_static("text/html"))
+        );
+    }
+
+    #[tokio::test]
+    async fn charsets_default_and_override() {
+        assert_eq!(
+            content_type("toml/charsets.toml", "http://localhost/index.htm").await,
+            Some(HeaderValue::from_static("text/html; charset=utf-8"))
+        );
+        assert_eq!(
+            content_type("toml/charsets.toml", "http://localhost/assets/main.css").await,
+            Some(HeaderValue::from_static("text/css; charset=iso-8859-1"))
+        );
+    }
+
+    #[tokio::test]
+    async fn content_types_forced() {
+        let headers =
+            response_headers("toml/content_types.toml", "http://localhost/assets/main.js").await;
+        assert_eq!(
+            headers.get("content-type"),
+            Some(&HeaderValue::from_static("text/plain; charset=utf-8"))
+        );
+        assert_eq!(headers.get("content-disposition"), None);
+    }
+
+    #[tokio::test]
+    async fn content_types_download() {
+        let headers = response_headers(
+            "toml/content_types.toml",
+            "http://localhost/assets/main.css",
+        )
+        .await;
+        assert_eq!(
+            headers.get("content-type"),
+            Some(&HeaderValue::from_static("text/css; charset=utf-8"))
+        );
+        assert_eq!(
+            headers.get("content-disposition"),
+            Some(&HeaderValue::from_static(
+                "attachment; filename=\"main.css\""
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn content_types_not_matched() {
+        let headers =
+            response_headers("toml/content_types.toml", "http://localhost/index.htm").await;
+        assert_eq!(
+            headers.get("content-type"),
+            Some(&HeaderValue::from_static("text/html; charset=utf-8"))
+        );
+        assert_eq!(headers.get("content-disposition"), None);
+    }
+}
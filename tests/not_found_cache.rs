@@ -0,0 +1,67 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::{Body, Method, Request, Response};
+    use std::net::SocketAddr;
+    use std::path::PathBuf;
+
+    use static_web_server::handler::RequestHandler;
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    fn make_handler(root: PathBuf) -> RequestHandler {
+        let mut opts = fixture_settings("toml/not_found_cache.toml");
+        opts.general.root = root;
+        fixture_req_handler(fixture_req_handler_opts(opts.general, opts.advanced))
+    }
+
+    async fn request(req_handler: &RequestHandler, method: Method, uri: &str) -> Response<Body> {
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+        let mut req = Request::default();
+        *req.method_mut() = method;
+        *req.uri_mut() = uri.parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn not_found_cache_hit() {
+        let root = std::env::temp_dir().join(format!("sws-not-found-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let req_handler = make_handler(root.clone());
+
+        let res = request(&req_handler, Method::GET, "http://localhost/new.txt").await;
+        assert_eq!(res.status(), 404);
+
+        // The path is still not found until the cached entry expires
+        std::fs::write(root.join("new.txt"), "new").unwrap();
+        let res = request(&req_handler, Method::GET, "http://localhost/new.txt").await;
+        assert_eq!(res.status(), 404);
+        let res = request(&req_handler, Method::HEAD, "http://localhost/new.txt").await;
+        assert_eq!(res.status(), 404);
+
+        // A reloaded configuration starts with an empty cache
+        let req_handler = make_handler(root.clone());
+        let res = request(&req_handler, Method::GET, "http://localhost/new.txt").await;
+        assert_eq!(res.status(), 200);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn not_found_cache_found() {
+        let req_handler = make_handler(PathBuf::from("tests/fixtures/public"));
+        for _ in 0..2 {
+            let res = request(&req_handler, Method::GET, "http://localhost/index.htm").await;
+            assert_eq!(res.status(), 200);
+        }
+    }
+}
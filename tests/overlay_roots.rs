@@ -0,0 +1,63 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::{Body, Request, Response};
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    async fn request(uri: &str) -> Response<Body> {
+        let opts = fixture_settings("toml/overlay_roots.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.uri_mut() = uri.parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    async fn body(res: Response<Body>) -> String {
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        String::from_utf8_lossy(&body).into_owned()
+    }
+
+    #[tokio::test]
+    async fn overlay_roots_priority() {
+        // The root directory overrides the overlay roots
+        let res = request("http://localhost/style.css").await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(body(res).await, "body { color: black; }\n");
+
+        let res = request("http://localhost/").await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(body(res).await, "<h1>site</h1>\n");
+    }
+
+    #[tokio::test]
+    async fn overlay_roots_fallthrough() {
+        let res = request("http://localhost/theme.js").await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(body(res).await, "console.log(\"theme\");\n");
+
+        let res = request("http://localhost/docs/").await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(body(res).await, "<h1>theme docs</h1>\n");
+
+        let res = request("http://localhost/docs").await;
+        assert_eq!(res.status(), 308);
+
+        let res = request("http://localhost/missing.js").await;
+        assert_eq!(res.status(), 404);
+    }
+}
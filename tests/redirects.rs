@@ -313,4 +313,106 @@ pub mod tests {
             }
         };
     }
+
+    #[tokio::test]
+    async fn redirects_regex_groups() {
+        let opts = fixture_settings("toml/redirects.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+
+        let mut req = Request::default();
+        *req.uri_mut() = "http://localhost/blog/2023/hello-world".parse().unwrap();
+
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => {
+                assert_eq!(res.status(), 301);
+                assert_eq!(
+                    res.headers()["location"],
+                    "http://localhost/posts/2023/hello-world"
+                );
+            }
+            Err(err) => {
+                panic!("unexpected error: {err}")
+            }
+        };
+    }
+
+    #[tokio::test]
+    async fn redirects_regex_groups_whole_path() {
+        let opts = fixture_settings("toml/redirects.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        // The regex pattern must match the whole URI path
+        for uri in [
+            "http://localhost/old/blog/2023/hello-world",
+            "http://localhost/blog/latest/hello-world",
+        ] {
+            let mut req = Request::default();
+            *req.uri_mut() = uri.parse().unwrap();
+
+            match req_handler.handle(&mut req, remote_addr).await {
+                Ok(res) => {
+                    assert_eq!(res.status(), 404, "{uri}");
+                }
+                Err(err) => {
+                    panic!("unexpected error: {err}")
+                }
+            };
+        }
+    }
+
+    #[tokio::test]
+    async fn redirects_conditions() {
+        let opts = fixture_settings("toml/redirects.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let cases = [
+            // Header condition
+            (
+                "http://localhost/download",
+                Some("Mozilla/5.0 (Linux; Android 14) Mobile Safari/537.36"),
+                Some("http://localhost/download/mobile"),
+            ),
+            // Query conditions, all of them must match
+            (
+                "http://localhost/download?ref=home&lang=es",
+                None,
+                Some("http://localhost/es/download"),
+            ),
+            ("http://localhost/download?lang=es", None, None),
+            ("http://localhost/download?lang=en&ref=home", None, None),
+            // No matching conditions
+            (
+                "http://localhost/download",
+                Some("Mozilla/5.0 (X11; Linux)"),
+                None,
+            ),
+        ];
+        for (uri, user_agent, location) in cases {
+            let mut req = Request::default();
+            *req.uri_mut() = uri.parse().unwrap();
+            if let Some(user_agent) = user_agent {
+                req.headers_mut()
+                    .insert("user-agent", user_agent.parse().unwrap());
+            }
+
+            match req_handler.handle(&mut req, remote_addr).await {
+                Ok(res) => match location {
+                    Some(location) => {
+                        assert_eq!(res.status(), 302, "{uri}");
+                        assert_eq!(res.headers()["location"], location, "{uri}");
+                    }
+                    None => assert_eq!(res.status(), 404, "{uri}"),
+                },
+                Err(err) => {
+                    panic!("unexpected error: {err}")
+                }
+            };
+        }
+    }
 }
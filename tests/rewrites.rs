@@ -210,4 +210,108 @@ pub mod tests {
             }
         };
     }
+
+    #[tokio::test]
+    async fn rewrites_regex_groups() {
+        let opts = fixture_settings("toml/rewrites.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.uri_mut() = "http://localhost/docs/v2/404.html".parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(mut res) => {
+                assert_eq!(res.status(), 200);
+                assert_eq!(res.headers()["content-type"], "text/html");
+
+                let body = hyper::body::to_bytes(res.body_mut())
+                    .await
+                    .expect("unexpected bytes error during `body` conversion");
+                let body_str = std::str::from_utf8(&body).unwrap();
+                assert!(body_str.contains("404 Content"))
+            }
+            Err(err) => {
+                panic!("unexpected error: {err}")
+            }
+        };
+    }
+
+    #[tokio::test]
+    async fn rewrites_chain() {
+        let opts = fixture_settings("toml/rewrites.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.uri_mut() = "http://localhost/latest/404.html".parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(mut res) => {
+                assert_eq!(res.status(), 200);
+                assert_eq!(req.uri().path(), "/404.html");
+
+                let body = hyper::body::to_bytes(res.body_mut())
+                    .await
+                    .expect("unexpected bytes error during `body` conversion");
+                let body_str = std::str::from_utf8(&body).unwrap();
+                assert!(body_str.contains("404 Content"))
+            }
+            Err(err) => {
+                panic!("unexpected error: {err}")
+            }
+        };
+    }
+
+    #[tokio::test]
+    async fn rewrites_conditions() {
+        let opts = fixture_settings("toml/rewrites.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.uri_mut() = "http://lite.localhost/index.html".parse().unwrap();
+        req.headers_mut().insert("save-data", "on".parse().unwrap());
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(mut res) => {
+                assert_eq!(res.status(), 200);
+                let body = hyper::body::to_bytes(res.body_mut())
+                    .await
+                    .expect("unexpected bytes error during `body` conversion");
+                let body_str = std::str::from_utf8(&body).unwrap();
+                assert!(body_str.contains("50x Service Unavailable"))
+            }
+            Err(err) => {
+                panic!("unexpected error: {err}")
+            }
+        };
+
+        // Other hosts or requests without the header are not rewritten
+        for (uri, save_data) in [
+            ("http://lite.localhost/index.html", "off"),
+            ("http://localhost/index.html", "on"),
+        ] {
+            let mut req = Request::default();
+            *req.uri_mut() = uri.parse().unwrap();
+            req.headers_mut()
+                .insert("save-data", save_data.parse().unwrap());
+
+            match req_handler.handle(&mut req, remote_addr).await {
+                Ok(res) => {
+                    assert_eq!(res.status(), 301, "{uri}");
+                    assert_eq!(
+                        res.headers()["location"],
+                        "http://localhost/new-generic/index.html"
+                    );
+                }
+                Err(err) => {
+                    panic!("unexpected error: {err}")
+                }
+            };
+        }
+    }
 }
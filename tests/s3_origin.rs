@@ -0,0 +1,256 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(all(test, feature = "s3-origin"))]
+pub mod tests {
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server, StatusCode};
+    use percent_encoding::percent_decode_str;
+    use std::collections::BTreeSet;
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use static_web_server::fs::s3::{S3Filesystem, S3OriginOpts};
+    use static_web_server::handler::RequestHandler;
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    const OBJECTS: &[(&str, &str)] = &[
+        ("site/index.html", "<h1>edge</h1>\n"),
+        ("site/app.js", "console.log(\"served from the edge\");\n"),
+        ("site/docs/index.html", "<h1>docs</h1>\n"),
+        ("site/assets/logo.txt", "logo\n"),
+        ("site/assets/fonts/sans.txt", "sans\n"),
+    ];
+
+    /// Requests received by the mock S3 origin.
+    #[derive(Default)]
+    struct Counters {
+        downloads: AtomicUsize,
+        revalidations: AtomicUsize,
+    }
+
+    fn query_param(req: &Request<Body>, key: &str) -> String {
+        req.uri()
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| percent_decode_str(value).decode_utf8_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    /// Responds like an S3 bucket named `bucket` holding the `OBJECTS`.
+    fn s3_response(req: Request<Body>, counters: &Counters) -> Response<Body> {
+        let signed = req
+            .headers()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("AWS4-HMAC-SHA256 Credential=AKID/"));
+        if !signed || !req.headers().contains_key("x-amz-date") {
+            return status_response(StatusCode::FORBIDDEN);
+        }
+
+        let path = percent_decode_str(req.uri().path()).decode_utf8_lossy();
+        if path == "/bucket" {
+            let prefix = query_param(&req, "prefix");
+            let delimiter = query_param(&req, "delimiter");
+            let mut xml = String::from("<ListBucketResult>");
+            let mut common_prefixes = BTreeSet::new();
+            for (key, contents) in OBJECTS.iter().filter(|(key, _)| key.starts_with(&prefix)) {
+                match key[prefix.len()..].split_once('/') {
+                    Some((dir, _)) if delimiter == "/" => {
+                        common_prefixes.insert(format!("{prefix}{dir}/"));
+                    }
+                    _ => xml.push_str(&format!(
+                        "<Contents><Key>{key}</Key><Size>{}</Size></Contents>",
+                        contents.len()
+                    )),
+                }
+            }
+            for common_prefix in common_prefixes {
+                xml.push_str(&format!(
+                    "<CommonPrefixes><Prefix>{common_prefix}</Prefix></CommonPrefixes>"
+                ));
+            }
+            xml.push_str("<IsTruncated>false</IsTruncated></ListBucketResult>");
+            return Response::new(Body::from(xml));
+        }
+
+        let key = path.trim_start_matches("/bucket/");
+        let Some((_, contents)) = OBJECTS.iter().find(|(name, _)| *name == key) else {
+            return status_response(StatusCode::NOT_FOUND);
+        };
+        let etag = format!("\"{}\"", contents.len());
+        if req
+            .headers()
+            .get("if-none-match")
+            .is_some_and(|value| *value == *etag)
+        {
+            counters.revalidations.fetch_add(1, Ordering::SeqCst);
+            return status_response(StatusCode::NOT_MODIFIED);
+        }
+        counters.downloads.fetch_add(1, Ordering::SeqCst);
+        Response::builder()
+            .header("etag", etag)
+            .header("last-modified", "Tue, 14 Nov 2023 22:13:20 GMT")
+            .body(Body::from(*contents))
+            .unwrap()
+    }
+
+    fn status_response(status: StatusCode) -> Response<Body> {
+        let mut resp = Response::new(Body::empty());
+        *resp.status_mut() = status;
+        resp
+    }
+
+    /// Starts the mock S3 origin on an ephemeral port.
+    fn mock_s3() -> (SocketAddr, Arc<Counters>) {
+        let counters = Arc::new(Counters::default());
+        let service_counters = counters.clone();
+        let make_service = make_service_fn(move |_| {
+            let counters = service_counters.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let resp = s3_response(req, &counters);
+                    async move { Ok::<_, Infallible>(resp) }
+                }))
+            }
+        });
+        let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        (addr, counters)
+    }
+
+    fn s3_handler(name: &str, cache_ttl: u64) -> (RequestHandler, Arc<Counters>, PathBuf) {
+        let (addr, counters) = mock_s3();
+        let cache_dir = std::env::temp_dir().join(format!("sws-s3-{name}-{}", std::process::id()));
+        let origin = S3Filesystem::new(S3OriginOpts {
+            url: format!("http://{addr}/bucket/site"),
+            region: "eu-west-1".to_owned(),
+            access_key_id: "AKID".to_owned(),
+            secret_access_key: "secret".to_owned(),
+            cache_dir: cache_dir.clone(),
+            cache_ttl,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let opts = fixture_settings("toml/s3_origin.toml");
+        let mut req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        req_handler_opts.root_filesystem = Some(Arc::new(origin));
+        (fixture_req_handler(req_handler_opts), counters, cache_dir)
+    }
+
+    async fn request(
+        req_handler: &RequestHandler,
+        uri: &str,
+        range: Option<&str>,
+    ) -> Response<Body> {
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+        let mut req = Request::default();
+        *req.uri_mut() = uri.parse().unwrap();
+        if let Some(range) = range {
+            req.headers_mut().insert("range", range.parse().unwrap());
+        }
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    async fn body(res: Response<Body>) -> String {
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        String::from_utf8_lossy(&body).into_owned()
+    }
+
+    #[tokio::test]
+    async fn s3_origin_files() {
+        let (req_handler, counters, cache_dir) = s3_handler("files", 60);
+
+        let res = request(&req_handler, "http://localhost/", None).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "text/html");
+        assert!(res.headers().contains_key("last-modified"));
+        assert_eq!(body(res).await, "<h1>edge</h1>\n");
+
+        let res = request(&req_handler, "http://localhost/docs/", None).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(body(res).await, "<h1>docs</h1>\n");
+        let res = request(&req_handler, "http://localhost/docs", None).await;
+        assert_eq!(res.status(), 308);
+
+        let res = request(&req_handler, "http://localhost/app.js", Some("bytes=0-10")).await;
+        assert_eq!(res.status(), 206);
+        assert_eq!(res.headers()["content-type"], "text/javascript");
+        assert_eq!(body(res).await, "console.log");
+
+        // The objects are served from the disk cache until their TTL expires
+        let res = request(&req_handler, "http://localhost/app.js", None).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(body(res).await, "console.log(\"served from the edge\");\n");
+        assert_eq!(counters.downloads.load(Ordering::SeqCst), 3);
+        assert_eq!(counters.revalidations.load(Ordering::SeqCst), 0);
+
+        let res = request(&req_handler, "http://localhost/missing.html", None).await;
+        assert_eq!(res.status(), 404);
+
+        std::fs::remove_dir_all(cache_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn s3_origin_revalidation() {
+        let (req_handler, counters, cache_dir) = s3_handler("revalidation", 0);
+
+        for _ in 0..2 {
+            let res = request(&req_handler, "http://localhost/index.html", None).await;
+            assert_eq!(res.status(), 200);
+            assert_eq!(body(res).await, "<h1>edge</h1>\n");
+        }
+        assert_eq!(counters.downloads.load(Ordering::SeqCst), 1);
+        assert_eq!(counters.revalidations.load(Ordering::SeqCst), 1);
+
+        // The disk cache is revalidated as well by a new origin, E.g. after a restart
+        let (req_handler, counters, _) = s3_handler("revalidation", 0);
+        let res = request(&req_handler, "http://localhost/index.html", None).await;
+        assert_eq!(body(res).await, "<h1>edge</h1>\n");
+        assert_eq!(counters.downloads.load(Ordering::SeqCst), 0);
+        assert_eq!(counters.revalidations.load(Ordering::SeqCst), 1);
+
+        std::fs::remove_dir_all(cache_dir).ok();
+    }
+
+    #[cfg(feature = "directory-listing")]
+    #[tokio::test]
+    async fn s3_origin_dir_listing() {
+        let (req_handler, _, cache_dir) = s3_handler("dir-listing", 60);
+
+        let res = request(&req_handler, "http://localhost/assets/", None).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "application/json");
+        let entries: serde_json::Value = serde_json::from_str(&body(res).await).unwrap();
+        let mut names: Vec<_> = entries
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| {
+                (
+                    entry["name"].as_str().unwrap(),
+                    entry["type"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        names.sort();
+        assert_eq!(names, [("fonts", "directory"), ("logo.txt", "file")]);
+
+        std::fs::remove_dir_all(cache_dir).ok();
+    }
+}
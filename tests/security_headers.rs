@@ -0,0 +1,46 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::Request;
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    #[tokio::test]
+    async fn security_headers_profile() {
+        let opts = fixture_settings("toml/security_headers.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        for (uri, status) in [
+            ("http://localhost/index.htm", 200),
+            ("http://localhost/unknown.html", 404),
+        ] {
+            let mut req = Request::default();
+            *req.uri_mut() = uri.parse().unwrap();
+
+            let res = req_handler.handle(&mut req, remote_addr).await.unwrap();
+            let headers = res.headers();
+            assert_eq!(res.status(), status, "{uri}");
+            assert!(headers.get("strict-transport-security").is_none());
+            assert_eq!(headers["x-frame-options"], "SAMEORIGIN");
+            assert_eq!(headers["x-content-type-options"], "nosniff");
+            assert_eq!(
+                headers["content-security-policy"],
+                "default-src 'self'; frame-ancestors https://example.com"
+            );
+            assert_eq!(
+                headers["referrer-policy"],
+                "strict-origin-when-cross-origin"
+            );
+            assert_eq!(headers["permissions-policy"], "camera=(), microphone=()");
+        }
+    }
+}
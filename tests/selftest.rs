@@ -0,0 +1,22 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use static_web_server::{selftest, testing::fixtures::fixture_settings};
+
+    #[test]
+    fn selftest_passes() {
+        let opts = fixture_settings("toml/selftest.toml");
+        selftest::run(opts).unwrap();
+    }
+
+    #[test]
+    fn selftest_fails() {
+        let mut opts = fixture_settings("toml/selftest.toml");
+        opts.general.index_files = "missing.html".to_owned();
+        assert!(selftest::run(opts).is_err());
+    }
+}
@@ -0,0 +1,37 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::Request;
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    #[tokio::test]
+    async fn server_ident_all_responses() {
+        let opts = fixture_settings("toml/server_ident.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+        let ident = format!("static-web-server/{}", env!("CARGO_PKG_VERSION"));
+
+        for (uri, status, server) in [
+            ("http://localhost/index.htm", 200, ident.as_str()),
+            ("http://localhost/unknown.html", 404, ident.as_str()),
+            // Custom headers take precedence
+            ("http://localhost/assets/main.js", 200, "Static Web Server"),
+        ] {
+            let mut req = Request::default();
+            *req.uri_mut() = uri.parse().unwrap();
+
+            let res = req_handler.handle(&mut req, remote_addr).await.unwrap();
+            assert_eq!(res.status(), status, "{uri}");
+            assert_eq!(res.headers()["server"], server, "{uri}");
+        }
+    }
+}
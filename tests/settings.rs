@@ -5,7 +5,11 @@
 
 #[cfg(test)]
 mod tests {
-    use static_web_server::settings::file::Settings;
+    use static_web_server::settings::{
+        dump::{self, DumpFormat},
+        file::Settings,
+    };
+    use static_web_server::testing::fixtures::fixture_settings;
     use std::path::{Path, PathBuf};
 
     #[test]
@@ -25,4 +29,102 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn toml_file_includes() {
+        let config_path = Path::new("tests/fixtures/toml/include/sws.toml");
+        let settings = Settings::read(config_path).unwrap();
+
+        // Included files are merged in order, the last one wins
+        let general = settings.general.unwrap();
+        assert_eq!(general.root, Some(PathBuf::from("tests/fixtures/public")));
+        assert_eq!(general.port, Some(8789));
+        assert_eq!(general.cache_control_headers, Some(false));
+
+        // Arrays are appended
+        let redirects = settings.advanced.unwrap().redirects.unwrap();
+        let sources = redirects
+            .iter()
+            .map(|redirect| redirect.source.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(sources, ["/main", "/included"]);
+        assert_eq!(redirects[1].destination, "/index.html");
+    }
+
+    #[test]
+    fn toml_file_interpolation_disabled() {
+        let config_path = Path::new("tests/fixtures/toml/include/literal.toml");
+        let settings = Settings::read(config_path).unwrap();
+
+        // The `${` references are kept as they are unless `interpolate` is enabled
+        let redirects = settings.advanced.unwrap().redirects.unwrap();
+        assert_eq!(redirects[0].destination, "/manual/${1}");
+    }
+
+    #[test]
+    fn config_dump() {
+        #[allow(unused_mut)]
+        let mut opts = fixture_settings("toml/include/sws.toml");
+        #[cfg(feature = "basic-auth")]
+        {
+            opts.general.basic_auth = "user:$2y$05$hash".to_owned();
+        }
+
+        let config = dump::dump(&opts, DumpFormat::Toml).unwrap();
+        assert!(config.starts_with("[general]\n"));
+        let config: toml::Value = toml::from_str(&config).unwrap();
+        assert_eq!(config["general"]["port"].as_integer(), Some(8789));
+        assert_eq!(config["advanced"]["redirects"].as_array().unwrap().len(), 2);
+        // The interpolated values are printed as their variable references
+        assert_eq!(
+            config["advanced"]["headers"][0]["headers"]["X-Upstream-Url"].as_str(),
+            Some("https://user:${SWS_TEST_SECRET:-}@example.com")
+        );
+        #[cfg(feature = "basic-auth")]
+        assert_eq!(
+            config["general"]["basic-auth"].as_str(),
+            Some(dump::REDACTED)
+        );
+
+        let config = dump::dump(&opts, DumpFormat::Json).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&config).unwrap();
+        assert_eq!(config["general"]["root"], "tests/fixtures/public");
+        assert!(!config.to_string().contains("$2y$05$hash"));
+    }
+
+    #[test]
+    fn toml_file_json_schema() {
+        let schema = Settings::json_schema().unwrap();
+        let schema: serde_json::Value = serde_json::from_str(&schema).unwrap();
+        let props = &schema["properties"];
+        assert!(props.get("general").is_some());
+        assert!(props.get("advanced").is_some());
+
+        // Only the redirect status codes are valid redirect kinds
+        let kind = &schema["$defs"]["RedirectsKind"];
+        assert_eq!(kind["type"], "integer");
+        assert_eq!(kind["enum"], serde_json::json!([301, 302]));
+        let kind_ref = &schema["$defs"]["Redirects"]["properties"]["kind"]["$ref"];
+        assert_eq!(kind_ref, "#/$defs/RedirectsKind");
+    }
+
+    #[test]
+    fn toml_file_startup_errors() {
+        let config_file = "tests/fixtures/toml/startup_errors.toml";
+        let err = static_web_server::Settings::get_unparsed(
+            false,
+            &["static-web-server", "--config-file", config_file],
+        )
+        .err()
+        .unwrap()
+        .to_string();
+
+        // All the invalid entries are reported at once, tagged by module
+        assert!(err.starts_with("3 configuration errors found:"), "{err}");
+        assert!(err.contains("\n  [redirects] can not compile"), "{err}");
+        assert!(err.contains("\n  [rewrites] rewrite source \"/old/{*}\" can't be chained"));
+        assert!(
+            err.contains("\n  [virtual-hosts] root directory for virtual host missing.localhost")
+        );
+    }
 }
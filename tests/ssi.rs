@@ -0,0 +1,113 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::Request;
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    #[tokio::test]
+    async fn ssi_process_directives() {
+        let opts = fixture_settings("toml/ssi.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.method_mut() = hyper::Method::GET;
+        *req.uri_mut() = "http://localhost/index.shtml".parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => {
+                assert_eq!(res.status(), 200);
+                assert!(res.headers().get("last-modified").is_none());
+                assert!(res.headers().get("accept-ranges").is_none());
+
+                let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+                let body_str = String::from_utf8(body_bytes.to_vec()).unwrap();
+                assert!(body_str.contains("<header>SSI Header</header>"));
+                assert!(body_str.contains("<p>Document: /index.shtml</p>"));
+                assert!(body_str.contains("<footer>SSI Footer 2024\n</footer>"));
+                assert!(body_str.contains("[an error occurred while processing this directive]"));
+                assert!(!body_str.contains("<!--#"));
+            }
+            Err(err) => {
+                panic!("unexpected error: {err}")
+            }
+        };
+    }
+
+    #[tokio::test]
+    async fn ssi_skip_other_extensions() {
+        let opts = fixture_settings("toml/ssi.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.method_mut() = hyper::Method::GET;
+        *req.uri_mut() = "http://localhost/plain.html".parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => {
+                assert_eq!(res.status(), 200);
+
+                let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+                let body_str = String::from_utf8(body_bytes.to_vec()).unwrap();
+                assert!(body_str.contains("<!--#include virtual=\"/parts/header.html\" -->"));
+            }
+            Err(err) => {
+                panic!("unexpected error: {err}")
+            }
+        };
+    }
+
+    #[tokio::test]
+    async fn ssi_ignore_range_and_conditional_headers() {
+        let opts = fixture_settings("toml/ssi.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        // The headers apply to the SSI source, so the evaluated content is sent in full
+        for (name, value) in [
+            ("range", "bytes=0-9"),
+            ("range", "bytes=100000-"),
+            ("if-modified-since", "Fri, 31 Dec 9999 23:59:59 GMT"),
+            ("if-unmodified-since", "Thu, 01 Jan 1970 00:00:00 GMT"),
+        ] {
+            let mut req = Request::default();
+            *req.method_mut() = hyper::Method::GET;
+            *req.uri_mut() = "http://localhost/index.shtml".parse().unwrap();
+            req.headers_mut().insert(name, value.parse().unwrap());
+
+            match req_handler.handle(&mut req, remote_addr).await {
+                Ok(res) => {
+                    assert_eq!(res.status(), 200, "{name}: {value}");
+                    assert!(res.headers().get("content-range").is_none());
+                    assert!(res.headers().get("last-modified").is_none());
+                    assert!(
+                        res.headers()["content-type"]
+                            .to_str()
+                            .unwrap()
+                            .starts_with("text/html")
+                    );
+
+                    let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+                    let body_str = String::from_utf8(body_bytes.to_vec()).unwrap();
+                    assert!(body_str.contains("<header>SSI Header</header>"));
+                    assert!(!body_str.contains("<!--#"));
+                }
+                Err(err) => {
+                    panic!("unexpected error: {err}")
+                }
+            };
+        }
+    }
+}
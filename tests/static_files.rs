@@ -20,8 +20,12 @@ mod tests {
     ))]
     use static_web_server::compression;
 
+    use static_web_server::deadline::Deadline;
     #[cfg(feature = "directory-listing")]
     use static_web_server::directory_listing::DirListFmt;
+    #[cfg(feature = "directory-listing")]
+    use static_web_server::directory_listing_sort::DirListSort;
+    use static_web_server::fs::symlinks::FollowSymlinks;
     use static_web_server::static_files::{self, HandleOpts};
 
     fn root_dir() -> PathBuf {
@@ -45,21 +49,44 @@ mod tests {
             method: &Method::GET,
             headers: &HeaderMap::new(),
             base_path: &root_dir(),
+            root_filesystem: None,
+            overlay_paths: &[],
+            embedded_assets: None,
             uri_path: "index.htm",
             uri_query: None,
             #[cfg(feature = "experimental")]
             memory_cache: None,
+            chunk_cache: None,
+            #[cfg(feature = "mmap")]
+            mmap_max_size: 0,
+            type_map: None,
+            deadline: None,
+            follow_symlinks: FollowSymlinks::Always,
             #[cfg(feature = "directory-listing")]
             dir_listing: false,
             #[cfg(feature = "directory-listing")]
-            dir_listing_order: 6,
+            dir_listing_sort: &DirListSort::from_code(6),
             #[cfg(feature = "directory-listing")]
             dir_listing_format: &DirListFmt::Html,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_dir_size: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_details: false,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_locales: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_locale: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_hide: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_readme: None,
             #[cfg(feature = "directory-listing-download")]
             dir_listing_download: &[],
             redirect_trailing_slash: true,
             compression_static: false,
+            compression_static_order: &[],
             ignore_hidden_files: false,
+            ignore_patterns: None,
             disable_symlinks: false,
             index_files: &["index.htm"],
         })
@@ -87,27 +114,110 @@ mod tests {
         assert_eq!(body, buf);
     }
 
+    #[tokio::test]
+    async fn handle_file_deadline_expired() {
+        // The client disconnected
+        let deadline = Deadline::default();
+        deadline.cancel();
+
+        for (uri_path, dir_listing) in [("index.htm", false), ("assets/", true)] {
+            let result = static_files::handle(&HandleOpts {
+                method: &Method::GET,
+                headers: &HeaderMap::new(),
+                base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
+                uri_path,
+                uri_query: None,
+                #[cfg(feature = "experimental")]
+                memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: Some(&deadline),
+                follow_symlinks: FollowSymlinks::Always,
+                #[cfg(feature = "directory-listing")]
+                dir_listing,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_sort: &DirListSort::from_code(6),
+                #[cfg(feature = "directory-listing")]
+                dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
+                #[cfg(feature = "directory-listing-download")]
+                dir_listing_download: &[],
+                redirect_trailing_slash: true,
+                compression_static: false,
+                compression_static_order: &[],
+                ignore_hidden_files: false,
+                ignore_patterns: None,
+                disable_symlinks: false,
+                index_files: &["index.htm"],
+            })
+            .await;
+
+            #[cfg(not(feature = "directory-listing"))]
+            let _ = dir_listing;
+            assert_eq!(result.err(), Some(StatusCode::REQUEST_TIMEOUT));
+        }
+    }
+
     #[tokio::test]
     async fn handle_file_head() {
         let result = static_files::handle(&HandleOpts {
             method: &Method::HEAD,
             headers: &HeaderMap::new(),
             base_path: &root_dir(),
+            root_filesystem: None,
+            overlay_paths: &[],
+            embedded_assets: None,
             uri_path: "index.htm",
             uri_query: None,
             #[cfg(feature = "experimental")]
             memory_cache: None,
+            chunk_cache: None,
+            #[cfg(feature = "mmap")]
+            mmap_max_size: 0,
+            type_map: None,
+            deadline: None,
+            follow_symlinks: FollowSymlinks::Always,
             #[cfg(feature = "directory-listing")]
             dir_listing: false,
             #[cfg(feature = "directory-listing")]
-            dir_listing_order: 6,
+            dir_listing_sort: &DirListSort::from_code(6),
             #[cfg(feature = "directory-listing")]
             dir_listing_format: &DirListFmt::Html,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_dir_size: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_details: false,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_locales: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_locale: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_hide: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_readme: None,
             #[cfg(feature = "directory-listing-download")]
             dir_listing_download: &[],
             redirect_trailing_slash: true,
             compression_static: false,
+            compression_static_order: &[],
             ignore_hidden_files: false,
+            ignore_patterns: None,
             disable_symlinks: false,
             index_files: &["index.htm"],
         })
@@ -142,21 +252,44 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "xyz.html",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -178,21 +311,44 @@ mod tests {
             method: &Method::GET,
             headers: &HeaderMap::new(),
             base_path: &root_dir(),
+            root_filesystem: None,
+            overlay_paths: &[],
+            embedded_assets: None,
             uri_path: "assets",
             uri_query: None,
             #[cfg(feature = "experimental")]
             memory_cache: None,
+            chunk_cache: None,
+            #[cfg(feature = "mmap")]
+            mmap_max_size: 0,
+            type_map: None,
+            deadline: None,
+            follow_symlinks: FollowSymlinks::Always,
             #[cfg(feature = "directory-listing")]
             dir_listing: false,
             #[cfg(feature = "directory-listing")]
-            dir_listing_order: 0,
+            dir_listing_sort: &DirListSort::from_code(0),
             #[cfg(feature = "directory-listing")]
             dir_listing_format: &DirListFmt::Html,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_dir_size: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_details: false,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_locales: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_locale: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_hide: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_readme: None,
             #[cfg(feature = "directory-listing-download")]
             dir_listing_download: &[],
             redirect_trailing_slash: true,
             compression_static: false,
+            compression_static_order: &[],
             ignore_hidden_files: false,
+            ignore_patterns: None,
             disable_symlinks: false,
             index_files: &["index.htm"],
         })
@@ -216,21 +372,44 @@ mod tests {
             method: &Method::GET,
             headers: &HeaderMap::new(),
             base_path: &root_dir(),
+            root_filesystem: None,
+            overlay_paths: &[],
+            embedded_assets: None,
             uri_path: "assets",
             uri_query: None,
             #[cfg(feature = "experimental")]
             memory_cache: None,
+            chunk_cache: None,
+            #[cfg(feature = "mmap")]
+            mmap_max_size: 0,
+            type_map: None,
+            deadline: None,
+            follow_symlinks: FollowSymlinks::Always,
             #[cfg(feature = "directory-listing")]
             dir_listing: false,
             #[cfg(feature = "directory-listing")]
-            dir_listing_order: 0,
+            dir_listing_sort: &DirListSort::from_code(0),
             #[cfg(feature = "directory-listing")]
             dir_listing_format: &DirListFmt::Html,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_dir_size: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_details: false,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_locales: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_locale: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_hide: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_readme: None,
             #[cfg(feature = "directory-listing-download")]
             dir_listing_download: &[],
             redirect_trailing_slash: true,
             compression_static: false,
+            compression_static_order: &[],
             ignore_hidden_files: false,
+            ignore_patterns: None,
             disable_symlinks: false,
             index_files: &["index.htm"],
         })
@@ -253,21 +432,44 @@ mod tests {
             method: &Method::GET,
             headers: &HeaderMap::new(),
             base_path: &root_dir(),
+            root_filesystem: None,
+            overlay_paths: &[],
+            embedded_assets: None,
             uri_path: "assets",
             uri_query: None,
             #[cfg(feature = "experimental")]
             memory_cache: None,
+            chunk_cache: None,
+            #[cfg(feature = "mmap")]
+            mmap_max_size: 0,
+            type_map: None,
+            deadline: None,
+            follow_symlinks: FollowSymlinks::Always,
             #[cfg(feature = "directory-listing")]
             dir_listing: false,
             #[cfg(feature = "directory-listing")]
-            dir_listing_order: 0,
+            dir_listing_sort: &DirListSort::from_code(0),
             #[cfg(feature = "directory-listing")]
             dir_listing_format: &DirListFmt::Html,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_dir_size: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_details: false,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_locales: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_locale: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_hide: None,
+            #[cfg(feature = "directory-listing")]
+            dir_listing_readme: None,
             #[cfg(feature = "directory-listing-download")]
             dir_listing_download: &[],
             redirect_trailing_slash: false,
             compression_static: false,
+            compression_static_order: &[],
             ignore_hidden_files: false,
+            ignore_patterns: None,
             disable_symlinks: false,
             index_files: &[],
         })
@@ -296,21 +498,44 @@ mod tests {
                     method: &method,
                     headers: &HeaderMap::new(),
                     base_path: &root_dir(),
+                    root_filesystem: None,
+                    overlay_paths: &[],
+                    embedded_assets: None,
                     uri_path: uri,
                     uri_query: None,
                     #[cfg(feature = "experimental")]
                     memory_cache: None,
+                    chunk_cache: None,
+                    #[cfg(feature = "mmap")]
+                    mmap_max_size: 0,
+                    type_map: None,
+                    deadline: None,
+                    follow_symlinks: FollowSymlinks::Always,
                     #[cfg(feature = "directory-listing")]
                     dir_listing: false,
                     #[cfg(feature = "directory-listing")]
-                    dir_listing_order: 6,
+                    dir_listing_sort: &DirListSort::from_code(6),
                     #[cfg(feature = "directory-listing")]
                     dir_listing_format: &DirListFmt::Html,
+                    #[cfg(feature = "directory-listing")]
+                    dir_listing_dir_size: None,
+                    #[cfg(feature = "directory-listing")]
+                    dir_listing_details: false,
+                    #[cfg(feature = "directory-listing")]
+                    dir_listing_locales: None,
+                    #[cfg(feature = "directory-listing")]
+                    dir_listing_locale: None,
+                    #[cfg(feature = "directory-listing")]
+                    dir_listing_hide: None,
+                    #[cfg(feature = "directory-listing")]
+                    dir_listing_readme: None,
                     #[cfg(feature = "directory-listing-download")]
                     dir_listing_download: &[],
                     redirect_trailing_slash: true,
                     compression_static: false,
+                    compression_static_order: &[],
                     ignore_hidden_files: false,
+                    ignore_patterns: None,
                     disable_symlinks: false,
                     index_files: &[],
                 })
@@ -353,21 +578,44 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/assets/index%2ehtml",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
             })
@@ -392,21 +640,44 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/%2E%2e.html",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -433,21 +704,44 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "index.htm",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -475,21 +769,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "index.htm",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -520,21 +837,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "index.htm",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -563,21 +903,44 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "index.htm",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -604,21 +967,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "index.htm",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -644,21 +1030,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "index.htm",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -688,21 +1097,44 @@ mod tests {
                 method: &method,
                 headers: &HeaderMap::new(),
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "index.htm",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -775,21 +1207,44 @@ mod tests {
                 method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "index.htm",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -850,21 +1305,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "index.htm",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -904,21 +1382,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "assets/index.html",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
             })
@@ -958,21 +1459,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "assets/index.html",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
             })
@@ -1013,21 +1537,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "index.htm",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -1060,21 +1607,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "assets/index.html",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
             })
@@ -1117,21 +1687,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "assets/index.html",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
             })
@@ -1171,21 +1764,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "index.htm",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -1225,21 +1841,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "index.htm",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -1282,21 +1921,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "assets/index.html",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -1329,21 +1991,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "index.htm",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -1375,21 +2060,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "assets/index.html",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
             })
@@ -1436,21 +2144,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir(),
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "assets/index.html",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: false,
+                compression_static_order: &[],
                 ignore_hidden_files: false,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &[],
             })
@@ -1489,21 +2220,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir,
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: ".dotfile",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: true,
+                compression_static_order: &[],
                 ignore_hidden_files: true,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm"],
             })
@@ -1533,21 +2287,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir,
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: true,
+                compression_static_order: &[],
                 ignore_hidden_files: true,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm", "index.htm"],
             })
@@ -1579,21 +2356,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir,
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/symlink",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: true,
+                compression_static_order: &[],
                 ignore_hidden_files: true,
+                ignore_patterns: None,
                 disable_symlinks: true,
                 index_files: &["index.htm", "index.htm"],
             })
@@ -1615,21 +2415,44 @@ mod tests {
                 method: &method,
                 headers: &headers,
                 base_path: &root_dir,
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
                 uri_path: "/symlink/spécial file.txt~",
                 uri_query: None,
                 #[cfg(feature = "experimental")]
                 memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks: FollowSymlinks::Always,
                 #[cfg(feature = "directory-listing")]
                 dir_listing: false,
                 #[cfg(feature = "directory-listing")]
-                dir_listing_order: 6,
+                dir_listing_sort: &DirListSort::from_code(6),
                 #[cfg(feature = "directory-listing")]
                 dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
                 #[cfg(feature = "directory-listing-download")]
                 dir_listing_download: &[],
                 redirect_trailing_slash: true,
                 compression_static: true,
+                compression_static_order: &[],
                 ignore_hidden_files: true,
+                ignore_patterns: None,
                 disable_symlinks: false,
                 index_files: &["index.htm", "index.htm"],
             })
@@ -1651,4 +2474,92 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn handle_follow_symlinks() {
+        let root_dir = PathBuf::from("tests/fixtures/symlinks/public/");
+        let headers = HeaderMap::new();
+
+        let cases = [
+            (FollowSymlinks::Always, "/inside/", None),
+            (FollowSymlinks::Always, "/escape/secret.txt", None),
+            (FollowSymlinks::SameRoot, "/inside/", None),
+            (FollowSymlinks::SameRoot, "/inside/index.html", None),
+            (
+                FollowSymlinks::SameRoot,
+                "/escape/secret.txt",
+                Some(StatusCode::FORBIDDEN),
+            ),
+            (
+                FollowSymlinks::SameRoot,
+                "/escape/missing.txt",
+                Some(StatusCode::FORBIDDEN),
+            ),
+            (FollowSymlinks::Never, "/docs/", None),
+            (
+                FollowSymlinks::Never,
+                "/inside/",
+                Some(StatusCode::FORBIDDEN),
+            ),
+            (
+                FollowSymlinks::Never,
+                "/inside/index.html",
+                Some(StatusCode::FORBIDDEN),
+            ),
+        ];
+
+        for (follow_symlinks, uri_path, expected) in cases {
+            let result = static_files::handle(&HandleOpts {
+                method: &Method::GET,
+                headers: &headers,
+                base_path: &root_dir,
+                root_filesystem: None,
+                overlay_paths: &[],
+                embedded_assets: None,
+                uri_path,
+                uri_query: None,
+                #[cfg(feature = "experimental")]
+                memory_cache: None,
+                chunk_cache: None,
+                #[cfg(feature = "mmap")]
+                mmap_max_size: 0,
+                type_map: None,
+                deadline: None,
+                follow_symlinks,
+                #[cfg(feature = "directory-listing")]
+                dir_listing: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_sort: &DirListSort::from_code(6),
+                #[cfg(feature = "directory-listing")]
+                dir_listing_format: &DirListFmt::Html,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_dir_size: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_details: false,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locales: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_locale: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_hide: None,
+                #[cfg(feature = "directory-listing")]
+                dir_listing_readme: None,
+                #[cfg(feature = "directory-listing-download")]
+                dir_listing_download: &[],
+                redirect_trailing_slash: true,
+                compression_static: false,
+                compression_static_order: &[],
+                ignore_hidden_files: false,
+                ignore_patterns: None,
+                disable_symlinks: false,
+                index_files: &["index.html"],
+            })
+            .await;
+
+            match expected {
+                Some(status) => assert_eq!(result.err(), Some(status), "{uri_path}"),
+                None => assert_eq!(result.unwrap().resp.status(), 200, "{uri_path}"),
+            }
+        }
+    }
 }
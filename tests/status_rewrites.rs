@@ -0,0 +1,59 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::{Request, StatusCode};
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    async fn response_status(method: hyper::Method, uri: &str) -> (StatusCode, bool) {
+        let opts = fixture_settings("toml/status_rewrites.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.method_mut() = method;
+        *req.uri_mut() = uri.parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => {
+                let status = res.status();
+                let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+                (status, body_bytes.is_empty())
+            }
+            Err(err) => {
+                panic!("unexpected error: {err}")
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn status_rewrites_matching_route() {
+        let (status, empty_body) =
+            response_status(hyper::Method::GET, "http://localhost/api/users").await;
+        assert_eq!(status, StatusCode::GONE);
+        assert!(!empty_body);
+
+        let (status, empty_body) =
+            response_status(hyper::Method::HEAD, "http://localhost/api/users").await;
+        assert_eq!(status, StatusCode::GONE);
+        assert!(empty_body);
+    }
+
+    #[tokio::test]
+    async fn status_rewrites_non_matching_route() {
+        let (status, _) =
+            response_status(hyper::Method::GET, "http://localhost/missing.html").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = response_status(hyper::Method::GET, "http://localhost/index.htm").await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
@@ -0,0 +1,75 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(all(test, feature = "subresource-integrity"))]
+pub mod tests {
+    use hyper::{Body, Method, Request, Response};
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    async fn request(method: Method, uri: &str) -> Response<Body> {
+        let opts = fixture_settings("toml/subresource_integrity.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.method_mut() = method;
+        *req.uri_mut() = uri.parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn sri_manifest() {
+        let res = request(Method::GET, "http://localhost/sri.json").await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "application/json");
+        assert_eq!(res.headers()["cache-control"], "no-cache");
+
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let manifest: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(manifest["algorithm"], "sha256");
+        assert_eq!(
+            manifest["files"]["/assets/main.js"],
+            "sha256-fxm1o8x5hYw6b/+rlpupKMQYvNTCOVzI6ealFJOuxB8="
+        );
+        assert_eq!(
+            manifest["files"]["/assets/main.css"],
+            "sha256-MgCYo1qSIYNLKNi8NvMQ7vDEkbbkkqwcQ3gr+XFTN2Q="
+        );
+        let files = manifest["files"].as_object().unwrap();
+        assert!(
+            files
+                .keys()
+                .all(|path| path.ends_with(".js") || path.ends_with(".css"))
+        );
+    }
+
+    #[tokio::test]
+    async fn sri_manifest_head() {
+        let res = request(Method::HEAD, "http://localhost/sri.json").await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "application/json");
+
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn sri_manifest_other_paths() {
+        let res = request(Method::GET, "http://localhost/sri.json/").await;
+        assert_eq!(res.status(), 404);
+
+        let res = request(Method::GET, "http://localhost/assets/main.js").await;
+        assert_eq!(res.status(), 200);
+    }
+}
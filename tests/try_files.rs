@@ -0,0 +1,64 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::{Body, Request, Response};
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    async fn response(uri: &str) -> Response<Body> {
+        let opts = fixture_settings("toml/try_files.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.uri_mut() = uri.parse().unwrap();
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("unexpected error: {err}")
+            }
+        }
+    }
+
+    async fn body(res: Response<Body>) -> String {
+        let body = hyper::body::to_bytes(res.into_body())
+            .await
+            .expect("unexpected bytes error during `body` conversion");
+        String::from_utf8_lossy(&body).into_owned()
+    }
+
+    #[tokio::test]
+    async fn try_files_html_suffix() {
+        let res = response("http://localhost/404").await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "text/html");
+        assert!(body(res).await.contains("404 Content"));
+    }
+
+    #[tokio::test]
+    async fn try_files_directory() {
+        let res = response("http://localhost/assets").await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "text/html");
+    }
+
+    #[tokio::test]
+    async fn try_files_fallback() {
+        let res = response("http://localhost/some/page").await;
+        assert_eq!(res.status(), 200);
+        assert!(body(res).await.contains("50x Service Unavailable"));
+
+        // Entries without a fallback leave the request as is
+        let res = response("http://localhost/assets/unknown.js").await;
+        assert_eq!(res.status(), 404);
+    }
+}
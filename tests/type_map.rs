@@ -0,0 +1,65 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::{Body, Request, Response};
+    use std::net::SocketAddr;
+
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    async fn request(uri: &str) -> Response<Body> {
+        let opts = fixture_settings("toml/type_map.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.uri_mut() = uri.parse().unwrap();
+        req.headers_mut()
+            .insert("accept-encoding", "gzip".parse().unwrap());
+
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn type_map_known_types() {
+        let res = request("http://localhost/assets/app.js").await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "text/javascript");
+        assert_eq!(res.headers()["content-encoding"], "gzip");
+        assert_eq!(res.headers()["cache-control"], "max-age=31536000");
+
+        let res = request("http://localhost/").await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "text/html");
+        assert_eq!(res.headers()["cache-control"], "max-age=86400");
+    }
+
+    #[tokio::test]
+    async fn type_map_sniffed_text() {
+        // `.lock` files are unknown to the MIME types database but contain text
+        let res = request("http://localhost/yarn.lock").await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "text/plain; charset=utf-8");
+        assert_eq!(res.headers()["content-encoding"], "gzip");
+        assert_eq!(res.headers()["cache-control"], "max-age=86400");
+    }
+
+    #[tokio::test]
+    async fn type_map_custom_mime_types() {
+        // Custom MIME types are excluded from compression
+        let res = request("http://localhost/assets/app.css").await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "text/x-custom-css");
+        assert!(res.headers().get("content-encoding").is_none());
+        assert_eq!(res.headers()["cache-control"], "max-age=31536000");
+    }
+}
@@ -0,0 +1,177 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(rust_2018_idioms)]
+#![deny(dead_code)]
+
+#[cfg(test)]
+pub mod tests {
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server, StatusCode};
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    use static_web_server::handler::RequestHandler;
+    use static_web_server::settings::{Advanced, WebSocketProxy};
+    use static_web_server::testing::fixtures::{
+        REMOTE_ADDR, fixture_req_handler, fixture_req_handler_opts, fixture_settings,
+    };
+
+    /// Starts an upstream server echoing the WebSocket connections of the `/chat` path.
+    fn echo_upstream() -> SocketAddr {
+        let make_service = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|mut req: Request<Body>| async move {
+                if req.uri().path() != "/chat" || !req.headers().contains_key("upgrade") {
+                    let mut resp = Response::new(Body::from("no chat here"));
+                    *resp.status_mut() = StatusCode::NOT_FOUND;
+                    return Ok::<_, Infallible>(resp);
+                }
+                let forwarded_for = req.headers()["x-forwarded-for"].clone();
+                let upgrade = hyper::upgrade::on(&mut req);
+                tokio::spawn(async move {
+                    let upgraded = upgrade.await.unwrap();
+                    let (mut reader, mut writer) = tokio::io::split(upgraded);
+                    let _ = tokio::io::copy(&mut reader, &mut writer).await;
+                });
+                let resp = Response::builder()
+                    .status(StatusCode::SWITCHING_PROTOCOLS)
+                    .header("connection", "upgrade")
+                    .header("upgrade", "websocket")
+                    .header("sec-websocket-accept", "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=")
+                    .header("x-upstream-forwarded-for", forwarded_for)
+                    .body(Body::empty())
+                    .unwrap();
+                Ok(resp)
+            }))
+        });
+        let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    /// Starts SWS proxying the `/ws` path prefix to an echo upstream server.
+    fn proxy_server() -> SocketAddr {
+        let upstream = echo_upstream();
+        let opts = fixture_settings("toml/websocket_proxy.toml");
+        let mut req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        req_handler_opts.advanced_opts = Some(Advanced {
+            websocket_proxy: Some(vec![WebSocketProxy {
+                path: "/ws".to_owned(),
+                upstream: format!("http://{upstream}/").parse().unwrap(),
+                timeout: Duration::from_secs(2),
+            }]),
+            ..Default::default()
+        });
+        let req_handler: Arc<RequestHandler> = Arc::new(fixture_req_handler(req_handler_opts));
+
+        let make_service = make_service_fn(move |_| {
+            let req_handler = req_handler.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |mut req: Request<Body>| {
+                    let req_handler = req_handler.clone();
+                    async move {
+                        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+                        req_handler.handle(&mut req, remote_addr).await
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    /// Sends a WebSocket handshake request, returning the connection and the response head.
+    async fn handshake(addr: SocketAddr, path: &str) -> (TcpStream, String) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let req = format!(
+            "GET {path} HTTP/1.1\r\nhost: localhost\r\nconnection: Upgrade\r\nupgrade: websocket\r\n\
+             sec-websocket-key: dGhlIHNhbXBsZSBub25jZQ==\r\nsec-websocket-version: 13\r\n\r\n"
+        );
+        stream.write_all(req.as_bytes()).await.unwrap();
+
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            head.push(stream.read_u8().await.unwrap());
+        }
+        (
+            stream,
+            String::from_utf8(head).unwrap().to_ascii_lowercase(),
+        )
+    }
+
+    async fn request(uri: &str, upgrade: bool) -> Response<Body> {
+        let opts = fixture_settings("toml/websocket_proxy.toml");
+        let req_handler_opts = fixture_req_handler_opts(opts.general, opts.advanced);
+        let req_handler = fixture_req_handler(req_handler_opts);
+        let remote_addr = Some(REMOTE_ADDR.parse::<SocketAddr>().unwrap());
+
+        let mut req = Request::default();
+        *req.uri_mut() = uri.parse().unwrap();
+        if upgrade {
+            req.headers_mut()
+                .insert("connection", "upgrade".parse().unwrap());
+            req.headers_mut()
+                .insert("upgrade", "websocket".parse().unwrap());
+        }
+        match req_handler.handle(&mut req, remote_addr).await {
+            Ok(res) => res,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn websocket_proxy_tunnel() {
+        let addr = proxy_server();
+        let (mut stream, head) = handshake(addr, "/ws/chat").await;
+        assert!(head.starts_with("http/1.1 101 "), "{head}");
+        assert!(head.contains("\r\nupgrade: websocket\r\n"), "{head}");
+        assert!(head.contains("\r\nsec-websocket-accept: s3pplmbitxaq9kygzzhzrbk+xoo=\r\n"));
+        assert!(
+            head.contains("\r\nx-upstream-forwarded-for: 127.0.0.1\r\n"),
+            "{head}"
+        );
+
+        // The frames are tunneled in both directions
+        for message in [&b"ping"[..], &b"pong pong"[..]] {
+            stream.write_all(message).await.unwrap();
+            let mut echoed = vec![0; message.len()];
+            stream.read_exact(&mut echoed).await.unwrap();
+            assert_eq!(echoed, message);
+        }
+    }
+
+    #[tokio::test]
+    async fn websocket_proxy_upstream_rejection() {
+        let addr = proxy_server();
+        let (mut stream, head) = handshake(addr, "/ws/other").await;
+        assert!(head.starts_with("http/1.1 404 "), "{head}");
+        let mut body = [0; 12];
+        stream.read_exact(&mut body).await.unwrap();
+        assert_eq!(&body, b"no chat here");
+    }
+
+    #[tokio::test]
+    async fn websocket_proxy_upgrade_required() {
+        let res = request("http://localhost/ws/chat", false).await;
+        assert_eq!(res.status(), StatusCode::UPGRADE_REQUIRED);
+        assert_eq!(res.headers()["upgrade"], "websocket");
+
+        // Other paths are served as usual
+        let res = request("http://localhost/wsdocs", false).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let res = request("http://localhost/index.htm", true).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn websocket_proxy_bad_gateway() {
+        let res = request("http://localhost/ws", true).await;
+        assert_eq!(res.status(), StatusCode::BAD_GATEWAY);
+    }
+}